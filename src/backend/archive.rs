@@ -0,0 +1,94 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, TimeDelta};
+use rusqlite::Connection;
+
+use crate::backend::database::{archive_tasks_in_db, get_all_db_contents};
+use crate::backend::task::{Status, Task};
+
+/// Parses an age given as a number followed by a unit,
+/// `h` for hours, `d` for days, or `w` for weeks. i.e. "30d"
+pub fn parse_age(input: &str) -> Result<TimeDelta> {
+    let input = input.trim().to_lowercase();
+    let invalid = || {
+        format!(
+            "'{}' is not a valid age, use something like 12h, 30d or 2w",
+            input
+        )
+    };
+
+    let Some(unit) = input.chars().last() else {
+        bail!(invalid());
+    };
+    let amount: i64 = input[..input.len() - unit.len_utf8()]
+        .parse()
+        .with_context(invalid)?;
+    if amount < 0 {
+        bail!(invalid());
+    }
+
+    let age = match unit {
+        'h' => TimeDelta::try_hours(amount),
+        'd' => TimeDelta::try_days(amount),
+        'w' => TimeDelta::try_weeks(amount),
+        _ => None,
+    };
+    age.with_context(invalid)
+}
+
+/// Returns the `Task`s that were completed before the `cutoff`
+pub fn tasks_to_archive(tasks: Vec<Task>, cutoff: DateTime<Local>) -> Vec<Task> {
+    tasks
+        .into_iter()
+        .filter(|task| task.status == Status::Completed)
+        .filter(|task| {
+            task.completed_on
+                .is_some_and(|completed| completed < cutoff)
+        })
+        .collect()
+}
+
+/// Moves the `Task`s completed more than `older_than` ago into the archive.
+/// Returns how many were archived.
+pub fn archive_completed(conn: &Connection, older_than: TimeDelta) -> Result<usize> {
+    let task_list = get_all_db_contents(conn)?;
+    let tasks = tasks_to_archive(task_list.tasks, Local::now() - older_than);
+    archive_tasks_in_db(conn, &tasks)?;
+    Ok(tasks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").unwrap(), TimeDelta::days(30));
+        assert_eq!(parse_age(" 2W ").unwrap(), TimeDelta::weeks(2));
+        assert_eq!(parse_age("12h").unwrap(), TimeDelta::hours(12));
+        assert_eq!(parse_age("0d").unwrap(), TimeDelta::zero());
+        assert!(parse_age("").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("-1d").is_err());
+        assert!(parse_age("3m").is_err());
+    }
+
+    #[test]
+    fn test_tasks_to_archive() {
+        let now = Local::now();
+        let mut old_task = Task::new(String::from("Old"), None, None, None, None, None);
+        old_task.status = Status::Completed;
+        old_task.completed_on = Some(now - TimeDelta::days(40));
+        let mut recent_task = Task::new(String::from("Recent"), None, None, None, None, None);
+        recent_task.status = Status::Completed;
+        recent_task.completed_on = Some(now - TimeDelta::days(2));
+        let open_task = Task::new(String::from("Open"), None, None, None, None, None);
+
+        let tasks = vec![old_task.clone(), recent_task.clone(), open_task];
+        assert_eq!(
+            tasks_to_archive(tasks.clone(), now - TimeDelta::days(30)),
+            vec![old_task.clone()]
+        );
+        assert_eq!(tasks_to_archive(tasks, now), vec![old_task, recent_task]);
+    }
+}
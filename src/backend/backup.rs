@@ -0,0 +1,144 @@
+use std::fs::{create_dir_all, read_dir, remove_file};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use rusqlite::{Connection, DatabaseName};
+
+use crate::backend::config::get_config_dir;
+use crate::backend::encryption::{export_encrypted, passphrase};
+
+/// Returns the folder backups are kept in, next to the config file
+pub fn get_backups_dir() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("backups"))
+}
+
+/// Copies the database on the `&Connection` into `dir` before something that
+/// can't be taken back, i.e. "wipe", naming the copy after the database, when
+/// it was made, and the `reason`. Only the newest `keep` backups of the
+/// database are kept, and none are made if `keep` is 0 or the database is in
+/// memory. Backups of an encrypted database are encrypted with the same
+/// passphrase. Returns where the backup was written, if one was.
+pub fn backup_db(
+    conn: &Connection,
+    dir: &Path,
+    reason: &str,
+    keep: usize,
+    now: DateTime<Local>,
+) -> Result<Option<PathBuf>> {
+    let Some(db_path) = conn.path().filter(|path| !path.is_empty()) else {
+        return Ok(None);
+    };
+    if keep == 0 {
+        return Ok(None);
+    }
+    let name = Path::new(db_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("checklist");
+
+    create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let backup_path = dir.join(format!(
+        "{}-{}-{}.sqlite",
+        name,
+        now.format("%Y%m%d-%H%M%S-%3f"),
+        reason
+    ));
+    if let Some(passphrase) = passphrase() {
+        export_encrypted(conn, &backup_path, &passphrase)?;
+    } else {
+        conn.backup(DatabaseName::Main, &backup_path, None)
+            .with_context(|| format!("Failed to back up the database to {:?}", backup_path))?;
+    }
+
+    prune_backups(dir, name, keep)?;
+    Ok(Some(backup_path))
+}
+
+/// Backs up the database on the `&Connection` to the backups folder before
+/// something that can't be taken back, keeping the newest `keep` backups
+pub fn backup_before(conn: &Connection, reason: &str, keep: usize) -> Result<Option<PathBuf>> {
+    backup_db(conn, &get_backups_dir()?, reason, keep, Local::now())
+}
+
+/// Returns the backups in `dir` of the database called `name`, oldest first.
+/// Names start with when they were made, so sorting them sorts by age.
+fn list_backups(dir: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let prefix = format!("{}-", name);
+    let mut backups: Vec<PathBuf> = read_dir(dir)
+        .with_context(|| format!("Failed to read {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "sqlite")
+                && path
+                    .file_name()
+                    .and_then(|file| file.to_str())
+                    .is_some_and(|file| {
+                        // "test.checklist-..." mustn't count as a backup of "checklist"
+                        file.strip_prefix(&prefix)
+                            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+                    })
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Deletes all but the newest `keep` backups of the database called `name`
+fn prune_backups(dir: &Path, name: &str, keep: usize) -> Result<()> {
+    let backups = list_backups(dir, name)?;
+    let extra = backups.len().saturating_sub(keep);
+    for backup in &backups[..extra] {
+        remove_file(backup)
+            .with_context(|| format!("Failed to remove the old backup {:?}", backup))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::backend::database::{
+        add_to_db, get_all_db_contents, load_backup, make_memory_connection,
+    };
+    use crate::backend::task::Task;
+
+    #[test]
+    fn test_backup_db() {
+        let dir = std::env::temp_dir().join(format!("checklist-backups-{}", Uuid::new_v4()));
+        let db_path = std::env::temp_dir().join(format!("checklist-{}.sqlite", Uuid::new_v4()));
+        let memory = make_memory_connection().unwrap();
+        memory
+            .execute("VACUUM INTO ?1", [db_path.to_str().unwrap()])
+            .unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        let task = Task::new(String::from("Keep me"), None, None, None, None, None);
+        add_to_db(&conn, &task).unwrap();
+
+        // Nothing to back up for a database in memory, or when turned off
+        let now = Local::now();
+        assert_eq!(backup_db(&memory, &dir, "wipe", 3, now).unwrap(), None);
+        assert_eq!(backup_db(&conn, &dir, "wipe", 0, now).unwrap(), None);
+
+        let mut made = vec![];
+        for (i, reason) in ["wipe", "import", "migrate", "wipe"].iter().enumerate() {
+            let at = now + TimeDelta::seconds(i as i64);
+            made.push(backup_db(&conn, &dir, reason, 3, at).unwrap().unwrap());
+        }
+        assert!(made[3].to_str().unwrap().ends_with("-wipe.sqlite"));
+
+        // The oldest is pruned, keeping the newest 3
+        let name = db_path.file_stem().unwrap().to_str().unwrap();
+        assert_eq!(list_backups(&dir, name).unwrap(), made[1..].to_vec());
+
+        let restored = load_backup(&made[3]).unwrap();
+        assert_eq!(get_all_db_contents(&restored).unwrap().tasks, vec![task]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        remove_file(&db_path).unwrap();
+    }
+}
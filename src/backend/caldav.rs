@@ -0,0 +1,1105 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::backend::config::Config;
+use crate::backend::database::{get_caldav_synced, set_caldav_synced, Gone};
+use crate::backend::http::{curl, Response};
+use crate::backend::open::shell_output;
+use crate::backend::sync::{apply_plan, reconcile, Snapshot, SyncPlan};
+use crate::backend::task::{ExternalRef, Status, Task, Urgency};
+
+/// System the `ExternalRef` of a `Task` pulled from a CalDAV server is in,
+/// with the UID of its VTODO as the id
+pub const CALDAV_SYSTEM: &str = "caldav";
+
+/// The properties of a VTODO a `Task` is synced to. Everything else in
+/// it (alarms, properties other apps set) is left as it was.
+const MAPPED: [&str; 12] = [
+    "SUMMARY",
+    "DESCRIPTION",
+    "PRIORITY",
+    "STATUS",
+    "X-CHECKLIST-STATUS",
+    "DUE",
+    "COMPLETED",
+    "CATEGORIES",
+    "CREATED",
+    "RELATED-TO",
+    "LAST-MODIFIED",
+    "DTSTAMP",
+];
+
+/// Asks for the etag and contents of every VTODO in the calendar
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VTODO"/>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#;
+
+/// The calendar on a CalDAV server (i.e. Nextcloud Tasks) to sync with,
+/// and who to log in as
+#[derive(Debug)]
+pub struct CaldavAccount {
+    /// URL of the calendar collection, not the server
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl CaldavAccount {
+    /// Reads the account from the `Config`, running the `caldav_password_command`
+    /// for the password if there isn't a `caldav_password`
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let Some(url) = config.caldav_url.clone() else {
+            bail!("No CalDAV calendar to sync with, set \"caldav_url\" in the config file");
+        };
+        let Some(username) = config.caldav_username.clone() else {
+            bail!("No CalDAV user to log in as, set \"caldav_username\" in the config file");
+        };
+        let password = match (&config.caldav_password, &config.caldav_password_command) {
+            (Some(password), _) => password.clone(),
+            (None, Some(command)) => shell_output(command, "CalDAV password command")?,
+            (None, None) => bail!(
+                "No CalDAV password, set \"caldav_password\" or \"caldav_password_command\" in the config file"
+            ),
+        };
+        Ok(Self {
+            url,
+            username,
+            password,
+        })
+    }
+
+    /// Sends a request to the URL, logged in as the user
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<Response> {
+        let user = format!("{}:{}", self.username, self.password);
+        curl(method, url, headers, body, Some(&user))
+    }
+}
+
+/// Returns the contents of each element with the name in the XML, whatever
+/// namespace prefix it has. Good enough for the multistatus responses CalDAV
+/// servers send, which don't nest elements in ones with the same name.
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('>') else {
+            break;
+        };
+        let tag = &after[..end];
+        rest = &after[end + 1..];
+        if tag.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let tag_name = tag
+            .trim_end_matches('/')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let local_name = tag_name.rsplit(':').next().unwrap_or_default();
+        if !local_name.eq_ignore_ascii_case(name) {
+            continue;
+        }
+        if tag.ends_with('/') {
+            found.push("");
+            continue;
+        }
+        let close = format!("</{}>", tag_name);
+        let Some(close_at) = rest.find(&close) else {
+            break;
+        };
+        found.push(&rest[..close_at]);
+        rest = &rest[close_at + close.len()..];
+    }
+    found
+}
+
+/// Returns the text in an XML element, taking out CDATA sections and entities
+fn xml_text(raw: &str) -> String {
+    let mut text = String::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("<![CDATA[") {
+        text.push_str(&unescape_xml(&rest[..start]));
+        let after = &rest[start + "<![CDATA[".len()..];
+        let end = after.find("]]>").unwrap_or(after.len());
+        text.push_str(&after[..end]);
+        rest = after.get(end + "]]>".len()..).unwrap_or_default();
+    }
+    text.push_str(&unescape_xml(rest));
+    text
+}
+
+/// Turns XML entities (i.e. &amp; or &#10;) back into the characters they stand for
+fn unescape_xml(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        let after = &rest[amp + 1..];
+        let decoded = after.find(';').and_then(|semi| {
+            let c = match &after[..semi] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            c.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                unescaped.push(c);
+                rest = &after[semi + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = after;
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// A VTODO on the server, in the iCalendar object at the href
+#[derive(Debug, PartialEq)]
+struct RemoteTodo {
+    href: String,
+    etag: Option<String>,
+    calendar: String,
+}
+
+/// Returns the VTODOs in the multistatus response to a calendar query
+fn parse_multistatus(xml: &str) -> Vec<RemoteTodo> {
+    xml_elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = xml_text(xml_elements(response, "href").first()?)
+                .trim()
+                .to_string();
+            let calendar = xml_text(xml_elements(response, "calendar-data").first()?);
+            if calendar.trim().is_empty() {
+                return None;
+            }
+            let etag = xml_elements(response, "getetag")
+                .first()
+                .map(|etag| xml_text(etag).trim().to_string())
+                .filter(|etag| !etag.is_empty());
+            Some(RemoteTodo {
+                href,
+                etag,
+                calendar,
+            })
+        })
+        .collect()
+}
+
+/// Returns the full URL of the href, which servers tend to give as a path
+fn resolve_href(collection: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    if href.starts_with('/') {
+        let origin_end = collection
+            .find("://")
+            .and_then(|scheme| {
+                let host = scheme + "://".len();
+                collection[host..].find('/').map(|path| host + path)
+            })
+            .unwrap_or(collection.len());
+        return format!("{}{}", &collection[..origin_end], href);
+    }
+    format!("{}/{}", collection.trim_end_matches('/'), href)
+}
+
+/// Returns the lines of the iCalendar text, joining back up the ones folded
+/// onto the next line
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let Some(continued) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(continued);
+                continue;
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+    lines
+}
+
+/// Folds the line every 75 octets as iCalendar asks, without splitting a
+/// character, ending it with a CRLF
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Escapes the text for an iCalendar TEXT value
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Turns an iCalendar TEXT value back into the text it was escaped from
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Splits an iCalendar list of TEXT values (i.e. CATEGORIES) at the commas
+/// that aren't escaped, unescaping each one
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut item = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if c == ',' && !escaped {
+            items.push(unescape_text(&item));
+            item.clear();
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        item.push(c);
+    }
+    items.push(unescape_text(&item));
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A content line of an iCalendar object, i.e. `DUE;VALUE=DATE:20240501`
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn new(name: &str, value: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            params: vec![],
+            value: value.into(),
+        }
+    }
+
+    fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Reads the property from an unfolded content line, minding the quotes
+    /// parameter values can have colons and semicolons in
+    fn parse(line: &str) -> Option<Self> {
+        let mut in_quotes = false;
+        let mut parts = vec![];
+        let mut start = 0;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ';' | ':' if !in_quotes => {
+                    parts.push(&line[start..i]);
+                    start = i + 1;
+                    if c == ':' {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if start == 0 || line[..start].ends_with(';') {
+            return None;
+        }
+        let (name, params) = parts.split_first()?;
+        let params = params
+            .iter()
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                Some((
+                    name.to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        Some(Self {
+            name: name.to_ascii_uppercase(),
+            params,
+            value: line[start..].to_string(),
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the content line for the property, unfolded
+    fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (name, value) in &self.params {
+            if value.contains([':', ';', ',']) {
+                line.push_str(&format!(";{}=\"{}\"", name, value));
+            } else {
+                line.push_str(&format!(";{}={}", name, value));
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+
+    /// Whether the property is one a `Task` is synced to. Only RELATED-TO
+    /// pointing at a parent is, not ones pointing at siblings or children.
+    fn is_mapped(&self) -> bool {
+        if self.name == "RELATED-TO" {
+            return self
+                .param("RELTYPE")
+                .is_none_or(|reltype| reltype.eq_ignore_ascii_case("PARENT"));
+        }
+        MAPPED.contains(&self.name.as_str())
+    }
+}
+
+/// Returns the properties of the first VTODO in the iCalendar text, leaving
+/// out the ones of anything inside it (i.e. a VALARM)
+fn vtodo_properties(text: &str) -> Option<Vec<Property>> {
+    let mut properties = vec![];
+    let mut depth = 0;
+    for line in unfold(text) {
+        let upper = line.to_ascii_uppercase();
+        if depth == 0 {
+            if upper == "BEGIN:VTODO" {
+                depth = 1;
+            }
+            continue;
+        }
+        if upper.starts_with("BEGIN:") {
+            depth += 1;
+        } else if upper.starts_with("END:") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(properties);
+            }
+        } else if depth == 1 {
+            properties.extend(Property::parse(&line));
+        }
+    }
+    None
+}
+
+/// Returns the first property with the name
+fn find<'a>(properties: &'a [Property], name: &str) -> Option<&'a Property> {
+    properties.iter().find(|property| property.name == name)
+}
+
+/// Reads an iCalendar DATE-TIME or DATE. Times in UTC end with a Z, while ones
+/// without (whatever their TZID) are taken to be in the local time zone.
+fn parse_ical_time(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(time.and_utc().with_timezone(&Local));
+    }
+    let time = match NaiveDate::parse_from_str(value, "%Y%m%d") {
+        Ok(date) => date.and_hms_opt(0, 0, 0)?,
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+    };
+    time.and_local_timezone(Local).earliest()
+}
+
+/// Formats the time as an iCalendar DATE-TIME in UTC
+fn ical_time(time: &DateTime<Local>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Returns the VTODO PRIORITY (1 highest, 9 lowest) for the `Urgency`
+fn priority(urgency: Urgency) -> u8 {
+    match urgency {
+        Urgency::Critical => 1,
+        Urgency::High => 3,
+        Urgency::Medium => 5,
+        Urgency::Low => 9,
+    }
+}
+
+/// Returns the `Urgency` for the VTODO PRIORITY, with 0 (no priority) being `Low`
+fn urgency_from_priority(priority: u8) -> Urgency {
+    match priority {
+        1..=2 => Urgency::Critical,
+        3..=4 => Urgency::High,
+        5 => Urgency::Medium,
+        _ => Urgency::Low,
+    }
+}
+
+/// Keeps the time the `Task` already has if it's the same to the second,
+/// since iCalendar doesn't go any finer
+fn same_second(time: DateTime<Local>, had: Option<DateTime<Local>>) -> DateTime<Local> {
+    match had {
+        Some(had) if had.timestamp() == time.timestamp() => had,
+        _ => time,
+    }
+}
+
+/// Returns the properties of the VTODO for the `Task`, with when it last changed.
+/// `parent_uid` is the UID of the VTODO for its parent, if it has one.
+fn todo_properties(
+    task: &Task,
+    modified: &DateTime<Local>,
+    parent_uid: Option<&str>,
+) -> Vec<Property> {
+    let mut properties = vec![
+        Property::new("DTSTAMP", ical_time(&Local::now())),
+        Property::new("SUMMARY", escape_text(&task.name)),
+        Property::new("PRIORITY", priority(task.urgency).to_string()),
+    ];
+    if let Some(description) = &task.description {
+        properties.push(Property::new("DESCRIPTION", escape_text(description)));
+    }
+    let status = match task.status {
+        Status::Open | Status::Paused => "NEEDS-ACTION",
+        Status::Working => "IN-PROCESS",
+        Status::Completed => "COMPLETED",
+    };
+    properties.push(Property::new("STATUS", status));
+    if task.status == Status::Paused {
+        properties.push(Property::new("X-CHECKLIST-STATUS", "PAUSED"));
+    }
+    if let (Status::Completed, Some(completed_on)) = (task.status, &task.completed_on) {
+        properties.push(Property::new("COMPLETED", ical_time(completed_on)));
+    }
+    if let Some(due) = task.due {
+        properties.push(
+            Property::new("DUE", due.format("%Y%m%d").to_string()).with_param("VALUE", "DATE"),
+        );
+    }
+    if let Some(tags) = task.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let mut tags: Vec<&String> = tags.iter().collect();
+        tags.sort();
+        let categories: Vec<String> = tags.into_iter().map(|tag| escape_text(tag)).collect();
+        properties.push(Property::new("CATEGORIES", categories.join(",")));
+    }
+    properties.push(Property::new("CREATED", ical_time(&task.date_added)));
+    if let Some(parent_uid) = parent_uid {
+        properties.push(Property::new("RELATED-TO", parent_uid).with_param("RELTYPE", "PARENT"));
+    }
+    properties.push(Property::new("LAST-MODIFIED", ical_time(modified)));
+    properties
+}
+
+/// Returns the `Task` the VTODO with the properties is, starting from `had` (the
+/// copy of it here, if there is one) so the fields a VTODO has no place for are
+/// kept. `ids` has the id of the `Task` for each UID, for finding its parent.
+fn task_from_todo(
+    properties: &[Property],
+    id: Uuid,
+    uid: &str,
+    had: Option<&Task>,
+    ids: &HashMap<String, Uuid>,
+) -> Task {
+    let mut task = match had {
+        Some(task) => task.clone(),
+        None => {
+            let mut task = Task::from_sql(
+                id,
+                String::new(),
+                None,
+                None,
+                Urgency::Low,
+                Status::Open,
+                None,
+                Local::now(),
+                None,
+            );
+            if uid != id.to_string() {
+                task.external_ref = Some(ExternalRef {
+                    system: CALDAV_SYSTEM.to_string(),
+                    id: uid.to_string(),
+                    url: None,
+                });
+            }
+            task
+        }
+    };
+    let text = |name: &str| find(properties, name).map(|property| unescape_text(&property.value));
+    let time =
+        |name: &str| find(properties, name).and_then(|property| parse_ical_time(&property.value));
+
+    task.name = text("SUMMARY")
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| String::from("Untitled to-do"));
+    task.description = text("DESCRIPTION").filter(|description| !description.is_empty());
+    task.urgency = urgency_from_priority(
+        find(properties, "PRIORITY")
+            .and_then(|property| property.value.trim().parse().ok())
+            .unwrap_or(0),
+    );
+    let status = text("STATUS").unwrap_or_default().to_ascii_uppercase();
+    let paused =
+        text("X-CHECKLIST-STATUS").is_some_and(|status| status.eq_ignore_ascii_case("PAUSED"));
+    task.status = match status.as_str() {
+        "COMPLETED" | "CANCELLED" => Status::Completed,
+        "IN-PROCESS" => Status::Working,
+        _ if paused => Status::Paused,
+        _ => Status::Open,
+    };
+    task.completed_on = if task.status == Status::Completed {
+        match time("COMPLETED") {
+            Some(completed) => Some(same_second(completed, task.completed_on)),
+            None => task.completed_on.or_else(|| Some(Local::now())),
+        }
+    } else {
+        None
+    };
+    task.due = time("DUE").map(|due| due.date_naive());
+    let tags: HashSet<String> = properties
+        .iter()
+        .filter(|property| property.name == "CATEGORIES")
+        .flat_map(|property| split_list(&property.value))
+        .collect();
+    task.tags = if tags.is_empty() { None } else { Some(tags) };
+    if let Some(created) = time("CREATED") {
+        task.date_added = same_second(created, Some(task.date_added));
+    }
+    task.parent = properties
+        .iter()
+        .find(|property| property.is_mapped() && property.name == "RELATED-TO")
+        .and_then(|property| {
+            let parent = property.value.trim();
+            ids.get(parent)
+                .copied()
+                .or_else(|| Uuid::parse_str(parent).ok())
+        });
+    task
+}
+
+/// Returns when the VTODO last changed, going by LAST-MODIFIED, then DTSTAMP,
+/// and taking it to be as old as can be without either
+fn todo_modified(properties: &[Property]) -> DateTime<Local> {
+    ["LAST-MODIFIED", "DTSTAMP"]
+        .iter()
+        .find_map(|name| {
+            find(properties, name).and_then(|property| parse_ical_time(&property.value))
+        })
+        .unwrap_or_else(|| DateTime::<Utc>::UNIX_EPOCH.with_timezone(&Local))
+}
+
+/// Puts the properties in place of the ones a `Task` is synced to in the first
+/// VTODO of the iCalendar text, keeping everything else as it was
+fn update_calendar(text: &str, properties: &[Property]) -> String {
+    let mut updated = String::new();
+    let mut depth = 0;
+    let mut done = false;
+    for line in unfold(text) {
+        let upper = line.to_ascii_uppercase();
+        if depth == 0 {
+            if !done && upper == "BEGIN:VTODO" {
+                depth = 1;
+            }
+        } else if upper.starts_with("BEGIN:") {
+            depth += 1;
+        } else if upper.starts_with("END:") {
+            depth -= 1;
+            if depth == 0 {
+                for property in properties {
+                    updated.push_str(&fold(&property.to_line()));
+                }
+                done = true;
+            }
+        } else if depth == 1 && Property::parse(&line).is_some_and(|property| property.is_mapped())
+        {
+            continue;
+        }
+        updated.push_str(&fold(&line));
+    }
+    updated
+}
+
+/// Returns a new iCalendar object with a VTODO with the UID and properties
+fn new_calendar(uid: &str, properties: &[Property]) -> String {
+    let mut calendar = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//checklist//EN",
+        "BEGIN:VTODO",
+    ] {
+        calendar.push_str(&fold(line));
+    }
+    calendar.push_str(&fold(&Property::new("UID", uid).to_line()));
+    for property in properties {
+        calendar.push_str(&fold(&property.to_line()));
+    }
+    calendar.push_str(&fold("END:VTODO"));
+    calendar.push_str(&fold("END:VCALENDAR"));
+    calendar
+}
+
+/// Fails with what the CalDAV server said unless the response has the status
+fn expect_status(response: Response, expected: &[u16], what: &str) -> Result<Response> {
+    if expected.contains(&response.status) {
+        return Ok(response);
+    }
+    if response.status == 412 {
+        bail!(
+            "Failed to {}, it changed on the CalDAV server while syncing. Sync again to pick that up",
+            what
+        );
+    }
+    bail!(
+        "Failed to {}, the CalDAV server said {}: {}",
+        what,
+        response.status,
+        response.body.trim()
+    )
+}
+
+/// Syncs the database with the VTODOs in the CalDAV calendar, the same way
+/// `sync_tasks` does with a `checklist serve` server: whichever copy changed
+/// last wins, and deleting or archiving a `Task` deletes its VTODO. Status,
+/// urgency (as PRIORITY), due date, tags (as CATEGORIES), description, and
+/// parent are synced, with the rest of a `Task` staying here and the rest of a
+/// VTODO staying on the server.
+pub fn sync_caldav(
+    conn: &Connection,
+    account: &CaldavAccount,
+    backup_count: usize,
+) -> Result<SyncPlan> {
+    let response = account.request(
+        "REPORT",
+        &account.url,
+        &[
+            ("Depth", "1"),
+            ("Content-Type", "application/xml; charset=utf-8"),
+        ],
+        Some(CALENDAR_QUERY),
+    )?;
+    let response = expect_status(response, &[207], "get the to-dos")?;
+
+    let local = Snapshot::read(conn)?;
+    let synced = get_caldav_synced(conn)?;
+    let mut ids: HashMap<String, Uuid> = synced
+        .iter()
+        .map(|(uid, (id, _))| (uid.clone(), *id))
+        .collect();
+    for (id, (task, _)) in &local.tasks {
+        if let Some(external_ref) = task
+            .external_ref
+            .as_ref()
+            .filter(|external_ref| external_ref.system == CALDAV_SYSTEM)
+        {
+            ids.entry(external_ref.id.clone()).or_insert(*id);
+        }
+    }
+
+    let mut todos = vec![];
+    for todo in parse_multistatus(&response.body) {
+        let Some(properties) = vtodo_properties(&todo.calendar) else {
+            continue;
+        };
+        let Some(uid) = find(&properties, "UID").map(|uid| uid.value.trim().to_string()) else {
+            continue;
+        };
+        let id = *ids
+            .entry(uid.clone())
+            .or_insert_with(|| Uuid::parse_str(&uid).unwrap_or_else(|_| Uuid::new_v4()));
+        todos.push((id, uid, properties, todo));
+    }
+
+    let mut remote = Snapshot::default();
+    let mut objects: HashMap<Uuid, (String, RemoteTodo)> = HashMap::new();
+    for (id, uid, properties, todo) in todos {
+        let had = local.tasks.get(&id).map(|(task, _)| task);
+        let task = task_from_todo(&properties, id, &uid, had, &ids);
+        remote.tasks.insert(id, (task, todo_modified(&properties)));
+        objects.insert(id, (uid, todo));
+    }
+    for (uid, (id, seen)) in &synced {
+        if !objects.values().any(|(remote_uid, _)| remote_uid == uid) {
+            let gone = Gone {
+                when: *seen,
+                archived: false,
+            };
+            remote.deleted.insert(*id, gone);
+        }
+    }
+
+    let mut plan = reconcile(&local, &remote);
+    // A calendar has no archive, and the to-do is already completed on it, so it stays
+    plan.archive_remote.clear();
+    let mut uids: HashMap<Uuid, String> = ids.iter().map(|(uid, id)| (*id, uid.clone())).collect();
+    for (id, (uid, _)) in &objects {
+        uids.insert(*id, uid.clone());
+    }
+    let uid_of = |id: Uuid| uids.get(&id).cloned().unwrap_or_else(|| id.to_string());
+
+    let mut now_synced: HashMap<String, Uuid> = objects
+        .iter()
+        .map(|(id, (uid, _))| (uid.clone(), *id))
+        .collect();
+    for (task, modified) in &plan.push {
+        let parent_uid = task.parent.map(uid_of);
+        let properties = todo_properties(task, modified, parent_uid.as_deref());
+        let what = format!("send '{}'", task.name);
+        match objects.get(&task.get_id()) {
+            Some((_, todo)) => {
+                let calendar = update_calendar(&todo.calendar, &properties);
+                let mut headers = vec![("Content-Type", "text/calendar; charset=utf-8")];
+                if let Some(etag) = &todo.etag {
+                    headers.push(("If-Match", etag.as_str()));
+                }
+                let url = resolve_href(&account.url, &todo.href);
+                let response = account.request("PUT", &url, &headers, Some(&calendar))?;
+                expect_status(response, &[200, 201, 204], &what)?;
+            }
+            None => {
+                let uid = uid_of(task.get_id());
+                let calendar = new_calendar(&uid, &properties);
+                let url = format!("{}/{}.ics", account.url.trim_end_matches('/'), uid);
+                let headers = [
+                    ("Content-Type", "text/calendar; charset=utf-8"),
+                    ("If-None-Match", "*"),
+                ];
+                let response = account.request("PUT", &url, &headers, Some(&calendar))?;
+                expect_status(response, &[200, 201, 204], &what)?;
+                now_synced.insert(uid, task.get_id());
+            }
+        }
+    }
+    for id in &plan.delete_remote {
+        let Some((uid, todo)) = objects.get(id) else {
+            continue;
+        };
+        let mut headers = vec![];
+        if let Some(etag) = &todo.etag {
+            headers.push(("If-Match", etag.as_str()));
+        }
+        let url = resolve_href(&account.url, &todo.href);
+        let response = account.request("DELETE", &url, &headers, None)?;
+        // Someone else may have deleted it in the meantime
+        expect_status(response, &[200, 204, 404], &format!("delete '{}'", uid))?;
+        now_synced.remove(uid);
+    }
+
+    apply_plan(conn, &plan, backup_count)?;
+    set_caldav_synced(conn, &now_synced)?;
+    info!("Synced with {}: {}", account.url, plan.summary());
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+    use crate::backend::import::tests::{random_task, Random};
+
+    #[test]
+    fn test_fold_and_escape() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(50));
+        let folded = fold(&line);
+        assert!(folded
+            .split("\r\n")
+            .all(|folded_line| folded_line.len() <= 75));
+        assert_eq!(unfold(&folded), vec![line]);
+        assert_eq!(
+            unfold("SUMMARY:Wa\r\n ter the\r\n\tplants\r\nEND:VTODO\r\n"),
+            vec!["SUMMARY:Water theplants", "END:VTODO"]
+        );
+
+        let text = "Milk, eggs; and a back\\slash\nSecond line";
+        assert_eq!(
+            escape_text(text),
+            "Milk\\, eggs\\; and a back\\\\slash\\nSecond line"
+        );
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(
+            split_list("home,work\\,ish, errands,"),
+            vec!["home", "work,ish", "errands"]
+        );
+
+        let property =
+            Property::parse("RELATED-TO;RELTYPE=PARENT;X-NOTE=\"a:b;c\":abc-123").unwrap();
+        assert_eq!(property.name, "RELATED-TO");
+        assert_eq!(property.param("reltype"), Some("PARENT"));
+        assert_eq!(property.param("X-NOTE"), Some("a:b;c"));
+        assert_eq!(property.value, "abc-123");
+        assert_eq!(Property::parse(&property.to_line()), Some(property));
+        assert_eq!(Property::parse("no colon here"), None);
+    }
+
+    #[test]
+    fn test_priority() {
+        for urgency in [
+            Urgency::Low,
+            Urgency::Medium,
+            Urgency::High,
+            Urgency::Critical,
+        ] {
+            assert_eq!(urgency_from_priority(priority(urgency)), urgency);
+        }
+        assert_eq!(urgency_from_priority(0), Urgency::Low);
+        assert_eq!(urgency_from_priority(2), Urgency::Critical);
+        assert_eq!(urgency_from_priority(7), Urgency::Low);
+    }
+
+    #[test]
+    fn test_todo_round_trip() {
+        let parent = Task::new("Move".to_string(), None, None, None, None, None);
+        let mut task = Task::new(
+            "Pack, label; the boxes".to_string(),
+            Some("Kitchen first\nthen the books".to_string()),
+            Some("Got tape".to_string()),
+            Some(Urgency::High),
+            Some(Status::Paused),
+            Some(HashSet::from(["home".to_string(), "move".to_string()])),
+        );
+        task.due = NaiveDate::from_ymd_opt(2024, 5, 1);
+        task.parent = Some(parent.get_id());
+        let modified = Local.with_ymd_and_hms(2024, 4, 20, 9, 30, 0).unwrap();
+        let parent_uid = "parent-uid";
+        let properties = todo_properties(&task, &modified, Some(parent_uid));
+        let uid = task.get_id().to_string();
+        let calendar = new_calendar(&uid, &properties);
+        let ids = HashMap::from([(parent_uid.to_string(), parent.get_id())]);
+
+        let read = vtodo_properties(&calendar).unwrap();
+        assert_eq!(find(&read, "UID").unwrap().value, uid);
+        assert_eq!(todo_modified(&read), modified);
+        // Starting from the copy here, nothing changes
+        assert_eq!(
+            task_from_todo(&read, task.get_id(), &uid, Some(&task), &ids),
+            task
+        );
+
+        // Starting from nothing, everything but the fields a VTODO has no place for comes back
+        let pulled = task_from_todo(&read, task.get_id(), &uid, None, &ids);
+        assert_eq!(pulled.get_id(), task.get_id());
+        assert_eq!(pulled.name, task.name);
+        assert_eq!(pulled.description, task.description);
+        assert_eq!(pulled.latest, None);
+        assert_eq!(pulled.urgency, Urgency::High);
+        assert_eq!(pulled.status, Status::Paused);
+        assert_eq!(pulled.tags, task.tags);
+        assert_eq!(pulled.due, task.due);
+        assert_eq!(pulled.parent, Some(parent.get_id()));
+        assert_eq!(pulled.date_added.timestamp(), task.date_added.timestamp());
+        assert_eq!(pulled.external_ref, None);
+
+        // A to-do made elsewhere keeps its UID as the reference
+        let pulled = task_from_todo(&read, Uuid::new_v4(), "made-elsewhere", None, &ids);
+        assert_eq!(pulled.external_ref.unwrap().id, "made-elsewhere");
+    }
+
+    #[test]
+    fn test_random_round_trip() {
+        // iCalendar times go to the second, and its text has no carriage returns
+        let to_the_second = |time: DateTime<Local>| time.with_nanosecond(0).unwrap();
+        let without_cr = |text: String| text.replace('\r', "");
+        for seed in 1..=5u64 {
+            let mut random = Random::seeded(seed);
+            for _ in 0..40 {
+                let task = random_task(&mut random);
+                let modified = random_task(&mut random).date_added;
+                let uid = task.get_id().to_string();
+                let calendar = new_calendar(&uid, &todo_properties(&task, &modified, None));
+                let read = vtodo_properties(&calendar).unwrap();
+                let pulled = task_from_todo(&read, task.get_id(), &uid, None, &HashMap::new());
+
+                let mut expected = task;
+                expected.name = without_cr(expected.name);
+                expected.description = expected.description.map(without_cr);
+                expected.date_added = to_the_second(expected.date_added);
+                expected.completed_on = expected.completed_on.map(to_the_second);
+                // The rest isn't synced to the VTODO, so a task pulled from
+                // the calendar for the first time doesn't have it
+                expected.latest = None;
+                expected.recurrence = None;
+                expected.project = None;
+                expected.time_spent = 0;
+                expected.timer_started = None;
+                expected.scheduled = None;
+                expected.scheduled_start = None;
+                expected.duration = None;
+                expected.external_ref = None;
+                expected.locked = false;
+                expected.criteria = vec![];
+                expected.previous_occurrence = None;
+                assert_eq!(pulled, expected, "seed {}", seed);
+                assert_eq!(todo_modified(&read), to_the_second(modified));
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_calendar() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VTODO\r\n\
+            UID:abc\r\n\
+            SUMMARY:Old name\r\n\
+            STATUS:NEEDS-ACTION\r\n\
+            X-APPLE-SORT-ORDER:12\r\n\
+            RELATED-TO;RELTYPE=SIBLING:def\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:DISPLAY\r\n\
+            DESCRIPTION:Reminder\r\n\
+            END:VALARM\r\n\
+            END:VTODO\r\n\
+            END:VCALENDAR\r\n";
+        let properties = vec![
+            Property::new("SUMMARY", "New name"),
+            Property::new("STATUS", "COMPLETED"),
+        ];
+        let updated = update_calendar(calendar, &properties);
+        assert_eq!(
+            unfold(&updated),
+            vec![
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "BEGIN:VTODO",
+                "UID:abc",
+                "X-APPLE-SORT-ORDER:12",
+                "RELATED-TO;RELTYPE=SIBLING:def",
+                "BEGIN:VALARM",
+                "ACTION:DISPLAY",
+                "DESCRIPTION:Reminder",
+                "END:VALARM",
+                "SUMMARY:New name",
+                "STATUS:COMPLETED",
+                "END:VTODO",
+                "END:VCALENDAR",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/remote.php/dav/calendars/me/tasks/abc.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>&quot;1a2b&quot;</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR&#13;
+BEGIN:VTODO&#13;
+UID:abc&#13;
+SUMMARY:Fish &amp; chips&#13;
+END:VTODO&#13;
+END:VCALENDAR&#13;
+</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>def.ics</d:href>
+    <d:propstat>
+      <d:prop><d:getetag/><cal:calendar-data><![CDATA[BEGIN:VCALENDAR
+BEGIN:VTODO
+UID:def
+SUMMARY:<b>Bold</b>
+END:VTODO
+END:VCALENDAR]]></cal:calendar-data></d:prop>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let todos = parse_multistatus(xml);
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].href, "/remote.php/dav/calendars/me/tasks/abc.ics");
+        assert_eq!(todos[0].etag.as_deref(), Some("\"1a2b\""));
+        let summary = |todo: &RemoteTodo| {
+            let properties = vtodo_properties(&todo.calendar).unwrap();
+            unescape_text(&find(&properties, "SUMMARY").unwrap().value)
+        };
+        assert_eq!(summary(&todos[0]), "Fish & chips");
+        assert_eq!(todos[1].etag, None);
+        assert_eq!(summary(&todos[1]), "<b>Bold</b>");
+
+        let collection = "https://cloud.example.com/remote.php/dav/calendars/me/tasks/";
+        assert_eq!(
+            resolve_href(collection, &todos[0].href),
+            "https://cloud.example.com/remote.php/dav/calendars/me/tasks/abc.ics"
+        );
+        assert_eq!(
+            resolve_href(collection, &todos[1].href),
+            "https://cloud.example.com/remote.php/dav/calendars/me/tasks/def.ics"
+        );
+        assert_eq!(
+            resolve_href(collection, "https://other.example.com/x.ics"),
+            "https://other.example.com/x.ics"
+        );
+    }
+}
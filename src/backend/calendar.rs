@@ -0,0 +1,340 @@
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc,
+    Weekday,
+};
+
+/// The calendar used for the rest of the program, set on startup and
+/// again when switching to another profile's database
+static CALENDAR: RwLock<Option<Calendar>> = RwLock::new(None);
+
+/// The timezone days are counted in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Zone {
+    /// Whatever timezone the machine is set to, following its DST changes
+    #[default]
+    Local,
+    /// A fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+impl FromStr for Zone {
+    type Err = anyhow::Error;
+
+    /// Parses "local", "UTC", or an offset like "+05:30"
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "" | "local" => Ok(Zone::Local),
+            "utc" | "z" => Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap())),
+            _ => {
+                let offset = FixedOffset::from_str(s).with_context(|| {
+                    format!(
+                        "'{}' is not a valid timezone, use local, UTC, or an offset like +05:30",
+                        s
+                    )
+                })?;
+                Ok(Zone::Fixed(offset))
+            }
+        }
+    }
+}
+
+/// Struct that holds where days and weeks begin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calendar {
+    pub zone: Zone,
+    pub week_start: Weekday,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            zone: Zone::Local,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl Calendar {
+    /// Returns the date the `instant` falls on
+    pub fn date_of<Tz: TimeZone>(&self, instant: &DateTime<Tz>) -> NaiveDate {
+        match self.zone {
+            Zone::Local => instant.with_timezone(&Local).date_naive(),
+            Zone::Fixed(offset) => instant.with_timezone(&offset).date_naive(),
+        }
+    }
+
+    /// Returns the time of day the `instant` falls on
+    pub fn time_of<Tz: TimeZone>(&self, instant: &DateTime<Tz>) -> NaiveTime {
+        match self.zone {
+            Zone::Local => instant.with_timezone(&Local).time(),
+            Zone::Fixed(offset) => instant.with_timezone(&offset).time(),
+        }
+    }
+
+    /// Returns when the `date` starts and when the day after it starts
+    pub fn day_bounds(&self, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self.zone {
+            Zone::Local => to_utc(day_bounds_in(date, &Local)),
+            Zone::Fixed(offset) => to_utc(day_bounds_in(date, &offset)),
+        }
+    }
+
+    /// Returns when the week holding the `date` starts and when the next one starts
+    pub fn week_bounds(&self, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self.zone {
+            Zone::Local => to_utc(week_bounds_in(date, self.week_start, &Local)),
+            Zone::Fixed(offset) => to_utc(week_bounds_in(date, self.week_start, &offset)),
+        }
+    }
+}
+
+fn to_utc<Tz: TimeZone>(bounds: (DateTime<Tz>, DateTime<Tz>)) -> (DateTime<Utc>, DateTime<Utc>) {
+    (bounds.0.with_timezone(&Utc), bounds.1.with_timezone(&Utc))
+}
+
+/// Sets the calendar used for working out days and weeks from now on
+pub fn set_calendar(calendar: Calendar) {
+    if let Ok(mut current) = CALENDAR.write() {
+        *current = Some(calendar);
+    }
+}
+
+/// Returns the calendar used for working out days and weeks
+pub fn calendar() -> Calendar {
+    CALENDAR
+        .read()
+        .ok()
+        .and_then(|current| *current)
+        .unwrap_or_default()
+}
+
+/// Returns today's date in the configured timezone
+pub fn today() -> NaiveDate {
+    calendar().date_of(&Utc::now())
+}
+
+/// Describes how far the `date` is from `today` in words, i.e. "yesterday",
+/// "3 days ago", or "in 2 weeks". Further out dates are rounded down to
+/// weeks, months, or years.
+pub fn relative_day(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (date - today).num_days();
+    let (count, unit) = match days.abs() {
+        0 => return String::from("today"),
+        1 if days > 0 => return String::from("tomorrow"),
+        1 => return String::from("yesterday"),
+        n @ 2..=13 => (n, "day"),
+        n @ 14..=59 => (n / 7, "week"),
+        n @ 60..=729 => (n / 30, "month"),
+        n => (n / 365, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    if days > 0 {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+/// Returns the first day of the week the `date` is in,
+/// for weeks beginning on `week_start`
+pub fn start_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days_in =
+        (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - Days::new(days_in as u64)
+}
+
+/// Returns when the `date` begins in the timezone. This is midnight, unless a
+/// DST change skips over midnight, in which case it's the first time that exists.
+pub fn start_of_day<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Tz> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    // DST gaps are at most a couple of hours, so stepping through the day will find one
+    for minutes in (0..24 * 60).step_by(15) {
+        if let Some(start) = tz
+            .from_local_datetime(&(midnight + TimeDelta::minutes(minutes)))
+            .earliest()
+        {
+            return start;
+        }
+    }
+    tz.from_utc_datetime(&midnight)
+}
+
+/// Returns when the `date` begins and when the day after it begins.
+/// Days around DST changes are 23 or 25 hours long rather than 24.
+pub fn day_bounds_in<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> (DateTime<Tz>, DateTime<Tz>) {
+    let next_day = date.succ_opt().unwrap_or(date);
+    (start_of_day(date, tz), start_of_day(next_day, tz))
+}
+
+/// Returns when the week holding the `date` begins and when the next one begins
+pub fn week_bounds_in<Tz: TimeZone>(
+    date: NaiveDate,
+    week_start: Weekday,
+    tz: &Tz,
+) -> (DateTime<Tz>, DateTime<Tz>) {
+    let first_day = start_of_week(date, week_start);
+    let next_week = first_day + Days::new(7);
+    (start_of_day(first_day, tz), start_of_day(next_week, tz))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{LocalResult, NaiveDateTime};
+
+    use super::*;
+
+    /// US Eastern time for 2024, which moved to EDT on March 10th
+    /// and back to EST on November 3rd, both at 2am
+    #[derive(Clone, Copy, Debug)]
+    struct Eastern;
+
+    fn est() -> FixedOffset {
+        FixedOffset::west_opt(5 * 3600).unwrap()
+    }
+
+    fn edt() -> FixedOffset {
+        FixedOffset::west_opt(4 * 3600).unwrap()
+    }
+
+    fn instant(date: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date).unwrap().to_utc()
+    }
+
+    impl TimeZone for Eastern {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Eastern
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // Keep whichever offsets map back onto themselves, earliest instant first
+            let offsets: Vec<FixedOffset> = [edt(), est()]
+                .into_iter()
+                .filter(|offset| {
+                    let utc = *local - TimeDelta::seconds(offset.local_minus_utc() as i64);
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+            match offsets[..] {
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let dst_starts = instant("2024-03-10T07:00:00Z").naive_utc();
+            let dst_ends = instant("2024-11-03T06:00:00Z").naive_utc();
+            if dst_starts <= *utc && *utc < dst_ends {
+                edt()
+            } else {
+                est()
+            }
+        }
+    }
+
+    #[test]
+    fn test_relative_day() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        for (days, expected) in [
+            (0, "today"),
+            (1, "tomorrow"),
+            (-1, "yesterday"),
+            (3, "in 3 days"),
+            (-13, "13 days ago"),
+            (14, "in 2 weeks"),
+            (-59, "8 weeks ago"),
+            (60, "in 2 months"),
+            (-45, "6 weeks ago"),
+            (-400, "13 months ago"),
+            (800, "in 2 years"),
+        ] {
+            let date = today + TimeDelta::days(days);
+            assert_eq!(relative_day(date, today), expected, "{}", days);
+        }
+    }
+
+    #[test]
+    fn test_parse_zone() {
+        assert_eq!("local".parse::<Zone>().unwrap(), Zone::Local);
+        assert_eq!(
+            "UTC".parse::<Zone>().unwrap(),
+            Zone::Fixed(FixedOffset::east_opt(0).unwrap())
+        );
+        assert_eq!(
+            "+05:30".parse::<Zone>().unwrap(),
+            Zone::Fixed(FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap())
+        );
+        assert!("Mars/Olympus_Mons".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn test_start_of_week() {
+        // A Wednesday
+        let date = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        assert_eq!(
+            start_of_week(date, Weekday::Mon),
+            NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()
+        );
+        assert_eq!(
+            start_of_week(date, Weekday::Sun),
+            NaiveDate::from_ymd_opt(2024, 3, 10).unwrap()
+        );
+        assert_eq!(start_of_week(date, Weekday::Wed), date);
+        assert_eq!(
+            start_of_week(date, Weekday::Thu),
+            NaiveDate::from_ymd_opt(2024, 3, 7).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day_bounds_across_dst() {
+        // An ordinary day is 24 hours
+        let (start, end) = day_bounds_in(NaiveDate::from_ymd_opt(2024, 3, 9).unwrap(), &Eastern);
+        assert_eq!(start, instant("2024-03-09T05:00:00Z"));
+        assert_eq!(end - start, TimeDelta::hours(24));
+
+        // Springing forward loses an hour
+        let (start, end) = day_bounds_in(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(), &Eastern);
+        assert_eq!(start, instant("2024-03-10T05:00:00Z"));
+        assert_eq!(end, instant("2024-03-11T04:00:00Z"));
+        assert_eq!(end - start, TimeDelta::hours(23));
+
+        // Falling back gains one
+        let (start, end) = day_bounds_in(NaiveDate::from_ymd_opt(2024, 11, 3).unwrap(), &Eastern);
+        assert_eq!(start, instant("2024-11-03T04:00:00Z"));
+        assert_eq!(end, instant("2024-11-04T05:00:00Z"));
+        assert_eq!(end - start, TimeDelta::hours(25));
+    }
+
+    #[test]
+    fn test_week_bounds_across_dst() {
+        let (start, end) = week_bounds_in(
+            NaiveDate::from_ymd_opt(2024, 3, 13).unwrap(),
+            Weekday::Sun,
+            &Eastern,
+        );
+        assert_eq!(start, instant("2024-03-10T05:00:00Z"));
+        assert_eq!(end, instant("2024-03-17T04:00:00Z"));
+        assert_eq!(end - start, TimeDelta::hours(7 * 24 - 1));
+
+        // Something done late on Saturday night still counts towards that week,
+        // even though 7 * 24 hours from the start of the week would have passed it by
+        let late_saturday = instant("2024-03-17T03:30:00Z");
+        assert!(start <= late_saturday && late_saturday < end);
+    }
+}
@@ -0,0 +1,1601 @@
+use std::fs::read_to_string;
+use std::io::{read_to_string as read_all, stdin, stdout, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue};
+use rusqlite::Connection;
+
+use crate::backend::backup::backup_before;
+use crate::backend::backup::get_backups_dir;
+use crate::backend::calendar::{calendar, today};
+use crate::backend::config::{get_config_file, known_profiles, profile, read_config};
+use crate::backend::database::{
+    add_dependency_in_db, add_to_db, get_all_db_contents, get_archive_contents, get_dependencies,
+    get_task_events, has_next_occurrence_in_db, promote_tasks, remove_dependency_in_db, search_db,
+    update_task_in_db, TaskSelect,
+};
+use crate::backend::dependencies::Dependencies;
+use crate::backend::doctor::{check_config, check_encrypted_database, check_theme, Health};
+use crate::backend::encryption::{export_encrypted, new_passphrase};
+use crate::backend::events::{FieldChange, TaskEvent};
+use crate::backend::fuzzy::fuzzy_score;
+use crate::backend::import::import_tasks;
+use crate::backend::open::open_uri;
+use crate::backend::plan::{plan_days, Suggestion};
+use crate::backend::qr::QrCode;
+use crate::backend::query::Query;
+use crate::backend::tags::{all_tag_counts, check_tag_name, retag, Retagged};
+use crate::backend::task::{
+    find_external, format_slot_minutes, parent_ready_to_complete, parse_completed_on,
+    parse_date_added, parse_due_date, parse_scheduled_date, reopen_parents, CompleteParents,
+    Criterion, Display, ExternalRef, SortBy, Status, TagMatch, Task, Urgency,
+};
+use crate::backend::taskwarrior::{import_taskwarrior, tasks_from_taskwarrior};
+use crate::backend::ticket::{task_tickets, ticket_url};
+use crate::backend::todoist::{
+    fetch_todoist, import_todoist, is_todoist_token, tasks_from_todoist,
+};
+use crate::display::picker::pick_task;
+use crate::display::theme::get_toml_file;
+
+/// Set by `--quiet` to keep commands from printing anything but errors
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Stops commands from printing anything but errors
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Prints a line to stdout, unless `--quiet` was passed in
+pub fn say(message: impl std::fmt::Display) {
+    if !QUIET.load(Ordering::Relaxed) {
+        println!("{}", message);
+    }
+}
+
+/// Prints a line to stderr, unless `--quiet` was passed in
+pub fn complain(message: impl std::fmt::Display) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Enum of how a command from the command line turned out,
+/// so that scripts can branch on the exit code.
+/// Errors exit with 1, and clap uses 2 for invalid arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    Success,
+    NoMatch,
+    Ambiguous,
+    Empty,
+    Cancelled,
+    Blocked,
+    Unhealthy,
+}
+
+impl CommandStatus {
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            CommandStatus::Success => ExitCode::SUCCESS,
+            CommandStatus::NoMatch => ExitCode::from(3),
+            CommandStatus::Ambiguous => ExitCode::from(4),
+            CommandStatus::Empty => ExitCode::from(5),
+            CommandStatus::Cancelled => ExitCode::from(6),
+            CommandStatus::Blocked => ExitCode::from(7),
+            CommandStatus::Unhealthy => ExitCode::from(8),
+        }
+    }
+}
+
+/// Struct that holds the changes to make to a `Task` from the command line.
+/// Anything left as `None` is kept as is.
+#[derive(Default)]
+pub struct TaskChanges {
+    pub name: Option<String>,
+    pub status: Option<Status>,
+    pub urgency: Option<Urgency>,
+    pub description: Option<String>,
+    pub latest: Option<String>,
+    pub project: Option<String>,
+    pub due: Option<String>,
+    pub scheduled: Option<String>,
+    pub completed_on: Option<String>,
+    /// Acceptance criteria to add
+    pub criteria: Vec<String>,
+    /// Acceptance criteria to tick off, counting from 1
+    pub tick: Vec<usize>,
+}
+
+impl TaskChanges {
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.status.is_none()
+            && self.urgency.is_none()
+            && self.description.is_none()
+            && self.latest.is_none()
+            && self.project.is_none()
+            && self.due.is_none()
+            && self.scheduled.is_none()
+            && self.completed_on.is_none()
+            && self.criteria.is_empty()
+            && self.tick.is_empty()
+    }
+}
+
+/// Struct that holds a new `Task` given on the command line, before it is checked
+#[derive(Default)]
+pub struct NewTask {
+    pub name: String,
+    pub description: Option<String>,
+    pub urgency: Option<Urgency>,
+    pub status: Option<Status>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub due: Option<String>,
+    pub date_added: Option<String>,
+    pub external_ref: Option<String>,
+    pub external_url: Option<String>,
+    /// Name (or the start of the id) of the `Task` it's a subtask of
+    pub parent: Option<String>,
+}
+
+impl NewTask {
+    /// Checks the dates given and builds the `Task`
+    fn into_task(self, now: DateTime<Local>) -> Result<Task> {
+        let tags = if self.tags.is_empty() {
+            None
+        } else {
+            Some(self.tags.into_iter().collect())
+        };
+        let mut task = Task::new(
+            self.name,
+            self.description,
+            None,
+            self.urgency,
+            self.status,
+            tags,
+        );
+        task.project = self.project;
+        if let Some(due) = self.due {
+            task.due = parse_due_date(&due, calendar().date_of(&now))?;
+        }
+        // Keep the true age of tasks brought over from elsewhere
+        if let Some(date_added) = self.date_added {
+            task.date_added = parse_date_added(&date_added, now)?;
+        }
+        if let Some(external_ref) = self.external_ref {
+            let mut external_ref: ExternalRef = external_ref.parse()?;
+            external_ref.url = self.external_url;
+            task.external_ref = Some(external_ref);
+        } else if self.external_url.is_some() {
+            bail!("--external-url needs an --external-ref to go with it");
+        }
+        Ok(task)
+    }
+}
+
+/// Struct that holds the filters to apply when listing `Task`s from the command line
+#[derive(Clone, Debug)]
+pub struct ListFilters {
+    pub display: Display,
+    pub tags: String,
+    pub tag_match: TagMatch,
+    pub project: Option<String>,
+    pub query: Option<Query>,
+}
+
+impl Default for ListFilters {
+    fn default() -> Self {
+        ListFilters {
+            display: Display::NotCompleted,
+            tags: String::new(),
+            tag_match: TagMatch::Any,
+            project: None,
+            query: None,
+        }
+    }
+}
+
+/// Returns the `Task`s matching the `ListFilters`, most urgent first
+pub fn filtered_tasks(conn: &Connection, filters: &ListFilters) -> Result<Vec<Task>> {
+    let mut select = TaskSelect::new()
+        .display(filters.display)
+        .tags(&filters.tags, filters.tag_match)
+        .order_by(SortBy::Urgency, true);
+    if let Some(project) = &filters.project {
+        select = select.project(project);
+    }
+    if let Some(query) = &filters.query {
+        select = select.query(query);
+    }
+    Ok(select.run(conn)?.tasks)
+}
+
+/// Formats `Task`s as a plain numbered list, one `Task` per line
+pub fn format_task_list(tasks: &[Task]) -> Vec<String> {
+    if tasks.is_empty() {
+        return vec![String::from("No tasks to show")];
+    }
+    tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| format!("{:>3}. {:<8} {}", i + 1, task.urgency, task.summary()))
+        .collect()
+}
+
+/// Prints the `Task`s matching the `ListFilters`. Returns `CommandStatus::Empty`
+/// if none matched and `fail_if_empty` is true.
+pub fn list_tasks(
+    conn: &Connection,
+    filters: &ListFilters,
+    fail_if_empty: bool,
+) -> Result<CommandStatus> {
+    let tasks = filtered_tasks(conn, filters)?;
+    for line in format_task_list(&tasks) {
+        say(line);
+    }
+
+    if fail_if_empty && tasks.is_empty() {
+        Ok(CommandStatus::Empty)
+    } else {
+        Ok(CommandStatus::Success)
+    }
+}
+
+/// Prints the `Task`s whose name, description, or latest update match the `query`,
+/// best matches first. Returns `CommandStatus::NoMatch` if none did.
+pub fn search_tasks(conn: &Connection, query: &str, display: Display) -> Result<CommandStatus> {
+    let mut task_list = search_db(conn, query)?;
+    task_list.filter_tasks(Some(display), String::new(), TagMatch::Any);
+    for line in format_task_list(&task_list.tasks) {
+        say(line);
+    }
+
+    if task_list.tasks.is_empty() {
+        Ok(CommandStatus::NoMatch)
+    } else {
+        Ok(CommandStatus::Success)
+    }
+}
+
+/// Returns SQLite's `data_version`, which changes whenever another
+/// connection commits to the database
+fn data_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA data_version", (), |row| row.get(0))
+        .context("Failed to read the database's data_version")
+}
+
+/// Prints the `Task`s matching the `ListFilters`, re-printing them in place
+/// whenever the database changes. Checks for changes every `interval`
+/// and runs until interrupted.
+pub fn watch_tasks(conn: &Connection, filters: &ListFilters, interval: Duration) -> Result<()> {
+    let mut stdout = stdout();
+    let mut last_version = None;
+
+    loop {
+        let version = data_version(conn)?;
+        if last_version != Some(version) {
+            last_version = Some(version);
+
+            let lines = format_task_list(&filtered_tasks(conn, filters)?);
+            queue!(
+                stdout,
+                terminal::Clear(ClearType::All),
+                terminal::Clear(ClearType::Purge),
+                cursor::MoveTo(0, 0)
+            )?;
+            writeln!(
+                stdout,
+                "Watching tasks (updated {}) - Ctrl-c to stop",
+                Local::now().format("%H:%M:%S")
+            )?;
+            writeln!(stdout)?;
+            for line in lines {
+                writeln!(stdout, "{}", line)?;
+            }
+            stdout.flush()?;
+        }
+        sleep(interval);
+    }
+}
+
+/// Returns the `Task`s a query from the command line could be referring to.
+/// A task whose id starts with the query, or whose name matches it exactly,
+/// wins outright. Otherwise every task whose name fuzzy matches is returned.
+pub fn find_matching_tasks(tasks: Vec<Task>, query: &str) -> Vec<Task> {
+    let query = query.trim();
+    let lowered = query.to_lowercase();
+
+    let exact: Vec<Task> = tasks
+        .iter()
+        .filter(|task| {
+            task.name.to_lowercase() == lowered
+                || (query.len() >= 4 && task.get_id().to_string().starts_with(&lowered))
+        })
+        .cloned()
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    tasks
+        .into_iter()
+        .filter(|task| fuzzy_score(query, &task.name).is_some())
+        .collect()
+}
+
+/// Narrows a query down to a single `Task`. If several match, the user picks
+/// one from an inline fuzzy finder, or gets them listed out if we aren't
+/// attached to a terminal. Gives back the `CommandStatus` to exit with
+/// if a single `Task` couldn't be settled on.
+fn select_task(tasks: Vec<Task>, query: &str) -> Result<Result<Task, CommandStatus>> {
+    let mut matches = find_matching_tasks(tasks, query);
+
+    match matches.len() {
+        0 => {
+            complain(format!("No tasks matched '{}'", query));
+            Ok(Err(CommandStatus::NoMatch))
+        }
+        1 => Ok(Ok(matches.pop().unwrap())),
+        _ => {
+            if !std::io::stdin().is_terminal() {
+                complain(format!("'{}' matches more than one task:", query));
+                for task in &matches {
+                    complain(format!("  {}", task.summary()));
+                }
+                return Ok(Err(CommandStatus::Ambiguous));
+            }
+            Ok(pick_task(&matches, query)?.ok_or(CommandStatus::Cancelled))
+        }
+    }
+}
+
+/// Tells the user the `Task` is locked, returning true if it is
+fn refuse_locked(task: &Task) -> bool {
+    if task.locked {
+        complain(format!(
+            "'{}' is locked, unlock it first with `checklist unlock`",
+            task.name
+        ));
+    }
+    task.locked
+}
+
+/// Complains and returns true if the `Task` has acceptance criteria left to
+/// tick off, since it can't be completed until they all are
+fn refuse_unmet_criteria(task: &Task) -> bool {
+    match task.check_criteria() {
+        Ok(()) => false,
+        Err(e) => {
+            complain(format!("{}, see `checklist update --tick`", e));
+            true
+        }
+    }
+}
+
+/// Asks a yes or no question on the terminal, taking no for an answer if
+/// there's no terminal to ask on
+fn confirm(question: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    println!("{} (y/n)", question);
+    loop {
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        match answer.trim().to_lowercase().as_str() {
+            "y" => return Ok(true),
+            "n" | "" => return Ok(false),
+            _ => println!("You must provide either a 'y' or 'n'"),
+        }
+    }
+}
+
+/// Adds the next occurrence of the just completed `Task`, if it recurs
+fn add_next_occurrence(conn: &Connection, task: &Task) -> Result<()> {
+    let Some(next_task) = task.next_occurrence() else {
+        return Ok(());
+    };
+    // Completing it again after reopening it doesn't line up another
+    if has_next_occurrence_in_db(conn, task.get_id())? {
+        return Ok(());
+    }
+    add_to_db(conn, &next_task)?;
+    if let Some(scheduled) = next_task.scheduled {
+        say(format!("Next occurrence added for {}", scheduled));
+    }
+    Ok(())
+}
+
+/// Keeps the parents of the `Task` in step with it once its status has
+/// changed. Reopening it reopens any `Completed` parents, and completing
+/// the last subtask left completes the parent too, if `complete_parents`
+/// says so.
+fn roll_up_parents(
+    conn: &Connection,
+    task: &Task,
+    complete_parents: CompleteParents,
+) -> Result<()> {
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    if task.status != Status::Completed {
+        for parent in reopen_parents(&tasks, task) {
+            update_task_in_db(conn, &parent)?;
+            say(format!("Reopened: {}", parent.name));
+        }
+        return Ok(());
+    }
+
+    let mut done = task.clone();
+    while let Some(mut parent) = parent_ready_to_complete(&tasks, &done) {
+        let complete = match complete_parents {
+            CompleteParents::Always => true,
+            CompleteParents::Never => false,
+            CompleteParents::Ask => confirm(&format!(
+                "Every subtask of '{}' is done, complete it too?",
+                parent.name
+            ))?,
+        };
+        if !complete {
+            break;
+        }
+        parent.status = Status::Completed;
+        parent.completed_on = Some(Local::now());
+        parent.stop_timer();
+        update_task_in_db(conn, &parent)?;
+        say(format!("Completed: {}", parent.name));
+        add_next_occurrence(conn, &parent)?;
+
+        tasks = get_all_db_contents(conn)?.tasks;
+        done = parent;
+    }
+    Ok(())
+}
+
+/// Adds the `NewTask` to the database. If it has an external reference that a
+/// `Task` in the database (archive included) already has, nothing is added,
+/// so imports can be run again safely.
+pub fn add_task(conn: &Connection, mut new_task: NewTask) -> Result<CommandStatus> {
+    let parent = match new_task.parent.take() {
+        Some(query) => match select_task(get_all_db_contents(conn)?.tasks, &query)? {
+            Ok(parent) => Some(parent.get_id()),
+            Err(status) => {
+                complain("No task was added");
+                return Ok(status);
+            }
+        },
+        None => None,
+    };
+    let mut task = new_task.into_task(Local::now())?;
+    task.parent = parent;
+    if let Some(external_ref) = &task.external_ref {
+        let mut tasks = get_all_db_contents(conn)?.tasks;
+        tasks.extend(get_archive_contents(conn)?.tasks);
+        if let Some(existing) = find_external(&tasks, external_ref) {
+            say(format!(
+                "Already added from {}: {}",
+                external_ref,
+                existing.summary()
+            ));
+            return Ok(CommandStatus::Success);
+        }
+    }
+    add_to_db(conn, &task)?;
+    say(format!("Added: {}", task.summary()));
+    Ok(CommandStatus::Success)
+}
+
+/// Marks the `Task` matching the query as `Completed`,
+/// creating its next occurrence if it recurs.
+/// Only `Task`s in the `project` are considered if one is given.
+/// Its parent is completed too if it was the last subtask left, as
+/// `complete_parents` says.
+pub fn complete_task(
+    conn: &Connection,
+    query: &str,
+    project: Option<&str>,
+    complete_parents: CompleteParents,
+) -> Result<CommandStatus> {
+    let mut task_list = get_all_db_contents(conn)?;
+    task_list
+        .tasks
+        .retain(|task| task.status != Status::Completed);
+    if let Some(project) = project {
+        task_list.filter_project(project);
+    }
+
+    let mut task = match select_task(task_list.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => {
+            complain("No task was completed");
+            return Ok(status);
+        }
+    };
+    if refuse_locked(&task) || refuse_unmet_criteria(&task) {
+        return Ok(CommandStatus::Blocked);
+    }
+
+    task.status = Status::Completed;
+    task.completed_on = Some(Local::now());
+    task.stop_timer();
+    update_task_in_db(conn, &task)?;
+    say(format!("Completed: {}", task.name));
+    add_next_occurrence(conn, &task)?;
+    roll_up_parents(conn, &task, complete_parents)?;
+
+    Ok(CommandStatus::Success)
+}
+
+/// Adds a copy of the `Task` matching the query, with its own id.
+/// The copy starts out `Open` if `reopen` is true.
+pub fn clone_task(conn: &Connection, query: &str, reopen: bool) -> Result<CommandStatus> {
+    let task = match select_task(get_all_db_contents(conn)?.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => {
+            complain("No task was cloned");
+            return Ok(status);
+        }
+    };
+
+    let copy = task.duplicate(reopen);
+    add_to_db(conn, &copy)?;
+    say(format!("Added: {}", copy.summary()));
+    Ok(CommandStatus::Success)
+}
+
+/// Locks the `Task` matching the query so it can't be updated or deleted,
+/// or unlocks it if `locked` is false
+pub fn set_task_lock(conn: &Connection, query: &str, locked: bool) -> Result<CommandStatus> {
+    let mut task = match select_task(get_all_db_contents(conn)?.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+
+    task.locked = locked;
+    update_task_in_db(conn, &task)?;
+    if locked {
+        say(format!("Locked: {}", task.name));
+    } else {
+        say(format!("Unlocked: {}", task.name));
+    }
+    Ok(CommandStatus::Success)
+}
+
+/// Copies the tasks of a `--test` or `--memory` session into the real database
+pub fn promote_session(from: &Connection, to: &Connection) -> Result<CommandStatus> {
+    let (promoted, skipped) = promote_tasks(from, to)?;
+    if promoted + skipped == 0 {
+        complain("There are no tasks to promote");
+        return Ok(CommandStatus::Empty);
+    }
+    say(promoted_message(promoted, skipped));
+    Ok(CommandStatus::Success)
+}
+
+/// Describes how promoting tasks into the real database went
+pub fn promoted_message(promoted: usize, skipped: usize) -> String {
+    let mut message = format!("Promoted {} task(s) to the real database", promoted);
+    if skipped > 0 {
+        message.push_str(&format!(", {} already there", skipped));
+    }
+    message
+}
+
+/// Imports the tasks in a CSV written by `checklist export`,
+/// read from standard input if the path is "-". The database is backed up
+/// first, keeping the newest `backup_count` backups.
+pub fn import_file(conn: &Connection, path: &Path, backup_count: usize) -> Result<CommandStatus> {
+    let text = if path == Path::new("-") {
+        read_all(stdin()).context("Failed to read the CSV from standard input")?
+    } else {
+        read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?
+    };
+
+    if let Some(backup) = backup_before(conn, "import", backup_count)? {
+        say(format!("Backed up the database to {:?}", backup));
+    }
+
+    let (added, skipped) = import_tasks(conn, &text)?;
+    let mut message = format!("Imported {} task(s)", added);
+    if skipped > 0 {
+        message.push_str(&format!(", {} already there", skipped));
+    }
+    say(message);
+    Ok(CommandStatus::Success)
+}
+
+/// Imports the tasks (and the dependencies between them) from a TaskWarrior
+/// JSON export, read from standard input if the path is -
+pub fn import_taskwarrior_file(
+    conn: &Connection,
+    path: &Path,
+    backup_count: usize,
+) -> Result<CommandStatus> {
+    let text = if path == Path::new("-") {
+        read_all(stdin()).context("Failed to read the TaskWarrior export from standard input")?
+    } else {
+        read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?
+    };
+    let (tasks, dependencies) = tasks_from_taskwarrior(&text)?;
+
+    if let Some(backup) = backup_before(conn, "import", backup_count)? {
+        say(format!("Backed up the database to {:?}", backup));
+    }
+
+    let (added, skipped) = import_taskwarrior(conn, &tasks, &dependencies)?;
+    let mut message = format!("Imported {} task(s) from TaskWarrior", added);
+    if skipped > 0 {
+        message.push_str(&format!(", {} already there", skipped));
+    }
+    say(message);
+    Ok(CommandStatus::Success)
+}
+
+/// Imports the tasks from a Todoist export, read from standard input if the
+/// source is -, or fetched from Todoist if it's an API token instead of a file
+pub fn import_todoist_source(
+    conn: &Connection,
+    source: &str,
+    backup_count: usize,
+) -> Result<CommandStatus> {
+    let text = if source == "-" {
+        read_all(stdin()).context("Failed to read the Todoist export from standard input")?
+    } else if Path::new(source).exists() {
+        read_to_string(source).with_context(|| format!("Failed to read {:?}", source))?
+    } else if is_todoist_token(source) {
+        fetch_todoist(source)?
+    } else {
+        bail!("'{}' is neither a file nor a Todoist API token", source);
+    };
+    let tasks = tasks_from_todoist(&text)?;
+
+    if let Some(backup) = backup_before(conn, "import", backup_count)? {
+        say(format!("Backed up the database to {:?}", backup));
+    }
+
+    let (added, skipped) = import_todoist(conn, tasks)?;
+    let mut message = format!("Imported {} task(s) from Todoist", added);
+    if skipped > 0 {
+        message.push_str(&format!(", {} already there", skipped));
+    }
+    say(message);
+    Ok(CommandStatus::Success)
+}
+
+/// Returns the tags given that no task uses, complaining about each one
+fn unused_tags(conn: &Connection, tags: &[String]) -> Result<Vec<String>> {
+    let used = all_tag_counts(conn)?;
+    let unused: Vec<String> = tags
+        .iter()
+        .filter(|tag| !used.iter().any(|(used, _)| used == *tag))
+        .cloned()
+        .collect();
+    for tag in &unused {
+        complain(format!("No task is tagged '{}'", tag));
+    }
+    Ok(unused)
+}
+
+/// Reports on retagging, failing with `Blocked` if a locked task kept the old tag
+fn retagged_status(retagged: Retagged, done: &str) -> CommandStatus {
+    say(retagged.message(done));
+    if retagged.locked > 0 {
+        CommandStatus::Blocked
+    } else {
+        CommandStatus::Success
+    }
+}
+
+/// Renames the tag on every task, archived ones included. Renaming to
+/// a tag that is already in use is refused, since that is a merge.
+pub fn rename_tag(conn: &Connection, old: &str, new: &str) -> Result<CommandStatus> {
+    check_tag_name(new)?;
+    if !unused_tags(conn, &[old.to_string()])?.is_empty() {
+        return Ok(CommandStatus::NoMatch);
+    }
+    if all_tag_counts(conn)?.iter().any(|(tag, _)| tag == new) {
+        complain(format!(
+            "'{}' is already a tag, use `checklist tag merge` to fold '{}' into it",
+            new, old
+        ));
+        return Ok(CommandStatus::Blocked);
+    }
+
+    let retagged = retag(conn, &[old.to_string()], Some(new))?;
+    Ok(retagged_status(
+        retagged,
+        &format!("Renamed '{}' to '{}'", old, new),
+    ))
+}
+
+/// Folds the tags into the `into` tag on every task, archived ones included
+pub fn merge_tags(conn: &Connection, tags: &[String], into: &str) -> Result<CommandStatus> {
+    check_tag_name(into)?;
+    if unused_tags(conn, tags)?.len() == tags.len() {
+        return Ok(CommandStatus::NoMatch);
+    }
+
+    let retagged = retag(conn, tags, Some(into))?;
+    Ok(retagged_status(
+        retagged,
+        &format!("Merged '{}' into '{}'", tags.join("', '"), into),
+    ))
+}
+
+/// Takes the tag off every task, archived ones included
+pub fn remove_tag(conn: &Connection, tag: &str) -> Result<CommandStatus> {
+    if !unused_tags(conn, &[tag.to_string()])?.is_empty() {
+        return Ok(CommandStatus::NoMatch);
+    }
+
+    let retagged = retag(conn, &[tag.to_string()], None)?;
+    Ok(retagged_status(retagged, &format!("Removed '{}'", tag)))
+}
+
+/// What goes in the QR code printed for a `Task`
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum QrContent {
+    /// The task's summary, i.e. its name, status, project and tags
+    Summary,
+    /// A checklist://task/<id> link to the task
+    Uri,
+}
+
+/// Checks the config, database, and theme.toml can be used, printing what
+/// was found along with how to fix anything that's wrong. Returns
+/// `CommandStatus::Unhealthy` if anything keeps checklist from working.
+pub fn run_doctor(testing: bool) -> Result<CommandStatus> {
+    let (mut findings, config) = check_config(&get_config_file(testing)?);
+    if let Some(config) = config {
+        findings.extend(check_encrypted_database(&config));
+    }
+    findings.push(check_theme(&get_toml_file()?));
+
+    for finding in &findings {
+        say(format!(
+            "{} {}: {}",
+            finding.health, finding.check, finding.detail
+        ));
+        if let Some(fix) = &finding.fix {
+            say(format!("    Fix: {}", fix));
+        }
+    }
+
+    let count = |health: Health| {
+        findings
+            .iter()
+            .filter(|finding| finding.health == health)
+            .count()
+    };
+    let (problems, warnings) = (count(Health::Problem), count(Health::Warning));
+    say("");
+    if problems + warnings == 0 {
+        say("Everything looks good");
+    } else {
+        say(format!(
+            "Found {} problem(s) and {} warning(s)",
+            problems, warnings
+        ));
+    }
+    if problems > 0 {
+        Ok(CommandStatus::Unhealthy)
+    } else {
+        Ok(CommandStatus::Success)
+    }
+}
+
+/// Prints the profiles there are config files for and the database each one
+/// uses, marking the one in use with a '*'
+pub fn show_profiles() -> Result<()> {
+    let profiles = known_profiles()?;
+    let width = profiles
+        .iter()
+        .map(|profile| profile.label().len())
+        .max()
+        .unwrap_or_default();
+    let current = profile();
+    for profile in profiles {
+        let database = match &profile.config {
+            Ok(config) => format!("{}", config.db_path.display()),
+            Err(e) => format!("(can't read {:?}: {})", profile.config_file, e),
+        };
+        let marker = if profile.name == current { '*' } else { ' ' };
+        println!("{} {:<width$}  {}", marker, profile.label(), database);
+    }
+    Ok(())
+}
+
+/// Encrypts the database the config points to with a new passphrase, which
+/// is asked for from then on whenever checklist opens it
+pub fn encrypt_db(conn: Connection, testing: bool) -> Result<()> {
+    if conn.path().filter(|path| !path.is_empty()).is_none() {
+        bail!("An in-memory database can't be encrypted");
+    }
+    let mut config = read_config(testing).context("Failed to read in config")?;
+    if config.encrypted {
+        bail!("The database at {:?} is already encrypted", config.db_path);
+    }
+    let passphrase = new_passphrase(&config)?;
+
+    // Written alongside and then moved over, so a failure partway leaves the original alone
+    let encrypted_path = config.db_path.with_extension("encrypting");
+    export_encrypted(&conn, &encrypted_path, &passphrase)?;
+    drop(conn);
+    std::fs::rename(&encrypted_path, &config.db_path).with_context(|| {
+        format!(
+            "Failed to replace {:?} with the encrypted copy at {:?}",
+            config.db_path, encrypted_path
+        )
+    })?;
+    config.encrypted = true;
+    config.save(testing)?;
+
+    say(format!("Encrypted the database at {:?}", config.db_path));
+    let backups = get_backups_dir()?;
+    if backups.exists() {
+        say(format!(
+            "Backups made before now in {:?} aren't encrypted, so delete them if they hold anything sensitive",
+            backups
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the `checklist://` URI referring to the `Task`
+pub fn task_uri(task: &Task) -> String {
+    format!("checklist://task/{}", task.get_id())
+}
+
+/// Prints the details of the `Task` matching the query, followed by
+/// a QR code of it if `qr` is given
+pub fn show_task(conn: &Connection, query: &str, qr: Option<QrContent>) -> Result<CommandStatus> {
+    let task = match select_task(get_all_db_contents(conn)?.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+
+    say(task.summary());
+    say(format!("  Id: {}", task.get_id()));
+    say(format!("  Urgency: {}", task.urgency));
+    if let Some(description) = &task.description {
+        say(format!("  Description: {}", description));
+    }
+    if let Some(latest) = &task.latest {
+        say(format!("  Latest: {}", latest));
+    }
+    if let Some(due) = task.due {
+        say(format!("  Due: {}", due));
+    }
+    if let Some(scheduled) = task.scheduled {
+        say(format!("  Snoozed until: {}", scheduled));
+    }
+    if let Some(external_ref) = &task.external_ref {
+        match &external_ref.url {
+            Some(url) => say(format!("  From: {} ({})", external_ref, url)),
+            None => say(format!("  From: {}", external_ref)),
+        }
+    }
+
+    if let Some(content) = qr {
+        let text = match content {
+            QrContent::Summary => task.summary(),
+            QrContent::Uri => task_uri(&task),
+        };
+        say("");
+        for line in QrCode::encode(&text)?.to_lines() {
+            say(line);
+        }
+    }
+
+    Ok(CommandStatus::Success)
+}
+
+/// Prints how the `Task` matching the query (archived ones included)
+/// changed over time, oldest change first
+pub fn show_history(conn: &Connection, query: &str) -> Result<CommandStatus> {
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.extend(get_archive_contents(conn)?.tasks);
+    let task = match select_task(tasks, query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+
+    let changes = history_lines(&get_task_events(conn, task.get_id())?);
+    say(task.summary());
+    if changes.is_empty() {
+        say("  No changes recorded");
+    }
+    for change in changes {
+        say(format!("  {}", change));
+    }
+    Ok(CommandStatus::Success)
+}
+
+/// Makes the `Task` matching the query wait on the one matching `on`, or with
+/// `remove`, stops it waiting. Refuses a dependency that would go in a circle.
+pub fn depend_task(
+    conn: &Connection,
+    query: &str,
+    on: &str,
+    remove: bool,
+) -> Result<CommandStatus> {
+    let tasks = get_all_db_contents(conn)?.tasks;
+    let task = match select_task(tasks.clone(), query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+    let blocker = match select_task(tasks, on)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+
+    if remove {
+        if remove_dependency_in_db(conn, task.get_id(), blocker.get_id())? {
+            say(format!(
+                "'{}' no longer waits on '{}'",
+                task.name, blocker.name
+            ));
+        } else {
+            complain(format!(
+                "'{}' wasn't waiting on '{}'",
+                task.name, blocker.name
+            ));
+        }
+        return Ok(CommandStatus::Success);
+    }
+    if task.get_id() == blocker.get_id() {
+        bail!("A task can't wait on itself");
+    }
+    let dependencies = Dependencies::new(&get_dependencies(conn)?);
+    if dependencies.waits_on(blocker.get_id(), task.get_id()) {
+        bail!(
+            "'{}' already waits on '{}', so that would go in a circle",
+            blocker.name,
+            task.name
+        );
+    }
+    add_dependency_in_db(conn, task.get_id(), blocker.get_id())?;
+    say(format!("'{}' now waits on '{}'", task.name, blocker.name));
+    Ok(CommandStatus::Success)
+}
+
+/// Prints the dependency chain around the `Task` matching the query: what
+/// waits on it, and what it waits on
+pub fn show_graph(conn: &Connection, query: &str) -> Result<CommandStatus> {
+    let tasks = get_all_db_contents(conn)?.tasks;
+    let task = match select_task(tasks.clone(), query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+    let dependencies = Dependencies::new(&get_dependencies(conn)?);
+    for line in dependencies.graph(&tasks, task.get_id()) {
+        say(line.text);
+    }
+    Ok(CommandStatus::Success)
+}
+
+/// Returns each change as "YYYY-MM-DD HH:MM  urgency: Medium → High",
+/// leaving out the ones that didn't really change anything
+pub fn history_lines(events: &[TaskEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| {
+            FieldChange::from_event(event)
+                .map(|change| format!("{}  {}", event.changed.format("%Y-%m-%d %H:%M"), change))
+        })
+        .collect()
+}
+
+/// Opens the tracker in the browser at the tickets mentioned in the `Task`
+/// matching the query, using the `ticket_url` template from the config
+pub fn open_ticket(
+    conn: &Connection,
+    query: &str,
+    template: Option<&str>,
+) -> Result<CommandStatus> {
+    let Some(template) = template else {
+        bail!("Set \"ticket_url\" in the config file (see `checklist where -c`) to open tickets, i.e. \"https://example.atlassian.net/browse/{{ticket}}\"");
+    };
+    let task = match select_task(get_all_db_contents(conn)?.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => return Ok(status),
+    };
+
+    let tickets = task_tickets(&task);
+    if tickets.is_empty() {
+        complain(format!("No tickets mentioned in '{}'", task.name));
+        return Ok(CommandStatus::NoMatch);
+    }
+    for ticket in tickets {
+        let url = ticket_url(template, &ticket);
+        open_uri(&url)?;
+        say(format!("Opened {}", url));
+    }
+    Ok(CommandStatus::Success)
+}
+
+/// Applies the `TaskChanges` given to the `Task` matching the query.
+/// Completing or reopening it rolls up to its parents, as `complete_parents` says.
+pub fn update_task(
+    conn: &Connection,
+    query: &str,
+    changes: TaskChanges,
+    complete_parents: CompleteParents,
+) -> Result<CommandStatus> {
+    if changes.is_empty() {
+        bail!("Nothing to update, pass in at least one field to change (see --help)");
+    }
+    // Check the dates before asking the user to pick a task
+    let today = today();
+    let due = match &changes.due {
+        Some(due) => Some(parse_due_date(due, today)?),
+        None => None,
+    };
+    let scheduled = match &changes.scheduled {
+        Some(scheduled) => Some(parse_scheduled_date(scheduled, today)?),
+        None => None,
+    };
+
+    let task_list = get_all_db_contents(conn)?;
+    let mut task = match select_task(task_list.tasks, query)? {
+        Ok(task) => task,
+        Err(status) => {
+            complain("No task was updated");
+            return Ok(status);
+        }
+    };
+    if refuse_locked(&task) {
+        return Ok(CommandStatus::Blocked);
+    }
+    let was_completed = task.status == Status::Completed;
+
+    if let Some(name) = changes.name {
+        task.name = name;
+    }
+    if let Some(urgency) = changes.urgency {
+        task.urgency = urgency;
+    }
+    if let Some(description) = changes.description {
+        task.description = Some(description).filter(|d| !d.is_empty());
+    }
+    if let Some(latest) = changes.latest {
+        task.latest = Some(latest).filter(|l| !l.is_empty());
+    }
+    if let Some(project) = changes.project {
+        task.project = Some(project).filter(|p| !p.is_empty());
+    }
+    if let Some(due) = due {
+        task.due = due;
+    }
+    if let Some(scheduled) = scheduled {
+        task.scheduled = scheduled;
+    }
+    for criterion in &changes.criteria {
+        let criterion = Criterion::new(criterion);
+        if criterion.text.is_empty() {
+            bail!("An acceptance criterion can't be blank");
+        }
+        task.criteria.push(criterion);
+    }
+    for &number in &changes.tick {
+        match number.checked_sub(1).and_then(|i| task.criteria.get_mut(i)) {
+            Some(criterion) => criterion.done = true,
+            None => bail!(
+                "'{}' has {} acceptance criteria, there's no number {}",
+                task.name,
+                task.criteria.len(),
+                number
+            ),
+        }
+    }
+    if let Some(status) = changes.status {
+        task.status = status;
+        if status != Status::Completed {
+            task.completed_on = None;
+        } else if !was_completed {
+            task.completed_on = Some(Local::now());
+            task.stop_timer();
+        }
+    }
+    if let Some(completed_on) = changes.completed_on {
+        if task.status != Status::Completed {
+            bail!("Only completed tasks have a completion date to change");
+        }
+        task.completed_on = Some(parse_completed_on(&completed_on, &task, Local::now())?);
+    }
+    if !was_completed && task.status == Status::Completed && refuse_unmet_criteria(&task) {
+        return Ok(CommandStatus::Blocked);
+    }
+
+    update_task_in_db(conn, &task)?;
+    say(format!("Updated: {}", task.summary()));
+
+    if !was_completed && task.status == Status::Completed {
+        add_next_occurrence(conn, &task)?;
+    }
+    if was_completed != (task.status == Status::Completed) {
+        roll_up_parents(conn, &task, complete_parents)?;
+    }
+
+    Ok(CommandStatus::Success)
+}
+
+/// Suggests which of the next `days` to do the open `Task`s on, filling each
+/// day up to `capacity` minutes, and prints the plan. Once confirmed (or
+/// straight away, if `yes`), each task is scheduled for the day it was given.
+pub fn plan_tasks(conn: &Connection, days: u64, capacity: i64, yes: bool) -> Result<CommandStatus> {
+    let tasks = get_all_db_contents(conn)?.tasks;
+    let today = today();
+    let plan = plan_days(&tasks, today, days, capacity);
+
+    let mut day = today;
+    for _ in 0..days {
+        let suggestions: Vec<&Suggestion> = plan
+            .suggestions
+            .iter()
+            .filter(|suggestion| suggestion.day == day)
+            .collect();
+        if !suggestions.is_empty() {
+            say(format!(
+                "{} ({} of {})",
+                day.format("%a %Y-%m-%d"),
+                format_slot_minutes(plan.minutes_on(day)),
+                format_slot_minutes(capacity)
+            ));
+            for suggestion in suggestions {
+                let mut notes = vec![format_slot_minutes(suggestion.minutes)];
+                if suggestion.guessed {
+                    notes.push(String::from("no estimate"));
+                }
+                if let Some(due) = suggestion.late {
+                    notes.push(format!("after its due date {}", due));
+                }
+                say(format!("    {} ({})", suggestion.name, notes.join(", ")));
+            }
+        }
+        let Some(next) = day.succ_opt() else {
+            break;
+        };
+        day = next;
+    }
+    if !plan.unplanned.is_empty() {
+        say("No room for:");
+        for (name, minutes) in &plan.unplanned {
+            say(format!("    {} ({})", name, format_slot_minutes(*minutes)));
+        }
+    }
+
+    if plan.suggestions.is_empty() {
+        say("Nothing to plan");
+        return Ok(CommandStatus::Success);
+    }
+    if !yes && !confirm("Schedule these?")? {
+        say("Nothing was scheduled");
+        return Ok(CommandStatus::Success);
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start scheduling tasks")?;
+    for suggestion in &plan.suggestions {
+        if let Some(task) = tasks.iter().find(|task| task.get_id() == suggestion.id) {
+            let mut task = task.clone();
+            task.scheduled = Some(suggestion.day);
+            update_task_in_db(&tx, &task)?;
+        }
+    }
+    tx.commit().context("Failed to schedule tasks")?;
+    say(format!("Scheduled {} task(s)", plan.suggestions.len()));
+    Ok(CommandStatus::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::backend::database::get_db;
+
+    #[test]
+    fn test_find_matching_tasks() {
+        let fix_build = Task::new(String::from("Fix build"), None, None, None, None, None);
+        let fix_bug = Task::new(String::from("Fix bug"), None, None, None, None, None);
+        let groceries = Task::new(String::from("Groceries"), None, None, None, None, None);
+        let tasks = vec![fix_build.clone(), fix_bug.clone(), groceries.clone()];
+
+        // Fuzzy matches on name
+        let matches = find_matching_tasks(tasks.clone(), "fix");
+        assert_eq!(matches.len(), 2);
+        let matches = find_matching_tasks(tasks.clone(), "fbld");
+        assert_eq!(matches, vec![fix_build.clone()]);
+
+        // Exact names win outright over fuzzy matches
+        let matches = find_matching_tasks(tasks.clone(), "fix BUG");
+        assert_eq!(matches, vec![fix_bug]);
+
+        // As do ids
+        let id_prefix = groceries.get_id().to_string()[..8].to_string();
+        let matches = find_matching_tasks(tasks.clone(), &id_prefix);
+        assert_eq!(matches, vec![groceries]);
+
+        let matches = find_matching_tasks(tasks, "nothing like it");
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let mut fence = Task::new(
+            String::from("Paint fence"),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            Some(HashSet::from([String::from("outside")])),
+        );
+        fence.project = Some(String::from("House"));
+        let dishes = Task::new(
+            String::from("Dishes"),
+            None,
+            None,
+            None,
+            Some(Status::Completed),
+            None,
+        );
+        let conn = get_db(true, false).unwrap();
+        add_to_db(&conn, &dishes).unwrap();
+        add_to_db(&conn, &fence).unwrap();
+
+        assert_eq!(
+            filtered_tasks(&conn, &ListFilters::default()).unwrap(),
+            vec![fence.clone()]
+        );
+
+        let filters = ListFilters {
+            display: Display::All,
+            tags: String::new(),
+            tag_match: TagMatch::Any,
+            project: Some(String::from("house")),
+            query: None,
+        };
+        assert_eq!(
+            filtered_tasks(&conn, &filters).unwrap(),
+            vec![fence.clone()]
+        );
+
+        assert_eq!(
+            format_task_list(&[fence]),
+            vec![String::from(
+                "  1. High     Paint fence [Open] @House (outside)"
+            )]
+        );
+        assert_eq!(
+            format_task_list(&[]),
+            vec![String::from("No tasks to show")]
+        );
+    }
+
+    #[test]
+    fn test_command_statuses() {
+        let conn = get_db(true, false).unwrap();
+        let mut fence = Task::new(String::from("Paint fence"), None, None, None, None, None);
+        fence.project = Some(String::from("House"));
+        add_to_db(&conn, &fence).unwrap();
+
+        let filters = ListFilters {
+            tags: String::from("nothing"),
+            ..ListFilters::default()
+        };
+        assert_eq!(
+            list_tasks(&conn, &filters, true).unwrap(),
+            CommandStatus::Empty
+        );
+        assert_eq!(
+            list_tasks(&conn, &filters, false).unwrap(),
+            CommandStatus::Success
+        );
+
+        assert_eq!(
+            complete_task(&conn, "groceries", None, CompleteParents::Never).unwrap(),
+            CommandStatus::NoMatch
+        );
+        assert_eq!(
+            complete_task(&conn, "fence", Some("Garden"), CompleteParents::Never).unwrap(),
+            CommandStatus::NoMatch
+        );
+        assert_eq!(
+            complete_task(&conn, "fence", Some("House"), CompleteParents::Never).unwrap(),
+            CommandStatus::Success
+        );
+        let task_list = get_all_db_contents(&conn).unwrap();
+        assert_eq!(task_list.tasks[0].status, Status::Completed);
+
+        // Already completed tasks can't be completed again
+        assert_eq!(
+            complete_task(&conn, "fence", None, CompleteParents::Never).unwrap(),
+            CommandStatus::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_new_task() {
+        let now = Local::now();
+        let new_task = NewTask {
+            name: String::from("Migrated task"),
+            tags: vec![String::from("old")],
+            date_added: Some(String::from("2020-01-15")),
+            ..NewTask::default()
+        };
+        let task = new_task.into_task(now).unwrap();
+        assert_eq!(task.name, "Migrated task");
+        assert_eq!(task.tags, Some(HashSet::from([String::from("old")])));
+        assert_eq!(
+            calendar().date_of(&task.date_added).to_string(),
+            "2020-01-15"
+        );
+
+        let from_the_future = NewTask {
+            name: String::from("Time traveller"),
+            date_added: Some((now.date_naive() + chrono::Days::new(2)).to_string()),
+            ..NewTask::default()
+        };
+        assert!(from_the_future.into_task(now).is_err());
+
+        let orphan_url = NewTask {
+            name: String::from("Fix login"),
+            external_url: Some(String::from("https://example.com/issues/7")),
+            ..NewTask::default()
+        };
+        assert!(orphan_url.into_task(now).is_err());
+    }
+
+    #[test]
+    fn test_add_task_once() {
+        let conn = get_db(true, false).unwrap();
+        let imported = || NewTask {
+            name: String::from("Fix login"),
+            external_ref: Some(String::from("github:7")),
+            external_url: Some(String::from("https://example.com/issues/7")),
+            ..NewTask::default()
+        };
+        assert_eq!(add_task(&conn, imported()).unwrap(), CommandStatus::Success);
+        assert_eq!(add_task(&conn, imported()).unwrap(), CommandStatus::Success);
+
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(
+            tasks[0].external_ref,
+            Some(ExternalRef {
+                system: String::from("github"),
+                id: String::from("7"),
+                url: Some(String::from("https://example.com/issues/7")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_clone_task() {
+        let conn = get_db(true, false).unwrap();
+        let task = Task::new(
+            String::from("Weekly report"),
+            None,
+            None,
+            None,
+            Some(Status::Completed),
+            None,
+        );
+        add_to_db(&conn, &task).unwrap();
+
+        assert_eq!(
+            clone_task(&conn, "nothing like it", true).unwrap(),
+            CommandStatus::NoMatch
+        );
+        assert_eq!(
+            clone_task(&conn, "Weekly report", true).unwrap(),
+            CommandStatus::Success
+        );
+
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        assert_eq!(tasks.len(), 2);
+        let copy = tasks
+            .iter()
+            .find(|copy| copy.get_id() != task.get_id())
+            .unwrap();
+        assert_eq!(copy.name, "Weekly report");
+        assert_eq!(copy.status, Status::Open);
+    }
+
+    #[test]
+    fn test_locked_task() {
+        let conn = get_db(true, false).unwrap();
+        let task = Task::new(String::from("Template"), None, None, None, None, None);
+        add_to_db(&conn, &task).unwrap();
+        assert_eq!(
+            set_task_lock(&conn, "Template", true).unwrap(),
+            CommandStatus::Success
+        );
+
+        let changes = || TaskChanges {
+            name: Some(String::from("Renamed")),
+            ..TaskChanges::default()
+        };
+        assert_eq!(
+            update_task(&conn, "Template", changes(), CompleteParents::Never).unwrap(),
+            CommandStatus::Blocked
+        );
+        assert_eq!(
+            complete_task(&conn, "Template", None, CompleteParents::Never).unwrap(),
+            CommandStatus::Blocked
+        );
+        let stored = &get_all_db_contents(&conn).unwrap().tasks[0];
+        assert_eq!(stored.name, "Template");
+        assert_eq!(stored.status, Status::Open);
+
+        set_task_lock(&conn, "Template", false).unwrap();
+        assert_eq!(
+            update_task(&conn, "Template", changes(), CompleteParents::Never).unwrap(),
+            CommandStatus::Success
+        );
+    }
+
+    #[test]
+    fn test_acceptance_criteria() {
+        let conn = get_db(true, false).unwrap();
+        let task = Task::new(String::from("Release"), None, None, None, None, None);
+        add_to_db(&conn, &task).unwrap();
+
+        let changes = TaskChanges {
+            criteria: vec![
+                String::from("Tests pass"),
+                String::from("Changelog written"),
+            ],
+            tick: vec![1],
+            ..TaskChanges::default()
+        };
+        assert_eq!(
+            update_task(&conn, "Release", changes, CompleteParents::Never).unwrap(),
+            CommandStatus::Success
+        );
+        let stored = &get_all_db_contents(&conn).unwrap().tasks[0];
+        assert_eq!(stored.criteria.len(), 2);
+        assert_eq!(stored.criteria_left(), 1);
+
+        // It can't be completed with a criterion left, either way round
+        assert_eq!(
+            complete_task(&conn, "Release", None, CompleteParents::Never).unwrap(),
+            CommandStatus::Blocked
+        );
+        let complete = || TaskChanges {
+            status: Some(Status::Completed),
+            ..TaskChanges::default()
+        };
+        assert_eq!(
+            update_task(&conn, "Release", complete(), CompleteParents::Never).unwrap(),
+            CommandStatus::Blocked
+        );
+        assert_eq!(
+            get_all_db_contents(&conn).unwrap().tasks[0].status,
+            Status::Open
+        );
+
+        let tick = |number| TaskChanges {
+            tick: vec![number],
+            ..TaskChanges::default()
+        };
+        assert!(update_task(&conn, "Release", tick(3), CompleteParents::Never).is_err());
+        update_task(&conn, "Release", tick(2), CompleteParents::Never).unwrap();
+        assert_eq!(
+            update_task(&conn, "Release", complete(), CompleteParents::Never).unwrap(),
+            CommandStatus::Success
+        );
+    }
+
+    #[test]
+    fn test_plan_tasks() {
+        let conn = get_db(true, false).unwrap();
+        let mut big = Task::new(String::from("Big"), None, None, None, None, None);
+        big.duration = Some(240);
+        add_to_db(&conn, &big).unwrap();
+        let mut small = Task::new(String::from("Small"), None, None, None, None, None);
+        small.duration = Some(180);
+        add_to_db(&conn, &small).unwrap();
+
+        assert_eq!(
+            plan_tasks(&conn, 7, 360, true).unwrap(),
+            CommandStatus::Success
+        );
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        let scheduled = |name: &str| {
+            tasks
+                .iter()
+                .find(|task| task.name == name)
+                .unwrap()
+                .scheduled
+        };
+        assert_eq!(scheduled("Big"), Some(today()));
+        assert_eq!(scheduled("Small"), today().succ_opt());
+    }
+
+    #[test]
+    fn test_promote_session() {
+        let scratch = get_db(true, false).unwrap();
+        let real = get_db(true, false).unwrap();
+        assert_eq!(
+            promote_session(&scratch, &real).unwrap(),
+            CommandStatus::Empty
+        );
+
+        let task = Task::new(String::from("Demo"), None, None, None, None, None);
+        add_to_db(&scratch, &task).unwrap();
+        assert_eq!(
+            promote_session(&scratch, &real).unwrap(),
+            CommandStatus::Success
+        );
+        assert_eq!(get_all_db_contents(&real).unwrap().tasks[0].name, "Demo");
+        assert_eq!(
+            promoted_message(0, 1),
+            "Promoted 0 task(s) to the real database, 1 already there"
+        );
+    }
+
+    #[test]
+    fn test_tag_commands() {
+        let conn = get_db(true, false).unwrap();
+        let tags = |tags: &[&str]| Some(tags.iter().map(|tag| tag.to_string()).collect());
+        add_to_db(
+            &conn,
+            &Task::new(
+                String::from("Ship"),
+                None,
+                None,
+                None,
+                None,
+                tags(&["wrk", "js"]),
+            ),
+        )
+        .unwrap();
+        add_to_db(
+            &conn,
+            &Task::new(
+                String::from("Test"),
+                None,
+                None,
+                None,
+                None,
+                tags(&["work"]),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            rename_tag(&conn, "nope", "other").unwrap(),
+            CommandStatus::NoMatch
+        );
+        assert_eq!(
+            rename_tag(&conn, "wrk", "work").unwrap(),
+            CommandStatus::Blocked
+        );
+        assert_eq!(
+            merge_tags(&conn, &[String::from("wrk")], "work").unwrap(),
+            CommandStatus::Success
+        );
+        assert_eq!(
+            rename_tag(&conn, "js", "javascript").unwrap(),
+            CommandStatus::Success
+        );
+        assert_eq!(remove_tag(&conn, "work").unwrap(), CommandStatus::Success);
+
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        let ship = tasks.iter().find(|task| task.name == "Ship").unwrap();
+        assert_eq!(ship.tags, tags(&["javascript"]));
+        let test = tasks.iter().find(|task| task.name == "Test").unwrap();
+        assert_eq!(test.tags, None);
+    }
+}
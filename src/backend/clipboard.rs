@@ -0,0 +1,29 @@
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+
+/// The system clipboard, opened the first time it's used and kept open
+/// from then on, since on Linux what was copied only stays on the clipboard
+/// while it's open (or until a clipboard manager takes it over)
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Runs the `work` on the system clipboard, opening it if it isn't yet
+fn with_clipboard<T>(work: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>) -> Result<T> {
+    let mut clipboard = CLIPBOARD.lock().unwrap_or_else(PoisonError::into_inner);
+    let clipboard = match clipboard.as_mut() {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(Clipboard::new().context("Failed to open the clipboard")?),
+    };
+    Ok(work(clipboard)?)
+}
+
+/// Puts the text on the system clipboard
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    with_clipboard(|clipboard| clipboard.set_text(text)).context("Failed to copy to the clipboard")
+}
+
+/// Returns the text on the system clipboard
+pub fn paste_from_clipboard() -> Result<String> {
+    with_clipboard(|clipboard| clipboard.get_text()).context("Failed to paste from the clipboard")
+}
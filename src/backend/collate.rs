@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::sync::RwLock;
+
+use icu_collator::{CaseFirst, Collator, CollatorOptions};
+use icu_locid::Locale;
+use serde::{Deserialize, Serialize};
+
+/// The collation used for the rest of the program, and the locale whose
+/// alphabet it follows, set on startup and again when switching to another
+/// profile's database
+static COLLATION: RwLock<(Collation, Option<Locale>)> = RwLock::new((Collation::Natural, None));
+
+thread_local! {
+    /// The collator for the locale in use, made the first time it's needed on
+    /// each thread and again whenever the locale changes, since making one
+    /// takes far longer than comparing with it
+    static COLLATOR: RefCell<Option<(Option<Locale>, Option<Collator>)>> = const { RefCell::new(None) };
+}
+
+/// How names, tags, and group headers are put in order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Collation {
+    /// Alphabetically, the way people read them, by the Unicode collation
+    /// algorithm: case is ignored, and letters with accents go with the letter
+    /// they're written on, i.e. "apple", "Éclair", "Zebra", unless the
+    /// configured locale's alphabet says otherwise
+    #[default]
+    Natural,
+    /// By the bytes of the text, so capitals come first and accents come last,
+    /// i.e. "Zebra", "apple", "Éclair"
+    Bytes,
+}
+
+/// Sets the collation used for sorting text from now on, and the locale
+/// whose alphabet `Collation::Natural` follows
+pub fn set_collation(collation: Collation, locale: Option<Locale>) {
+    if let Ok(mut current) = COLLATION.write() {
+        *current = (collation, locale);
+    }
+}
+
+/// Returns the collation used for sorting text
+pub fn collation() -> Collation {
+    COLLATION
+        .read()
+        .map(|current| current.0)
+        .unwrap_or_default()
+}
+
+/// Makes a collator for the locale, or for no language in particular
+/// without one, that puts capitals before lowercase when that's all that
+/// tells two pieces of text apart
+fn make_collator(locale: Option<&Locale>) -> Option<Collator> {
+    let mut options = CollatorOptions::new();
+    options.case_first = Some(CaseFirst::UpperFirst);
+    let locale = locale.map(Into::into).unwrap_or_default();
+    Collator::try_new(&locale, options).ok()
+}
+
+/// Compares two pieces of text by the Unicode collation algorithm, with the
+/// configured locale's collator, falling back to their bytes if there's
+/// no collator for it
+fn compare_natural(a: &str, b: &str) -> Ordering {
+    let locale = COLLATION
+        .read()
+        .map(|current| current.1.clone())
+        .unwrap_or_default();
+    COLLATOR.with_borrow_mut(|collator| {
+        if !collator
+            .as_ref()
+            .is_some_and(|(made_for, _)| *made_for == locale)
+        {
+            *collator = Some((locale.clone(), make_collator(locale.as_ref())));
+        }
+        match collator {
+            Some((_, Some(collator))) => collator.compare(a, b),
+            _ => a.cmp(b),
+        }
+    })
+}
+
+/// Compares two pieces of text by the collation in use. Under
+/// `Collation::Natural`, text that only differs by accents or case is
+/// still put in a set order: unaccented before accented, then by case,
+/// and text the collator can't tell apart at all by its bytes.
+pub fn collate(a: &str, b: &str) -> Ordering {
+    match collation() {
+        Collation::Natural => compare_natural(a, b).then_with(|| a.cmp(b)),
+        Collation::Bytes => a.cmp(b),
+    }
+}
+
+/// Sorts the text by the collation in use
+pub fn sort_text<T: AsRef<str>>(text: &mut [T]) {
+    text.sort_by(|a, b| collate(a.as_ref(), b.as_ref()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collate() {
+        let mut names = vec![
+            "Zebra", "éclair", "apple", "Eclair", "Øresund", "oak", "Straße",
+        ];
+        sort_text(&mut names);
+        assert_eq!(
+            names,
+            vec!["apple", "Eclair", "éclair", "oak", "Øresund", "Straße", "Zebra"]
+        );
+        assert_eq!(collate("Apple", "apple"), Ordering::Less);
+        assert_eq!(collate("apple", "apple"), Ordering::Equal);
+        // Letters written with a combining accent sort as the accented letter
+        assert_eq!(
+            compare_natural("A\u{30A}ngstro\u{308}m", "Ångström"),
+            Ordering::Equal
+        );
+
+        // Swedish has Å, Ä, and Ö as letters of their own, after Z
+        let mut words = vec!["Öl", "Ärlig", "Zebra", "Åsna", "Apa", "O\u{308}ga"];
+        sort_text(&mut words);
+        assert_eq!(
+            words,
+            vec!["Apa", "Ärlig", "Åsna", "O\u{308}ga", "Öl", "Zebra"]
+        );
+        let swedish = make_collator(Some(&"sv-SE".parse().unwrap())).unwrap();
+        words.sort_by(|a, b| swedish.compare(a, b));
+        assert_eq!(
+            words,
+            vec!["Apa", "Zebra", "Åsna", "Ärlig", "O\u{308}ga", "Öl"]
+        );
+    }
+}
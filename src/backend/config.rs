@@ -1,19 +1,176 @@
-use std::fs::{rename, File};
+use std::ffi::OsString;
+use std::fs::{read_dir, rename, File};
 use std::io::{prelude::*, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{TimeDelta, Weekday};
 use directories::BaseDirs;
+use icu_locid::Locale;
 use serde::{Deserialize, Serialize};
 
-use crate::backend::task::Display;
+use crate::backend::archive::parse_age;
+use crate::backend::calendar::{Calendar, Zone};
+use crate::backend::collate::Collation;
+use crate::backend::error::ChecklistError;
+use crate::backend::notify::QuietHours;
+use crate::backend::plan::{parse_capacity, DEFAULT_CAPACITY};
+use crate::backend::store::Storage;
+use crate::backend::task::{CompleteParents, Display, RollUp, SortBy, TagMatch};
+use crate::display::colors::ColorSupport;
+use crate::display::keymap::Keymap;
+use crate::display::theme_presets::ThemePreset;
+
+/// Environment variable a profile can be picked with, instead of `--profile`
+pub const PROFILE_VAR: &str = "CHECKLIST_PROFILE";
+
+/// Environment variable the directory checklist keeps its files in can be
+/// moved with, i.e. to keep a `--test` run away from the real ones
+pub const CONFIG_DIR_VAR: &str = "CHECKLIST_CONFIG_DIR";
+
+/// The profile in use, if one was picked
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+fn notifications_default() -> bool {
+    true
+}
+
+fn timezone_default() -> String {
+    String::from("local")
+}
+
+fn week_start_default() -> Weekday {
+    Weekday::Mon
+}
+
+fn backup_count_default() -> usize {
+    10
+}
+
+fn daily_capacity_default() -> String {
+    String::from(DEFAULT_CAPACITY)
+}
+
+fn still_working_after_default() -> Option<String> {
+    Some(String::from("4h"))
+}
+
+fn roll_up_default() -> Vec<RollUp> {
+    vec![RollUp::Urgency, RollUp::Due, RollUp::Estimate]
+}
 
 /// Struct to hold information for the program between sessions
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub db_path: PathBuf,
     pub display_filter: Display,
+    /// Whether the sort is descending, whatever it is on
     pub urgency_sort_desc: bool,
+    /// What the TUI sorts tasks on
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Whether to send desktop notifications for due tasks when the TUI opens
+    #[serde(default = "notifications_default")]
+    pub notifications: bool,
+    /// Timezone days are counted in: "local", "UTC", or an offset like "+05:30"
+    #[serde(default = "timezone_default")]
+    pub timezone: String,
+    /// Day that weeks begin on
+    #[serde(default = "week_start_default")]
+    pub week_start: Weekday,
+    /// Whether tasks need any or all of the tags in a tag filter
+    #[serde(default)]
+    pub tag_match: TagMatch,
+    /// Command to hand a task off with instead of opening a `mailto:` link
+    #[serde(default)]
+    pub handoff_command: Option<String>,
+    /// URL of the ticket tracker, with `{ticket}` where the ticket (i.e. ABC-123) goes
+    #[serde(default)]
+    pub ticket_url: Option<String>,
+    /// Whether `qc` asks before completing `Critical` tasks or ones tagged `important`
+    #[serde(default)]
+    pub confirm_complete: bool,
+    /// How many backups of the database to keep from before wipes, imports,
+    /// and schema migrations. 0 turns them off
+    #[serde(default = "backup_count_default")]
+    pub backup_count: usize,
+    /// Window of the day notifications are held back in, i.e. "22:00-07:00"
+    #[serde(default)]
+    pub quiet_hours: Option<String>,
+    /// Command `checklist digest --send` hands the daily digest to, i.e. to email it
+    #[serde(default)]
+    pub digest_command: Option<String>,
+    /// Whether the database is encrypted with SQLCipher, needing a passphrase to open
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Command printing the database's passphrase, i.e. a keyring lookup
+    #[serde(default)]
+    pub passphrase_command: Option<String>,
+    /// Most `Critical` and `High` tasks to list at once, with the rest behind a toggle
+    #[serde(default)]
+    pub urgent_limit: Option<usize>,
+    /// What happens to a parent task once all of its subtasks are completed
+    #[serde(default)]
+    pub complete_parents: CompleteParents,
+    /// Which values parent tasks show rolled up from their subtasks
+    #[serde(default = "roll_up_default")]
+    pub roll_up: Vec<RollUp>,
+    /// URL of the `checklist serve` server `checklist sync` syncs with
+    #[serde(default)]
+    pub sync_url: Option<String>,
+    /// Token `checklist serve` asks for, and `checklist sync` sends
+    #[serde(default)]
+    pub sync_token: Option<String>,
+    /// URL of the CalDAV calendar `checklist sync caldav` syncs with, i.e. a Nextcloud Tasks list
+    #[serde(default)]
+    pub caldav_url: Option<String>,
+    /// User to log in to the CalDAV server as
+    #[serde(default)]
+    pub caldav_username: Option<String>,
+    /// Password (or app password) to log in to the CalDAV server with
+    #[serde(default)]
+    pub caldav_password: Option<String>,
+    /// Command printing the CalDAV password instead, i.e. a keyring lookup
+    #[serde(default)]
+    pub caldav_password_command: Option<String>,
+    /// How much time a day has for tasks when `checklist plan` fills it, i.e. "6h"
+    #[serde(default = "daily_capacity_default")]
+    pub daily_capacity: String,
+    /// How long a task can stay `Working` without an update before the TUI
+    /// asks after it, i.e. "4h". Null turns the prompt off
+    #[serde(default = "still_working_after_default")]
+    pub still_working_after: Option<String>,
+    /// Whether the tasks are kept in the SQLite database or a folder of JSON or TOML files
+    #[serde(default)]
+    pub storage: Storage,
+    /// Folder the task files are kept in, when `storage` is JSON or TOML
+    #[serde(default)]
+    pub tasks_dir: Option<PathBuf>,
+    /// URL of the PostgreSQL database the tasks are kept in, when `storage` is Postgres
+    #[serde(default)]
+    pub postgres_url: Option<String>,
+    /// How names, tags, and group headers are sorted
+    #[serde(default)]
+    pub collation: Collation,
+    /// Locale whose alphabet the `Natural` collation follows, i.e. "sv" to put
+    /// Å, Ä, and Ö after Z. Without one, no language's in particular.
+    #[serde(default)]
+    pub collation_locale: Option<String>,
+    /// Whether to print what was added, completed, updated, and tracked
+    /// once the TUI is quit
+    #[serde(default)]
+    pub exit_summary: bool,
+    /// Keys layered over the usual ones, for those used to vim or emacs
+    #[serde(default)]
+    pub keymap: Keymap,
+    /// A bundled theme to use in place of the theme.toml file, i.e. "gruvbox"
+    #[serde(default)]
+    pub theme: Option<ThemePreset>,
+    /// How many colors the terminal can show, worked out from the
+    /// environment when it isn't set
+    #[serde(default)]
+    pub colors: Option<ColorSupport>,
 }
 
 impl Config {
@@ -26,57 +183,149 @@ impl Config {
             db_path,
             display_filter,
             urgency_sort_desc,
+            sort_by: SortBy::default(),
+            notifications: notifications_default(),
+            timezone: timezone_default(),
+            week_start: week_start_default(),
+            tag_match: TagMatch::default(),
+            handoff_command: None,
+            ticket_url: None,
+            confirm_complete: false,
+            backup_count: backup_count_default(),
+            quiet_hours: None,
+            digest_command: None,
+            encrypted: false,
+            passphrase_command: None,
+            urgent_limit: None,
+            complete_parents: CompleteParents::default(),
+            roll_up: roll_up_default(),
+            sync_url: None,
+            sync_token: None,
+            caldav_url: None,
+            caldav_username: None,
+            caldav_password: None,
+            caldav_password_command: None,
+            daily_capacity: daily_capacity_default(),
+            still_working_after: still_working_after_default(),
+            storage: Storage::default(),
+            tasks_dir: None,
+            postgres_url: None,
+            collation: Collation::default(),
+            collation_locale: None,
+            exit_summary: false,
+            keymap: Keymap::default(),
+            theme: None,
+            colors: None,
+        }
+    }
+
+    /// Returns the `Calendar` set up by the `timezone` and `week_start`
+    pub fn calendar(&self) -> Result<Calendar> {
+        let zone: Zone = self.timezone.parse()?;
+        Ok(Calendar {
+            zone,
+            week_start: self.week_start,
+        })
+    }
+
+    /// Returns the locale set up by `collation_locale`, if there is one
+    pub fn collation_locale(&self) -> Result<Option<Locale>> {
+        self.collation_locale
+            .as_deref()
+            .map(|locale| {
+                locale.parse().map_err(|e| {
+                    anyhow!(
+                        "'{}' is not a locale like \"sv\" or \"de-AT\": {}",
+                        locale,
+                        e
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns the `QuietHours` set up by `quiet_hours`, if there are any
+    pub fn quiet_hours(&self) -> Result<Option<QuietHours>> {
+        self.quiet_hours
+            .as_deref()
+            .map(|quiet_hours| quiet_hours.parse())
+            .transpose()
+    }
+
+    /// Returns how many minutes a day has for tasks, going by `daily_capacity`
+    pub fn daily_capacity(&self) -> Result<i64> {
+        parse_capacity(&self.daily_capacity)
+    }
+
+    /// Returns how long a task can stay `Working` without an update before
+    /// it's asked after, going by `still_working_after`
+    pub fn still_working_after(&self) -> Result<Option<TimeDelta>> {
+        self.still_working_after
+            .as_deref()
+            .map(parse_age)
+            .transpose()
+    }
+
+    /// Returns the folder the task files are kept in, which unless `tasks_dir`
+    /// says otherwise sits next to the database, i.e. checklist-tasks
+    pub fn tasks_dir(&self) -> PathBuf {
+        if let Some(tasks_dir) = &self.tasks_dir {
+            return tasks_dir.clone();
         }
+        let name = self
+            .db_path
+            .file_stem()
+            .map_or(String::from("checklist"), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+        self.db_path.with_file_name(format!("{}-tasks", name))
     }
 
     /// Saves the `Config` to a config.json file.
     /// Save location is based on `directories::BaseDirs`.
-    /// `testing` bool will save a test.config.json file instead.
+    /// `testing` bool will save a test.config.json file instead,
+    /// and a profile saves to its own, i.e. config.work.json.
     pub fn save(&self, testing: bool) -> Result<()> {
-        match get_config_dir() {
-            Ok(conf_local_dir) => {
-                // We want to update our config
-                // We can do this by creating a .tmp file and renaming it
-                // This minimizes the chance of data being lost if an error
-                // happens mid-write
-                let mut config_file = String::from("config.json");
-                if testing {
-                    config_file = format!("test.{}", config_file);
-                }
-                let tmp_file = format!("{}.tmp", config_file);
-
-                let config_file_path = conf_local_dir.join(&config_file);
-                let tmp_file_path = conf_local_dir.join(&tmp_file);
-
-                let config_string =
-                    serde_json::to_string(self).context("Failed to deserialize Config")?;
-
-                // Create a .tmp file
-                let mut file =
-                    File::create(&tmp_file_path).context("Failed to make a .tmp file")?;
-                file.write_all(config_string.as_bytes())
-                    .context("Failed to write to config file")?;
-
-                // Rename .tmp file to old file
-                rename(&tmp_file_path, &config_file_path)
-                    .with_context(|| { format!("Failed to update config file with rename:\ntmp_file: {:?}\nconfig_file:{:?}", tmp_file, config_file)})?;
-            }
-            Err(e) => {
-                println!("Failed getting the configuration location: {:?}", e);
-                panic!()
-            }
-        }
+        let conf_local_dir = get_config_dir()?;
+        // We want to update our config
+        // We can do this by creating a .tmp file and renaming it
+        // This minimizes the chance of data being lost if an error
+        // happens mid-write
+        let config_file = profile_file_name("config.json", testing);
+        let tmp_file = format!("{}.tmp", config_file);
+
+        let config_file_path = conf_local_dir.join(&config_file);
+        let tmp_file_path = conf_local_dir.join(&tmp_file);
+
+        let config_string = serde_json::to_string(self).context("Failed to deserialize Config")?;
+
+        // Create a .tmp file
+        let mut file = File::create(&tmp_file_path).context("Failed to make a .tmp file")?;
+        file.write_all(config_string.as_bytes())
+            .context("Failed to write to config file")?;
+
+        // Rename .tmp file to old file
+        rename(&tmp_file_path, &config_file_path).with_context(|| {
+            format!(
+                "Failed to update config file with rename:\ntmp_file: {:?}\nconfig_file:{:?}",
+                tmp_file, config_file
+            )
+        })?;
         Ok(())
     }
 }
 
 /// Gets the directory where all checklist files are saved.
-/// This is based on `directories::BaseDirs`
+/// This is based on `directories::BaseDirs`, unless moved with `CONFIG_DIR_VAR`
 pub fn get_config_dir() -> Result<PathBuf> {
-    let base_directories =
-        BaseDirs::new().expect("Could not find the user's local config directory.");
-
-    let conf_local_dir = base_directories.config_local_dir().join("checklist");
+    let conf_local_dir = match config_dir_override() {
+        Some(dir) => dir,
+        None => {
+            let base_directories =
+                BaseDirs::new().ok_or(ChecklistError::NoConfigDir(CONFIG_DIR_VAR))?;
+            base_directories.config_local_dir().join("checklist")
+        }
+    };
     // Create our checklist folder in local directory if it doesn't exist
     if !conf_local_dir.exists() {
         // Create a brand new config file
@@ -88,30 +337,180 @@ pub fn get_config_dir() -> Result<PathBuf> {
     Ok(conf_local_dir)
 }
 
+/// Returns the directory `CONFIG_DIR_VAR` is set to, if it's set to one
+fn dir_from_env(value: Option<OsString>) -> Option<PathBuf> {
+    value.filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+/// Returns the directory set with `CONFIG_DIR_VAR`, if any
+#[cfg(not(test))]
+fn config_dir_override() -> Option<PathBuf> {
+    dir_from_env(std::env::var_os(CONFIG_DIR_VAR))
+}
+
+/// Each test gets a directory of its own in place of the real one, so tests
+/// can't clobber the user's files or race each other over the same ones
+#[cfg(test)]
+fn config_dir_override() -> Option<PathBuf> {
+    dir_from_env(Some(
+        TEST_CONFIG_DIR.with(|dir| dir.0.clone().into_os_string()),
+    ))
+}
+
+/// A directory under the system's temp directory, removed once dropped
+#[cfg(test)]
+struct TestConfigDir(PathBuf);
+
+#[cfg(test)]
+impl Drop for TestConfigDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Config directory of the test running on this thread, since each test gets a thread
+    static TEST_CONFIG_DIR: TestConfigDir = {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        TestConfigDir(std::env::temp_dir().join(format!(
+            "checklist-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        )))
+    };
+}
+
+/// Keeps using the config file and database of the profile given, i.e.
+/// "work", from now on, or the default ones if `None`. Profile names go in
+/// file names, so they can only have letters, numbers, '-' and '_'.
+pub fn set_profile(profile: Option<&str>) -> Result<()> {
+    if let Some(profile) = profile {
+        check_profile_name(profile)?;
+    }
+    if let Ok(mut current) = PROFILE.write() {
+        *current = profile.map(String::from);
+    }
+    Ok(())
+}
+
+/// Returns the profile in use, if one was picked
+pub fn profile() -> Option<String> {
+    PROFILE.read().ok()?.clone()
+}
+
+fn check_profile_name(profile: &str) -> Result<()> {
+    if profile.is_empty()
+        || !profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Profile names can only have letters, numbers, '-' and '_', not {:?}",
+            profile
+        );
+    }
+    Ok(())
+}
+
+/// Returns the name of the file `base` (i.e. "config.json") for the
+/// `profile`, i.e. "config.work.json", with "test." in front if `testing`
+fn file_name_for(base: &str, profile: Option<&str>, testing: bool) -> String {
+    let name = match (profile, base.rsplit_once('.')) {
+        (Some(profile), Some((stem, extension))) => {
+            format!("{}.{}.{}", stem, profile, extension)
+        }
+        (Some(profile), None) => format!("{}.{}", base, profile),
+        (None, _) => base.to_string(),
+    };
+    if testing {
+        format!("test.{}", name)
+    } else {
+        name
+    }
+}
+
+/// Returns the name of the file `base` (i.e. "config.json") for the
+/// profile in use, with "test." in front if `testing`
+pub fn profile_file_name(base: &str, testing: bool) -> String {
+    file_name_for(base, profile().as_deref(), testing)
+}
+
+/// A profile that has been set up, with its config read in
+pub struct Profile {
+    /// `None` for the default profile
+    pub name: Option<String>,
+    pub config_file: PathBuf,
+    pub config: Result<Config>,
+}
+
+impl Profile {
+    /// How the profile is shown, i.e. "work" or "default"
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+}
+
+/// Returns the default profile and the others with a config file, sorted,
+/// reading in their configs. The default is left out if it isn't set up.
+pub fn known_profiles() -> Result<Vec<Profile>> {
+    let dir = get_config_dir()?;
+    let mut names = vec![None];
+    names.extend(list_profiles(&dir)?.into_iter().map(Some));
+    Ok(names
+        .into_iter()
+        .filter_map(|name| {
+            let config_file = dir.join(file_name_for("config.json", name.as_deref(), false));
+            if name.is_none() && !config_file.exists() {
+                return None;
+            }
+            let config = read_config_file(&config_file);
+            Some(Profile {
+                name,
+                config_file,
+                config,
+            })
+        })
+        .collect())
+}
+
+/// Returns the profiles that have a config file in the `dir`, sorted
+pub fn list_profiles(dir: &Path) -> Result<Vec<String>> {
+    let mut profiles: Vec<String> = read_dir(dir)
+        .with_context(|| format!("Failed to read {:?}", dir))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|file| {
+            let profile = file.strip_prefix("config.")?.strip_suffix(".json")?;
+            check_profile_name(profile).ok()?;
+            Some(profile.to_string())
+        })
+        .collect();
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// Returns where the config.json file should be, or the
+/// test.config.json file if `testing` is true, for the profile in use
+pub fn get_config_file(testing: bool) -> Result<PathBuf> {
+    Ok(get_config_dir()?.join(profile_file_name("config.json", testing)))
+}
+
 /// Looks for where the config.json file should be,
 /// and reads it in returning a `Result<Config>`
 pub fn read_config(testing: bool) -> Result<Config> {
-    match get_config_dir() {
-        Ok(local_config_dir) => {
-            let mut config_f = String::from("config.json");
-            if testing {
-                config_f = format!("test.{}", config_f);
-            }
-            let config_file_path = local_config_dir.join(&config_f);
+    read_config_file(&get_config_file(testing)?)
+}
 
-            let config_file = std::fs::File::open(&config_file_path)
-                .with_context(|| format!("Failed to open {:?}", config_file_path))?;
-            let reader = BufReader::new(config_file);
+/// Reads in the config file at the `Path`, returning a `Result<Config>`
+pub fn read_config_file(path: &Path) -> Result<Config> {
+    let config_file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let reader = BufReader::new(config_file);
 
-            let config: Config = serde_json::from_reader(reader)?;
+    let config: Config = serde_json::from_reader(reader)?;
 
-            Ok(config)
-        }
-        Err(e) => {
-            println!("Failed getting the configuration location: {:?}", e);
-            panic!()
-        }
-    }
+    Ok(config)
 }
 
 /// Will set the SQLite database path in the configuration file to use
@@ -119,7 +518,7 @@ pub fn read_config(testing: bool) -> Result<Config> {
 /// configuration file instead.
 pub fn set_new_path(path: PathBuf, testing: bool) -> Result<()> {
     if !path.exists() {
-        panic!("A valid path that exists needs to be supplied")
+        return Err(ChecklistError::MissingDatabase(path).into());
     }
     let absolute_path = std::fs::canonicalize(&path).with_context(|| {
         format!(
@@ -147,15 +546,22 @@ pub fn set_new_path(path: PathBuf, testing: bool) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dir_from_env() {
+        assert_eq!(dir_from_env(None), None);
+        assert_eq!(dir_from_env(Some(OsString::new())), None);
+        assert_eq!(
+            dir_from_env(Some(OsString::from("/tmp/checklist"))),
+            Some(PathBuf::from("/tmp/checklist"))
+        );
+    }
+
     fn save_and_read_config(db_path: PathBuf) {
         let config = Config::new(db_path.clone());
         match config.save(true) {
             Ok(()) => {
-                let base_directories = BaseDirs::new().expect("Should find");
-                let config_file = base_directories
-                    .config_local_dir()
-                    .join("checklist/test.config.json");
-                assert_eq!(config_file.exists(), true);
+                let config_file = get_config_dir().unwrap().join("test.config.json");
+                assert!(config_file.exists());
             }
             Err(_) => {
                 println!("Encounted an error saving the test config file");
@@ -174,6 +580,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_profile_files() {
+        assert_eq!(file_name_for("config.json", None, false), "config.json");
+        assert_eq!(file_name_for("config.json", None, true), "test.config.json");
+        assert_eq!(
+            file_name_for("config.json", Some("work"), false),
+            "config.work.json"
+        );
+        assert_eq!(
+            file_name_for("checklist.sqlite", Some("work"), true),
+            "test.checklist.work.sqlite"
+        );
+        assert!(check_profile_name("side-project_2").is_ok());
+        assert!(check_profile_name("../work").is_err());
+        assert!(check_profile_name("").is_err());
+
+        let dir = std::env::temp_dir().join(format!("checklist-profiles-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "config.json",
+            "test.config.json",
+            "config.work.json",
+            "config.home.json",
+            "test.config.side.json",
+            "config.json.tmp",
+        ] {
+            File::create(dir.join(file)).unwrap();
+        }
+        assert_eq!(
+            list_profiles(&dir).unwrap(),
+            vec![String::from("home"), String::from("work")]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_multiple_saves() {
         let db_path = PathBuf::from("db_path.db");
@@ -183,6 +624,24 @@ mod tests {
         save_and_read_config(second_db_path);
     }
 
+    #[test]
+    fn test_config_dir_is_kept_apart() {
+        let dir = get_config_dir().unwrap();
+        assert!(dir.starts_with(std::env::temp_dir()));
+        assert!(dir.exists());
+        let real = BaseDirs::new()
+            .unwrap()
+            .config_local_dir()
+            .join("checklist");
+        assert_ne!(dir, real);
+
+        // Another test gets another directory
+        let other = std::thread::spawn(|| get_config_dir().unwrap())
+            .join()
+            .unwrap();
+        assert_ne!(dir, other);
+    }
+
     #[test]
     fn test_updating_the_config() -> Result<()> {
         let mut config = Config::new(PathBuf::from("first_db_path.db"));
@@ -1,20 +1,267 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use clap::ValueEnum;
+use rusqlite::backup::Progress;
+use rusqlite::types::Value;
+use rusqlite::{
+    params, params_from_iter, Connection, DatabaseName, ErrorCode, OpenFlags, OptionalExtension,
+    Row,
+};
+use tracing::{info, warn};
 
-use crate::backend::config::{get_config_dir, read_config, Config};
-use crate::backend::task::{Task, TaskList};
+use crate::backend::backup::backup_before;
+use crate::backend::calendar::{calendar, today};
+use crate::backend::collate::collate;
+use crate::backend::config::{get_config_dir, profile_file_name, read_config, Config};
+use crate::backend::encryption::{
+    get_passphrase, import_encrypted, passphrase, remember_passphrase, unlock,
+};
+use crate::backend::events::TaskEvent;
+use crate::backend::notify::Notification;
+use crate::backend::query::{DateField, Op, Query};
+use crate::backend::store::search_in;
+use crate::backend::task::{
+    criteria_to_text, parse_criteria, Display, RollUp, RolledUp, SortBy, Status, TagMatch, Task,
+    TaskList, Urgency, WorkSession,
+};
 
-/// Returns a `Result<Connection>` to an in-memory SQLite db
-pub fn make_memory_connection() -> Result<Connection> {
-    println!("Setting up an in-memory sqlite_db");
-    let conn =
-        Connection::open_in_memory().with_context(|| "Failed to create database in memory")?;
+/// File name of the database set up in the config directory
+pub const DEFAULT_DB_NAME: &str = "checklist.sqlite";
+
+/// How long a statement waits on another checklist (i.e. the TUI open in another
+/// terminal) that is writing to the database, before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many more times a change is tried when the database is still busy after
+/// `BUSY_TIMEOUT`, or can't wait on it at all
+const BUSY_RETRIES: u32 = 3;
+
+/// Schema changes made to the 'task' table since it was first created, in order.
+/// The `user_version` of a SQLite database tracks how many of these it has had applied.
+/// The 'archive' table mirrors the columns of the 'task' table, so columns added to
+/// one need adding to the other.
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE task ADD COLUMN recurrence TEXT",
+    "ALTER TABLE task ADD COLUMN project TEXT",
+    "ALTER TABLE task ADD COLUMN time_spent INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE task ADD COLUMN timer_started DATE",
+    "ALTER TABLE task ADD COLUMN due DATE",
+    "ALTER TABLE task ADD COLUMN scheduled DATE",
+    // Dates used to be stored with whatever offset the machine had, normalize them to UTC
+    "UPDATE task SET
+        date_added = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', date_added), date_added),
+        completed_on = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', completed_on), completed_on),
+        timer_started = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', timer_started), timer_started)",
+    "CREATE TABLE IF NOT EXISTS archive (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT,
+        latest TEXT,
+        urgency TEXT,
+        status TEXT NOT NULL,
+        tags TEXT,
+        date_added DATE NOT NULL,
+        completed_on DATE,
+        recurrence TEXT,
+        project TEXT,
+        time_spent INTEGER NOT NULL DEFAULT 0,
+        timer_started DATE,
+        due DATE,
+        scheduled DATE
+    )",
+    // Full-text index over the text of each task, kept in step with the 'task' table by triggers
+    "CREATE VIRTUAL TABLE IF NOT EXISTS task_search USING fts5(
+        id UNINDEXED,
+        name,
+        description,
+        latest
+    );
+    CREATE TRIGGER IF NOT EXISTS task_search_insert AFTER INSERT ON task BEGIN
+        INSERT INTO task_search (id, name, description, latest)
+        VALUES (new.id, new.name, new.description, new.latest);
+    END;
+    CREATE TRIGGER IF NOT EXISTS task_search_delete AFTER DELETE ON task BEGIN
+        DELETE FROM task_search WHERE id = old.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS task_search_update AFTER UPDATE ON task BEGIN
+        DELETE FROM task_search WHERE id = old.id;
+        INSERT INTO task_search (id, name, description, latest)
+        VALUES (new.id, new.name, new.description, new.latest);
+    END;
+    DELETE FROM task_search;
+    INSERT INTO task_search (id, name, description, latest)
+    SELECT id, name, description, latest FROM task",
+    "ALTER TABLE task ADD COLUMN external_ref TEXT;
+    ALTER TABLE archive ADD COLUMN external_ref TEXT",
+    "ALTER TABLE task ADD COLUMN scheduled_start DATE;
+    ALTER TABLE task ADD COLUMN duration INTEGER;
+    ALTER TABLE archive ADD COLUMN scheduled_start DATE;
+    ALTER TABLE archive ADD COLUMN duration INTEGER",
+    // Each time a timer stops, log how long it ran for, so time can be broken down by week
+    "CREATE TABLE IF NOT EXISTS work_sessions (
+        task_id TEXT NOT NULL,
+        started DATE NOT NULL,
+        ended DATE NOT NULL
+    );
+    CREATE TRIGGER IF NOT EXISTS work_session_log AFTER UPDATE OF timer_started ON task
+    WHEN old.timer_started IS NOT NULL AND new.timer_started IS NULL BEGIN
+        INSERT INTO work_sessions (task_id, started, ended)
+        VALUES (old.id, old.timer_started, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+    END",
+    "ALTER TABLE task ADD COLUMN locked INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE archive ADD COLUMN locked INTEGER NOT NULL DEFAULT 0",
+    // Log each field a change to a task touches, so how it evolved can be shown
+    "CREATE TABLE IF NOT EXISTS task_events (
+        task_id TEXT NOT NULL,
+        changed DATE NOT NULL,
+        field TEXT NOT NULL,
+        before TEXT,
+        after TEXT
+    );
+    CREATE TRIGGER IF NOT EXISTS task_event_insert AFTER INSERT ON task BEGIN
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        VALUES (new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'added', NULL, new.name);
+    END;
+    CREATE TRIGGER IF NOT EXISTS task_event_update AFTER UPDATE ON task BEGIN
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'name', old.name, new.name WHERE old.name IS NOT new.name;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'description', old.description, new.description WHERE old.description IS NOT new.description;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'latest', old.latest, new.latest WHERE old.latest IS NOT new.latest;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'urgency', old.urgency, new.urgency WHERE old.urgency IS NOT new.urgency;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'status', old.status, new.status WHERE old.status IS NOT new.status;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'tags', old.tags, new.tags WHERE old.tags IS NOT new.tags;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'project', old.project, new.project WHERE old.project IS NOT new.project;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'due', old.due, new.due WHERE old.due IS NOT new.due;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'scheduled', old.scheduled, new.scheduled WHERE old.scheduled IS NOT new.scheduled;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'recurrence', old.recurrence, new.recurrence WHERE old.recurrence IS NOT new.recurrence;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'scheduled_start', old.scheduled_start, new.scheduled_start WHERE old.scheduled_start IS NOT new.scheduled_start;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'duration', old.duration, new.duration WHERE old.duration IS NOT new.duration;
+        INSERT INTO task_events (task_id, changed, field, before, after)
+        SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'locked', old.locked, new.locked WHERE old.locked IS NOT new.locked;
+    END",
+    // When reminders about a task are snoozed until, so it lasts between sessions
+    "CREATE TABLE IF NOT EXISTS reminder_snoozes (
+        task_id TEXT PRIMARY KEY,
+        until DATE NOT NULL
+    )",
+    // Notifications held back during quiet hours, to send as a digest once they end
+    "CREATE TABLE IF NOT EXISTS held_notifications (
+        summary TEXT NOT NULL,
+        body TEXT NOT NULL
+    )",
+    // Which tasks have to wait on which, forgotten once either one is deleted or archived
+    "CREATE TABLE IF NOT EXISTS task_dependencies (
+        task_id TEXT NOT NULL,
+        blocker_id TEXT NOT NULL,
+        PRIMARY KEY (task_id, blocker_id)
+    );
+    CREATE TRIGGER IF NOT EXISTS task_dependencies_delete AFTER DELETE ON task BEGIN
+        DELETE FROM task_dependencies WHERE task_id = old.id OR blocker_id = old.id;
+    END",
+    // The task each one is a subtask of. Subtasks of a deleted or archived task move up a level
+    "ALTER TABLE task ADD COLUMN parent TEXT;
+    ALTER TABLE archive ADD COLUMN parent TEXT;
+    CREATE TRIGGER IF NOT EXISTS task_parent_delete AFTER DELETE ON task BEGIN
+        UPDATE task SET parent = old.parent WHERE parent = old.id;
+    END",
+    // When each task last changed, and which ones have gone (deleted or archived),
+    // so that copies of the database can be synced with each other
+    "ALTER TABLE task ADD COLUMN modified DATE;
+    ALTER TABLE archive ADD COLUMN modified DATE;
+    UPDATE task SET modified = date_added;
+    UPDATE archive SET modified = date_added;
+    CREATE TRIGGER IF NOT EXISTS task_modified_insert AFTER INSERT ON task
+    WHEN new.modified IS NULL BEGIN
+        UPDATE task SET modified = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS task_modified_update AFTER UPDATE ON task
+    WHEN new.modified IS old.modified BEGIN
+        UPDATE task SET modified = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = new.id;
+    END;
+    CREATE TABLE IF NOT EXISTS deleted_tasks (
+        id TEXT PRIMARY KEY,
+        deleted DATE NOT NULL
+    );
+    CREATE TRIGGER IF NOT EXISTS deleted_tasks_log AFTER DELETE ON task BEGIN
+        INSERT OR REPLACE INTO deleted_tasks (id, deleted)
+        VALUES (old.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+    END;
+    CREATE TRIGGER IF NOT EXISTS deleted_tasks_revive AFTER INSERT ON task BEGIN
+        DELETE FROM deleted_tasks WHERE id = new.id;
+    END;
+    DROP TRIGGER IF EXISTS task_search_update;
+    CREATE TRIGGER task_search_update AFTER UPDATE OF name, description, latest ON task BEGIN
+        DELETE FROM task_search WHERE id = old.id;
+        INSERT INTO task_search (id, name, description, latest)
+        VALUES (new.id, new.name, new.description, new.latest);
+    END",
+    // The to-dos seen on the CalDAV server when last synced with it, so ones
+    // deleted there since can be told apart from ones that were never there
+    "CREATE TABLE IF NOT EXISTS caldav_synced (
+        uid TEXT PRIMARY KEY,
+        id TEXT NOT NULL,
+        synced DATE NOT NULL
+    )",
+    // What has to be ticked off before each task can be completed, one to a line
+    "ALTER TABLE task ADD COLUMN criteria TEXT;
+    ALTER TABLE archive ADD COLUMN criteria TEXT",
+    // Indexes on what tasks are filtered and sorted on, so large databases stay quick to list
+    "CREATE INDEX IF NOT EXISTS task_status ON task (status);
+    CREATE INDEX IF NOT EXISTS task_urgency ON task (urgency);
+    CREATE INDEX IF NOT EXISTS task_due ON task (due);
+    CREATE INDEX IF NOT EXISTS task_scheduled ON task (scheduled);
+    CREATE INDEX IF NOT EXISTS task_date_added ON task (date_added)",
+    // The occurrence of a recurring task each one was lined up after, so completing
+    // it again doesn't line up another
+    "ALTER TABLE task ADD COLUMN previous_occurrence TEXT;
+    ALTER TABLE archive ADD COLUMN previous_occurrence TEXT;
+    CREATE INDEX IF NOT EXISTS task_previous_occurrence ON task (previous_occurrence);
+    CREATE INDEX IF NOT EXISTS archive_previous_occurrence ON archive (previous_occurrence)",
+    // Whether each task that has gone was archived rather than deleted, so a sync
+    // archives it in the other copies too. Archiving copies it over before it goes.
+    "ALTER TABLE deleted_tasks ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+    UPDATE deleted_tasks SET archived = 1 WHERE id IN (SELECT id FROM archive);
+    DROP TRIGGER IF EXISTS deleted_tasks_log;
+    CREATE TRIGGER deleted_tasks_log AFTER DELETE ON task BEGIN
+        INSERT OR REPLACE INTO deleted_tasks (id, deleted, archived)
+        VALUES (
+            old.id,
+            strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+            EXISTS (SELECT 1 FROM archive WHERE id = old.id)
+        );
+    END",
+];
+
+/// Formats a `DateTime<Local>` as a UTC RFC3339 string for storing in SQLite,
+/// so stored dates don't shift when the machine's timezone changes.
+/// They are converted back to local time when read.
+fn to_utc_rfc3339(date: &DateTime<Local>) -> String {
+    date.with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
 
+/// Creates the 'task' table as it was originally laid out, if it doesn't exist yet.
+/// Columns added since then come in through `migrate_db`.
+fn create_task_table(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE task (
+        "CREATE TABLE IF NOT EXISTS task (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
             description TEXT,
@@ -26,7 +273,109 @@ pub fn make_memory_connection() -> Result<Connection> {
             completed_on DATE
         )",
         (),
-    )?;
+    )
+    .context("Failed to create the task table")?;
+
+    Ok(())
+}
+
+/// Applies any `MIGRATIONS` the SQLite database on the `&Connection` given hasn't had yet.
+pub fn migrate_db(conn: &Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(migration)
+            .with_context(|| format!("Failed to apply migration: {}", migration))?;
+        conn.pragma_update(None, "user_version", (i + 1) as i64)
+            .context("Failed to update the database's schema version")?;
+    }
+
+    Ok(())
+}
+
+/// Returns true if the SQLite database on the `&Connection` given already has a 'task' table.
+fn has_task_table(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'task')",
+        (),
+        |row| row.get(0),
+    )
+    .context("Failed to look for the task table")
+}
+
+/// Returns how many `MIGRATIONS` the SQLite database on the `&Connection` given has had applied.
+pub fn schema_version(conn: &Connection) -> Result<usize> {
+    conn.query_row("PRAGMA user_version", (), |row| row.get(0))
+        .context("Failed to read the database's schema version")
+}
+
+/// Returns the schema version a SQLite database is at once every `MIGRATIONS` is applied.
+pub fn latest_schema_version() -> usize {
+    MIGRATIONS.len()
+}
+
+/// Returns the problems SQLite's `PRAGMA integrity_check` finds in the database,
+/// which is empty if there aren't any.
+pub fn integrity_problems(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare("PRAGMA integrity_check")
+        .context("Failed to check the database's integrity")?;
+    let problems = stmt
+        .query_map((), |row| row.get::<usize, String>(0))
+        .context("Failed to check the database's integrity")?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .context("Failed to read the database's integrity check")?;
+    Ok(problems
+        .into_iter()
+        .filter(|problem| problem != "ok")
+        .collect())
+}
+
+/// Returns a `Result<Connection>` to the SQLite database at the `&Path` given, failing
+/// if it isn't there instead of creating it, and without applying any `MIGRATIONS`.
+/// It isn't opened read-only, since checking the integrity of the search index
+/// needs to be able to write.
+pub fn open_existing(path: &Path) -> Result<Connection> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_WRITE
+        | OpenFlags::SQLITE_OPEN_NO_MUTEX
+        | OpenFlags::SQLITE_OPEN_URI;
+    let conn = Connection::open_with_flags(path, flags)
+        .with_context(|| format!("Failed to open the database at {:?}", path))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("Failed to set how long to wait on the database")?;
+    Ok(conn)
+}
+
+/// Returns a `Result<Connection>` to an in-memory SQLite db
+pub fn make_memory_connection() -> Result<Connection> {
+    println!("Setting up an in-memory sqlite_db");
+    let conn =
+        Connection::open_in_memory().with_context(|| "Failed to create database in memory")?;
+    register_collation(&conn)?;
+
+    create_task_table(&conn)?;
+    migrate_db(&conn)?;
+
+    Ok(conn)
+}
+
+/// Returns a `Result<Connection>` to an in-memory copy of the SQLite database backed up
+/// at the `&Path` given, with any `MIGRATIONS` it is missing applied. The backup itself
+/// is only ever read from.
+pub fn load_backup(path: &Path) -> Result<Connection> {
+    let mut conn =
+        Connection::open_in_memory().with_context(|| "Failed to create database in memory")?;
+    register_collation(&conn)?;
+    // Backups of an encrypted database are encrypted with the same passphrase
+    if let Some(passphrase) = passphrase() {
+        import_encrypted(&conn, path, &passphrase)?;
+    } else {
+        conn.restore(DatabaseName::Main, path, None::<fn(Progress)>)
+            .with_context(|| format!("Failed to read the backup at {:?}", path))?;
+    }
+
+    create_task_table(&conn)?;
+    migrate_db(&conn)?;
 
     Ok(conn)
 }
@@ -35,13 +384,75 @@ pub fn make_memory_connection() -> Result<Connection> {
 fn make_connection(path: &PathBuf) -> Result<Connection> {
     let conn = Connection::open(path)
         .with_context(|| format!("Failed connect to the database at {:?}", path))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("Failed to set how long to wait on the database")?;
+    register_collation(&conn)?;
 
     Ok(conn)
 }
 
+/// Lets SQL put names in order by the configured collation, with `COLLATE checklist`,
+/// so tasks sorted by name can be read in a page at a time
+fn register_collation(conn: &Connection) -> Result<()> {
+    conn.create_collation("checklist", collate)
+        .context("Failed to set up sorting by name")
+}
+
+/// Puts the database in WAL mode, so that reading it doesn't hold up another
+/// checklist writing to it, or the other way around. It sticks with the file, so
+/// it only has to be set once, but is checked each time the database is opened.
+fn use_wal(conn: &Connection) -> Result<()> {
+    // Some filesystems can't do WAL, and SQLite leaves the mode as it was for them,
+    // which only makes waiting on other writers more likely
+    let mode = conn
+        .pragma_update_and_check(None, "journal_mode", "WAL", |row| {
+            row.get::<usize, String>(0)
+        })
+        .context("Failed to put the database in WAL mode")?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        warn!(
+            "The database stayed in {} mode, as WAL isn't supported where it's kept",
+            mode
+        );
+    }
+    Ok(())
+}
+
+/// Returns true if the error came from the database being locked by another connection
+pub fn is_busy(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(failure, _))
+                if failure.code == ErrorCode::DatabaseBusy || failure.code == ErrorCode::DatabaseLocked
+        )
+    })
+}
+
+/// Runs the `change`, trying it again (up to `BUSY_RETRIES` times, waiting a
+/// little longer each time) for as long as it fails because the database is busy.
+/// The `change` has to be safe to run again, i.e. a whole transaction.
+pub fn retry_busy<T>(mut change: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match change() {
+            Err(e) if attempt < BUSY_RETRIES && is_busy(&e) => {
+                attempt += 1;
+                warn!(
+                    "The database is busy, trying again ({} of {}): {:#}",
+                    attempt, BUSY_RETRIES, e
+                );
+                sleep(Duration::from_millis(100 * attempt as u64));
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Creates a SQLite database. Will create a "test" SQLite database
-/// if testing bool brought in. This is a standalone SQLite database 
-/// but with "test." prefixed. 
+/// if testing bool brought in. This is a standalone SQLite database
+/// but with "test." prefixed. A profile gets its own, i.e.
+/// checklist.work.sqlite.
 ///
 /// Problematically this also creates and saves a `Config` based on
 /// the path used to create the SQLite database. Probably best to decouple
@@ -50,11 +461,7 @@ pub fn create_sqlite_db(testing: bool) -> Result<()> {
     let local_config_dir = get_config_dir()?;
     let mut sqlite_path = local_config_dir;
 
-    if testing {
-        sqlite_path = sqlite_path.join("test.checklist.sqlite");
-    } else {
-        sqlite_path = sqlite_path.join("checklist.sqlite");
-    }
+    sqlite_path = sqlite_path.join(profile_file_name(DEFAULT_DB_NAME, testing));
 
     println!("Setting up a database at {:?}", sqlite_path);
     let conn = make_connection(&sqlite_path)?;
@@ -62,20 +469,8 @@ pub fn create_sqlite_db(testing: bool) -> Result<()> {
     let config = Config::new(sqlite_path);
     config.save(testing)?;
 
-    conn.execute(
-        "CREATE TABLE task (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            description TEXT,
-            latest TEXT,
-            urgency TEXT,
-            status TEXT NOT NULL,
-            tags TEXT,
-            date_added DATE NOT NULL,
-            completed_on DATE
-        )",
-        (),
-    )?;
+    create_task_table(&conn)?;
+    migrate_db(&conn)?;
 
     Ok(())
 }
@@ -84,35 +479,63 @@ pub fn create_sqlite_db(testing: bool) -> Result<()> {
 pub fn get_db(memory: bool, testing: bool) -> Result<Connection> {
     if memory {
         println!("Using an in-memory sqlite database");
-        let conn = make_memory_connection().unwrap();
+        let conn = make_memory_connection().context("Failed to create database in memory")?;
         Ok(conn)
     } else {
         let config = read_config(testing).context("Failed to read in config")?;
-        let conn = make_connection(&config.db_path).with_context(|| {
-            format!(
-                "Failed to make a connection to the database: {:?}",
-                config.db_path,
-            )
-        })?;
-        Ok(conn)
+        let passphrase = if config.encrypted {
+            Some(get_passphrase(&config)?)
+        } else {
+            None
+        };
+        open_db(&config, passphrase.as_deref())
+    }
+}
+
+/// Returns a `Result<Connection>` to the SQLite database the `Config` points to,
+/// unlocked with the `passphrase` if it's encrypted, with any `MIGRATIONS` it is
+/// missing applied.
+pub fn open_db(config: &Config, passphrase: Option<&str>) -> Result<Connection> {
+    let conn = make_connection(&config.db_path).with_context(|| {
+        format!(
+            "Failed to make a connection to the database: {:?}",
+            config.db_path,
+        )
+    })?;
+    if let Some(passphrase) = passphrase {
+        unlock(&conn, &config.db_path, passphrase)?;
     }
+    remember_passphrase(passphrase);
+    // Only once unlocked, as nothing can be read from an encrypted database before then
+    use_wal(&conn)?;
+    let existing = has_task_table(&conn)?;
+    create_task_table(&conn)?;
+    // Keep a copy from before the schema changes, in case a migration goes wrong
+    if existing && schema_version(&conn)? < latest_schema_version() {
+        if let Some(path) = backup_before(&conn, "migrate", config.backup_count)? {
+            eprintln!("Backed up the database to {:?} before updating it", path);
+        }
+    }
+    migrate_db(&conn)?;
+    info!("Opened the database at {:?}", config.db_path);
+    Ok(conn)
 }
 
 /// Adds a `&Task` to a SQLite database based on the `&Connection` given.
 pub fn add_to_db(conn: &Connection, task: &Task) -> Result<()> {
     // Handle inserting tags
     let mut tags_insert = None;
-    match &task.tags {
-        Some(tags) => {
-            tags_insert = Some(tags.clone().into_iter().collect::<Vec<String>>().join(";"))
-        }
-        None => {}
+    if let Some(tags) = &task.tags {
+        tags_insert = Some(tags.clone().into_iter().collect::<Vec<String>>().join(";"))
     }
 
-    conn.execute(
-        "INSERT INTO task (id, name, description, latest, urgency, status, tags, date_added, completed_on) 
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        (
+    // Cached, since adding and updating tasks happens over and over from the TUI
+    conn.prepare_cached(
+        "INSERT INTO task (id, name, description, latest, urgency, status, tags, date_added, completed_on, recurrence, project, time_spent, timer_started, due, scheduled, external_ref, scheduled_start, duration, locked, parent, criteria, previous_occurrence) 
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+    )
+    .context("Failed to prepare inserting the task")?
+    .execute(params![
             &task.get_id(),
             &task.name,
             &task.description,
@@ -120,10 +543,22 @@ pub fn add_to_db(conn: &Connection, task: &Task) -> Result<()> {
             &task.urgency,
             &task.status,
             tags_insert,
-            &task.get_date_added(),
-            &task.completed_on,
-        ),
-    )
+            to_utc_rfc3339(&task.get_date_added()),
+            task.completed_on.as_ref().map(to_utc_rfc3339),
+            &task.recurrence,
+            &task.project,
+            &task.time_spent,
+            task.timer_started.as_ref().map(to_utc_rfc3339),
+            &task.due,
+            &task.scheduled,
+            &task.external_ref,
+            task.scheduled_start.as_ref().map(to_utc_rfc3339),
+            &task.duration,
+            &task.locked,
+            &task.parent,
+            criteria_to_text(&task.criteria),
+            &task.previous_occurrence,
+        ])
     .context("Failed to insert values into database")?;
 
     Ok(())
@@ -132,172 +567,2099 @@ pub fn add_to_db(conn: &Connection, task: &Task) -> Result<()> {
 /// Updates a `&Task` in a SQLite database based on the `&Connecton` given.
 pub fn update_task_in_db(conn: &Connection, task: &Task) -> Result<()> {
     let mut tags_insert = None;
-    match &task.tags {
-        Some(tags) => {
-            tags_insert = Some(tags.clone().into_iter().collect::<Vec<String>>().join(";"))
-        }
-        None => {}
+    if let Some(tags) = &task.tags {
+        tags_insert = Some(tags.clone().into_iter().collect::<Vec<String>>().join(";"))
     }
 
-    conn.execute(
-        "UPDATE task SET name = ?1, description = ?2, latest = ?3, urgency = ?4, status = ?5, tags = ?6, date_added = ?7, completed_on = ?8 WHERE id = ?9"
-        , (
-            &task.name, 
-            &task.description, 
-            &task.latest, 
-            &task.urgency, 
-            &task.status, 
-            tags_insert, 
-            &task.get_date_added(), 
-            &task.completed_on,
-            &task.get_id())).context("Failed to update values for the task")?;
+    conn.prepare_cached(
+        "UPDATE task SET name = ?1, description = ?2, latest = ?3, urgency = ?4, status = ?5, tags = ?6, date_added = ?7, completed_on = ?8, recurrence = ?9, project = ?10, time_spent = ?11, timer_started = ?12, due = ?13, scheduled = ?14, external_ref = ?15, scheduled_start = ?16, duration = ?17, locked = ?18, parent = ?19, criteria = ?20, previous_occurrence = ?21 WHERE id = ?22")
+        .context("Failed to prepare updating the task")?
+        .execute(params![
+            &task.name,
+            &task.description,
+            &task.latest,
+            &task.urgency,
+            &task.status,
+            tags_insert,
+            to_utc_rfc3339(&task.get_date_added()),
+            task.completed_on.as_ref().map(to_utc_rfc3339),
+            &task.recurrence,
+            &task.project,
+            &task.time_spent,
+            task.timer_started.as_ref().map(to_utc_rfc3339),
+            &task.due,
+            &task.scheduled,
+            &task.external_ref,
+            task.scheduled_start.as_ref().map(to_utc_rfc3339),
+            &task.duration,
+            &task.locked,
+            &task.parent,
+            criteria_to_text(&task.criteria),
+            &task.previous_occurrence,
+            &task.get_id()])
+        .context("Failed to update values for the task")?;
+
+    Ok(())
+}
+
+/// Updates just the tags of a `&Task`, whether it is in the 'task' or 'archive' table.
+pub fn update_tags_in_db(conn: &Connection, task: &Task) -> Result<()> {
+    let mut tags_insert = None;
+    if let Some(tags) = &task.tags {
+        tags_insert = Some(tags.clone().into_iter().collect::<Vec<String>>().join(";"))
+    }
 
+    for table in ["task", "archive"] {
+        conn.prepare_cached(&format!("UPDATE {} SET tags = ?1 WHERE id = ?2", table))
+            .and_then(|mut stmt| stmt.execute(params![tags_insert, &task.get_id()]))
+            .context("Failed to update the tags for the task")?;
+    }
     Ok(())
 }
 
 /// Deletes a `&Task` in a SQLite database based on the `&Connecton` given.
 pub fn delete_task_in_db(conn: &Connection, task: &Task) -> Result<()> {
     // println!("Deleting task from db");
-    conn.execute("DELETE FROM task WHERE id = ?1", [&task.get_id()]).context("Failed to delete task from the database")?;
+    conn.prepare_cached("DELETE FROM task WHERE id = ?1")
+        .and_then(|mut stmt| stmt.execute([&task.get_id()]))
+        .context("Failed to delete task from the database")?;
+    Ok(())
+}
+
+/// Moves the `Task`s given from the 'task' table into the 'archive' table.
+/// Either all of them are moved or none are.
+pub fn archive_tasks_in_db(conn: &Connection, tasks: &[Task]) -> Result<()> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start archiving tasks")?;
+    for task in tasks {
+        archive_task_in_db(&tx, task)?;
+    }
+    tx.commit().context("Failed to archive tasks")?;
+    Ok(())
+}
+
+/// Moves the `Task` from the 'task' table into the 'archive' table, for
+/// when it's part of a bigger transaction
+pub fn archive_task_in_db(conn: &Connection, task: &Task) -> Result<()> {
+    conn.execute(
+        "INSERT INTO archive SELECT * FROM task WHERE id = ?1",
+        [&task.get_id()],
+    )
+    .context("Failed to copy the task into the archive")?;
+    conn.execute("DELETE FROM task WHERE id = ?1", [&task.get_id()])
+        .context("Failed to remove the archived task from the task table")?;
     Ok(())
 }
 
+/// Copies every task from one database into another, archived ones and their
+/// logged work sessions included, skipping any the other database already has.
+/// Either all of them are copied or none are.
+/// Returns how many tasks were copied and how many were skipped.
+pub fn promote_tasks(from: &Connection, to: &Connection) -> Result<(usize, usize)> {
+    let existing: HashSet<uuid::Uuid> = get_all_db_contents(to)?
+        .tasks
+        .iter()
+        .chain(get_archive_contents(to)?.tasks.iter())
+        .map(|task| task.get_id())
+        .collect();
+    let tasks = get_all_db_contents(from)?.tasks;
+    let archived = get_archive_contents(from)?.tasks;
+
+    let tx = to
+        .unchecked_transaction()
+        .context("Failed to start promoting tasks")?;
+    let mut promoted = HashSet::new();
+    for task in tasks.iter().chain(archived.iter()) {
+        if existing.contains(&task.get_id()) {
+            continue;
+        }
+        add_to_db(&tx, task)?;
+        promoted.insert(task.get_id());
+    }
+    for task in &archived {
+        if promoted.contains(&task.get_id()) {
+            tx.execute(
+                "INSERT INTO archive SELECT * FROM task WHERE id = ?1",
+                [&task.get_id()],
+            )
+            .context("Failed to copy the task into the archive")?;
+            tx.execute("DELETE FROM task WHERE id = ?1", [&task.get_id()])
+                .context("Failed to remove the archived task from the task table")?;
+        }
+    }
+    for session in get_work_sessions(from)? {
+        if promoted.contains(&session.task_id) {
+            tx.execute(
+                "INSERT INTO work_sessions (task_id, started, ended) VALUES (?1, ?2, ?3)",
+                params![
+                    &session.task_id,
+                    to_utc_rfc3339(&session.started),
+                    to_utc_rfc3339(&session.ended)
+                ],
+            )
+            .context("Failed to copy a work session")?;
+        }
+    }
+    tx.commit().context("Failed to promote tasks")?;
+
+    let skipped = tasks.len() + archived.len() - promoted.len();
+    Ok((promoted.len(), skipped))
+}
+
 /// Returns a `Result<TaskList>` of all tasks in a SQLite database on the `&Connection` given.
 pub fn get_all_db_contents(conn: &Connection) -> Result<TaskList> {
-    let mut stmt = conn.prepare("SELECT * FROM task").unwrap();
+    get_table_contents(conn, "task")
+}
 
-    let task_iter = stmt
-        .query_map(params![], |row| {
-            // Need separate handling for the tags
-            // Basically convert string back to a vector
-            let mut tags_entry = None;
-            let tags_option: Option<String> = row.get(6).unwrap();
-
-            if let Some(tags) = tags_option {
-                    let tags_parts = tags.split(";");
-                    let mut tags_vec = vec![];
-                    for part in tags_parts {
-                        tags_vec.push(part.to_string());
-                    }
-                    tags_entry = Some(HashSet::from_iter(tags_vec));
+/// Returns a `Result<TaskList>` of all archived tasks in a SQLite database on the `&Connection` given.
+pub fn get_archive_contents(conn: &Connection) -> Result<TaskList> {
+    get_table_contents(conn, "archive")
+}
+
+/// Returns true if a task (archived ones included) was already lined up as the
+/// next occurrence of the recurring task with the id given
+pub fn has_next_occurrence_in_db(conn: &Connection, id: uuid::Uuid) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM task WHERE previous_occurrence = ?1)
+        OR EXISTS (SELECT 1 FROM archive WHERE previous_occurrence = ?1)",
+        [id],
+        |row| row.get(0),
+    )
+    .context("Failed to look for the next occurrence of the task")
+}
+
+/// Returns how many tasks are in the archive
+pub fn count_archived(conn: &Connection) -> Result<usize> {
+    conn.prepare_cached("SELECT COUNT(*) FROM archive")
+        .and_then(|mut stmt| stmt.query_row((), |row| row.get(0)))
+        .context("Failed to count the archived tasks")
+}
+
+/// Most ids put in one `IN (...)`, well under how many parameters SQLite takes
+const IDS_PER_QUERY: usize = 500;
+
+/// Returns a '?' for each of `count` parameters, split by commas
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Returns how many subtasks each of the tasks with the ids has, leaving out
+/// the ones with none
+pub fn subtask_counts_in_db(
+    conn: &Connection,
+    ids: &[uuid::Uuid],
+) -> Result<HashMap<uuid::Uuid, usize>> {
+    let mut counts = HashMap::new();
+    for chunk in ids.chunks(IDS_PER_QUERY) {
+        let sql = format!(
+            "SELECT parent, COUNT(*) FROM task WHERE parent IN ({}) GROUP BY parent",
+            placeholders(chunk.len())
+        );
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to count the subtasks")?;
+        let rows = stmt
+            .query_map(params_from_iter(chunk), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .context("Failed to count the subtasks")?;
+        for row in rows {
+            let (parent, count) = row.context("Failed to read a count of subtasks")?;
+            counts.insert(parent, count);
+        }
+    }
+    Ok(counts)
+}
+
+/// Rolls the fields given up from the subtasks of each of the tasks with the
+/// ids, all the way down, the same as `roll_up` does over every task.
+/// Parents with nothing to show are left out.
+pub fn roll_up_in_db(
+    conn: &Connection,
+    ids: &[uuid::Uuid],
+    fields: &[RollUp],
+) -> Result<HashMap<uuid::Uuid, RolledUp>> {
+    let mut rolled_up = HashMap::new();
+    if fields.is_empty() {
+        return Ok(rolled_up);
+    }
+    let urgencies = Urgency::value_variants();
+    for chunk in ids.chunks(IDS_PER_QUERY) {
+        // UNION drops repeats, so a loop of parents can't go round forever
+        let sql = format!(
+            "WITH RECURSIVE below(root, id) AS (
+                SELECT parent, id FROM task WHERE parent IN ({})
+                UNION
+                SELECT below.root, task.id FROM task JOIN below ON task.parent = below.id
+            )
+            SELECT below.root, MAX({}), MIN(task.due), SUM(task.duration)
+            FROM below JOIN task ON task.id = below.id
+            WHERE task.status != ? AND task.id != below.root
+            GROUP BY below.root",
+            placeholders(chunk.len()),
+            rank_sql("task.urgency", urgencies)
+        );
+        let mut values: Vec<Value> = chunk
+            .iter()
+            .map(|id| Value::Blob(id.as_bytes().to_vec()))
+            .collect();
+        values.push(Value::Text(Status::Completed.to_string()));
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to roll up the subtasks")?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, uuid::Uuid>(0)?,
+                    row.get::<_, Option<usize>>(1)?,
+                    row.get::<_, Option<chrono::NaiveDate>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            })
+            .context("Failed to roll up the subtasks")?;
+        for row in rows {
+            let (parent, urgency, due, estimate) =
+                row.context("Failed to read what the subtasks roll up to")?;
+            let rolled = RolledUp {
+                urgency: urgency
+                    .and_then(|rank| urgencies.get(rank).copied())
+                    .filter(|_| fields.contains(&RollUp::Urgency)),
+                due: due.filter(|_| fields.contains(&RollUp::Due)),
+                estimate: estimate.filter(|_| fields.contains(&RollUp::Estimate)),
+            };
+            if !rolled.is_empty() {
+                rolled_up.insert(parent, rolled);
             }
+        }
+    }
+    Ok(rolled_up)
+}
 
-            Ok(Task::from_sql(
-                row.get(0).unwrap(),
-                row.get(1).unwrap(),
-                row.get(2).unwrap(),
-                row.get(3).unwrap(),
-                row.get(4).unwrap(),
-                row.get(5).unwrap(),
-                tags_entry,
-                row.get(7).unwrap(),
-                row.get(8).unwrap(),
-            ))
-        })
-        .unwrap();
+/// Returns how many tasks were added or changed, or went (deleted or archived),
+/// after `since`
+pub fn count_changed_since(conn: &Connection, since: &DateTime<Local>) -> Result<usize> {
+    conn.prepare_cached(
+        "SELECT (SELECT COUNT(*) FROM task WHERE julianday(modified) > julianday(?1))
+            + (SELECT COUNT(*) FROM deleted_tasks WHERE julianday(deleted) > julianday(?1))",
+    )
+    .and_then(|mut stmt| stmt.query_row([to_utc_rfc3339(since)], |row| row.get(0)))
+    .context("Failed to count the tasks changed")
+}
+
+/// Returns a `Result<TaskList>` of all tasks in the table given, which is
+/// either 'task' or 'archive'
+fn get_table_contents(conn: &Connection, table: &str) -> Result<TaskList> {
+    // Read every time the list is redrawn after a change, so the statement is kept around
+    let mut stmt = conn
+        .prepare_cached(&format!("SELECT * FROM {}", table))
+        .with_context(|| format!("Failed to read the {} table", table))?;
+
+    let task_iter = stmt
+        .query_map(params![], task_from_row)
+        .with_context(|| format!("Failed to read the {} table", table))?;
 
     let mut task_list = TaskList::new();
     for task in task_iter {
-        task_list.tasks.push(task.unwrap());
+        task_list
+            .tasks
+            .push(task.context("Failed to read a task from the database")?);
     }
 
     Ok(task_list)
 }
 
-/// Deletes all tasks in a SQLite database on the `&Connection` given.
-/// If `hard` is true, this will also DROP the task table.
-pub fn remove_all_db_contents(conn: &Connection, hard: bool) -> Result<()> {
-    if hard {
-        conn.execute("DROP TABLE task", ())
-            .context("Failed to drop the task table")?;
-        println!("'task' table dropped successfully");
-    } else {
-        conn.execute("DELETE FROM task", ())
-            .context("Failed to wipe all tasks from the task table")?;
-        println!("Tasks from 'task' table deleted successfully");
+/// Builds a `Task` from a row holding every column of the 'task' table, in order
+fn task_from_row(row: &Row) -> rusqlite::Result<Task> {
+    // Need separate handling for the tags
+    // Basically convert string back to a vector
+    let mut tags_entry = None;
+    let tags_option: Option<String> = row.get(6)?;
+
+    if let Some(tags) = tags_option {
+        let tags_parts = tags.split(";");
+        let mut tags_vec = vec![];
+        for part in tags_parts {
+            tags_vec.push(part.to_string());
+        }
+        tags_entry = Some(HashSet::from_iter(tags_vec));
     }
+
+    let mut task = Task::from_sql(
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        tags_entry,
+        row.get(7)?,
+        row.get(8)?,
+    );
+    task.recurrence = row.get(9)?;
+    task.project = row.get(10)?;
+    task.time_spent = row.get(11)?;
+    task.timer_started = row.get(12)?;
+    task.due = row.get(13)?;
+    task.scheduled = row.get(14)?;
+    task.external_ref = row.get(15)?;
+    task.scheduled_start = row.get(16)?;
+    task.duration = row.get(17)?;
+    task.locked = row.get(18)?;
+    task.parent = row.get(19)?;
+    task.criteria = row
+        .get::<_, Option<String>>(21)?
+        .map(|criteria| parse_criteria(&criteria))
+        .unwrap_or_default();
+    task.previous_occurrence = row.get(22)?;
+
+    Ok(task)
+}
+
+/// Returns every logged stretch of time a timer ran for, oldest first
+pub fn get_work_sessions(conn: &Connection) -> Result<Vec<WorkSession>> {
+    let mut stmt = conn
+        .prepare("SELECT task_id, started, ended FROM work_sessions ORDER BY started")
+        .context("Failed to read the work sessions")?;
+    let sessions = stmt
+        .query_map(params![], |row| {
+            Ok(WorkSession {
+                task_id: row.get(0)?,
+                started: row.get(1)?,
+                ended: row.get(2)?,
+            })
+        })
+        .context("Failed to read the work sessions")?
+        .collect::<rusqlite::Result<Vec<WorkSession>>>()
+        .context("Failed to read a work session")?;
+    Ok(sessions)
+}
+
+/// Returns every change logged for the `Task` with the id given, oldest first
+pub fn get_task_events(conn: &Connection, id: uuid::Uuid) -> Result<Vec<TaskEvent>> {
+    let mut stmt = conn
+        .prepare_cached("SELECT changed, field, before, after FROM task_events WHERE task_id = ?1 ORDER BY changed, rowid")
+        .context("Failed to read the task's history")?;
+    let events = stmt
+        .query_map([id], |row| {
+            Ok(TaskEvent {
+                changed: row.get(0)?,
+                field: row.get(1)?,
+                before: row.get(2)?,
+                after: row.get(3)?,
+            })
+        })
+        .context("Failed to read the task's history")?
+        .collect::<rusqlite::Result<Vec<TaskEvent>>>()
+        .context("Failed to read a change to the task")?;
+    Ok(events)
+}
+
+/// Snoozes reminders about the `Task` with the id given until `until`,
+/// replacing any snooze it already had
+pub fn snooze_reminders_in_db(
+    conn: &Connection,
+    id: uuid::Uuid,
+    until: &DateTime<Local>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO reminder_snoozes (task_id, until) VALUES (?1, ?2)",
+        params![id, to_utc_rfc3339(until)],
+    )
+    .context("Failed to snooze the task's reminders")?;
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::backend::{config::read_config, task::{Status, Urgency}};
-    use std::fs::remove_file;
+/// Returns the ids of the `Task`s whose reminders are snoozed past `now`
+pub fn get_snoozed_reminders(
+    conn: &Connection,
+    now: &DateTime<Local>,
+) -> Result<HashSet<uuid::Uuid>> {
+    let mut stmt = conn
+        .prepare_cached("SELECT task_id FROM reminder_snoozes WHERE until > ?1")
+        .context("Failed to read the snoozed reminders")?;
+    let snoozed = stmt
+        .query_map([to_utc_rfc3339(now)], |row| row.get(0))
+        .context("Failed to read the snoozed reminders")?
+        .collect::<rusqlite::Result<HashSet<uuid::Uuid>>>()
+        .context("Failed to read a snoozed reminder")?;
+    Ok(snoozed)
+}
 
-    use super::*;
+/// Returns when each `Task` was last added, had its status changed, or got a
+/// latest update, going by its history
+pub fn get_last_touched(conn: &Connection) -> Result<HashMap<uuid::Uuid, DateTime<Local>>> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT task_id, MAX(changed) FROM task_events
+            WHERE field IN ('added', 'status', 'latest') GROUP BY task_id",
+        )
+        .context("Failed to read when tasks were last updated")?;
+    let touched = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to read when tasks were last updated")?
+        .collect::<rusqlite::Result<HashMap<uuid::Uuid, DateTime<Local>>>>()
+        .context("Failed to read when a task was last updated")?;
+    Ok(touched)
+}
 
-    fn wipe_existing_test_db(test_db_path: &PathBuf) {
-        if test_db_path.exists() {
-            remove_file(test_db_path).unwrap();
-        }
-    }
+/// Makes the `Task` with the `task` id wait on the one with the `blocker` id
+pub fn add_dependency_in_db(
+    conn: &Connection,
+    task: uuid::Uuid,
+    blocker: uuid::Uuid,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, blocker_id) VALUES (?1, ?2)",
+        params![task, blocker],
+    )
+    .context("Failed to add the dependency")?;
+    Ok(())
+}
 
-    #[test]
-    fn create_db() {
-        let local_config_dir = get_config_dir().unwrap();
-        let test_db_path = local_config_dir.join("test.checklist.sqlite");
-        wipe_existing_test_db(&test_db_path);
-        assert_eq!(test_db_path.exists(), false);
+/// Stops the `Task` with the `task` id waiting on the one with the `blocker` id,
+/// returning false if it wasn't
+pub fn remove_dependency_in_db(
+    conn: &Connection,
+    task: uuid::Uuid,
+    blocker: uuid::Uuid,
+) -> Result<bool> {
+    let removed = conn
+        .execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 AND blocker_id = ?2",
+            params![task, blocker],
+        )
+        .context("Failed to remove the dependency")?;
+    Ok(removed > 0)
+}
 
-        create_sqlite_db(true).unwrap();
+/// Returns every dependency as a (task, blocker) pair of ids, oldest first
+pub fn get_dependencies(conn: &Connection) -> Result<Vec<(uuid::Uuid, uuid::Uuid)>> {
+    let mut stmt = conn
+        .prepare("SELECT task_id, blocker_id FROM task_dependencies ORDER BY rowid")
+        .context("Failed to read the dependencies")?;
+    let pairs = stmt
+        .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to read the dependencies")?
+        .collect::<rusqlite::Result<Vec<(uuid::Uuid, uuid::Uuid)>>>()
+        .context("Failed to read a dependency")?;
+    Ok(pairs)
+}
 
-        let config = read_config(true).unwrap();
-        assert_eq!(config.db_path.exists(), true);
-        let _ = make_connection(&config.db_path).unwrap();
+/// Returns when each `Task` last changed, by id
+pub fn get_modified(conn: &Connection) -> Result<HashMap<uuid::Uuid, DateTime<Local>>> {
+    let mut stmt = conn
+        .prepare("SELECT id, modified FROM task WHERE modified IS NOT NULL")
+        .context("Failed to read when tasks changed")?;
+    let modified = stmt
+        .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to read when tasks changed")?
+        .collect::<rusqlite::Result<HashMap<uuid::Uuid, DateTime<Local>>>>()
+        .context("Failed to read when a task changed")?;
+    Ok(modified)
+}
 
-        wipe_existing_test_db(&test_db_path);
-        assert_eq!(test_db_path.exists(), false);
+/// Returns when the `Task` with the id last changed, if it's in the 'task'
+/// table and that's known
+pub fn get_task_modified(conn: &Connection, id: uuid::Uuid) -> Result<Option<DateTime<Local>>> {
+    conn.query_row("SELECT modified FROM task WHERE id = ?1", [id], |row| {
+        row.get::<_, Option<DateTime<Local>>>(0)
+    })
+    .optional()
+    .map(Option::flatten)
+    .context("Failed to read when the task changed")
+}
+
+/// Sets when the `Task` with the id last changed, i.e. to when it changed
+/// in the copy of the database it was synced from
+pub fn set_modified(conn: &Connection, id: uuid::Uuid, modified: &DateTime<Local>) -> Result<()> {
+    conn.execute(
+        "UPDATE task SET modified = ?1 WHERE id = ?2",
+        params![to_utc_rfc3339(modified), id],
+    )
+    .context("Failed to set when the task changed")?;
+    Ok(())
+}
+
+/// Puts a `Task` synced from another copy of the database in place of the one with
+/// its id, keeping when it last changed there. If there isn't one it is added,
+/// coming back out of the archive if it was archived here, since it has changed since.
+pub fn put_synced_task(conn: &Connection, task: &Task, modified: &DateTime<Local>) -> Result<()> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM task WHERE id = ?1)",
+            [&task.get_id()],
+            |row| row.get(0),
+        )
+        .context("Failed to look for the synced task")?;
+    if exists {
+        update_task_in_db(conn, task)?;
+    } else {
+        conn.execute("DELETE FROM archive WHERE id = ?1", [&task.get_id()])
+            .context("Failed to take the synced task out of the archive")?;
+        add_to_db(conn, task)?;
     }
+    set_modified(conn, task.get_id(), modified)
+}
 
-    #[test]
-    fn add_delete_to_database() {
-        let conn = get_db(true, false).unwrap();
+/// When a `Task` went from the 'task' table, and whether it was archived
+/// rather than deleted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gone {
+    pub when: DateTime<Local>,
+    pub archived: bool,
+}
 
-        let new_task = Task::new(
-            "My new task".to_string(),
-            None,
-            None,
-            Some(Urgency::Critical),
-            Some(Status::Open),
-            Some(HashSet::from_iter(vec![
-                String::from("Tag1"),
-                String::from("Tag2"),
-            ])),
-        );
-        add_to_db(&conn, &new_task).unwrap();
+/// Returns when each `Task` that has gone (deleted or archived) went, by id
+pub fn get_deleted(conn: &Connection) -> Result<HashMap<uuid::Uuid, Gone>> {
+    let mut stmt = conn
+        .prepare("SELECT id, deleted, archived FROM deleted_tasks")
+        .context("Failed to read the deleted tasks")?;
+    let deleted = stmt
+        .query_map((), |row| {
+            let gone = Gone {
+                when: row.get(1)?,
+                archived: row.get(2)?,
+            };
+            Ok((row.get(0)?, gone))
+        })
+        .context("Failed to read the deleted tasks")?
+        .collect::<rusqlite::Result<HashMap<uuid::Uuid, Gone>>>()
+        .context("Failed to read a deleted task")?;
+    Ok(deleted)
+}
 
-        // Check if data we get back from database matches
-        let task_list = get_all_db_contents(&conn).unwrap();
-        assert_eq!(task_list.len(), 1);
-        let task = task_list.tasks.get(0).unwrap();
-        assert_eq!(task.name, "My new task".to_string());
-        assert_eq!(task.description, None);
-        assert_eq!(task.latest, None);
-        assert_eq!(task.urgency, Urgency::Critical);
-        assert_eq!(task.status, Status::Open);
-        assert_eq!(task.tags, Some(HashSet::from_iter(vec![
-            String::from("Tag1"),
-            String::from("Tag2"),
-        ])));
-        assert!(task.completed_on.is_none());
+/// Returns the to-dos seen on the CalDAV server when last synced with it, by UID,
+/// with the id of the `Task` each one is and when it was seen
+pub fn get_caldav_synced(
+    conn: &Connection,
+) -> Result<HashMap<String, (uuid::Uuid, DateTime<Local>)>> {
+    let mut stmt = conn
+        .prepare("SELECT uid, id, synced FROM caldav_synced")
+        .context("Failed to read the synced to-dos")?;
+    let synced = stmt
+        .query_map((), |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .context("Failed to read the synced to-dos")?
+        .collect::<rusqlite::Result<HashMap<String, (uuid::Uuid, DateTime<Local>)>>>()
+        .context("Failed to read a synced to-do")?;
+    Ok(synced)
+}
 
-        // Again, see if data we get back matches
-        let task_list = get_all_db_contents(&conn).unwrap();
-        assert_eq!(task_list.len(), 1);
-        let task = task_list.tasks.get(0).unwrap();
-        assert_eq!(task.name, "My new task".to_string());
-        assert_eq!(task.description, Some("New description".to_string()));
-        assert_eq!(task.latest, Some("New latest".to_string()));
-        assert_eq!(task.urgency, Urgency::Critical);
-        assert_eq!(task.status, Status::Completed);
-        assert_eq!(task.tags, Some(HashSet::from_iter(vec![
-            String::from("Tag2"),
-        ])));
-        assert!(task.completed_on.is_some());
+/// Replaces the to-dos seen on the CalDAV server with the ones given by UID,
+/// with the id of the `Task` each one is, seen just now
+pub fn set_caldav_synced(conn: &Connection, synced: &HashMap<String, uuid::Uuid>) -> Result<()> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start saving the synced to-dos")?;
+    tx.execute("DELETE FROM caldav_synced", ())
+        .context("Failed to clear the synced to-dos")?;
+    let now = to_utc_rfc3339(&Local::now());
+    for (uid, id) in synced {
+        tx.execute(
+            "INSERT INTO caldav_synced (uid, id, synced) VALUES (?1, ?2, ?3)",
+            params![uid, id, now],
+        )
+        .context("Failed to save a synced to-do")?;
+    }
+    tx.commit().context("Failed to save the synced to-dos")?;
+    Ok(())
+}
+
+/// Holds the `Notification`s back until quiet hours end, in place of any held before
+pub fn hold_notifications(conn: &Connection, notifications: &[Notification]) -> Result<()> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start holding notifications")?;
+    tx.execute("DELETE FROM held_notifications", ())
+        .context("Failed to clear the held notifications")?;
+    for notification in notifications {
+        tx.execute(
+            "INSERT INTO held_notifications (summary, body) VALUES (?1, ?2)",
+            params![notification.summary, notification.body],
+        )
+        .context("Failed to hold a notification")?;
+    }
+    tx.commit().context("Failed to hold notifications")?;
+    Ok(())
+}
+
+/// Returns the `Notification`s held back during quiet hours
+pub fn get_held_notifications(conn: &Connection) -> Result<Vec<Notification>> {
+    let mut stmt = conn
+        .prepare("SELECT summary, body FROM held_notifications ORDER BY rowid")
+        .context("Failed to read the held notifications")?;
+    let held = stmt
+        .query_map((), |row| {
+            Ok(Notification {
+                summary: row.get(0)?,
+                body: row.get(1)?,
+            })
+        })
+        .context("Failed to read the held notifications")?
+        .collect::<rusqlite::Result<Vec<Notification>>>()
+        .context("Failed to read a held notification")?;
+    Ok(held)
+}
+
+/// Stops holding the `Notification`s held back during quiet hours, once they're sent
+pub fn clear_held_notifications(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM held_notifications", ())
+        .context("Failed to clear the held notifications")?;
+    Ok(())
+}
+
+/// Turns what the user typed into an FTS5 query that matches tasks holding
+/// words starting with each of the words typed. Quoting each word keeps
+/// characters like '-' or '*' from being read as FTS5 syntax.
+fn to_search_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Returns a `Result<TaskList>` of the tasks whose name, description, or latest update
+/// match the `query`, best matches first. An empty `query` matches every task.
+pub fn search_db(conn: &Connection, query: &str) -> Result<TaskList> {
+    let search_query = to_search_query(query);
+    if search_query.is_empty() {
+        return get_all_db_contents(conn);
+    }
+
+    // Run again with each character typed into the search box
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT task.* FROM task_search JOIN task ON task.id = task_search.id
+            WHERE task_search MATCH ?1 ORDER BY task_search.rank",
+        )
+        .context("Failed to prepare the search")?;
+    let task_iter = stmt
+        .query_map([search_query], task_from_row)
+        .context("Failed to search the tasks")?;
+
+    let mut task_list = TaskList::new();
+    for task in task_iter {
+        task_list
+            .tasks
+            .push(task.context("Failed to read a task from the database")?);
+    }
+
+    Ok(task_list)
+}
+
+/// Escapes the wildcards in `text` so it can go into a LIKE pattern that uses '\' to escape
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Returns SQL ranking the `column` by where its value comes in the `variants`,
+/// since they are stored by name and would otherwise compare alphabetically
+fn rank_sql<T: std::fmt::Display>(column: &str, variants: &[T]) -> String {
+    let ranks: Vec<String> = variants
+        .iter()
+        .enumerate()
+        .map(|(rank, variant)| format!("WHEN '{}' THEN {}", variant, rank))
+        .collect();
+    format!("(CASE {} {} END)", column, ranks.join(" "))
+}
+
+/// Compiles a `Query` into an SQL WHERE clause over the 'task' table,
+/// pushing the values it compares against onto `values` in the order they are used
+fn query_to_sql(query: &Query, values: &mut Vec<Value>) -> String {
+    match query {
+        Query::And(left, right) => format!(
+            "({} AND {})",
+            query_to_sql(left, values),
+            query_to_sql(right, values)
+        ),
+        Query::Or(left, right) => format!(
+            "({} OR {})",
+            query_to_sql(left, values),
+            query_to_sql(right, values)
+        ),
+        Query::Not(inner) => format!("NOT {}", query_to_sql(inner, values)),
+        Query::Status(op, status) => {
+            values.push(Value::Text(status.to_string()));
+            format!("status {} ?", op.to_sql())
+        }
+        Query::Urgency(op, urgency) => {
+            let rank = Urgency::value_variants()
+                .iter()
+                .position(|variant| variant == urgency)
+                .unwrap_or_default();
+            values.push(Value::Integer(rank as i64));
+            format!(
+                "{} {} ?",
+                rank_sql("urgency", Urgency::value_variants()),
+                op.to_sql()
+            )
+        }
+        Query::Tag(op, tag) => {
+            // Tags are stored joined by ';', so wrap them in ';' to match whole tags
+            values.push(Value::Text(format!("%;{};%", escape_like(tag))));
+            let clause = "(';' || COALESCE(tags, '') || ';') LIKE ? ESCAPE '\\'";
+            match op {
+                Op::NotEq => format!("NOT {}", clause),
+                _ => clause.to_string(),
+            }
+        }
+        Query::Project(op, project) => {
+            values.push(Value::Text(project.clone()));
+            format!("COALESCE(project, '') {} ? COLLATE NOCASE", op.to_sql())
+        }
+        Query::Date(field, op, date) => {
+            let column = match field {
+                DateField::Due => "due",
+                DateField::Scheduled => "scheduled",
+                DateField::Added => "date_added",
+                DateField::Completed => "completed_on",
+            };
+            let Some(date) = date else {
+                return match op {
+                    Op::NotEq => format!("{} IS NOT NULL", column),
+                    _ => format!("{} IS NULL", column),
+                };
+            };
+            match field {
+                DateField::Due | DateField::Scheduled => {
+                    values.push(Value::Text(date.to_string()));
+                    format!("{} {} ?", column, op.to_sql())
+                }
+                // These are instants, so compare them against when the day starts and ends
+                DateField::Added | DateField::Completed => {
+                    let (start, end) = calendar().day_bounds(*date);
+                    let (start, end) = (start.to_rfc3339(), end.to_rfc3339());
+                    let column = format!("julianday({})", column);
+                    let (clause, bounds) = match op {
+                        Op::Eq => (
+                            format!("({0} >= julianday(?) AND {0} < julianday(?))", column),
+                            vec![start, end],
+                        ),
+                        Op::NotEq => (
+                            format!("({0} < julianday(?) OR {0} >= julianday(?))", column),
+                            vec![start, end],
+                        ),
+                        Op::Lt => (format!("{} < julianday(?)", column), vec![start]),
+                        Op::LtEq => (format!("{} < julianday(?)", column), vec![end]),
+                        Op::Gt => (format!("{} >= julianday(?)", column), vec![end]),
+                        Op::GtEq => (format!("{} >= julianday(?)", column), vec![start]),
+                    };
+                    values.extend(bounds.into_iter().map(Value::Text));
+                    clause
+                }
+            }
+        }
+        Query::Text(text) => {
+            let pattern = format!("%{}%", escape_like(text));
+            values.push(Value::Text(pattern.clone()));
+            values.push(Value::Text(pattern));
+            "(name LIKE ? ESCAPE '\\' OR COALESCE(description, '') LIKE ? ESCAPE '\\')".to_string()
+        }
+    }
+}
+
+/// A SELECT over the 'task' table (or the archive) that narrows the tasks down and
+/// puts them in order in SQL, so listing a few tasks from a large database doesn't
+/// mean reading in every one of them. What it compares against goes in as parameters.
+#[derive(Debug, Default)]
+pub struct TaskSelect {
+    conditions: Vec<String>,
+    /// Values for the parameters in the `conditions`, in the order they're used
+    values: Vec<Value>,
+    order: Option<(SortBy, bool)>,
+    rows: Option<Range<usize>>,
+    archived: bool,
+    /// What was asked for, to pick the same tasks out in memory when there's
+    /// no SQLite database to run it on
+    wanted: Wanted,
+}
+
+/// The filters a `TaskSelect` was built up with
+#[derive(Debug, Default)]
+struct Wanted {
+    display: Option<Display>,
+    tags: Option<(String, TagMatch)>,
+    projects: Vec<String>,
+    queries: Vec<Query>,
+    search: Option<String>,
+    parent: Option<Option<uuid::Uuid>>,
+    ids: Option<Vec<uuid::Uuid>>,
+    slotted: Option<(DateTime<Local>, DateTime<Local>)>,
+}
+
+impl TaskSelect {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects from the archive instead of the tasks that aren't archived
+    pub fn archived(mut self) -> Self {
+        self.archived = true;
+        self
+    }
+
+    /// Returns true if it selects from the archive
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Keeps the tasks the `Display` shows, the same as `TaskList::filter_tasks`
+    pub fn display(mut self, display: Display) -> Self {
+        self.wanted.display = Some(display);
+        match display {
+            Display::All => {}
+            Display::Completed => {
+                self.values.push(Value::Text(Status::Completed.to_string()));
+                self.conditions.push(String::from("status = ?"));
+            }
+            Display::NotCompleted => {
+                self.values.push(Value::Text(Status::Completed.to_string()));
+                self.values.push(Value::Text(today().to_string()));
+                self.conditions.push(String::from(
+                    "status != ? AND (scheduled IS NULL OR scheduled <= ?)",
+                ));
+            }
+        }
+        self
+    }
+
+    /// Keeps the tasks matching the tag filter, the same as `matches_tag_filter`
+    pub fn tags(mut self, tags_filter: &str, tag_match: TagMatch) -> Self {
+        self.wanted.tags = Some((tags_filter.to_string(), tag_match));
+        let mut wanted = vec![];
+        let mut unwanted = vec![];
+        for term in tags_filter
+            .split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|term| !term.is_empty())
+        {
+            match term.strip_prefix('-') {
+                Some(tag) if !tag.is_empty() => unwanted.push(tag),
+                _ => wanted.push(term),
+            }
+        }
+
+        if !wanted.is_empty() {
+            let clauses: Vec<String> = wanted.into_iter().map(|tag| self.has_tag(tag)).collect();
+            let joiner = match tag_match {
+                TagMatch::Any => " OR ",
+                TagMatch::All => " AND ",
+            };
+            self.conditions.push(clauses.join(joiner));
+        }
+        for tag in unwanted {
+            let clause = self.has_tag(tag);
+            self.conditions.push(format!("NOT {}", clause));
+        }
+        self
+    }
+
+    /// Returns a condition on any of the task's tags containing `text`, case sensitively
+    fn has_tag(&mut self, text: &str) -> String {
+        // Tags are stored joined by ';', so no one tag can have it in
+        if text.contains(';') {
+            return String::from("0");
+        }
+        self.values.push(Value::Text(text.to_string()));
+        String::from("instr(COALESCE(tags, ''), ?) > 0")
+    }
+
+    /// Keeps the tasks in the `project`, ignoring case
+    pub fn project(mut self, project: &str) -> Self {
+        self.wanted.projects.push(project.to_string());
+        self.values.push(Value::Text(project.to_string()));
+        self.conditions
+            .push(String::from("project = ? COLLATE NOCASE"));
+        self
+    }
+
+    /// Keeps the tasks matching the `Query`
+    pub fn query(mut self, query: &Query) -> Self {
+        self.wanted.queries.push(query.clone());
+        let clause = query_to_sql(query, &mut self.values);
+        self.conditions.push(clause);
+        self
+    }
+
+    /// Keeps the tasks whose name, description, or latest update match the
+    /// `text`, the same as `search_db`. They're put in order like the rest,
+    /// rather than best matches first. Empty `text` keeps every task.
+    pub fn search(mut self, text: &str) -> Self {
+        let search_query = to_search_query(text);
+        if !search_query.is_empty() {
+            self.wanted.search = Some(text.to_string());
+            self.values.push(Value::Text(search_query));
+            self.conditions.push(String::from(
+                "id IN (SELECT id FROM task_search WHERE task_search MATCH ?)",
+            ));
+        }
+        self
+    }
+
+    /// Keeps the tasks directly under the `parent`, or the ones that aren't
+    /// subtasks if it's `None`, the same as `TaskList::filter_parent`
+    pub fn parent(mut self, parent: Option<uuid::Uuid>) -> Self {
+        self.wanted.parent = Some(parent);
+        match parent {
+            Some(parent) => {
+                self.values.push(Value::Blob(parent.as_bytes().to_vec()));
+                self.conditions.push(String::from("parent = ?"));
+            }
+            None => self.conditions.push(String::from("parent IS NULL")),
+        }
+        self
+    }
+
+    /// Keeps the tasks with the ids
+    pub fn ids(mut self, ids: &[uuid::Uuid]) -> Self {
+        self.wanted.ids = Some(ids.to_vec());
+        self.values
+            .extend(ids.iter().map(|id| Value::Blob(id.as_bytes().to_vec())));
+        self.conditions
+            .push(format!("id IN ({})", placeholders(ids.len())));
+        self
+    }
+
+    /// Keeps the tasks with a time slot starting from `from` up until `to`
+    pub fn slotted(mut self, from: DateTime<Local>, to: DateTime<Local>) -> Self {
+        self.wanted.slotted = Some((from, to));
+        self.values.push(Value::Text(to_utc_rfc3339(&from)));
+        self.values.push(Value::Text(to_utc_rfc3339(&to)));
+        self.conditions.push(String::from(
+            "julianday(scheduled_start) >= julianday(?) AND julianday(scheduled_start) < julianday(?)",
+        ));
+        self
+    }
+
+    /// Puts the tasks in order the same as `TaskList::sort`
+    pub fn order_by(mut self, sort_by: SortBy, descending: bool) -> Self {
+        self.order = Some((sort_by, descending));
+        self
+    }
+
+    /// Reads in only the `rows` of what's picked out, counting from 0
+    pub fn rows(mut self, rows: Range<usize>) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    /// Returns the FROM and WHERE of the SELECT, with a '?' for each of the `values`
+    fn source_sql(&self) -> String {
+        let table = if self.archived { "archive" } else { "task" };
+        let mut sql = format!(" FROM {}", table);
+        if !self.conditions.is_empty() {
+            let conditions: Vec<String> = self
+                .conditions
+                .iter()
+                .map(|condition| format!("({})", condition))
+                .collect();
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        sql
+    }
+
+    /// Returns what goes after ORDER BY. The id settles any ties, so the tasks
+    /// come in the same order each time and pages of them don't overlap.
+    fn order_sql(&self) -> String {
+        let Some((sort_by, descending)) = self.order else {
+            return String::from("rowid");
+        };
+        let direction = if descending { "DESC" } else { "ASC" };
+        let urgency = rank_sql("urgency", Urgency::value_variants());
+        let order = match sort_by {
+            // Ties go to the newest first when descending, the oldest first otherwise
+            SortBy::Urgency => {
+                format!("{0} {1}, julianday(date_added) {1}", urgency, direction)
+            }
+            sort_by => {
+                let key = match sort_by {
+                    SortBy::Added => format!("julianday(date_added) {}", direction),
+                    // Tasks without a due date go last either way
+                    SortBy::Due => format!("due IS NULL, due {}", direction),
+                    SortBy::Name => format!("name COLLATE checklist {}", direction),
+                    _ => format!(
+                        "{} {}",
+                        rank_sql("status", Status::value_variants()),
+                        direction
+                    ),
+                };
+                format!("{}, {} DESC, julianday(date_added) DESC", key, urgency)
+            }
+        };
+        format!("{}, id", order)
+    }
+
+    /// Returns the SELECT, with a '?' for each of the `values`
+    fn to_sql(&self) -> String {
+        let mut sql = format!(
+            "SELECT *{} ORDER BY {}",
+            self.source_sql(),
+            self.order_sql()
+        );
+        if let Some(rows) = &self.rows {
+            sql.push_str(&format!(" LIMIT {} OFFSET {}", rows.len(), rows.start));
+        }
+        sql
+    }
+
+    /// Runs the SELECT on the SQLite database on the `&Connection` given
+    pub fn run(&self, conn: &Connection) -> Result<TaskList> {
+        let mut stmt = conn
+            .prepare_cached(&self.to_sql())
+            .context("Failed to prepare the task filter")?;
+        let task_iter = stmt
+            .query_map(params_from_iter(self.values.iter()), task_from_row)
+            .context("Failed to filter the tasks")?;
+
+        let mut task_list = TaskList::new();
+        for task in task_iter {
+            task_list
+                .tasks
+                .push(task.context("Failed to read a filtered task")?);
+        }
+
+        Ok(task_list)
+    }
+
+    /// Returns how many tasks are picked out, leaving aside which `rows` are asked for
+    pub fn count(&self, conn: &Connection) -> Result<usize> {
+        conn.prepare_cached(&format!("SELECT COUNT(*){}", self.source_sql()))
+            .and_then(|mut stmt| {
+                stmt.query_row(params_from_iter(self.values.iter()), |row| row.get(0))
+            })
+            .context("Failed to count the filtered tasks")
+    }
+
+    /// Returns where the task with the id comes among those picked out, or
+    /// None if it isn't one of them, leaving aside which `rows` are asked for
+    pub fn position(&self, conn: &Connection, id: uuid::Uuid) -> Result<Option<usize>> {
+        let sql = format!(
+            "SELECT position FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY {}) - 1 AS position{}) WHERE id = ?",
+            self.order_sql(),
+            self.source_sql()
+        );
+        let values = self
+            .values
+            .iter()
+            .cloned()
+            .chain([Value::Blob(id.as_bytes().to_vec())]);
+        conn.prepare_cached(&sql)
+            .and_then(|mut stmt| {
+                stmt.query_row(params_from_iter(values), |row| row.get(0))
+                    .optional()
+            })
+            .context("Failed to find where the task is listed")
+    }
+
+    /// Picks the tasks out of the `TaskList` and puts them in order in memory,
+    /// the same as running the SELECT would (`rows` aside), for stores that can't
+    pub fn matching(&self, mut task_list: TaskList) -> TaskList {
+        let wanted = &self.wanted;
+        if let Some(text) = &wanted.search {
+            search_in(&mut task_list, text);
+        }
+        if wanted.display.is_some() || wanted.tags.is_some() {
+            let (tags, tag_match) = wanted.tags.clone().unwrap_or_default();
+            task_list.filter_tasks(wanted.display, tags, tag_match);
+        }
+        for project in &wanted.projects {
+            task_list.filter_project(project);
+        }
+        for query in &wanted.queries {
+            task_list.tasks.retain(|task| query.matches(task));
+        }
+        if let Some(parent) = wanted.parent {
+            task_list.filter_parent(parent);
+        }
+        if let Some(ids) = &wanted.ids {
+            task_list.tasks.retain(|task| ids.contains(&task.get_id()));
+        }
+        if let Some((from, to)) = wanted.slotted {
+            task_list.tasks.retain(|task| {
+                task.scheduled_start
+                    .is_some_and(|start| from <= start && start < to)
+            });
+        }
+        if let Some((sort_by, descending)) = self.order {
+            task_list.sort(sort_by, descending);
+        }
+        task_list
+    }
+
+    /// Picks the tasks out of the `TaskList` in memory the same as running the
+    /// SELECT would, keeping only the `rows` asked for
+    pub fn pick(&self, task_list: TaskList) -> TaskList {
+        let mut task_list = self.matching(task_list);
+        if let Some(rows) = &self.rows {
+            task_list.tasks = task_list
+                .tasks
+                .into_iter()
+                .skip(rows.start)
+                .take(rows.len())
+                .collect();
+        }
+        task_list
+    }
+}
+
+/// Deletes all tasks, archived ones included, in a SQLite database on the `&Connection` given.
+/// If `hard` is true, this will also DROP the task, archive, and search tables.
+pub fn remove_all_db_contents(conn: &Connection, hard: bool) -> Result<()> {
+    // Either everything goes or nothing does, rather than a half wiped database
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start wiping the database")?;
+    let message = if hard {
+        // Statements kept around for the tables are no use once they're gone
+        conn.flush_prepared_statement_cache();
+        conn.execute("DROP TABLE task", ())
+            .context("Failed to drop the task table")?;
+        conn.execute("DROP TABLE IF EXISTS archive", ())
+            .context("Failed to drop the archive table")?;
+        conn.execute("DROP TABLE IF EXISTS task_search", ())
+            .context("Failed to drop the search table")?;
+        conn.execute("DROP TABLE IF EXISTS work_sessions", ())
+            .context("Failed to drop the work sessions table")?;
+        conn.execute("DROP TABLE IF EXISTS task_events", ())
+            .context("Failed to drop the task history table")?;
+        conn.execute("DROP TABLE IF EXISTS reminder_snoozes", ())
+            .context("Failed to drop the reminder snoozes table")?;
+        conn.execute("DROP TABLE IF EXISTS held_notifications", ())
+            .context("Failed to drop the held notifications table")?;
+        conn.execute("DROP TABLE IF EXISTS task_dependencies", ())
+            .context("Failed to drop the dependencies table")?;
+        conn.execute("DROP TABLE IF EXISTS deleted_tasks", ())
+            .context("Failed to drop the deleted tasks table")?;
+        conn.execute("DROP TABLE IF EXISTS caldav_synced", ())
+            .context("Failed to drop the synced to-dos table")?;
+        // A recreated table starts from the original layout, so migrations need to run again
+        conn.pragma_update(None, "user_version", 0)
+            .context("Failed to reset the database's schema version")?;
+        "'task' and 'archive' tables dropped successfully"
+    } else {
+        conn.execute("DELETE FROM task", ())
+            .context("Failed to wipe all tasks from the task table")?;
+        conn.execute("DELETE FROM archive", ())
+            .context("Failed to wipe all tasks from the archive table")?;
+        conn.execute("DELETE FROM work_sessions", ())
+            .context("Failed to wipe the work sessions")?;
+        conn.execute("DELETE FROM task_events", ())
+            .context("Failed to wipe the task history")?;
+        conn.execute("DELETE FROM reminder_snoozes", ())
+            .context("Failed to wipe the reminder snoozes")?;
+        conn.execute("DELETE FROM held_notifications", ())
+            .context("Failed to wipe the held notifications")?;
+        conn.execute("DELETE FROM task_dependencies", ())
+            .context("Failed to wipe the dependencies")?;
+        conn.execute("DELETE FROM deleted_tasks", ())
+            .context("Failed to wipe the deleted tasks")?;
+        conn.execute("DELETE FROM caldav_synced", ())
+            .context("Failed to wipe the synced to-dos")?;
+        "Tasks from 'task' and 'archive' tables deleted successfully"
+    };
+    tx.commit().context("Failed to wipe the database")?;
+    println!("{}", message);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{
+        config::read_config,
+        task::{count_subtasks, roll_up, ExternalRef, Recurrence, Status, Urgency},
+    };
+    use std::fs::remove_file;
+
+    use super::*;
+
+    fn wipe_existing_test_db(test_db_path: &PathBuf) {
+        if test_db_path.exists() {
+            remove_file(test_db_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn create_db() {
+        let local_config_dir = get_config_dir().unwrap();
+        let test_db_path = local_config_dir.join("test.checklist.sqlite");
+        wipe_existing_test_db(&test_db_path);
+        assert!(!test_db_path.exists());
+
+        create_sqlite_db(true).unwrap();
+
+        let config = read_config(true).unwrap();
+        assert!(config.db_path.exists());
+        let _ = make_connection(&config.db_path).unwrap();
+
+        wipe_existing_test_db(&test_db_path);
+        assert!(!test_db_path.exists());
+    }
+
+    #[test]
+    fn add_delete_to_database() {
+        let conn = get_db(true, false).unwrap();
+
+        let new_task = Task::new(
+            "My new task".to_string(),
+            None,
+            None,
+            Some(Urgency::Critical),
+            Some(Status::Open),
+            Some(HashSet::from_iter(vec![
+                String::from("Tag1"),
+                String::from("Tag2"),
+            ])),
+        );
+        add_to_db(&conn, &new_task).unwrap();
+
+        // Check if data we get back from database matches
+        let task_list = get_all_db_contents(&conn).unwrap();
+        assert_eq!(task_list.len(), 1);
+        let task = task_list.tasks.first().unwrap();
+        assert_eq!(task.name, "My new task".to_string());
+        assert_eq!(task.description, None);
+        assert_eq!(task.latest, None);
+        assert_eq!(task.urgency, Urgency::Critical);
+        assert_eq!(task.status, Status::Open);
+        assert_eq!(
+            task.tags,
+            Some(HashSet::from_iter(vec![
+                String::from("Tag1"),
+                String::from("Tag2"),
+            ]))
+        );
+        assert!(task.completed_on.is_none());
+
+        // Update the task
+        let mut updated_task = new_task.clone();
+        updated_task.description = Some("New description".to_string());
+        updated_task.latest = Some("New latest".to_string());
+        updated_task.status = Status::Completed;
+        updated_task.completed_on = Some(chrono::Local::now());
+        updated_task.tags = Some(HashSet::from_iter(vec![String::from("Tag2")]));
+        update_task_in_db(&conn, &updated_task).unwrap();
+
+        // Again, see if data we get back matches
+        let task_list = get_all_db_contents(&conn).unwrap();
+        assert_eq!(task_list.len(), 1);
+        let task = task_list.tasks.first().unwrap();
+        assert_eq!(task.name, "My new task".to_string());
+        assert_eq!(task.description, Some("New description".to_string()));
+        assert_eq!(task.latest, Some("New latest".to_string()));
+        assert_eq!(task.urgency, Urgency::Critical);
+        assert_eq!(task.status, Status::Completed);
+        assert_eq!(
+            task.tags,
+            Some(HashSet::from_iter(vec![String::from("Tag2"),]))
+        );
+        assert!(task.completed_on.is_some());
 
         // Let's see if delete works as well!
         delete_task_in_db(&conn, &new_task).unwrap();
         let task_list = get_all_db_contents(&conn).unwrap();
         assert_eq!(task_list.len(), 0);
     }
+
+    #[test]
+    fn migrate_original_table() {
+        // Mimic a database made before any migrations existed
+        let conn = Connection::open_in_memory().unwrap();
+        create_task_table(&conn).unwrap();
+        let version: usize = conn
+            .query_row("PRAGMA user_version", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 0);
+
+        migrate_db(&conn).unwrap();
+        let version: usize = conn
+            .query_row("PRAGMA user_version", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        // Running again should be a no-op
+        migrate_db(&conn).unwrap();
+
+        let mut new_task = Task::new("Recurring task".to_string(), None, None, None, None, None);
+        new_task.recurrence = Some(Recurrence::EveryNDays(4));
+        new_task.project = Some("Chores".to_string());
+        add_to_db(&conn, &new_task).unwrap();
+
+        let task_list = get_all_db_contents(&conn).unwrap();
+        assert_eq!(
+            task_list.tasks[0].recurrence,
+            Some(Recurrence::EveryNDays(4))
+        );
+        assert_eq!(task_list.tasks[0].project, Some("Chores".to_string()));
+        assert_eq!(task_list.tasks[0].time_spent, 0);
+        assert_eq!(task_list.tasks[0].timer_started, None);
+        assert_eq!(task_list.tasks[0].due, None);
+        assert_eq!(task_list.tasks[0].scheduled, None);
+        assert_eq!(task_list.tasks[0].external_ref, None);
+        assert_eq!(task_list.tasks[0].scheduled_start, None);
+        assert_eq!(task_list.tasks[0].duration, None);
+        assert!(!task_list.tasks[0].locked);
+    }
+
+    #[test]
+    fn dates_stored_as_utc() {
+        let conn = get_db(true, false).unwrap();
+        let mut new_task = Task::new("Timezones".to_string(), None, None, None, None, None);
+        new_task.timer_started = Some(Local::now());
+        add_to_db(&conn, &new_task).unwrap();
+
+        let (date_added, timer_started): (String, String) = conn
+            .query_row("SELECT date_added, timer_started FROM task", (), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert!(date_added.ends_with('Z'));
+        assert!(timer_started.ends_with('Z'));
+        assert!(DateTime::parse_from_rfc3339(&date_added).is_ok());
+
+        let task_list = get_all_db_contents(&conn).unwrap();
+        assert_eq!(task_list.tasks[0].date_added, new_task.date_added);
+        assert_eq!(task_list.tasks[0].timer_started, new_task.timer_started);
+    }
+
+    #[test]
+    fn migrate_dates_to_utc() {
+        // Mimic a database with dates stored in the machine's timezone
+        let conn = Connection::open_in_memory().unwrap();
+        create_task_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO task (id, name, urgency, status, date_added, completed_on) VALUES (?1, 'Old task', 'Low', 'Completed', ?2, ?3)",
+            (uuid::Uuid::new_v4(), "2024-03-10 23:30:00.250+02:00", "2024-03-11 09:00:00-05:00"),
+        )
+        .unwrap();
+        migrate_db(&conn).unwrap();
+
+        let (date_added, completed_on): (String, String) = conn
+            .query_row("SELECT date_added, completed_on FROM task", (), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(date_added, "2024-03-10T21:30:00.250Z");
+        assert_eq!(completed_on, "2024-03-11T14:00:00.000Z");
+
+        let task_list = get_all_db_contents(&conn).unwrap();
+        let expected = DateTime::parse_from_rfc3339("2024-03-10T23:30:00.25+02:00").unwrap();
+        assert_eq!(task_list.tasks[0].date_added, expected);
+    }
+
+    #[test]
+    fn archive_tasks() {
+        let conn = get_db(true, false).unwrap();
+        let kept_task = Task::new("Keep me".to_string(), None, None, None, None, None);
+        let mut archived_task = Task::new(
+            "Archive me".to_string(),
+            None,
+            None,
+            None,
+            Some(Status::Completed),
+            None,
+        );
+        archived_task.project = Some("Chores".to_string());
+        archived_task.external_ref = Some(ExternalRef {
+            system: "github".to_string(),
+            id: "42".to_string(),
+            url: Some("https://github.com/dylanfair/checklist/issues/42".to_string()),
+        });
+        archived_task.scheduled_start = Some(Local::now());
+        archived_task.duration = Some(45);
+        archived_task.locked = true;
+        add_to_db(&conn, &kept_task).unwrap();
+        add_to_db(&conn, &archived_task).unwrap();
+
+        archive_tasks_in_db(&conn, std::slice::from_ref(&archived_task)).unwrap();
+        assert_eq!(get_all_db_contents(&conn).unwrap().tasks, vec![kept_task]);
+        assert_eq!(
+            get_archive_contents(&conn).unwrap().tasks,
+            vec![archived_task]
+        );
+        assert_eq!(count_archived(&conn).unwrap(), 1);
+
+        remove_all_db_contents(&conn, false).unwrap();
+        assert_eq!(get_archive_contents(&conn).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn log_work_sessions() {
+        let conn = get_db(true, false).unwrap();
+        let mut task = Task::new("Write report".to_string(), None, None, None, None, None);
+        let started = Local::now() - chrono::TimeDelta::minutes(25);
+        task.timer_started = Some(started);
+        add_to_db(&conn, &task).unwrap();
+        assert!(get_work_sessions(&conn).unwrap().is_empty());
+
+        // Changing something else leaves the timer running
+        task.latest = Some("Halfway".to_string());
+        update_task_in_db(&conn, &task).unwrap();
+        assert!(get_work_sessions(&conn).unwrap().is_empty());
+
+        task.stop_timer();
+        update_task_in_db(&conn, &task).unwrap();
+        let sessions = get_work_sessions(&conn).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].task_id, task.get_id());
+        assert_eq!(sessions[0].started, started);
+        assert!(sessions[0].ended > started);
+    }
+
+    #[test]
+    fn log_task_events() {
+        let conn = get_db(true, false).unwrap();
+        let mut task = Task::new("Write report".to_string(), None, None, None, None, None);
+        add_to_db(&conn, &task).unwrap();
+
+        task.urgency = Urgency::High;
+        task.description = Some("Draft the summary".to_string());
+        update_task_in_db(&conn, &task).unwrap();
+        // Saving without changing anything logs nothing
+        update_task_in_db(&conn, &task).unwrap();
+        task.locked = true;
+        update_task_in_db(&conn, &task).unwrap();
+
+        let events = get_task_events(&conn, task.get_id()).unwrap();
+        let fields: Vec<&str> = events.iter().map(|event| event.field.as_str()).collect();
+        assert_eq!(fields, vec!["added", "description", "urgency", "locked"]);
+        assert_eq!(events[0].after, Some("Write report".to_string()));
+        assert_eq!(events[1].before, None);
+        assert_eq!(events[2].before, Some("Low".to_string()));
+        assert_eq!(events[2].after, Some("High".to_string()));
+        assert_eq!(events[3].after, Some("1".to_string()));
+    }
+
+    #[test]
+    fn find_next_occurrence() {
+        let conn = get_db(true, false).unwrap();
+        let mut laundry = Task::new("Laundry".to_string(), None, None, None, None, None);
+        laundry.recurrence = Some(Recurrence::Weekly);
+        add_to_db(&conn, &laundry).unwrap();
+        assert!(!has_next_occurrence_in_db(&conn, laundry.get_id()).unwrap());
+
+        let next = laundry.next_occurrence().unwrap();
+        add_to_db(&conn, &next).unwrap();
+        assert!(has_next_occurrence_in_db(&conn, laundry.get_id()).unwrap());
+        assert!(!has_next_occurrence_in_db(&conn, next.get_id()).unwrap());
+
+        // Still found once the next occurrence is archived
+        archive_tasks_in_db(&conn, &[next]).unwrap();
+        assert!(has_next_occurrence_in_db(&conn, laundry.get_id()).unwrap());
+    }
+
+    #[test]
+    fn track_dependencies() {
+        let conn = get_db(true, false).unwrap();
+        let taxes = Task::new("Taxes".to_string(), None, None, None, None, None);
+        let receipts = Task::new("Find receipts".to_string(), None, None, None, None, None);
+        let release = Task::new("Release".to_string(), None, None, None, None, None);
+        for task in [&taxes, &receipts, &release] {
+            add_to_db(&conn, task).unwrap();
+        }
+
+        add_dependency_in_db(&conn, taxes.get_id(), receipts.get_id()).unwrap();
+        add_dependency_in_db(&conn, release.get_id(), taxes.get_id()).unwrap();
+        // Adding it again changes nothing
+        add_dependency_in_db(&conn, taxes.get_id(), receipts.get_id()).unwrap();
+        assert_eq!(
+            get_dependencies(&conn).unwrap(),
+            vec![
+                (taxes.get_id(), receipts.get_id()),
+                (release.get_id(), taxes.get_id())
+            ]
+        );
+
+        assert!(remove_dependency_in_db(&conn, release.get_id(), taxes.get_id()).unwrap());
+        assert!(!remove_dependency_in_db(&conn, release.get_id(), taxes.get_id()).unwrap());
+
+        // Deleting either end forgets the dependency
+        delete_task_in_db(&conn, &receipts).unwrap();
+        assert!(get_dependencies(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn track_modified_and_deleted() {
+        let conn = get_db(true, false).unwrap();
+        let mut taxes = Task::new("Taxes".to_string(), None, None, None, None, None);
+        let receipts = Task::new("Find receipts".to_string(), None, None, None, None, None);
+        add_to_db(&conn, &taxes).unwrap();
+        add_to_db(&conn, &receipts).unwrap();
+        let added = get_modified(&conn).unwrap()[&taxes.get_id()];
+
+        // Any change to a task marks when it happened
+        let long_ago = Local::now() - chrono::TimeDelta::days(30);
+        set_modified(&conn, taxes.get_id(), &long_ago).unwrap();
+        assert_eq!(get_modified(&conn).unwrap()[&taxes.get_id()], long_ago);
+        taxes.urgency = Urgency::High;
+        update_task_in_db(&conn, &taxes).unwrap();
+        assert!(get_modified(&conn).unwrap()[&taxes.get_id()] >= added);
+
+        // Deleted and archived tasks are remembered, until added back
+        delete_task_in_db(&conn, &receipts).unwrap();
+        archive_tasks_in_db(&conn, std::slice::from_ref(&taxes)).unwrap();
+        let deleted = get_deleted(&conn).unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert!(!deleted[&receipts.get_id()].archived);
+        assert!(deleted[&taxes.get_id()].archived);
+        add_to_db(&conn, &receipts).unwrap();
+        assert_eq!(
+            get_deleted(&conn).unwrap().into_keys().collect::<Vec<_>>(),
+            vec![taxes.get_id()]
+        );
+
+        // Which is enough to tell how many changed since a given time
+        let a_minute = chrono::TimeDelta::minutes(1);
+        assert_eq!(
+            count_changed_since(&conn, &(Local::now() - a_minute)).unwrap(),
+            2
+        );
+        assert_eq!(
+            count_changed_since(&conn, &(Local::now() + a_minute)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn roll_up_subtasks() {
+        let conn = get_db(true, false).unwrap();
+        let taxes = Task::new(
+            "Taxes".to_string(),
+            None,
+            None,
+            Some(Urgency::Low),
+            None,
+            None,
+        );
+        let mut receipts = Task::new("Find receipts".to_string(), None, None, None, None, None);
+        receipts.parent = Some(taxes.get_id());
+        receipts.due = chrono::NaiveDate::from_ymd_opt(2025, 4, 10);
+        receipts.duration = Some(30);
+        let mut shoebox = Task::new(
+            "Shoebox".to_string(),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            None,
+        );
+        shoebox.parent = Some(receipts.get_id());
+        shoebox.due = chrono::NaiveDate::from_ymd_opt(2025, 4, 1);
+        shoebox.duration = Some(15);
+        let mut done = Task::new(
+            "Bank".to_string(),
+            None,
+            None,
+            Some(Urgency::Critical),
+            None,
+            None,
+        );
+        done.parent = Some(taxes.get_id());
+        done.status = Status::Completed;
+        for task in [&taxes, &receipts, &shoebox, &done] {
+            add_to_db(&conn, task).unwrap();
+        }
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        let ids = [taxes.get_id(), receipts.get_id(), shoebox.get_id()];
+
+        // The database works them out the same as it's done in memory
+        let mut counts = count_subtasks(&tasks);
+        counts.retain(|id, _| ids.contains(id));
+        assert_eq!(subtask_counts_in_db(&conn, &ids).unwrap(), counts);
+        assert_eq!(counts[&taxes.get_id()], 2);
+        for fields in [
+            vec![RollUp::Urgency, RollUp::Due, RollUp::Estimate],
+            vec![RollUp::Due],
+            vec![],
+        ] {
+            assert_eq!(
+                roll_up_in_db(&conn, &ids, &fields).unwrap(),
+                roll_up(&tasks, &fields)
+            );
+        }
+        // Completed subtasks are left out, but not what's under them
+        let rolled = &roll_up_in_db(&conn, &ids, &[RollUp::Urgency, RollUp::Estimate]).unwrap();
+        assert_eq!(rolled[&taxes.get_id()].urgency, Some(Urgency::High));
+        assert_eq!(rolled[&taxes.get_id()].estimate, Some(45));
+
+        // Only the ids asked about are looked at
+        assert!(roll_up_in_db(&conn, &[shoebox.get_id()], &[RollUp::Due])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn snooze_reminders() {
+        let conn = get_db(true, false).unwrap();
+        let now = Local::now();
+        let (first, second) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        snooze_reminders_in_db(&conn, first, &(now + chrono::TimeDelta::minutes(10))).unwrap();
+        snooze_reminders_in_db(&conn, second, &(now - chrono::TimeDelta::minutes(10))).unwrap();
+        assert_eq!(
+            get_snoozed_reminders(&conn, &now).unwrap(),
+            HashSet::from([first])
+        );
+
+        // Snoozing again replaces the old snooze
+        snooze_reminders_in_db(&conn, first, &now).unwrap();
+        assert!(get_snoozed_reminders(&conn, &now).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hold_notifications_until_cleared() {
+        let conn = get_db(true, false).unwrap();
+        let notification = |summary: &str| Notification {
+            summary: summary.to_string(),
+            body: String::from("Taxes"),
+        };
+        hold_notifications(&conn, &[notification("first")]).unwrap();
+        // Holding again replaces what was held
+        hold_notifications(&conn, &[notification("second"), notification("third")]).unwrap();
+        assert_eq!(
+            get_held_notifications(&conn).unwrap(),
+            vec![notification("second"), notification("third")]
+        );
+        // Reading them doesn't stop holding them
+        assert_eq!(get_held_notifications(&conn).unwrap().len(), 2);
+        clear_held_notifications(&conn).unwrap();
+        assert!(get_held_notifications(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_tasks() {
+        let conn = get_db(true, false).unwrap();
+        let mut renew = Task::new(
+            "Renew passport".to_string(),
+            Some("Photos at the post office".to_string()),
+            None,
+            None,
+            None,
+            None,
+        );
+        let groceries = Task::new(
+            "Groceries".to_string(),
+            None,
+            Some("Out of oat-milk".to_string()),
+            None,
+            None,
+            None,
+        );
+        add_to_db(&conn, &renew).unwrap();
+        add_to_db(&conn, &groceries).unwrap();
+
+        assert_eq!(search_db(&conn, "pass").unwrap().tasks, vec![renew.clone()]);
+        assert_eq!(
+            search_db(&conn, "post photo").unwrap().tasks,
+            vec![renew.clone()]
+        );
+        assert_eq!(
+            search_db(&conn, "oat-milk").unwrap().tasks,
+            vec![groceries.clone()]
+        );
+        assert_eq!(search_db(&conn, "  ").unwrap().len(), 2);
+
+        // The index follows updates and deletes
+        renew.name = "Renew license".to_string();
+        update_task_in_db(&conn, &renew).unwrap();
+        assert_eq!(search_db(&conn, "passport").unwrap().len(), 0);
+        assert_eq!(
+            search_db(&conn, "license").unwrap().tasks,
+            vec![renew.clone()]
+        );
+        delete_task_in_db(&conn, &renew).unwrap();
+        assert_eq!(search_db(&conn, "license").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn load_backup_into_memory() {
+        let backup_path = std::env::temp_dir().join("checklist.load_backup_test.sqlite");
+        wipe_existing_test_db(&backup_path);
+        let backup = make_connection(&backup_path).unwrap();
+        create_task_table(&backup).unwrap();
+        migrate_db(&backup).unwrap();
+        let task = Task::new("Backed up".to_string(), None, None, None, None, None);
+        add_to_db(&backup, &task).unwrap();
+        drop(backup);
+
+        let conn = load_backup(&backup_path).unwrap();
+        assert_eq!(
+            get_all_db_contents(&conn).unwrap().tasks,
+            vec![task.clone()]
+        );
+
+        // Changes to the copy don't reach the backup
+        delete_task_in_db(&conn, &task).unwrap();
+        let backup = make_connection(&backup_path).unwrap();
+        assert_eq!(get_all_db_contents(&backup).unwrap().tasks, vec![task]);
+
+        wipe_existing_test_db(&backup_path);
+    }
+
+    #[test]
+    fn query_tasks() {
+        let conn = get_db(true, false).unwrap();
+        let mut report = Task::new(
+            "Write report".to_string(),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            Some(HashSet::from(["work".to_string()])),
+        );
+        report.due = chrono::NaiveDate::from_ymd_opt(2024, 12, 20);
+        let mut fence = Task::new(
+            "Paint fence".to_string(),
+            Some("100% weatherproof".to_string()),
+            None,
+            Some(Urgency::Low),
+            None,
+            Some(HashSet::from(["home".to_string(), "outside".to_string()])),
+        );
+        fence.project = Some("House".to_string());
+        let mut taxes = Task::new(
+            "Taxes".to_string(),
+            None,
+            None,
+            Some(Urgency::Critical),
+            Some(Status::Completed),
+            Some(HashSet::from(["homework".to_string()])),
+        );
+        taxes.completed_on = Some(Local::now());
+        for task in [&report, &fence, &taxes] {
+            add_to_db(&conn, task).unwrap();
+        }
+
+        let names = |query: &str| {
+            let query: Query = query.parse().unwrap();
+            let mut names: Vec<String> = TaskSelect::new()
+                .query(&query)
+                .run(&conn)
+                .unwrap()
+                .tasks
+                .into_iter()
+                .map(|task| task.name)
+                .collect();
+            names.sort();
+            // Matching outside the database picks the same tasks
+            let mut matched: Vec<String> = [&report, &fence, &taxes]
+                .into_iter()
+                .filter(|task| query.matches(task))
+                .map(|task| task.name.clone())
+                .collect();
+            matched.sort();
+            assert_eq!(matched, names);
+            names
+        };
+        assert_eq!(
+            names("status:open AND (tag:work OR urgency>=high) due<2025-01-01"),
+            vec!["Write report"]
+        );
+        assert_eq!(names("urgency>=high"), vec!["Taxes", "Write report"]);
+        assert_eq!(names("tag:home"), vec!["Paint fence"]);
+        assert_eq!(names("not tag:home"), vec!["Taxes", "Write report"]);
+        assert_eq!(names("project:house"), vec!["Paint fence"]);
+        assert_eq!(names("due:none status!=completed"), vec!["Paint fence"]);
+        assert_eq!(names("completed:today"), vec!["Taxes"]);
+        assert_eq!(names("added<today"), Vec::<String>::new());
+        assert_eq!(names("100%"), vec!["Paint fence"]);
+    }
+
+    #[test]
+    fn select_tasks() {
+        let conn = get_db(true, false).unwrap();
+        let mut report = Task::new(
+            "Write report".to_string(),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            Some(HashSet::from(["work".to_string()])),
+        );
+        report.due = chrono::NaiveDate::from_ymd_opt(2024, 12, 20);
+        let mut fence = Task::new(
+            "paint fence".to_string(),
+            None,
+            None,
+            Some(Urgency::Low),
+            None,
+            Some(HashSet::from(["home".to_string(), "outside".to_string()])),
+        );
+        fence.project = Some("House".to_string());
+        let taxes = Task::new(
+            "Taxes".to_string(),
+            None,
+            None,
+            Some(Urgency::Critical),
+            Some(Status::Completed),
+            Some(HashSet::from(["homework".to_string()])),
+        );
+        let mut later = Task::new(
+            "Later".to_string(),
+            None,
+            None,
+            Some(Urgency::Medium),
+            None,
+            None,
+        );
+        later.scheduled = today().succ_opt();
+        let mut tasks = vec![report, fence, taxes, later];
+        for (hours, task) in tasks.iter_mut().enumerate() {
+            task.date_added = Local::now() - chrono::TimeDelta::hours(hours as i64);
+        }
+        for task in &tasks {
+            add_to_db(&conn, task).unwrap();
+        }
+
+        // Picks out and orders the same tasks as filtering and sorting them in memory
+        let check = |display: Display,
+                     tags: &str,
+                     tag_match: TagMatch,
+                     sort_by: SortBy,
+                     descending: bool| {
+            let select = TaskSelect::new()
+                .display(display)
+                .tags(tags, tag_match)
+                .order_by(sort_by, descending);
+            let selected = select.run(&conn).unwrap();
+            let mut task_list = TaskList::new();
+            task_list.tasks = tasks.clone();
+            assert_eq!(select.pick(task_list.clone()).tasks, selected.tasks);
+            task_list.filter_tasks(Some(display), tags.to_string(), tag_match);
+            task_list.sort(sort_by, descending);
+            assert_eq!(selected.tasks, task_list.tasks);
+            selected
+                .tasks
+                .into_iter()
+                .map(|task| task.name)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            check(Display::All, "", TagMatch::Any, SortBy::Urgency, true),
+            vec!["Taxes", "Write report", "Later", "paint fence"]
+        );
+        assert_eq!(
+            check(
+                Display::NotCompleted,
+                "",
+                TagMatch::Any,
+                SortBy::Urgency,
+                false
+            ),
+            vec!["paint fence", "Write report"]
+        );
+        assert_eq!(
+            check(Display::All, "home", TagMatch::Any, SortBy::Name, false),
+            vec!["paint fence", "Taxes"]
+        );
+        assert_eq!(
+            check(
+                Display::All,
+                "home,-office",
+                TagMatch::All,
+                SortBy::Name,
+                true
+            ),
+            vec!["Taxes", "paint fence"]
+        );
+        assert_eq!(
+            check(
+                Display::All,
+                "HOME out;side",
+                TagMatch::Any,
+                SortBy::Due,
+                false
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check(Display::Completed, "", TagMatch::Any, SortBy::Status, false),
+            vec!["Taxes"]
+        );
+        assert_eq!(
+            check(Display::All, "", TagMatch::Any, SortBy::Due, true)[0],
+            "Write report"
+        );
+        check(Display::All, "", TagMatch::Any, SortBy::Added, true);
+
+        let select = TaskSelect::new()
+            .project("house")
+            .query(&"urgency<high".parse().unwrap());
+        let names: Vec<String> = select
+            .run(&conn)
+            .unwrap()
+            .tasks
+            .into_iter()
+            .map(|task| task.name)
+            .collect();
+        assert_eq!(names, vec!["paint fence"]);
+        assert_eq!(select.values.len(), 2);
+
+        let mut receipts = Task::new("Find receipts".to_string(), None, None, None, None, None);
+        receipts.parent = Some(tasks[2].get_id());
+        add_to_db(&conn, &receipts).unwrap();
+        let names = |select: TaskSelect| -> Vec<String> {
+            select
+                .run(&conn)
+                .unwrap()
+                .tasks
+                .into_iter()
+                .map(|task| task.name)
+                .collect()
+        };
+        assert_eq!(names(TaskSelect::new().search("fen")), vec!["paint fence"]);
+        assert_eq!(
+            names(TaskSelect::new().parent(Some(tasks[2].get_id()))),
+            vec!["Find receipts"]
+        );
+        assert_eq!(names(TaskSelect::new().parent(None)).len(), 4);
+        archive_tasks_in_db(&conn, &tasks[2..3]).unwrap();
+        assert_eq!(names(TaskSelect::new().archived()), vec!["Taxes"]);
+    }
+
+    #[test]
+    fn select_pages() {
+        let conn = get_db(true, false).unwrap();
+        let mut tasks = vec![];
+        for (i, name) in ["Zebra", "apple", "Éclair", "banana", "Cherry"]
+            .iter()
+            .cycle()
+            .take(25)
+            .enumerate()
+        {
+            let urgency = if i % 2 == 0 {
+                Urgency::High
+            } else {
+                Urgency::Low
+            };
+            let mut task = Task::new(name.to_string(), None, None, Some(urgency), None, None);
+            task.date_added = Local::now() - chrono::TimeDelta::minutes(i as i64);
+            add_to_db(&conn, &task).unwrap();
+            tasks.push(task);
+        }
+        let mut task_list = TaskList::new();
+        task_list.tasks = tasks.clone();
+
+        // Names are in order by the collation, in the database as in memory
+        let select = TaskSelect::new().order_by(SortBy::Name, false);
+        let all = select.run(&conn).unwrap().tasks;
+        let first: Vec<&str> = all
+            .iter()
+            .step_by(5)
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(first, vec!["apple", "banana", "Cherry", "Éclair", "Zebra"]);
+        assert_eq!(select.matching(task_list.clone()).tasks, all);
+
+        // Pages read in one after another make up the whole list
+        let select = TaskSelect::new()
+            .display(Display::All)
+            .order_by(SortBy::Urgency, true);
+        let all = select.run(&conn).unwrap().tasks;
+        assert_eq!(select.count(&conn).unwrap(), 25);
+        let mut paged = vec![];
+        for start in (0..25).step_by(10) {
+            let page = TaskSelect::new()
+                .display(Display::All)
+                .order_by(SortBy::Urgency, true)
+                .rows(start..start + 10);
+            let rows = page.run(&conn).unwrap().tasks;
+            assert_eq!(page.pick(task_list.clone()).tasks, rows);
+            paged.extend(rows);
+        }
+        assert_eq!(paged, all);
+
+        // And each task is found where it's listed
+        for (i, task) in all.iter().enumerate() {
+            assert_eq!(select.position(&conn, task.get_id()).unwrap(), Some(i));
+        }
+        let select = select.query(&"urgency>=high".parse().unwrap());
+        assert_eq!(select.count(&conn).unwrap(), 13);
+        assert_eq!(select.position(&conn, all[24].get_id()).unwrap(), None);
+    }
+
+    #[test]
+    fn wait_on_busy_database() {
+        let path = std::env::temp_dir().join(format!(
+            "checklist.busy_test.{}.sqlite",
+            uuid::Uuid::new_v4()
+        ));
+        let first = make_connection(&path).unwrap();
+        create_task_table(&first).unwrap();
+        migrate_db(&first).unwrap();
+        use_wal(&first).unwrap();
+        let mode: String = first
+            .query_row("PRAGMA journal_mode", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        let second = make_connection(&path).unwrap();
+        second.busy_timeout(Duration::ZERO).unwrap();
+        first.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let task = Task::new("Taxes".to_string(), None, None, None, None, None);
+        assert!(is_busy(&add_to_db(&second, &task).unwrap_err()));
+        // Reading doesn't have to wait on the writer
+        assert!(get_all_db_contents(&second).unwrap().tasks.is_empty());
+
+        // Tried again once the other writer is done
+        let mut attempts = 0;
+        retry_busy(|| {
+            attempts += 1;
+            if attempts == 2 {
+                first.execute_batch("COMMIT")?;
+            }
+            add_to_db(&second, &task)
+        })
+        .unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(
+            get_all_db_contents(&first).unwrap().tasks,
+            vec![task.clone()]
+        );
+
+        // Anything else fails straight away
+        attempts = 0;
+        assert!(retry_busy(|| {
+            attempts += 1;
+            add_to_db(&second, &task)
+        })
+        .is_err());
+        assert_eq!(attempts, 1);
+
+        drop((first, second));
+        wipe_existing_test_db(&path);
+    }
+
+    #[test]
+    fn promote_between_databases() {
+        let scratch = get_db(true, false).unwrap();
+        let real = get_db(true, false).unwrap();
+
+        let mut timed = Task::new("Timed".to_string(), None, None, None, None, None);
+        timed.timer_started = Some(Local::now() - chrono::TimeDelta::minutes(10));
+        add_to_db(&scratch, &timed).unwrap();
+        timed.stop_timer();
+        update_task_in_db(&scratch, &timed).unwrap();
+        let old = Task::new("Old".to_string(), None, None, None, None, None);
+        add_to_db(&scratch, &old).unwrap();
+        archive_tasks_in_db(&scratch, std::slice::from_ref(&old)).unwrap();
+        // Already in the real database, so it's left alone
+        let shared = Task::new("Shared".to_string(), None, None, None, None, None);
+        add_to_db(&scratch, &shared).unwrap();
+        add_to_db(&real, &shared).unwrap();
+
+        assert_eq!(promote_tasks(&scratch, &real).unwrap(), (2, 1));
+        assert_eq!(get_all_db_contents(&real).unwrap().tasks.len(), 2);
+        assert_eq!(
+            get_archive_contents(&real).unwrap().tasks[0].get_id(),
+            old.get_id()
+        );
+        let sessions = get_work_sessions(&real).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].task_id, timed.get_id());
+
+        // Promoting again doesn't make duplicates
+        assert_eq!(promote_tasks(&scratch, &real).unwrap(), (0, 3));
+        assert_eq!(get_work_sessions(&real).unwrap().len(), 1);
+    }
 }
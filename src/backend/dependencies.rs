@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::backend::task::Task;
+
+/// Which `Task`s have to wait on which
+#[derive(Debug, Default)]
+pub struct Dependencies {
+    /// The `Task`s each `Task` waits on
+    blockers: HashMap<Uuid, Vec<Uuid>>,
+    /// The `Task`s waiting on each `Task`
+    dependents: HashMap<Uuid, Vec<Uuid>>,
+}
+
+/// A line of the dependency graph, and the `Task` on it if there is one
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphLine {
+    pub id: Option<Uuid>,
+    pub text: String,
+}
+
+impl Dependencies {
+    /// Builds the dependencies from (task, blocker) pairs
+    pub fn new(pairs: &[(Uuid, Uuid)]) -> Self {
+        let mut dependencies = Self::default();
+        for (task, blocker) in pairs {
+            dependencies
+                .blockers
+                .entry(*task)
+                .or_default()
+                .push(*blocker);
+            dependencies
+                .dependents
+                .entry(*blocker)
+                .or_default()
+                .push(*task);
+        }
+        dependencies
+    }
+
+    /// Returns the `Task`s the one with the id waits on directly
+    pub fn blockers(&self, id: Uuid) -> &[Uuid] {
+        self.blockers.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the `Task`s waiting directly on the one with the id
+    pub fn dependents(&self, id: Uuid) -> &[Uuid] {
+        self.dependents.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns true if the `task` waits on the `blocker`, directly or further
+    /// down the chain
+    pub fn waits_on(&self, task: Uuid, blocker: Uuid) -> bool {
+        let mut seen = HashSet::new();
+        let mut next = vec![task];
+        while let Some(id) = next.pop() {
+            for waited_on in self.blockers(id) {
+                if *waited_on == blocker {
+                    return true;
+                }
+                if seen.insert(*waited_on) {
+                    next.push(*waited_on);
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the ids of the `Task`s in the chain around the one with the id,
+    /// it included: all it waits on and all waiting on it, as far as it goes
+    pub fn chain(&self, id: Uuid) -> HashSet<Uuid> {
+        let mut chain = HashSet::from([id]);
+        for next in [Self::blockers, Self::dependents] {
+            let mut ahead = vec![id];
+            while let Some(id) = ahead.pop() {
+                for linked in next(self, id) {
+                    if chain.insert(*linked) {
+                        ahead.push(*linked);
+                    }
+                }
+            }
+        }
+        chain
+    }
+
+    /// Draws the chain around the `Task` with the id as a tree: what's
+    /// waiting on it above, and what it waits on below, each as far as the
+    /// chain goes. `Task`s that can't be found (i.e. archived) are left out.
+    pub fn graph(&self, tasks: &[Task], id: Uuid) -> Vec<GraphLine> {
+        let by_id: HashMap<Uuid, &Task> = tasks.iter().map(|task| (task.get_id(), task)).collect();
+        let Some(task) = by_id.get(&id) else {
+            return vec![];
+        };
+
+        let mut lines = vec![header("Blocks:")];
+        let before = lines.len();
+        self.branch(&by_id, id, "  ", &mut lines, &|id| self.dependents(id));
+        if lines.len() == before {
+            lines.push(header("  nothing"));
+        }
+        lines.push(GraphLine {
+            id: Some(id),
+            text: format!("▶ {}", task.summary()),
+        });
+        lines.push(header("Blocked by:"));
+        let before = lines.len();
+        self.branch(&by_id, id, "  ", &mut lines, &|id| self.blockers(id));
+        if lines.len() == before {
+            lines.push(header("  nothing"));
+        }
+        lines
+    }
+
+    /// Adds a line for each `Task` `next` leads to from the id, and the ones
+    /// they lead to under them
+    fn branch<'a>(
+        &'a self,
+        by_id: &HashMap<Uuid, &Task>,
+        id: Uuid,
+        prefix: &str,
+        lines: &mut Vec<GraphLine>,
+        next: &dyn Fn(Uuid) -> &'a [Uuid],
+    ) {
+        let found: Vec<&Task> = next(id)
+            .iter()
+            .filter_map(|id| by_id.get(id).copied())
+            .collect();
+        for (i, task) in found.iter().enumerate() {
+            let last = i + 1 == found.len();
+            let (joint, indent) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            lines.push(GraphLine {
+                id: Some(task.get_id()),
+                text: format!("{}{}{}", prefix, joint, task.summary()),
+            });
+            self.branch(
+                by_id,
+                task.get_id(),
+                &format!("{}{}", prefix, indent),
+                lines,
+                next,
+            );
+        }
+    }
+}
+
+fn header(text: &str) -> GraphLine {
+    GraphLine {
+        id: None,
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[GraphLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let make_task = |name: &str| Task::new(String::from(name), None, None, None, None, None);
+        let (spec, approval, ci, taxes, release, announce) = (
+            make_task("Write spec"),
+            make_task("Get approval"),
+            make_task("Fix CI"),
+            make_task("Taxes"),
+            make_task("Release"),
+            make_task("Announce"),
+        );
+        let pairs = [
+            (taxes.get_id(), spec.get_id()),
+            (taxes.get_id(), ci.get_id()),
+            (spec.get_id(), approval.get_id()),
+            (release.get_id(), taxes.get_id()),
+            (announce.get_id(), release.get_id()),
+        ];
+        let dependencies = Dependencies::new(&pairs);
+        let tasks = vec![
+            spec.clone(),
+            approval.clone(),
+            ci,
+            taxes.clone(),
+            release,
+            announce.clone(),
+        ];
+
+        assert!(dependencies.waits_on(announce.get_id(), approval.get_id()));
+        assert!(!dependencies.waits_on(approval.get_id(), announce.get_id()));
+
+        let graph = dependencies.graph(&tasks, taxes.get_id());
+        assert_eq!(
+            texts(&graph),
+            vec![
+                "Blocks:",
+                "  └─ Release [Open]",
+                "     └─ Announce [Open]",
+                "▶ Taxes [Open]",
+                "Blocked by:",
+                "  ├─ Write spec [Open]",
+                "  │  └─ Get approval [Open]",
+                "  └─ Fix CI [Open]",
+            ]
+        );
+        assert_eq!(graph[3].id, Some(taxes.get_id()));
+        assert_eq!(graph[0].id, None);
+
+        // Archived tasks can't be found, so they're left out
+        let graph = dependencies.graph(&tasks[..2], approval.get_id());
+        assert_eq!(
+            texts(&graph),
+            vec![
+                "Blocks:",
+                "  └─ Write spec [Open]",
+                "▶ Get approval [Open]",
+                "Blocked by:",
+                "  nothing",
+            ]
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::backend::config::Config;
+use crate::backend::database::{
+    integrity_problems, latest_schema_version, open_existing, schema_version,
+};
+use crate::backend::encryption::{encryption_available, get_passphrase, unlock};
+use crate::backend::ticket::TICKET_PLACEHOLDER;
+use crate::display::theme::parse_theme;
+
+/// How a check turned out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Health {
+    Good,
+    /// Works, but not the way it probably should
+    Warning,
+    /// Keeps checklist from working
+    Problem,
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Health::Good => write!(f, "✓"),
+            Health::Warning => write!(f, "!"),
+            Health::Problem => write!(f, "✗"),
+        }
+    }
+}
+
+/// What one of the checks found, and how to fix it if something is wrong
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    /// What was checked, i.e. "Config"
+    pub check: &'static str,
+    pub health: Health,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Finding {
+    fn good(check: &'static str, detail: String) -> Self {
+        Self {
+            check,
+            health: Health::Good,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn warning(check: &'static str, detail: String, fix: &str) -> Self {
+        Self {
+            check,
+            health: Health::Warning,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+
+    fn problem(check: &'static str, detail: String, fix: &str) -> Self {
+        Self {
+            check,
+            health: Health::Problem,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// Checks the config file at the `Path` can be read, and that the settings
+/// in it make sense. Returns the `Config` too if it could be read, so the
+/// database it points to can be checked next.
+pub fn check_config(path: &Path) -> (Vec<Finding>, Option<Config>) {
+    if !path.exists() {
+        let finding = Finding::problem(
+            "Config",
+            format!("No config file at {:?}", path),
+            "Run `checklist init` to set one up, or `checklist init --set <PATH>` to use a database you already have",
+        );
+        return (vec![finding], None);
+    }
+    let config = match read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| Ok(serde_json::from_str::<Config>(&text)?))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            let finding = Finding::problem(
+                "Config",
+                format!("{:?} can't be read: {}", path, e),
+                "Fix the spot mentioned, or move the file aside and run `checklist init` to make a new one",
+            );
+            return (vec![finding], None);
+        }
+    };
+
+    let mut findings = vec![Finding::good("Config", format!("Read {:?}", path))];
+    if let Err(e) = config.calendar() {
+        findings.push(Finding::problem(
+            "Config",
+            format!("{}", e),
+            "Set \"timezone\" in the config file to \"local\", \"UTC\", or an offset like \"+05:30\"",
+        ));
+    }
+    if let Err(e) = config.collation_locale() {
+        findings.push(Finding::problem(
+            "Config",
+            format!("{}", e),
+            "Set \"collation_locale\" in the config file to a language like \"sv\", or remove it",
+        ));
+    }
+    if let Err(e) = config.quiet_hours() {
+        findings.push(Finding::problem(
+            "Config",
+            format!("{}", e),
+            "Set \"quiet_hours\" in the config file to a window like \"22:00-07:00\", or remove it",
+        ));
+    }
+    if let Err(e) = config.still_working_after() {
+        findings.push(Finding::problem(
+            "Config",
+            format!("{}", e),
+            "Set \"still_working_after\" in the config file to a length like \"4h\", or null to turn it off",
+        ));
+    }
+    if let Some(ticket_url) = &config.ticket_url {
+        if !ticket_url.contains(TICKET_PLACEHOLDER) {
+            findings.push(Finding::warning(
+                "Config",
+                format!("\"ticket_url\" has no {} in it, so every ticket opens the same page", TICKET_PLACEHOLDER),
+                "Put {ticket} where the ticket goes, i.e. \"https://example.atlassian.net/browse/{ticket}\"",
+            ));
+        }
+    }
+    (findings, Some(config))
+}
+
+/// Checks the database the `Config` points to, getting its passphrase
+/// first if it's encrypted
+pub fn check_encrypted_database(config: &Config) -> Vec<Finding> {
+    if !config.encrypted {
+        return check_database(&config.db_path, None);
+    }
+    if !encryption_available() {
+        return vec![Finding::problem(
+            "Database",
+            format!(
+                "{:?} is encrypted, but this checklist was built without encryption",
+                config.db_path
+            ),
+            "Reinstall checklist with `cargo install checklist-tui --features encryption`",
+        )];
+    }
+    match get_passphrase(config) {
+        Ok(passphrase) => check_database(&config.db_path, Some(&passphrase)),
+        Err(e) => vec![Finding::problem(
+            "Database",
+            format!(
+                "Couldn't get the passphrase for {:?}: {:#}",
+                config.db_path, e
+            ),
+            "Set CHECKLIST_PASSPHRASE, or fix the \"passphrase_command\" in the config file",
+        )],
+    }
+}
+
+/// Checks the SQLite database at the `Path` is there, is up to date, and
+/// passes SQLite's integrity check, unlocking it with the `passphrase` if
+/// it's encrypted. Nothing in it is changed.
+pub fn check_database(path: &Path, passphrase: Option<&str>) -> Vec<Finding> {
+    if !path.exists() {
+        return vec![Finding::problem(
+            "Database",
+            format!("No database at {:?}", path),
+            "Run `checklist init` to make a new one, or `checklist init --set <PATH>` to point at where it moved to",
+        )];
+    }
+    let (conn, version) = match open_existing(path).and_then(|conn| {
+        if let Some(passphrase) = passphrase {
+            unlock(&conn, path, passphrase)?;
+        }
+        let version = schema_version(&conn)?;
+        Ok((conn, version))
+    }) {
+        Ok(opened) => opened,
+        Err(e) => {
+            return vec![Finding::problem(
+                "Database",
+                format!("{:?} can't be read as a SQLite database: {:#}", path, e),
+                "Point the config at the right file with `checklist init --set <PATH>`",
+            )]
+        }
+    };
+
+    let mut findings = vec![];
+    let latest = latest_schema_version();
+    findings.push(if version > latest {
+        Finding::warning(
+            "Database",
+            format!(
+                "{:?} is at schema version {}, but this checklist only knows up to {}",
+                path, version, latest
+            ),
+            "Upgrade checklist, since a newer one has been using this database",
+        )
+    } else if version < latest {
+        Finding::warning(
+            "Database",
+            format!("{:?} is at schema version {} of {}", path, version, latest),
+            "Run any checklist command (i.e. `checklist list`) to bring it up to date",
+        )
+    } else {
+        Finding::good(
+            "Database",
+            format!("{:?} is at schema version {}", path, version),
+        )
+    });
+
+    findings.push(match integrity_problems(&conn) {
+        Ok(problems) if problems.is_empty() => {
+            Finding::good("Database", String::from("Passed the integrity check"))
+        }
+        Ok(problems) => Finding::problem(
+            "Database",
+            format!("Failed the integrity check: {}", problems.join("; ")),
+            "Copy the database somewhere safe, then salvage what's left with `sqlite3 <DB> .recover | sqlite3 <NEW DB>` and `checklist init --set <NEW DB>`",
+        ),
+        Err(e) => Finding::problem(
+            "Database",
+            format!("Couldn't run the integrity check: {:#}", e),
+            "Copy the database somewhere safe, then salvage what's left with `sqlite3 <DB> .recover | sqlite3 <NEW DB>` and `checklist init --set <NEW DB>`",
+        ),
+    });
+    findings
+}
+
+/// Checks the theme.toml at the `Path` parses. Not having one is fine,
+/// since the default theme gets used.
+pub fn check_theme(path: &Path) -> Finding {
+    if !path.exists() {
+        return Finding::warning(
+            "Theme",
+            format!("No theme.toml at {:?}, so the default theme is used", path),
+            "Run `checklist init` to write one out to customize",
+        );
+    }
+    match read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(parse_theme)
+    {
+        Ok((_, missing)) if missing.is_empty() => Finding::good("Theme", format!("Read {:?}", path)),
+        Ok((_, missing)) => Finding::good(
+            "Theme",
+            format!(
+                "Read {:?}, using defaults for [{}] (added the next time checklist opens)",
+                path,
+                missing.join("], [")
+            ),
+        ),
+        Err(e) => Finding::problem(
+            "Theme",
+            format!("{:?} can't be read: {:#}", path, e),
+            "Fix the spot mentioned, or move the file aside and run `checklist init` to make a new one",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{remove_file, write};
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::backend::database::make_memory_connection;
+
+    fn temp_path(extension: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("checklist-doctor-{}.{}", Uuid::new_v4(), extension))
+    }
+
+    fn health(findings: &[Finding]) -> Vec<Health> {
+        findings.iter().map(|finding| finding.health).collect()
+    }
+
+    #[test]
+    fn test_check_config() {
+        let path = temp_path("json");
+        let (findings, config) = check_config(&path);
+        assert_eq!(health(&findings), vec![Health::Problem]);
+        assert!(config.is_none());
+
+        write(&path, "{\"db_path\": ").unwrap();
+        let (findings, config) = check_config(&path);
+        assert_eq!(health(&findings), vec![Health::Problem]);
+        assert!(config.is_none());
+
+        let mut config = Config::new(temp_path("sqlite"));
+        config.timezone = String::from("Mars/Olympus");
+        config.ticket_url = Some(String::from("https://example.com/browse"));
+        config.collation_locale = Some(String::from("Swedish, please"));
+        config.quiet_hours = Some(String::from("late"));
+        config.still_working_after = Some(String::from("a while"));
+        write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        let (findings, config) = check_config(&path);
+        assert_eq!(
+            health(&findings),
+            vec![
+                Health::Good,
+                Health::Problem,
+                Health::Problem,
+                Health::Problem,
+                Health::Problem,
+                Health::Warning
+            ]
+        );
+        assert!(findings[1].detail.contains("Mars/Olympus"));
+        assert!(findings[2].detail.contains("Swedish, please"));
+        assert!(config.is_some());
+
+        remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_database() {
+        let path = temp_path("sqlite");
+        assert_eq!(health(&check_database(&path, None)), vec![Health::Problem]);
+
+        write(&path, "not a database at all").unwrap();
+        assert_eq!(health(&check_database(&path, None)), vec![Health::Problem]);
+        remove_file(&path).unwrap();
+
+        let conn = make_memory_connection().unwrap();
+        conn.execute("VACUUM INTO ?1", [path.to_str().unwrap()])
+            .unwrap();
+        assert_eq!(
+            health(&check_database(&path, None)),
+            vec![Health::Good, Health::Good]
+        );
+
+        // An older database gets brought up to date when it's next opened
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        remove_file(&path).unwrap();
+        conn.execute("VACUUM INTO ?1", [path.to_str().unwrap()])
+            .unwrap();
+        assert_eq!(
+            health(&check_database(&path, None)),
+            vec![Health::Warning, Health::Good]
+        );
+        remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_theme() {
+        let path = temp_path("toml");
+        assert_eq!(check_theme(&path).health, Health::Warning);
+
+        write(&path, "[theme_colors]\nmain_bg = ").unwrap();
+        assert_eq!(check_theme(&path).health, Health::Problem);
+
+        write(&path, "[theme_colors]\n[text_colors]\n").unwrap();
+        let finding = check_theme(&path);
+        assert_eq!(finding.health, Health::Good);
+        assert!(finding.detail.contains("[theme_styles]"));
+
+        remove_file(&path).unwrap();
+    }
+}
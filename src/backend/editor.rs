@@ -0,0 +1,76 @@
+use std::env;
+use std::fs::{read_to_string, remove_file, write};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+/// Returns the editor set in `$VISUAL` or `$EDITOR`,
+/// falling back on `vi` (or `notepad` on Windows)
+fn editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                String::from("notepad")
+            } else {
+                String::from("vi")
+            }
+        })
+}
+
+/// Writes the text to a temporary file, opens it with the `editor` command,
+/// and returns what the file holds once the editor exits.
+/// The command goes through the shell, so it can have arguments (i.e. `code --wait`).
+fn edit_with(editor: &str, text: &str) -> Result<String> {
+    let path = env::temp_dir().join(format!("checklist-{}.md", Uuid::new_v4()));
+    write(&path, text).context("Failed to write the text out for the editor")?;
+
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command
+            .arg("/C")
+            .arg(format!("{} \"{}\"", editor, path.display()));
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&path);
+        command
+    };
+    let status = command.status();
+    let edited = read_to_string(&path);
+    let _ = remove_file(&path);
+
+    let status = status.with_context(|| format!("Failed to run the editor '{}'", editor))?;
+    if !status.success() {
+        bail!("The editor exited with {}", status);
+    }
+    let edited = edited.context("Failed to read the text back from the editor")?;
+    // Editors like to end files with a line break
+    Ok(edited.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Opens the text in the user's `$VISUAL` or `$EDITOR`, returning the edited text
+pub fn edit_in_editor(text: &str) -> Result<String> {
+    edit_with(&editor(), text)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_with() {
+        assert_eq!(
+            edit_with("sed -i -e 's/draft/final/'", "A draft\nof notes\n").unwrap(),
+            "A final\nof notes"
+        );
+        assert!(edit_with("false", "Unchanged").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::{stderr, stdin, IsTerminal, Write};
+use std::path::Path;
+use std::sync::RwLock;
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use rusqlite::{params, Connection, DatabaseName};
+
+use crate::backend::config::Config;
+use crate::backend::open::shell_output;
+use crate::backend::terminal::RawModeGuard;
+
+/// Environment variable the passphrase can be given in, i.e. when run from cron
+pub const PASSPHRASE_VAR: &str = "CHECKLIST_PASSPHRASE";
+
+/// The passphrase the database was unlocked with, so backups of it are encrypted too
+static PASSPHRASE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Returns true if checklist was built with the `encryption` feature, using
+/// SQLCipher in place of SQLite
+pub fn encryption_available() -> bool {
+    cfg!(feature = "encryption")
+}
+
+fn require_encryption() -> Result<()> {
+    if !encryption_available() {
+        bail!("This checklist was built without encryption. Reinstall it with `cargo install checklist-tui --features encryption` to use an encrypted database");
+    }
+    Ok(())
+}
+
+/// Returns the passphrase the database was unlocked with, if it's encrypted
+pub fn passphrase() -> Option<String> {
+    PASSPHRASE.read().ok()?.clone()
+}
+
+/// Keeps the passphrase the database in use was unlocked with, or `None`
+/// if it isn't encrypted
+pub fn remember_passphrase(passphrase: Option<&str>) {
+    if let Ok(mut current) = PASSPHRASE.write() {
+        *current = passphrase.map(String::from);
+    }
+}
+
+/// Returns the passphrase for the database from `CHECKLIST_PASSPHRASE`, or
+/// else from the `passphrase_command` in the `Config` (i.e. a keyring
+/// lookup), or `None` if it has to be typed in
+pub fn saved_passphrase(config: &Config) -> Result<Option<String>> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+        if !passphrase.is_empty() {
+            return Ok(Some(passphrase));
+        }
+    }
+    if let Some(command) = &config.passphrase_command {
+        let passphrase = shell_output(command, "passphrase command")?;
+        if passphrase.is_empty() {
+            bail!("The passphrase command didn't print a passphrase");
+        }
+        return Ok(Some(passphrase));
+    }
+    Ok(None)
+}
+
+/// Returns the passphrase for the database from `saved_passphrase`, or else
+/// by asking for it on the terminal
+pub fn get_passphrase(config: &Config) -> Result<String> {
+    match saved_passphrase(config)? {
+        Some(passphrase) => Ok(passphrase),
+        None => prompt_passphrase("Passphrase for the database: "),
+    }
+}
+
+/// Returns the passphrase to encrypt the database with, from the same places
+/// as `get_passphrase`, but asking for it twice if it has to be typed in
+pub fn new_passphrase(config: &Config) -> Result<String> {
+    if let Some(passphrase) = saved_passphrase(config)? {
+        return Ok(passphrase);
+    }
+    let passphrase = prompt_passphrase("New passphrase for the database: ")?;
+    if passphrase.is_empty() {
+        bail!("The passphrase can't be empty");
+    }
+    if prompt_passphrase("Type it again: ")? != passphrase {
+        bail!("The passphrases didn't match");
+    }
+    Ok(passphrase)
+}
+
+/// Asks for a passphrase on the terminal, without showing what's typed
+fn prompt_passphrase(prompt: &str) -> Result<String> {
+    if !stdin().is_terminal() {
+        bail!(
+            "The database is encrypted, but there's no terminal to ask for the passphrase on. Set {} or \"passphrase_command\" in the config file",
+            PASSPHRASE_VAR
+        );
+    }
+    eprint!("{}", prompt);
+    stderr().flush()?;
+    let guard = RawModeGuard::enable()?;
+    let passphrase = read_hidden();
+    drop(guard);
+    eprintln!();
+    passphrase
+}
+
+/// Reads keys until Enter is pressed, without echoing them
+fn read_hidden() -> Result<String> {
+    let mut passphrase = String::new();
+    loop {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = read()?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Enter => return Ok(passphrase),
+            KeyCode::Esc => bail!("Cancelled"),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => bail!("Cancelled"),
+            KeyCode::Backspace => {
+                passphrase.pop();
+            }
+            KeyCode::Char(c) => passphrase.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// Unlocks the encrypted database at the `Path` the `&Connection` is open
+/// on, failing if the passphrase is wrong
+pub fn unlock(conn: &Connection, path: &Path, passphrase: &str) -> Result<()> {
+    require_encryption()?;
+    conn.pragma_update(None, "key", passphrase)
+        .context("Failed to set the database's passphrase")?;
+    // SQLCipher only finds out whether the passphrase is right once something is read
+    conn.query_row("SELECT count(*) FROM sqlite_master", (), |row| {
+        row.get::<usize, i64>(0)
+    })
+    .with_context(|| {
+        format!(
+            "Wrong passphrase for the database at {:?}, or it isn't encrypted",
+            path
+        )
+    })?;
+    Ok(())
+}
+
+/// Writes a copy of the database on the `&Connection` to the `Path`,
+/// encrypted with the passphrase
+pub fn export_encrypted(conn: &Connection, path: &Path, passphrase: &str) -> Result<()> {
+    require_encryption()?;
+    let version: usize = conn.query_row("PRAGMA user_version", (), |row| row.get(0))?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        params![path.to_string_lossy(), passphrase],
+    )
+    .with_context(|| format!("Failed to create {:?}", path))?;
+    let exported = conn
+        .query_row("SELECT sqlcipher_export('encrypted')", (), |_| Ok(()))
+        .and_then(|_| {
+            conn.pragma_update(
+                Some(DatabaseName::Attached("encrypted")),
+                "user_version",
+                version,
+            )
+        });
+    conn.execute("DETACH DATABASE encrypted", ())?;
+    exported.with_context(|| format!("Failed to write an encrypted copy to {:?}", path))
+}
+
+/// Copies the database at the `Path`, encrypted with the passphrase, into
+/// the one on the `&Connection`
+pub fn import_encrypted(conn: &Connection, path: &Path, passphrase: &str) -> Result<()> {
+    require_encryption()?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        params![path.to_string_lossy(), passphrase],
+    )
+    .with_context(|| format!("Failed to open {:?}", path))?;
+    let imported = conn
+        .query_row("SELECT sqlcipher_export('main', 'encrypted')", (), |_| {
+            Ok(())
+        })
+        .and_then(|_| {
+            conn.query_row("PRAGMA encrypted.user_version", (), |row| {
+                row.get::<usize, usize>(0)
+            })
+        })
+        .and_then(|version| conn.pragma_update(None, "user_version", version));
+    conn.execute("DETACH DATABASE encrypted", ())?;
+    imported.with_context(|| format!("Failed to read the encrypted database at {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_get_passphrase() {
+        let mut config = Config::new(PathBuf::from("checklist.sqlite"));
+        config.passphrase_command = Some(String::from("printf 'correct horse\\n'"));
+        assert_eq!(get_passphrase(&config).unwrap(), "correct horse");
+
+        config.passphrase_command = Some(String::from("true"));
+        assert!(get_passphrase(&config).is_err());
+        config.passphrase_command = Some(String::from("exit 1"));
+        assert!(get_passphrase(&config).is_err());
+    }
+
+    #[test]
+    fn test_unlock_needs_encryption() {
+        if encryption_available() {
+            return;
+        }
+        let conn = Connection::open_in_memory().unwrap();
+        let error = unlock(&conn, Path::new(":memory:"), "hunter2").unwrap_err();
+        assert!(error.to_string().contains("--features encryption"));
+        assert_eq!(passphrase(), None);
+    }
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Errors from data checklist was given that it can't use, worded so the user
+/// knows what to fix. They're passed around as `anyhow::Error`s like the rest,
+/// and can be picked back out with `downcast_ref` where they need handling.
+#[derive(Debug, Error, PartialEq)]
+pub enum ChecklistError {
+    #[error("'{0}' is not a valid urgency, use Low, Medium, High, or Critical")]
+    InvalidUrgency(String),
+    #[error("'{0}' is not a valid status, use Open, Working, Paused, or Completed")]
+    InvalidStatus(String),
+    #[error("Couldn't find a config directory for checklist, set {0} to one instead")]
+    NoConfigDir(&'static str),
+    #[error("{0:?} doesn't exist, so it can't be used as the database")]
+    MissingDatabase(PathBuf),
+    #[error("{0} needs the tasks kept in a SQLite database")]
+    NeedsSqlite(&'static str),
+    #[error("The config file at {0:?} can't be read, even after setting checklist up again")]
+    UnreadableConfig(PathBuf),
+}
@@ -0,0 +1,253 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::{DateTime, Local};
+
+/// A change to one field of a `Task`, logged by the database when it happens
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskEvent {
+    pub changed: DateTime<Local>,
+    /// The field that changed, or "added" when the `Task` was added
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// A run of words that were kept, taken out, or put in
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// How a field of a `Task` changed, ready to be shown
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldChange {
+    /// The `Task` was added, with the name it was given
+    Added(String),
+    /// A field went from one value to another, i.e. "urgency: Medium → High"
+    Value {
+        field: String,
+        before: String,
+        after: String,
+    },
+    /// Text that changed, word by word
+    Text { field: String, diff: Vec<Diff> },
+    /// Tags put on and taken off
+    Tags {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+/// Fields holding text long enough to be worth showing word by word
+const TEXT_FIELDS: [&str; 2] = ["description", "latest"];
+
+/// Compares two texts word by word, returning runs of the words kept,
+/// taken out, and put in, in the order they read
+pub fn word_diff(before: &str, after: &str) -> Vec<Diff> {
+    let old: Vec<&str> = before.split_whitespace().collect();
+    let new: Vec<&str> = after.split_whitespace().collect();
+
+    // Longest common subsequence of words, from the end of both
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff: Vec<Diff> = vec![];
+    let mut push = |part: Diff| {
+        let merged = match (diff.last_mut(), &part) {
+            (Some(Diff::Same(run)), Diff::Same(word))
+            | (Some(Diff::Removed(run)), Diff::Removed(word))
+            | (Some(Diff::Added(run)), Diff::Added(word)) => {
+                run.push(' ');
+                run.push_str(word);
+                true
+            }
+            _ => false,
+        };
+        if !merged {
+            diff.push(part);
+        }
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(Diff::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            push(Diff::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push(Diff::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Returns the tags stored joined by ';'
+fn tag_set(tags: Option<&str>) -> HashSet<&str> {
+    tags.map(|tags| tags.split(';').collect())
+        .unwrap_or_default()
+}
+
+/// Returns how the value is shown for the field
+fn shown_value(field: &str, value: Option<&str>) -> String {
+    match (field, value) {
+        ("locked", Some("1")) => String::from("yes"),
+        ("locked", Some("0")) => String::from("no"),
+        ("scheduled_start", Some(value)) => DateTime::parse_from_rfc3339(value)
+            .map(|start| {
+                start
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| value.to_string()),
+        ("duration", Some(value)) => format!("{} min", value),
+        (_, Some(value)) => value.to_string(),
+        (_, None) => String::from("(none)"),
+    }
+}
+
+impl FieldChange {
+    /// Works out how the field changed, or returns `None` if it didn't really,
+    /// i.e. the same tags stored in a different order
+    pub fn from_event(event: &TaskEvent) -> Option<FieldChange> {
+        let before = event.before.as_deref();
+        let after = event.after.as_deref();
+        let field = event.field.clone();
+        match event.field.as_str() {
+            "added" => Some(FieldChange::Added(after.unwrap_or_default().to_string())),
+            "tags" => {
+                let (old, new) = (tag_set(before), tag_set(after));
+                let mut added: Vec<String> =
+                    new.difference(&old).map(|tag| tag.to_string()).collect();
+                let mut removed: Vec<String> =
+                    old.difference(&new).map(|tag| tag.to_string()).collect();
+                if added.is_empty() && removed.is_empty() {
+                    return None;
+                }
+                added.sort();
+                removed.sort();
+                Some(FieldChange::Tags { added, removed })
+            }
+            name if TEXT_FIELDS.contains(&name) => Some(FieldChange::Text {
+                field,
+                diff: word_diff(before.unwrap_or_default(), after.unwrap_or_default()),
+            }),
+            name => Some(FieldChange::Value {
+                before: shown_value(name, before),
+                after: shown_value(name, after),
+                field,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    /// Writes the change out as plain text, marking words taken out
+    /// as [-like this-] and words put in as {+like this+}
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Added(name) => write!(f, "added as '{}'", name),
+            FieldChange::Value {
+                field,
+                before,
+                after,
+            } => write!(f, "{}: {} → {}", field, before, after),
+            FieldChange::Text { field, diff } => {
+                let words: Vec<String> = diff
+                    .iter()
+                    .map(|part| match part {
+                        Diff::Same(words) => words.clone(),
+                        Diff::Removed(words) => format!("[-{}-]", words),
+                        Diff::Added(words) => format!("{{+{}+}}", words),
+                    })
+                    .collect();
+                write!(f, "{}: {}", field, words.join(" "))
+            }
+            FieldChange::Tags { added, removed } => {
+                let tags: Vec<String> = added
+                    .iter()
+                    .map(|tag| format!("+{}", tag))
+                    .chain(removed.iter().map(|tag| format!("-{}", tag)))
+                    .collect();
+                write!(f, "tags: {}", tags.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(field: &str, before: Option<&str>, after: Option<&str>) -> TaskEvent {
+        TaskEvent {
+            changed: Local::now(),
+            field: field.to_string(),
+            before: before.map(String::from),
+            after: after.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            word_diff("call the bank about fees", "email the bank about the fees"),
+            vec![
+                Diff::Removed(String::from("call")),
+                Diff::Added(String::from("email")),
+                Diff::Same(String::from("the bank about")),
+                Diff::Added(String::from("the")),
+                Diff::Same(String::from("fees")),
+            ]
+        );
+        assert_eq!(
+            word_diff("", "new text"),
+            vec![Diff::Added(String::from("new text"))]
+        );
+        assert!(word_diff("", "").is_empty());
+    }
+
+    #[test]
+    fn test_field_change() {
+        let urgency = FieldChange::from_event(&event("urgency", Some("Medium"), Some("High")));
+        assert_eq!(urgency.unwrap().to_string(), "urgency: Medium → High");
+
+        let due = FieldChange::from_event(&event("due", None, Some("2024-05-01")));
+        assert_eq!(due.unwrap().to_string(), "due: (none) → 2024-05-01");
+
+        let locked = FieldChange::from_event(&event("locked", Some("0"), Some("1")));
+        assert_eq!(locked.unwrap().to_string(), "locked: no → yes");
+
+        let description = FieldChange::from_event(&event(
+            "description",
+            Some("Fix the login bug"),
+            Some("Fix the signup bug"),
+        ));
+        assert_eq!(
+            description.unwrap().to_string(),
+            "description: Fix the [-login-] {+signup+} bug"
+        );
+
+        let tags = FieldChange::from_event(&event("tags", Some("work;urgent"), Some("home;work")));
+        assert_eq!(tags.unwrap().to_string(), "tags: +home -urgent");
+        // Stored in a different order, but the same tags
+        assert!(FieldChange::from_event(&event("tags", Some("a;b"), Some("b;a"))).is_none());
+
+        let added = FieldChange::from_event(&event("added", None, Some("Write report")));
+        assert_eq!(added.unwrap().to_string(), "added as 'Write report'");
+    }
+}
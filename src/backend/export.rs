@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::fs::write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, SecondsFormat};
+use clap::ValueEnum;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::backend::database::{
+    get_all_db_contents, get_archive_contents, get_dependencies, get_work_sessions,
+};
+use crate::backend::task::{criteria_to_text, Task, WorkSession};
+use crate::backend::taskwarrior::taskwarrior_json;
+
+/// Name given in the time log to sessions of `Task`s that have since been deleted
+const DELETED: &str = "(deleted)";
+
+/// Columns of the CSV with a row for each `Task`. Together they hold
+/// everything about a `Task`, so the CSV can be imported again.
+pub const TASK_FIELDS: [&str; 23] = [
+    "id",
+    "name",
+    "status",
+    "urgency",
+    "project",
+    "tags",
+    "due",
+    "date_added",
+    "completed_on",
+    "description",
+    "latest",
+    "recurrence",
+    "scheduled",
+    "time_spent",
+    "timer_started",
+    "scheduled_start",
+    "duration",
+    "external_ref",
+    "external_url",
+    "locked",
+    "parent",
+    "criteria",
+    "previous_occurrence",
+];
+
+/// Columns of the CSV with a row for each work session
+pub const TIMELOG_FIELDS: [&str; 6] = ["start", "end", "hours", "task", "project", "tags"];
+
+/// What `checklist export` writes out
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// One CSV row per task
+    Csv,
+    /// One CSV row per stretch of time tracked with the timer, for invoicing
+    Timelog,
+    /// TaskWarrior's JSON, for `task import`
+    Taskwarrior,
+}
+
+/// Quotes the field if it has anything in it that would break the CSV row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Joins the fields into a CSV row
+fn csv_row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| csv_field(field.as_ref()))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Formats the time for a CSV row, down to the second
+fn csv_time(time: &DateTime<Local>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// Formats the time for a CSV row as precisely as it is stored,
+/// so it comes back the same when imported
+pub fn exact_time(time: &DateTime<Local>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+}
+
+/// Returns the `Task`'s tags, sorted and separated by semicolons
+fn joined_tags(task: &Task) -> String {
+    let mut tags: Vec<&str> = task.tags.iter().flatten().map(|tag| tag.as_str()).collect();
+    tags.sort();
+    tags.join(";")
+}
+
+/// Returns where each of the fields asked for is among the `known` ones,
+/// in the order asked for. Field names are matched ignoring case.
+fn field_indices(fields: &[String], known: &[&str]) -> Result<Vec<usize>> {
+    let mut indices = vec![];
+    for field in fields {
+        let field = field.trim();
+        match known
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(field))
+        {
+            Some(index) => indices.push(index),
+            None => bail!(
+                "Unknown field '{}', the fields that can be exported are: {}",
+                field,
+                known.join(", ")
+            ),
+        }
+    }
+    if indices.is_empty() {
+        bail!("No fields were given to export");
+    }
+    Ok(indices)
+}
+
+/// Builds the lines of a CSV out of the rows, keeping only the columns
+/// for the fields given (all of them if there are none)
+fn csv_lines(
+    header: &[&str],
+    rows: Vec<Vec<String>>,
+    fields: Option<&[String]>,
+) -> Result<Vec<String>> {
+    let indices = match fields {
+        Some(fields) => field_indices(fields, header)?,
+        None => (0..header.len()).collect(),
+    };
+    let pick =
+        |row: &[&str]| -> Vec<String> { indices.iter().map(|&i| row[i].to_string()).collect() };
+
+    let mut lines = vec![csv_row(&pick(header))];
+    for row in &rows {
+        let row: Vec<&str> = row.iter().map(|field| field.as_str()).collect();
+        lines.push(csv_row(&pick(&row)));
+    }
+    Ok(lines)
+}
+
+/// Returns the values of a `Task` for each of the `TASK_FIELDS`
+pub fn task_row(task: &Task) -> Vec<String> {
+    vec![
+        task.get_id().to_string(),
+        task.name.clone(),
+        task.status.to_string(),
+        task.urgency.to_string(),
+        task.project.clone().unwrap_or_default(),
+        joined_tags(task),
+        task.due.map(|due| due.to_string()).unwrap_or_default(),
+        exact_time(&task.get_date_added()),
+        task.completed_on
+            .as_ref()
+            .map(exact_time)
+            .unwrap_or_default(),
+        task.description.clone().unwrap_or_default(),
+        task.latest.clone().unwrap_or_default(),
+        task.recurrence
+            .map(|recurrence| recurrence.to_string())
+            .unwrap_or_default(),
+        task.scheduled
+            .map(|scheduled| scheduled.to_string())
+            .unwrap_or_default(),
+        task.time_spent.to_string(),
+        task.timer_started
+            .as_ref()
+            .map(exact_time)
+            .unwrap_or_default(),
+        task.scheduled_start
+            .as_ref()
+            .map(exact_time)
+            .unwrap_or_default(),
+        task.duration
+            .map(|duration| duration.to_string())
+            .unwrap_or_default(),
+        task.external_ref
+            .as_ref()
+            .map(|external_ref| external_ref.to_string())
+            .unwrap_or_default(),
+        task.external_ref
+            .as_ref()
+            .and_then(|external_ref| external_ref.url.clone())
+            .unwrap_or_default(),
+        task.locked.to_string(),
+        task.parent
+            .map(|parent| parent.to_string())
+            .unwrap_or_default(),
+        criteria_to_text(&task.criteria).unwrap_or_default(),
+        task.previous_occurrence
+            .map(|previous| previous.to_string())
+            .unwrap_or_default(),
+    ]
+}
+
+/// Builds the lines of a CSV with a row for each `Task`, with only the
+/// fields given (all of them if there are none)
+pub fn tasks_csv(tasks: &[Task], fields: Option<&[String]>) -> Result<Vec<String>> {
+    csv_lines(&TASK_FIELDS, tasks.iter().map(task_row).collect(), fields)
+}
+
+/// Writes a CSV of the `Task`s out to the file at `path`
+pub fn write_tasks_csv(path: &Path, tasks: &[Task]) -> Result<()> {
+    let mut csv = tasks_csv(tasks, None)?.join("\n");
+    csv.push('\n');
+    write(path, csv).with_context(|| format!("Failed to write the tasks to {:?}", path))
+}
+
+/// Builds the lines of a CSV with a row for each work session: when it
+/// started and ended, how many hours it lasted, and the `Task` it was for.
+/// Only the fields given are kept (all of them if there are none).
+pub fn timelog_csv(
+    tasks: &[Task],
+    sessions: &[WorkSession],
+    fields: Option<&[String]>,
+) -> Result<Vec<String>> {
+    let by_id: HashMap<Uuid, &Task> = tasks.iter().map(|task| (task.get_id(), task)).collect();
+
+    let mut rows = vec![];
+    for session in sessions {
+        let task = by_id.get(&session.task_id);
+        rows.push(vec![
+            csv_time(&session.started),
+            csv_time(&session.ended),
+            format!("{:.2}", session.seconds() as f64 / 3600.0),
+            task.map_or(String::from(DELETED), |task| task.name.clone()),
+            task.and_then(|task| task.project.clone())
+                .unwrap_or_default(),
+            task.map(|task| joined_tags(task)).unwrap_or_default(),
+        ]);
+    }
+    csv_lines(&TIMELOG_FIELDS, rows, fields)
+}
+
+/// Prints the export in the `format` given, limited to a single project
+/// if one is given, with only the fields given. The time log covers archived `Task`s too.
+pub fn print_export(
+    conn: &Connection,
+    format: ExportFormat,
+    project: Option<&str>,
+    fields: Option<&[String]>,
+) -> Result<()> {
+    let mut task_list = get_all_db_contents(conn)?;
+    let lines = match format {
+        ExportFormat::Csv => {
+            if let Some(project) = project {
+                task_list.filter_project(project);
+            }
+            tasks_csv(&task_list.tasks, fields)?
+        }
+        ExportFormat::Timelog => {
+            task_list.tasks.extend(get_archive_contents(conn)?.tasks);
+            let mut sessions = get_work_sessions(conn)?;
+            if let Some(project) = project {
+                task_list.filter_project(project);
+                let kept: Vec<Uuid> = task_list.tasks.iter().map(|task| task.get_id()).collect();
+                sessions.retain(|session| kept.contains(&session.task_id));
+            }
+            timelog_csv(&task_list.tasks, &sessions, fields)?
+        }
+        ExportFormat::Taskwarrior => {
+            if fields.is_some() {
+                bail!("--fields only works with the CSV formats");
+            }
+            if let Some(project) = project {
+                task_list.filter_project(project);
+            }
+            vec![taskwarrior_json(&task_list.tasks, &get_dependencies(conn)?).to_string()]
+        }
+    };
+
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_timelog_csv() {
+        let mut task = Task::new(
+            String::from("Build site, part 2"),
+            None,
+            None,
+            None,
+            None,
+            Some(HashSet::from([String::from("web"), String::from("acme")])),
+        );
+        task.project = Some(String::from("acme"));
+        let started: DateTime<Local> = DateTime::parse_from_rfc3339("2024-03-06T09:00:00Z")
+            .unwrap()
+            .into();
+        let sessions = vec![
+            WorkSession {
+                task_id: task.get_id(),
+                started,
+                ended: started + Duration::minutes(90),
+            },
+            WorkSession {
+                task_id: Uuid::new_v4(),
+                started,
+                ended: started + Duration::minutes(20),
+            },
+        ];
+
+        let lines = timelog_csv(std::slice::from_ref(&task), &sessions, None).unwrap();
+        assert_eq!(lines[0], "start,end,hours,task,project,tags");
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},{},1.50,\"Build site, part 2\",acme,acme;web",
+                csv_time(&started),
+                csv_time(&(started + Duration::minutes(90)))
+            )
+        );
+        assert!(lines[2].ends_with(",0.33,(deleted),,"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_write_tasks_csv() {
+        let mut task = Task::new(String::from("Say \"hi\""), None, None, None, None, None);
+        task.project = Some(String::from("home"));
+        let path = std::env::temp_dir().join(format!("checklist-{}.csv", Uuid::new_v4()));
+
+        write_tasks_csv(&path, std::slice::from_ref(&task)).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id,name,status,"));
+        assert!(lines[1].starts_with(&format!(
+            "{},\"Say \"\"hi\"\"\",Open,Low,home,",
+            task.get_id()
+        )));
+    }
+
+    #[test]
+    fn test_export_fields() {
+        let mut task = Task::new(String::from("Plan trip"), None, None, None, None, None);
+        task.due = Some(chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        let fields = |fields: &[&str]| -> Vec<String> {
+            fields.iter().map(|field| field.to_string()).collect()
+        };
+
+        let lines = tasks_csv(
+            std::slice::from_ref(&task),
+            Some(&fields(&["name", " Due", "status"])),
+        )
+        .unwrap();
+        assert_eq!(lines, vec!["name,due,status", "Plan trip,2024-05-01,Open"]);
+
+        let error = tasks_csv(&[], Some(&fields(&["name", "priority"])))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Unknown field 'priority'"));
+        assert!(error.ends_with("locked, parent, criteria, previous_occurrence"));
+        assert!(timelog_csv(&[], &[], Some(&fields(&["status"]))).is_err());
+        assert_eq!(
+            timelog_csv(&[], &[], Some(&fields(&["hours", "task"]))).unwrap(),
+            vec!["hours,task"]
+        );
+    }
+}
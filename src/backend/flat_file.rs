@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_file, rename, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::backend::export::{task_row, TASK_FIELDS};
+use crate::backend::import::task_from_record;
+use crate::backend::store::{Storage, TaskStore};
+use crate::backend::task::{Task, TaskList};
+
+/// Folder inside the tasks folder that archived tasks are moved into
+const ARCHIVE_DIR: &str = "archive";
+
+/// Keeps each `Task` in a file of its own, named after its id, so the tasks
+/// can be read, edited, and put under version control as plain text. The
+/// files hold the same fields `checklist export` does, leaving out empty ones.
+pub struct FlatFileStore {
+    dir: PathBuf,
+    /// What the task files are written in
+    storage: Storage,
+}
+
+impl FlatFileStore {
+    /// Opens the folder of task files at `dir`, written in the `storage`
+    /// format, making it if it isn't there yet
+    pub fn open(dir: &Path, storage: Storage) -> Result<Self> {
+        if storage == Storage::Sqlite {
+            bail!("A SQLite database isn't a folder of task files");
+        }
+        create_dir_all(dir.join(ARCHIVE_DIR))
+            .with_context(|| format!("Failed to make the tasks folder {:?}", dir))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            storage,
+        })
+    }
+
+    /// Returns the extension of the task files
+    fn extension(&self) -> &'static str {
+        match self.storage {
+            Storage::Toml => "toml",
+            _ => "json",
+        }
+    }
+
+    /// Returns the folder the tasks, or the archived ones, are kept in
+    fn folder(&self, archived: bool) -> PathBuf {
+        if archived {
+            self.dir.join(ARCHIVE_DIR)
+        } else {
+            self.dir.clone()
+        }
+    }
+
+    /// Returns the path of the file the `Task` with the id is kept in
+    fn path(&self, archived: bool, id: Uuid) -> PathBuf {
+        self.folder(archived)
+            .join(format!("{}.{}", id, self.extension()))
+    }
+
+    /// Writes out the `Task` to its file, going through a .tmp file so a
+    /// failed write doesn't leave half a task behind
+    fn write_task(&self, archived: bool, task: &Task) -> Result<()> {
+        let fields: BTreeMap<&str, String> = TASK_FIELDS
+            .into_iter()
+            .zip(task_row(task))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        let text = match self.storage {
+            Storage::Toml => {
+                toml::to_string(&fields).context("Failed to write the task as TOML")?
+            }
+            _ => {
+                serde_json::to_string_pretty(&fields).context("Failed to write the task as JSON")?
+            }
+        };
+
+        let path = self.path(archived, task.get_id());
+        let tmp_path = path.with_extension("tmp");
+        write(&tmp_path, text).with_context(|| format!("Failed to write {:?}", tmp_path))?;
+        rename(&tmp_path, &path).with_context(|| format!("Failed to save {:?}", path))
+    }
+
+    /// Reads the `Task` in the file at `path`
+    fn read_task(&self, path: &Path) -> Result<Task> {
+        let text = read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let fields: BTreeMap<String, Value> = match self.storage {
+            Storage::Toml => {
+                toml::from_str(&text).with_context(|| format!("{:?} isn't valid TOML", path))?
+            }
+            _ => serde_json::from_str(&text)
+                .with_context(|| format!("{:?} isn't valid JSON", path))?,
+        };
+
+        let mut header = vec![];
+        let mut record = vec![];
+        for (field, value) in fields {
+            let value = match value {
+                Value::String(text) => text,
+                Value::Null => continue,
+                // Numbers and booleans written in by hand
+                other => other.to_string(),
+            };
+            header.push(field);
+            record.push(value);
+        }
+        // A file added by hand can leave its id to its name
+        if !header.iter().any(|field| field == "id") {
+            let id = path
+                .file_stem()
+                .and_then(|stem| Uuid::parse_str(&stem.to_string_lossy()).ok());
+            let Some(id) = id else {
+                bail!("{:?} needs an id, or to be named after one", path);
+            };
+            header.push(String::from("id"));
+            record.push(id.to_string());
+        }
+        task_from_record(&header, &record).with_context(|| format!("Failed to read {:?}", path))
+    }
+
+    /// Returns every `Task` in the folder, oldest first
+    fn read_tasks(&self, archived: bool) -> Result<TaskList> {
+        let folder = self.folder(archived);
+        let entries = read_dir(&folder).with_context(|| format!("Failed to read {:?}", folder))?;
+        let mut task_list = TaskList::new();
+        for entry in entries {
+            let path = entry
+                .with_context(|| format!("Failed to read {:?}", folder))?
+                .path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == self.extension()) {
+                task_list.tasks.push(self.read_task(&path)?);
+            }
+        }
+        task_list
+            .tasks
+            .sort_by_key(|task| (task.get_date_added(), task.get_id()));
+        Ok(task_list)
+    }
+}
+
+impl TaskStore for FlatFileStore {
+    fn add(&self, task: &Task) -> Result<()> {
+        if self.path(false, task.get_id()).exists() {
+            bail!("There's already a task with the id {}", task.get_id());
+        }
+        self.write_task(false, task)
+    }
+
+    fn update(&self, task: &Task) -> Result<()> {
+        // Like the database, there's nothing to update if it's gone
+        if !self.path(false, task.get_id()).exists() {
+            return Ok(());
+        }
+        self.write_task(false, task)
+    }
+
+    fn delete(&self, task: &Task) -> Result<()> {
+        let path = self.path(false, task.get_id());
+        if path.exists() {
+            remove_file(&path).with_context(|| format!("Failed to delete {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    fn archive(&self, tasks: &[Task]) -> Result<()> {
+        for task in tasks {
+            let (from, to) = (
+                self.path(false, task.get_id()),
+                self.path(true, task.get_id()),
+            );
+            rename(&from, &to).with_context(|| format!("Failed to archive {:?}", from))?;
+        }
+        Ok(())
+    }
+
+    fn tasks(&self) -> Result<TaskList> {
+        self.read_tasks(false)
+    }
+
+    fn archived(&self) -> Result<TaskList> {
+        self.read_tasks(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::backend::database::TaskSelect;
+    use crate::backend::import::tests::{random_task, Random};
+    use crate::backend::query::Query;
+    use crate::backend::task::{Criterion, Status, Urgency};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("checklist-{}-{}", name, Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_random_round_trip() {
+        // The files hold every field `checklist export` does, so the tasks
+        // come back just as they were
+        for storage in [Storage::Json, Storage::Toml] {
+            for seed in 1..=5u64 {
+                let dir = temp_path("tasks");
+                let store = FlatFileStore::open(&dir, storage).unwrap();
+                let mut random = Random::seeded(seed);
+                let mut tasks: Vec<Task> = (0..40).map(|_| random_task(&mut random)).collect();
+                for task in &tasks {
+                    store.add(task).unwrap();
+                }
+
+                let mut read = store.tasks().unwrap().tasks;
+                read.sort_by_key(|task| task.get_id());
+                tasks.sort_by_key(|task| task.get_id());
+                assert_eq!(read, tasks, "{:?}, seed {}", storage, seed);
+                std::fs::remove_dir_all(&dir).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_file_store() {
+        for storage in [Storage::Json, Storage::Toml] {
+            let dir = temp_path("tasks");
+            let store = FlatFileStore::open(&dir, storage).unwrap();
+            let mut report = Task::new(
+                String::from("Write report"),
+                Some(String::from("Two pages,\n\"quoted\"")),
+                None,
+                Some(Urgency::High),
+                None,
+                Some(HashSet::from([String::from("work")])),
+            );
+            report.criteria = vec![Criterion::new("Proofread")];
+            let taxes = Task::new(String::from("Taxes"), None, None, None, None, None);
+            store.add(&report).unwrap();
+            store.add(&taxes).unwrap();
+            assert!(store.add(&taxes).is_err());
+
+            report.status = Status::Working;
+            store.update(&report).unwrap();
+            let mut tasks = store.tasks().unwrap().tasks;
+            tasks.sort_by_key(|task| task.name.clone());
+            assert_eq!(tasks, vec![taxes.clone(), report.clone()]);
+            assert_eq!(
+                store
+                    .select(&TaskSelect::new().search("two PAGES"))
+                    .unwrap()
+                    .tasks,
+                vec![report.clone()]
+            );
+            let query: Query = "tag:work".parse().unwrap();
+            assert_eq!(
+                store
+                    .select(&TaskSelect::new().query(&query))
+                    .unwrap()
+                    .tasks,
+                vec![report.clone()]
+            );
+
+            store.archive(std::slice::from_ref(&taxes)).unwrap();
+            assert_eq!(store.archived().unwrap().tasks, vec![taxes]);
+            assert_eq!(store.count_archived().unwrap(), 1);
+            store.delete(&report).unwrap();
+            assert!(store.tasks().unwrap().tasks.is_empty());
+            assert!(store.dependencies().is_err());
+
+            // Files written by hand only need a name, and an id to go by
+            let id = Uuid::new_v4();
+            write(
+                dir.join(format!("{}.json", id)),
+                "{\"name\": \"By hand\", \"locked\": true}",
+            )
+            .unwrap();
+            write(
+                dir.join(format!("{}.toml", id)),
+                "name = \"By hand\"\nlocked = true",
+            )
+            .unwrap();
+            let tasks = store.tasks().unwrap().tasks;
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks[0].get_id(), id);
+            assert_eq!(tasks[0].name, "By hand");
+            assert!(tasks[0].locked);
+
+            // Each store only reads files with its own extension
+            let no_id = match storage {
+                Storage::Toml => "name = \"No id\"",
+                _ => "{\"name\": \"No id\"}",
+            };
+            write(dir.join(format!("notes.{}", store.extension())), no_id).unwrap();
+            assert!(store.tasks().is_err());
+        }
+    }
+}
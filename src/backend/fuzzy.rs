@@ -0,0 +1,70 @@
+/// Scores how well a `query` fuzzy matches a `candidate`, ignoring case.
+/// Every character of the query needs to show up in the candidate in order,
+/// otherwise `None` is returned. Higher scores are better matches, with
+/// consecutive characters and characters at the start of words favored.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut query_index = 0;
+    let mut last_match: Option<usize> = None;
+
+    for (i, ch) in candidate.iter().enumerate() {
+        if query_index == query.len() {
+            break;
+        }
+        if *ch != query[query_index] {
+            continue;
+        }
+
+        score += 1;
+        match last_match {
+            // Consecutive characters are a strong signal
+            Some(last) if last + 1 == i => score += 5,
+            // Otherwise penalize the gap we had to skip over
+            Some(last) => score -= (i - last - 1).min(5) as i64,
+            None => score -= i.min(5) as i64,
+        }
+        if i == 0 || !candidate[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        last_match = Some(i);
+        query_index += 1;
+    }
+
+    if query_index == query.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_matches() {
+        assert!(fuzzy_score("", "anything").is_some());
+        assert!(fuzzy_score("fb", "Fix build").is_some());
+        assert!(fuzzy_score("BUILD", "fix build").is_some());
+        assert!(fuzzy_score("bf", "Fix build").is_none());
+        assert!(fuzzy_score("fix builds", "Fix build").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_score_ranking() {
+        // Consecutive matches beat scattered ones
+        assert!(
+            fuzzy_score("build", "Fix build").unwrap()
+                > fuzzy_score("build", "bring up il docs").unwrap()
+        );
+        // Matches at word starts beat ones mid-word
+        assert!(fuzzy_score("fb", "fix build").unwrap() > fuzzy_score("fb", "offbeat").unwrap());
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+
+use crate::backend::open::{open_uri, run_shell};
+use crate::backend::task::Task;
+
+/// Returns the body of a hand off, with the details someone picking up
+/// the `Task` would need
+pub fn handoff_body(task: &Task) -> String {
+    let mut lines = vec![task.summary(), format!("Urgency: {}", task.urgency)];
+    if let Some(due) = task.due {
+        lines.push(format!("Due: {}", due));
+    }
+    if let Some(description) = &task.description {
+        lines.push(String::new());
+        lines.push(description.clone());
+    }
+    if let Some(latest) = &task.latest {
+        lines.push(String::new());
+        lines.push(format!("Latest: {}", latest));
+    }
+    lines.join("\n")
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986,
+/// so the text can go in a URI
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Returns a `mailto:` URI with the subject and body filled in from the
+/// `Task`, leaving the recipient to be picked in the mail client
+pub fn mailto_uri(task: &Task) -> String {
+    format!(
+        "mailto:?subject={}&body={}",
+        percent_encode(&task.name),
+        percent_encode(&handoff_body(task).replace('\n', "\r\n"))
+    )
+}
+
+/// Hands the `Task` off, either by running the `command` given through the
+/// shell or by opening a `mailto:` link in the default mail client. The
+/// command gets the details in the `CHECKLIST_SUBJECT`, `CHECKLIST_BODY`,
+/// and `CHECKLIST_MAILTO` environment variables.
+pub fn hand_off(task: &Task, command: Option<&str>) -> Result<()> {
+    let uri = mailto_uri(task);
+    let Some(command) = command else {
+        return open_uri(&uri);
+    };
+
+    run_shell(
+        command,
+        &[
+            ("CHECKLIST_SUBJECT", &task.name),
+            ("CHECKLIST_BODY", &handoff_body(task)),
+            ("CHECKLIST_MAILTO", &uri),
+        ],
+        "",
+        "hand off command",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::task::Urgency;
+
+    #[test]
+    fn test_mailto_uri() {
+        let mut task = Task::new(
+            String::from("Renew passport"),
+            Some(String::from("Photos & form")),
+            None,
+            Some(Urgency::High),
+            None,
+            None,
+        );
+        task.due = chrono::NaiveDate::from_ymd_opt(2024, 5, 1);
+
+        assert_eq!(
+            handoff_body(&task),
+            "Renew passport [Open]\nUrgency: High\nDue: 2024-05-01\n\nPhotos & form"
+        );
+        assert_eq!(
+            mailto_uri(&task),
+            "mailto:?subject=Renew%20passport&body=Renew%20passport%20%5BOpen%5D%0D%0A\
+             Urgency%3A%20High%0D%0ADue%3A%202024-05-01%0D%0A%0D%0APhotos%20%26%20form"
+        );
+    }
+}
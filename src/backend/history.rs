@@ -0,0 +1,236 @@
+use anyhow::Result;
+
+use crate::backend::store::TaskStore;
+use crate::backend::task::Task;
+
+/// How many operations are kept around to undo
+const HISTORY_LIMIT: usize = 100;
+
+/// A single change made to a `Task` in the database
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Task),
+    // Boxed to keep the variants close in size
+    Updated { before: Box<Task>, after: Task },
+    Deleted(Task),
+}
+
+impl Change {
+    /// Returns the change that puts things back the way they were
+    fn inverse(&self) -> Change {
+        match self {
+            Change::Added(task) => Change::Deleted(task.clone()),
+            Change::Updated { before, after } => Change::Updated {
+                before: Box::new(after.clone()),
+                after: *before.clone(),
+            },
+            Change::Deleted(task) => Change::Added(task.clone()),
+        }
+    }
+
+    fn apply(&self, store: &dyn TaskStore) -> Result<()> {
+        match self {
+            Change::Added(task) => store.add(task),
+            Change::Updated { after, .. } => store.update(after),
+            Change::Deleted(task) => store.delete(task),
+        }
+    }
+}
+
+/// Everything one action in the TUI changed, i.e. completing a recurring
+/// `Task` updates it and adds its next occurrence
+pub type Operation = Vec<Change>;
+
+/// Log of the operations made in the TUI, so they can be undone and redone
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+impl History {
+    /// Records an operation that was just made. Anything that was undone
+    /// can no longer be redone.
+    pub fn record(&mut self, operation: Operation) {
+        if operation.is_empty() {
+            return;
+        }
+        self.undo.push(operation);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Undoes the latest operation, returning it, or None if there was nothing to undo
+    pub fn undo(&mut self, store: &dyn TaskStore) -> Result<Option<Operation>> {
+        let Some(operation) = self.undo.pop() else {
+            return Ok(None);
+        };
+        let undone = store.atomically(&mut || {
+            for change in operation.iter().rev() {
+                change.inverse().apply(store)?;
+            }
+            Ok(())
+        });
+        // Nothing was undone, so it can still be tried again
+        if let Err(e) = undone {
+            self.undo.push(operation);
+            return Err(e);
+        }
+        self.redo.push(operation.clone());
+        Ok(Some(operation))
+    }
+
+    /// Redoes the latest undone operation, returning it, or None if there was nothing to redo
+    pub fn redo(&mut self, store: &dyn TaskStore) -> Result<Option<Operation>> {
+        let Some(operation) = self.redo.pop() else {
+            return Ok(None);
+        };
+        let redone = store.atomically(&mut || {
+            for change in operation.iter() {
+                change.apply(store)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = redone {
+            self.redo.push(operation);
+            return Err(e);
+        }
+        self.undo.push(operation.clone());
+        Ok(Some(operation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use anyhow::bail;
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::backend::database::{
+        add_to_db, delete_task_in_db, get_all_db_contents, get_db, update_task_in_db,
+    };
+    use crate::backend::task::{Status, TaskList};
+
+    /// Store whose writes fail while `failing` is set
+    struct FailingStore {
+        conn: Connection,
+        failing: Cell<bool>,
+    }
+
+    impl FailingStore {
+        fn write(&self, write: impl FnOnce() -> Result<()>) -> Result<()> {
+            if self.failing.get() {
+                bail!("The disk is full");
+            }
+            write()
+        }
+    }
+
+    impl TaskStore for FailingStore {
+        fn add(&self, task: &Task) -> Result<()> {
+            self.write(|| self.conn.add(task))
+        }
+
+        fn update(&self, task: &Task) -> Result<()> {
+            self.write(|| self.conn.update(task))
+        }
+
+        fn delete(&self, task: &Task) -> Result<()> {
+            self.write(|| self.conn.delete(task))
+        }
+
+        fn archive(&self, tasks: &[Task]) -> Result<()> {
+            self.write(|| self.conn.archive(tasks))
+        }
+
+        fn tasks(&self) -> Result<TaskList> {
+            self.conn.tasks()
+        }
+
+        fn archived(&self) -> Result<TaskList> {
+            self.conn.archived()
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let conn = get_db(true, false).unwrap();
+        let mut history = History::default();
+
+        let task = Task::new(String::from("Keep me"), None, None, None, None, None);
+        add_to_db(&conn, &task).unwrap();
+        history.record(vec![Change::Added(task.clone())]);
+
+        let mut completed = task.clone();
+        completed.status = Status::Completed;
+        update_task_in_db(&conn, &completed).unwrap();
+        history.record(vec![Change::Updated {
+            before: Box::new(task.clone()),
+            after: completed.clone(),
+        }]);
+
+        delete_task_in_db(&conn, &completed).unwrap();
+        history.record(vec![Change::Deleted(completed.clone())]);
+        assert!(get_all_db_contents(&conn).unwrap().tasks.is_empty());
+
+        // Undoing the delete brings back the completed task
+        history.undo(&conn).unwrap();
+        assert_eq!(
+            get_all_db_contents(&conn).unwrap().tasks,
+            vec![completed.clone()]
+        );
+
+        // Then undoing the update reopens it
+        history.undo(&conn).unwrap();
+        assert_eq!(
+            get_all_db_contents(&conn).unwrap().tasks,
+            vec![task.clone()]
+        );
+
+        history.redo(&conn).unwrap();
+        assert_eq!(get_all_db_contents(&conn).unwrap().tasks, vec![completed]);
+
+        // A new operation drops whatever could have been redone
+        history.record(vec![Change::Added(Task::new(
+            String::from("Another"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        ))]);
+        assert!(history.redo(&conn).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_undo_and_redo_are_kept() {
+        let store = FailingStore {
+            conn: get_db(true, false).unwrap(),
+            failing: Cell::new(false),
+        };
+        let mut history = History::default();
+        let task = Task::new(String::from("Keep me"), None, None, None, None, None);
+        store.add(&task).unwrap();
+        history.record(vec![Change::Added(task.clone())]);
+
+        // The undo that failed is still there to try again
+        store.failing.set(true);
+        assert!(history.undo(&store).is_err());
+        assert!(history.redo(&store).unwrap().is_none());
+        store.failing.set(false);
+        assert!(history.undo(&store).unwrap().is_some());
+        assert!(store.tasks().unwrap().tasks.is_empty());
+
+        // And so is the redo
+        store.failing.set(true);
+        assert!(history.redo(&store).is_err());
+        store.failing.set(false);
+        assert!(history.redo(&store).unwrap().is_some());
+        assert_eq!(store.tasks().unwrap().tasks, vec![task]);
+        assert!(history.redo(&store).unwrap().is_none());
+    }
+}
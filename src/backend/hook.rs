@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+
+use crate::backend::cli::{complain, CommandStatus};
+use crate::backend::database::get_all_db_contents;
+use crate::backend::task::{Status, Task};
+
+/// Tag that marks a `Task` as needing to be done before committing
+const BLOCKING_TAG: &str = "blocking";
+
+/// Returns the name of the git repository the current directory is in,
+/// which is the name of its top level folder
+fn current_repo_name() -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("Not inside a git repository");
+    }
+
+    let toplevel = String::from_utf8(output.stdout).context("git gave back a non UTF-8 path")?;
+    let name = Path::new(toplevel.trim())
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Could not work out the name of the git repository")?;
+    Ok(name.to_string())
+}
+
+/// Returns true if the tag is the blocking tag, with or without a leading '#'
+fn is_blocking_tag(tag: &str) -> bool {
+    tag.trim_start_matches('#')
+        .eq_ignore_ascii_case(BLOCKING_TAG)
+}
+
+/// Returns the `Task`s in the `project` that aren't completed
+/// and are tagged as blocking
+pub fn blocking_tasks(tasks: Vec<Task>, project: &str) -> Vec<Task> {
+    let project = project.to_lowercase();
+    tasks
+        .into_iter()
+        .filter(|task| task.status != Status::Completed)
+        .filter(|task| {
+            task.project
+                .as_ref()
+                .is_some_and(|task_project| task_project.to_lowercase() == project)
+        })
+        .filter(|task| {
+            task.tags
+                .as_ref()
+                .is_some_and(|tags| tags.iter().any(|tag| is_blocking_tag(tag)))
+        })
+        .collect()
+}
+
+/// Runs the pre-commit hook, failing if any open `Task` for the repository
+/// is tagged `#blocking`. Tasks belong to a repository through their project,
+/// which defaults to the name of the repository's folder.
+pub fn pre_commit(conn: &Connection, project: Option<String>) -> Result<CommandStatus> {
+    let project = match project {
+        Some(project) => project,
+        None => current_repo_name()?,
+    };
+
+    let task_list = get_all_db_contents(conn)?;
+    let blocking = blocking_tasks(task_list.tasks, &project);
+    if blocking.is_empty() {
+        return Ok(CommandStatus::Success);
+    }
+
+    complain(format!(
+        "checklist: {} blocking task(s) still open for '{}':",
+        blocking.len(),
+        project
+    ));
+    for task in &blocking {
+        complain(format!("  {}", task.summary()));
+    }
+    Ok(CommandStatus::Blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_blocking_tasks() {
+        let make_task = |name: &str, project: &str, tag: &str, status: Status| {
+            let mut task = Task::new(
+                String::from(name),
+                None,
+                None,
+                None,
+                Some(status),
+                Some(HashSet::from([String::from(tag)])),
+            );
+            task.project = Some(String::from(project));
+            task
+        };
+        let fix_tests = make_task("Fix tests", "checklist", "#blocking", Status::Open);
+        let bump_version = make_task("Bump version", "Checklist", "Blocking", Status::Working);
+        let done = make_task("Done already", "checklist", "blocking", Status::Completed);
+        let elsewhere = make_task("Other repo", "website", "blocking", Status::Open);
+        let not_blocking = make_task("Nice to have", "checklist", "someday", Status::Open);
+
+        let tasks = vec![
+            fix_tests.clone(),
+            bump_version.clone(),
+            done,
+            elsewhere,
+            not_blocking,
+        ];
+        assert_eq!(
+            blocking_tasks(tasks.clone(), "checklist"),
+            vec![fix_tests, bump_version]
+        );
+        assert!(blocking_tasks(tasks, "dotfiles").is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use tracing::debug;
+
+/// Largest body a request or response can have, so a bad client can't make
+/// the server hold on to as much memory as it likes
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Largest the first line and headers of a message can be together
+const MAX_HEADER: usize = 64 * 1024;
+
+/// How long to wait on the other end before giving up
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP request, just as much of one as syncing needs
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    /// Returns the value of the header with the name, ignoring its case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An HTTP response with a JSON body (or none)
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    /// A response with a JSON error message in it
+    pub fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self::new(
+            status,
+            serde_json::json!({ "error": message.to_string() }).to_string(),
+        )
+    }
+}
+
+/// Returns the standard reason phrase for the status codes the server uses
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+/// The first line, headers, and body of an HTTP message
+type Message = (String, Vec<(String, String)>, String);
+
+/// Reads a line of the first line or headers of a message, taking what it
+/// reads off what's `left` of `MAX_HEADER`
+fn read_header_line(reader: &mut impl BufRead, left: &mut usize) -> Result<String> {
+    let mut line = String::new();
+    let read = reader.take(*left as u64).read_line(&mut line)?;
+    if read == *left && !line.ends_with('\n') {
+        bail!("The headers are too big, at over {} bytes", MAX_HEADER);
+    }
+    *left -= read;
+    Ok(line)
+}
+
+/// Reads the first line, headers, and body of an HTTP message.
+/// The body is as long as the `Content-Length` header says, or empty.
+fn read_message(reader: &mut impl BufRead) -> Result<Message> {
+    let mut left = MAX_HEADER;
+    let start = read_header_line(reader, &mut left)?;
+    if start.is_empty() {
+        bail!("The connection closed before anything was sent");
+    }
+
+    let mut headers = vec![];
+    loop {
+        let line = read_header_line(reader, &mut left)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            bail!("'{}' is not an HTTP header", line);
+        };
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let length = match headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+    {
+        Some((_, length)) => length
+            .parse::<usize>()
+            .with_context(|| format!("'{}' is not a content length", length))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        bail!("The body is too big, at {} bytes", length);
+    }
+    // Read as it comes rather than setting aside the whole length up front,
+    // so a client can't claim a big body and then not send it
+    let mut body = vec![];
+    reader.take(length as u64).read_to_end(&mut body)?;
+    if body.len() < length {
+        bail!("The connection closed before the whole body was sent");
+    }
+    let body = String::from_utf8(body).context("The body isn't UTF-8")?;
+    Ok((start.trim_end().to_string(), headers, body))
+}
+
+/// Reads from a `TcpStream` until a deadline, however slowly the other end
+/// sends, so a client can't hold on to the server by trickling in a request
+pub struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> DeadlineReader<'a> {
+    /// Reads from the stream for up to `TIMEOUT` from now
+    pub fn new(stream: &'a TcpStream) -> Self {
+        Self {
+            stream,
+            deadline: Instant::now() + TIMEOUT,
+        }
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "The request took too long to send",
+            ));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+/// Reads an HTTP request
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let (start, headers, body) = read_message(reader)?;
+    let mut parts = start.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("'{}' is not an HTTP request line", start);
+    };
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body,
+    })
+}
+
+/// Writes an HTTP response, closing the connection after it
+pub fn write_response(writer: &mut impl Write, response: &Response) -> Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads an HTTP response
+pub fn read_response(reader: &mut impl BufRead) -> Result<Response> {
+    let (start, _, body) = read_message(reader)?;
+    let status = start
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .with_context(|| format!("'{}' is not an HTTP status line", start))?;
+    Ok(Response { status, body })
+}
+
+/// Splits an `http://host:port/prefix` URL into the address to connect to
+/// and the path to put in front of every request
+fn split_url(url: &str) -> Result<(&str, &str)> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        bail!("'{}' is not a URL like http://host:7373", url);
+    };
+    let (address, prefix) = match rest.find('/') {
+        Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+        None => (rest, ""),
+    };
+    if address.is_empty() {
+        bail!("'{}' has no host in it", url);
+    }
+    Ok((address, prefix))
+}
+
+/// Sends a request to the server at the URL, waiting for its response.
+/// An `https://` URL is sent through `curl`, i.e. to `checklist serve`
+/// behind a proxy that adds HTTPS.
+pub fn send(
+    url: &str,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+    token: Option<&str>,
+) -> Result<Response> {
+    if url.starts_with("https://") {
+        let authorization = token.map(|token| format!("Bearer {}", token));
+        let mut headers = vec![];
+        if let Some(authorization) = &authorization {
+            headers.push(("Authorization", authorization.as_str()));
+        }
+        if body.is_some_and(|body| !body.is_empty()) {
+            headers.push(("Content-Type", "application/json"));
+        }
+        let url = format!("{}{}", url.trim_end_matches('/'), path);
+        return curl(method, &url, &headers, body, None);
+    }
+    let (address, prefix) = split_url(url)?;
+    let stream =
+        TcpStream::connect(address).with_context(|| format!("Failed to connect to {}", url))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut request = format!(
+        "{} {}{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        method, prefix, path, address
+    );
+    if let Some(token) = token {
+        request.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    let body = body.unwrap_or_default();
+    if !body.is_empty() {
+        request.push_str("Content-Type: application/json\r\n");
+    }
+    request.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+
+    let mut writer = &stream;
+    writer.write_all(request.as_bytes())?;
+    writer.flush()?;
+    let response = read_response(&mut BufReader::new(&stream))
+        .with_context(|| format!("Failed to read the response from {}", url))?;
+    debug!("{} {}{} answered {}", method, url, path, response.status);
+    Ok(response)
+}
+
+/// Sends a request to the URL with curl, for servers that need HTTPS. The
+/// settings go in on curl's standard input so passwords and tokens don't show
+/// up in the list of running processes. `user` is "name:password" to log in with.
+pub fn curl(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: Option<&str>,
+    user: Option<&str>,
+) -> Result<Response> {
+    let mut settings = String::new();
+    let mut set = |name: &str, value: &str| {
+        settings.push_str(&format!("{} = {}\n", name, curl_quote(value)));
+    };
+    set("url", url);
+    set("request", method);
+    if let Some(user) = user {
+        set("user", user);
+    }
+    set("max-time", &TIMEOUT.as_secs().to_string());
+    set("write-out", "\n%{http_code}");
+    for (name, value) in headers {
+        set("header", &format!("{}: {}", name, value));
+    }
+    if let Some(body) = body {
+        set("data-binary", body);
+    }
+    settings.push_str("silent\nshow-error\n");
+
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl, which needs to be installed")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(settings.as_bytes())
+            .context("Failed to hand the request to curl")?;
+    }
+    let output = child
+        .wait_with_output()
+        .context("Failed to run curl, which needs to be installed")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to reach {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let printed = String::from_utf8(output.stdout)
+        .with_context(|| format!("{} sent back something that isn't UTF-8", url))?;
+    let Some((body, status)) = printed.rsplit_once('\n') else {
+        bail!("curl didn't say what {} answered with", url);
+    };
+    let status = status
+        .trim()
+        .parse()
+        .with_context(|| format!("curl didn't say what {} answered with", url))?;
+    debug!("{} {} answered {}", method, url, status);
+    Ok(Response::new(status, body))
+}
+
+/// Quotes the value for a curl config file
+fn curl_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_messages() {
+        let raw = "PUT /tasks/42 HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer hunter2\r\nContent-Length: 13\r\n\r\n{\"name\":\"a\"}\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/tasks/42");
+        assert_eq!(request.header("Authorization"), Some("Bearer hunter2"));
+        assert_eq!(request.body, "{\"name\":\"a\"}\n");
+
+        let mut written = vec![];
+        write_response(&mut written, &Response::error(404, "No such task")).unwrap();
+        let response = read_response(&mut written.as_slice()).unwrap();
+        assert_eq!(response, Response::error(404, "No such task"));
+
+        assert_eq!(
+            split_url("http://tasks.lan:7373/checklist/").unwrap(),
+            ("tasks.lan:7373", "/checklist")
+        );
+        assert_eq!(
+            split_url("http://127.0.0.1:7373").unwrap(),
+            ("127.0.0.1:7373", "")
+        );
+        assert!(split_url("https://tasks.lan").is_err());
+        assert!(split_url("tasks.lan:7373").is_err());
+
+        let huge = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER)
+        );
+        assert!(read_request(&mut huge.as_bytes()).is_err());
+        let cut_short = "PUT /tasks/42 HTTP/1.1\r\nContent-Length: 1000\r\n\r\n{}";
+        assert!(read_request(&mut cut_short.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashSet;
+use std::mem::take;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::backend::database::{add_to_db, get_all_db_contents, get_archive_contents};
+use crate::backend::export::TASK_FIELDS;
+use crate::backend::task::{parse_criteria, ExternalRef, Status, Task, Urgency};
+
+/// Splits CSV text into records of fields. Fields can be quoted, with `""`
+/// standing for a quote, to hold commas and line breaks. Blank lines are skipped.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if quoted {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(take(&mut field));
+                records.push(take(&mut record));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if quoted {
+        bail!("The CSV ends inside a quoted field");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records.retain(|record| record.len() > 1 || !record[0].is_empty());
+    Ok(records)
+}
+
+/// Returns the field, or `None` if it was left empty
+fn optional(field: &str) -> Option<&str> {
+    if field.is_empty() {
+        None
+    } else {
+        Some(field)
+    }
+}
+
+/// Parses a timestamp written out by the export
+pub fn parse_time(field: &str) -> Result<DateTime<Local>> {
+    Ok(DateTime::parse_from_rfc3339(field)
+        .with_context(|| format!("'{}' is not an RFC3339 timestamp", field))?
+        .with_timezone(&Local))
+}
+
+/// Parses a date written out by the export
+fn parse_date(field: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(field, "%Y-%m-%d")
+        .with_context(|| format!("'{}' is not a YYYY-MM-DD date", field))
+}
+
+/// Builds a `Task` from a record of the CSV, reading each field by the column
+/// it is under. Only `name` is needed, anything left out gets what a new
+/// `Task` would have (including a new id).
+pub fn task_from_record(header: &[String], record: &[String]) -> Result<Task> {
+    let field = |column: &str| {
+        header
+            .iter()
+            .position(|name| name == column)
+            .and_then(|i| optional(&record[i]))
+    };
+    let number = |column: &str| -> Result<Option<i64>> {
+        field(column)
+            .map(|value| {
+                value
+                    .parse()
+                    .with_context(|| format!("'{}' is not a number", value))
+            })
+            .transpose()
+    };
+    let task_id = |column: &str| -> Result<Option<Uuid>> {
+        field(column)
+            .map(|id| Uuid::parse_str(id).with_context(|| format!("'{}' is not a valid id", id)))
+            .transpose()
+    };
+
+    let Some(name) = field("name") else {
+        bail!("A task needs a name");
+    };
+    let id = match field("id") {
+        Some(id) => Uuid::parse_str(id).with_context(|| format!("'{}' is not a valid id", id))?,
+        None => Uuid::new_v4(),
+    };
+    let status = match field("status") {
+        Some(status) => Status::from_str(status, true)
+            .map_err(|_| anyhow!("'{}' is not a valid status", status))?,
+        None => Status::Open,
+    };
+    let urgency = match field("urgency") {
+        Some(urgency) => Urgency::from_str(urgency, true)
+            .map_err(|_| anyhow!("'{}' is not a valid urgency", urgency))?,
+        None => Urgency::Low,
+    };
+    let tags = field("tags").map(|tags| {
+        tags.split(';')
+            .map(String::from)
+            .collect::<HashSet<String>>()
+    });
+
+    let mut task = Task::from_sql(
+        id,
+        name.to_string(),
+        field("description").map(String::from),
+        field("latest").map(String::from),
+        urgency,
+        status,
+        tags,
+        field("date_added")
+            .map(parse_time)
+            .transpose()?
+            .unwrap_or_else(Local::now),
+        field("completed_on").map(parse_time).transpose()?,
+    );
+    task.recurrence = field("recurrence").map(str::parse).transpose()?;
+    task.project = field("project").map(String::from);
+    task.time_spent = number("time_spent")?.unwrap_or_default();
+    task.timer_started = field("timer_started").map(parse_time).transpose()?;
+    task.due = field("due").map(parse_date).transpose()?;
+    task.scheduled = field("scheduled").map(parse_date).transpose()?;
+    task.external_ref = field("external_ref")
+        .map(str::parse::<ExternalRef>)
+        .transpose()?
+        .map(|external_ref| ExternalRef {
+            url: field("external_url").map(String::from),
+            ..external_ref
+        });
+    task.scheduled_start = field("scheduled_start").map(parse_time).transpose()?;
+    task.duration = number("duration")?;
+    task.locked = match field("locked") {
+        Some(locked) => locked
+            .parse()
+            .with_context(|| format!("'{}' is not true or false", locked))?,
+        None => false,
+    };
+    task.parent = task_id("parent")?;
+    task.criteria = field("criteria").map(parse_criteria).unwrap_or_default();
+    task.previous_occurrence = task_id("previous_occurrence")?;
+    Ok(task)
+}
+
+/// Reads `Task`s from CSV text in the layout `checklist export` writes out.
+/// The columns can be any of the exported fields, in any order, but
+/// there needs to be a `name` one.
+pub fn tasks_from_csv(text: &str) -> Result<Vec<Task>> {
+    let mut records = parse_csv(text)?.into_iter();
+    let Some(header) = records.next() else {
+        bail!("The CSV is empty");
+    };
+    let header: Vec<String> = header
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    for column in &header {
+        if !TASK_FIELDS.contains(&column.as_str()) {
+            bail!(
+                "Unknown column '{}', the columns that can be imported are: {}",
+                column,
+                TASK_FIELDS.join(", ")
+            );
+        }
+    }
+    if !header.iter().any(|column| column == "name") {
+        bail!("The CSV needs a 'name' column");
+    }
+
+    let mut tasks = vec![];
+    for (i, record) in records.enumerate() {
+        if record.len() != header.len() {
+            bail!(
+                "Row {} has {} fields but the header has {}",
+                i + 1,
+                record.len(),
+                header.len()
+            );
+        }
+        tasks.push(task_from_record(&header, &record).with_context(|| format!("Row {}", i + 1))?);
+    }
+    Ok(tasks)
+}
+
+/// Adds the `Task`s read from CSV text to the database, skipping any with an id
+/// the database (archive included) already has, so an import can be run again.
+/// Either all of them are added or none are.
+/// Returns how many were added and how many were skipped.
+pub fn import_tasks(conn: &Connection, text: &str) -> Result<(usize, usize)> {
+    let tasks = tasks_from_csv(text)?;
+    let existing: HashSet<Uuid> = get_all_db_contents(conn)?
+        .tasks
+        .iter()
+        .chain(get_archive_contents(conn)?.tasks.iter())
+        .map(|task| task.get_id())
+        .collect();
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start importing tasks")?;
+    let mut added = 0;
+    for task in &tasks {
+        if !existing.contains(&task.get_id()) {
+            add_to_db(&tx, task)?;
+            added += 1;
+        }
+    }
+    tx.commit().context("Failed to import tasks")?;
+    Ok((added, tasks.len() - added))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::backend::database::get_db;
+    use crate::backend::export::{tasks_csv, timelog_csv};
+    use crate::backend::task::{Criterion, Recurrence, WorkSession};
+
+    /// Small seeded random number generator (xorshift), so a failing
+    /// round trip can be reproduced
+    pub struct Random(u64);
+
+    impl Random {
+        /// Starts from the seed, spread out so small seeds don't start
+        /// off with small numbers
+        pub fn seeded(seed: u64) -> Self {
+            Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        }
+
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn chance(&mut self) -> bool {
+            self.below(2) == 0
+        }
+
+        fn pick<'a>(&mut self, options: &[&'a str]) -> &'a str {
+            options[self.below(options.len() as u64) as usize]
+        }
+
+        fn time(&mut self) -> DateTime<Local> {
+            let start = Local.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+            start
+                + Duration::seconds(self.below(5 * 365 * 24 * 3600) as i64)
+                + Duration::nanoseconds(self.below(1_000_000_000) as i64)
+        }
+
+        fn date(&mut self) -> NaiveDate {
+            self.time().date_naive()
+        }
+    }
+
+    /// Text that trips up CSV: separators, quotes, line breaks, and non-ASCII
+    const WORDS: [&str; 10] = [
+        "plain",
+        "comma, separated",
+        "\"quoted\"",
+        "two\nlines",
+        "windows\r\nline",
+        "café",
+        "日本語",
+        "🦀 emoji",
+        "  padded  ",
+        "trailing,",
+    ];
+
+    /// Returns a `Task` with every field filled in at random, with text that's
+    /// awkward to write out in any format
+    pub fn random_task(random: &mut Random) -> Task {
+        let text = |random: &mut Random| -> String {
+            (0..1 + random.below(3))
+                .map(|_| random.pick(&WORDS))
+                .collect::<Vec<&str>>()
+                .join(" ")
+        };
+
+        let urgencies = [
+            Urgency::Low,
+            Urgency::Medium,
+            Urgency::High,
+            Urgency::Critical,
+        ];
+        let statuses = [
+            Status::Open,
+            Status::Working,
+            Status::Paused,
+            Status::Completed,
+        ];
+        let status = statuses[random.below(4) as usize];
+        let mut task = Task::from_sql(
+            Uuid::new_v4(),
+            text(random),
+            random.chance().then(|| text(random)),
+            random.chance().then(|| text(random)),
+            urgencies[random.below(4) as usize],
+            status,
+            random.chance().then(|| {
+                (0..1 + random.below(3))
+                    .map(|_| {
+                        random
+                            .pick(&["work", "home", "deep work", "café", "a,b", "\"q\""])
+                            .to_string()
+                    })
+                    .collect()
+            }),
+            random.time(),
+            (status == Status::Completed).then(|| random.time()),
+        );
+        task.recurrence = match random.below(5) {
+            0 => Some(Recurrence::Daily),
+            1 => Some(Recurrence::Weekly),
+            2 => Some(Recurrence::Monthly(
+                random.chance().then(|| 1 + random.below(31) as u32),
+            )),
+            3 => Some(Recurrence::EveryNDays(1 + random.below(30) as u32)),
+            _ => None,
+        };
+        task.project = random.chance().then(|| text(random));
+        task.time_spent = random.below(100_000) as i64;
+        task.timer_started = random.chance().then(|| random.time());
+        task.due = random.chance().then(|| random.date());
+        task.scheduled = random.chance().then(|| random.date());
+        task.scheduled_start = random.chance().then(|| random.time());
+        task.duration = random.chance().then(|| 15 + random.below(120) as i64);
+        task.external_ref = random.chance().then(|| ExternalRef {
+            system: random.pick(&["github", "jira"]).to_string(),
+            id: random.below(10_000).to_string(),
+            url: random
+                .chance()
+                .then(|| String::from("https://example.com/a?b=1,c=\"2\"")),
+        });
+        task.locked = random.chance();
+        task.criteria = (0..random.below(3))
+            .map(|_| Criterion {
+                done: random.chance(),
+                ..Criterion::new(&text(random))
+            })
+            .collect();
+        task.previous_occurrence = random.chance().then(Uuid::new_v4);
+        task
+    }
+
+    fn sorted(mut tasks: Vec<Task>) -> Vec<Task> {
+        tasks.sort_by_key(|task| task.get_id());
+        tasks
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(
+            parse_csv("a,\"b, \"\"c\"\"\",\r\n\n\"d\ne\",,f\n").unwrap(),
+            vec![vec!["a", "b, \"c\"", ""], vec!["d\ne", "", "f"],]
+        );
+        assert!(parse_csv("a,\"b").is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        for seed in 1..=5u64 {
+            let mut random = Random::seeded(seed);
+            let exported = get_db(true, false).unwrap();
+            for _ in 0..40 {
+                add_to_db(&exported, &random_task(&mut random)).unwrap();
+            }
+            let csv = tasks_csv(&get_all_db_contents(&exported).unwrap().tasks, None)
+                .unwrap()
+                .join("\n");
+
+            let imported = get_db(true, false).unwrap();
+            assert_eq!(import_tasks(&imported, &csv).unwrap(), (40, 0));
+            assert_eq!(
+                sorted(get_all_db_contents(&imported).unwrap().tasks),
+                sorted(get_all_db_contents(&exported).unwrap().tasks),
+                "seed {}",
+                seed
+            );
+
+            // Importing again doesn't make duplicates
+            assert_eq!(import_tasks(&imported, &csv).unwrap(), (0, 40));
+        }
+    }
+
+    #[test]
+    fn test_partial_import() {
+        let csv = "Name,tags\n\"Buy milk, eggs\",home;errands\n";
+        let tasks = tasks_from_csv(csv).unwrap();
+        assert_eq!(tasks[0].name, "Buy milk, eggs");
+        assert_eq!(
+            tasks[0].tags,
+            Some(HashSet::from([
+                String::from("home"),
+                String::from("errands")
+            ]))
+        );
+        assert_eq!(tasks[0].status, Status::Open);
+
+        assert!(tasks_from_csv("name,priority\nA,1\n")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unknown column 'priority'"));
+        assert!(tasks_from_csv("status\nOpen\n").is_err());
+        assert!(tasks_from_csv("name,status\nA\n").is_err());
+        assert!(tasks_from_csv("name,status\nA,Sleeping\n").is_err());
+    }
+
+    #[test]
+    fn test_timelog_round_trip() {
+        // The time log isn't made of tasks, so check its fields read back as written
+        let mut random = Random(42);
+        let tasks: Vec<Task> = (0..10).map(|_| random_task(&mut random)).collect();
+        let sessions: Vec<WorkSession> = tasks
+            .iter()
+            .map(|task| {
+                let started = random.time();
+                WorkSession {
+                    task_id: task.get_id(),
+                    started,
+                    ended: started + Duration::minutes(random.below(600) as i64),
+                }
+            })
+            .collect();
+
+        let csv = timelog_csv(&tasks, &sessions, None).unwrap().join("\n");
+        let records = parse_csv(&csv).unwrap();
+        assert_eq!(records.len(), sessions.len() + 1);
+        for ((record, session), task) in records[1..].iter().zip(&sessions).zip(&tasks) {
+            assert_eq!(
+                parse_time(&record[0]).unwrap().timestamp(),
+                session.started.timestamp()
+            );
+            assert_eq!(
+                parse_time(&record[1]).unwrap().timestamp(),
+                session.ended.timestamp()
+            );
+            assert_eq!(record[3], task.name);
+            assert_eq!(record[4], task.project.clone().unwrap_or_default());
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+
+use crate::backend::config::get_config_dir;
+
+/// Folder in the config directory the log files are written to
+const LOG_DIR: &str = "logs";
+
+/// How many days of log files are kept before the oldest is deleted
+const LOG_FILES_KEPT: usize = 7;
+
+/// Returns the folder the log files are written to
+pub fn get_log_dir() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join(LOG_DIR))
+}
+
+/// Returns the most detailed level that's logged for the number of times
+/// `-v` was passed in: warnings and errors by default, then info, debug,
+/// and trace
+pub fn log_level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Starts writing log messages to a file in the logs folder of the config
+/// directory, i.e. checklist.2025-03-03.log, starting a new one each day.
+/// Nothing is logged to the terminal, so the TUI is left alone.
+pub fn init_logging(verbosity: u8) -> Result<()> {
+    let log_dir = get_log_dir()?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("checklist")
+        .filename_suffix("log")
+        .max_log_files(LOG_FILES_KEPT)
+        .build(&log_dir)
+        .with_context(|| format!("Failed to start a log file in {:?}", log_dir))?;
+    tracing_subscriber::fmt()
+        .with_writer(appender)
+        .with_ansi(false)
+        .with_max_level(log_level(verbosity))
+        .try_init()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("Failed to start logging")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level() {
+        assert_eq!(log_level(0), LevelFilter::WARN);
+        assert_eq!(log_level(2), LevelFilter::DEBUG);
+        assert_eq!(log_level(5), LevelFilter::TRACE);
+    }
+}
@@ -1,4 +1,42 @@
+pub mod archive;
+pub mod backup;
+pub mod caldav;
+pub mod calendar;
+pub mod cli;
+pub mod clipboard;
+pub mod collate;
 pub mod config;
 pub mod database;
+pub mod dependencies;
+pub mod doctor;
+pub mod editor;
+pub mod encryption;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod flat_file;
+pub mod fuzzy;
+pub mod handoff;
+pub mod history;
+pub mod hook;
+pub mod http;
+pub mod import;
+pub mod log;
+pub mod notify;
+pub mod open;
+pub mod plan;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod qr;
+pub mod query;
+pub mod report;
+pub mod snapshot;
+pub mod store;
+pub mod sync;
+pub mod tags;
 pub mod task;
+pub mod taskwarrior;
+pub mod terminal;
+pub mod ticket;
+pub mod todoist;
 pub mod wipe;
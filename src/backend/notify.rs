@@ -0,0 +1,507 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeDelta};
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::backend::calendar::{calendar, today};
+use crate::backend::database::{
+    clear_held_notifications, get_all_db_contents, get_held_notifications, get_snoozed_reminders,
+    hold_notifications,
+};
+use crate::backend::task::{Status, Task};
+
+/// How many task names to fit into a single notification before
+/// summarizing the rest as "and N more"
+const MAX_NAMES: usize = 5;
+
+/// Struct that holds a notification ready to be sent
+#[derive(Debug, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+}
+
+/// A window of the day, like 22:00-07:00, during which notifications and
+/// reminders are held back, to be delivered as a digest once it ends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl FromStr for QuietHours {
+    type Err = anyhow::Error;
+
+    /// Parses a window like "22:00-07:00", which can run past midnight
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            format!(
+                "'{}' is not a valid quiet hours window, use HH:MM-HH:MM like 22:00-07:00",
+                s
+            )
+        };
+        let (start, end) = s.split_once('-').with_context(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").with_context(invalid)?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").with_context(invalid)?;
+        if start == end {
+            bail!("Quiet hours can't start and end at the same time");
+        }
+        Ok(QuietHours { start, end })
+    }
+}
+
+impl QuietHours {
+    /// Returns true if the `time` of day falls in the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Returns true if `now` falls in the window, going by the configured timezone
+    pub fn is_quiet(&self, now: &DateTime<Local>) -> bool {
+        self.contains(calendar().time_of(now))
+    }
+}
+
+/// Rolls the notifications held back during quiet hours into one,
+/// with a line for each, i.e. "2 tasks overdue: Taxes, Rent"
+pub fn digest(held: &[Notification]) -> Notification {
+    let lines: Vec<String> = held
+        .iter()
+        .map(|notification| {
+            let summary = notification.summary.as_str();
+            let names: Vec<&str> = notification.body.lines().collect();
+            format!(
+                "{}: {}",
+                summary.strip_prefix("checklist: ").unwrap_or(summary),
+                names.join(", ")
+            )
+        })
+        .collect();
+    Notification {
+        summary: String::from("checklist: while it was quiet"),
+        body: lines.join("\n"),
+    }
+}
+
+/// Joins the names of the `Task`s into a notification body
+fn names_body(tasks: &[&Task]) -> String {
+    let mut lines: Vec<String> = tasks
+        .iter()
+        .take(MAX_NAMES)
+        .map(|task| task.name.clone())
+        .collect();
+    if tasks.len() > MAX_NAMES {
+        lines.push(format!("and {} more", tasks.len() - MAX_NAMES));
+    }
+    lines.join("\n")
+}
+
+/// Builds the notifications for `Task`s that are overdue, or due
+/// within `days` of `today`. At most one notification is made for each.
+pub fn due_notifications(tasks: &[Task], today: NaiveDate, days: u64) -> Vec<Notification> {
+    let overdue: Vec<&Task> = tasks.iter().filter(|task| task.is_overdue(today)).collect();
+    let due_soon: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.is_due_within(today, days))
+        .collect();
+
+    let mut notifications = vec![];
+    if !overdue.is_empty() {
+        notifications.push(Notification {
+            summary: format!("checklist: {} overdue", plural_tasks(overdue.len())),
+            body: names_body(&overdue),
+        });
+    }
+    if !due_soon.is_empty() {
+        notifications.push(Notification {
+            summary: format!("checklist: {} due soon", plural_tasks(due_soon.len())),
+            body: names_body(&due_soon),
+        });
+    }
+    notifications
+}
+
+/// What a `Reminder` is about
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReminderKind {
+    Overdue,
+    DueSoon,
+    /// The `Task` has been `Working` a while without an update
+    StillWorking,
+}
+
+/// A `Task` that's overdue, due soon, or has gone quiet, to remind the user about
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reminder {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: ReminderKind,
+}
+
+impl Reminder {
+    /// Describes what the reminder is about, i.e. "'Taxes' is overdue"
+    pub fn message(&self) -> String {
+        match self.kind {
+            ReminderKind::Overdue => format!("'{}' is overdue", self.name),
+            ReminderKind::DueSoon => format!("'{}' is due soon", self.name),
+            ReminderKind::StillWorking => {
+                format!("Still working on '{}'? Add an update", self.name)
+            }
+        }
+    }
+}
+
+/// Returns a `Reminder` for each `Task` that is overdue, or due within `days`
+/// of `today`, leaving out the ones whose reminders are `snoozed`. Overdue ones come first.
+pub fn due_reminders(
+    tasks: &[Task],
+    today: NaiveDate,
+    days: u64,
+    snoozed: &HashSet<Uuid>,
+) -> Vec<Reminder> {
+    let reminder = |task: &Task| Reminder {
+        id: task.get_id(),
+        name: task.name.clone(),
+        kind: if task.is_overdue(today) {
+            ReminderKind::Overdue
+        } else {
+            ReminderKind::DueSoon
+        },
+    };
+    let mut reminders: Vec<Reminder> = tasks
+        .iter()
+        .filter(|task| !snoozed.contains(&task.get_id()))
+        .filter(|task| task.is_overdue(today) || task.is_due_within(today, days))
+        .map(reminder)
+        .collect();
+    reminders.sort_by_key(|reminder| reminder.kind != ReminderKind::Overdue);
+    reminders
+}
+
+/// Returns a `Reminder` for each `Working` `Task` that went `after` or longer
+/// without its status or latest update changing, going by when each was
+/// `last_touched`. Tasks with nothing logged, or whose reminders are
+/// `snoozed`, are left out.
+pub fn still_working_reminders(
+    tasks: &[Task],
+    last_touched: &HashMap<Uuid, DateTime<Local>>,
+    now: &DateTime<Local>,
+    after: TimeDelta,
+    snoozed: &HashSet<Uuid>,
+) -> Vec<Reminder> {
+    tasks
+        .iter()
+        .filter(|task| task.status == Status::Working && !snoozed.contains(&task.get_id()))
+        .filter(|task| {
+            last_touched
+                .get(&task.get_id())
+                .is_some_and(|touched| *now - *touched >= after)
+        })
+        .map(|task| Reminder {
+            id: task.get_id(),
+            name: task.name.clone(),
+            kind: ReminderKind::StillWorking,
+        })
+        .collect()
+}
+
+/// How long reminders about a `Task` can be snoozed for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnoozeFor {
+    TenMinutes,
+    Hour,
+    /// Until the start of tomorrow
+    Tomorrow,
+}
+
+impl SnoozeFor {
+    pub const ALL: [SnoozeFor; 3] = [SnoozeFor::TenMinutes, SnoozeFor::Hour, SnoozeFor::Tomorrow];
+
+    /// The key that snoozes a reminder for this long
+    pub fn key(self) -> char {
+        match self {
+            SnoozeFor::TenMinutes => '1',
+            SnoozeFor::Hour => '2',
+            SnoozeFor::Tomorrow => '3',
+        }
+    }
+
+    /// Returns how long the key pressed snoozes a reminder for, if it's one of them
+    pub fn from_key(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|snooze| snooze.key() == key)
+    }
+
+    /// How it's shown, i.e. "10m"
+    pub fn label(self) -> &'static str {
+        match self {
+            SnoozeFor::TenMinutes => "10m",
+            SnoozeFor::Hour => "1h",
+            SnoozeFor::Tomorrow => "tomorrow",
+        }
+    }
+
+    /// Returns when a reminder snoozed at `now` comes back
+    pub fn until(self, now: DateTime<Local>) -> DateTime<Local> {
+        match self {
+            SnoozeFor::TenMinutes => now + TimeDelta::minutes(10),
+            SnoozeFor::Hour => now + TimeDelta::hours(1),
+            SnoozeFor::Tomorrow => {
+                let calendar = calendar();
+                let (_, tomorrow) = calendar.day_bounds(calendar.date_of(&now));
+                tomorrow.with_timezone(&Local)
+            }
+        }
+    }
+}
+
+fn plural_tasks(count: usize) -> String {
+    if count == 1 {
+        String::from("1 task")
+    } else {
+        format!("{} tasks", count)
+    }
+}
+
+/// Sends a desktop notification through the notifier the OS provides:
+/// the notification server over D-Bus on Linux/BSD, and Notification
+/// Center on macOS
+pub fn send_notification(notification: &Notification) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("checklist")
+        .summary(&notification.summary)
+        .body(&notification.body)
+        .show()
+        .context("Failed to send the desktop notification")?;
+    Ok(())
+}
+
+/// What came of checking for `Task`s to notify about
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    /// The notifications that were sent
+    Sent(Vec<Notification>),
+    /// How many notifications were held back for being in quiet hours
+    Held(usize),
+}
+
+/// Sends desktop notifications for the `Task`s in the database that are
+/// overdue or due within `days`, unless their reminders are snoozed.
+/// During `quiet` hours they are held back instead, and the first time this
+/// is called after the window ends, what was held is sent as a digest.
+pub fn notify_due_tasks(
+    conn: &Connection,
+    days: u64,
+    quiet: Option<QuietHours>,
+) -> Result<Delivery> {
+    let now = Local::now();
+    let snoozed = get_snoozed_reminders(conn, &now)?;
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.retain(|task| !snoozed.contains(&task.get_id()));
+    let mut notifications = due_notifications(&tasks, today(), days);
+
+    if quiet.is_some_and(|quiet| quiet.is_quiet(&now)) {
+        // Only the latest matter, since they're worked out fresh each time
+        hold_notifications(conn, &notifications)?;
+        return Ok(Delivery::Held(notifications.len()));
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start sending notifications")?;
+    let held = get_held_notifications(&tx)?;
+    if !held.is_empty() {
+        // The digest already covers whatever is still the same
+        notifications.retain(|notification| !held.contains(notification));
+        notifications.insert(0, digest(&held));
+    }
+    for notification in &notifications {
+        send_notification(notification)?;
+    }
+    // Only once everything is sent is what was held done with, so if a
+    // notification can't be sent the digest is tried again next time
+    clear_held_notifications(&tx)?;
+    tx.commit()
+        .context("Failed to clear the held notifications")?;
+    Ok(Delivery::Sent(notifications))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_notifications() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let mut tasks = vec![];
+        for (name, due) in [
+            ("Taxes", NaiveDate::from_ymd_opt(2024, 3, 1)),
+            ("Dentist", NaiveDate::from_ymd_opt(2024, 3, 11)),
+            ("Holiday", NaiveDate::from_ymd_opt(2024, 6, 1)),
+            ("Someday", None),
+        ] {
+            let mut task = Task::new(String::from(name), None, None, None, None, None);
+            task.due = due;
+            tasks.push(task);
+        }
+
+        assert_eq!(
+            due_notifications(&tasks, today, 1),
+            vec![
+                Notification {
+                    summary: String::from("checklist: 1 task overdue"),
+                    body: String::from("Taxes"),
+                },
+                Notification {
+                    summary: String::from("checklist: 1 task due soon"),
+                    body: String::from("Dentist"),
+                },
+            ]
+        );
+        assert_eq!(due_notifications(&tasks[2..], today, 1), vec![]);
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let mut tasks = vec![];
+        for (name, due) in [
+            ("Dentist", NaiveDate::from_ymd_opt(2024, 3, 11)),
+            ("Taxes", NaiveDate::from_ymd_opt(2024, 3, 1)),
+            ("Rent", NaiveDate::from_ymd_opt(2024, 3, 9)),
+            ("Holiday", NaiveDate::from_ymd_opt(2024, 6, 1)),
+        ] {
+            let mut task = Task::new(String::from(name), None, None, None, None, None);
+            task.due = due;
+            tasks.push(task);
+        }
+
+        let reminders = due_reminders(&tasks, today, 1, &HashSet::new());
+        let messages: Vec<String> = reminders.iter().map(Reminder::message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "'Taxes' is overdue",
+                "'Rent' is overdue",
+                "'Dentist' is due soon"
+            ]
+        );
+
+        let snoozed = HashSet::from([tasks[1].get_id()]);
+        let reminders = due_reminders(&tasks, today, 1, &snoozed);
+        assert_eq!(reminders.len(), 2);
+        assert_eq!(reminders[0].name, "Rent");
+    }
+
+    #[test]
+    fn test_still_working_reminders() {
+        let now = Local::now();
+        let mut tasks = vec![];
+        for name in ["Report", "Slides", "Emails", "Filing"] {
+            tasks.push(Task::new(
+                String::from(name),
+                None,
+                None,
+                None,
+                Some(Status::Working),
+                None,
+            ));
+        }
+        tasks[3].status = Status::Paused;
+        let last_touched = HashMap::from([
+            (tasks[0].get_id(), now - TimeDelta::hours(5)),
+            (tasks[1].get_id(), now - TimeDelta::hours(1)),
+            (tasks[3].get_id(), now - TimeDelta::hours(5)),
+        ]);
+
+        let reminders = still_working_reminders(
+            &tasks,
+            &last_touched,
+            &now,
+            TimeDelta::hours(4),
+            &HashSet::new(),
+        );
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(
+            reminders[0].message(),
+            "Still working on 'Report'? Add an update"
+        );
+
+        let snoozed = HashSet::from([tasks[0].get_id()]);
+        let reminders =
+            still_working_reminders(&tasks, &last_touched, &now, TimeDelta::hours(4), &snoozed);
+        assert!(reminders.is_empty());
+    }
+
+    #[test]
+    fn test_snooze_for() {
+        let now = Local::now();
+        assert_eq!(SnoozeFor::from_key('2'), Some(SnoozeFor::Hour));
+        assert_eq!(SnoozeFor::from_key('4'), None);
+        assert_eq!(
+            SnoozeFor::TenMinutes.until(now),
+            now + TimeDelta::minutes(10)
+        );
+
+        let tomorrow = SnoozeFor::Tomorrow.until(now);
+        assert!(tomorrow > now);
+        assert_eq!(calendar().date_of(&tomorrow), today().succ_opt().unwrap());
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let time = |h: u32, m: u32| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let overnight: QuietHours = "22:00-07:00".parse().unwrap();
+        assert!(overnight.contains(time(23, 30)));
+        assert!(overnight.contains(time(6, 59)));
+        assert!(!overnight.contains(time(7, 0)));
+        assert!(!overnight.contains(time(12, 0)));
+
+        let lunch: QuietHours = " 12:00 - 13:00 ".parse().unwrap();
+        assert!(lunch.contains(time(12, 0)));
+        assert!(!lunch.contains(time(13, 0)));
+
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("10pm-7am".parse::<QuietHours>().is_err());
+        assert!("09:00-09:00".parse::<QuietHours>().is_err());
+    }
+
+    #[test]
+    fn test_digest() {
+        let held = vec![
+            Notification {
+                summary: String::from("checklist: 2 tasks overdue"),
+                body: String::from("Taxes\nRent"),
+            },
+            Notification {
+                summary: String::from("checklist: 1 task due soon"),
+                body: String::from("Dentist"),
+            },
+        ];
+        assert_eq!(
+            digest(&held),
+            Notification {
+                summary: String::from("checklist: while it was quiet"),
+                body: String::from("2 tasks overdue: Taxes, Rent\n1 task due soon: Dentist"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_names_body() {
+        let tasks: Vec<Task> = (1..=7)
+            .map(|i| Task::new(format!("Task{}", i), None, None, None, None, None))
+            .collect();
+        let task_refs: Vec<&Task> = tasks.iter().collect();
+        assert_eq!(
+            names_body(&task_refs),
+            "Task1\nTask2\nTask3\nTask4\nTask5\nand 2 more"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+/// Opens the URI with whatever the OS has set to handle it, i.e. a
+/// `mailto:` link in the mail client or a web page in the browser.
+/// Uses `xdg-open` on Linux/BSD, `open` on macOS, and `start` on Windows.
+pub fn open_uri(uri: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(uri);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]).arg(uri);
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("xdg-open");
+        command.arg(uri);
+        command
+    } else {
+        bail!("Opening links isn't supported on this platform yet");
+    };
+
+    let output = command.output().context("Failed to open the link")?;
+    if !output.status.success() {
+        bail!("Opening the link exited with {}", output.status);
+    }
+    Ok(())
+}
+
+/// Returns a `Command` running `command` through the shell (`sh -c`,
+/// or `cmd /C` on Windows)
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Runs the `command` the user configured through the shell, with the
+/// environment variables given and `input` written to its standard input.
+/// `what` names the command in errors, i.e. "hand off command".
+pub fn run_shell(command: &str, env: &[(&str, &str)], input: &str, what: &str) -> Result<()> {
+    let mut child = shell(command)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run the {}", what))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that doesn't read its input closes it early, which is fine
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to run the {}", what))?;
+    if !status.success() {
+        bail!("The {} exited with {}", what, status);
+    }
+    Ok(())
+}
+
+/// Runs the `command` the user configured through the shell, returning what
+/// it printed with the trailing newline taken off. `what` names the command
+/// in errors, i.e. "passphrase command".
+pub fn shell_output(command: &str, what: &str) -> Result<String> {
+    let output = shell(command)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run the {}", what))?;
+    if !output.status.success() {
+        bail!("The {} exited with {}", what, output.status);
+    }
+    let printed = String::from_utf8(output.stdout)
+        .with_context(|| format!("The {} printed something that isn't UTF-8", what))?;
+    Ok(printed.trim_end_matches(['\r', '\n']).to_string())
+}
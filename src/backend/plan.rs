@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{bail, Result};
+use chrono::{Days, NaiveDate};
+use uuid::Uuid;
+
+use crate::backend::calendar::calendar;
+use crate::backend::task::{parse_slot_minutes, Status, Task, DEFAULT_SLOT_MINUTES};
+
+/// How much time a day has for tasks when `daily_capacity` isn't set
+pub const DEFAULT_CAPACITY: &str = "6h";
+
+/// Parses how much time a day has for tasks, i.e. "6h", "4h30m", or "300" minutes
+pub fn parse_capacity(input: &str) -> Result<i64> {
+    match parse_slot_minutes(&input.trim().to_lowercase()) {
+        Some(minutes) if minutes > 0 => Ok(minutes),
+        _ => bail!(
+            "'{}' is not a valid daily capacity, use hours and minutes, i.e. 6h or 4h30m",
+            input.trim()
+        ),
+    }
+}
+
+/// A `Task` the plan puts on a day
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    pub id: Uuid,
+    pub name: String,
+    pub day: NaiveDate,
+    /// Minutes it's estimated to take
+    pub minutes: i64,
+    /// Whether it had no estimate, so `DEFAULT_SLOT_MINUTES` was used
+    pub guessed: bool,
+    /// The due date it lands after, if it couldn't fit in before it
+    pub late: Option<NaiveDate>,
+}
+
+/// Which days to do the open `Task`s on, filling each day up to a capacity
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    pub suggestions: Vec<Suggestion>,
+    /// Minutes on each day already taken by tasks scheduled or slotted there
+    pub booked: BTreeMap<NaiveDate, i64>,
+    /// Names and estimates of the tasks that didn't fit on any day
+    pub unplanned: Vec<(String, i64)>,
+}
+
+impl Plan {
+    /// Returns the minutes planned on the day, booked tasks included
+    pub fn minutes_on(&self, day: NaiveDate) -> i64 {
+        self.booked.get(&day).copied().unwrap_or(0)
+            + self
+                .suggestions
+                .iter()
+                .filter(|suggestion| suggestion.day == day)
+                .map(|suggestion| suggestion.minutes)
+                .sum::<i64>()
+    }
+}
+
+/// Plans the open `Task`s over the `days` starting `today`, so no day has more
+/// than `capacity` minutes on it. Tasks due soonest go first, then the most
+/// urgent, each on the earliest day with room that's still before it's due
+/// (or the earliest with room at all, if none are). Tasks already scheduled or
+/// given a time slot in those days stay put and take up room, and ones
+/// scheduled after them are left alone. Estimates come from the time slot
+/// duration, and tasks with open subtasks are planned through their subtasks.
+pub fn plan_days(tasks: &[Task], today: NaiveDate, days: u64, capacity: i64) -> Plan {
+    let window: Vec<NaiveDate> = (0..days)
+        .filter_map(|day| today.checked_add_days(Days::new(day)))
+        .collect();
+    let Some(&last_day) = window.last() else {
+        return Plan::default();
+    };
+    let open_parents: HashSet<Uuid> = tasks
+        .iter()
+        .filter(|task| task.status != Status::Completed)
+        .filter_map(|task| task.parent)
+        .collect();
+
+    let mut plan = Plan::default();
+    let mut candidates = vec![];
+    for task in tasks {
+        if task.status == Status::Completed || open_parents.contains(&task.get_id()) {
+            continue;
+        }
+        let minutes = task.duration.unwrap_or(DEFAULT_SLOT_MINUTES);
+        let slot_day = task.scheduled_start.map(|start| calendar().date_of(&start));
+        match (slot_day, task.scheduled) {
+            (Some(day), _) if today <= day => {
+                if day <= last_day {
+                    *plan.booked.entry(day).or_insert(0) += minutes;
+                }
+            }
+            (_, Some(scheduled)) if today < scheduled => {
+                if scheduled <= last_day {
+                    *plan.booked.entry(scheduled).or_insert(0) += minutes;
+                }
+            }
+            _ if task.locked => {}
+            _ => candidates.push(task),
+        }
+    }
+
+    // Soonest due first, with no due date last, then the most urgent
+    candidates.sort_by(|a, b| {
+        (a.due.is_none(), a.due, b.urgency, a.date_added).cmp(&(
+            b.due.is_none(),
+            b.due,
+            a.urgency,
+            b.date_added,
+        ))
+    });
+    for task in candidates {
+        let minutes = task.duration.unwrap_or(DEFAULT_SLOT_MINUTES);
+        let has_room = |day: &&NaiveDate| plan.minutes_on(**day) + minutes <= capacity;
+        let in_time = window
+            .iter()
+            .filter(|day| task.due.is_none_or(|due| **day <= due))
+            .find(has_room);
+        let day = match in_time {
+            Some(day) => Some((*day, None)),
+            None => window.iter().find(has_room).map(|day| (*day, task.due)),
+        };
+        match day {
+            Some((day, late)) => plan.suggestions.push(Suggestion {
+                id: task.get_id(),
+                name: task.name.clone(),
+                day,
+                minutes,
+                guessed: task.duration.is_none(),
+                late,
+            }),
+            None => plan.unplanned.push((task.name.clone(), minutes)),
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::task::Urgency;
+
+    fn task(name: &str, minutes: Option<i64>, due: Option<NaiveDate>, urgency: Urgency) -> Task {
+        let mut task = Task::new(String::from(name), None, None, Some(urgency), None, None);
+        task.duration = minutes;
+        task.due = due;
+        task
+    }
+
+    #[test]
+    fn test_plan_days() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let day = |n: u64| today.checked_add_days(Days::new(n)).unwrap();
+
+        let report = task("Report", Some(180), Some(day(1)), Urgency::Low);
+        let slides = task("Slides", Some(180), Some(day(1)), Urgency::High);
+        let taxes = task("Taxes", Some(240), Some(day(0)), Urgency::Medium);
+        let email = task("Email", None, None, Urgency::Critical);
+        let move_house = task("Move house", Some(600), None, Urgency::High);
+        let mut booked = task("Dentist", Some(120), None, Urgency::Low);
+        booked.scheduled = Some(day(1));
+        let mut later = task("Later", Some(60), None, Urgency::Low);
+        later.scheduled = Some(day(10));
+        let mut done = task("Done", Some(60), None, Urgency::Low);
+        done.status = Status::Completed;
+        let parent = task("Parent", Some(60), None, Urgency::Low);
+        let mut child = task("Child", Some(60), None, Urgency::Low);
+        child.parent = Some(parent.get_id());
+
+        let tasks = vec![
+            report, slides, taxes, email, move_house, booked, later, done, parent, child,
+        ];
+        let plan = plan_days(&tasks, today, 3, 360);
+        let planned: Vec<(&str, NaiveDate, Option<NaiveDate>)> = plan
+            .suggestions
+            .iter()
+            .map(|suggestion| (suggestion.name.as_str(), suggestion.day, suggestion.late))
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                ("Taxes", day(0), None),
+                // Slides is more urgent, so it gets the room left before they're due
+                ("Slides", day(1), None),
+                ("Report", day(2), Some(day(1))),
+                ("Email", day(0), None),
+                ("Child", day(0), None),
+            ]
+        );
+        assert!(plan.suggestions[3].guessed);
+        assert_eq!(plan.booked, BTreeMap::from([(day(1), 120)]));
+        assert_eq!(plan.minutes_on(day(1)), 300);
+        assert_eq!(plan.unplanned, vec![(String::from("Move house"), 600)]);
+
+        assert_eq!(plan_days(&tasks, today, 0, 360), Plan::default());
+
+        assert_eq!(parse_capacity("6h").unwrap(), 360);
+        assert_eq!(parse_capacity(" 4H30m ").unwrap(), 270);
+        assert!(parse_capacity("0").is_err());
+        assert!(parse_capacity("all day").is_err());
+    }
+}
@@ -0,0 +1,466 @@
+use anyhow::{bail, Result};
+
+/// Codewords for each version at error correction level M: the error correction
+/// codewords per block, followed by the number of blocks and data codewords per
+/// block in each of the (up to two) groups
+const VERSIONS: [(usize, [(usize, usize); 2]); 10] = [
+    (10, [(1, 16), (0, 0)]),
+    (16, [(1, 28), (0, 0)]),
+    (26, [(1, 44), (0, 0)]),
+    (18, [(2, 32), (0, 0)]),
+    (24, [(2, 43), (0, 0)]),
+    (16, [(4, 27), (0, 0)]),
+    (18, [(4, 31), (0, 0)]),
+    (22, [(2, 38), (2, 39)]),
+    (22, [(3, 36), (2, 37)]),
+    (26, [(4, 43), (1, 44)]),
+];
+
+/// Where the alignment patterns go, along both axes, for each version
+const ALIGNMENT_POSITIONS: [&[usize]; 10] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+
+/// Modules of light border to leave around the code
+const QUIET_ZONE: usize = 4;
+
+/// A QR code, encoded in byte mode with medium error correction
+#[derive(Debug)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<Vec<bool>>,
+    is_function: Vec<Vec<bool>>,
+}
+
+impl QrCode {
+    /// Encodes the text in the smallest version that fits it, up to version 10
+    /// (a little over 200 bytes)
+    pub fn encode(text: &str) -> Result<Self> {
+        let data = text.as_bytes();
+        let Some(version) = (1..=VERSIONS.len()).find(|version| {
+            let count_bits = if *version < 10 { 8 } else { 16 };
+            4 + count_bits + data.len() * 8 <= data_capacity(*version) * 8
+        }) else {
+            bail!(
+                "Too much text for a QR code ({} bytes, at most {} fit)",
+                data.len(),
+                data_capacity(VERSIONS.len()) - 3
+            );
+        };
+
+        let size = 17 + 4 * version;
+        let mut qr = QrCode {
+            size,
+            modules: vec![vec![false; size]; size],
+            is_function: vec![vec![false; size]; size],
+        };
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&add_error_correction(
+            &data_codewords(data, version),
+            version,
+        ));
+
+        // Go with whichever mask leaves the fewest confusing patterns
+        let mask = (0..8)
+            .min_by_key(|mask| {
+                qr.apply_mask(*mask);
+                qr.draw_format_bits(*mask);
+                let penalty = qr.penalty();
+                qr.apply_mask(*mask);
+                penalty
+            })
+            .unwrap_or(0);
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+        Ok(qr)
+    }
+
+    /// Returns the QR code drawn with block characters, two rows of modules per
+    /// line. Light modules are drawn filled in, to suit light text on a dark terminal.
+    pub fn to_lines(&self) -> Vec<String> {
+        let full = self.size + 2 * QUIET_ZONE;
+        let is_light = |y: usize, x: usize| {
+            if y < QUIET_ZONE || x < QUIET_ZONE {
+                return true;
+            }
+            let (y, x) = (y - QUIET_ZONE, x - QUIET_ZONE);
+            y >= self.size || x >= self.size || !self.modules[y][x]
+        };
+
+        (0..full)
+            .step_by(2)
+            .map(|y| {
+                (0..full)
+                    .map(
+                        |x| match (is_light(y, x), y + 1 < full && is_light(y + 1, x)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                    )
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y][x] = dark;
+        self.is_function[y][x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        // Timing patterns
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns, along with their separators
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+
+        // Alignment patterns, skipping the corners the finder patterns are in
+        let positions = ALIGNMENT_POSITIONS[version - 1];
+        let last = positions.len().saturating_sub(1);
+        for (i, cx) in positions.iter().enumerate() {
+            for (j, cy) in positions.iter().enumerate() {
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let (x, y) = ((*cx as i32 + dx) as usize, (*cy as i32 + dy) as usize);
+                        self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format bits, they get drawn for real once the mask is known
+        self.draw_format_bits(0);
+
+        if version >= 7 {
+            let mut remainder = version;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = (version << 12) | remainder;
+            for i in 0..18 {
+                let dark = (bits >> i) & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: usize) {
+        let size = self.size;
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        // Around the top left finder pattern
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Split between the other two
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Fills in the codewords, zigzagging up and down two columns at a time from the right
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.is_function[y][x] && i < codewords.len() * 8 {
+                        self.modules[y][x] = (codewords[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Flips the data modules the mask covers. Applying it twice undoes it.
+    fn apply_mask(&mut self, mask: usize) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if flip && !self.is_function[y][x] {
+                    self.modules[y][x] = !self.modules[y][x];
+                }
+            }
+        }
+    }
+
+    /// Scores how hard the code is to read, from long runs of one colour,
+    /// 2x2 blocks of one colour, and how far it is from half dark
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+
+        for line in 0..size {
+            for horizontal in [true, false] {
+                let mut run = 0;
+                let mut previous = None;
+                for i in 0..size {
+                    let dark = if horizontal {
+                        self.modules[line][i]
+                    } else {
+                        self.modules[i][line]
+                    };
+                    if previous == Some(dark) {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run = 1;
+                        previous = Some(dark);
+                    }
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.modules[y][x];
+                if dark == self.modules[y][x + 1]
+                    && dark == self.modules[y + 1][x]
+                    && dark == self.modules[y + 1][x + 1]
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().flatten().filter(|dark| **dark).count();
+        let total = size * size;
+        let percent_off = (dark * 100).abs_diff(total * 50) / total;
+        penalty + percent_off / 5 * 10
+    }
+}
+
+/// How many data codewords the version holds
+fn data_capacity(version: usize) -> usize {
+    let (_, groups) = VERSIONS[version - 1];
+    groups.iter().map(|(blocks, data)| blocks * data).sum()
+}
+
+/// Returns the 15 format bits for error correction level M and the mask
+fn format_bits(mask: usize) -> usize {
+    // Level M is 00
+    let data = mask;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    ((data << 10) | remainder) ^ 0x5412
+}
+
+/// Packs the bytes into data codewords: the byte mode indicator, the length,
+/// the bytes themselves, then padding up to the version's capacity
+fn data_codewords(data: &[u8], version: usize) -> Vec<u8> {
+    let mut bits: Vec<bool> = vec![];
+    let mut push = |value: usize, length: usize| {
+        for i in (0..length).rev() {
+            bits.push((value >> i) & 1 == 1);
+        }
+    };
+    push(0b0100, 4);
+    push(data.len(), if version < 10 { 8 } else { 16 });
+    for byte in data {
+        push(*byte as usize, 8);
+    }
+
+    let capacity = data_capacity(version) * 8;
+    let terminator = (capacity - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    while !bits.len().is_multiple_of(8) {
+        bits.push(false);
+    }
+
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= capacity / 8 {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Splits the data codewords into blocks, works out the error correction for
+/// each, then interleaves them all together
+fn add_error_correction(data: &[u8], version: usize) -> Vec<u8> {
+    let (ec_length, groups) = VERSIONS[version - 1];
+    let divisor = reed_solomon_divisor(ec_length);
+
+    let mut blocks: Vec<(&[u8], Vec<u8>)> = vec![];
+    let mut start = 0;
+    for (count, length) in groups {
+        for _ in 0..count {
+            let block = &data[start..start + length];
+            blocks.push((block, reed_solomon_remainder(block, &divisor)));
+            start += length;
+        }
+    }
+
+    let mut result = vec![];
+    let longest = blocks
+        .iter()
+        .map(|(block, _)| block.len())
+        .max()
+        .unwrap_or(0);
+    for i in 0..longest {
+        result.extend(blocks.iter().filter_map(|(block, _)| block.get(i)));
+    }
+    for i in 0..ec_length {
+        result.extend(blocks.iter().map(|(_, ec)| ec[i]));
+    }
+    result
+}
+
+/// Multiplies two numbers in GF(2^8), modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    z as u8
+}
+
+/// Returns the generator polynomial of the degree, highest power first,
+/// leaving out its leading coefficient of 1
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree - 1];
+    result.push(1);
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+/// Returns the error correction codewords for the data
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (value, coefficient) in result.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_correction() {
+        // "HELLO WORLD" in alphanumeric mode at 1-M, as worked through by Thonky's QR tutorial
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(5), 0b100000011001110);
+        assert_eq!(format_bits(7), 0b100101010100000);
+    }
+
+    #[test]
+    fn test_encode() {
+        let qr = QrCode::encode("Call mum").unwrap();
+        assert_eq!(qr.size, 21);
+        // The corners of the finder patterns are dark, their separators light
+        assert!(qr.modules[0][0] && qr.modules[0][20] && qr.modules[20][0]);
+        assert!(!qr.modules[7][7] && !qr.modules[7][13]);
+        // Each line of block characters covers two rows of modules
+        let lines = qr.to_lines();
+        assert_eq!(lines.len(), (21 + 2 * QUIET_ZONE).div_ceil(2));
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == 21 + 2 * QUIET_ZONE));
+
+        let uri = format!("checklist://task/{}", uuid::Uuid::new_v4());
+        assert_eq!(QrCode::encode(&uri).unwrap().size, 33);
+        assert!(QrCode::encode(&"x".repeat(300)).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+
+use crate::backend::calendar::{calendar, today};
+use crate::backend::task::{parse_due_date, Status, Task, Urgency};
+
+/// How a field is compared against a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Op {
+    /// Returns the SQL for the comparison
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::NotEq => "!=",
+            Op::Lt => "<",
+            Op::LtEq => "<=",
+            Op::Gt => ">",
+            Op::GtEq => ">=",
+        }
+    }
+
+    /// Returns true if a value comparing as `ordering` to the one compared
+    /// against passes the comparison
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering.is_eq(),
+            Op::NotEq => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::LtEq => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::GtEq => ordering.is_ge(),
+        }
+    }
+}
+
+/// The date fields of a `Task` that can be compared against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Scheduled,
+    Added,
+    Completed,
+}
+
+/// A filter over `Task`s written in the query language, like
+/// `status:open AND (tag:work OR urgency>=high) due<2025-01-01`.
+///
+/// Terms are `field:value`, or `field` followed by one of `!= < <= > >=` and a value.
+/// Terms next to each other have to all match, as if joined by `AND`. `OR` and `NOT`
+/// combine them further, and parentheses group them. Words that aren't terms match
+/// against the name and description. Values with spaces go in double quotes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Status(Op, Status),
+    Urgency(Op, Urgency),
+    Tag(Op, String),
+    Project(Op, String),
+    /// A date of `None` matches `Task`s without that date
+    Date(DateField, Op, Option<NaiveDate>),
+    Text(String),
+}
+
+impl Query {
+    /// Returns true if the `Task` matches, the same as it would in the database,
+    /// for stores that can't run the query there
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Query::And(left, right) => left.matches(task) && right.matches(task),
+            Query::Or(left, right) => left.matches(task) || right.matches(task),
+            Query::Not(inner) => !inner.matches(task),
+            Query::Status(op, status) => op.holds(task.status.to_string().cmp(&status.to_string())),
+            Query::Urgency(op, urgency) => op.holds(task.urgency.cmp(urgency)),
+            Query::Tag(op, tag) => {
+                let has_tag = task
+                    .tags
+                    .iter()
+                    .flatten()
+                    .any(|task_tag| task_tag.eq_ignore_ascii_case(tag));
+                match op {
+                    Op::NotEq => !has_tag,
+                    _ => has_tag,
+                }
+            }
+            Query::Project(op, project) => {
+                let task_project = task.project.as_deref().unwrap_or_default();
+                op.holds(
+                    task_project
+                        .to_ascii_lowercase()
+                        .cmp(&project.to_ascii_lowercase()),
+                )
+            }
+            Query::Date(field, op, date) => {
+                let task_date = match field {
+                    DateField::Due => task.due,
+                    DateField::Scheduled => task.scheduled,
+                    DateField::Added => Some(calendar().date_of(&task.get_date_added())),
+                    DateField::Completed => task.completed_on.map(|done| calendar().date_of(&done)),
+                };
+                match (task_date, date) {
+                    (task_date, None) => match op {
+                        Op::NotEq => task_date.is_some(),
+                        _ => task_date.is_none(),
+                    },
+                    (Some(task_date), Some(date)) => op.holds(task_date.cmp(date)),
+                    (None, Some(_)) => false,
+                }
+            }
+            Query::Text(text) => {
+                let text = text.to_lowercase();
+                task.name.to_lowercase().contains(&text)
+                    || task
+                        .description
+                        .as_deref()
+                        .is_some_and(|description| description.to_lowercase().contains(&text))
+            }
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        if tokens.is_empty() {
+            bail!("The query is empty");
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let query = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected '{}' in the query", token);
+        }
+        Ok(query)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// A term or bare word, with any quotes still in it
+    Word(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Word(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+/// Splits the query into tokens. Parentheses and whitespace inside
+/// double quotes are kept as part of the word they are in.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    let mut in_quotes = false;
+
+    let end_word = |word: &mut String, tokens: &mut Vec<Token>| {
+        if word.is_empty() {
+            return;
+        }
+        let token = match word.to_uppercase().as_str() {
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Word(word.clone()),
+        };
+        tokens.push(token);
+        word.clear();
+    };
+
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                word.push(ch);
+            }
+            _ if in_quotes => word.push(ch),
+            '(' | ')' => {
+                end_word(&mut word, &mut tokens);
+                tokens.push(if ch == '(' {
+                    Token::LeftParen
+                } else {
+                    Token::RightParen
+                });
+            }
+            _ if ch.is_whitespace() => end_word(&mut word, &mut tokens),
+            _ => word.push(ch),
+        }
+    }
+    if in_quotes {
+        bail!("A double quote in the query is never closed");
+    }
+    end_word(&mut word, &mut tokens);
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// or := and ("OR" and)*
+    fn or(&mut self) -> Result<Query> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    /// and := not ("AND"? not)*
+    fn and(&mut self) -> Result<Query> {
+        let mut query = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Not | Token::LeftParen | Token::Word(_)) => {}
+                _ => return Ok(query),
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    /// not := "NOT" not | "(" or ")" | term
+    fn not(&mut self) -> Result<Query> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.not()?))),
+            Some(Token::LeftParen) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(query),
+                    _ => bail!("A parenthesis in the query is never closed"),
+                }
+            }
+            Some(Token::Word(word)) => parse_term(&word),
+            Some(token) => bail!("Expected a term in the query but found '{}'", token),
+            None => bail!("The query ends before it should"),
+        }
+    }
+}
+
+/// Strips the double quotes off a value, if it has them
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Parses a single term, i.e. `urgency>=high`, or a bare word
+fn parse_term(word: &str) -> Result<Query> {
+    let Some(op_start) = word.find([':', '=', '!', '<', '>']) else {
+        return Ok(Query::Text(unquote(word).to_string()));
+    };
+    // Anything quoted before the comparison is a bare word, i.e. "10:30"
+    if word[..op_start].contains('"') {
+        return Ok(Query::Text(unquote(word).to_string()));
+    }
+
+    let field = word[..op_start].to_lowercase();
+    let rest = &word[op_start..];
+    let (op, value) = [
+        (":", Op::Eq),
+        ("!=", Op::NotEq),
+        ("<=", Op::LtEq),
+        (">=", Op::GtEq),
+        ("=", Op::Eq),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ]
+    .into_iter()
+    .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|value| (op, value)))
+    .with_context(|| format!("'{}' is not a valid comparison", rest))?;
+    let value = unquote(value);
+    if value.is_empty() {
+        bail!("'{}' needs a value to compare against", word);
+    }
+
+    let equality_only = |name: &str| {
+        if matches!(op, Op::Eq | Op::NotEq) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "{} can only be compared with ':' or '!='",
+                name
+            ))
+        }
+    };
+
+    match field.as_str() {
+        "status" => {
+            equality_only("status")?;
+            let status = Status::from_str(value, true)
+                .map_err(|_| anyhow::anyhow!("'{}' is not a valid status", value))?;
+            Ok(Query::Status(op, status))
+        }
+        "urgency" => {
+            let urgency = Urgency::from_str(value, true)
+                .map_err(|_| anyhow::anyhow!("'{}' is not a valid urgency", value))?;
+            Ok(Query::Urgency(op, urgency))
+        }
+        "tag" => {
+            equality_only("tag")?;
+            Ok(Query::Tag(op, value.to_string()))
+        }
+        "project" => {
+            equality_only("project")?;
+            Ok(Query::Project(op, value.to_string()))
+        }
+        "due" | "scheduled" | "added" | "completed" => {
+            let date_field = match field.as_str() {
+                "due" => DateField::Due,
+                "scheduled" => DateField::Scheduled,
+                "added" => DateField::Added,
+                _ => DateField::Completed,
+            };
+            let date = if value.eq_ignore_ascii_case("none") {
+                equality_only("none")?;
+                None
+            } else {
+                parse_due_date(value, today())?
+            };
+            Ok(Query::Date(date_field, op, date))
+        }
+        _ => bail!(
+            "'{}' is not a field that can be queried, use status, urgency, tag, project, due, scheduled, added, or completed",
+            field
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(query: Query) -> Box<Query> {
+        Box::new(query)
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query: Query = "status:open AND (tag:work OR urgency>=high) due<2025-01-01"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query,
+            Query::And(
+                boxed(Query::And(
+                    boxed(Query::Status(Op::Eq, Status::Open)),
+                    boxed(Query::Or(
+                        boxed(Query::Tag(Op::Eq, String::from("work"))),
+                        boxed(Query::Urgency(Op::GtEq, Urgency::High)),
+                    )),
+                )),
+                boxed(Query::Date(
+                    DateField::Due,
+                    Op::Lt,
+                    NaiveDate::from_ymd_opt(2025, 1, 1)
+                )),
+            )
+        );
+
+        assert_eq!(
+            "not project:\"side hustle\" or milk"
+                .parse::<Query>()
+                .unwrap(),
+            Query::Or(
+                boxed(Query::Not(boxed(Query::Project(
+                    Op::Eq,
+                    String::from("side hustle")
+                )))),
+                boxed(Query::Text(String::from("milk"))),
+            )
+        );
+        assert_eq!(
+            "due:none".parse::<Query>().unwrap(),
+            Query::Date(DateField::Due, Op::Eq, None)
+        );
+    }
+
+    #[test]
+    fn test_parse_query_errors() {
+        assert!("".parse::<Query>().is_err());
+        assert!("status:open AND".parse::<Query>().is_err());
+        assert!("(tag:work".parse::<Query>().is_err());
+        assert!("tag:work)".parse::<Query>().is_err());
+        assert!("status>open".parse::<Query>().is_err());
+        assert!("urgency:extreme".parse::<Query>().is_err());
+        assert!("colour:blue".parse::<Query>().is_err());
+        assert!("due<none".parse::<Query>().is_err());
+        assert!("project:\"side".parse::<Query>().is_err());
+    }
+}
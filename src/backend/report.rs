@@ -0,0 +1,865 @@
+use std::collections::{BTreeMap, HashMap};
+
+use std::cmp::Reverse;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc, Weekday};
+use clap::ValueEnum;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::backend::calendar::{calendar, relative_day, start_of_week, today};
+use crate::backend::database::{get_all_db_contents, get_archive_contents, get_work_sessions};
+use crate::backend::open::run_shell;
+use crate::backend::task::{Status, Task, WorkSession};
+
+/// Label used in the time report for `Task`s without any tags
+const UNTAGGED: &str = "(untagged)";
+
+/// Label used in the focus report for `Task`s without a project
+const NO_PROJECT: &str = "(no project)";
+
+/// Width of the longest bar in the focus report
+const BAR_WIDTH: f64 = 40.0;
+
+/// Characters the bars of the focus report are filled with, one per tag or project
+const BAR_FILLS: [char; 8] = ['█', '▓', '▒', '░', '#', '*', '+', '='];
+
+/// How many of the oldest open `Task`s the stats list
+const OLDEST_OPEN: usize = 5;
+
+/// Converts seconds into hours, rounded to two decimal places
+fn to_hours(seconds: i64) -> f64 {
+    (seconds as f64 / 3600.0 * 100.0).round() / 100.0
+}
+
+/// Builds the lines of the time report: hours spent per `Task`, per tag,
+/// and overall. `Task`s without any time tracked are left out.
+/// A `Task`'s time counts towards each of its tags.
+pub fn time_report(tasks: &[Task]) -> Vec<String> {
+    let mut tracked: Vec<(&Task, i64)> = tasks
+        .iter()
+        .map(|task| (task, task.total_time_spent()))
+        .filter(|(_, seconds)| *seconds > 0)
+        .collect();
+
+    if tracked.is_empty() {
+        return vec![String::from("No time has been tracked yet")];
+    }
+    tracked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+
+    let mut per_tag: BTreeMap<&str, i64> = BTreeMap::new();
+    for (task, seconds) in &tracked {
+        match &task.tags {
+            Some(tags) if !tags.is_empty() => {
+                for tag in tags {
+                    *per_tag.entry(tag.as_str()).or_default() += seconds;
+                }
+            }
+            _ => *per_tag.entry(UNTAGGED).or_default() += seconds,
+        }
+    }
+    let mut per_tag: Vec<(&str, i64)> = per_tag.into_iter().collect();
+    per_tag.sort_by_key(|(_, seconds)| std::cmp::Reverse(*seconds));
+
+    let mut lines = vec![String::from("Hours per task:")];
+    for (task, seconds) in &tracked {
+        let running = if task.timer_started.is_some() {
+            " (running)"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "  {:>8.2}  {}{}",
+            to_hours(*seconds),
+            task.name,
+            running
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from("Hours per tag:"));
+    for (tag, seconds) in per_tag {
+        lines.push(format!("  {:>8.2}  {}", to_hours(seconds), tag));
+    }
+
+    let total: i64 = tracked.iter().map(|(_, seconds)| seconds).sum();
+    lines.push(String::new());
+    lines.push(format!("Total: {:.2} hours", to_hours(total)));
+
+    lines
+}
+
+/// Prints the time report for the `Task`s in the database,
+/// limited to a single project if one is given
+pub fn print_time_report(conn: &Connection, project: Option<&str>) -> Result<()> {
+    let mut task_list = get_all_db_contents(conn)?;
+    if let Some(project) = project {
+        task_list.filter_project(project);
+    }
+
+    for line in time_report(&task_list.tasks) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Builds the lines of the completed report: the `Task`s completed
+/// from `start` up to (but not including) `end`, most recent first.
+/// `period` describes the time span, i.e. "today".
+pub fn completed_report(
+    tasks: &[Task],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    period: &str,
+) -> Vec<String> {
+    let mut completed: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| {
+            task.completed_on
+                .is_some_and(|completed| start <= completed && completed < end)
+        })
+        .collect();
+    completed.sort_by_key(|task| Reverse(task.completed_on));
+
+    let mut lines = vec![format!("Completed {}: {}", period, completed.len())];
+    for task in completed {
+        if let Some(completed_on) = task.completed_on {
+            lines.push(format!(
+                "  {}  {}",
+                calendar().date_of(&completed_on),
+                task.name
+            ));
+        }
+    }
+    lines
+}
+
+/// Prints the `Task`s completed today, or this week if `week` is true,
+/// archived ones included. Days and weeks follow the configured calendar.
+pub fn print_completed_report(conn: &Connection, week: bool) -> Result<()> {
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.extend(get_archive_contents(conn)?.tasks);
+
+    let (start, end, period) = if week {
+        let (start, end) = calendar().week_bounds(today());
+        (start, end, "this week")
+    } else {
+        let (start, end) = calendar().day_bounds(today());
+        (start, end, "today")
+    };
+
+    for line in completed_report(&tasks, start, end, period) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Builds the lines of the daily digest for `today`: the `Task`s that are
+/// overdue, the ones due today, and the ones completed yesterday, archived
+/// ones included
+pub fn digest_report(tasks: &[Task], archived: &[Task], today: NaiveDate) -> Vec<String> {
+    let mut overdue: Vec<&Task> = tasks.iter().filter(|task| task.is_overdue(today)).collect();
+    overdue.sort_by_key(|task| task.due);
+    let due_today: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.status != Status::Completed && task.due == Some(today))
+        .collect();
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let (start, end) = calendar().day_bounds(yesterday);
+    let mut completed: Vec<&Task> = tasks
+        .iter()
+        .chain(archived)
+        .filter(|task| {
+            task.completed_on
+                .is_some_and(|completed| start <= completed && completed < end)
+        })
+        .collect();
+    completed.sort_by_key(|task| task.completed_on);
+
+    if overdue.is_empty() && due_today.is_empty() && completed.is_empty() {
+        return vec![String::from(
+            "Nothing is overdue or due today, and nothing was completed yesterday",
+        )];
+    }
+
+    let mut lines = vec![];
+    if !overdue.is_empty() {
+        lines.push(format!("Overdue: {}", overdue.len()));
+        for task in overdue {
+            if let Some(due) = task.due {
+                lines.push(format!(
+                    "  {}  {} (due {})",
+                    due,
+                    task.summary(),
+                    relative_day(due, today)
+                ));
+            }
+        }
+    }
+    if !due_today.is_empty() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("Due today: {}", due_today.len()));
+        for task in due_today {
+            lines.push(format!("  {}", task.summary()));
+        }
+    }
+    if !completed.is_empty() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("Completed yesterday: {}", completed.len()));
+        for task in completed {
+            lines.push(format!("  {}", task.name));
+        }
+    }
+    lines
+}
+
+/// Builds the summary printed when the TUI is quit: how many `Task`s were
+/// added, completed, and otherwise updated between `before` and `after`, and
+/// how much time was tracked on them. Archived tasks should be in both, so
+/// archiving a task doesn't count as anything.
+pub fn session_report(before: &[Task], after: &[Task]) -> Vec<String> {
+    let before: HashMap<Uuid, &Task> = before.iter().map(|task| (task.get_id(), task)).collect();
+    let mut added = vec![];
+    let mut completed = vec![];
+    let mut updated = 0;
+    let mut tracked = 0;
+    for task in after {
+        let old = before.get(&task.get_id());
+        tracked += task.total_time_spent() - old.map_or(0, |old| old.total_time_spent());
+        if old.is_none() {
+            added.push(task.name.as_str());
+        }
+        if task.status == Status::Completed && old.is_none_or(|old| old.status != Status::Completed)
+        {
+            completed.push(task.name.as_str());
+        } else if old.is_some_and(|old| *old != task) {
+            updated += 1;
+        }
+    }
+
+    let mut counts = vec![];
+    if !added.is_empty() {
+        counts.push(format!("{} added", added.len()));
+    }
+    if !completed.is_empty() {
+        counts.push(format!("{} completed", completed.len()));
+    }
+    if updated > 0 {
+        counts.push(format!("{} updated", updated));
+    }
+    if tracked > 0 {
+        counts.push(format!("{}h tracked", to_hours(tracked)));
+    }
+    if counts.is_empty() {
+        return vec![String::from("This session: nothing changed")];
+    }
+
+    let mut lines = vec![format!("This session: {}", counts.join(", "))];
+    for name in completed {
+        lines.push(format!("  Completed {}", name));
+    }
+    for name in added {
+        lines.push(format!("  Added {}", name));
+    }
+    lines
+}
+
+/// Prints the daily digest for today, or with a `command`, hands it to that
+/// shell command instead, i.e. to email it. The command gets the digest in
+/// the `CHECKLIST_SUBJECT` and `CHECKLIST_BODY` environment variables, and
+/// the body on its standard input too.
+pub fn print_digest(conn: &Connection, send: bool, command: Option<&str>) -> Result<()> {
+    let tasks = get_all_db_contents(conn)?.tasks;
+    let archived = get_archive_contents(conn)?.tasks;
+    let today = today();
+    let body = digest_report(&tasks, &archived, today).join("\n");
+
+    if !send {
+        println!("{}", body);
+        return Ok(());
+    }
+    let Some(command) = command else {
+        bail!("Set \"digest_command\" in the config file (see `checklist where -c`) to send the digest, i.e. \"mail -s \\\"$CHECKLIST_SUBJECT\\\" me@example.com\"");
+    };
+    let subject = format!("checklist digest for {}", today.format("%a %Y-%m-%d"));
+    run_shell(
+        command,
+        &[("CHECKLIST_SUBJECT", &subject), ("CHECKLIST_BODY", &body)],
+        &body,
+        "digest command",
+    )
+}
+
+/// Builds the lines of the stats: how many `Task`s there are per status
+/// and per tag, how many were completed in the last 7 and 30 days, and
+/// which open `Task`s have been around the longest. `archived` only
+/// counts towards the completions.
+pub fn stats_report(tasks: &[Task], archived: &[Task], now: DateTime<Local>) -> Vec<String> {
+    let mut lines = vec![format!("Tasks: {}", tasks.len())];
+    for status in [
+        Status::Open,
+        Status::Working,
+        Status::Paused,
+        Status::Completed,
+    ] {
+        let count = tasks.iter().filter(|task| task.status == status).count();
+        lines.push(format!("  {:>5}  {}", count, status));
+    }
+    if !archived.is_empty() {
+        lines.push(format!("  {:>5}  Archived", archived.len()));
+    }
+
+    let mut per_tag: BTreeMap<&str, usize> = BTreeMap::new();
+    for task in tasks {
+        match &task.tags {
+            Some(tags) if !tags.is_empty() => {
+                for tag in tags {
+                    *per_tag.entry(tag.as_str()).or_default() += 1;
+                }
+            }
+            _ => *per_tag.entry(UNTAGGED).or_default() += 1,
+        }
+    }
+    let mut per_tag: Vec<(&str, usize)> = per_tag.into_iter().collect();
+    per_tag.sort_by_key(|(_, count)| Reverse(*count));
+    if !per_tag.is_empty() {
+        lines.push(String::new());
+        lines.push(String::from("Tasks per tag:"));
+        for (tag, count) in per_tag {
+            lines.push(format!("  {:>5}  {}", count, tag));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(String::from("Completed:"));
+    for days in [7, 30] {
+        let since = now - Duration::days(days);
+        let count = tasks
+            .iter()
+            .chain(archived)
+            .filter(|task| task.completed_on.is_some_and(|completed| completed > since))
+            .count();
+        lines.push(format!("  {:>5}  in the last {} days", count, days));
+    }
+
+    let mut open: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.status != Status::Completed)
+        .collect();
+    if !open.is_empty() {
+        open.sort_by_key(|task| task.get_date_added());
+        lines.push(String::new());
+        lines.push(String::from("Oldest open tasks:"));
+        for task in open.into_iter().take(OLDEST_OPEN) {
+            let age = (now - task.get_date_added()).num_days();
+            lines.push(format!("  {:>4}d  {}", age, task.summary()));
+        }
+    }
+
+    lines
+}
+
+/// Prints the stats for the `Task`s in the database,
+/// limited to a single project if one is given
+pub fn print_stats(conn: &Connection, project: Option<&str>) -> Result<()> {
+    let mut task_list = get_all_db_contents(conn)?;
+    let mut archive = get_archive_contents(conn)?;
+    if let Some(project) = project {
+        task_list.filter_project(project);
+        archive.filter_project(project);
+    }
+
+    for line in stats_report(&task_list.tasks, &archive.tasks, Local::now()) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// What the focus report breaks the tracked time down by
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum FocusBy {
+    Tag,
+    Project,
+}
+
+/// Returns the tags or project the `Task` counts towards, each with the
+/// share of the `Task` it gets. `Task`s with several tags are split evenly
+/// between them, so nothing is counted twice.
+fn focus_shares(task: &Task, by: FocusBy) -> Vec<(String, f64)> {
+    match by {
+        FocusBy::Tag => match &task.tags {
+            Some(tags) if !tags.is_empty() => {
+                let share = 1.0 / tags.len() as f64;
+                tags.iter().map(|tag| (tag.clone(), share)).collect()
+            }
+            _ => vec![(String::from(UNTAGGED), 1.0)],
+        },
+        FocusBy::Project => {
+            let project = task.project.clone().unwrap_or(String::from(NO_PROJECT));
+            vec![(project, 1.0)]
+        }
+    }
+}
+
+/// Builds the lines of the focus report: the hours tracked each week,
+/// starting from `first_week`, as bars stacked by tag or project,
+/// followed by how the tracked time compares to the open `Task`s.
+/// Weeks start on `week_start`, and sessions of `Task`s that have
+/// since been deleted are left out.
+pub fn focus_report(
+    tasks: &[Task],
+    sessions: &[WorkSession],
+    first_week: NaiveDate,
+    weeks: u32,
+    week_start: Weekday,
+    by: FocusBy,
+) -> Vec<String> {
+    let shares: HashMap<Uuid, Vec<(String, f64)>> = tasks
+        .iter()
+        .map(|task| (task.get_id(), focus_shares(task, by)))
+        .collect();
+
+    let mut per_week: Vec<HashMap<&str, f64>> = vec![HashMap::new(); weeks as usize];
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for session in sessions {
+        let week = start_of_week(calendar().date_of(&session.started), week_start);
+        let index = (week - first_week).num_days().div_euclid(7);
+        let (Ok(index), Some(shares)) = (usize::try_from(index), shares.get(&session.task_id))
+        else {
+            continue;
+        };
+        if index >= per_week.len() {
+            continue;
+        }
+        let hours = session.seconds() as f64 / 3600.0;
+        for (name, share) in shares {
+            *per_week[index].entry(name.as_str()).or_default() += hours * share;
+            *totals.entry(name.as_str()).or_default() += hours * share;
+        }
+    }
+
+    let label = match by {
+        FocusBy::Tag => "tag",
+        FocusBy::Project => "project",
+    };
+    if totals.is_empty() {
+        return vec![format!("No time was tracked in the last {} week(s)", weeks)];
+    }
+
+    let mut series: Vec<(&str, f64)> = totals.into_iter().collect();
+    series.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let fill = |index: usize| BAR_FILLS[index % BAR_FILLS.len()];
+
+    let busiest = per_week
+        .iter()
+        .map(|week| week.values().sum::<f64>())
+        .fold(0.0, f64::max);
+    let mut lines = vec![format!("Hours per week by {}:", label)];
+    for (index, week) in per_week.iter().enumerate() {
+        let mut bar = String::new();
+        for (series_index, (name, _)) in series.iter().enumerate() {
+            let hours = week.get(name).copied().unwrap_or_default();
+            let width = (hours / busiest * BAR_WIDTH).round() as usize;
+            bar.push_str(&fill(series_index).to_string().repeat(width));
+        }
+        let start = first_week + Duration::weeks(index as i64);
+        lines.push(format!(
+            "  {}  {:<width$}  {:>6.2}",
+            start,
+            bar,
+            week.values().sum::<f64>(),
+            width = BAR_WIDTH as usize
+        ));
+    }
+
+    lines.push(String::new());
+    let name_width = series
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or_default();
+    for (index, (name, hours)) in series.iter().enumerate() {
+        lines.push(format!(
+            "  {}  {:<name_width$}  {:>6.2}",
+            fill(index),
+            name,
+            hours
+        ));
+    }
+
+    // Compare where the time went with what is waiting to be done
+    let mut backlog: HashMap<String, f64> = HashMap::new();
+    let mut open_count = 0;
+    for task in tasks.iter().filter(|task| task.status != Status::Completed) {
+        open_count += 1;
+        for (name, share) in focus_shares(task, by) {
+            *backlog.entry(name).or_default() += share;
+        }
+    }
+    let tracked: f64 = series.iter().map(|(_, hours)| hours).sum();
+    let mut names: Vec<&str> = series.iter().map(|(name, _)| *name).collect();
+    let mut waiting: Vec<&str> = backlog
+        .keys()
+        .map(|name| name.as_str())
+        .filter(|name| !names.contains(name))
+        .collect();
+    waiting.sort();
+    names.extend(waiting);
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    lines.push(String::new());
+    lines.push(String::from("Time vs backlog:"));
+    lines.push(format!(
+        "  {:<name_width$}  {:>6}  {:>6}",
+        "", "time", "open"
+    ));
+    for name in names {
+        let hours = series
+            .iter()
+            .find(|(series_name, _)| *series_name == name)
+            .map(|(_, hours)| *hours)
+            .unwrap_or_default();
+        let open = backlog.get(name).copied().unwrap_or_default();
+        let open_percent = if open_count > 0 {
+            open / open_count as f64 * 100.0
+        } else {
+            0.0
+        };
+        lines.push(format!(
+            "  {:<name_width$}  {:>5.0}%  {:>5.0}%",
+            name,
+            hours / tracked * 100.0,
+            open_percent
+        ));
+    }
+
+    lines
+}
+
+/// Prints the focus report for the last `weeks` weeks, this one included,
+/// archived `Task`s included
+pub fn print_focus_report(conn: &Connection, weeks: u32, by: FocusBy) -> Result<()> {
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.extend(get_archive_contents(conn)?.tasks);
+    let sessions = get_work_sessions(conn)?;
+
+    let weeks = weeks.max(1);
+    let week_start = calendar().week_start;
+    let first_week = start_of_week(today(), week_start) - Duration::weeks(i64::from(weeks) - 1);
+
+    for line in focus_report(&tasks, &sessions, first_week, weeks, week_start, by) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_time_report() {
+        let mut writing = Task::new(
+            String::from("Write docs"),
+            None,
+            None,
+            None,
+            None,
+            Some(HashSet::from([String::from("work"), String::from("docs")])),
+        );
+        writing.time_spent = 2 * 3600;
+        let mut chores = Task::new(String::from("Chores"), None, None, None, None, None);
+        chores.time_spent = 1800;
+        let untracked = Task::new(String::from("Nap"), None, None, None, None, None);
+
+        assert_eq!(
+            time_report(std::slice::from_ref(&untracked)),
+            vec![String::from("No time has been tracked yet")]
+        );
+
+        let report = time_report(&[chores, untracked, writing]);
+        assert_eq!(
+            report,
+            vec![
+                String::from("Hours per task:"),
+                String::from("      2.00  Write docs"),
+                String::from("      0.50  Chores"),
+                String::new(),
+                String::from("Hours per tag:"),
+                String::from("      2.00  docs"),
+                String::from("      2.00  work"),
+                String::from("      0.50  (untagged)"),
+                String::new(),
+                String::from("Total: 2.50 hours"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_completed_report() {
+        let start = DateTime::parse_from_rfc3339("2024-03-10T05:00:00Z")
+            .unwrap()
+            .to_utc();
+        let end = DateTime::parse_from_rfc3339("2024-03-11T04:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        let mut tasks = vec![];
+        for (name, completed_on) in [
+            ("Too early", "2024-03-10T04:59:59Z"),
+            ("First", "2024-03-10T05:00:00Z"),
+            ("Last", "2024-03-11T03:59:59Z"),
+            ("Too late", "2024-03-11T04:00:00Z"),
+        ] {
+            let mut task = Task::new(String::from(name), None, None, None, None, None);
+            task.completed_on = Some(DateTime::parse_from_rfc3339(completed_on).unwrap().into());
+            tasks.push(task);
+        }
+        tasks.push(Task::new(
+            String::from("Never"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        ));
+
+        let report = completed_report(&tasks, start, end, "today");
+        assert_eq!(report[0], "Completed today: 2");
+        assert!(report[1].ends_with("Last"));
+        assert!(report[2].ends_with("First"));
+        assert_eq!(report.len(), 3);
+    }
+
+    #[test]
+    fn test_digest_report() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let make_task = |name: &str, due: Option<NaiveDate>, status| {
+            let mut task = Task::new(String::from(name), None, None, None, Some(status), None);
+            task.due = due;
+            task
+        };
+        let noon = |date: NaiveDate| {
+            date.and_hms_opt(12, 0, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .unwrap()
+        };
+
+        assert_eq!(
+            digest_report(&[], &[], today),
+            vec![String::from(
+                "Nothing is overdue or due today, and nothing was completed yesterday"
+            )]
+        );
+
+        let mut shipped = make_task("Ship it", None, Status::Completed);
+        shipped.completed_on = Some(noon(today.pred_opt().unwrap()));
+        let mut filed = make_task("File taxes", Some(today), Status::Completed);
+        filed.completed_on = Some(noon(today));
+        let mut archived = make_task("Old chore", None, Status::Completed);
+        archived.completed_on = Some(noon(today.pred_opt().unwrap()));
+        let tasks = vec![
+            make_task("Rent", NaiveDate::from_ymd_opt(2024, 3, 12), Status::Open),
+            make_task("Dentist", Some(today), Status::Open),
+            make_task(
+                "Taxes",
+                NaiveDate::from_ymd_opt(2024, 3, 1),
+                Status::Working,
+            ),
+            make_task("Holiday", NaiveDate::from_ymd_opt(2024, 4, 1), Status::Open),
+            shipped,
+            filed,
+        ];
+
+        assert_eq!(
+            digest_report(&tasks, &[archived], today),
+            vec![
+                String::from("Overdue: 2"),
+                String::from("  2024-03-01  Taxes [Working] (due 2 weeks ago)"),
+                String::from("  2024-03-12  Rent [Open] (due 3 days ago)"),
+                String::new(),
+                String::from("Due today: 1"),
+                String::from("  Dentist [Open]"),
+                String::new(),
+                String::from("Completed yesterday: 2"),
+                String::from("  Ship it"),
+                String::from("  Old chore"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_report() {
+        let make_task = |name: &str| Task::new(String::from(name), None, None, None, None, None);
+        let report = make_task("Write report");
+        let taxes = make_task("Taxes");
+        let chores = make_task("Chores");
+        let before = vec![report.clone(), taxes.clone(), chores.clone()];
+
+        assert_eq!(
+            session_report(&before, &before),
+            vec![String::from("This session: nothing changed")]
+        );
+
+        let mut report_done = report;
+        report_done.status = Status::Completed;
+        let mut taxes_worked = taxes;
+        taxes_worked.time_spent = 5400;
+        let groceries = make_task("Groceries");
+        // Chores was deleted
+        let after = vec![report_done, taxes_worked, groceries];
+        assert_eq!(
+            session_report(&before, &after),
+            vec![
+                String::from("This session: 1 added, 1 completed, 1 updated, 1.5h tracked"),
+                String::from("  Completed Write report"),
+                String::from("  Added Groceries"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_report() {
+        let now = DateTime::parse_from_rfc3339("2024-03-31T12:00:00Z")
+            .unwrap()
+            .into();
+        let days_ago = |days| now - Duration::days(days);
+        let make_task = |name: &str, tag: Option<&str>, status, added| {
+            let mut task = Task::new(
+                String::from(name),
+                None,
+                None,
+                None,
+                Some(status),
+                tag.map(|tag| HashSet::from([String::from(tag)])),
+            );
+            task.date_added = days_ago(added);
+            task
+        };
+
+        let mut shipped = make_task("Ship it", Some("work"), Status::Completed, 20);
+        shipped.completed_on = Some(days_ago(3));
+        let mut filed = make_task("File taxes", None, Status::Completed, 60);
+        filed.completed_on = Some(days_ago(10));
+        let tasks = vec![
+            make_task("Write docs", Some("work"), Status::Working, 40),
+            make_task("Call mum", None, Status::Open, 2),
+            shipped,
+        ];
+        let mut archived = make_task("Old chore", None, Status::Completed, 90);
+        archived.completed_on = Some(days_ago(45));
+
+        assert_eq!(
+            stats_report(&tasks, &[filed, archived], now),
+            vec![
+                String::from("Tasks: 3"),
+                String::from("      1  Open"),
+                String::from("      1  Working"),
+                String::from("      0  Paused"),
+                String::from("      1  Completed"),
+                String::from("      2  Archived"),
+                String::new(),
+                String::from("Tasks per tag:"),
+                String::from("      2  work"),
+                String::from("      1  (untagged)"),
+                String::new(),
+                String::from("Completed:"),
+                String::from("      1  in the last 7 days"),
+                String::from("      2  in the last 30 days"),
+                String::new(),
+                String::from("Oldest open tasks:"),
+                String::from("    40d  Write docs [Working] (work)"),
+                String::from("     2d  Call mum [Open]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_focus_report() {
+        let first_week = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let mut work = Task::new(
+            String::from("Ship it"),
+            None,
+            None,
+            None,
+            None,
+            Some(HashSet::from([String::from("work")])),
+        );
+        work.status = Status::Completed;
+        let both = Task::new(
+            String::from("Write docs"),
+            None,
+            None,
+            None,
+            None,
+            Some(HashSet::from([String::from("work"), String::from("docs")])),
+        );
+        let chores = Task::new(String::from("Chores"), None, None, None, None, None);
+        let gone = Task::new(String::from("Deleted"), None, None, None, None, None);
+
+        // Sessions are in the middle of the day so they land on the same date in any timezone
+        let session = |task: &Task, started: &str, hours: i64| {
+            let started: DateTime<Local> = DateTime::parse_from_rfc3339(started).unwrap().into();
+            WorkSession {
+                task_id: task.get_id(),
+                started,
+                ended: started + Duration::hours(hours),
+            }
+        };
+        let sessions = vec![
+            session(&work, "2024-03-06T11:00:00Z", 4),
+            session(&both, "2024-03-07T11:00:00Z", 2),
+            session(&chores, "2024-03-13T11:00:00Z", 1),
+            session(&gone, "2024-03-13T11:00:00Z", 3),
+            session(&chores, "2024-02-28T11:00:00Z", 9),
+        ];
+        let tasks = vec![work, both, chores];
+
+        assert_eq!(
+            focus_report(&tasks, &[], first_week, 2, Weekday::Mon, FocusBy::Tag),
+            vec![String::from("No time was tracked in the last 2 week(s)")]
+        );
+
+        assert_eq!(
+            focus_report(&tasks, &sessions, first_week, 2, Weekday::Mon, FocusBy::Tag),
+            vec![
+                String::from("Hours per week by tag:"),
+                format!("  2024-03-04  {}{}    6.00", "█".repeat(33), "▒".repeat(7)),
+                format!("  2024-03-11  {}{}    1.00", "▓".repeat(7), " ".repeat(33)),
+                String::new(),
+                String::from("  █  work          5.00"),
+                String::from("  ▓  (untagged)    1.00"),
+                String::from("  ▒  docs          1.00"),
+                String::new(),
+                String::from("Time vs backlog:"),
+                String::from("                time    open"),
+                String::from("  work           71%     25%"),
+                String::from("  (untagged)     14%     50%"),
+                String::from("  docs           14%     25%"),
+            ]
+        );
+
+        let by_project = focus_report(
+            &tasks,
+            &sessions,
+            first_week,
+            1,
+            Weekday::Mon,
+            FocusBy::Project,
+        );
+        assert_eq!(by_project[0], "Hours per week by project:");
+        assert_eq!(by_project[3], "  █  (no project)    6.00");
+    }
+}
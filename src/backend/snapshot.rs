@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use rusqlite::Connection;
+
+use crate::backend::archive::parse_age;
+use crate::backend::calendar::calendar;
+use crate::backend::database::{
+    add_to_db, get_all_db_contents, get_archive_contents, get_db, load_backup,
+    make_memory_connection,
+};
+use crate::backend::task::{Status, Task};
+
+/// Returns the `Task`s as they stood at `as_of`. Those added later are left out,
+/// and those completed later go back to being `Open`.
+pub fn tasks_as_of(tasks: Vec<Task>, as_of: DateTime<Local>) -> Vec<Task> {
+    tasks
+        .into_iter()
+        .filter(|task| task.date_added <= as_of)
+        .map(|mut task| {
+            if task.completed_on.is_some_and(|completed| completed > as_of) {
+                task.status = Status::Open;
+                task.completed_on = None;
+            }
+            task
+        })
+        .collect()
+}
+
+/// Works out the instant `as_of` refers to, either the end of a date (YYYY-MM-DD)
+/// or an age back from now, like "2w"
+fn parse_as_of(as_of: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    if let Ok(date) = NaiveDate::parse_from_str(as_of.trim(), "%Y-%m-%d") {
+        let (_, end_of_day) = calendar().day_bounds(date);
+        return Ok(end_of_day.with_timezone(&Local).min(now));
+    }
+    match parse_age(as_of) {
+        Ok(age) => Ok(now - age),
+        Err(_) => bail!(
+            "'{}' is neither a backup file, a date (YYYY-MM-DD), nor an age like 2w",
+            as_of
+        ),
+    }
+}
+
+/// Opens an in-memory copy of the tasks as of `as_of`, which is either the path to
+/// a backup of the database, a date (YYYY-MM-DD), or an age like "2w". Dates and
+/// ages are worked out from when tasks were added and completed, archived ones included.
+/// Being a copy, nothing done to it is saved.
+pub fn open_snapshot(as_of: &str, memory: bool, testing: bool) -> Result<Connection> {
+    let backup = Path::new(as_of);
+    if backup.is_file() {
+        return load_backup(backup);
+    }
+
+    let as_of = parse_as_of(as_of, Local::now())?;
+    let conn = get_db(memory, testing)?;
+    let mut tasks = get_all_db_contents(&conn)?.tasks;
+    tasks.extend(get_archive_contents(&conn)?.tasks);
+
+    let snapshot = make_memory_connection()?;
+    for task in tasks_as_of(tasks, as_of) {
+        add_to_db(&snapshot, &task)?;
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[test]
+    fn test_tasks_as_of() {
+        let now = Local::now();
+        let two_weeks_ago = now - TimeDelta::weeks(2);
+
+        let mut done_since = Task::new(String::from("Done since"), None, None, None, None, None);
+        done_since.date_added = now - TimeDelta::weeks(3);
+        done_since.status = Status::Completed;
+        done_since.completed_on = Some(now - TimeDelta::days(3));
+        let mut done_before = done_since.clone();
+        done_before.name = String::from("Done before");
+        done_before.completed_on = Some(now - TimeDelta::days(20));
+        let added_since = Task::new(String::from("Added since"), None, None, None, None, None);
+
+        let tasks = vec![done_since.clone(), done_before.clone(), added_since];
+        let snapshot = tasks_as_of(tasks, two_weeks_ago);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].name, "Done since");
+        assert_eq!(snapshot[0].status, Status::Open);
+        assert_eq!(snapshot[0].completed_on, None);
+        assert_eq!(snapshot[1], done_before);
+    }
+
+    #[test]
+    fn test_parse_as_of() {
+        let now = Local::now();
+        assert_eq!(parse_as_of("2w", now).unwrap(), now - TimeDelta::weeks(2));
+        assert!(parse_as_of("2020-01-15", now).unwrap() < now);
+        assert!(parse_as_of("last tuesday", now).is_err());
+    }
+}
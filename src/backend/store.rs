@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::backend::config::Config;
+use crate::backend::database::{
+    add_to_db, archive_tasks_in_db, count_archived, count_changed_since, delete_task_in_db,
+    get_all_db_contents, get_archive_contents, get_dependencies, get_last_touched,
+    get_snoozed_reminders, get_task_events, has_next_occurrence_in_db, open_db, promote_tasks,
+    retry_busy, roll_up_in_db, snooze_reminders_in_db, subtask_counts_in_db, update_task_in_db,
+    TaskSelect,
+};
+use crate::backend::error::ChecklistError;
+use crate::backend::events::TaskEvent;
+use crate::backend::flat_file::FlatFileStore;
+use crate::backend::notify::{notify_due_tasks, Delivery, QuietHours};
+#[cfg(feature = "postgres")]
+use crate::backend::postgres::PostgresStore;
+use crate::backend::tags::{all_tag_counts, retag, tag_counts, Retagged};
+use crate::backend::task::{count_subtasks, roll_up, RollUp, RolledUp, Task, TaskList};
+
+/// What the tasks are kept in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Storage {
+    /// A SQLite database, at `db_path`
+    #[default]
+    Sqlite,
+    /// A folder with a JSON file for each task
+    Json,
+    /// A folder with a TOML file for each task
+    Toml,
+    /// A shared PostgreSQL database, at `postgres_url`
+    Postgres,
+}
+
+/// Somewhere `Task`s are kept. The TUI adds, updates, deletes, and looks up
+/// tasks only through this, so another backend can stand in for SQLite.
+pub trait TaskStore {
+    /// Adds the `Task`
+    fn add(&self, task: &Task) -> Result<()>;
+
+    /// Saves the `Task` over the one with the same id
+    fn update(&self, task: &Task) -> Result<()>;
+
+    /// Deletes the `Task` with the same id
+    fn delete(&self, task: &Task) -> Result<()>;
+
+    /// Moves the `Task`s into the archive, either all of them or none
+    fn archive(&self, tasks: &[Task]) -> Result<()>;
+
+    /// Returns every `Task` that isn't archived
+    fn tasks(&self) -> Result<TaskList>;
+
+    /// Returns every archived `Task`
+    fn archived(&self) -> Result<TaskList>;
+
+    /// Returns how many tasks are archived
+    fn count_archived(&self) -> Result<usize> {
+        Ok(self.archived()?.tasks.len())
+    }
+
+    /// Returns the tasks the `TaskSelect` picks out, in its order
+    fn select(&self, select: &TaskSelect) -> Result<TaskList> {
+        let task_list = if select.is_archived() {
+            self.archived()?
+        } else {
+            self.tasks()?
+        };
+        Ok(select.pick(task_list))
+    }
+
+    /// Returns how many tasks the `TaskSelect` picks out, whichever rows it asks for
+    fn count(&self, select: &TaskSelect) -> Result<usize> {
+        let task_list = if select.is_archived() {
+            self.archived()?
+        } else {
+            self.tasks()?
+        };
+        Ok(select.matching(task_list).len())
+    }
+
+    /// Returns where the `Task` with the id comes among those the `TaskSelect`
+    /// picks out, whichever rows it asks for, or None if it isn't one of them
+    fn position(&self, select: &TaskSelect, id: Uuid) -> Result<Option<usize>> {
+        let task_list = if select.is_archived() {
+            self.archived()?
+        } else {
+            self.tasks()?
+        };
+        Ok(select
+            .matching(task_list)
+            .tasks
+            .iter()
+            .position(|task| task.get_id() == id))
+    }
+
+    /// Returns true if a `Task` (archived ones included) was already lined up
+    /// as the next occurrence of the recurring one with the id
+    fn has_next_occurrence(&self, id: Uuid) -> Result<bool> {
+        let mut tasks = self.tasks()?.tasks;
+        tasks.extend(self.archived()?.tasks);
+        Ok(tasks
+            .iter()
+            .any(|task| task.previous_occurrence == Some(id)))
+    }
+
+    /// Returns how many subtasks each of the `Task`s with the ids has, as
+    /// `count_subtasks` does, leaving out the ones with none
+    fn subtask_counts(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, usize>> {
+        let mut counts = count_subtasks(&self.tasks()?.tasks);
+        counts.retain(|id, _| ids.contains(id));
+        Ok(counts)
+    }
+
+    /// Returns what the subtasks of each of the `Task`s with the ids roll up
+    /// to, as `roll_up` does, leaving out the ones with nothing to show
+    fn roll_up(&self, ids: &[Uuid], fields: &[RollUp]) -> Result<HashMap<Uuid, RolledUp>> {
+        let mut rolled_up = roll_up(&self.tasks()?.tasks, fields);
+        rolled_up.retain(|id, _| ids.contains(id));
+        Ok(rolled_up)
+    }
+
+    /// Runs the `work` so the changes it makes are saved together, or not at
+    /// all if it fails. Stores that can't hold changes back just run it.
+    fn atomically(&self, work: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        work()
+    }
+
+    /// Returns the tags used across every `Task`, archived ones included,
+    /// along with how many use each
+    fn tag_counts(&self) -> Result<Vec<(String, usize)>> {
+        let mut tasks = self.tasks()?.tasks;
+        tasks.extend(self.archived()?.tasks);
+        Ok(tag_counts(&tasks))
+    }
+
+    // What's below keeps its own tables beside the tasks, so only SQLite has it
+
+    /// Returns how many `Task`s were added, changed, deleted, or archived after
+    /// `since`, or None if the store doesn't keep track
+    fn changed_since(&self, _since: &DateTime<Local>) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
+    /// Takes the `from` tags off every `Task`, archived ones included,
+    /// putting the `to` tag on in their place if there is one
+    fn retag(&self, _from: &[String], _to: Option<&str>) -> Result<Retagged> {
+        Err(ChecklistError::NeedsSqlite("Renaming and deleting tags").into())
+    }
+
+    /// Returns every dependency between `Task`s, as (task, blocker) pairs
+    fn dependencies(&self) -> Result<Vec<(Uuid, Uuid)>> {
+        Err(ChecklistError::NeedsSqlite("The dependency graph").into())
+    }
+
+    /// Returns what happened to the `Task` with the id, oldest first
+    fn task_events(&self, _id: Uuid) -> Result<Vec<TaskEvent>> {
+        Err(ChecklistError::NeedsSqlite("The task history").into())
+    }
+
+    /// Returns when each `Task` was last changed
+    fn last_touched(&self) -> Result<HashMap<Uuid, DateTime<Local>>> {
+        Err(ChecklistError::NeedsSqlite("Reminding about tasks left Working").into())
+    }
+
+    /// Holds off reminders about the `Task` with the id until then
+    fn snooze_reminders(&self, _id: Uuid, _until: &DateTime<Local>) -> Result<()> {
+        Err(ChecklistError::NeedsSqlite("Snoozing reminders").into())
+    }
+
+    /// Returns the ids of the `Task`s with reminders snoozed as of `now`
+    fn snoozed_reminders(&self, _now: &DateTime<Local>) -> Result<HashSet<Uuid>> {
+        Err(ChecklistError::NeedsSqlite("Reminders").into())
+    }
+
+    /// Sends desktop notifications for the `Task`s due within `days`, as
+    /// `notify_due_tasks` does
+    fn notify_due(&self, _days: u64, _quiet: Option<QuietHours>) -> Result<Delivery> {
+        Err(ChecklistError::NeedsSqlite("Desktop notifications").into())
+    }
+
+    /// Copies every `Task` into the database `to`, as `promote_tasks` does,
+    /// returning how many were copied and how many skipped
+    fn promote_into(&self, _to: &Connection) -> Result<(usize, usize)> {
+        Err(ChecklistError::NeedsSqlite("Promoting tasks").into())
+    }
+}
+
+/// Opens wherever the `Config` keeps its tasks: the SQLite database (unlocked
+/// with the `passphrase` if it's encrypted), or the folder of task files
+pub fn open_store(config: &Config, passphrase: Option<&str>) -> Result<Box<dyn TaskStore>> {
+    match config.storage {
+        Storage::Sqlite => Ok(Box::new(open_db(config, passphrase)?)),
+        Storage::Postgres => open_postgres(config),
+        storage => Ok(Box::new(FlatFileStore::open(&config.tasks_dir(), storage)?)),
+    }
+}
+
+/// Connects to the PostgreSQL database at the `postgres_url` of the `Config`
+#[cfg(feature = "postgres")]
+fn open_postgres(config: &Config) -> Result<Box<dyn TaskStore>> {
+    let Some(url) = &config.postgres_url else {
+        bail!("Set \"postgres_url\" in the config file to keep the tasks in PostgreSQL");
+    };
+    Ok(Box::new(PostgresStore::connect(url)?))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_config: &Config) -> Result<Box<dyn TaskStore>> {
+    bail!("Keeping the tasks in PostgreSQL needs checklist built with the postgres feature")
+}
+
+/// Keeps only the tasks whose name, description, or latest update have every
+/// word of the `text` in them, for stores that can't search on their own
+pub fn search_in(task_list: &mut TaskList, text: &str) {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    task_list.tasks.retain(|task| {
+        let haystack = [
+            Some(task.name.as_str()),
+            task.description.as_deref(),
+            task.latest.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .to_lowercase();
+        words.iter().all(|word| haystack.contains(word))
+    });
+}
+
+// Changes are tried again while another checklist has the database locked
+impl TaskStore for Connection {
+    fn add(&self, task: &Task) -> Result<()> {
+        retry_busy(|| add_to_db(self, task))
+    }
+
+    fn update(&self, task: &Task) -> Result<()> {
+        retry_busy(|| update_task_in_db(self, task))
+    }
+
+    fn delete(&self, task: &Task) -> Result<()> {
+        retry_busy(|| delete_task_in_db(self, task))
+    }
+
+    fn archive(&self, tasks: &[Task]) -> Result<()> {
+        retry_busy(|| archive_tasks_in_db(self, tasks))
+    }
+
+    fn tasks(&self) -> Result<TaskList> {
+        get_all_db_contents(self)
+    }
+
+    fn archived(&self) -> Result<TaskList> {
+        get_archive_contents(self)
+    }
+
+    fn count_archived(&self) -> Result<usize> {
+        count_archived(self)
+    }
+
+    fn select(&self, select: &TaskSelect) -> Result<TaskList> {
+        select.run(self)
+    }
+
+    fn count(&self, select: &TaskSelect) -> Result<usize> {
+        select.count(self)
+    }
+
+    fn position(&self, select: &TaskSelect, id: Uuid) -> Result<Option<usize>> {
+        select.position(self, id)
+    }
+
+    fn has_next_occurrence(&self, id: Uuid) -> Result<bool> {
+        has_next_occurrence_in_db(self, id)
+    }
+
+    fn subtask_counts(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, usize>> {
+        subtask_counts_in_db(self, ids)
+    }
+
+    fn roll_up(&self, ids: &[Uuid], fields: &[RollUp]) -> Result<HashMap<Uuid, RolledUp>> {
+        roll_up_in_db(self, ids, fields)
+    }
+
+    fn atomically(&self, work: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        // Waits for other writers to finish up front, instead of failing part
+        // way through. Only starting is tried again, the work is run just once.
+        let tx = retry_busy(|| {
+            Transaction::new_unchecked(self, TransactionBehavior::Immediate)
+                .context("Failed to start saving the changes")
+        })?;
+        work()?;
+        tx.commit().context("Failed to save the changes")
+    }
+
+    fn tag_counts(&self) -> Result<Vec<(String, usize)>> {
+        all_tag_counts(self)
+    }
+
+    fn changed_since(&self, since: &DateTime<Local>) -> Result<Option<usize>> {
+        count_changed_since(self, since).map(Some)
+    }
+
+    fn retag(&self, from: &[String], to: Option<&str>) -> Result<Retagged> {
+        retry_busy(|| retag(self, from, to))
+    }
+
+    fn dependencies(&self) -> Result<Vec<(Uuid, Uuid)>> {
+        get_dependencies(self)
+    }
+
+    fn task_events(&self, id: Uuid) -> Result<Vec<TaskEvent>> {
+        get_task_events(self, id)
+    }
+
+    fn last_touched(&self) -> Result<HashMap<Uuid, DateTime<Local>>> {
+        get_last_touched(self)
+    }
+
+    fn snooze_reminders(&self, id: Uuid, until: &DateTime<Local>) -> Result<()> {
+        retry_busy(|| snooze_reminders_in_db(self, id, until))
+    }
+
+    fn snoozed_reminders(&self, now: &DateTime<Local>) -> Result<HashSet<Uuid>> {
+        get_snoozed_reminders(self, now)
+    }
+
+    fn notify_due(&self, days: u64, quiet: Option<QuietHours>) -> Result<Delivery> {
+        notify_due_tasks(self, days, quiet)
+    }
+
+    fn promote_into(&self, to: &Connection) -> Result<(usize, usize)> {
+        promote_tasks(self, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::database::get_db;
+    use crate::backend::task::Status;
+
+    #[test]
+    fn test_sqlite_store() {
+        let conn = get_db(true, false).unwrap();
+        let store: &dyn TaskStore = &conn;
+        let mut task = Task::new(String::from("Taxes"), None, None, None, None, None);
+        store.add(&task).unwrap();
+        task.status = Status::Completed;
+        store.update(&task).unwrap();
+        assert_eq!(store.tasks().unwrap().tasks, vec![task.clone()]);
+        assert_eq!(
+            store
+                .select(&TaskSelect::new().search("tax"))
+                .unwrap()
+                .tasks,
+            vec![task.clone()]
+        );
+
+        // Nothing is kept from work that fails part way
+        let extra = Task::new(String::from("Extra"), None, None, None, None, None);
+        let failed = store.atomically(&mut || {
+            store.add(&extra)?;
+            bail!("Changed my mind")
+        });
+        assert!(failed.is_err());
+        assert_eq!(store.tasks().unwrap().tasks, vec![task.clone()]);
+
+        store.archive(std::slice::from_ref(&task)).unwrap();
+        assert!(store.tasks().unwrap().tasks.is_empty());
+        assert_eq!(store.count_archived().unwrap(), 1);
+        assert_eq!(store.archived().unwrap().tasks, vec![task]);
+        assert!(store.dependencies().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,822 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::backend::backup::backup_before;
+use crate::backend::cli::{complain, say};
+use crate::backend::database::{
+    archive_task_in_db, archive_tasks_in_db, delete_task_in_db, get_all_db_contents, get_deleted,
+    get_modified, get_task_modified, put_synced_task, Gone, TaskSelect,
+};
+use crate::backend::export::{exact_time, task_row, TASK_FIELDS};
+use crate::backend::http::{
+    read_request, send, write_response, DeadlineReader, Request, Response, TIMEOUT,
+};
+use crate::backend::import::{parse_time, task_from_record};
+use crate::backend::task::Task;
+
+/// Address `checklist serve` listens on unless told otherwise
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:7373";
+
+/// Every `Task` in a copy of the database with when it last changed,
+/// and when each one that has gone (deleted or archived) went
+#[derive(Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub tasks: HashMap<Uuid, (Task, DateTime<Local>)>,
+    pub deleted: HashMap<Uuid, Gone>,
+}
+
+impl Snapshot {
+    /// Reads the `Snapshot` of the database on the `&Connection` given
+    pub fn read(conn: &Connection) -> Result<Self> {
+        let modified = get_modified(conn)?;
+        let tasks = get_all_db_contents(conn)?
+            .tasks
+            .into_iter()
+            .map(|task| {
+                let changed = modified
+                    .get(&task.get_id())
+                    .copied()
+                    .unwrap_or(task.date_added);
+                (task.get_id(), (task, changed))
+            })
+            .collect();
+        Ok(Snapshot {
+            tasks,
+            deleted: get_deleted(conn)?,
+        })
+    }
+
+    /// Returns the `Snapshot` as JSON, with the `Task`s sorted by id
+    pub fn to_json(&self) -> Value {
+        let mut tasks: Vec<&(Task, DateTime<Local>)> = self.tasks.values().collect();
+        tasks.sort_by_key(|(task, _)| task.get_id());
+        let deleted: Map<String, Value> = self
+            .deleted
+            .iter()
+            .map(|(id, gone)| {
+                let gone = json!({ "deleted": exact_time(&gone.when), "archived": gone.archived });
+                (id.to_string(), gone)
+            })
+            .collect();
+        json!({
+            "tasks": tasks
+                .into_iter()
+                .map(|(task, modified)| task_json(task, modified))
+                .collect::<Vec<Value>>(),
+            "deleted": deleted,
+        })
+    }
+
+    /// Reads a `Snapshot` back from the JSON `to_json` makes
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text).context("The tasks aren't valid JSON")?;
+        let Some(tasks) = value["tasks"].as_array() else {
+            bail!("There are no tasks in what the server sent");
+        };
+        let mut snapshot = Snapshot::default();
+        for task in tasks {
+            let (task, modified) = task_from_json(task, None)?;
+            let Some(modified) = modified else {
+                bail!("'{}' is missing when it last changed", task.name);
+            };
+            snapshot.tasks.insert(task.get_id(), (task, modified));
+        }
+        for (id, gone) in value["deleted"].as_object().into_iter().flatten() {
+            let id = Uuid::parse_str(id).with_context(|| format!("'{}' is not a valid id", id))?;
+            // Servers from before archiving was synced only say when it went
+            let (when, archived) = match gone {
+                Value::String(when) => (when.as_str(), false),
+                _ => match (gone["deleted"].as_str(), gone["archived"].as_bool()) {
+                    (Some(when), Some(archived)) => (when, archived),
+                    _ => bail!("When {} was deleted should be a string", id),
+                },
+            };
+            let when = parse_time(when)?;
+            snapshot.deleted.insert(id, Gone { when, archived });
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Returns the `Task` as a JSON object with a string for each of the `TASK_FIELDS`,
+/// written the same way as in the CSV export, along with when it last changed
+pub fn task_json(task: &Task, modified: &DateTime<Local>) -> Value {
+    let mut object: Map<String, Value> = TASK_FIELDS
+        .iter()
+        .zip(task_row(task))
+        .map(|(field, value)| (field.to_string(), value.into()))
+        .collect();
+    object.insert("modified".to_string(), exact_time(modified).into());
+    Value::Object(object)
+}
+
+/// Builds a `Task` from a JSON object like the ones `task_json` makes, and when it
+/// last changed if that's in there. Only `name` is needed. When an `id` is given,
+/// the `Task` gets it, and the object can't have a different one.
+pub fn task_from_json(value: &Value, id: Option<Uuid>) -> Result<(Task, Option<DateTime<Local>>)> {
+    let Some(object) = value.as_object() else {
+        bail!("A task should be a JSON object");
+    };
+    let mut header = vec![];
+    let mut record = vec![];
+    let mut modified = None;
+    for (field, value) in object {
+        let Some(value) = value.as_str() else {
+            bail!("'{}' should be a string", field);
+        };
+        if field == "modified" {
+            if !value.is_empty() {
+                modified = Some(parse_time(value)?);
+            }
+            continue;
+        }
+        if !TASK_FIELDS.contains(&field.as_str()) {
+            bail!(
+                "Unknown field '{}', the fields a task has are: {}",
+                field,
+                TASK_FIELDS.join(", ")
+            );
+        }
+        header.push(field.clone());
+        record.push(value.to_string());
+    }
+    if let Some(id) = id {
+        match header.iter().position(|field| field == "id") {
+            Some(i) if Uuid::parse_str(&record[i]).ok() != Some(id) => {
+                bail!("The task's id doesn't match the {} in the path", id)
+            }
+            Some(_) => {}
+            None => {
+                header.push("id".to_string());
+                record.push(id.to_string());
+            }
+        }
+    }
+    Ok((task_from_record(&header, &record)?, modified))
+}
+
+/// Returns the `Task` with the id and when it last changed, if it's in the database
+fn find_task(conn: &Connection, id: Uuid) -> Result<Option<(Task, DateTime<Local>)>> {
+    let Some(task) = TaskSelect::new().ids(&[id]).run(conn)?.tasks.pop() else {
+        return Ok(None);
+    };
+    let modified = get_task_modified(conn, id)?.unwrap_or(task.date_added);
+    Ok(Some((task, modified)))
+}
+
+/// Answers a request to the server, which is one of:
+///
+/// - `GET /tasks`: every task, and when each deleted one went
+/// - `POST /tasks`: adds a task
+/// - `GET /tasks/<id>`: a task
+/// - `PUT /tasks/<id>`: replaces a task, adding it if it isn't there
+/// - `DELETE /tasks/<id>`: deletes a task
+/// - `POST /tasks/<id>/archive`: archives a task
+///
+/// Tasks sent in keep their `modified` time if they have one, and otherwise
+/// count as changed just now.
+pub fn handle_request(conn: &Connection, request: &Request) -> Result<Response> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let id = match segments[..] {
+        ["tasks", id] | ["tasks", id, "archive"] => match Uuid::parse_str(id) {
+            Ok(id) => Some(id),
+            Err(_) => return Ok(Response::error(400, format!("'{}' is not a valid id", id))),
+        },
+        _ => None,
+    };
+    let body = || -> Result<Value> {
+        serde_json::from_str(&request.body).context("The task isn't valid JSON")
+    };
+
+    match (request.method.as_str(), &segments[..], id) {
+        ("GET", ["tasks"], _) => Ok(Response::new(
+            200,
+            Snapshot::read(conn)?.to_json().to_string(),
+        )),
+        ("POST", ["tasks"], _) => {
+            let (task, modified) = match body().and_then(|body| task_from_json(&body, None)) {
+                Ok(parsed) => parsed,
+                Err(e) => return Ok(Response::error(400, format!("{:#}", e))),
+            };
+            if find_task(conn, task.get_id())?.is_some() {
+                return Ok(Response::error(
+                    409,
+                    format!("There is already a task with the id {}", task.get_id()),
+                ));
+            }
+            let modified = modified.unwrap_or_else(Local::now);
+            put_synced_task(conn, &task, &modified)?;
+            Ok(Response::new(201, task_json(&task, &modified).to_string()))
+        }
+        ("GET", ["tasks", _], Some(id)) => match find_task(conn, id)? {
+            Some((task, modified)) => {
+                Ok(Response::new(200, task_json(&task, &modified).to_string()))
+            }
+            None => Ok(Response::error(
+                404,
+                format!("There is no task with the id {}", id),
+            )),
+        },
+        ("PUT", ["tasks", _], Some(id)) => {
+            let (task, modified) = match body().and_then(|body| task_from_json(&body, Some(id))) {
+                Ok(parsed) => parsed,
+                Err(e) => return Ok(Response::error(400, format!("{:#}", e))),
+            };
+            let existed = find_task(conn, id)?.is_some();
+            let modified = modified.unwrap_or_else(Local::now);
+            put_synced_task(conn, &task, &modified)?;
+            let status = if existed { 200 } else { 201 };
+            Ok(Response::new(
+                status,
+                task_json(&task, &modified).to_string(),
+            ))
+        }
+        ("DELETE", ["tasks", _], Some(id)) => match find_task(conn, id)? {
+            Some((task, _)) => {
+                delete_task_in_db(conn, &task)?;
+                Ok(Response::new(204, ""))
+            }
+            None => Ok(Response::error(
+                404,
+                format!("There is no task with the id {}", id),
+            )),
+        },
+        ("POST", ["tasks", _, "archive"], Some(id)) => match find_task(conn, id)? {
+            Some((task, _)) => {
+                archive_tasks_in_db(conn, &[task])?;
+                Ok(Response::new(204, ""))
+            }
+            None => Ok(Response::error(
+                404,
+                format!("There is no task with the id {}", id),
+            )),
+        },
+        (_, ["tasks"], _) | (_, ["tasks", _], _) | (_, ["tasks", _, "archive"], _) => Ok(
+            Response::error(405, format!("{} isn't allowed on {}", request.method, path)),
+        ),
+        _ => Ok(Response::error(
+            404,
+            format!("There is nothing at {}", path),
+        )),
+    }
+}
+
+/// Returns true if the two are the same, taking as long to say so wherever
+/// they differ, so the token can't be guessed a byte at a time from timings
+fn same_secret(given: &[u8], expected: &[u8]) -> bool {
+    let differences = given
+        .iter()
+        .zip(expected)
+        .fold(0, |differences, (a, b)| differences | (a ^ b));
+    differences == 0 && given.len() == expected.len()
+}
+
+/// Returns true if the request has the token in it, or there isn't one to have
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    match token {
+        Some(token) => request
+            .header("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|given| same_secret(given.as_bytes(), token.as_bytes())),
+        None => true,
+    }
+}
+
+/// Reads a request off the connection and writes back the response to it
+fn handle_connection(conn: &Connection, stream: &TcpStream, token: Option<&str>) -> Result<()> {
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let response = match read_request(&mut BufReader::new(DeadlineReader::new(stream))) {
+        Ok(request) if !authorized(&request, token) => {
+            Response::error(401, "The token is missing or wrong")
+        }
+        Ok(request) => handle_request(conn, &request)
+            .unwrap_or_else(|e| Response::error(500, format!("{:#}", e))),
+        Err(e) => Response::error(400, format!("{:#}", e)),
+    };
+    let mut writer = stream;
+    write_response(&mut writer, &response)
+}
+
+/// Answers requests on the listener one at a time, for as long as it's open.
+/// Each request has `TIMEOUT` to arrive in, so a slow one can't hold up the rest.
+/// A request that goes wrong is complained about without stopping the server.
+pub fn serve_on(conn: &Connection, listener: &TcpListener, token: Option<&str>) -> Result<()> {
+    for stream in listener.incoming() {
+        let result = stream
+            .context("Failed to accept a connection")
+            .and_then(|stream| handle_connection(conn, &stream, token));
+        if let Err(e) = result {
+            warn!("Failed to answer a sync request: {:#}", e);
+            complain(format!("{:#}", e));
+        }
+    }
+    Ok(())
+}
+
+/// Serves the tasks in the database over HTTP at the address given, for
+/// `checklist sync` to sync with. Only requests with the token are answered,
+/// if there is one. Runs until checklist is stopped.
+pub fn serve(conn: &Connection, listen: &str, token: Option<&str>) -> Result<()> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    say(format!(
+        "Serving tasks on http://{}",
+        listener.local_addr()?
+    ));
+    if token.is_none() {
+        say("No sync_token is set, so anyone who can reach the server can change your tasks");
+    }
+    serve_on(conn, &listener, token)
+}
+
+/// What syncing with the server does to each copy of the database
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    /// `Task`s to bring over from the server, with when they last changed there
+    pub pull: Vec<(Task, DateTime<Local>)>,
+    /// `Task`s to send to the server, with when they last changed here
+    pub push: Vec<(Task, DateTime<Local>)>,
+    /// `Task`s deleted on the server since they last changed here
+    pub delete_local: Vec<Task>,
+    /// Ids of the `Task`s deleted here since they last changed on the server
+    pub delete_remote: Vec<Uuid>,
+    /// `Task`s archived on the server since they last changed here
+    pub archive_local: Vec<Task>,
+    /// Ids of the `Task`s archived here since they last changed on the server
+    pub archive_remote: Vec<Uuid>,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.pull.is_empty()
+            && self.push.is_empty()
+            && self.delete_local.is_empty()
+            && self.delete_remote.is_empty()
+            && self.archive_local.is_empty()
+            && self.archive_remote.is_empty()
+    }
+
+    /// Returns a line summing up what the sync did
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return String::from("Already in sync");
+        }
+        format!(
+            "Pulled {} task(s), pushed {}, deleted {} here and {} on the server, archived {} here and {} on the server",
+            self.pull.len(),
+            self.push.len(),
+            self.delete_local.len(),
+            self.delete_remote.len(),
+            self.archive_local.len(),
+            self.archive_remote.len()
+        )
+    }
+}
+
+/// Works out how to bring the local and remote copies of the database in line.
+/// Whichever copy of a `Task` changed last wins, with ties going to the server,
+/// and a `Task` deleted on one side stays deleted unless it changed on the other
+/// side after that. One archived on a side is archived on the other, the same way. Since times are compared across machines, their clocks need
+/// to roughly agree.
+pub fn reconcile(local: &Snapshot, remote: &Snapshot) -> SyncPlan {
+    let ids: BTreeSet<&Uuid> = local.tasks.keys().chain(remote.tasks.keys()).collect();
+    let mut plan = SyncPlan::default();
+    for id in ids {
+        match (local.tasks.get(id), remote.tasks.get(id)) {
+            (Some((ours, ours_modified)), Some((theirs, theirs_modified))) => {
+                if ours == theirs {
+                    continue;
+                }
+                if ours_modified > theirs_modified {
+                    plan.push.push((ours.clone(), *ours_modified));
+                } else {
+                    plan.pull.push((theirs.clone(), *theirs_modified));
+                }
+            }
+            (Some((ours, ours_modified)), None) => match remote.deleted.get(id) {
+                Some(gone) if gone.when >= *ours_modified && gone.archived => {
+                    plan.archive_local.push(ours.clone())
+                }
+                Some(gone) if gone.when >= *ours_modified => plan.delete_local.push(ours.clone()),
+                _ => plan.push.push((ours.clone(), *ours_modified)),
+            },
+            (None, Some((theirs, theirs_modified))) => match local.deleted.get(id) {
+                Some(gone) if gone.when >= *theirs_modified && gone.archived => {
+                    plan.archive_remote.push(*id)
+                }
+                Some(gone) if gone.when >= *theirs_modified => plan.delete_remote.push(*id),
+                _ => plan.pull.push((theirs.clone(), *theirs_modified)),
+            },
+            (None, None) => {}
+        }
+    }
+    plan
+}
+
+/// Fails with the server's error message unless the response was a success
+fn expect_success(response: Response, what: &str) -> Result<Response> {
+    if (200..300).contains(&response.status) {
+        return Ok(response);
+    }
+    let message = serde_json::from_str::<Value>(&response.body)
+        .ok()
+        .and_then(|body| body["error"].as_str().map(String::from))
+        .unwrap_or(response.body);
+    bail!(
+        "Failed to {}, the server said {}: {}",
+        what,
+        response.status,
+        message
+    )
+}
+
+/// Syncs the database with the `checklist serve` server at the URL, sending
+/// the token if there is one. Changes are sent to the server before any are
+/// made here, and the ones made here are made all together or not at all.
+/// If anything here is going to change, the database is backed up first,
+/// keeping the newest `backup_count` backups.
+pub fn sync_tasks(
+    conn: &Connection,
+    url: &str,
+    token: Option<&str>,
+    backup_count: usize,
+) -> Result<SyncPlan> {
+    let response = expect_success(send(url, "GET", "/tasks", None, token)?, "get the tasks")?;
+    let remote = Snapshot::from_json(&response.body)?;
+    let plan = reconcile(&Snapshot::read(conn)?, &remote);
+
+    for (task, modified) in &plan.push {
+        let body = task_json(task, modified).to_string();
+        let path = format!("/tasks/{}", task.get_id());
+        expect_success(
+            send(url, "PUT", &path, Some(&body), token)?,
+            &format!("send '{}'", task.name),
+        )?;
+    }
+    for id in &plan.delete_remote {
+        let response = send(url, "DELETE", &format!("/tasks/{}", id), None, token)?;
+        // Someone else may have deleted it in the meantime
+        if response.status != 404 {
+            expect_success(response, &format!("delete {}", id))?;
+        }
+    }
+    for id in &plan.archive_remote {
+        let response = send(url, "POST", &format!("/tasks/{}/archive", id), None, token)?;
+        // Or archived it
+        if response.status != 404 {
+            expect_success(response, &format!("archive {}", id))?;
+        }
+    }
+
+    apply_plan(conn, &plan, backup_count)?;
+    info!("Synced with {}: {}", url, plan.summary());
+    Ok(plan)
+}
+
+/// Makes the changes the `SyncPlan` has for this copy of the database, all
+/// together or not at all. If there are any, the database is backed up first,
+/// keeping the newest `backup_count` backups.
+pub fn apply_plan(conn: &Connection, plan: &SyncPlan, backup_count: usize) -> Result<()> {
+    if plan.pull.is_empty() && plan.delete_local.is_empty() && plan.archive_local.is_empty() {
+        return Ok(());
+    }
+    if let Some(backup) = backup_before(conn, "sync", backup_count)? {
+        say(format!("Backed up the database to {:?}", backup));
+    }
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start syncing tasks")?;
+    for (task, modified) in &plan.pull {
+        put_synced_task(&tx, task, modified)?;
+    }
+    for task in &plan.delete_local {
+        delete_task_in_db(&tx, task)?;
+    }
+    for task in &plan.archive_local {
+        archive_task_in_db(&tx, task)?;
+    }
+    tx.commit().context("Failed to sync tasks")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::backend::database::{
+        add_to_db, get_archive_contents, get_db, set_modified, update_task_in_db,
+    };
+    use crate::backend::task::Urgency;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_task_json() {
+        let mut task = Task::new(
+            "Taxes".to_string(),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            None,
+        );
+        task.project = Some("home".to_string());
+        let modified = Local::now();
+        let value = task_json(&task, &modified);
+        assert_eq!(value["name"], "Taxes");
+        assert_eq!(
+            task_from_json(&value, None).unwrap(),
+            (task.clone(), Some(modified))
+        );
+
+        // The id in the path fills in a missing one, but can't contradict one
+        let id = Uuid::new_v4();
+        let (named, modified) = task_from_json(&json!({ "name": "Rent" }), Some(id)).unwrap();
+        assert_eq!((named.get_id(), modified), (id, None));
+        assert!(task_from_json(&value, Some(id)).is_err());
+        assert!(task_from_json(&json!({ "name": "Rent", "colour": "red" }), None).is_err());
+        assert!(task_from_json(&json!({ "name": 42 }), None).is_err());
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let now = Local::now();
+        let earlier = now - TimeDelta::hours(1);
+        let shared = Task::new("Shared".to_string(), None, None, None, None, None);
+        let mut ours = shared.clone();
+        ours.urgency = Urgency::Critical;
+        let only_ours = Task::new("Only ours".to_string(), None, None, None, None, None);
+        let only_theirs = Task::new("Only theirs".to_string(), None, None, None, None, None);
+        let gone_there = Task::new("Gone there".to_string(), None, None, None, None, None);
+        let gone_here = Task::new("Gone here".to_string(), None, None, None, None, None);
+        let archived_there = Task::new("Archived there".to_string(), None, None, None, None, None);
+        let deleted = |when| Gone {
+            when,
+            archived: false,
+        };
+
+        let mut local = Snapshot::default();
+        let mut remote = Snapshot::default();
+        for (task, modified) in [
+            (&ours, now),
+            (&only_ours, now),
+            (&gone_there, earlier),
+            (&archived_there, earlier),
+        ] {
+            local.tasks.insert(task.get_id(), (task.clone(), modified));
+        }
+        local.deleted.insert(gone_here.get_id(), deleted(now));
+        for (task, modified) in [
+            (&shared, earlier),
+            (&only_theirs, now),
+            (&gone_here, earlier),
+        ] {
+            remote.tasks.insert(task.get_id(), (task.clone(), modified));
+        }
+        remote.deleted.insert(gone_there.get_id(), deleted(now));
+        let archived = Gone {
+            when: now,
+            archived: true,
+        };
+        remote.deleted.insert(archived_there.get_id(), archived);
+
+        let plan = reconcile(&local, &remote);
+        let mut pushed: Vec<&str> = plan
+            .push
+            .iter()
+            .map(|(task, _)| task.name.as_str())
+            .collect();
+        pushed.sort();
+        assert_eq!(pushed, vec!["Only ours", "Shared"]);
+        assert_eq!(plan.pull, vec![(only_theirs.clone(), now)]);
+        assert_eq!(plan.delete_local, vec![gone_there.clone()]);
+        assert_eq!(plan.delete_remote, vec![gone_here.get_id()]);
+        // An archived task is archived on the other side, not deleted
+        assert_eq!(plan.archive_local, vec![archived_there.clone()]);
+        assert!(plan.archive_remote.is_empty());
+
+        // Changes made after a deletion bring the task back, and ties go to the server
+        remote.tasks.insert(shared.get_id(), (shared.clone(), now));
+        local
+            .deleted
+            .insert(gone_here.get_id(), deleted(earlier - TimeDelta::hours(1)));
+        let plan = reconcile(&local, &remote);
+        let mut pulled: Vec<&str> = plan
+            .pull
+            .iter()
+            .map(|(task, _)| task.name.as_str())
+            .collect();
+        pulled.sort();
+        assert_eq!(pulled, vec!["Gone here", "Only theirs", "Shared"]);
+        assert!(plan.delete_remote.is_empty());
+
+        assert!(reconcile(&remote, &remote).is_empty());
+
+        // What went is sent along with whether it was archived, and servers
+        // from before that count everything as deleted
+        let sent = Snapshot::from_json(&remote.to_json().to_string()).unwrap();
+        assert_eq!(sent.deleted, remote.deleted);
+        let when = exact_time(&now);
+        let older = json!({ "tasks": [], "deleted": { gone_there.get_id().to_string(): when } });
+        let sent = Snapshot::from_json(&older.to_string()).unwrap();
+        assert_eq!(
+            sent.deleted[&gone_there.get_id()],
+            deleted(parse_time(&when).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_authorized() {
+        let mut with_token = request("GET", "/tasks", "");
+        with_token.headers = vec![("authorization".to_string(), "Bearer hunter2".to_string())];
+        assert!(authorized(&with_token, Some("hunter2")));
+        assert!(!authorized(&with_token, Some("hunter3")));
+        assert!(!authorized(&with_token, Some("hunter22")));
+        assert!(!authorized(&request("GET", "/tasks", ""), Some("hunter2")));
+        assert!(authorized(&request("GET", "/tasks", ""), None));
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let conn = get_db(true, false).unwrap();
+        let task = Task::new("Taxes".to_string(), None, None, None, None, None);
+        let path = format!("/tasks/{}", task.get_id());
+
+        let body = task_json(&task, &Local::now()).to_string();
+        assert_eq!(
+            handle_request(&conn, &request("POST", "/tasks", &body))
+                .unwrap()
+                .status,
+            201
+        );
+        assert_eq!(
+            handle_request(&conn, &request("POST", "/tasks", &body))
+                .unwrap()
+                .status,
+            409
+        );
+        let response = handle_request(&conn, &request("GET", &path, "")).unwrap();
+        assert_eq!(
+            task_from_json(&serde_json::from_str(&response.body).unwrap(), None)
+                .unwrap()
+                .0,
+            task
+        );
+
+        let response =
+            handle_request(&conn, &request("PUT", &path, r#"{"name": "Do taxes"}"#)).unwrap();
+        assert_eq!(response.status, 200);
+        let snapshot = Snapshot::from_json(
+            &handle_request(&conn, &request("GET", "/tasks", ""))
+                .unwrap()
+                .body,
+        )
+        .unwrap();
+        assert_eq!(snapshot.tasks[&task.get_id()].0.name, "Do taxes");
+
+        assert_eq!(
+            handle_request(&conn, &request("DELETE", &path, ""))
+                .unwrap()
+                .status,
+            204
+        );
+        assert_eq!(
+            handle_request(&conn, &request("DELETE", &path, ""))
+                .unwrap()
+                .status,
+            404
+        );
+        let snapshot = Snapshot::read(&conn).unwrap();
+        assert!(snapshot.tasks.is_empty());
+        assert!(!snapshot.deleted[&task.get_id()].archived);
+
+        put_synced_task(&conn, &task, &Local::now()).unwrap();
+        let archive = format!("{}/archive", path);
+        assert_eq!(
+            handle_request(&conn, &request("POST", &archive, ""))
+                .unwrap()
+                .status,
+            204
+        );
+        assert_eq!(
+            handle_request(&conn, &request("POST", &archive, ""))
+                .unwrap()
+                .status,
+            404
+        );
+        assert!(Snapshot::read(&conn).unwrap().deleted[&task.get_id()].archived);
+        assert_eq!(
+            handle_request(&conn, &request("GET", &archive, ""))
+                .unwrap()
+                .status,
+            405
+        );
+
+        assert_eq!(
+            handle_request(&conn, &request("GET", "/tasks/42", ""))
+                .unwrap()
+                .status,
+            400
+        );
+        assert_eq!(
+            handle_request(&conn, &request("PATCH", "/tasks", ""))
+                .unwrap()
+                .status,
+            405
+        );
+        assert_eq!(
+            handle_request(&conn, &request("GET", "/", ""))
+                .unwrap()
+                .status,
+            404
+        );
+        assert_eq!(
+            handle_request(&conn, &request("PUT", &path, "{"))
+                .unwrap()
+                .status,
+            400
+        );
+    }
+
+    #[test]
+    fn test_sync_with_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let conn = get_db(true, false).unwrap();
+            serve_on(&conn, &listener, Some("hunter2")).unwrap();
+        });
+        let laptop = get_db(true, false).unwrap();
+        let desktop = get_db(true, false).unwrap();
+
+        let mut taxes = Task::new("Taxes".to_string(), None, None, None, None, None);
+        let rent = Task::new("Rent".to_string(), None, None, None, None, None);
+        let added = Local::now() - TimeDelta::days(2);
+        for task in [&taxes, &rent] {
+            add_to_db(&laptop, task).unwrap();
+            set_modified(&laptop, task.get_id(), &added).unwrap();
+        }
+        assert!(sync_tasks(&laptop, &url, None, 0).is_err());
+        assert_eq!(
+            sync_tasks(&laptop, &url, Some("hunter2"), 0)
+                .unwrap()
+                .push
+                .len(),
+            2
+        );
+        assert_eq!(
+            sync_tasks(&desktop, &url, Some("hunter2"), 0)
+                .unwrap()
+                .pull
+                .len(),
+            2
+        );
+
+        // The desktop's later change wins, and the laptop's deletion carries over
+        let long_ago = Local::now() - TimeDelta::days(1);
+        taxes.urgency = Urgency::Critical;
+        update_task_in_db(&laptop, &taxes).unwrap();
+        set_modified(&laptop, taxes.get_id(), &long_ago).unwrap();
+        taxes.urgency = Urgency::Medium;
+        update_task_in_db(&desktop, &taxes).unwrap();
+        delete_task_in_db(&laptop, &rent).unwrap();
+        sync_tasks(&desktop, &url, Some("hunter2"), 0).unwrap();
+        let plan = sync_tasks(&laptop, &url, Some("hunter2"), 0).unwrap();
+        assert_eq!(plan.delete_remote, vec![rent.get_id()]);
+        sync_tasks(&desktop, &url, Some("hunter2"), 0).unwrap();
+
+        for conn in [&laptop, &desktop] {
+            let tasks = get_all_db_contents(conn).unwrap().tasks;
+            assert_eq!(tasks, vec![taxes.clone()]);
+        }
+        assert!(sync_tasks(&laptop, &url, Some("hunter2"), 0)
+            .unwrap()
+            .is_empty());
+
+        // Archiving carries over as archiving
+        archive_tasks_in_db(&desktop, std::slice::from_ref(&taxes)).unwrap();
+        let plan = sync_tasks(&desktop, &url, Some("hunter2"), 0).unwrap();
+        assert_eq!(plan.archive_remote, vec![taxes.get_id()]);
+        let plan = sync_tasks(&laptop, &url, Some("hunter2"), 0).unwrap();
+        assert_eq!(plan.archive_local, vec![taxes.clone()]);
+        assert!(plan.delete_local.is_empty());
+        assert!(get_all_db_contents(&laptop).unwrap().tasks.is_empty());
+        assert_eq!(get_archive_contents(&laptop).unwrap().tasks, vec![taxes]);
+    }
+}
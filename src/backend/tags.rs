@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+
+use crate::backend::collate::collate;
+use crate::backend::database::{get_all_db_contents, get_archive_contents, update_tags_in_db};
+use crate::backend::task::Task;
+
+/// Returns every tag used by the `Task`s along with how many use it,
+/// most used first
+pub fn tag_counts(tasks: &[Task]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tag in tasks.iter().flat_map(|task| task.tags.iter().flatten()) {
+        *counts.entry(tag.as_str()).or_default() += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(tag, count)| (tag.to_string(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| collate(&a.0, &b.0)));
+    counts
+}
+
+/// Returns the tags used across every `Task`, archived ones included
+pub fn all_tag_counts(conn: &Connection) -> Result<Vec<(String, usize)>> {
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.extend(get_archive_contents(conn)?.tasks);
+    Ok(tag_counts(&tasks))
+}
+
+/// Checks the name can be used as a tag. Tags are stored joined by ';',
+/// so they can't have one in them.
+pub fn check_tag_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("A tag can't be empty");
+    }
+    if name.contains(';') {
+        bail!("A tag can't have ';' in it");
+    }
+    Ok(())
+}
+
+/// How many `Task`s retagging changed, and how many it left alone for being locked
+#[derive(Debug, Default, PartialEq)]
+pub struct Retagged {
+    pub changed: usize,
+    pub locked: usize,
+}
+
+impl Retagged {
+    /// Describes what was done, i.e. "Renamed 'a' to 'b' on 3 task(s)"
+    pub fn message(&self, done: &str) -> String {
+        let mut message = format!("{} on {} task(s)", done, self.changed);
+        if self.locked > 0 {
+            message.push_str(&format!(", {} locked task(s) left as is", self.locked));
+        }
+        message
+    }
+}
+
+/// Takes the `from` tags off every `Task` (archived ones included), putting
+/// the `to` tag on in their place if there is one. Locked `Task`s are left as
+/// they are. Either every `Task` is retagged or none are.
+pub fn retag(conn: &Connection, from: &[String], to: Option<&str>) -> Result<Retagged> {
+    if let Some(to) = to {
+        check_tag_name(to)?;
+    }
+    let mut tasks = get_all_db_contents(conn)?.tasks;
+    tasks.extend(get_archive_contents(conn)?.tasks);
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start retagging tasks")?;
+    let mut retagged = Retagged::default();
+    for mut task in tasks {
+        let Some(tags) = task.tags.as_mut() else {
+            continue;
+        };
+        if !from.iter().any(|tag| tags.contains(tag)) {
+            continue;
+        }
+        if task.locked {
+            retagged.locked += 1;
+            continue;
+        }
+
+        tags.retain(|tag| !from.contains(tag));
+        if let Some(to) = to {
+            tags.insert(to.to_string());
+        }
+        if tags.is_empty() {
+            task.tags = None;
+        }
+        update_tags_in_db(&tx, &task)?;
+        retagged.changed += 1;
+    }
+    tx.commit().context("Failed to retag tasks")?;
+    Ok(retagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::backend::database::{add_to_db, archive_tasks_in_db, get_db};
+
+    fn tagged(name: &str, tags: &[&str]) -> Task {
+        Task::new(
+            name.to_string(),
+            None,
+            None,
+            None,
+            None,
+            Some(tags.iter().map(|tag| tag.to_string()).collect()),
+        )
+    }
+
+    #[test]
+    fn test_retag() {
+        let conn = get_db(true, false).unwrap();
+        add_to_db(&conn, &tagged("Fix bug", &["work", "urgent"])).unwrap();
+        add_to_db(&conn, &tagged("Call", &["Work"])).unwrap();
+        let old = tagged("Old", &["work"]);
+        add_to_db(&conn, &old).unwrap();
+        archive_tasks_in_db(&conn, std::slice::from_ref(&old)).unwrap();
+        let mut locked = tagged("Template", &["urgent"]);
+        locked.locked = true;
+        add_to_db(&conn, &locked).unwrap();
+
+        assert_eq!(
+            all_tag_counts(&conn).unwrap(),
+            vec![
+                (String::from("urgent"), 2),
+                (String::from("work"), 2),
+                (String::from("Work"), 1),
+            ]
+        );
+
+        // Merging into a tag a task already has doesn't double it up
+        let merged = retag(
+            &conn,
+            &[String::from("Work"), String::from("work")],
+            Some("job"),
+        )
+        .unwrap();
+        assert_eq!(
+            merged,
+            Retagged {
+                changed: 3,
+                locked: 0
+            }
+        );
+        let archived = &get_archive_contents(&conn).unwrap().tasks[0];
+        assert_eq!(archived.tags, Some(HashSet::from([String::from("job")])));
+
+        let removed = retag(&conn, &[String::from("urgent")], None).unwrap();
+        assert_eq!(
+            removed,
+            Retagged {
+                changed: 1,
+                locked: 1
+            }
+        );
+        assert_eq!(
+            removed.message("Removed 'urgent'"),
+            "Removed 'urgent' on 1 task(s), 1 locked task(s) left as is"
+        );
+        let tasks = get_all_db_contents(&conn).unwrap().tasks;
+        let template = tasks.iter().find(|task| task.name == "Template").unwrap();
+        assert_eq!(template.tags, Some(HashSet::from([String::from("urgent")])));
+
+        assert!(retag(&conn, &[String::from("job")], Some("a;b")).is_err());
+        assert!(check_tag_name("  ").is_err());
+    }
+}
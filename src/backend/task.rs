@@ -1,23 +1,102 @@
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 use std::string::ToString;
 
+use anyhow::{bail, Context, Result};
+
 use chrono::prelude::*;
+use chrono::{Days, Months, TimeDelta};
 use clap::ValueEnum;
 use crossterm::style::Stylize;
 use ratatui::widgets::ListState;
-use rusqlite::{types::FromSql, types::ValueRef, ToSql};
+use rusqlite::{types::FromSql, types::FromSqlError, types::ValueRef, ToSql};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::backend::calendar::{calendar, start_of_day, today};
+use crate::backend::collate::{collate, sort_text};
+use crate::backend::error::ChecklistError;
+
 /// Enum to help control what tasks are to be displayed
-#[derive(Clone, Copy, Debug, ValueEnum, strum_macros::Display, Serialize, Deserialize)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, ValueEnum, strum_macros::Display, Serialize, Deserialize,
+)]
 pub enum Display {
     All,
     Completed,
     NotCompleted,
 }
 
+/// How the tags in a tag filter are matched. Tags prefixed with `-` always
+/// rule a `Task` out, whichever is used.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, ValueEnum, strum_macros::Display, Serialize, Deserialize,
+)]
+pub enum TagMatch {
+    /// A `Task` needs any one of the tags
+    #[default]
+    Any,
+    /// A `Task` needs every one of the tags
+    All,
+}
+
+/// What happens to a parent `Task` once all of its subtasks are `Completed`.
+/// Whichever is used, reopening a subtask reopens its `Completed` parents.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, ValueEnum, strum_macros::Display, Serialize, Deserialize,
+)]
+pub enum CompleteParents {
+    /// Ask whether to complete the parent too
+    #[default]
+    Ask,
+    /// Complete the parent straight away
+    Always,
+    /// Leave the parent as it is
+    Never,
+}
+
+/// Fields a parent `Task` can show rolled up from its subtasks
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, ValueEnum, strum_macros::Display, Serialize, Deserialize,
+)]
+pub enum RollUp {
+    /// The highest urgency among the subtasks
+    Urgency,
+    /// The earliest due date among the subtasks
+    Due,
+    /// The minutes set aside for the subtasks' time slots, added up
+    Estimate,
+}
+
+/// What the `Task`s in the TUI are sorted on
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, ValueEnum, strum_macros::Display, Serialize, Deserialize,
+)]
+pub enum SortBy {
+    #[default]
+    Urgency,
+    /// When the `Task` was added
+    Added,
+    Due,
+    Name,
+    Status,
+}
+
+impl SortBy {
+    /// Will rotate through the different enum variants
+    pub fn next(&mut self) {
+        match self {
+            SortBy::Urgency => *self = SortBy::Added,
+            SortBy::Added => *self = SortBy::Due,
+            SortBy::Due => *self = SortBy::Name,
+            SortBy::Name => *self = SortBy::Status,
+            SortBy::Status => *self = SortBy::Urgency,
+        }
+    }
+}
+
 impl Display {
     /// Will rotate through the different enum variants
     pub fn next(&mut self) {
@@ -64,17 +143,17 @@ impl Urgency {
     }
 }
 
-impl From<&str> for Urgency {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Urgency {
+    type Error = ChecklistError;
+
+    /// Reads back an `Urgency` as it's stored, by its exact name
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "Low" => Urgency::Low,
-            "Medium" => Urgency::Medium,
-            "High" => Urgency::High,
-            "Critical" => Urgency::Critical,
-            _ => {
-                println!("String received was not a valid Urgency");
-                panic!()
-            }
+            "Low" => Ok(Urgency::Low),
+            "Medium" => Ok(Urgency::Medium),
+            "High" => Ok(Urgency::High),
+            "Critical" => Ok(Urgency::Critical),
+            _ => Err(ChecklistError::InvalidUrgency(s.to_string())),
         }
     }
 }
@@ -87,7 +166,10 @@ impl ToSql for Urgency {
 
 impl FromSql for Urgency {
     fn column_result(value: ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
-        value.as_str().map(Into::into)
+        value
+            .as_str()?
+            .try_into()
+            .map_err(|e: ChecklistError| FromSqlError::Other(Box::new(e)))
     }
 }
 
@@ -113,17 +195,17 @@ impl Status {
     }
 }
 
-impl From<&str> for Status {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Status {
+    type Error = ChecklistError;
+
+    /// Reads back a `Status` as it's stored, by its exact name
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "Open" => Status::Open,
-            "Working" => Status::Working,
-            "Paused" => Status::Paused,
-            "Completed" => Status::Completed,
-            _ => {
-                println!("String received wasn not a valid Status");
-                panic!()
-            }
+            "Open" => Ok(Status::Open),
+            "Working" => Ok(Status::Working),
+            "Paused" => Ok(Status::Paused),
+            "Completed" => Ok(Status::Completed),
+            _ => Err(ChecklistError::InvalidStatus(s.to_string())),
         }
     }
 }
@@ -136,10 +218,239 @@ impl ToSql for Status {
 
 impl FromSql for Status {
     fn column_result(value: ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
-        value.as_str().map(Into::into)
+        value
+            .as_str()?
+            .try_into()
+            .map_err(|e: ChecklistError| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+/// Enum to handle how often a `Task` repeats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    /// Once a month, on the day of the month it started on, or the last day
+    /// of the months too short to have it. Until the first occurrence is
+    /// lined up, the day is the one it's scheduled for.
+    Monthly(Option<u32>),
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    /// Returns the date one recurrence on from the `NaiveDate` given
+    pub fn next_date(self, from: NaiveDate) -> NaiveDate {
+        let next_date = match self {
+            Recurrence::Daily => from.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => from.checked_add_days(Days::new(7)),
+            Recurrence::Monthly(day) => {
+                let day = day.unwrap_or(from.day());
+                let next_month = from
+                    .with_day(1)
+                    .and_then(|first| first.checked_add_months(Months::new(1)));
+                next_month.and_then(|first| (1..=day).rev().find_map(|day| first.with_day(day)))
+            }
+            Recurrence::EveryNDays(n) => from.checked_add_days(Days::new(n as u64)),
+        };
+        next_date.unwrap_or(NaiveDate::MAX)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "Daily"),
+            Recurrence::Weekly => write!(f, "Weekly"),
+            Recurrence::Monthly(None) => write!(f, "Monthly"),
+            Recurrence::Monthly(Some(day)) => write!(f, "Monthly on day {}", day),
+            Recurrence::EveryNDays(n) => write!(f, "Every {} days", n),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    /// Parses "daily", "weekly", "monthly", "monthly on day N", or "every N days"
+    /// (case insensitive)
+    fn from_str(s: &str) -> Result<Self> {
+        let lowered = s.trim().to_lowercase();
+        match lowered.as_str() {
+            "daily" => Ok(Recurrence::Daily),
+            "weekly" => Ok(Recurrence::Weekly),
+            "monthly" => Ok(Recurrence::Monthly(None)),
+            _ => {
+                let words: Vec<&str> = lowered.split_whitespace().collect();
+                match words[..] {
+                    ["every", n, "days" | "day"] => {
+                        let days: u32 = n
+                            .parse()
+                            .with_context(|| format!("'{}' is not a number of days", n))?;
+                        if days == 0 {
+                            bail!("A recurrence needs to be at least every 1 day");
+                        }
+                        Ok(Recurrence::EveryNDays(days))
+                    }
+                    ["monthly", "on", "day", n] => {
+                        let day: u32 = n
+                            .parse()
+                            .with_context(|| format!("'{}' is not a day of the month", n))?;
+                        if !(1..=31).contains(&day) {
+                            bail!("A month only has days 1 to 31");
+                        }
+                        Ok(Recurrence::Monthly(Some(day)))
+                    }
+                    _ => bail!("'{}' is not a valid recurrence", s),
+                }
+            }
+        }
+    }
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Where a `Task` came from in another system, like a GitHub issue, a CalDAV
+/// event, or a Jira ticket. Importers match on the `system` and `id` so that
+/// bringing the same thing in again doesn't add it twice.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalRef {
+    pub system: String,
+    pub id: String,
+    pub url: Option<String>,
+}
+
+impl ExternalRef {
+    /// Returns true if both refer to the same thing in the same system
+    pub fn matches(&self, other: &ExternalRef) -> bool {
+        self.system.eq_ignore_ascii_case(&other.system) && self.id == other.id
+    }
+}
+
+impl fmt::Display for ExternalRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.system, self.id)
+    }
+}
+
+impl FromStr for ExternalRef {
+    type Err = anyhow::Error;
+
+    /// Parses a reference written as "system:id", i.e. "github:42"
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().split_once(':') {
+            Some((system, id)) if !system.is_empty() && !id.is_empty() => Ok(ExternalRef {
+                system: system.to_string(),
+                id: id.to_string(),
+                url: None,
+            }),
+            _ => bail!(
+                "'{}' is not a valid external reference, use SYSTEM:ID (i.e. github:42)",
+                s
+            ),
+        }
+    }
+}
+
+impl ToSql for ExternalRef {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        Ok(json.into())
+    }
+}
+
+impl FromSql for ExternalRef {
+    fn column_result(value: ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(e.into()))
     }
 }
 
+/// Something that has to hold before a `Task` counts as done, ticked off one
+/// at a time. Unlike subtasks, they aren't tasks of their own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Criterion {
+    pub text: String,
+    pub done: bool,
+}
+
+impl Criterion {
+    /// Creates an unticked `Criterion`, kept to one line
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.split_whitespace().collect::<Vec<&str>>().join(" "),
+            done: false,
+        }
+    }
+}
+
+/// Writes acceptance criteria one to a line, as "[x] text" or "[ ] text".
+/// Returns `None` if there aren't any.
+pub fn criteria_to_text(criteria: &[Criterion]) -> Option<String> {
+    if criteria.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = criteria
+        .iter()
+        .map(|criterion| {
+            let tick = if criterion.done { 'x' } else { ' ' };
+            format!("[{}] {}", tick, criterion.text)
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Reads acceptance criteria written the way `criteria_to_text` does.
+/// Lines without a box are taken as unticked, and blank ones are skipped.
+pub fn parse_criteria(text: &str) -> Vec<Criterion> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (done, rest) = match line.get(..3) {
+                Some("[x]") | Some("[X]") => (true, &line[3..]),
+                Some("[ ]") => (false, &line[3..]),
+                _ => (false, line),
+            };
+            Criterion {
+                done,
+                ..Criterion::new(rest)
+            }
+        })
+        .filter(|criterion| !criterion.text.is_empty())
+        .collect()
+}
+
+/// A stretch of time a `Task`'s timer ran for
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkSession {
+    pub task_id: Uuid,
+    pub started: DateTime<Local>,
+    pub ended: DateTime<Local>,
+}
+
+impl WorkSession {
+    /// Returns how many seconds the session lasted
+    pub fn seconds(&self) -> i64 {
+        (self.ended - self.started).num_seconds().max(0)
+    }
+}
+
+/// Tag that marks a `Task` as important, along with `Critical` urgency
+const IMPORTANT_TAG: &str = "important";
+
 /// Struct that holds the attributes to a Task
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Task {
@@ -152,6 +463,25 @@ pub struct Task {
     pub tags: Option<HashSet<String>>,
     pub date_added: DateTime<Local>,
     pub completed_on: Option<DateTime<Local>>,
+    pub recurrence: Option<Recurrence>,
+    pub project: Option<String>,
+    pub time_spent: i64,
+    pub timer_started: Option<DateTime<Local>>,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    pub external_ref: Option<ExternalRef>,
+    /// When the time slot set aside for the `Task` begins
+    pub scheduled_start: Option<DateTime<Local>>,
+    /// How many minutes the time slot lasts
+    pub duration: Option<i64>,
+    /// Locked `Task`s can't be updated or deleted until they are unlocked
+    pub locked: bool,
+    /// The `Task` this one is a subtask of
+    pub parent: Option<Uuid>,
+    /// What has to be ticked off before the `Task` can be `Completed`
+    pub criteria: Vec<Criterion>,
+    /// The occurrence of a recurring `Task` this one was lined up after
+    pub previous_occurrence: Option<Uuid>,
 }
 
 impl Task {
@@ -180,6 +510,19 @@ impl Task {
             } else {
                 None
             },
+            recurrence: None,
+            project: None,
+            time_spent: 0,
+            timer_started: None,
+            due: None,
+            scheduled: None,
+            external_ref: None,
+            scheduled_start: None,
+            duration: None,
+            locked: false,
+            parent: None,
+            criteria: vec![],
+            previous_occurrence: None,
         }
     }
 
@@ -191,6 +534,68 @@ impl Task {
         self.date_added
     }
 
+    /// Returns true if the `Task` is `Critical` or tagged `important`
+    /// (with or without a leading '#')
+    pub fn is_important(&self) -> bool {
+        self.urgency == Urgency::Critical
+            || self.tags.as_ref().is_some_and(|tags| {
+                tags.iter().any(|tag| {
+                    tag.trim_start_matches('#')
+                        .eq_ignore_ascii_case(IMPORTANT_TAG)
+                })
+            })
+    }
+
+    /// Returns a copy of the `Task` with its own id, added just now.
+    /// Tracked time, the external reference, and the lock stay with the original.
+    /// If `reopen` is true, the copy starts out `Open` with its criteria unticked.
+    pub fn duplicate(&self, reopen: bool) -> Task {
+        let mut copy = self.clone();
+        copy.id = Uuid::new_v4();
+        copy.date_added = Local::now();
+        copy.time_spent = 0;
+        copy.timer_started = None;
+        copy.external_ref = None;
+        copy.locked = false;
+        copy.previous_occurrence = None;
+        if reopen {
+            copy.status = Status::Open;
+            copy.completed_on = None;
+            copy.untick_criteria();
+        }
+        copy
+    }
+
+    /// Returns how many of the `Task`'s acceptance criteria aren't ticked yet
+    pub fn criteria_left(&self) -> usize {
+        self.criteria
+            .iter()
+            .filter(|criterion| !criterion.done)
+            .count()
+    }
+
+    /// Fails if the `Task` has acceptance criteria that aren't ticked yet,
+    /// since it can't be `Completed` until they all are
+    pub fn check_criteria(&self) -> Result<()> {
+        match self.criteria_left() {
+            0 => Ok(()),
+            left => bail!(
+                "'{}' has {} of {} acceptance criteria left to tick off",
+                self.name,
+                left,
+                self.criteria.len()
+            ),
+        }
+    }
+
+    /// Unticks every acceptance criterion, for doing the `Task` over again
+    pub fn untick_criteria(&mut self) {
+        for criterion in &mut self.criteria {
+            criterion.done = false;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn from_sql(
         id: Uuid,
         name: String,
@@ -212,7 +617,513 @@ impl Task {
             tags,
             date_added,
             completed_on,
+            recurrence: None,
+            project: None,
+            time_spent: 0,
+            timer_started: None,
+            due: None,
+            scheduled: None,
+            external_ref: None,
+            scheduled_start: None,
+            duration: None,
+            locked: false,
+            parent: None,
+            criteria: vec![],
+            previous_occurrence: None,
+        }
+    }
+
+    /// Returns a one line summary of the `Task` (name, status, project, and tags)
+    /// to tell it apart from others on the command line
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} [{}]", self.name, self.status);
+        if let Some(project) = &self.project {
+            summary = format!("{} @{}", summary, project);
+        }
+        if let Some(tags) = &self.tags {
+            let mut tags_vec = Vec::from_iter(tags);
+            sort_text(&mut tags_vec);
+            let tags_string = tags_vec
+                .into_iter()
+                .map(|tag| tag.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+            summary = format!("{} ({})", summary, tags_string);
+        }
+        summary
+    }
+
+    /// Returns true if the `Task` isn't completed and its due date has passed
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status != Status::Completed && self.due.is_some_and(|due| due < today)
+    }
+
+    /// Returns true if the `Task` isn't completed and is due between
+    /// `today` and `days` from now
+    pub fn is_due_within(&self, today: NaiveDate, days: u64) -> bool {
+        let Some(last_day) = today.checked_add_days(Days::new(days)) else {
+            return false;
+        };
+        self.status != Status::Completed
+            && self.due.is_some_and(|due| today <= due && due <= last_day)
+    }
+
+    /// Returns true if the `Task` is snoozed, being scheduled for after `today`
+    pub fn is_snoozed(&self, today: NaiveDate) -> bool {
+        self.scheduled.is_some_and(|scheduled| scheduled > today)
+    }
+
+    /// Returns when the `Task`'s time slot begins and ends, if it has one
+    pub fn time_slot(&self) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        let start = self.scheduled_start?;
+        let minutes = self.duration.unwrap_or(DEFAULT_SLOT_MINUTES);
+        Some((start, start + TimeDelta::minutes(minutes)))
+    }
+
+    /// Returns the seconds spent on the `Task`, including any timer still running
+    pub fn total_time_spent(&self) -> i64 {
+        let running = match self.timer_started {
+            Some(started) => (Local::now() - started).num_seconds().max(0),
+            None => 0,
+        };
+        self.time_spent + running
+    }
+
+    /// Starts the timer if it isn't running, otherwise stops it
+    pub fn toggle_timer(&mut self) {
+        if self.timer_started.is_some() {
+            self.stop_timer();
+        } else {
+            self.timer_started = Some(Local::now());
+        }
+    }
+
+    /// Stops the timer if it is running, adding what it counted to `time_spent`
+    pub fn stop_timer(&mut self) {
+        self.time_spent = self.total_time_spent();
+        self.timer_started = None;
+    }
+
+    /// Creates the next occurrence of a recurring `Task` as a fresh `Open` task,
+    /// linked back to this one and scheduled for the next date it recurs on.
+    /// Returns `None` if the `Task` doesn't recur.
+    pub fn next_occurrence(&self) -> Option<Task> {
+        let recurrence = self.recurrence?;
+
+        let mut next_task = Task::new(
+            self.name.clone(),
+            self.description.clone(),
+            None,
+            Some(self.urgency),
+            Some(Status::Open),
+            self.tags.clone(),
+        );
+        next_task.project = self.project.clone();
+        next_task.criteria = self.criteria.clone();
+        next_task.untick_criteria();
+        next_task.previous_occurrence = Some(self.id);
+
+        // Step forward from the date this occurrence was for until we land on
+        // one that hasn't come yet. It stays snoozed until then.
+        let occurred = self.scheduled.unwrap_or(self.date_added.date_naive());
+        // Monthly ones hold on to the day they started on, so a month on from
+        // the 31st is the end of February, and a month on from that the 31st again
+        let recurrence = match recurrence {
+            Recurrence::Monthly(None) => Recurrence::Monthly(Some(occurred.day())),
+            recurrence => recurrence,
+        };
+        next_task.recurrence = Some(recurrence);
+        let today = today();
+        let mut next_date = recurrence.next_date(occurred);
+        while next_date <= today {
+            next_date = recurrence.next_date(next_date);
         }
+        next_task.scheduled = Some(next_date);
+        // Keep the same gap between the two dates and being due
+        next_task.due = self.due.map(|due| due + (next_date - occurred));
+
+        Some(next_task)
+    }
+}
+
+/// Parses a date written out in words: "today", "tomorrow", a weekday like
+/// "fri" or "next friday" (the coming one, never today), "next week" or
+/// "next month", or "in 3 days" (or weeks, or months)
+fn parse_relative_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["in", count, unit] => {
+            let count: u32 = count.parse().ok()?;
+            match unit.trim_end_matches('s') {
+                "day" => today.checked_add_days(Days::new(count.into())),
+                "week" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+                "month" => today.checked_add_months(Months::new(count)),
+                _ => None,
+            }
+        }
+        ["next", day] | [day] => {
+            let weekday: Weekday = day.parse().ok()?;
+            let days_ahead = match weekday.days_since(today.weekday()) {
+                0 => 7,
+                days => days,
+            };
+            today.checked_add_days(Days::new(days_ahead.into()))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a date given as YYYY-MM-DD or in words (see `parse_relative_date`),
+/// where an empty input means no date. `kind` names the date in errors.
+fn parse_date(input: &str, today: NaiveDate, kind: &str) -> Result<Option<NaiveDate>> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    if let Some(date) = parse_relative_date(&input, today) {
+        return Ok(Some(date));
+    }
+    let date = NaiveDate::parse_from_str(&input, "%Y-%m-%d").with_context(|| {
+        format!(
+            "'{}' is not a valid {}, use YYYY-MM-DD, today, tomorrow, a weekday like fri, or in 3 days",
+            input, kind
+        )
+    })?;
+    Ok(Some(date))
+}
+
+/// Parses a due date given as YYYY-MM-DD, or in words like "tomorrow",
+/// "next fri", or "in 3 days", where an empty input means no due date
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Result<Option<NaiveDate>> {
+    parse_date(input, today, "due date")
+}
+
+/// Parses the date to snooze a `Task` until, given as YYYY-MM-DD, or in words
+/// like "tomorrow", "next fri", or "in 3 days", where an empty input means
+/// it isn't snoozed
+pub fn parse_scheduled_date(input: &str, today: NaiveDate) -> Result<Option<NaiveDate>> {
+    parse_date(input, today, "snooze date")
+}
+
+/// How many minutes a time slot lasts when no duration is given
+pub const DEFAULT_SLOT_MINUTES: i64 = 30;
+
+/// Parses how long a time slot lasts, i.e. "45m", "1h", "1h30m", or "90" minutes
+pub fn parse_slot_minutes(input: &str) -> Option<i64> {
+    if let Ok(minutes) = input.parse::<i64>() {
+        return Some(minutes);
+    }
+    let (hours, rest) = match input.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<i64>().ok()?, rest),
+        None => (0, input),
+    };
+    let minutes = match rest {
+        "" => 0,
+        rest => rest.strip_suffix('m')?.parse::<i64>().ok()?,
+    };
+    Some(hours * 60 + minutes)
+}
+
+/// Formats how long a time slot lasts the way `parse_time_slot` reads it, i.e. "1h30m"
+pub fn format_slot_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+/// Parses a time slot on `date`, given as a start time and how long it lasts,
+/// i.e. "14:00 45m" or "9:30 1h30m". Without a duration the slot lasts
+/// `DEFAULT_SLOT_MINUTES`. Returns when it starts and how many minutes it
+/// lasts, where an empty input means no time slot.
+pub fn parse_time_slot(input: &str, date: NaiveDate) -> Result<Option<(DateTime<Local>, i64)>> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let (start, minutes) = match words.as_slice() {
+        [] => return Ok(None),
+        [start] => (*start, Some(DEFAULT_SLOT_MINUTES)),
+        [start, duration] => (*start, parse_slot_minutes(&duration.to_lowercase())),
+        _ => (input, None),
+    };
+    let (Ok(start), Some(minutes)) = (NaiveTime::parse_from_str(start, "%H:%M"), minutes) else {
+        bail!(
+            "'{}' is not a valid time slot, use HH:MM and how long, i.e. 14:00 45m",
+            input.trim()
+        );
+    };
+    if minutes <= 0 {
+        bail!("A time slot has to last at least a minute");
+    }
+    let start = Local
+        .from_local_datetime(&date.and_time(start))
+        .earliest()
+        .with_context(|| format!("{} doesn't exist in the local timezone", start))?;
+    Ok(Some((start, minutes)))
+}
+
+/// Returns the `Task`s with a time slot on `date`, in the order they come up
+pub fn agenda(tasks: &[Task], date: NaiveDate) -> Vec<&Task> {
+    let mut planned: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| {
+            task.scheduled_start
+                .is_some_and(|start| calendar().date_of(&start) == date)
+        })
+        .collect();
+    planned.sort_by_key(|task| task.scheduled_start);
+    planned
+}
+
+/// Format completion dates are shown in when editing them
+pub const COMPLETED_ON_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Parses a new completion date for the `Task`, given as "YYYY-MM-DD HH:MM",
+/// "YYYY-MM-DD", or "yesterday". A date without a time keeps the time of day
+/// the `Task` was completed at. It can't be before the `Task` was added or after `now`.
+pub fn parse_completed_on(
+    input: &str,
+    task: &Task,
+    now: DateTime<Local>,
+) -> Result<DateTime<Local>> {
+    let input = input.trim().to_lowercase();
+    let time = task.completed_on.unwrap_or(now).time();
+    let completed = match input.as_str() {
+        "yesterday" => now.date_naive().pred_opt().map(|date| date.and_time(time)),
+        _ => NaiveDateTime::parse_from_str(&input, "%Y-%m-%d %H:%M")
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(&input, "%Y-%m-%d")
+                    .ok()
+                    .map(|date| date.and_time(time))
+            }),
+    };
+    let Some(completed) = completed else {
+        bail!(
+            "'{}' is not a valid completion date, use YYYY-MM-DD HH:MM, YYYY-MM-DD, or yesterday",
+            input
+        );
+    };
+    let completed = Local
+        .from_local_datetime(&completed)
+        .earliest()
+        .with_context(|| format!("{} doesn't exist in the local timezone", completed))?;
+
+    // Only minutes can be entered, so compare against the minute it was added in
+    let added_minute = task
+        .date_added
+        .with_second(0)
+        .and_then(|added| added.with_nanosecond(0))
+        .unwrap_or(task.date_added);
+    if completed < added_minute {
+        bail!(
+            "A task can't be completed before it was added ({})",
+            task.date_added.format(COMPLETED_ON_FORMAT)
+        );
+    }
+    if completed > now {
+        bail!("A task can't be completed in the future");
+    }
+    Ok(completed)
+}
+
+/// Parses when a `Task` was added, given as an RFC3339 timestamp, "YYYY-MM-DD HH:MM",
+/// or "YYYY-MM-DD" (the start of that day). It can't be after `now`.
+pub fn parse_date_added(input: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    let input = input.trim();
+    let added = if let Ok(added) = DateTime::parse_from_rfc3339(input) {
+        added.with_timezone(&Local)
+    } else if let Ok(added) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+        Local
+            .from_local_datetime(&added)
+            .earliest()
+            .with_context(|| format!("{} doesn't exist in the local timezone", added))?
+    } else if let Ok(added) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        start_of_day(added, &Local)
+    } else {
+        bail!(
+            "'{}' is not a valid date added, use YYYY-MM-DD, YYYY-MM-DD HH:MM, or an RFC3339 timestamp",
+            input
+        );
+    };
+
+    if added > now {
+        bail!("A task can't be added in the future");
+    }
+    Ok(added)
+}
+
+/// Formats a number of seconds as hours, minutes, and seconds, i.e. "1h 05m 09s"
+pub fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let seconds = seconds % 60;
+
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Returns the `Task` brought in from the same place as the `ExternalRef`, if any
+pub fn find_external<'a>(tasks: &'a [Task], external_ref: &ExternalRef) -> Option<&'a Task> {
+    tasks.iter().find(|task| {
+        task.external_ref
+            .as_ref()
+            .is_some_and(|existing| existing.matches(external_ref))
+    })
+}
+
+/// Counts the subtasks directly under each `Task` that has any
+pub fn count_subtasks(tasks: &[Task]) -> HashMap<Uuid, usize> {
+    let mut counts = HashMap::new();
+    for parent in tasks.iter().filter_map(|task| task.parent) {
+        *counts.entry(parent).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Values a parent `Task` shows rolled up from its subtasks
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RolledUp {
+    pub urgency: Option<Urgency>,
+    pub due: Option<NaiveDate>,
+    /// Minutes
+    pub estimate: Option<i64>,
+}
+
+impl RolledUp {
+    pub fn is_empty(&self) -> bool {
+        self.urgency.is_none() && self.due.is_none() && self.estimate.is_none()
+    }
+}
+
+/// Rolls the fields given up from the subtasks of each `Task` that has any, all
+/// the way down, worked out fresh from the `Task`s given. `Completed` subtasks are
+/// left out, since they no longer need doing. Parents with nothing to show are left out.
+pub fn roll_up(tasks: &[Task], fields: &[RollUp]) -> HashMap<Uuid, RolledUp> {
+    let mut children: HashMap<Uuid, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        if let Some(parent) = task.parent {
+            children.entry(parent).or_default().push(task);
+        }
+    }
+
+    let mut rolled_up = HashMap::new();
+    for parent in children.keys() {
+        let mut rolled = RolledUp::default();
+        let mut seen = HashSet::from([*parent]);
+        let mut stack = children[parent].clone();
+        while let Some(subtask) = stack.pop() {
+            if !seen.insert(subtask.get_id()) {
+                continue;
+            }
+            if let Some(grandchildren) = children.get(&subtask.get_id()) {
+                stack.extend(grandchildren);
+            }
+            if subtask.status == Status::Completed {
+                continue;
+            }
+            if fields.contains(&RollUp::Urgency) {
+                rolled.urgency = rolled.urgency.max(Some(subtask.urgency));
+            }
+            if fields.contains(&RollUp::Due) {
+                rolled.due = match (rolled.due, subtask.due) {
+                    (Some(due), Some(other)) => Some(due.min(other)),
+                    (due, other) => due.or(other),
+                };
+            }
+            if fields.contains(&RollUp::Estimate) {
+                if let Some(duration) = subtask.duration {
+                    rolled.estimate = Some(rolled.estimate.unwrap_or(0) + duration);
+                }
+            }
+        }
+        if !rolled.is_empty() {
+            rolled_up.insert(*parent, rolled);
+        }
+    }
+    rolled_up
+}
+
+/// Returns the parent of the `Task` if all of its subtasks are `Completed`
+/// but it isn't yet, going by the `Task`s given. Locked parents, and ones
+/// with acceptance criteria left to tick off, are left alone.
+pub fn parent_ready_to_complete(tasks: &[Task], task: &Task) -> Option<Task> {
+    let parent_id = task.parent?;
+    let parent = tasks.iter().find(|parent| {
+        parent.get_id() == parent_id
+            && parent.status != Status::Completed
+            && !parent.locked
+            && parent.criteria_left() == 0
+    })?;
+    tasks
+        .iter()
+        .filter(|subtask| subtask.parent == Some(parent_id))
+        .all(|subtask| subtask.status == Status::Completed)
+        .then(|| parent.clone())
+}
+
+/// Returns the parents of the `Task`, all the way up, that are `Completed`,
+/// reopened so they aren't left finished with a subtask still to do.
+/// Locked parents are left alone.
+pub fn reopen_parents(tasks: &[Task], task: &Task) -> Vec<Task> {
+    let mut reopened = vec![];
+    let mut seen = HashSet::new();
+    let mut next = task.parent;
+    while let Some(parent_id) = next.filter(|id| seen.insert(*id)) {
+        let Some(parent) = tasks.iter().find(|parent| parent.get_id() == parent_id) else {
+            break;
+        };
+        if parent.status == Status::Completed && !parent.locked {
+            let mut parent = parent.clone();
+            parent.status = Status::Open;
+            parent.completed_on = None;
+            reopened.push(parent);
+        }
+        next = parent.parent;
+    }
+    reopened
+}
+
+/// Checks a `Task`'s tags against a tag filter, i.e. "work, urgent -blocked".
+/// Tags in the filter are split on commas and whitespace, and match any of the
+/// `Task`'s tags containing them. Those prefixed with `-` must not match, and the
+/// rest are combined based on `tag_match`. An empty filter matches everything.
+pub fn matches_tag_filter(task: &Task, tags_filter: &str, tag_match: TagMatch) -> bool {
+    let has_tag = |wanted: &str| {
+        task.tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|tag| tag.contains(wanted)))
+    };
+
+    let mut wanted = vec![];
+    for term in tags_filter
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|term| !term.is_empty())
+    {
+        match term.strip_prefix('-') {
+            Some(unwanted) if !unwanted.is_empty() => {
+                if has_tag(unwanted) {
+                    return false;
+                }
+            }
+            _ => wanted.push(term),
+        }
+    }
+
+    if wanted.is_empty() {
+        return true;
+    }
+    match tag_match {
+        TagMatch::Any => wanted.into_iter().any(has_tag),
+        TagMatch::All => wanted.into_iter().all(has_tag),
     }
 }
 
@@ -279,12 +1190,93 @@ impl TaskList {
         }
     }
 
+    /// Sorts the `TaskList` on the field given, largest first if `descending` is true.
+    /// `Task`s without a due date go last when sorting on it, and ties are
+    /// broken by `Urgency`, most urgent first.
+    pub fn sort(&mut self, sort_by: SortBy, descending: bool) {
+        if sort_by == SortBy::Urgency {
+            self.sort_by_urgency(descending);
+            return;
+        }
+
+        self.sort_by_urgency(true);
+        let direction = |ordering: Ordering| {
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        self.tasks.sort_by(|a, b| match sort_by {
+            SortBy::Urgency => Ordering::Equal,
+            SortBy::Added => direction(a.date_added.cmp(&b.date_added)),
+            SortBy::Due => match (a.due, b.due) {
+                (Some(a_due), Some(b_due)) => direction(a_due.cmp(&b_due)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortBy::Name => direction(collate(&a.name, &b.name)),
+            SortBy::Status => direction((a.status as u8).cmp(&(b.status as u8))),
+        });
+    }
+
+    /// Puts the `Task`s back in the order their ids are given in. Any `Task`
+    /// not in `order` goes after the rest, keeping their current order.
+    pub fn keep_order(&mut self, order: &[Uuid]) {
+        self.tasks.sort_by_key(|task| {
+            order
+                .iter()
+                .position(|id| *id == task.get_id())
+                .unwrap_or(usize::MAX)
+        });
+    }
+
     pub fn len(&self) -> usize {
         self.tasks.len()
     }
 
-    /// Filters the `TaskList`, either on a `Display` given or by a tag `String`
-    pub fn filter_tasks(&mut self, display_option: Option<Display>, tags_filter: String) {
+    /// Returns the id of the selected `Task`, if there is one
+    pub fn selected_id(&self) -> Option<Uuid> {
+        self.state
+            .selected()
+            .and_then(|i| self.tasks.get(i))
+            .map(|task| task.get_id())
+    }
+
+    /// Selects the `Task` with the given id, or nothing if it isn't in the `TaskList`
+    pub fn select_id(&mut self, id: Uuid) {
+        let position = self.tasks.iter().position(|task| task.get_id() == id);
+        self.state.select(position);
+    }
+
+    /// Selects the `Task` with the given id. If it has gone, selects whichever
+    /// `Task` still around was closest to it in `previous_order`, looking
+    /// below it first and then above it.
+    pub fn select_nearest(&mut self, id: Uuid, previous_order: &[Uuid]) {
+        let old_index = previous_order
+            .iter()
+            .position(|previous| *previous == id)
+            .unwrap_or(0);
+        let below = previous_order.iter().skip(old_index);
+        let above = previous_order.iter().take(old_index).rev();
+        let position = std::iter::once(&id)
+            .chain(below)
+            .chain(above)
+            .find_map(|id| self.tasks.iter().position(|task| task.get_id() == *id));
+        self.state.select(position);
+    }
+
+    /// Filters the `TaskList`, either on a `Display` given or by a tag `String`.
+    /// Snoozed `Task`s are only kept when displaying `All`.
+    /// See `matches_tag_filter` for how the tag `String` is read.
+    pub fn filter_tasks(
+        &mut self,
+        display_option: Option<Display>,
+        tags_filter: String,
+        tag_match: TagMatch,
+    ) {
+        let today = today();
         let mut tasks_to_keep = vec![];
         'task: for task in &mut self.tasks.iter() {
             // check if fits our display needs
@@ -296,42 +1288,66 @@ impl TaskList {
                         }
                     }
                     Display::NotCompleted => {
-                        if task.status == Status::Completed {
+                        if task.status == Status::Completed || task.is_snoozed(today) {
                             continue 'task;
                         }
                     }
                     Display::All => {}
                 },
                 None => {
-                    if task.status == Status::Completed {
+                    if task.status == Status::Completed || task.is_snoozed(today) {
                         continue 'task;
                     }
                 }
             }
 
-            // Check if our tag string is within any of our tags
-            if !tags_filter.is_empty() {
-                match task.tags.clone() {
-                    Some(task_tags) => {
-                        for tag in task_tags {
-                            if tag.contains(&tags_filter) {
-                                // on first match, we can add to our tasks
-                                // to keep and move on
-                                tasks_to_keep.push(task.clone());
-                                continue 'task;
-                            }
-                        }
-                        continue 'task;
-                    }
-                    None => continue 'task,
-                }
-            } else {
-                // if tags_filter is empty, we just push everything
+            if matches_tag_filter(task, &tags_filter, tag_match) {
                 tasks_to_keep.push(task.clone());
             }
         }
         self.tasks = tasks_to_keep;
     }
+
+    /// Keeps only the first `limit` `Critical` and `High` `Task`s that aren't
+    /// completed, in the order they're in, returning how many were left out
+    pub fn cap_urgent(&mut self, limit: usize) -> usize {
+        let before = self.tasks.len();
+        let mut urgent = 0;
+        self.tasks.retain(|task| {
+            if task.status == Status::Completed || task.urgency < Urgency::High {
+                return true;
+            }
+            urgent += 1;
+            urgent <= limit
+        });
+        before - self.tasks.len()
+    }
+
+    /// Keeps only the `Task`s that belong to the given project (case-insensitive)
+    pub fn filter_project(&mut self, project: &str) {
+        let project = project.to_lowercase();
+        self.tasks.retain(|task| {
+            task.project
+                .as_ref()
+                .is_some_and(|task_project| task_project.to_lowercase() == project)
+        });
+    }
+
+    /// Keeps only the `Task`s directly under the `parent`, or the ones
+    /// that aren't subtasks if it's `None`
+    pub fn filter_parent(&mut self, parent: Option<Uuid>) {
+        self.tasks.retain(|task| task.parent == parent);
+    }
+
+    /// Returns the distinct projects across the `Task`s, sorted
+    pub fn projects(&self) -> Vec<String> {
+        let projects: BTreeSet<String> = self
+            .tasks
+            .iter()
+            .filter_map(|task| task.project.clone())
+            .collect();
+        projects.into_iter().collect()
+    }
 }
 
 #[cfg(test)]
@@ -347,6 +1363,20 @@ mod tests {
         assert!(Urgency::Low == Urgency::Low);
     }
 
+    #[test]
+    fn test_stored_names() {
+        assert_eq!(Urgency::try_from("High"), Ok(Urgency::High));
+        assert_eq!(Status::try_from("Paused"), Ok(Status::Paused));
+        assert_eq!(
+            Urgency::try_from("high"),
+            Err(ChecklistError::InvalidUrgency(String::from("high")))
+        );
+        assert_eq!(
+            Status::try_from("Done"),
+            Err(ChecklistError::InvalidStatus(String::from("Done")))
+        );
+    }
+
     #[test]
     fn test_sort_by_urgency() {
         let task1 = Task::new(
@@ -412,4 +1442,794 @@ mod tests {
         assert_eq!(task_vec.tasks[4].urgency, Urgency::Critical);
         assert!(task_vec.tasks[0].date_added < task_vec.tasks[1].date_added);
     }
+
+    #[test]
+    fn test_recurrence_parsing() {
+        assert_eq!("daily".parse::<Recurrence>().unwrap(), Recurrence::Daily);
+        assert_eq!("Weekly".parse::<Recurrence>().unwrap(), Recurrence::Weekly);
+        assert_eq!(
+            " MONTHLY ".parse::<Recurrence>().unwrap(),
+            Recurrence::Monthly(None)
+        );
+        assert_eq!(
+            "monthly on day 31".parse::<Recurrence>().unwrap(),
+            Recurrence::Monthly(Some(31))
+        );
+        assert!("monthly on day 32".parse::<Recurrence>().is_err());
+        assert_eq!(
+            "every 3 days".parse::<Recurrence>().unwrap(),
+            Recurrence::EveryNDays(3)
+        );
+        assert!("every 0 days".parse::<Recurrence>().is_err());
+        assert!("every few days".parse::<Recurrence>().is_err());
+        assert!("yearly".parse::<Recurrence>().is_err());
+
+        // Should round trip through its string representation
+        for recurrence in [
+            Recurrence::Daily,
+            Recurrence::Weekly,
+            Recurrence::Monthly(None),
+            Recurrence::Monthly(Some(15)),
+            Recurrence::EveryNDays(10),
+        ] {
+            assert_eq!(
+                recurrence.to_string().parse::<Recurrence>().unwrap(),
+                recurrence
+            );
+        }
+    }
+
+    #[test]
+    fn test_recurrence_next_date() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(
+            Recurrence::Daily.next_date(start),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()
+        );
+        assert_eq!(
+            Recurrence::Weekly.next_date(start),
+            NaiveDate::from_ymd_opt(2024, 2, 7).unwrap()
+        );
+        // Clamps to the end of a shorter month, and back out again after it
+        let feb = Recurrence::Monthly(Some(31)).next_date(start);
+        assert_eq!(feb, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert_eq!(
+            Recurrence::Monthly(Some(31)).next_date(feb),
+            NaiveDate::from_ymd_opt(2024, 3, 31).unwrap()
+        );
+        assert_eq!(
+            Recurrence::Monthly(None).next_date(feb),
+            NaiveDate::from_ymd_opt(2024, 3, 29).unwrap()
+        );
+        assert_eq!(
+            Recurrence::EveryNDays(3).next_date(start),
+            NaiveDate::from_ymd_opt(2024, 2, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let task = Task::new(
+            String::from("Water plants"),
+            Some(String::from("The ones by the window")),
+            Some(String::from("Watered the cactus")),
+            Some(Urgency::Medium),
+            Some(Status::Completed),
+            Some(HashSet::from_iter(vec![String::from("home")])),
+        );
+        assert!(task.next_occurrence().is_none());
+
+        let mut recurring_task = task.clone();
+        recurring_task.recurrence = Some(Recurrence::Weekly);
+        recurring_task.date_added = Local::now() - TimeDelta::days(10);
+        recurring_task.due = Some((recurring_task.date_added + TimeDelta::days(2)).date_naive());
+
+        let next_task = recurring_task.next_occurrence().unwrap();
+        assert_ne!(next_task.get_id(), recurring_task.get_id());
+        assert_eq!(next_task.name, recurring_task.name);
+        assert_eq!(next_task.description, recurring_task.description);
+        assert_eq!(next_task.latest, None);
+        assert_eq!(next_task.urgency, Urgency::Medium);
+        assert_eq!(next_task.status, Status::Open);
+        assert!(next_task.completed_on.is_none());
+        assert_eq!(next_task.tags, recurring_task.tags);
+        assert_eq!(next_task.recurrence, Some(Recurrence::Weekly));
+        assert_eq!(next_task.previous_occurrence, Some(recurring_task.get_id()));
+        // Two weeks on from ten days ago is the first date not already passed.
+        // The occurrence is added now, and waits until then.
+        let added = recurring_task.date_added.date_naive();
+        assert_eq!(next_task.date_added.date_naive(), today());
+        assert_eq!(next_task.scheduled, Some(added + TimeDelta::days(14)));
+        assert_eq!(next_task.due, Some(added + TimeDelta::days(16)));
+
+        // The one after that goes on from the date it was scheduled for
+        let after = next_task.next_occurrence().unwrap();
+        assert_eq!(after.previous_occurrence, Some(next_task.get_id()));
+        assert_eq!(after.scheduled, Some(added + TimeDelta::days(21)));
+        assert_eq!(after.due, Some(added + TimeDelta::days(23)));
+
+        // Monthly ones keep to the 31st across February
+        let year = today().year() + 1;
+        let jan = NaiveDate::from_ymd_opt(year, 1, 31).unwrap();
+        let end_of_feb = NaiveDate::from_ymd_opt(year, 3, 1)
+            .unwrap()
+            .pred_opt()
+            .unwrap();
+        recurring_task.recurrence = Some(Recurrence::Monthly(None));
+        recurring_task.scheduled = Some(jan);
+        recurring_task.due = Some(jan);
+        let feb = recurring_task.next_occurrence().unwrap();
+        assert_eq!(feb.recurrence, Some(Recurrence::Monthly(Some(31))));
+        assert_eq!(feb.scheduled, Some(end_of_feb));
+        assert_eq!(feb.due, Some(end_of_feb));
+        let mar = feb.next_occurrence().unwrap();
+        assert_eq!(mar.scheduled, NaiveDate::from_ymd_opt(year, 3, 31));
+        assert_eq!(mar.due, NaiveDate::from_ymd_opt(year, 3, 31));
+    }
+
+    #[test]
+    fn test_is_important() {
+        let mut task = Task::new(String::from("Pay rent"), None, None, None, None, None);
+        assert!(!task.is_important());
+        task.urgency = Urgency::Critical;
+        assert!(task.is_important());
+
+        task.urgency = Urgency::High;
+        task.tags = Some(HashSet::from([String::from("#Important")]));
+        assert!(task.is_important());
+        task.tags = Some(HashSet::from([String::from("unimportant")]));
+        assert!(!task.is_important());
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let mut task = Task::new(
+            String::from("Water plants"),
+            Some(String::from("The ferns too")),
+            None,
+            Some(Urgency::High),
+            Some(Status::Completed),
+            Some(HashSet::from([String::from("home")])),
+        );
+        task.date_added = Local::now() - TimeDelta::days(3);
+        task.time_spent = 600;
+        task.project = Some(String::from("house"));
+        task.external_ref = Some("github:12".parse().unwrap());
+        task.locked = true;
+
+        let copy = task.duplicate(false);
+        assert_ne!(copy.get_id(), task.get_id());
+        assert!(copy.date_added > task.date_added);
+        assert_eq!(copy.name, task.name);
+        assert_eq!(copy.description, task.description);
+        assert_eq!(copy.urgency, task.urgency);
+        assert_eq!(copy.tags, task.tags);
+        assert_eq!(copy.project, task.project);
+        assert_eq!(copy.status, Status::Completed);
+        assert!(copy.completed_on.is_some());
+        assert_eq!(copy.time_spent, 0);
+        assert!(copy.external_ref.is_none());
+        assert!(!copy.locked);
+
+        let reopened = task.duplicate(true);
+        assert_eq!(reopened.status, Status::Open);
+        assert!(reopened.completed_on.is_none());
+    }
+
+    #[test]
+    fn test_criteria() {
+        let criteria =
+            parse_criteria("[x] Tests pass\n\n[ ]   Changelog\twritten\nDocs updated\n[X]");
+        assert_eq!(
+            criteria,
+            vec![
+                Criterion {
+                    text: String::from("Tests pass"),
+                    done: true
+                },
+                Criterion::new("Changelog written"),
+                Criterion::new("Docs updated"),
+            ]
+        );
+        assert_eq!(
+            criteria_to_text(&criteria).unwrap(),
+            "[x] Tests pass\n[ ] Changelog written\n[ ] Docs updated"
+        );
+        assert_eq!(criteria_to_text(&[]), None);
+
+        let mut parent = Task::new(String::from("Release"), None, None, None, None, None);
+        parent.criteria = criteria;
+        assert_eq!(parent.criteria_left(), 2);
+        assert!(parent.check_criteria().is_err());
+
+        // A parent with criteria left isn't completed along with its last subtask
+        let mut subtask = Task::new(String::from("Tag it"), None, None, None, None, None);
+        subtask.parent = Some(parent.get_id());
+        subtask.status = Status::Completed;
+        let tasks = vec![parent.clone(), subtask.clone()];
+        assert!(parent_ready_to_complete(&tasks, &subtask).is_none());
+
+        for criterion in &mut parent.criteria {
+            criterion.done = true;
+        }
+        assert!(parent.check_criteria().is_ok());
+        let tasks = vec![parent.clone(), subtask.clone()];
+        assert!(parent_ready_to_complete(&tasks, &subtask).is_some());
+
+        // Doing it over starts with nothing ticked
+        assert_eq!(parent.duplicate(true).criteria_left(), 3);
+        assert_eq!(parent.duplicate(false).criteria_left(), 0);
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut task = Task::new(String::from("Write report"), None, None, None, None, None);
+        assert_eq!(task.total_time_spent(), 0);
+
+        task.toggle_timer();
+        assert!(task.timer_started.is_some());
+        // Pretend the timer has been running for a while
+        task.timer_started = Some(Local::now() - TimeDelta::seconds(90));
+        assert!(task.total_time_spent() >= 90);
+
+        task.toggle_timer();
+        assert!(task.timer_started.is_none());
+        let spent = task.time_spent;
+        assert!((90..95).contains(&spent));
+
+        // Stopping a timer that isn't running changes nothing
+        task.stop_timer();
+        assert_eq!(task.time_spent, spent);
+    }
+
+    #[test]
+    fn test_external_ref() {
+        let issue: ExternalRef = "github:42".parse().unwrap();
+        assert_eq!(issue.to_string(), "github:42");
+        let event: ExternalRef = "caldav:a1b2@example.com:work".parse().unwrap();
+        assert_eq!(event.id, "a1b2@example.com:work");
+        for input in ["github", "github:", ":42"] {
+            assert!(input.parse::<ExternalRef>().is_err(), "{}", input);
+        }
+
+        let mut task = Task::new(String::from("Fix login"), None, None, None, None, None);
+        let tasks = vec![task.clone()];
+        assert_eq!(find_external(&tasks, &issue), None);
+        task.external_ref = Some(issue);
+        let tasks = vec![task.clone()];
+        assert_eq!(
+            find_external(&tasks, &"GitHub:42".parse().unwrap()),
+            Some(&task)
+        );
+        assert_eq!(find_external(&tasks, &"github:43".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_due_dates() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(parse_due_date("", today).unwrap(), None);
+        assert_eq!(parse_due_date("Today", today).unwrap(), Some(today));
+        assert_eq!(
+            parse_due_date("tomorrow", today).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 11)
+        );
+        assert_eq!(
+            parse_due_date("2024-04-01", today).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 4, 1)
+        );
+        // 2024-03-10 is a Sunday
+        for (input, expected) in [
+            ("next tuesday", (2024, 3, 12)),
+            ("Fri", (2024, 3, 15)),
+            ("sun", (2024, 3, 17)),
+            ("next week", (2024, 3, 17)),
+            ("next month", (2024, 4, 10)),
+            ("in 3 days", (2024, 3, 13)),
+            ("in 1 day", (2024, 3, 11)),
+            ("in  2 weeks", (2024, 3, 24)),
+            ("in 11 months", (2025, 2, 10)),
+        ] {
+            let (year, month, day) = expected;
+            assert_eq!(
+                parse_due_date(input, today).unwrap(),
+                NaiveDate::from_ymd_opt(year, month, day),
+                "{}",
+                input
+            );
+        }
+        for input in [
+            "next",
+            "in 3",
+            "in three days",
+            "in 3 fortnights",
+            "next fryday",
+        ] {
+            assert!(parse_due_date(input, today).is_err(), "{}", input);
+        }
+
+        let mut task = Task::new(String::from("Taxes"), None, None, None, None, None);
+        assert!(!task.is_overdue(today));
+        assert!(!task.is_due_within(today, 1));
+
+        task.due = NaiveDate::from_ymd_opt(2024, 3, 9);
+        assert!(task.is_overdue(today));
+        assert!(!task.is_due_within(today, 1));
+
+        task.due = NaiveDate::from_ymd_opt(2024, 3, 11);
+        assert!(!task.is_overdue(today));
+        assert!(task.is_due_within(today, 1));
+        assert!(!task.is_due_within(today, 0));
+
+        task.status = Status::Completed;
+        assert!(!task.is_due_within(today, 1));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(65), "1m 05s");
+        assert_eq!(format_duration(3600 + 5 * 60 + 9), "1h 05m 09s");
+    }
+
+    #[test]
+    fn test_filter_project() {
+        let mut house_task = Task::new(String::from("Paint fence"), None, None, None, None, None);
+        house_task.project = Some(String::from("House"));
+        let mut garden_task = Task::new(String::from("Plant beans"), None, None, None, None, None);
+        garden_task.project = Some(String::from("Garden"));
+        let loose_task = Task::new(String::from("Call mum"), None, None, None, None, None);
+
+        let mut task_list = TaskList::from(vec![house_task.clone(), garden_task, loose_task]);
+        assert_eq!(
+            task_list.projects(),
+            vec![String::from("Garden"), String::from("House")]
+        );
+
+        task_list.filter_project("house");
+        assert_eq!(task_list.tasks, vec![house_task]);
+    }
+
+    #[test]
+    fn test_subtasks() {
+        let taxes = Task::new(String::from("Taxes"), None, None, None, None, None);
+        let mut forms = Task::new(String::from("Find forms"), None, None, None, None, None);
+        forms.parent = Some(taxes.get_id());
+        let mut receipts = Task::new(String::from("Sort receipts"), None, None, None, None, None);
+        receipts.parent = Some(taxes.get_id());
+        let mut scan = Task::new(String::from("Scan receipts"), None, None, None, None, None);
+        scan.parent = Some(receipts.get_id());
+        let tasks = vec![taxes.clone(), forms.clone(), receipts.clone(), scan];
+
+        let counts = count_subtasks(&tasks);
+        assert_eq!(counts.get(&taxes.get_id()), Some(&2));
+        assert_eq!(counts.get(&receipts.get_id()), Some(&1));
+        assert_eq!(counts.get(&forms.get_id()), None);
+
+        let mut task_list = TaskList::from(tasks.clone());
+        task_list.filter_parent(None);
+        assert_eq!(task_list.tasks, vec![taxes.clone()]);
+        let mut task_list = TaskList::from(tasks);
+        task_list.filter_parent(Some(taxes.get_id()));
+        assert_eq!(task_list.tasks, vec![forms, receipts]);
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let make_task = |name: &str, urgency, status| {
+            Task::new(
+                String::from(name),
+                None,
+                None,
+                Some(urgency),
+                Some(status),
+                None,
+            )
+        };
+        let taxes = make_task("Taxes", Urgency::Low, Status::Open);
+        let mut forms = make_task("Find forms", Urgency::Medium, Status::Open);
+        forms.parent = Some(taxes.get_id());
+        forms.due = NaiveDate::from_ymd_opt(2025, 4, 10);
+        forms.duration = Some(30);
+        let mut receipts = make_task("Sort receipts", Urgency::Low, Status::Open);
+        receipts.parent = Some(taxes.get_id());
+        receipts.duration = Some(60);
+        let mut scan = make_task("Scan receipts", Urgency::High, Status::Working);
+        scan.parent = Some(receipts.get_id());
+        scan.due = NaiveDate::from_ymd_opt(2025, 4, 1);
+        let mut old = make_task("Old receipts", Urgency::Critical, Status::Completed);
+        old.parent = Some(receipts.get_id());
+        let tasks = vec![taxes.clone(), forms, receipts.clone(), scan, old];
+
+        // Values come up from every level below, but not from completed subtasks
+        let rolled_up = roll_up(&tasks, &[RollUp::Urgency, RollUp::Due, RollUp::Estimate]);
+        assert_eq!(
+            rolled_up[&taxes.get_id()],
+            RolledUp {
+                urgency: Some(Urgency::High),
+                due: NaiveDate::from_ymd_opt(2025, 4, 1),
+                estimate: Some(90),
+            }
+        );
+        assert_eq!(
+            rolled_up[&receipts.get_id()],
+            RolledUp {
+                urgency: Some(Urgency::High),
+                due: NaiveDate::from_ymd_opt(2025, 4, 1),
+                estimate: None,
+            }
+        );
+        assert_eq!(rolled_up.len(), 2);
+
+        // Only the fields asked for are rolled up
+        let rolled_up = roll_up(&tasks, &[RollUp::Estimate]);
+        assert_eq!(
+            rolled_up[&taxes.get_id()],
+            RolledUp {
+                estimate: Some(90),
+                ..RolledUp::default()
+            }
+        );
+        assert!(!rolled_up.contains_key(&receipts.get_id()));
+        assert!(roll_up(&tasks, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_roll_up_parents() {
+        let make_task = |name: &str, status| {
+            Task::new(String::from(name), None, None, None, Some(status), None)
+        };
+        let taxes = make_task("Taxes", Status::Open);
+        let mut forms = make_task("Find forms", Status::Completed);
+        forms.parent = Some(taxes.get_id());
+        let mut receipts = make_task("Sort receipts", Status::Open);
+        receipts.parent = Some(taxes.get_id());
+        let mut scan = make_task("Scan receipts", Status::Completed);
+        scan.parent = Some(receipts.get_id());
+
+        // Sorting the receipts still has to be done
+        let tasks = vec![taxes.clone(), forms.clone(), receipts.clone(), scan.clone()];
+        assert_eq!(parent_ready_to_complete(&tasks, &forms), None);
+        assert_eq!(
+            parent_ready_to_complete(&tasks, &scan),
+            Some(receipts.clone())
+        );
+        assert_eq!(parent_ready_to_complete(&tasks, &taxes), None);
+
+        receipts.status = Status::Completed;
+        let tasks = vec![taxes.clone(), forms.clone(), receipts.clone(), scan.clone()];
+        assert_eq!(
+            parent_ready_to_complete(&tasks, &receipts),
+            Some(taxes.clone())
+        );
+
+        // Reopening the scan reopens every completed task above it
+        let mut taxes_done = taxes.clone();
+        taxes_done.status = Status::Completed;
+        taxes_done.completed_on = Some(Local::now());
+        scan.status = Status::Open;
+        let tasks = vec![taxes_done, forms, receipts.clone(), scan.clone()];
+        let reopened = reopen_parents(&tasks, &scan);
+        assert_eq!(
+            reopened
+                .iter()
+                .map(|task| (task.name.as_str(), task.status, task.completed_on))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Sort receipts", Status::Open, None),
+                ("Taxes", Status::Open, None)
+            ]
+        );
+        assert!(reopen_parents(&tasks, &taxes).is_empty());
+    }
+
+    #[test]
+    fn test_cap_urgent() {
+        let make_task = |name: &str, urgency, status| {
+            Task::new(
+                String::from(name),
+                None,
+                None,
+                Some(urgency),
+                Some(status),
+                None,
+            )
+        };
+        let fire = make_task("Put out fire", Urgency::Critical, Status::Working);
+        let taxes = make_task("Taxes", Urgency::High, Status::Open);
+        let shipped = make_task("Ship it", Urgency::Critical, Status::Completed);
+        let leak = make_task("Fix leak", Urgency::Critical, Status::Open);
+        let nap = make_task("Nap", Urgency::Low, Status::Open);
+
+        let mut task_list = TaskList::from(vec![
+            fire.clone(),
+            taxes.clone(),
+            shipped.clone(),
+            leak,
+            nap.clone(),
+        ]);
+        // Completed tasks don't count, and the rest are kept in order
+        assert_eq!(task_list.cap_urgent(2), 1);
+        assert_eq!(task_list.tasks, vec![fire, taxes, shipped, nap]);
+        assert_eq!(task_list.cap_urgent(5), 0);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut rent = Task::new(
+            String::from("pay rent"),
+            None,
+            None,
+            Some(Urgency::High),
+            Some(Status::Working),
+            None,
+        );
+        rent.due = NaiveDate::from_ymd_opt(2024, 10, 1);
+        let mut leak = Task::new(
+            String::from("Fix leak"),
+            None,
+            None,
+            Some(Urgency::Critical),
+            None,
+            None,
+        );
+        leak.date_added = rent.date_added - TimeDelta::days(1);
+        let mut plants = Task::new(
+            String::from("Water plants"),
+            None,
+            None,
+            Some(Urgency::Low),
+            Some(Status::Completed),
+            None,
+        );
+        plants.date_added = rent.date_added - TimeDelta::days(2);
+        plants.due = NaiveDate::from_ymd_opt(2024, 9, 1);
+
+        let mut task_list = TaskList::from(vec![rent.clone(), leak.clone(), plants.clone()]);
+        task_list.sort(SortBy::Added, true);
+        assert_eq!(
+            task_list.tasks,
+            vec![rent.clone(), leak.clone(), plants.clone()]
+        );
+
+        // Tasks without a due date go last, whichever way round
+        task_list.sort(SortBy::Due, false);
+        assert_eq!(
+            task_list.tasks,
+            vec![plants.clone(), rent.clone(), leak.clone()]
+        );
+        task_list.sort(SortBy::Due, true);
+        assert_eq!(
+            task_list.tasks,
+            vec![rent.clone(), plants.clone(), leak.clone()]
+        );
+
+        task_list.sort(SortBy::Name, false);
+        assert_eq!(
+            task_list.tasks,
+            vec![leak.clone(), rent.clone(), plants.clone()]
+        );
+
+        task_list.sort(SortBy::Status, false);
+        assert_eq!(task_list.tasks, vec![leak, rent, plants]);
+    }
+
+    #[test]
+    fn test_select_nearest() {
+        let tasks: Vec<Task> = ["One", "Two", "Three", "Four"]
+            .into_iter()
+            .map(|name| Task::new(String::from(name), None, None, None, None, None))
+            .collect();
+        let order: Vec<Uuid> = tasks.iter().map(|task| task.get_id()).collect();
+
+        let mut task_list = TaskList::from(tasks.clone());
+        task_list.tasks.reverse();
+        task_list.select_nearest(order[1], &order);
+        assert_eq!(task_list.selected_id(), Some(order[1]));
+
+        // Two is gone, so Three after it gets selected
+        task_list.tasks.retain(|task| task.get_id() != order[1]);
+        task_list.select_nearest(order[1], &order);
+        assert_eq!(task_list.selected_id(), Some(order[2]));
+
+        // With nothing after it, the closest before it is picked
+        task_list.tasks.retain(|task| task.get_id() == order[0]);
+        task_list.select_nearest(order[3], &order);
+        assert_eq!(task_list.selected_id(), Some(order[0]));
+
+        task_list.tasks.clear();
+        task_list.select_nearest(order[0], &order);
+        assert_eq!(task_list.selected_id(), None);
+    }
+
+    #[test]
+    fn test_keep_order() {
+        let low = Task::new(
+            String::from("Water plants"),
+            None,
+            None,
+            Some(Urgency::Low),
+            None,
+            None,
+        );
+        let high = Task::new(
+            String::from("Pay rent"),
+            None,
+            None,
+            Some(Urgency::High),
+            None,
+            None,
+        );
+        let critical = Task::new(
+            String::from("Fix leak"),
+            None,
+            None,
+            Some(Urgency::Critical),
+            None,
+            None,
+        );
+
+        let mut task_list = TaskList::from(vec![high.clone(), critical.clone(), low.clone()]);
+        task_list.sort_by_urgency(true);
+        task_list.keep_order(&[low.get_id(), high.get_id()]);
+        assert_eq!(task_list.tasks, vec![low, high, critical]);
+    }
+
+    #[test]
+    fn test_matches_tag_filter() {
+        let task = Task::new(
+            String::from("Fix the gutter"),
+            None,
+            None,
+            None,
+            None,
+            Some(HashSet::from_iter(vec![
+                String::from("home"),
+                String::from("urgent"),
+            ])),
+        );
+        let untagged = Task::new(String::from("Call mum"), None, None, None, None, None);
+
+        assert!(matches_tag_filter(&task, "", TagMatch::All));
+        assert!(matches_tag_filter(&untagged, "", TagMatch::Any));
+        assert!(matches_tag_filter(&task, "hom", TagMatch::Any));
+        assert!(matches_tag_filter(&task, "work, home", TagMatch::Any));
+        assert!(!matches_tag_filter(&task, "work, home", TagMatch::All));
+        assert!(matches_tag_filter(&task, "home urgent", TagMatch::All));
+        assert!(!matches_tag_filter(&task, "home -urgent", TagMatch::Any));
+        assert!(!matches_tag_filter(&untagged, "home", TagMatch::Any));
+        assert!(matches_tag_filter(&untagged, "-work", TagMatch::All));
+        assert!(!matches_tag_filter(&task, "-urg", TagMatch::Any));
+    }
+
+    #[test]
+    fn test_time_slots() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let at = |hour, minute| {
+            Local
+                .from_local_datetime(&today.and_hms_opt(hour, minute, 0).unwrap())
+                .unwrap()
+        };
+        assert_eq!(parse_time_slot("  ", today).unwrap(), None);
+        assert_eq!(
+            parse_time_slot("14:00 45m", today).unwrap(),
+            Some((at(14, 0), 45))
+        );
+        assert_eq!(
+            parse_time_slot("9:30 1H30M", today).unwrap(),
+            Some((at(9, 30), 90))
+        );
+        assert_eq!(
+            parse_time_slot("16:15 2h", today).unwrap(),
+            Some((at(16, 15), 120))
+        );
+        assert_eq!(
+            parse_time_slot("08:00", today).unwrap(),
+            Some((at(8, 0), DEFAULT_SLOT_MINUTES))
+        );
+        for input in ["25:00", "14:00 soon", "14:00 0m", "14:00 45m extra", "2pm"] {
+            assert!(parse_time_slot(input, today).is_err(), "{}", input);
+        }
+        for minutes in [45, 60, 90] {
+            assert_eq!(
+                parse_slot_minutes(&format_slot_minutes(minutes)),
+                Some(minutes)
+            );
+        }
+
+        let mut standup = Task::new(String::from("Standup"), None, None, None, None, None);
+        standup.scheduled_start = Some(at(9, 0));
+        standup.duration = Some(15);
+        let mut review = Task::new(String::from("Review"), None, None, None, None, None);
+        review.scheduled_start = Some(at(8, 0));
+        let mut tomorrow = Task::new(String::from("Later"), None, None, None, None, None);
+        tomorrow.scheduled_start = Some(at(8, 0) + TimeDelta::days(1));
+        let unplanned = Task::new(String::from("Someday"), None, None, None, None, None);
+
+        assert_eq!(standup.time_slot(), Some((at(9, 0), at(9, 15))));
+        assert_eq!(review.time_slot(), Some((at(8, 0), at(8, 30))));
+        let tasks = vec![standup.clone(), tomorrow, review.clone(), unplanned];
+        assert_eq!(agenda(&tasks, today), vec![&review, &standup]);
+    }
+
+    #[test]
+    fn test_snoozed_tasks() {
+        let today = Local::now().date_naive();
+        assert!(parse_scheduled_date("someday", today).is_err());
+        assert_eq!(
+            parse_scheduled_date("tomorrow", today).unwrap(),
+            today.succ_opt()
+        );
+
+        let mut snoozed_task =
+            Task::new(String::from("Renew passport"), None, None, None, None, None);
+        snoozed_task.scheduled = today.succ_opt();
+        let mut woken_task = Task::new(String::from("Water plants"), None, None, None, None, None);
+        woken_task.scheduled = Some(today);
+        let plain_task = Task::new(String::from("Do dishes"), None, None, None, None, None);
+        assert!(snoozed_task.is_snoozed(today));
+        assert!(!woken_task.is_snoozed(today));
+        assert!(!plain_task.is_snoozed(today));
+
+        let all_tasks = vec![snoozed_task, woken_task.clone(), plain_task.clone()];
+        let mut task_list = TaskList::from(all_tasks.clone());
+        task_list.filter_tasks(None, String::new(), TagMatch::Any);
+        assert_eq!(
+            task_list.tasks,
+            vec![woken_task.clone(), plain_task.clone()]
+        );
+
+        let mut task_list = TaskList::from(all_tasks.clone());
+        task_list.filter_tasks(Some(Display::NotCompleted), String::new(), TagMatch::Any);
+        assert_eq!(task_list.tasks, vec![woken_task, plain_task]);
+
+        let mut task_list = TaskList::from(all_tasks.clone());
+        task_list.filter_tasks(Some(Display::All), String::new(), TagMatch::Any);
+        assert_eq!(task_list.tasks, all_tasks);
+    }
+
+    #[test]
+    fn test_parse_completed_on() {
+        let now = Local.with_ymd_and_hms(2024, 3, 12, 9, 0, 0).unwrap();
+        let mut task = Task::new(String::from("Mow lawn"), None, None, None, None, None);
+        task.date_added = Local.with_ymd_and_hms(2024, 3, 10, 18, 30, 45).unwrap();
+        task.completed_on = Some(Local.with_ymd_and_hms(2024, 3, 12, 8, 15, 0).unwrap());
+
+        assert_eq!(
+            parse_completed_on("2024-03-11 21:05", &task, now).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 11, 21, 5, 0).unwrap()
+        );
+        // Dates alone keep the time of day it was completed at
+        assert_eq!(
+            parse_completed_on("yesterday", &task, now).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 11, 8, 15, 0).unwrap()
+        );
+        assert_eq!(
+            parse_completed_on("2024-03-11", &task, now).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 11, 8, 15, 0).unwrap()
+        );
+        // The minute it was added in is fine, but not before
+        assert!(parse_completed_on("2024-03-10 18:30", &task, now).is_ok());
+        assert!(parse_completed_on("2024-03-10 18:29", &task, now).is_err());
+        assert!(parse_completed_on("2024-03-12 09:01", &task, now).is_err());
+        assert!(parse_completed_on("last tuesday", &task, now).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_added() {
+        let now = Local.with_ymd_and_hms(2024, 3, 12, 9, 0, 0).unwrap();
+        assert_eq!(
+            parse_date_added("2023-11-02", now).unwrap(),
+            Local.with_ymd_and_hms(2023, 11, 2, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_date_added("2023-11-02 14:45", now).unwrap(),
+            Local.with_ymd_and_hms(2023, 11, 2, 14, 45, 0).unwrap()
+        );
+        assert_eq!(
+            parse_date_added("2023-11-02T14:45:00Z", now).unwrap(),
+            DateTime::parse_from_rfc3339("2023-11-02T14:45:00Z").unwrap()
+        );
+        assert!(parse_date_added("2024-03-13", now).is_err());
+        assert!(parse_date_added("a while ago", now).is_err());
+    }
 }
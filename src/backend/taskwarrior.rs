@@ -0,0 +1,425 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::backend::database::{
+    add_dependency_in_db, add_to_db, get_all_db_contents, get_archive_contents,
+};
+use crate::backend::task::{Status, Task, Urgency};
+
+/// Format TaskWarrior writes its dates and times in, always in UTC
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Formats the time the way TaskWarrior does
+fn tw_time(time: &DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format(TIME_FORMAT).to_string()
+}
+
+/// Formats the date as the start of that day here, since TaskWarrior only has times
+fn tw_date(date: NaiveDate) -> Option<String> {
+    let start = date
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()?;
+    Some(tw_time(&start))
+}
+
+/// Reads a TaskWarrior time, which newer versions can also write as RFC 3339
+fn parse_tw_time(value: &Value) -> Option<DateTime<Local>> {
+    let text = value.as_str()?;
+    if let Ok(time) = NaiveDateTime::parse_from_str(text, TIME_FORMAT) {
+        return Some(time.and_utc().with_timezone(&Local));
+    }
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|time| time.with_timezone(&Local))
+}
+
+/// Returns the TaskWarrior priority for the `Urgency`. TaskWarrior only has
+/// three, so `Critical` shares H with `High` and is told apart by the
+/// `checklist_urgency` attribute.
+fn priority(urgency: Urgency) -> Option<&'static str> {
+    match urgency {
+        Urgency::Critical | Urgency::High => Some("H"),
+        Urgency::Medium => Some("M"),
+        Urgency::Low => None,
+    }
+}
+
+/// Returns the `Urgency` for a TaskWarrior priority, with none being `Low`
+fn urgency_from_priority(priority: Option<&str>) -> Urgency {
+    match priority.map(str::to_ascii_uppercase).as_deref() {
+        Some("H") => Urgency::High,
+        Some("M") => Urgency::Medium,
+        _ => Urgency::Low,
+    }
+}
+
+/// Returns the `Task`s as TaskWarrior's JSON export, ready for `task import`.
+/// The ids carry over as uuids and the dependencies as `depends`. What TaskWarrior
+/// has no place for (the latest update, a `Critical` urgency, a `Paused` status)
+/// goes in `checklist_` attributes so it comes back when imported here again.
+pub fn taskwarrior_json(tasks: &[Task], dependencies: &[(Uuid, Uuid)]) -> Value {
+    let tasks = tasks
+        .iter()
+        .map(|task| {
+            let mut object = Map::new();
+            object.insert("uuid".into(), task.get_id().to_string().into());
+            object.insert("description".into(), task.name.clone().into());
+            let status = if task.status == Status::Completed {
+                "completed"
+            } else {
+                "pending"
+            };
+            object.insert("status".into(), status.into());
+            object.insert("entry".into(), tw_time(&task.date_added).into());
+            if let (Status::Completed, Some(completed_on)) = (task.status, &task.completed_on) {
+                object.insert("end".into(), tw_time(completed_on).into());
+            }
+            if task.status == Status::Working {
+                let started = task.timer_started.unwrap_or(task.date_added);
+                object.insert("start".into(), tw_time(&started).into());
+            }
+            if let Some(due) = task.due.and_then(tw_date) {
+                object.insert("due".into(), due.into());
+            }
+            if let Some(scheduled) = task.scheduled.and_then(tw_date) {
+                object.insert("scheduled".into(), scheduled.into());
+            }
+            if let Some(project) = &task.project {
+                object.insert("project".into(), project.clone().into());
+            }
+            if let Some(tags) = task.tags.as_ref().filter(|tags| !tags.is_empty()) {
+                let mut tags: Vec<&String> = tags.iter().collect();
+                tags.sort();
+                object.insert("tags".into(), json!(tags));
+            }
+            if let Some(priority) = priority(task.urgency) {
+                object.insert("priority".into(), priority.into());
+            }
+            if task.urgency == Urgency::Critical {
+                object.insert("checklist_urgency".into(), "critical".into());
+            }
+            if task.status == Status::Paused {
+                object.insert("checklist_status".into(), "paused".into());
+            }
+            if let Some(description) = &task.description {
+                object.insert(
+                    "annotations".into(),
+                    json!([{ "entry": tw_time(&task.date_added), "description": description }]),
+                );
+            }
+            if let Some(latest) = &task.latest {
+                object.insert("checklist_latest".into(), latest.clone().into());
+            }
+            let depends: Vec<String> = dependencies
+                .iter()
+                .filter(|(dependent, _)| *dependent == task.get_id())
+                .map(|(_, blocker)| blocker.to_string())
+                .collect();
+            if !depends.is_empty() {
+                object.insert("depends".into(), json!(depends));
+            }
+            Value::Object(object)
+        })
+        .collect();
+    Value::Array(tasks)
+}
+
+/// Reads the uuids a TaskWarrior task depends on, which older versions write
+/// as one string separated by commas and newer ones as a list
+fn depends(value: &Value) -> Vec<Uuid> {
+    let ids: Vec<&str> = match value {
+        Value::String(ids) => ids.split(',').collect(),
+        Value::Array(ids) => ids.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    ids.into_iter()
+        .filter_map(|id| Uuid::parse_str(id.trim()).ok())
+        .collect()
+}
+
+/// `Task`s read in, with the dependencies between them as (task, blocker) pairs
+pub type Imported = (Vec<Task>, Vec<(Uuid, Uuid)>);
+
+/// Reads the `Task`s from TaskWarrior's JSON export (`task export`), along with
+/// the dependencies between them as (task, blocker) pairs. Deleted tasks and
+/// the templates recurring tasks are made from are left out, with the
+/// occurrences already made coming in as tasks of their own.
+pub fn tasks_from_taskwarrior(text: &str) -> Result<Imported> {
+    let value: Value =
+        serde_json::from_str(text).context("The TaskWarrior export isn't valid JSON")?;
+    let Some(items) = value.as_array() else {
+        bail!("The TaskWarrior export should be a list of tasks, like `task export` writes out");
+    };
+
+    let mut tasks = vec![];
+    let mut dependencies = vec![];
+    for (i, item) in items.iter().enumerate() {
+        let status = item["status"].as_str().unwrap_or("pending");
+        if matches!(status, "deleted" | "recurring") {
+            continue;
+        }
+        let Some(id) = item["uuid"]
+            .as_str()
+            .and_then(|id| Uuid::parse_str(id).ok())
+        else {
+            bail!("Task {} in the TaskWarrior export has no valid uuid", i + 1);
+        };
+        let Some(name) = item["description"]
+            .as_str()
+            .filter(|name| !name.trim().is_empty())
+        else {
+            bail!(
+                "Task {} in the TaskWarrior export has no description",
+                i + 1
+            );
+        };
+
+        let started = parse_tw_time(&item["start"]);
+        let status = match (status, item["checklist_status"].as_str()) {
+            ("completed", _) => Status::Completed,
+            (_, Some("paused")) => Status::Paused,
+            _ if started.is_some() => Status::Working,
+            _ => Status::Open,
+        };
+        let urgency = match item["checklist_urgency"].as_str() {
+            Some("critical") => Urgency::Critical,
+            _ => urgency_from_priority(item["priority"].as_str()),
+        };
+        let tags: HashSet<String> = item["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(|tag| tag.replace(';', ","))
+            .collect();
+        let annotations: Vec<&str> = item["annotations"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|annotation| annotation["description"].as_str())
+            .collect();
+        let date_added = parse_tw_time(&item["entry"]).unwrap_or_else(Local::now);
+        let completed_on = (status == Status::Completed)
+            .then(|| parse_tw_time(&item["end"]).unwrap_or_else(Local::now));
+
+        let mut task = Task::from_sql(
+            id,
+            name.to_string(),
+            (!annotations.is_empty()).then(|| annotations.join("\n")),
+            item["checklist_latest"].as_str().map(String::from),
+            urgency,
+            status,
+            (!tags.is_empty()).then_some(tags),
+            date_added,
+            completed_on,
+        );
+        task.project = item["project"].as_str().map(String::from);
+        task.timer_started = started.filter(|_| status == Status::Working);
+        task.due = parse_tw_time(&item["due"]).map(|due| due.date_naive());
+        task.scheduled = parse_tw_time(&item["scheduled"]).map(|scheduled| scheduled.date_naive());
+        dependencies.extend(
+            depends(&item["depends"])
+                .into_iter()
+                .map(|blocker| (id, blocker)),
+        );
+        tasks.push(task);
+    }
+    Ok((tasks, dependencies))
+}
+
+/// Adds the `Task`s read from TaskWarrior to the database along with their
+/// dependencies, skipping any with an id the database (archive included)
+/// already has, so an import can be run again. Either all of them are added
+/// or none are. Returns how many were added and how many were skipped.
+pub fn import_taskwarrior(
+    conn: &Connection,
+    tasks: &[Task],
+    dependencies: &[(Uuid, Uuid)],
+) -> Result<(usize, usize)> {
+    let existing: HashSet<Uuid> = get_all_db_contents(conn)?
+        .tasks
+        .iter()
+        .chain(get_archive_contents(conn)?.tasks.iter())
+        .map(|task| task.get_id())
+        .collect();
+    let added: HashSet<Uuid> = tasks
+        .iter()
+        .map(|task| task.get_id())
+        .filter(|id| !existing.contains(id))
+        .collect();
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start importing tasks")?;
+    for task in tasks.iter().filter(|task| added.contains(&task.get_id())) {
+        add_to_db(&tx, task)?;
+    }
+    // Only dependencies of tasks added now, on tasks that are here, come in
+    for (task, blocker) in dependencies {
+        if added.contains(task) && (added.contains(blocker) || existing.contains(blocker)) {
+            add_dependency_in_db(&tx, *task, *blocker)?;
+        }
+    }
+    tx.commit().context("Failed to import tasks")?;
+    Ok((added.len(), tasks.len() - added.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+    use crate::backend::database::{get_db, get_dependencies};
+    use crate::backend::import::tests::{random_task, Random};
+
+    /// Returns the time without the fraction of a second TaskWarrior doesn't keep
+    fn to_the_second(time: DateTime<Local>) -> DateTime<Local> {
+        time.with_nanosecond(0).unwrap()
+    }
+
+    #[test]
+    fn test_taskwarrior_round_trip() {
+        let mut blocker = Task::new(
+            "Book the venue".to_string(),
+            Some("Somewhere with parking".to_string()),
+            Some("Called two places".to_string()),
+            Some(Urgency::Critical),
+            Some(Status::Paused),
+            Some(HashSet::from(["party".to_string()])),
+        );
+        blocker.date_added = Local.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        blocker.project = Some("Birthday".to_string());
+        blocker.due = NaiveDate::from_ymd_opt(2024, 4, 1);
+        let mut task = Task::new(
+            "Send invites".to_string(),
+            None,
+            None,
+            Some(Urgency::Medium),
+            Some(Status::Completed),
+            None,
+        );
+        task.date_added = Local.with_ymd_and_hms(2024, 3, 2, 10, 30, 0).unwrap();
+        task.completed_on = Some(Local.with_ymd_and_hms(2024, 3, 5, 18, 0, 0).unwrap());
+        task.scheduled = NaiveDate::from_ymd_opt(2024, 3, 4);
+        let dependencies = vec![(task.get_id(), blocker.get_id())];
+
+        let exported = taskwarrior_json(&[blocker.clone(), task.clone()], &dependencies);
+        assert_eq!(exported[0]["priority"], "H");
+        assert_eq!(exported[1]["status"], "completed");
+        assert_eq!(exported[1]["depends"][0], blocker.get_id().to_string());
+
+        let (tasks, read_dependencies) = tasks_from_taskwarrior(&exported.to_string()).unwrap();
+        assert_eq!(tasks, vec![blocker, task]);
+        assert_eq!(read_dependencies, dependencies);
+    }
+
+    #[test]
+    fn test_random_round_trip() {
+        for seed in 1..=5u64 {
+            let mut random = Random::seeded(seed);
+            let tasks: Vec<Task> = (0..40).map(|_| random_task(&mut random)).collect();
+            let exported = taskwarrior_json(&tasks, &[]).to_string();
+            let (read, _) = tasks_from_taskwarrior(&exported).unwrap();
+
+            let expected: Vec<Task> = tasks
+                .into_iter()
+                .map(|mut task| {
+                    task.date_added = to_the_second(task.date_added);
+                    task.completed_on = task.completed_on.map(to_the_second);
+                    // TaskWarrior's start is when an active task was started, so
+                    // only a `Working` task has a timer, from when it was added
+                    // if it had none
+                    task.timer_started = (task.status == Status::Working)
+                        .then(|| to_the_second(task.timer_started.unwrap_or(task.date_added)));
+                    // TaskWarrior keeps the recurrence on the template its
+                    // occurrences are made from, and has no place for the rest
+                    task.recurrence = None;
+                    task.previous_occurrence = None;
+                    task.time_spent = 0;
+                    task.scheduled_start = None;
+                    task.duration = None;
+                    task.external_ref = None;
+                    task.locked = false;
+                    task.criteria = vec![];
+                    task
+                })
+                .collect();
+            assert_eq!(read, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_tasks_from_taskwarrior() {
+        let export = r#"[
+            {"uuid": "0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f01", "description": "Fix the bike",
+             "status": "pending", "entry": "20240301T090000Z", "start": "20240302T090000Z",
+             "priority": "M", "tags": ["home", "errand;s"],
+             "annotations": [{"entry": "20240301T091000Z", "description": "Flat tyre"},
+                             {"entry": "20240301T092000Z", "description": "Needs a new chain"}],
+             "due": "20240310T230000Z", "urgency": 9.2},
+            {"uuid": "0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f02", "description": "Weekly review",
+             "status": "recurring", "recur": "weekly", "entry": "20240301T090000Z"},
+            {"uuid": "0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f03", "description": "Old idea",
+             "status": "deleted", "entry": "20240301T090000Z"},
+            {"uuid": "0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f04", "description": "Buy a pump",
+             "status": "waiting", "entry": "20240301T090000Z",
+             "depends": "0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f01,0b8d5f35-3b46-4a4c-8a7c-0f6a6a3d1f05"}
+        ]"#;
+        let (tasks, dependencies) = tasks_from_taskwarrior(export).unwrap();
+        assert_eq!(tasks.len(), 2);
+        let (bike, pump) = (&tasks[0], &tasks[1]);
+        assert_eq!(bike.status, Status::Working);
+        assert_eq!(
+            bike.timer_started,
+            Some(
+                Utc.with_ymd_and_hms(2024, 3, 2, 9, 0, 0)
+                    .unwrap()
+                    .with_timezone(&Local)
+            )
+        );
+        assert_eq!(bike.urgency, Urgency::Medium);
+        assert_eq!(
+            bike.tags,
+            Some(HashSet::from(["home".to_string(), "errand,s".to_string()]))
+        );
+        assert_eq!(
+            bike.description.as_deref(),
+            Some("Flat tyre\nNeeds a new chain")
+        );
+        assert_eq!(
+            bike.due,
+            Some(
+                Utc.with_ymd_and_hms(2024, 3, 10, 23, 0, 0)
+                    .unwrap()
+                    .with_timezone(&Local)
+                    .date_naive()
+            )
+        );
+        assert_eq!(pump.status, Status::Open);
+        assert_eq!(pump.urgency, Urgency::Low);
+
+        // The dependency on a task that isn't there is left out on import
+        let conn = get_db(true, false).unwrap();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(
+            import_taskwarrior(&conn, &tasks, &dependencies).unwrap(),
+            (2, 0)
+        );
+        assert_eq!(
+            get_dependencies(&conn).unwrap(),
+            vec![(pump.get_id(), bike.get_id())]
+        );
+        assert_eq!(
+            import_taskwarrior(&conn, &tasks, &dependencies).unwrap(),
+            (0, 2)
+        );
+
+        assert!(tasks_from_taskwarrior("{}").is_err());
+    }
+}
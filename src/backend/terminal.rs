@@ -0,0 +1,24 @@
+use anyhow::Result;
+use crossterm::terminal;
+use tracing::warn;
+
+/// Keeps the terminal in raw mode for as long as it's held, for the inline
+/// prompts that read a key at a time
+pub struct RawModeGuard;
+
+impl RawModeGuard {
+    /// Puts the terminal in raw mode until the guard is dropped
+    pub fn enable() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        // Panicking while already unwinding would abort, so just log it
+        if let Err(e) = terminal::disable_raw_mode() {
+            warn!("Could not disable raw mode: {}", e);
+        }
+    }
+}
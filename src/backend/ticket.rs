@@ -0,0 +1,97 @@
+use crate::backend::task::Task;
+
+/// Placeholder in the `ticket_url` config setting that the ticket goes in
+pub const TICKET_PLACEHOLDER: &str = "{ticket}";
+
+/// Returns true if the word looks like a ticket from a tracker like Jira or
+/// Linear: a project key of capital letters and digits, a dash, and a number
+fn is_ticket(word: &str) -> bool {
+    let Some((key, number)) = word.split_once('-') else {
+        return false;
+    };
+    key.len() >= 2
+        && key.starts_with(|c: char| c.is_ascii_uppercase())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Returns the tickets (i.e. ABC-123) mentioned in the text, in the order
+/// they first show up
+pub fn find_tickets(text: &str) -> Vec<String> {
+    let mut tickets: Vec<String> = vec![];
+    for word in text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+        if is_ticket(word) && !tickets.iter().any(|ticket| ticket == word) {
+            tickets.push(word.to_string());
+        }
+    }
+    tickets
+}
+
+/// Returns the tickets mentioned in the name, latest update,
+/// and description of the `Task`
+pub fn task_tickets(task: &Task) -> Vec<String> {
+    let text = [
+        Some(task.name.as_str()),
+        task.latest.as_deref(),
+        task.description.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<&str>>()
+    .join("\n");
+    find_tickets(&text)
+}
+
+/// Fills the ticket into the URL template, i.e.
+/// `https://example.atlassian.net/browse/{ticket}`.
+/// Templates without the placeholder get the ticket added on the end.
+pub fn ticket_url(template: &str, ticket: &str) -> String {
+    if template.contains(TICKET_PLACEHOLDER) {
+        template.replace(TICKET_PLACEHOLDER, ticket)
+    } else {
+        format!("{}{}", template, ticket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_tickets() {
+        assert_eq!(
+            find_tickets("Fix login (ABC-123), see ENG2-7 and ABC-123 again"),
+            vec![String::from("ABC-123"), String::from("ENG2-7")]
+        );
+        assert!(find_tickets("A-1 abc-12 ABC- -123 ABC-12x covid-19").is_empty());
+
+        let mut task = Task::new(
+            String::from("Review OPS-9"),
+            Some(String::from("Blocked on WEB-40")),
+            None,
+            None,
+            None,
+            None,
+        );
+        task.latest = Some(String::from("Pinged about OPS-9"));
+        assert_eq!(
+            task_tickets(&task),
+            vec![String::from("OPS-9"), String::from("WEB-40")]
+        );
+    }
+
+    #[test]
+    fn test_ticket_url() {
+        assert_eq!(
+            ticket_url("https://example.atlassian.net/browse/{ticket}", "ABC-1"),
+            "https://example.atlassian.net/browse/ABC-1"
+        );
+        assert_eq!(
+            ticket_url("https://linear.app/acme/issue/", "ENG-2"),
+            "https://linear.app/acme/issue/ENG-2"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::backend::database::{add_to_db, get_all_db_contents, get_archive_contents};
+use crate::backend::http::curl;
+use crate::backend::task::{find_external, ExternalRef, Status, Task, Urgency};
+
+/// System the `ExternalRef` of a `Task` imported from Todoist is in
+pub const TODOIST_SYSTEM: &str = "todoist";
+
+/// Todoist's sync endpoint, which hands back every project and open task at once
+const SYNC_URL: &str = "https://api.todoist.com/api/v1/sync";
+
+/// Returns true if the text looks like a Todoist API token rather than a file
+pub fn is_todoist_token(text: &str) -> bool {
+    text.len() >= 20 && text.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Fetches the projects and open tasks from Todoist with the API token, as JSON
+pub fn fetch_todoist(token: &str) -> Result<String> {
+    let authorization = format!("Bearer {}", token.trim());
+    let response = curl(
+        "POST",
+        SYNC_URL,
+        &[
+            ("Authorization", &authorization),
+            ("Content-Type", "application/x-www-form-urlencoded"),
+        ],
+        // resource_types=["projects","items"]
+        Some("sync_token=*&resource_types=%5B%22projects%22%2C%22items%22%5D"),
+        None,
+    )?;
+    match response.status {
+        200 => Ok(response.body),
+        401 | 403 => bail!("Todoist didn't accept the API token"),
+        status => bail!(
+            "Failed to fetch the tasks from Todoist, it said {}: {}",
+            status,
+            response.body.trim()
+        ),
+    }
+}
+
+/// Returns the text of a Todoist id, which can be a string or a number
+fn id_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) if !id.is_empty() => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Makes a Todoist project or label name into a tag, which can't have ';' in it
+fn as_tag(name: &str) -> Option<String> {
+    let tag = name.trim().replace(';', ",");
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Reads a Todoist time, which is RFC 3339 but can leave out the time zone
+fn parse_todoist_time(value: &Value) -> Option<DateTime<Local>> {
+    let text = value.as_str()?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Local));
+    }
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+/// Returns the `Urgency` for a Todoist priority. The API counts the other way
+/// around from the app, so 4 is what the app calls p1.
+fn urgency_from_priority(priority: u64) -> Urgency {
+    match priority {
+        4 => Urgency::Critical,
+        3 => Urgency::High,
+        2 => Urgency::Medium,
+        _ => Urgency::Low,
+    }
+}
+
+/// Reads the `Task`s from Todoist JSON, either what the sync API sends (`items`
+/// and `projects`) or what the REST API sends (`tasks` and `projects`, or just
+/// an array of tasks). Projects and labels become tags, priorities become
+/// `Urgency`, and subtasks stay under their parents. Each `Task` gets an
+/// `ExternalRef` to the Todoist task it came from.
+pub fn tasks_from_todoist(text: &str) -> Result<Vec<Task>> {
+    let value: Value = serde_json::from_str(text).context("The Todoist export isn't valid JSON")?;
+    let (items, projects) = match &value {
+        Value::Array(items) => (items.as_slice(), &[][..]),
+        Value::Object(object) => {
+            let Some(items) = object
+                .get("items")
+                .or_else(|| object.get("tasks"))
+                .and_then(Value::as_array)
+            else {
+                bail!("There are no tasks in the Todoist export, it needs an \"items\" or \"tasks\" list");
+            };
+            let projects = object
+                .get("projects")
+                .and_then(Value::as_array)
+                .map_or(&[][..], |projects| projects.as_slice());
+            (items.as_slice(), projects)
+        }
+        _ => bail!("There are no tasks in the Todoist export"),
+    };
+
+    let project_names: HashMap<String, &str> = projects
+        .iter()
+        .filter_map(|project| Some((id_text(&project["id"])?, project["name"].as_str()?)))
+        .collect();
+    let ids: HashMap<String, Uuid> = items
+        .iter()
+        .filter_map(|item| id_text(&item["id"]))
+        .map(|todoist_id| (todoist_id, Uuid::new_v4()))
+        .collect();
+
+    let mut tasks = vec![];
+    for (i, item) in items.iter().enumerate() {
+        if item["is_deleted"].as_bool() == Some(true) {
+            continue;
+        }
+        let Some(todoist_id) = id_text(&item["id"]) else {
+            bail!("Task {} in the Todoist export has no id", i + 1);
+        };
+        let Some(name) = item["content"]
+            .as_str()
+            .filter(|name| !name.trim().is_empty())
+        else {
+            bail!("Task {} in the Todoist export has no content", i + 1);
+        };
+
+        let completed = item["checked"]
+            .as_bool()
+            .or_else(|| item["is_completed"].as_bool())
+            .unwrap_or(false);
+        let mut tags: HashSet<String> = item["labels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter_map(as_tag)
+            .collect();
+        if let Some(project) = id_text(&item["project_id"])
+            .and_then(|project_id| project_names.get(&project_id).copied())
+            .and_then(as_tag)
+        {
+            tags.insert(project);
+        }
+        let date_added = parse_todoist_time(&item["added_at"])
+            .or_else(|| parse_todoist_time(&item["created_at"]))
+            .unwrap_or_else(Local::now);
+
+        let mut task = Task::from_sql(
+            ids[&todoist_id],
+            name.trim().to_string(),
+            item["description"]
+                .as_str()
+                .filter(|description| !description.trim().is_empty())
+                .map(String::from),
+            None,
+            urgency_from_priority(item["priority"].as_u64().unwrap_or(1)),
+            if completed {
+                Status::Completed
+            } else {
+                Status::Open
+            },
+            (!tags.is_empty()).then_some(tags),
+            date_added,
+            completed.then(|| parse_todoist_time(&item["completed_at"]).unwrap_or_else(Local::now)),
+        );
+        // Due dates with a time are written as a date and time, so only the date is kept
+        task.due = item["due"]["date"]
+            .as_str()
+            .and_then(|due| NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok());
+        task.parent = id_text(&item["parent_id"]).and_then(|parent| ids.get(&parent).copied());
+        task.external_ref = Some(ExternalRef {
+            system: TODOIST_SYSTEM.to_string(),
+            url: Some(format!("https://app.todoist.com/app/task/{}", todoist_id)),
+            id: todoist_id,
+        });
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Adds the `Task`s read from Todoist to the database, skipping any brought in
+/// before (archive included) so an import can be run again. Subtasks of those
+/// go under the copies already here. Either all of them are added or none are.
+/// Returns how many were added and how many were skipped.
+pub fn import_todoist(conn: &Connection, tasks: Vec<Task>) -> Result<(usize, usize)> {
+    let mut existing = get_all_db_contents(conn)?.tasks;
+    existing.extend(get_archive_contents(conn)?.tasks);
+
+    let mut already_here: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut new = vec![];
+    for task in tasks {
+        let found = task
+            .external_ref
+            .as_ref()
+            .and_then(|external_ref| find_external(&existing, external_ref));
+        match found {
+            Some(found) => {
+                already_here.insert(task.get_id(), found.get_id());
+            }
+            None => new.push(task),
+        }
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start importing tasks")?;
+    for task in &mut new {
+        if let Some(parent) = task.parent.and_then(|parent| already_here.get(&parent)) {
+            task.parent = Some(*parent);
+        }
+        add_to_db(&tx, task)?;
+    }
+    tx.commit().context("Failed to import tasks")?;
+    Ok((new.len(), already_here.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::database::get_db;
+
+    const EXPORT: &str = r#"{
+        "projects": [
+            {"id": "220474322", "name": "Inbox"},
+            {"id": "220474323", "name": "Home; garden"}
+        ],
+        "items": [
+            {
+                "id": "2995104339",
+                "content": "Repaint the fence",
+                "description": "Buy paint first",
+                "project_id": "220474323",
+                "priority": 4,
+                "labels": ["weekend"],
+                "due": {"date": "2024-06-01", "is_recurring": false},
+                "added_at": "2024-05-01T09:00:00.000000Z",
+                "checked": false
+            },
+            {
+                "id": "2995104340",
+                "content": "Sand it down",
+                "project_id": "220474323",
+                "parent_id": "2995104339",
+                "priority": 2,
+                "due": {"date": "2024-05-25T10:00:00"},
+                "checked": true,
+                "completed_at": "2024-05-20T18:30:00Z"
+            },
+            {
+                "id": "2995104341",
+                "content": "Gone",
+                "project_id": "220474322",
+                "is_deleted": true
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_tasks_from_todoist() {
+        let tasks = tasks_from_todoist(EXPORT).unwrap();
+        assert_eq!(tasks.len(), 2);
+        let (fence, sand) = (&tasks[0], &tasks[1]);
+
+        assert_eq!(fence.name, "Repaint the fence");
+        assert_eq!(fence.description.as_deref(), Some("Buy paint first"));
+        assert_eq!(fence.urgency, Urgency::Critical);
+        assert_eq!(fence.status, Status::Open);
+        assert_eq!(
+            fence.tags,
+            Some(HashSet::from([
+                "weekend".to_string(),
+                "Home, garden".to_string()
+            ]))
+        );
+        assert_eq!(fence.due, NaiveDate::from_ymd_opt(2024, 6, 1));
+        assert_eq!(
+            fence.date_added,
+            DateTime::parse_from_rfc3339("2024-05-01T09:00:00Z").unwrap()
+        );
+        assert_eq!(fence.external_ref.as_ref().unwrap().id, "2995104339");
+
+        assert_eq!(sand.urgency, Urgency::Medium);
+        assert_eq!(sand.status, Status::Completed);
+        assert_eq!(
+            sand.completed_on.unwrap(),
+            DateTime::parse_from_rfc3339("2024-05-20T18:30:00Z").unwrap()
+        );
+        assert_eq!(sand.due, NaiveDate::from_ymd_opt(2024, 5, 25));
+        assert_eq!(sand.parent, Some(fence.get_id()));
+
+        // The REST API's layout reads the same
+        let rest = r#"[{"id": 7, "content": "Call mum", "priority": 1, "is_completed": false}]"#;
+        let tasks = tasks_from_todoist(rest).unwrap();
+        assert_eq!(tasks[0].name, "Call mum");
+        assert_eq!(tasks[0].urgency, Urgency::Low);
+        assert_eq!(tasks[0].tags, None);
+        assert_eq!(tasks[0].external_ref.as_ref().unwrap().id, "7");
+
+        assert!(tasks_from_todoist("{\"projects\": []}").is_err());
+        assert!(tasks_from_todoist("not json").is_err());
+    }
+
+    #[test]
+    fn test_import_todoist() {
+        let conn = get_db(true, false).unwrap();
+        let mut tasks = tasks_from_todoist(EXPORT).unwrap();
+        // The subtask shows up later, once its parent has been imported
+        let sand = tasks.pop().unwrap();
+        assert_eq!(import_todoist(&conn, tasks).unwrap(), (1, 0));
+
+        let tasks = tasks_from_todoist(EXPORT).unwrap();
+        assert_eq!(import_todoist(&conn, tasks).unwrap(), (1, 1));
+        let imported = get_all_db_contents(&conn).unwrap().tasks;
+        assert_eq!(imported.len(), 2);
+        let fence = imported
+            .iter()
+            .find(|task| task.name == "Repaint the fence")
+            .unwrap();
+        let sanded = imported.iter().find(|task| task.name == sand.name).unwrap();
+        assert_eq!(sanded.parent, Some(fence.get_id()));
+
+        assert!(is_todoist_token("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_todoist_token("export.json"));
+    }
+}
@@ -1,12 +1,19 @@
 use anyhow::Result;
 use rusqlite::Connection;
 
+use crate::backend::backup::backup_before;
 use crate::backend::database::remove_all_db_contents;
 
 /// Wipes all tasks in a SQLite database `&Connection`.
 /// A confirmation prompt will be given unless `confirm_skip` is true.
 /// If `hard` is true, then this will also drop the Task table.
-pub fn wipe_tasks(conn: &Connection, confirm_skip: bool, hard: bool) -> Result<()> {
+/// The database is backed up first, keeping the newest `backup_count` backups.
+pub fn wipe_tasks(
+    conn: &Connection,
+    confirm_skip: bool,
+    hard: bool,
+    backup_count: usize,
+) -> Result<()> {
     if !confirm_skip {
         println!("Are you sure you want to proceed with the wipe? (y/n)");
         loop {
@@ -23,6 +30,9 @@ pub fn wipe_tasks(conn: &Connection, confirm_skip: bool, hard: bool) -> Result<(
             }
         }
     }
+    if let Some(path) = backup_before(conn, "wipe", backup_count)? {
+        println!("Backed up the database to {:?}", path);
+    }
     println!("Proceeding with wipe");
     remove_all_db_contents(conn, hard)?;
     println!("Success!");
@@ -1,12 +1,21 @@
 use std::collections::HashSet;
 
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{DateTime, Local, NaiveDate};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::backend::database::{add_to_db, update_task_in_db};
-use crate::backend::task::{Status, Task, Urgency};
+use crate::backend::calendar::today;
+use crate::backend::clipboard::{copy_to_clipboard, paste_from_clipboard};
+use crate::backend::collate::sort_text;
+use crate::backend::editor::edit_in_editor;
+use crate::backend::history::Change;
+use crate::backend::task::{
+    format_slot_minutes, parse_completed_on, parse_due_date, parse_scheduled_date, parse_time_slot,
+    Recurrence, Status, Task, Urgency, COMPLETED_ON_FORMAT, DEFAULT_SLOT_MINUTES,
+};
+use crate::display::keymap::KeyContext;
+use crate::display::text::{byte_index, grapheme_count, insert_char, remove_before};
 use crate::display::tui::App;
 
 /// Enum to flag if the input being provided by the user
@@ -20,7 +29,7 @@ pub enum EntryMode {
 }
 
 /// Enum to flag the stage we are at during the Add process.
-#[derive(PartialEq, PartialOrd, Eq, Ord, Default)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Default)]
 pub enum Stage {
     Staging,
     #[default]
@@ -30,6 +39,12 @@ pub enum Stage {
     Description,
     Latest,
     Tags,
+    Recurrence,
+    Project,
+    Due,
+    Scheduled,
+    CompletedOn,
+    TimeSlot,
     Finished,
 }
 
@@ -43,7 +58,10 @@ impl Stage {
             Stage::Status => *self = Stage::Description,
             Stage::Description => *self = Stage::Latest,
             Stage::Latest => *self = Stage::Tags,
-            Stage::Tags => *self = Stage::Finished,
+            Stage::Tags => *self = Stage::Recurrence,
+            Stage::Recurrence => *self = Stage::Project,
+            Stage::Project => *self = Stage::Due,
+            Stage::Due => *self = Stage::Finished,
             _ => {}
         }
     }
@@ -52,7 +70,10 @@ impl Stage {
     /// Begins at Finished, ends at Name
     pub fn back(&mut self) {
         match self {
-            Stage::Finished => *self = Stage::Tags,
+            Stage::Finished => *self = Stage::Due,
+            Stage::Due => *self = Stage::Project,
+            Stage::Project => *self = Stage::Recurrence,
+            Stage::Recurrence => *self = Stage::Tags,
             Stage::Tags => *self = Stage::Latest,
             Stage::Latest => *self = Stage::Description,
             Stage::Description => *self = Stage::Status,
@@ -73,6 +94,24 @@ pub struct Inputs {
     pub latest: String,
     pub tags: HashSet<String>,
     pub tags_input: String,
+    pub recurrence: String,
+    pub project: String,
+    pub due: String,
+    pub scheduled: String,
+    pub completed_on: String,
+    pub time_slot: String,
+}
+
+/// Returns the time slot of the `Task` the way it is typed in, i.e. "14:00 45m"
+fn time_slot_input(task: &Task) -> String {
+    match task.scheduled_start {
+        Some(start) => format!(
+            "{} {}",
+            start.format("%H:%M"),
+            format_slot_minutes(task.duration.unwrap_or(DEFAULT_SLOT_MINUTES))
+        ),
+        None => String::new(),
+    }
 }
 
 impl Inputs {
@@ -86,6 +125,78 @@ impl Inputs {
             latest: task.latest.clone().unwrap_or("".to_string()),
             tags: task.tags.clone().unwrap_or_default(),
             tags_input: "".to_string(),
+            recurrence: task
+                .recurrence
+                .map(|recurrence| recurrence.to_string())
+                .unwrap_or_default(),
+            project: task.project.clone().unwrap_or_default(),
+            due: task.due.map(|due| due.to_string()).unwrap_or_default(),
+            scheduled: task
+                .scheduled
+                .map(|scheduled| scheduled.to_string())
+                .unwrap_or_default(),
+            completed_on: task
+                .completed_on
+                .map(|completed_on| completed_on.format(COMPLETED_ON_FORMAT).to_string())
+                .unwrap_or_default(),
+            time_slot: time_slot_input(task),
+        }
+    }
+
+    /// Parses the time slot input as a slot for today,
+    /// where an empty input means no time slot
+    pub fn parse_time_slot(&self) -> Result<Option<(DateTime<Local>, i64)>> {
+        parse_time_slot(&self.time_slot, today())
+    }
+
+    /// Whether the time slot input differs from the `Task`'s own time slot,
+    /// so that updating something else doesn't move the slot to today
+    pub fn time_slot_changed(&self, task: &Task) -> bool {
+        self.time_slot != time_slot_input(task)
+    }
+
+    /// Parses the recurrence input, where an empty input means no recurrence
+    pub fn parse_recurrence(&self) -> Result<Option<Recurrence>> {
+        if self.recurrence.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.recurrence.parse()?))
+    }
+
+    /// Parses the due date input, where an empty input means no due date
+    pub fn parse_due(&self) -> Result<Option<NaiveDate>> {
+        parse_due_date(&self.due, today())
+    }
+
+    /// Parses the snooze date input, where an empty input means not snoozed
+    pub fn parse_scheduled(&self) -> Result<Option<NaiveDate>> {
+        parse_scheduled_date(&self.scheduled, today())
+    }
+
+    /// Parses the completion date input for the `Task` being updated.
+    /// Returns `None` if it is unchanged from the `Task`'s.
+    pub fn parse_completed_on(&self, task: &Task) -> Result<Option<DateTime<Local>>> {
+        let unchanged = task
+            .completed_on
+            .map(|completed_on| completed_on.format(COMPLETED_ON_FORMAT).to_string())
+            .unwrap_or_default();
+        if self.completed_on == unchanged {
+            return Ok(None);
+        }
+        Ok(Some(parse_completed_on(
+            &self.completed_on,
+            task,
+            Local::now(),
+        )?))
+    }
+
+    /// Returns the project input, where an empty input means no project
+    pub fn parse_project(&self) -> Option<String> {
+        let project = self.project.trim();
+        if project.is_empty() {
+            None
+        } else {
+            Some(project.to_string())
         }
     }
 }
@@ -99,52 +210,11 @@ impl App {
         }
     }
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        let stage = self.get_stage_off_entry_mode();
-
-        match stage {
-            Stage::Name => new_cursor_pos.clamp(0, self.inputs.name.chars().count()),
-            Stage::Description => new_cursor_pos.clamp(0, self.inputs.description.chars().count()),
-            Stage::Latest => new_cursor_pos.clamp(0, self.inputs.latest.chars().count()),
-            Stage::Tags => new_cursor_pos.clamp(0, self.inputs.tags_input.chars().count()),
-            _ => 0,
-        }
-    }
-
-    fn byte_index(&self) -> usize {
-        let stage = self.get_stage_off_entry_mode();
-
-        match stage {
-            Stage::Name => self
-                .inputs
-                .name
-                .char_indices()
-                .map(|(i, _)| i)
-                .nth(self.character_index)
-                .unwrap_or(self.inputs.name.len()),
-            Stage::Description => self
-                .inputs
-                .description
-                .char_indices()
-                .map(|(i, _)| i)
-                .nth(self.character_index)
-                .unwrap_or(self.inputs.description.len()),
-            Stage::Latest => self
-                .inputs
-                .latest
-                .char_indices()
-                .map(|(i, _)| i)
-                .nth(self.character_index)
-                .unwrap_or(self.inputs.latest.len()),
-            Stage::Tags => self
-                .inputs
-                .tags_input
-                .char_indices()
-                .map(|(i, _)| i)
-                .nth(self.character_index)
-                .unwrap_or(self.inputs.tags_input.len()),
-            _ => 0,
-        }
+    fn clamp_cursor(&mut self, new_cursor_pos: usize) -> usize {
+        let length = self
+            .current_input()
+            .map_or(0, |input| grapheme_count(input));
+        new_cursor_pos.clamp(0, length)
     }
 
     fn move_cursor_left(&mut self) {
@@ -157,78 +227,142 @@ impl App {
         self.character_index = self.clamp_cursor(cursor_moved_right);
     }
 
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-
-        let stage = self.get_stage_off_entry_mode();
+    /// Returns the input being typed into at the current stage
+    fn current_input(&mut self) -> Option<&mut String> {
+        let stage = match self.entry_mode {
+            EntryMode::Add | EntryMode::QuickAdd => self.add_stage,
+            EntryMode::Update => self.update_stage,
+        };
 
         match stage {
-            Stage::Name => self.inputs.name.insert(index, new_char),
-            Stage::Description => self.inputs.description.insert(index, new_char),
-            Stage::Latest => self.inputs.latest.insert(index, new_char),
-            Stage::Tags => self.inputs.tags_input.insert(index, new_char),
-            _ => {}
+            Stage::Name => Some(&mut self.inputs.name),
+            Stage::Description => Some(&mut self.inputs.description),
+            Stage::Latest => Some(&mut self.inputs.latest),
+            Stage::Tags => Some(&mut self.inputs.tags_input),
+            Stage::Recurrence => Some(&mut self.inputs.recurrence),
+            Stage::Project => Some(&mut self.inputs.project),
+            Stage::Due => Some(&mut self.inputs.due),
+            Stage::Scheduled => Some(&mut self.inputs.scheduled),
+            Stage::CompletedOn => Some(&mut self.inputs.completed_on),
+            Stage::TimeSlot => Some(&mut self.inputs.time_slot),
+            _ => None,
         }
-        self.move_cursor_right();
     }
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            let stage = self.get_stage_off_entry_mode();
-
-            match stage {
-                Stage::Name => {
-                    let before_char_to_delete =
-                        self.inputs.name.chars().take(from_left_to_current_index);
-                    let after_char_to_delete = self.inputs.name.chars().skip(current_index);
-                    self.inputs.name = before_char_to_delete.chain(after_char_to_delete).collect();
-                }
-                Stage::Description => {
-                    let before_char_to_delete = self
-                        .inputs
-                        .description
-                        .chars()
-                        .take(from_left_to_current_index);
-                    let after_char_to_delete = self.inputs.description.chars().skip(current_index);
-                    self.inputs.description =
-                        before_char_to_delete.chain(after_char_to_delete).collect();
-                }
-                Stage::Latest => {
-                    let before_char_to_delete =
-                        self.inputs.latest.chars().take(from_left_to_current_index);
-                    let after_char_to_delete = self.inputs.latest.chars().skip(current_index);
-                    self.inputs.latest =
-                        before_char_to_delete.chain(after_char_to_delete).collect();
-                }
-                Stage::Tags => {
-                    let before_char_to_delete = self
-                        .inputs
-                        .tags_input
-                        .chars()
-                        .take(from_left_to_current_index);
-                    let after_char_to_delete = self.inputs.tags_input.chars().skip(current_index);
-                    self.inputs.tags_input =
-                        before_char_to_delete.chain(after_char_to_delete).collect();
+    /// Handles `Ctrl+v` to paste at the cursor, and `Ctrl+c`/`Ctrl+x` to copy/cut
+    /// the highlighted tag, or the whole input if no tag is highlighted.
+    /// Returns true if the key was one of them.
+    fn handle_clipboard_keys(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers != KeyModifiers::CONTROL {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char('v') => match paste_from_clipboard() {
+                Ok(text) => {
+                    // Inputs are a single line, so line breaks become spaces
+                    let text = text.trim_end_matches(['\r', '\n']).replace("\r\n", " ");
+                    for ch in text.chars() {
+                        self.enter_char(if ch == '\n' || ch == '\r' { ' ' } else { ch });
+                    }
+                }
+                Err(e) => self.show_toast(format!("Paste failed: {}", e)),
+            },
+            KeyCode::Char(ch @ ('c' | 'x')) => {
+                let highlighted_tag = self.highlight_tags
+                    && *self.get_stage_off_entry_mode() == Stage::Tags
+                    && self.tags_highlight_value < self.inputs.tags.len();
+                let text = if highlighted_tag {
+                    // Match the order the tags are displayed in
+                    let mut tags = Vec::from_iter(self.inputs.tags.clone());
+                    sort_text(&mut tags);
+                    tags.swap_remove(self.tags_highlight_value)
+                } else {
+                    match self.current_input() {
+                        Some(input) => input.clone(),
+                        None => return true,
+                    }
+                };
+                if let Err(e) = copy_to_clipboard(&text) {
+                    self.show_toast(format!("Copy failed: {}", e));
+                    return true;
+                }
+                if ch == 'x' {
+                    if highlighted_tag {
+                        self.remove_tag();
+                    } else if let Some(input) = self.current_input() {
+                        input.clear();
+                        self.character_index = 0;
+                    }
                 }
-                _ => {}
             }
-            self.move_cursor_left();
+            _ => return false,
+        }
+        true
+    }
+
+    /// Opens what has been typed into the current input in `$EDITOR`,
+    /// putting the edited text back in its place. Like pasting, line
+    /// breaks become spaces, since inputs are a single line.
+    pub fn edit_current_input(&mut self) -> Result<()> {
+        let Some(input) = self.current_input() else {
+            return Ok(());
+        };
+        let edited = edit_in_editor(input)?;
+        *input = edited
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        self.character_index = grapheme_count(input);
+        Ok(())
+    }
+
+    fn enter_char(&mut self, new_char: char) {
+        let index = self.character_index;
+        if let Some(input) = self.current_input() {
+            self.character_index = insert_char(input, index, new_char);
+        }
+    }
+
+    fn delete_char(&mut self) {
+        let index = self.character_index;
+        if let Some(input) = self.current_input() {
+            if remove_before(input, index) {
+                self.move_cursor_left();
+            }
+        }
+    }
+
+    /// Deletes the character under the cursor, leaving the cursor where it is
+    fn delete_next_char(&mut self) {
+        let index = self.character_index;
+        if let Some(input) = self.current_input() {
+            remove_before(input, index + 1);
+        }
+    }
+
+    /// Deletes everything from the cursor to the end of the input
+    fn delete_to_end(&mut self) {
+        let index = self.character_index;
+        if let Some(input) = self.current_input() {
+            input.truncate(byte_index(input, index));
         }
     }
 
     /// Handles the `KeyEvent` when user is choosing what to update
     pub fn handle_update_staging(&mut self, key: KeyEvent) {
-        let current_index = self.tasklist.state.selected().unwrap();
+        // The task being updated may have gone from the list since
+        let Some(current_index) = self.tasklist.state.selected() else {
+            self.update_popup = false;
+            return;
+        };
         match key.code {
             KeyCode::Esc => self.update_popup = !self.update_popup,
             KeyCode::Char(ch) => {
                 if ch == '1' {
                     self.update_stage = Stage::Name;
-                    self.character_index = self.tasklist.tasks[current_index].name.len();
+                    self.character_index = grapheme_count(&self.inputs.name);
                 }
                 if ch == '2' {
                     self.update_stage = Stage::Status;
@@ -238,24 +372,41 @@ impl App {
                 }
                 if ch == '4' {
                     self.update_stage = Stage::Description;
-                    self.character_index = self.tasklist.tasks[current_index]
-                        .description
-                        .clone()
-                        .unwrap_or("".to_string())
-                        .len();
+                    self.character_index = grapheme_count(&self.inputs.description);
                 }
                 if ch == '5' {
                     self.update_stage = Stage::Latest;
-                    self.character_index = self.tasklist.tasks[current_index]
-                        .latest
-                        .clone()
-                        .unwrap_or("".to_string())
-                        .len();
+                    self.character_index = grapheme_count(&self.inputs.latest);
                 }
                 if ch == '6' {
                     self.character_index = 0;
                     self.update_stage = Stage::Tags;
                 }
+                if ch == '7' {
+                    self.update_stage = Stage::Recurrence;
+                    self.character_index = grapheme_count(&self.inputs.recurrence);
+                }
+                if ch == '8' {
+                    self.update_stage = Stage::Project;
+                    self.character_index = grapheme_count(&self.inputs.project);
+                }
+                if ch == '9' {
+                    self.update_stage = Stage::Due;
+                    self.character_index = grapheme_count(&self.inputs.due);
+                }
+                if ch == '0' {
+                    self.update_stage = Stage::Scheduled;
+                    self.character_index = grapheme_count(&self.inputs.scheduled);
+                }
+                // Only completed tasks have a completion date to change
+                if ch == 'c' && self.tasklist.tasks[current_index].status == Status::Completed {
+                    self.update_stage = Stage::CompletedOn;
+                    self.character_index = grapheme_count(&self.inputs.completed_on);
+                }
+                if ch == 't' {
+                    self.update_stage = Stage::TimeSlot;
+                    self.character_index = grapheme_count(&self.inputs.time_slot);
+                }
             }
             _ => {}
         }
@@ -263,6 +414,25 @@ impl App {
 
     /// Handles the `KeyEvent` when user is providing text input
     pub fn handle_keys_for_text_inputs(&mut self, key: KeyEvent) {
+        let key = self.config.keymap.translate(key, KeyContext::Form);
+        if self.handle_clipboard_keys(key) {
+            return;
+        }
+        if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('k') {
+            self.delete_to_end();
+            return;
+        }
+        // Long text is easier to write in an editor, which the main loop opens
+        if key.modifiers == KeyModifiers::CONTROL
+            && key.code == KeyCode::Char('e')
+            && matches!(
+                self.get_stage_off_entry_mode(),
+                Stage::Description | Stage::Latest
+            )
+        {
+            self.open_editor = true;
+            return;
+        }
         match key.code {
             KeyCode::Esc => {
                 if self.entry_mode == EntryMode::Add || self.entry_mode == EntryMode::QuickAdd {
@@ -273,6 +443,27 @@ impl App {
                 }
             }
             KeyCode::Enter => {
+                // Don't move on until the recurrence or dates are something we understand
+                let stage = self.get_stage_off_entry_mode();
+                if (*stage == Stage::Recurrence && self.inputs.parse_recurrence().is_err())
+                    || (*stage == Stage::Due && self.inputs.parse_due().is_err())
+                    || (*stage == Stage::Scheduled && self.inputs.parse_scheduled().is_err())
+                    || (*stage == Stage::TimeSlot && self.inputs.parse_time_slot().is_err())
+                {
+                    return;
+                }
+                if *stage == Stage::CompletedOn {
+                    let Some(current_index) = self.tasklist.state.selected() else {
+                        return;
+                    };
+                    if self
+                        .inputs
+                        .parse_completed_on(&self.tasklist.tasks[current_index])
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
                 if self.entry_mode == EntryMode::Add {
                     self.add_stage.next();
                 }
@@ -297,7 +488,10 @@ impl App {
                 }
             }
             KeyCode::Backspace => self.delete_char(),
+            KeyCode::Delete => self.delete_next_char(),
             KeyCode::Right => self.move_cursor_right(),
+            KeyCode::Home => self.character_index = 0,
+            KeyCode::End => self.character_index = self.clamp_cursor(usize::MAX),
             KeyCode::Char(ch) => self.enter_char(ch),
             _ => {}
         }
@@ -305,6 +499,10 @@ impl App {
 
     /// Handles the `KeyEvent` when the user is at the Tags `Stage`
     pub fn handle_keys_for_tags(&mut self, key: KeyEvent) {
+        let key = self.config.keymap.translate(key, KeyContext::Form);
+        if self.handle_clipboard_keys(key) {
+            return;
+        }
         match key.code {
             KeyCode::Esc => {
                 if self.entry_mode == EntryMode::Add {
@@ -375,10 +573,8 @@ impl App {
                 KeyCode::Right => {
                     self.move_cursor_right();
                 }
-                KeyCode::Down => {
-                    if !self.inputs.tags.is_empty() {
-                        self.highlight_tags = !self.highlight_tags;
-                    }
+                KeyCode::Down if !self.inputs.tags.is_empty() => {
+                    self.highlight_tags = !self.highlight_tags;
                 }
                 KeyCode::Char(ch) => self.enter_char(ch),
                 KeyCode::Backspace => self.delete_char(),
@@ -402,7 +598,7 @@ impl App {
     fn remove_tag(&mut self) {
         // Match what our displayed vectors are
         let mut task_tags_vec = Vec::from_iter(self.inputs.tags.clone());
-        task_tags_vec.sort();
+        sort_text(&mut task_tags_vec);
 
         // Get the value that is highlighted
         let tags_value = &task_tags_vec[self.tags_highlight_value];
@@ -520,7 +716,7 @@ impl App {
             Some(self.inputs.tags.clone())
         };
 
-        let new_task = Task::new(
+        let mut new_task = Task::new(
             self.inputs.name.clone(),
             description,
             latest,
@@ -528,16 +724,19 @@ impl App {
             Some(self.inputs.status),
             tags,
         );
-
-        add_to_db(&self.conn, &new_task).context("Failed to add the new task in")?;
+        new_task.recurrence = self.inputs.parse_recurrence().unwrap_or(None);
+        new_task.project = self.inputs.parse_project();
+        new_task.due = self.inputs.parse_due().unwrap_or(None);
+        new_task.parent = self.drill_path.last().copied();
+
+        self.store
+            .add(&new_task)
+            .context("Failed to add the new task in")?;
+        self.history.record(vec![Change::Added(new_task.clone())]);
         self.update_tasklist()
             .context("Failed to update the tasklist after adding the new task in")?;
 
-        for (i, task) in self.tasklist.tasks.iter().enumerate() {
-            if new_task.get_id() == task.get_id() {
-                self.tasklist.state.select(Some(i))
-            }
-        }
+        self.select_task(new_task.get_id())?;
 
         Ok(())
     }
@@ -545,8 +744,11 @@ impl App {
     /// Updates a `Task` in the SQLite database that has been selected
     /// in the TUI.
     pub fn update_selected_task(&mut self) -> Result<()> {
-        let current_selection = self.tasklist.state.selected().unwrap();
-        let current_uuid = self.tasklist.tasks[current_selection].get_id();
+        let Some(current_selection) = self.tasklist.state.selected() else {
+            return Ok(());
+        };
+        let was_completed = self.tasklist.tasks[current_selection].status == Status::Completed;
+        let before = self.tasklist.tasks[current_selection].clone();
 
         let description = if self.inputs.description.is_empty() {
             None
@@ -566,26 +768,74 @@ impl App {
 
         self.tasklist.tasks[current_selection].name = self.inputs.name.clone();
         self.tasklist.tasks[current_selection].urgency = self.inputs.urgency;
-        self.tasklist.tasks[current_selection].status = self.inputs.status;
+        let edited_completed_on = self
+            .inputs
+            .parse_completed_on(&self.tasklist.tasks[current_selection])
+            .unwrap_or(None);
+        let mut status = self.inputs.status;
+        if status == Status::Completed && !was_completed {
+            if let Err(e) = before.check_criteria() {
+                // The rest of the changes still go in, just not completing it
+                self.show_toast(format!("{}, C ticks them off", e));
+                status = before.status;
+            }
+        }
+        self.tasklist.tasks[current_selection].status = status;
         if self.tasklist.tasks[current_selection].status == Status::Completed {
-            self.tasklist.tasks[current_selection].completed_on = Some(Local::now());
+            // Keep when it was completed, unless it is newly completed or was edited
+            if let Some(completed_on) = edited_completed_on {
+                self.tasklist.tasks[current_selection].completed_on = Some(completed_on);
+            } else if !was_completed {
+                self.tasklist.tasks[current_selection].completed_on = Some(Local::now());
+            }
+            self.tasklist.tasks[current_selection].stop_timer();
         } else {
             self.tasklist.tasks[current_selection].completed_on = None;
         }
         self.tasklist.tasks[current_selection].description = description;
         self.tasklist.tasks[current_selection].latest = latest;
         self.tasklist.tasks[current_selection].tags = tags;
+        self.tasklist.tasks[current_selection].recurrence =
+            self.inputs.parse_recurrence().unwrap_or(None);
+        self.tasklist.tasks[current_selection].project = self.inputs.parse_project();
+        self.tasklist.tasks[current_selection].due = self.inputs.parse_due().unwrap_or(None);
+        self.tasklist.tasks[current_selection].scheduled =
+            self.inputs.parse_scheduled().unwrap_or(None);
+        if self
+            .inputs
+            .time_slot_changed(&self.tasklist.tasks[current_selection])
+        {
+            let time_slot = self.inputs.parse_time_slot().unwrap_or(None);
+            self.tasklist.tasks[current_selection].scheduled_start =
+                time_slot.map(|(start, _)| start);
+            self.tasklist.tasks[current_selection].duration = time_slot.map(|(_, minutes)| minutes);
+        }
 
-        update_task_in_db(&self.conn, &self.tasklist.tasks[current_selection])
+        self.store
+            .update(&self.tasklist.tasks[current_selection])
             .context("Failed to update task in the database")?;
-        self.update_tasklist()
-            .context("Failed to update the tasklist after adding the new task in")?;
-
-        for (i, task) in self.tasklist.tasks.iter().enumerate() {
-            if current_uuid == task.get_id() {
-                self.tasklist.state.select(Some(i))
+        let mut operation = vec![Change::Updated {
+            before: Box::new(before),
+            after: self.tasklist.tasks[current_selection].clone(),
+        }];
+
+        // Completing a recurring task lines up its next occurrence
+        if !was_completed && self.tasklist.tasks[current_selection].status == Status::Completed {
+            let next_task = self
+                .add_next_occurrence(&self.tasklist.tasks[current_selection])
+                .context("Failed to add the next occurrence of the task")?;
+            if let Some(next_task) = next_task {
+                operation.push(Change::Added(next_task));
             }
         }
+        let task = &self.tasklist.tasks[current_selection];
+        if was_completed != (task.status == Status::Completed) {
+            let id = task.get_id();
+            operation.extend(self.roll_up_parents(id)?);
+        }
+        self.history.record(operation);
+        self.update_tasklist()
+            .context("Failed to update the tasklist after adding the new task in")?;
 
         Ok(())
     }
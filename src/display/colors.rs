@@ -0,0 +1,217 @@
+use std::env;
+
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// How many colors the terminal can show
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ColorSupport {
+    /// No colors at all, as asked for with `NO_COLOR`
+    NoColor,
+    /// The 16 named ANSI colors
+    Ansi16,
+    /// The 256 colors of xterm
+    Ansi256,
+    /// Any RGB color
+    #[default]
+    TrueColor,
+}
+
+/// The 16 named colors with the RGB values xterm shows them with, for
+/// finding the closest one to another color
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The levels each of red, green, and blue go through in the 6x6x6 cube of
+/// the 256 colors
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Returns how far apart two colors look, weighting green the most since
+/// eyes are the most sensitive to it
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1.abs_diff(r2) as u32;
+    let dg = g1.abs_diff(g2) as u32;
+    let db = b1.abs_diff(b2) as u32;
+    2 * dr * dr + 4 * dg * dg + 3 * db * db
+}
+
+/// Returns the RGB value of one of the 256 colors
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let cube = index - 16;
+            (
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            )
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Returns the closest of the 256 colors to an RGB one, from either the
+/// color cube or the gray ramp
+fn nearest_indexed(rgb: (u8, u8, u8)) -> u8 {
+    let level = |value: u8| match value {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (value - 35) / 40,
+    };
+    let (r, g, b) = rgb;
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(indexed_rgb(gray), rgb) < distance(indexed_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Returns the closest of the 16 named colors to an RGB one
+fn nearest_named(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, named)| distance(*named, rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+impl ColorSupport {
+    /// Works out what the terminal can show from the environment: nothing if
+    /// `NO_COLOR` is set, any color if `COLORTERM` says so, 256 colors if
+    /// `TERM` does, and the 16 named ones otherwise
+    pub fn detect() -> Self {
+        Self::from_env(
+            env::var("NO_COLOR").ok().as_deref(),
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        // Only an empty NO_COLOR is ignored, see https://no-color.org
+        if no_color.is_some_and(|no_color| !no_color.is_empty()) {
+            return ColorSupport::NoColor;
+        }
+        if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+            return ColorSupport::TrueColor;
+        }
+        match term {
+            Some("dumb") => ColorSupport::NoColor,
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            Some(term) if term.contains("direct") => ColorSupport::TrueColor,
+            Some(_) => ColorSupport::Ansi16,
+            // The Windows terminals that don't set TERM show any color
+            None if cfg!(windows) => ColorSupport::TrueColor,
+            None => ColorSupport::Ansi16,
+        }
+    }
+
+    /// Returns the closest color to the one given that the terminal can show
+    pub fn degrade(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, color) | (_, color @ Color::Reset) => color,
+            (ColorSupport::NoColor, _) => Color::Reset,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => {
+                Color::Indexed(nearest_indexed((r, g, b)))
+            }
+            (ColorSupport::Ansi256, color) => color,
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_named((r, g, b)),
+            (ColorSupport::Ansi16, Color::Indexed(index)) => nearest_named(indexed_rgb(index)),
+            (ColorSupport::Ansi16, color) => color,
+        }
+    }
+}
+
+/// Swaps every color drawn for the closest one the terminal can show, so
+/// hex colors in the theme don't come out as something else entirely
+pub fn degrade_buffer(buffer: &mut Buffer, support: ColorSupport) {
+    if support == ColorSupport::TrueColor {
+        return;
+    }
+    for cell in buffer.content.iter_mut() {
+        let (fg, bg) = (support.degrade(cell.fg), support.degrade(cell.bg));
+        cell.set_fg(fg).set_bg(bg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::palette::tailwind::{EMERALD, SLATE};
+
+    use super::*;
+
+    #[test]
+    fn test_from_env() {
+        let detect = ColorSupport::from_env;
+        assert_eq!(
+            detect(Some("1"), Some("truecolor"), None),
+            ColorSupport::NoColor
+        );
+        assert_eq!(
+            detect(Some(""), Some("truecolor"), None),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(detect(None, None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, Some("dumb")), ColorSupport::NoColor);
+    }
+
+    #[test]
+    fn test_degrade() {
+        assert_eq!(ColorSupport::TrueColor.degrade(SLATE.c950), SLATE.c950);
+        assert_eq!(ColorSupport::NoColor.degrade(Color::Red), Color::Reset);
+        assert_eq!(ColorSupport::Ansi16.degrade(Color::Cyan), Color::Cyan);
+
+        // The dark tailwind backgrounds come out black, instead of whatever
+        // a basic terminal makes of them
+        assert_eq!(ColorSupport::Ansi16.degrade(SLATE.c950), Color::Black);
+        assert_eq!(ColorSupport::Ansi16.degrade(EMERALD.c950), Color::Black);
+        assert_eq!(
+            ColorSupport::Ansi16.degrade(Color::Rgb(250, 10, 10)),
+            Color::LightRed
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.degrade(Color::Indexed(196)),
+            Color::LightRed
+        );
+
+        assert_eq!(
+            ColorSupport::Ansi256.degrade(Color::Rgb(255, 0, 0)),
+            Color::Indexed(196)
+        );
+        assert_eq!(
+            ColorSupport::Ansi256.degrade(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244)
+        );
+        assert_eq!(ColorSupport::Ansi256.degrade(Color::Blue), Color::Blue);
+    }
+}
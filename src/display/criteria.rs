@@ -0,0 +1,105 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::backend::history::Change;
+use crate::backend::task::{Criterion, Task};
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::text::pop_grapheme;
+use crate::display::tui::App;
+
+/// Registers opening the selected task's acceptance criteria
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Tick off what the selected task needs before it's done",
+        key: KeyBinding::key('C'),
+        writes: true,
+        run: App::open_criteria,
+    })
+}
+
+impl App {
+    /// Opens the pop-up listing the selected `Task`'s acceptance criteria
+    pub fn open_criteria(&mut self) -> Result<()> {
+        if self.tasklist.state.selected().is_none() || self.selected_is_locked() {
+            return Ok(());
+        }
+        self.criteria_popup = true;
+        self.criteria_selected = 0;
+        // Start off adding one if there aren't any yet
+        self.criteria_adding = self.selected_criteria().is_empty();
+        self.criteria_value = String::new();
+        Ok(())
+    }
+
+    /// Returns the acceptance criteria of the selected `Task`
+    pub fn selected_criteria(&self) -> &[Criterion] {
+        self.tasklist
+            .state
+            .selected()
+            .map(|i| self.tasklist.tasks[i].criteria.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Handles keys while the acceptance criteria are open
+    pub fn handle_keys_for_criteria(&mut self, key: KeyEvent) -> Result<()> {
+        if self.criteria_adding {
+            match key.code {
+                KeyCode::Esc => self.criteria_adding = false,
+                KeyCode::Enter => {
+                    let criterion = Criterion::new(&self.criteria_value);
+                    self.criteria_adding = false;
+                    self.criteria_value = String::new();
+                    if !criterion.text.is_empty() {
+                        self.change_criteria(|task| task.criteria.push(criterion))?;
+                        self.criteria_selected = self.selected_criteria().len().saturating_sub(1);
+                    }
+                }
+                KeyCode::Backspace => pop_grapheme(&mut self.criteria_value),
+                KeyCode::Char(ch) => self.criteria_value.push(ch),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let selected = self.criteria_selected;
+        let has_selected = selected < self.selected_criteria().len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => self.criteria_popup = false,
+            KeyCode::Char('j') | KeyCode::Down if selected + 1 < self.selected_criteria().len() => {
+                self.criteria_selected += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.criteria_selected = selected.saturating_sub(1),
+            KeyCode::Char('a') => self.criteria_adding = true,
+            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Char('x') if has_selected => {
+                self.change_criteria(|task| {
+                    task.criteria[selected].done = !task.criteria[selected].done
+                })?;
+            }
+            KeyCode::Char('d') if has_selected => {
+                self.change_criteria(|task| {
+                    task.criteria.remove(selected);
+                })?;
+                self.criteria_selected =
+                    selected.min(self.selected_criteria().len().saturating_sub(1));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Changes the acceptance criteria of the selected `Task` and saves it,
+    /// so the change can be undone
+    fn change_criteria(&mut self, change: impl FnOnce(&mut Task)) -> Result<()> {
+        let Some(current_selection) = self.tasklist.state.selected() else {
+            return Ok(());
+        };
+        let before = self.tasklist.tasks[current_selection].clone();
+        change(&mut self.tasklist.tasks[current_selection]);
+        self.store.update(&self.tasklist.tasks[current_selection])?;
+        self.history.record(vec![Change::Updated {
+            before: Box::new(before),
+            after: self.tasklist.tasks[current_selection].clone(),
+        }]);
+        self.update_tasklist()
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::backend::calendar::set_calendar;
+use crate::backend::collate::set_collation;
+use crate::backend::config::{known_profiles, profile, read_config_file, set_profile};
+use crate::backend::encryption::{passphrase, remember_passphrase, saved_passphrase};
+use crate::backend::store::open_store;
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::theme::load_theme;
+use crate::display::tui::App;
+
+/// Registers opening the database picker
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Switch to another profile's database",
+        key: KeyBinding::key('D'),
+        writes: false,
+        run: App::open_db_picker,
+    })
+}
+
+impl App {
+    /// Opens the pop-up listing every profile that's been set up
+    pub fn open_db_picker(&mut self) -> Result<()> {
+        if self.in_memory() {
+            self.show_toast("Tasks in memory would be lost, so promote them with M first");
+            return Ok(());
+        }
+        self.db_picker_profiles = known_profiles()?;
+        let current = profile();
+        self.db_picker_selected = self
+            .db_picker_profiles
+            .iter()
+            .position(|profile| profile.name == current)
+            .unwrap_or(0);
+        self.db_picker = true;
+        Ok(())
+    }
+
+    /// Handles keys while the database picker is open
+    pub fn handle_keys_for_db_picker(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('D') => self.db_picker = false,
+            KeyCode::Char('j') | KeyCode::Down
+                if self.db_picker_selected + 1 < self.db_picker_profiles.len() =>
+            {
+                self.db_picker_selected += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.db_picker_selected = self.db_picker_selected.saturating_sub(1)
+            }
+            KeyCode::Enter => self.switch_database()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Closes the database in use and opens the one of the highlighted
+    /// profile in its place, along with its timezone and theme, saying in
+    /// the status bar if it can't be opened
+    fn switch_database(&mut self) -> Result<()> {
+        let Some(picked) = self.db_picker_profiles.get(self.db_picker_selected) else {
+            return Ok(());
+        };
+        let (name, label) = (picked.name.clone(), picked.label().to_string());
+        self.db_picker = false;
+
+        let config = match read_config_file(&picked.config_file) {
+            Ok(config) => config,
+            Err(e) => {
+                self.show_toast(format!("Can't read the config for '{}': {}", label, e));
+                return Ok(());
+            }
+        };
+        // There's no asking for a passphrase on the terminal with the TUI on it
+        let unlock_with = if config.encrypted {
+            match saved_passphrase(&config) {
+                Ok(Some(passphrase)) => Some(passphrase),
+                Ok(None) => {
+                    self.show_toast(format!(
+                        "'{}' is encrypted, so set CHECKLIST_PASSPHRASE or \"passphrase_command\" to open it from here",
+                        label
+                    ));
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.show_toast(format!("{}", e));
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
+
+        let previous = passphrase();
+        let store = match open_store(&config, unlock_with.as_deref()) {
+            Ok(store) => store,
+            Err(e) => {
+                remember_passphrase(previous.as_deref());
+                self.show_toast(format!("Can't open '{}': {:#}", label, e));
+                return Ok(());
+            }
+        };
+        // Anything changed on the profile being left goes to its own config file
+        self.save_config(true)?;
+        set_profile(name.as_deref())?;
+        let mut problems = vec![];
+        match config.collation_locale() {
+            Ok(locale) => set_collation(config.collation, locale),
+            Err(e) => {
+                set_collation(config.collation, None);
+                problems.push(format!("{}", e));
+            }
+        }
+        match config.calendar() {
+            Ok(calendar) => set_calendar(calendar),
+            Err(e) => problems.push(format!("{}", e)),
+        }
+        let theme = config
+            .theme
+            .map_or_else(load_theme, |preset| preset.theme());
+        match theme {
+            Ok(theme) => self.theme = theme,
+            Err(e) => problems.push(format!("kept the theme, {:#}", e)),
+        }
+        self.use_database(store, config);
+        self.update_tasklist()?;
+        if problems.is_empty() {
+            self.show_toast(format!("Switched to '{}'", label));
+        } else {
+            self.show_toast(format!(
+                "Switched to '{}', but {}",
+                label,
+                problems.join(", and ")
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use uuid::Uuid;
+
+use crate::backend::database::TaskSelect;
+use crate::backend::dependencies::Dependencies;
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::tui::App;
+
+/// Registers opening the dependency graph
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Show what the selected task waits on, and what waits on it",
+        key: KeyBinding::key('B'),
+        writes: false,
+        run: App::open_dependency_graph,
+    })
+}
+
+impl App {
+    /// Opens the pop-up with the dependency graph of the selected `Task`
+    pub fn open_dependency_graph(&mut self) -> Result<()> {
+        let Some(id) = self.tasklist.selected_id() else {
+            return Ok(());
+        };
+        self.center_dependency_graph(id)?;
+        self.dependency_graph = true;
+        Ok(())
+    }
+
+    /// Draws the graph around the `Task` with the id, highlighting it
+    fn center_dependency_graph(&mut self, id: Uuid) -> Result<()> {
+        let dependencies = Dependencies::new(&self.store.dependencies()?);
+        let chain: Vec<Uuid> = dependencies.chain(id).into_iter().collect();
+        let tasks = self.store.select(&TaskSelect::new().ids(&chain))?.tasks;
+        self.graph_lines = dependencies.graph(&tasks, id);
+        self.graph_selected = self
+            .graph_lines
+            .iter()
+            .position(|line| line.id == Some(id))
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    /// Handles keys while the dependency graph is open. Up and down only
+    /// stop on lines with a `Task`, and Enter moves the graph (and the
+    /// selection in the list) onto the highlighted one
+    pub fn handle_keys_for_dependency_graph(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('B') => self.dependency_graph = false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                if let Some(next) = self
+                    .graph_lines
+                    .iter()
+                    .enumerate()
+                    .skip(self.graph_selected + 1)
+                    .find(|(_, line)| line.id.is_some())
+                {
+                    self.graph_selected = next.0;
+                }
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                if let Some(previous) = self
+                    .graph_lines
+                    .iter()
+                    .enumerate()
+                    .take(self.graph_selected)
+                    .rev()
+                    .find(|(_, line)| line.id.is_some())
+                {
+                    self.graph_selected = previous.0;
+                }
+            }
+            KeyCode::Enter => {
+                let Some(id) = self
+                    .graph_lines
+                    .get(self.graph_selected)
+                    .and_then(|line| line.id)
+                else {
+                    return Ok(());
+                };
+                self.center_dependency_graph(id)?;
+                if !self.select_task(id)? {
+                    self.show_toast("That task is hidden by the current filters");
+                } else if let Some(position) = self.selected_index() {
+                    self.scroll_info.list_scroll = position;
+                    self.scroll_info.list_scroll_state =
+                        self.scroll_info.list_scroll_state.position(position);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::backend::task::Task;
+use crate::display::keymap::KeyContext;
+use crate::display::picker::rank_tasks;
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::text::pop_grapheme;
+use crate::display::tui::App;
+
+/// Registers opening the fuzzy finder
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Fuzzy find a task",
+        key: KeyBinding::ctrl('p'),
+        writes: false,
+        run: |app| app.open_finder(),
+    })
+}
+
+impl App {
+    /// Opens the fuzzy finder over the listed `Task`s. Only part of the list
+    /// may be read in, so it reads in the whole of it while it's open.
+    pub fn open_finder(&mut self) -> Result<()> {
+        self.finder_tasks = self.list_tasks()?;
+        self.finder_popup = true;
+        self.finder_value = String::new();
+        self.finder_selected = 0;
+        Ok(())
+    }
+
+    /// Closes the fuzzy finder, letting go of the `Task`s it was over
+    fn close_finder(&mut self) {
+        self.finder_popup = false;
+        self.finder_tasks = vec![];
+    }
+
+    /// Returns the listed `Task`s that fuzzy match what has been typed
+    /// into the finder, best matches first
+    pub fn finder_candidates(&self) -> Vec<&Task> {
+        rank_tasks(&self.finder_tasks, &self.finder_value)
+    }
+
+    /// Handles keys while the fuzzy finder is open. `Enter` jumps the
+    /// selection to the highlighted `Task`.
+    pub fn handle_keys_for_finder(&mut self, key: KeyEvent) -> Result<()> {
+        let key = self.config.keymap.translate(key, KeyContext::Line);
+        let candidates_len = self.finder_candidates().len();
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => self.close_finder(),
+            (KeyCode::Enter, _) => {
+                let chosen = self
+                    .finder_candidates()
+                    .get(self.finder_selected)
+                    .map(|task| task.get_id());
+                self.close_finder();
+                if let Some(id) = chosen {
+                    self.select_task(id)?;
+                    if let Some(position) = self.selected_index() {
+                        self.scroll_info.list_scroll = position;
+                        self.scroll_info.list_scroll_state =
+                            self.scroll_info.list_scroll_state.position(position);
+                    }
+                }
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                self.finder_selected = self.finder_selected.saturating_sub(1);
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL)
+                if self.finder_selected + 1 < candidates_len =>
+            {
+                self.finder_selected += 1;
+            }
+            (KeyCode::Backspace, _) => {
+                pop_grapheme(&mut self.finder_value);
+                self.finder_selected = 0;
+            }
+            (KeyCode::Char(ch), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.finder_value.push(ch);
+                self.finder_selected = 0;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
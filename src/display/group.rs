@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::backend::collate::collate;
+use crate::backend::task::{Task, TaskList};
+use crate::display::tui::App;
+
+/// What the `Task`s in the TUI are grouped on
+#[derive(Clone, Copy, Debug, Default, PartialEq, strum_macros::Display)]
+pub enum GroupBy {
+    #[default]
+    Off,
+    Status,
+    Tag,
+    Project,
+}
+
+impl GroupBy {
+    /// Will rotate through the different enum variants
+    pub fn next(&mut self) {
+        match self {
+            GroupBy::Off => *self = GroupBy::Status,
+            GroupBy::Status => *self = GroupBy::Tag,
+            GroupBy::Tag => *self = GroupBy::Project,
+            GroupBy::Project => *self = GroupBy::Off,
+        }
+    }
+}
+
+/// A row of the `Task` list when it is grouped
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupRow {
+    /// A section header, along with how many `Task`s are in it
+    Header {
+        name: String,
+        count: usize,
+        collapsed: bool,
+    },
+    /// The index of a `Task` in the `TaskList`
+    Task(usize),
+}
+
+/// Returns the group a `Task` falls under, along with where the group goes
+/// in the list. `Task`s with several tags go under the first one alphabetically.
+fn group_of(task: &Task, group_by: GroupBy) -> (u8, String) {
+    match group_by {
+        GroupBy::Off => (0, String::new()),
+        GroupBy::Status => (task.status as u8, task.status.to_string()),
+        GroupBy::Tag => match task
+            .tags
+            .as_ref()
+            .and_then(|tags| tags.iter().min_by(|a, b| collate(a, b)))
+        {
+            Some(tag) => (0, tag.clone()),
+            None => (1, String::from("No tag")),
+        },
+        GroupBy::Project => match &task.project {
+            Some(project) => (0, project.clone()),
+            None => (1, String::from("No project")),
+        },
+    }
+}
+
+/// Puts the `Task`s of each group next to each other, keeping their order within
+/// the group, and returns the rows to show for them. `Task`s in `collapsed` groups
+/// are taken out of the `TaskList`, leaving only their header.
+pub fn group_rows(
+    tasklist: &mut TaskList,
+    group_by: GroupBy,
+    collapsed: &HashSet<String>,
+) -> Vec<GroupRow> {
+    let mut grouped: Vec<((u8, String), Task)> = tasklist
+        .tasks
+        .drain(..)
+        .map(|task| (group_of(&task, group_by), task))
+        .collect();
+    grouped.sort_by(|((rank, name), _), ((other_rank, other_name), _)| {
+        rank.cmp(other_rank).then_with(|| collate(name, other_name))
+    });
+
+    let mut groups: Vec<(String, Vec<Task>)> = vec![];
+    for ((_, name), task) in grouped {
+        match groups.last_mut() {
+            Some((last, tasks)) if *last == name => tasks.push(task),
+            _ => groups.push((name, vec![task])),
+        }
+    }
+
+    let mut rows = vec![];
+    for (name, tasks) in groups {
+        let is_collapsed = collapsed.contains(&name);
+        rows.push(GroupRow::Header {
+            name,
+            count: tasks.len(),
+            collapsed: is_collapsed,
+        });
+        if is_collapsed {
+            continue;
+        }
+        for task in tasks {
+            rows.push(GroupRow::Task(tasklist.tasks.len()));
+            tasklist.tasks.push(task);
+        }
+    }
+    rows
+}
+
+impl App {
+    /// Whether the `Task` list is split into groups
+    pub fn is_grouped(&self) -> bool {
+        self.group_by != GroupBy::Off
+    }
+
+    /// Moves on to grouping by the next field, expanding every group
+    pub fn next_group_by(&mut self) -> Result<()> {
+        self.group_by.next();
+        self.collapsed_groups.clear();
+        self.tasklist.state.select(None);
+        self.group_state.select(None);
+        self.update_tasklist()
+    }
+
+    /// How many `Task`s are tucked away in collapsed groups
+    pub fn collapsed_count(&self) -> usize {
+        self.group_rows
+            .iter()
+            .map(|row| match row {
+                GroupRow::Header {
+                    count,
+                    collapsed: true,
+                    ..
+                } => *count,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Selects a row of the grouped list, along with its `Task` if it is one
+    pub fn select_row(&mut self, row: Option<usize>) {
+        let row = row.map(|row| row.min(self.group_rows.len().saturating_sub(1)));
+        self.group_state.select(row);
+        let task = row.and_then(|row| match self.group_rows.get(row) {
+            Some(GroupRow::Task(index)) => Some(*index),
+            _ => None,
+        });
+        self.tasklist.state.select(task);
+    }
+
+    /// Points the grouped list at the selected `Task`, for when it was selected
+    /// some other way. A selected header stays selected.
+    pub fn sync_group_selection(&mut self) {
+        match self.tasklist.state.selected() {
+            Some(index) => {
+                let row = self
+                    .group_rows
+                    .iter()
+                    .position(|row| *row == GroupRow::Task(index));
+                self.group_state.select(row);
+            }
+            None => {
+                let on_header = self.group_state.selected().is_some_and(|row| {
+                    matches!(self.group_rows.get(row), Some(GroupRow::Header { .. }))
+                });
+                if !on_header {
+                    self.group_state.select(None);
+                }
+            }
+        }
+    }
+
+    /// Collapses the group of the selected header or `Task`, or expands it if
+    /// already collapsed. The header is selected afterwards.
+    pub fn toggle_group(&mut self) -> Result<()> {
+        let Some(row) = self.group_state.selected() else {
+            return Ok(());
+        };
+        let name = match self.group_rows.get(row) {
+            Some(GroupRow::Header { name, .. }) => name.clone(),
+            Some(GroupRow::Task(index)) => group_of(&self.tasklist.tasks[*index], self.group_by).1,
+            None => return Ok(()),
+        };
+        if !self.collapsed_groups.remove(&name) {
+            self.collapsed_groups.insert(name.clone());
+        }
+
+        self.tasklist.state.select(None);
+        self.update_tasklist()?;
+        let header = self.group_rows.iter().position(|row| match row {
+            GroupRow::Header { name: header, .. } => *header == name,
+            _ => false,
+        });
+        self.select_row(header);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::task::Status;
+
+    #[test]
+    fn test_group_rows() {
+        let mut house = Task::new(String::from("Paint fence"), None, None, None, None, None);
+        house.project = Some(String::from("House"));
+        let mut garden = Task::new(
+            String::from("Plant beans"),
+            None,
+            None,
+            None,
+            Some(Status::Working),
+            None,
+        );
+        garden.project = Some(String::from("garden"));
+        let loose = Task::new(String::from("Call mum"), None, None, None, None, None);
+        let mut shed = house.clone();
+        shed.name = String::from("Fix shed");
+
+        let tasks = vec![loose.clone(), house.clone(), garden.clone(), shed.clone()];
+        let mut tasklist = TaskList::from(tasks.clone());
+        let rows = group_rows(&mut tasklist, GroupBy::Project, &HashSet::new());
+        let header = |name: &str, count, collapsed| GroupRow::Header {
+            name: String::from(name),
+            count,
+            collapsed,
+        };
+        assert_eq!(
+            rows,
+            vec![
+                header("garden", 1, false),
+                GroupRow::Task(0),
+                header("House", 2, false),
+                GroupRow::Task(1),
+                GroupRow::Task(2),
+                header("No project", 1, false),
+                GroupRow::Task(3),
+            ]
+        );
+        assert_eq!(tasklist.tasks, vec![garden, house, shed, loose.clone()]);
+
+        let mut tasklist = TaskList::from(tasks);
+        let collapsed = HashSet::from([String::from("Open")]);
+        let rows = group_rows(&mut tasklist, GroupBy::Status, &collapsed);
+        assert_eq!(
+            rows,
+            vec![
+                header("Open", 3, true),
+                header("Working", 1, false),
+                GroupRow::Task(0)
+            ]
+        );
+        assert_eq!(tasklist.len(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Something about a `Task` the `Task Info` block can show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InfoField {
+    Title,
+    Created,
+    /// The status, with when it was completed once it's done
+    Status,
+    Urgency,
+    Tags,
+    /// What's rolled up from the subtasks
+    RolledUp,
+    Locked,
+    Project,
+    Due,
+    /// When a snoozed task comes back
+    Snoozed,
+    TimeSlot,
+    TimeSpent,
+    /// Where an imported task came from
+    From,
+    Tickets,
+    Recurs,
+    /// The acceptance criteria, to tick off
+    Criteria,
+    Latest,
+    Description,
+}
+
+/// The name each `InfoField` goes by, in the order the `Task Info` block
+/// shows them unless the theme says otherwise
+const INFO_FIELDS: [(&str, InfoField); 18] = [
+    ("title", InfoField::Title),
+    ("created", InfoField::Created),
+    ("status", InfoField::Status),
+    ("urgency", InfoField::Urgency),
+    ("tags", InfoField::Tags),
+    ("rolled_up", InfoField::RolledUp),
+    ("locked", InfoField::Locked),
+    ("project", InfoField::Project),
+    ("due", InfoField::Due),
+    ("snoozed", InfoField::Snoozed),
+    ("time_slot", InfoField::TimeSlot),
+    ("time_spent", InfoField::TimeSpent),
+    ("from", InfoField::From),
+    ("tickets", InfoField::Tickets),
+    ("recurs", InfoField::Recurs),
+    ("criteria", InfoField::Criteria),
+    ("latest", InfoField::Latest),
+    ("description", InfoField::Description),
+];
+
+/// Which fields the `Task Info` block shows and in what order, written as
+/// their names split by spaces or commas, i.e. "title due status tags latest".
+/// Fields left out aren't shown. It's kept as written, to save it back the
+/// same way.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct InfoLayout {
+    text: String,
+    fields: Vec<InfoField>,
+}
+
+/// Returns the fields named in the layout, in order
+fn parse_fields(layout: &str) -> Result<Vec<InfoField>> {
+    let mut fields = vec![];
+    for name in layout
+        .split(|ch: char| ch.is_whitespace() || ch == ',')
+        .filter(|name| !name.is_empty())
+    {
+        let Some((_, field)) = INFO_FIELDS.iter().find(|(known, _)| *known == name) else {
+            let names: Vec<&str> = INFO_FIELDS.iter().map(|(name, _)| *name).collect();
+            bail!(
+                "'{}' isn't something the Task Info block can show, use any of {}",
+                name,
+                names.join(", ")
+            );
+        };
+        if fields.contains(field) {
+            bail!("'{}' is in the Task Info layout more than once", name);
+        }
+        fields.push(*field);
+    }
+    Ok(fields)
+}
+
+impl InfoLayout {
+    /// Returns the fields to show, in order
+    pub fn fields(&self) -> &[InfoField] {
+        &self.fields
+    }
+}
+
+impl Default for InfoLayout {
+    fn default() -> Self {
+        let names: Vec<&str> = INFO_FIELDS.iter().map(|(name, _)| *name).collect();
+        Self {
+            text: names.join(" "),
+            fields: INFO_FIELDS.iter().map(|(_, field)| *field).collect(),
+        }
+    }
+}
+
+impl TryFrom<String> for InfoLayout {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match parse_fields(&text) {
+            Ok(fields) => Ok(Self { text, fields }),
+            Err(e) => Err(format!("{:#}", e)),
+        }
+    }
+}
+
+impl From<InfoLayout> for String {
+    fn from(layout: InfoLayout) -> Self {
+        layout.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(
+            parse_fields("due, title  status").unwrap(),
+            vec![InfoField::Due, InfoField::Title, InfoField::Status]
+        );
+        assert!(parse_fields("").unwrap().is_empty());
+        assert!(parse_fields("title notes").is_err());
+        assert!(parse_fields("title due title").is_err());
+        assert_eq!(InfoLayout::default().fields().len(), INFO_FIELDS.len());
+    }
+}
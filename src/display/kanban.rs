@@ -0,0 +1,99 @@
+use anyhow::Result;
+
+use crate::backend::task::Status;
+use crate::display::tui::App;
+
+/// The columns of the Kanban board, from left to right
+pub const KANBAN_COLUMNS: [Status; 4] = [
+    Status::Open,
+    Status::Working,
+    Status::Paused,
+    Status::Completed,
+];
+
+impl App {
+    /// Returns the indexes of the listed `Task`s in each column of the Kanban board
+    pub fn kanban_columns(&self) -> Vec<Vec<usize>> {
+        KANBAN_COLUMNS
+            .iter()
+            .map(|status| {
+                self.tasklist
+                    .tasks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, task)| task.status == *status)
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns which column the selected `Task` is in
+    fn kanban_selected_column(&self) -> Option<usize> {
+        let selected = self.tasklist.state.selected()?;
+        let status = self.tasklist.tasks.get(selected)?.status;
+        KANBAN_COLUMNS.iter().position(|column| *column == status)
+    }
+
+    /// Moves the selection up or down its column. With nothing selected,
+    /// selects the first `Task` on the board.
+    pub fn kanban_select_in_column(&mut self, down: bool) {
+        let columns = self.kanban_columns();
+        let Some(column) = self.kanban_selected_column() else {
+            let first = columns.iter().find_map(|column| column.first().copied());
+            self.tasklist.state.select(first);
+            return;
+        };
+
+        let tasks = &columns[column];
+        let selected = self.tasklist.state.selected();
+        let Some(position) = tasks.iter().position(|i| Some(*i) == selected) else {
+            return;
+        };
+        let position = if down {
+            (position + 1).min(tasks.len() - 1)
+        } else {
+            position.saturating_sub(1)
+        };
+        self.tasklist.state.select(Some(tasks[position]));
+    }
+
+    /// Moves the selection to the top of the next column over that has any `Task`s
+    pub fn kanban_select_column(&mut self, right: bool) {
+        let columns = self.kanban_columns();
+        let next = match (self.kanban_selected_column(), right) {
+            (Some(column), true) => columns
+                .iter()
+                .skip(column + 1)
+                .find_map(|tasks| tasks.first().copied()),
+            (Some(column), false) => columns
+                .iter()
+                .take(column)
+                .rev()
+                .find_map(|tasks| tasks.first().copied()),
+            (None, _) => columns.iter().find_map(|tasks| tasks.first().copied()),
+        };
+        if next.is_some() {
+            self.tasklist.state.select(next);
+        }
+    }
+
+    /// Moves the selected `Task` into the next column over, changing its `Status`
+    pub fn kanban_move_selected(&mut self, right: bool) -> Result<()> {
+        let Some(column) = self.kanban_selected_column() else {
+            return Ok(());
+        };
+        let next = if right {
+            column + 1
+        } else {
+            match column.checked_sub(1) {
+                Some(next) => next,
+                None => return Ok(()),
+            }
+        };
+        match KANBAN_COLUMNS.get(next) {
+            Some(status) => self.set_selected_status(*status),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// Keys layered over the usual ones, for those used to another editor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Keymap {
+    /// Only the usual keys
+    #[default]
+    Default,
+    /// `gg` and `G` jump to the first and last task, `dd` deletes the
+    /// selected one, and `/` searches, moving the tag filter to `#`
+    Vim,
+    /// `CTRL n` and `CTRL p` move up and down, and text inputs take
+    /// `CTRL a`, `CTRL e`, `CTRL f`, `CTRL b`, `CTRL d`, and `CTRL k`
+    Emacs,
+}
+
+/// Where a key was pressed, since the same key does different things in each
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyContext {
+    /// The task list, with no popup open
+    List,
+    /// A single line being typed at the bottom, like the search or tag filter
+    Line,
+    /// A text input in the add or update popup
+    Form,
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+impl Keymap {
+    /// Returns the usual key for what the key pressed does in this keymap,
+    /// or the key pressed if it does the same thing either way. Sequences
+    /// like `gg` and `dd` are left to the task list to pick out.
+    pub fn translate(&self, pressed: KeyEvent, context: KeyContext) -> KeyEvent {
+        let none = KeyModifiers::NONE;
+        let ctrl = pressed.modifiers == KeyModifiers::CONTROL;
+        let alt = pressed.modifiers.contains(KeyModifiers::ALT);
+        let translated = match (self, context, pressed.code) {
+            (Keymap::Vim, KeyContext::List, KeyCode::Char('/')) if !ctrl => {
+                key(KeyCode::Char('f'), KeyModifiers::CONTROL)
+            }
+            (Keymap::Vim, KeyContext::List, KeyCode::Char('#')) if !ctrl => {
+                key(KeyCode::Char('/'), none)
+            }
+
+            (Keymap::Emacs, _, KeyCode::Char('n')) if ctrl => match context {
+                KeyContext::Form => key(KeyCode::Enter, none),
+                _ => key(KeyCode::Down, none),
+            },
+            (Keymap::Emacs, _, KeyCode::Char('p')) if ctrl => match context {
+                KeyContext::Form => key(KeyCode::Left, KeyModifiers::CONTROL),
+                _ => key(KeyCode::Up, none),
+            },
+            // CTRL p moves up, so the fuzzy finder is searched for instead
+            (Keymap::Emacs, KeyContext::List, KeyCode::Char('s')) if ctrl => {
+                key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            }
+            (Keymap::Emacs, KeyContext::List, KeyCode::Char('<')) if alt => {
+                key(KeyCode::Home, none)
+            }
+            (Keymap::Emacs, KeyContext::List, KeyCode::Char('>')) if alt => key(KeyCode::End, none),
+            (Keymap::Emacs, KeyContext::Line | KeyContext::Form, KeyCode::Char('g')) if ctrl => {
+                key(KeyCode::Esc, none)
+            }
+            (Keymap::Emacs, KeyContext::Form, code) if ctrl => match code {
+                KeyCode::Char('a') => key(KeyCode::Home, none),
+                KeyCode::Char('e') => key(KeyCode::End, none),
+                KeyCode::Char('f') => key(KeyCode::Right, none),
+                KeyCode::Char('b') => key(KeyCode::Left, none),
+                KeyCode::Char('d') => key(KeyCode::Delete, none),
+                _ => pressed,
+            },
+            // CTRL e goes to the end, so the editor is opened with ALT e instead
+            (Keymap::Emacs, KeyContext::Form, KeyCode::Char('e')) if alt => {
+                key(KeyCode::Char('e'), KeyModifiers::CONTROL)
+            }
+            _ => pressed,
+        };
+        KeyEvent {
+            kind: pressed.kind,
+            ..translated
+        }
+    }
+
+    /// The keys this keymap adds, and what they do, for the help menu
+    pub fn help(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Keymap::Default => &[],
+            Keymap::Vim => &[
+                ("gg", "Go to first task"),
+                ("G", "Go to last task"),
+                ("dd", "Delete without asking"),
+                ("/ <TEXT>", "Search task text"),
+                ("# <TEXT>", "Filter task on Tag"),
+            ],
+            Keymap::Emacs => &[
+                ("CTRL n/CTRL p", "Next/previous task or field"),
+                ("ALT </ALT >", "Go to first/last task"),
+                ("CTRL s", "Fuzzy find a task"),
+                ("CTRL g", "Cancel typing"),
+                ("CTRL a/CTRL e", "Start/end of input"),
+                ("CTRL f/CTRL b", "Forward/back a character"),
+                ("CTRL d", "Delete the next character"),
+                ("CTRL k", "Delete to the end of input"),
+                ("ALT e", "Edit in $EDITOR"),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctrl(ch: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL)
+    }
+
+    fn plain(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_translate() {
+        let slash = plain(KeyCode::Char('/'));
+        assert_eq!(Keymap::Default.translate(slash, KeyContext::List), slash);
+        assert_eq!(Keymap::Vim.translate(slash, KeyContext::List), ctrl('f'));
+        // Typing a / into the search itself is left alone
+        assert_eq!(Keymap::Vim.translate(slash, KeyContext::Line), slash);
+
+        let emacs = Keymap::Emacs;
+        assert_eq!(
+            emacs.translate(ctrl('n'), KeyContext::List),
+            plain(KeyCode::Down)
+        );
+        assert_eq!(
+            emacs.translate(ctrl('n'), KeyContext::Form),
+            plain(KeyCode::Enter)
+        );
+        assert_eq!(emacs.translate(ctrl('s'), KeyContext::List), ctrl('p'));
+        assert_eq!(
+            emacs.translate(ctrl('e'), KeyContext::Form),
+            plain(KeyCode::End)
+        );
+        // Archiving isn't moving to the start of anything in the list
+        assert_eq!(emacs.translate(ctrl('a'), KeyContext::List), ctrl('a'));
+        // CTRL k is deleting to the end in any keymap
+        assert_eq!(emacs.translate(ctrl('k'), KeyContext::Form), ctrl('k'));
+    }
+}
@@ -1,7 +1,31 @@
 // pub mod list_example;
 pub mod add;
+pub mod colors;
+pub mod criteria;
+pub mod db_picker;
+pub mod dependency_graph;
+pub mod finder;
+pub mod group;
+pub mod info_layout;
+pub mod kanban;
+pub mod keymap;
+pub mod picker;
+pub mod prompt;
 pub mod quick_actions;
+pub mod registry;
+pub mod reminders;
 pub mod render;
+pub mod row_format;
+pub mod row_rules;
+pub mod subtasks;
+pub mod tag_manager;
+pub mod task_history;
+pub mod text;
 pub mod theme;
+pub mod theme_editor;
+pub mod theme_presets;
+pub mod toast;
 pub mod tui;
 pub mod ui;
+pub mod urgent_limit;
+pub mod window;
@@ -0,0 +1,117 @@
+use std::io::{stdout, Write};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{style, Print, PrintStyledContent, Stylize};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue};
+
+use crate::backend::fuzzy::fuzzy_score;
+use crate::backend::task::Task;
+use crate::backend::terminal::RawModeGuard;
+use crate::display::text::{pop_grapheme, width};
+
+/// How many candidates are shown below the prompt at once
+const MAX_CANDIDATES: usize = 10;
+
+/// Returns the `Task`s that fuzzy match the `query`, best matches first
+pub fn rank_tasks<'a>(tasks: &'a [Task], query: &str) -> Vec<&'a Task> {
+    let mut scored: Vec<(i64, &Task)> = tasks
+        .iter()
+        .filter_map(|task| fuzzy_score(query, &task.name).map(|score| (score, task)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, task)| task).collect()
+}
+
+/// Opens an inline fuzzy finder under the current line of the terminal,
+/// letting the user narrow down and pick one of the `Task`s given.
+/// Starts off filtered by `query`. Returns `None` if the user backs out.
+pub fn pick_task(tasks: &[Task], query: &str) -> Result<Option<Task>> {
+    let prompt = "Pick a task: ";
+    let mut query = query.to_string();
+    let mut selected = 0;
+    let mut stdout = stdout();
+
+    let _guard = RawModeGuard::enable()?;
+
+    loop {
+        let candidates = rank_tasks(tasks, &query);
+        selected = selected.min(candidates.len().saturating_sub(1));
+        let shown = candidates.len().min(MAX_CANDIDATES);
+
+        // Redraw the prompt and candidates in place
+        queue!(
+            stdout,
+            cursor::MoveToColumn(0),
+            terminal::Clear(ClearType::FromCursorDown),
+            Print(prompt),
+            Print(&query)
+        )?;
+        for (i, task) in candidates.iter().take(shown).enumerate() {
+            queue!(stdout, Print("\r\n"))?;
+            if i == selected {
+                queue!(
+                    stdout,
+                    PrintStyledContent(style(format!("> {}", task.summary())).reverse())
+                )?;
+            } else {
+                queue!(stdout, Print(format!("  {}", task.summary())))?;
+            }
+        }
+        if shown == 0 {
+            queue!(
+                stdout,
+                Print("\r\n"),
+                PrintStyledContent("  No matches".dark_grey())
+            )?;
+        }
+        let lines_below = shown.max(1) as u16;
+        queue!(
+            stdout,
+            cursor::MoveUp(lines_below),
+            cursor::MoveToColumn((width(prompt) + width(&query)) as u16)
+        )?;
+        stdout.flush()?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => break,
+                KeyCode::Enter => {
+                    if let Some(task) = candidates.get(selected) {
+                        let task = (*task).clone();
+                        queue!(
+                            stdout,
+                            cursor::MoveToColumn(0),
+                            terminal::Clear(ClearType::FromCursorDown)
+                        )?;
+                        stdout.flush()?;
+                        return Ok(Some(task));
+                    }
+                }
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down if selected + 1 < shown => selected += 1,
+                KeyCode::Backspace => {
+                    pop_grapheme(&mut query);
+                }
+                KeyCode::Char(ch) => {
+                    query.push(ch);
+                    selected = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    queue!(
+        stdout,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown)
+    )?;
+    stdout.flush()?;
+    Ok(None)
+}
@@ -0,0 +1,439 @@
+use std::collections::BTreeSet;
+use std::io::{stdin, stdout, IsTerminal, Write};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue};
+use rusqlite::Connection;
+
+use crate::backend::cli::{complete_task, list_tasks, ListFilters};
+use crate::backend::config::read_config;
+use crate::backend::database::{add_to_db, get_all_db_contents, get_db};
+use crate::backend::task::{CompleteParents, Display, Task, TaskList};
+use crate::backend::terminal::RawModeGuard;
+use crate::display::text::pop_grapheme;
+
+const PROMPT: &str = "checklist> ";
+const COMMANDS: [&str; 6] = ["add", "list", "done", "filter", "help", "quit"];
+const FILTER_KINDS: [&str; 4] = ["status", "tag", "project", "clear"];
+
+/// Commands that can be given to the `checklist prompt` REPL
+#[derive(Debug, PartialEq)]
+enum PromptCommand {
+    Add(String),
+    List,
+    Done(String),
+    FilterStatus(Display),
+    FilterTag(String),
+    FilterProject(String),
+    FilterClear,
+    Help,
+    Quit,
+}
+
+/// Parses a line of input into a `PromptCommand`.
+/// Returns `None` for a blank line.
+fn parse_command(line: &str) -> Result<Option<PromptCommand>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    let command = match command.to_lowercase().as_str() {
+        "add" | "a" => {
+            if rest.is_empty() {
+                bail!("Usage: add <name>");
+            }
+            PromptCommand::Add(rest.to_string())
+        }
+        "list" | "ls" | "l" => PromptCommand::List,
+        "done" | "d" => {
+            if rest.is_empty() {
+                bail!("Usage: done <name or id>");
+            }
+            PromptCommand::Done(rest.to_string())
+        }
+        "filter" | "f" => {
+            let (kind, value) = rest.split_once(' ').unwrap_or((rest, ""));
+            let value = value.trim();
+            match kind.to_lowercase().as_str() {
+                "status" => match Display::from_str(value, true) {
+                    Ok(display) => PromptCommand::FilterStatus(display),
+                    Err(_) => bail!("Usage: filter status <{}>", status_names().join("|")),
+                },
+                "tag" if !value.is_empty() => PromptCommand::FilterTag(value.to_string()),
+                "tag" => bail!("Usage: filter tag <tag>"),
+                "project" if !value.is_empty() => PromptCommand::FilterProject(value.to_string()),
+                "project" => bail!("Usage: filter project <project>"),
+                "clear" => PromptCommand::FilterClear,
+                _ => bail!("Usage: filter <status|tag|project|clear> [value]"),
+            }
+        }
+        "help" | "h" | "?" => PromptCommand::Help,
+        "quit" | "exit" | "q" => PromptCommand::Quit,
+        other => bail!("Unknown command '{}', try 'help'", other),
+    };
+
+    Ok(Some(command))
+}
+
+/// Returns the names `Display` can be parsed from
+fn status_names() -> Vec<String> {
+    Display::value_variants()
+        .iter()
+        .filter_map(|display| display.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+/// Works out what could finish off the current `line`.
+/// Returns the byte index the completion starts replacing from,
+/// along with the candidates.
+fn completions(line: &str, tasks: &[Task]) -> (usize, Vec<String>) {
+    let starts_with = |candidate: &str, fragment: &str| {
+        candidate
+            .to_lowercase()
+            .starts_with(&fragment.to_lowercase())
+    };
+
+    let Some((command, rest)) = line.split_once(' ') else {
+        let candidates = COMMANDS
+            .iter()
+            .filter(|command| starts_with(command, line))
+            .map(|command| command.to_string())
+            .collect();
+        return (0, candidates);
+    };
+    let rest_start = command.len() + 1;
+
+    match command {
+        "done" | "d" => {
+            let candidates = tasks
+                .iter()
+                .filter(|task| starts_with(&task.name, rest))
+                .map(|task| task.name.clone())
+                .collect::<BTreeSet<String>>();
+            (rest_start, candidates.into_iter().collect())
+        }
+        "filter" | "f" => {
+            let Some((kind, value)) = rest.split_once(' ') else {
+                let candidates = FILTER_KINDS
+                    .iter()
+                    .filter(|kind| starts_with(kind, rest))
+                    .map(|kind| kind.to_string())
+                    .collect();
+                return (rest_start, candidates);
+            };
+            let value_start = rest_start + kind.len() + 1;
+            let candidates = match kind {
+                "status" => status_names()
+                    .into_iter()
+                    .filter(|name| starts_with(name, value))
+                    .collect(),
+                "tag" => tasks
+                    .iter()
+                    .filter_map(|task| task.tags.as_ref())
+                    .flatten()
+                    .filter(|tag| starts_with(tag, value))
+                    .cloned()
+                    .collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect(),
+                "project" => TaskList::from(tasks.to_vec())
+                    .projects()
+                    .into_iter()
+                    .filter(|project| starts_with(project, value))
+                    .collect(),
+                _ => vec![],
+            };
+            (value_start, candidates)
+        }
+        _ => (rest_start, vec![]),
+    }
+}
+
+/// Returns the longest prefix all of the `candidates` share
+fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut prefix: String = first.clone();
+    for candidate in &candidates[1..] {
+        let shared = prefix
+            .chars()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        prefix = prefix.chars().take(shared).collect();
+    }
+    prefix
+}
+
+/// Reads a line from the terminal, completing commands, task names,
+/// statuses and tags on Tab. Returns `None` on Ctrl-d with an empty line.
+fn read_line_with_completion(tasks: &[Task]) -> Result<Option<String>> {
+    let mut stdout = stdout();
+    let mut line = String::new();
+
+    let _guard = RawModeGuard::enable()?;
+
+    loop {
+        queue!(
+            stdout,
+            cursor::MoveToColumn(0),
+            terminal::Clear(ClearType::CurrentLine),
+            Print(PROMPT),
+            Print(&line)
+        )?;
+        stdout.flush()?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => {
+                queue!(stdout, Print("\r\n"))?;
+                stdout.flush()?;
+                return Ok(Some(line));
+            }
+            KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => line.clear(),
+            KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL && line.is_empty() => {
+                queue!(stdout, Print("\r\n"))?;
+                stdout.flush()?;
+                return Ok(None);
+            }
+            KeyCode::Tab => {
+                let (start, candidates) = completions(&line, tasks);
+                match candidates.len() {
+                    0 => {}
+                    1 => {
+                        line.truncate(start);
+                        line.push_str(&candidates[0]);
+                        line.push(' ');
+                    }
+                    _ => {
+                        let prefix = common_prefix(&candidates);
+                        if prefix.chars().count() > line[start..].chars().count() {
+                            line.truncate(start);
+                            line.push_str(&prefix);
+                        } else {
+                            queue!(stdout, Print("\r\n"), Print(candidates.join("  ")))?;
+                            queue!(stdout, Print("\r\n"))?;
+                        }
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                pop_grapheme(&mut line);
+            }
+            KeyCode::Char(_) if key.modifiers == KeyModifiers::CONTROL => {}
+            KeyCode::Char(ch) => line.push(ch),
+            _ => {}
+        }
+    }
+}
+
+/// Prints out the commands the REPL understands
+fn print_help() {
+    println!("Commands:");
+    println!("  add <name>                  Adds a new task");
+    println!("  list                        Lists tasks matching the current filters");
+    println!("  done <name or id>           Marks a task as Completed");
+    println!(
+        "  filter status <{}>  Filters tasks by status",
+        status_names().join("|")
+    );
+    println!("  filter tag <tag>            Filters tasks by tag");
+    println!("  filter project <project>    Filters tasks by project");
+    println!("  filter clear                Clears the filters");
+    println!("  help                        Shows this message");
+    println!("  quit                        Leaves the prompt");
+    println!("Tab completes commands, task names, statuses, tags and projects.");
+}
+
+/// Struct that holds the state of the REPL between commands
+struct Prompt {
+    conn: Connection,
+    filters: ListFilters,
+    complete_parents: CompleteParents,
+}
+
+impl Prompt {
+    /// Runs a single `PromptCommand`. Returns false once the user wants to quit.
+    fn run_command(&mut self, command: PromptCommand) -> Result<bool> {
+        match command {
+            PromptCommand::Add(name) => {
+                let task = Task::new(name, None, None, None, None, None);
+                add_to_db(&self.conn, &task)?;
+                println!("Added: {}", task.name);
+            }
+            PromptCommand::List => {
+                list_tasks(&self.conn, &self.filters, false)?;
+            }
+            PromptCommand::Done(query) => {
+                complete_task(
+                    &self.conn,
+                    &query,
+                    self.filters.project.as_deref(),
+                    self.complete_parents,
+                )?;
+            }
+            PromptCommand::FilterStatus(display) => {
+                self.filters.display = display;
+                println!("Showing status: {}", display);
+            }
+            PromptCommand::FilterTag(tag) => {
+                println!("Showing tag: {}", tag);
+                self.filters.tags = tag;
+            }
+            PromptCommand::FilterProject(project) => {
+                println!("Showing project: {}", project);
+                self.filters.project = Some(project);
+            }
+            PromptCommand::FilterClear => {
+                self.filters = ListFilters::default();
+                println!("Filters cleared");
+            }
+            PromptCommand::Help => print_help(),
+            PromptCommand::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// Runs `checklist prompt`, a line based REPL for working with tasks
+/// without taking over the terminal like the TUI does.
+/// Reads plain lines when stdin isn't a terminal, so it can also be scripted.
+pub fn run_prompt(memory: bool, testing: bool) -> Result<()> {
+    let mut prompt = Prompt {
+        conn: get_db(memory, testing)?,
+        filters: ListFilters::default(),
+        complete_parents: read_config(testing)
+            .map_or(CompleteParents::default(), |config| config.complete_parents),
+    };
+    let interactive = stdin().is_terminal();
+    if interactive {
+        println!("Type 'help' to see the available commands");
+    }
+
+    loop {
+        let line = if interactive {
+            let tasks = get_all_db_contents(&prompt.conn)?.tasks;
+            match read_line_with_completion(&tasks)? {
+                Some(line) => line,
+                None => break,
+            }
+        } else {
+            let mut line = String::new();
+            if stdin().read_line(&mut line)? == 0 {
+                break;
+            }
+            line
+        };
+
+        match parse_command(&line) {
+            Ok(Some(command)) => match prompt.run_command(command) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => println!("Error: {}", e),
+            },
+            Ok(None) => {}
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("   ").unwrap(), None);
+        assert_eq!(
+            parse_command("add Buy milk").unwrap(),
+            Some(PromptCommand::Add(String::from("Buy milk")))
+        );
+        assert_eq!(parse_command("ls").unwrap(), Some(PromptCommand::List));
+        assert_eq!(
+            parse_command("done  milk ").unwrap(),
+            Some(PromptCommand::Done(String::from("milk")))
+        );
+        assert_eq!(
+            parse_command("filter status completed").unwrap(),
+            Some(PromptCommand::FilterStatus(Display::Completed))
+        );
+        assert_eq!(
+            parse_command("filter tag work").unwrap(),
+            Some(PromptCommand::FilterTag(String::from("work")))
+        );
+        assert_eq!(
+            parse_command("filter project Garden shed").unwrap(),
+            Some(PromptCommand::FilterProject(String::from("Garden shed")))
+        );
+        assert_eq!(
+            parse_command("filter clear").unwrap(),
+            Some(PromptCommand::FilterClear)
+        );
+        assert_eq!(parse_command("exit").unwrap(), Some(PromptCommand::Quit));
+
+        assert!(parse_command("add").is_err());
+        assert!(parse_command("filter status sideways").is_err());
+        assert!(parse_command("jump").is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        let tasks = vec![
+            Task::new(
+                String::from("Buy milk"),
+                None,
+                None,
+                None,
+                None,
+                Some(HashSet::from([String::from("home")])),
+            ),
+            Task::new(
+                String::from("Build shed"),
+                None,
+                None,
+                None,
+                None,
+                Some(HashSet::from([String::from("home"), String::from("hobby")])),
+            ),
+        ];
+
+        assert_eq!(completions("fi", &tasks), (0, vec![String::from("filter")]));
+        assert_eq!(
+            completions("done bu", &tasks),
+            (
+                5,
+                vec![String::from("Build shed"), String::from("Buy milk")]
+            )
+        );
+        assert_eq!(
+            completions("filter s", &tasks),
+            (7, vec![String::from("status")])
+        );
+        assert_eq!(
+            completions("filter tag ho", &tasks),
+            (11, vec![String::from("hobby"), String::from("home")])
+        );
+        assert_eq!(completions("add ", &tasks), (4, vec![]));
+
+        let candidates = vec![String::from("Build shed"), String::from("Buy milk")];
+        assert_eq!(common_prefix(&candidates), "Bu");
+    }
+}
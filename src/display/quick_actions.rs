@@ -1,9 +1,10 @@
 use anyhow::Result;
 use chrono::Local;
 
-use crate::backend::database::update_task_in_db;
-use crate::backend::task::Status;
+use crate::backend::history::{Change, Operation};
+use crate::backend::task::{Status, Task};
 use crate::display::add::{EntryMode, Inputs, Stage};
+use crate::display::text::grapheme_count;
 use crate::display::tui::App;
 
 impl App {
@@ -17,9 +18,58 @@ impl App {
         self.inputs = Inputs::default();
     }
 
+    /// Sets up the App to snooze the selected `Task`, asking only for
+    /// the date to hide it until
+    pub fn quick_snooze_setup(&mut self) {
+        if self.selected_is_locked() {
+            return;
+        }
+        if let Some(current_index) = self.tasklist.state.selected() {
+            self.inputs = Inputs::from_task(&self.tasklist.tasks[current_index]);
+            if self.inputs.scheduled.is_empty() {
+                self.inputs.scheduled = String::from("tomorrow");
+            }
+            self.update_stage = Stage::Scheduled;
+            self.entry_mode = EntryMode::Update;
+            self.update_popup = !self.update_popup;
+            self.character_index = grapheme_count(&self.inputs.scheduled);
+        }
+    }
+
+    /// Sets up the App to give the selected `Task` a time slot today,
+    /// asking only for when it starts and how long it lasts
+    pub fn quick_time_slot_setup(&mut self) {
+        if self.selected_is_locked() {
+            return;
+        }
+        if let Some(current_index) = self.tasklist.state.selected() {
+            self.inputs = Inputs::from_task(&self.tasklist.tasks[current_index]);
+            self.update_stage = Stage::TimeSlot;
+            self.entry_mode = EntryMode::Update;
+            self.update_popup = !self.update_popup;
+            self.character_index = grapheme_count(&self.inputs.time_slot);
+        }
+    }
+
+    /// Adds a copy of the selected `Task` and selects it.
+    /// The copy starts out `Open` if `reopen` is true.
+    pub fn clone_selected(&mut self, reopen: bool) -> Result<()> {
+        let Some(current_selection) = self.tasklist.state.selected() else {
+            return Ok(());
+        };
+
+        let copy = self.tasklist.tasks[current_selection].duplicate(reopen);
+        self.store.add(&copy)?;
+        self.history.record(vec![Change::Added(copy.clone())]);
+        self.update_tasklist()?;
+        self.select_task(copy.get_id())?;
+        Ok(())
+    }
+
     /// Updates the `Status` of a `Task`.
     /// If `Completed`, goes to `Open`.
-    /// If not `Completed`, goes to `Completed`
+    /// If not `Completed`, goes to `Completed`, creating the next
+    /// occurrence if the `Task` recurs
     pub fn quick_status(&mut self) -> Result<()> {
         // Mark as complete, or if already complete then open
         let current_selection = match self.tasklist.state.selected() {
@@ -27,20 +77,200 @@ impl App {
             None => return Ok(()),
         };
 
-        //let current_task = &self.tasklist.tasks[current_selection];
+        let task = &self.tasklist.tasks[current_selection];
+        let (status, important, locked) = (task.status, task.is_important(), task.locked);
+        if status == Status::Completed {
+            self.set_selected_status(Status::Open)
+        } else if self.selected_has_criteria_left() {
+            Ok(())
+        } else if self.config.confirm_complete && important && !locked {
+            // Make sure the right task is being completed first
+            self.complete_popup = !self.complete_popup;
+            Ok(())
+        } else {
+            self.set_selected_status(Status::Completed)
+        }
+    }
 
-        if self.tasklist.tasks[current_selection].status == Status::Completed {
-            self.tasklist.tasks[current_selection].status = Status::Open;
+    /// Sets the `Status` of the selected `Task`. Completing it stops its
+    /// timer and creates the next occurrence if the `Task` recurs.
+    pub fn set_selected_status(&mut self, status: Status) -> Result<()> {
+        let current_selection = match self.tasklist.state.selected() {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+        if self.tasklist.tasks[current_selection].status == status || self.selected_is_locked() {
+            return Ok(());
+        }
+        if status == Status::Completed && self.selected_has_criteria_left() {
+            return Ok(());
+        }
+
+        let before = self.tasklist.tasks[current_selection].clone();
+        let mut operation = Vec::new();
+        self.tasklist.tasks[current_selection].status = status;
+        if status != Status::Completed {
             self.tasklist.tasks[current_selection].completed_on = None;
         } else {
-            self.tasklist.tasks[current_selection].status = Status::Completed;
             self.tasklist.tasks[current_selection].completed_on = Some(Local::now());
+            self.tasklist.tasks[current_selection].stop_timer();
+
+            // Completing a recurring task lines up its next occurrence
+            let completed = &self.tasklist.tasks[current_selection];
+            if let Some(next_task) = self.add_next_occurrence(completed)? {
+                operation.push(Change::Added(next_task));
+            }
         }
 
-        update_task_in_db(&self.conn, &self.tasklist.tasks[current_selection])?;
+        self.store.update(&self.tasklist.tasks[current_selection])?;
+        operation.insert(
+            0,
+            Change::Updated {
+                before: Box::new(before),
+                after: self.tasklist.tasks[current_selection].clone(),
+            },
+        );
+        let id = self.tasklist.tasks[current_selection].get_id();
+        operation.extend(self.roll_up_parents(id)?);
+        self.history.record(operation);
+        if status == Status::Completed {
+            let name = self.tasklist.tasks[current_selection].name.clone();
+            self.show_undo_toast(format!("Completed '{}'", name));
+        }
+        self.update_tasklist()
+    }
+
+    /// Adds the next occurrence of the `Task` if it recurs, returning it. Nothing
+    /// is added if one was already lined up, i.e. when it was completed before,
+    /// reopened, and completed again.
+    pub fn add_next_occurrence(&self, task: &Task) -> Result<Option<Task>> {
+        let Some(next_task) = task.next_occurrence() else {
+            return Ok(None);
+        };
+        if self.store.has_next_occurrence(task.get_id())? {
+            return Ok(None);
+        }
+        self.store.add(&next_task)?;
+        Ok(Some(next_task))
+    }
+
+    /// Starts or stops the timer on the selected `Task`
+    pub fn toggle_timer(&mut self) -> Result<()> {
+        let current_selection = match self.tasklist.state.selected() {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+
+        let before = self.tasklist.tasks[current_selection].clone();
+        self.tasklist.tasks[current_selection].toggle_timer();
+        self.store.update(&self.tasklist.tasks[current_selection])?;
+        self.history.record(vec![Change::Updated {
+            before: Box::new(before),
+            after: self.tasklist.tasks[current_selection].clone(),
+        }]);
+        self.update_tasklist()
+    }
+
+    /// Returns true if the selected `Task` is locked, saying so in the status bar
+    pub fn selected_is_locked(&mut self) -> bool {
+        let Some(task) = self
+            .tasklist
+            .state
+            .selected()
+            .map(|i| &self.tasklist.tasks[i])
+        else {
+            return false;
+        };
+        if !task.locked {
+            return false;
+        }
+        let message = format!("'{}' is locked, L unlocks it", task.name);
+        self.show_toast(message);
+        true
+    }
+
+    /// Returns true, with a toast saying so, if the selected `Task` has
+    /// acceptance criteria left to tick off before it can be completed
+    pub fn selected_has_criteria_left(&mut self) -> bool {
+        let Some(task) = self
+            .tasklist
+            .state
+            .selected()
+            .map(|i| &self.tasklist.tasks[i])
+        else {
+            return false;
+        };
+        match task.check_criteria() {
+            Ok(()) => false,
+            Err(e) => {
+                self.show_toast(format!("{}, C ticks them off", e));
+                true
+            }
+        }
+    }
+
+    /// Locks the selected `Task` so it can't be updated or deleted, or unlocks it
+    pub fn toggle_lock(&mut self) -> Result<()> {
+        let current_selection = match self.tasklist.state.selected() {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+
+        let before = self.tasklist.tasks[current_selection].clone();
+        self.tasklist.tasks[current_selection].locked = !before.locked;
+        self.store.update(&self.tasklist.tasks[current_selection])?;
+        self.history.record(vec![Change::Updated {
+            before: Box::new(before),
+            after: self.tasklist.tasks[current_selection].clone(),
+        }]);
+        self.update_tasklist()
+    }
+
+    /// Moves the selected `Task` into the archive, if it is `Completed`
+    pub fn archive_selected(&mut self) -> Result<()> {
+        let current_selection = match self.tasklist.state.selected() {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+        if self.tasklist.tasks[current_selection].status != Status::Completed {
+            return Ok(());
+        }
+
+        self.store.archive(std::slice::from_ref(
+            &self.tasklist.tasks[current_selection],
+        ))?;
+        self.update_tasklist()
+    }
+
+    /// Undoes the latest add, update, delete, or status change
+    pub fn undo(&mut self) -> Result<()> {
+        let operation = self.history.undo(self.store.as_ref())?;
+        if operation.is_some() {
+            // Whatever the toast offered to undo may have just been undone
+            self.toast = None;
+        }
+        self.reselect_after(operation)
+    }
+
+    /// Redoes the latest undone operation
+    pub fn redo(&mut self) -> Result<()> {
+        let operation = self.history.redo(self.store.as_ref())?;
+        self.reselect_after(operation)
+    }
+
+    /// Refreshes the tasklist after an undo or redo, selecting the
+    /// `Task` it changed if it is still around
+    fn reselect_after(&mut self, operation: Option<Operation>) -> Result<()> {
+        let Some(operation) = operation else {
+            return Ok(());
+        };
         self.update_tasklist()?;
 
-        self.tasklist.state.select(Some(current_selection));
+        let changed_id = match &operation[0] {
+            Change::Added(task) | Change::Deleted(task) => task.get_id(),
+            Change::Updated { after, .. } => after.get_id(),
+        };
+        self.select_task(changed_id)?;
         Ok(())
     }
 }
@@ -0,0 +1,216 @@
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::text::Span;
+
+use crate::display::tui::App;
+
+// Features that stand on their own (exporters, sync, extra panels) hook into
+// the TUI through the registry instead of adding to `handle_main_key` and the
+// help menu by hand. Each one has a `register` function that gets called
+// from `Registry::builtin`, adding its actions and status bar segments.
+
+/// A key that runs an `Action` from the main view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// A key pressed on its own. Capital letters work whether or not
+    /// the terminal reports the shift that goes with them.
+    pub fn key(ch: char) -> Self {
+        Self {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// A key that isn't a character, like Enter or Backspace
+    pub fn code(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// A key pressed with CTRL held down
+    pub fn ctrl(ch: char) -> Self {
+        Self {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    /// Returns true if the key pressed is this binding
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers.difference(KeyModifiers::SHIFT),
+            _ => key.modifiers,
+        };
+        self.code == key.code && self.modifiers == modifiers
+    }
+
+    /// How the binding is shown in the help menu, i.e. "T" or "CTRL e"
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => String::from("SPACE"),
+            KeyCode::Char(ch) => ch.to_string(),
+            code => format!("{:?}", code).to_uppercase(),
+        };
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            format!("CTRL {}", key)
+        } else {
+            key
+        }
+    }
+}
+
+/// Something a feature lets the user do from the main view
+pub struct Action {
+    /// What the help menu says it does
+    pub description: &'static str,
+    pub key: KeyBinding,
+    /// Whether it changes tasks, so it's turned off when looking at
+    /// the archive or a snapshot
+    pub writes: bool,
+    pub run: fn(&mut App) -> Result<()>,
+}
+
+/// Adds spans to the right side of the status bar, or nothing if it has
+/// nothing to say at the moment
+pub type StatusHook = fn(&App) -> Option<Vec<Span<'static>>>;
+
+/// The actions and status bar segments features have registered
+#[derive(Default)]
+pub struct Registry {
+    actions: Vec<Action>,
+    status_hooks: Vec<StatusHook>,
+}
+
+impl Registry {
+    /// Builds the registry from every feature compiled in
+    pub fn builtin() -> Result<Self> {
+        let mut registry = Registry::default();
+        crate::display::tui::register(&mut registry)?;
+        crate::display::finder::register(&mut registry)?;
+        crate::display::tag_manager::register(&mut registry)?;
+        crate::display::task_history::register(&mut registry)?;
+        crate::display::urgent_limit::register(&mut registry)?;
+        crate::display::db_picker::register(&mut registry)?;
+        crate::display::dependency_graph::register(&mut registry)?;
+        crate::display::subtasks::register(&mut registry)?;
+        crate::display::criteria::register(&mut registry)?;
+        crate::display::theme_editor::register(&mut registry)?;
+        Ok(registry)
+    }
+
+    /// Adds an action, failing if another action already has its key.
+    /// Registered keys are checked before the built in ones, so pick one that
+    /// isn't in the help menu yet.
+    pub fn register_action(&mut self, action: Action) -> Result<()> {
+        if let Some(taken) = self
+            .actions
+            .iter()
+            .find(|registered| registered.key == action.key)
+        {
+            bail!(
+                "'{}' is already bound to '{}'",
+                action.key.label(),
+                taken.description
+            );
+        }
+        self.actions.push(action);
+        Ok(())
+    }
+
+    /// Adds a segment to the status bar, shown in the order registered
+    pub fn register_status(&mut self, hook: StatusHook) {
+        self.status_hooks.push(hook);
+    }
+
+    /// Returns the action bound to the key pressed, if there is one
+    pub fn action_for(&self, key: &KeyEvent) -> Option<&Action> {
+        self.actions.iter().find(|action| action.key.matches(key))
+    }
+
+    /// Returns every registered action, in the order registered
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Returns the spans every status hook has to add
+    pub fn status_spans(&self, app: &App) -> Vec<Span<'static>> {
+        let mut spans = vec![];
+        for hook in &self.status_hooks {
+            if let Some(hook_spans) = hook(app) {
+                spans.extend(hook_spans);
+                spans.push("  ".into());
+            }
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nothing(_: &mut App) -> Result<()> {
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_action() {
+        let mut registry = Registry::default();
+        registry
+            .register_action(Action {
+                description: "Do a thing",
+                key: KeyBinding::key('T'),
+                writes: false,
+                run: nothing,
+            })
+            .unwrap();
+        assert!(registry
+            .register_action(Action {
+                description: "Do another thing",
+                key: KeyBinding::key('T'),
+                writes: true,
+                run: nothing,
+            })
+            .is_err());
+        registry
+            .register_action(Action {
+                description: "Do another thing",
+                key: KeyBinding::ctrl('t'),
+                writes: true,
+                run: nothing,
+            })
+            .unwrap();
+
+        // Capital letters match with or without the shift reported
+        let shifted = KeyEvent::new(KeyCode::Char('T'), KeyModifiers::SHIFT);
+        let bare = KeyEvent::new(KeyCode::Char('T'), KeyModifiers::NONE);
+        let ctrl = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL);
+        let plain = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE);
+        assert_eq!(
+            registry.action_for(&shifted).unwrap().description,
+            "Do a thing"
+        );
+        assert_eq!(
+            registry.action_for(&bare).unwrap().description,
+            "Do a thing"
+        );
+        assert_eq!(
+            registry.action_for(&ctrl).unwrap().description,
+            "Do another thing"
+        );
+        assert!(registry.action_for(&plain).is_none());
+
+        assert_eq!(KeyBinding::ctrl('t').label(), "CTRL t");
+        assert_eq!(registry.actions().len(), 2);
+
+        // Features compiled in don't step on each other's keys
+        assert!(Registry::builtin().is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+
+use crate::backend::calendar::today;
+use crate::backend::database::TaskSelect;
+use crate::backend::notify::{due_reminders, still_working_reminders, Reminder, SnoozeFor};
+use crate::backend::query::{DateField, Op, Query};
+use crate::backend::task::{Status, Task};
+use crate::display::tui::App;
+
+/// How often to look for tasks that have come due, or whose snooze ran out
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl App {
+    /// Queues up reminders about tasks that are overdue or due by tomorrow, or
+    /// have been `Working` a while without an update, skipping ones that are
+    /// snoozed or were already shown this session
+    pub fn check_reminders(&mut self) {
+        if !self.config.notifications || self.snapshot.is_some() {
+            return;
+        }
+        if self
+            .reminders_checked
+            .is_some_and(|checked| checked.elapsed() < REMINDER_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.reminders_checked = Some(Instant::now());
+
+        // Not being able to read the snoozes shouldn't get in the way of using
+        // the app, so it's only said the first time
+        let snoozed = match self.store.snoozed_reminders(&Local::now()) {
+            Ok(snoozed) => snoozed,
+            Err(e) => {
+                if !std::mem::replace(&mut self.reminders_failed, true) {
+                    self.show_toast(format!("No reminders: {:#}", e));
+                }
+                return;
+            }
+        };
+        let today = today();
+        let tasks = match self.reminder_tasks(today) {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                if !std::mem::replace(&mut self.reminders_failed, true) {
+                    self.show_toast(format!("No reminders: {:#}", e));
+                }
+                return;
+            }
+        };
+        let mut reminders = due_reminders(&tasks, today, 1, &snoozed);
+        if let Ok(Some(after)) = self.config.still_working_after() {
+            if let Ok(last_touched) = self.store.last_touched() {
+                reminders.extend(still_working_reminders(
+                    &tasks,
+                    &last_touched,
+                    &Local::now(),
+                    after,
+                    &snoozed,
+                ));
+            }
+        }
+        for reminder in reminders {
+            let queued = self.reminders.iter().any(|queued| queued.id == reminder.id);
+            if !queued && !self.reminded.contains(&reminder.id) {
+                self.reminders.push_back(reminder);
+            }
+        }
+    }
+
+    /// Reads in the tasks there could be a reminder about: ones not yet
+    /// `Completed` that are due by tomorrow, and ones being worked on
+    fn reminder_tasks(&self, today: NaiveDate) -> Result<Vec<Task>> {
+        let tomorrow = today.succ_opt().unwrap_or(today);
+        let due_soon = Query::And(
+            Box::new(Query::Status(Op::NotEq, Status::Completed)),
+            Box::new(Query::Date(DateField::Due, Op::LtEq, Some(tomorrow))),
+        );
+        let working = Query::Status(Op::Eq, Status::Working);
+        let select = TaskSelect::new().query(&Query::Or(Box::new(due_soon), Box::new(working)));
+        Ok(self.store.select(&select)?.tasks)
+    }
+
+    /// Shows the next queued reminder, once the status bar is free. During quiet
+    /// hours they stay queued, and once they end, the ones that piled up are
+    /// shown together as a digest.
+    pub fn show_next_reminder(&mut self) {
+        if self.current_toast().is_some() || self.reminders.is_empty() {
+            return;
+        }
+        let quiet = self.config.quiet_hours().ok().flatten();
+        if quiet.is_some_and(|quiet| quiet.is_quiet(&Local::now())) {
+            self.reminders_held = true;
+            return;
+        }
+        if std::mem::take(&mut self.reminders_held) && self.reminders.len() > 1 {
+            let messages: Vec<String> = self.reminders.iter().map(Reminder::message).collect();
+            self.reminded
+                .extend(self.reminders.drain(..).map(|reminder| reminder.id));
+            self.show_digest_toast(format!("While it was quiet: {}", messages.join(", ")));
+            return;
+        }
+        if let Some(reminder) = self.reminders.pop_front() {
+            self.reminded.insert(reminder.id);
+            self.show_reminder_toast(&reminder);
+        }
+    }
+
+    /// Snoozes the reminder being shown, so it comes back once the snooze
+    /// runs out, even if checklist was closed in between
+    pub fn snooze_reminder(&mut self, snooze: SnoozeFor) -> Result<()> {
+        let Some(id) = self.current_reminder() else {
+            return Ok(());
+        };
+        let name = self
+            .store
+            .select(&TaskSelect::new().ids(&[id]))?
+            .tasks
+            .pop()
+            .map(|task| task.name)
+            .unwrap_or_default();
+        self.store
+            .snooze_reminders(id, &snooze.until(Local::now()))?;
+        self.reminded.remove(&id);
+        let message = match snooze {
+            SnoozeFor::Tomorrow => format!("Snoozed reminders about '{}' until tomorrow", name),
+            _ => format!("Snoozed reminders about '{}' for {}", name, snooze.label()),
+        };
+        self.show_toast(message);
+        Ok(())
+    }
+}
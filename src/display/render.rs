@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
+use std::str::FromStr;
 
+use chrono::{DateTime, Local, NaiveDate};
 use ratatui::symbols::scrollbar;
 use ratatui::widgets::BorderType;
 use ratatui::Frame;
@@ -8,35 +10,68 @@ use ratatui::{
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, Clear, HighlightSpacing, List, ListItem, Paragraph, Scrollbar,
+        Block, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, Scrollbar,
         ScrollbarOrientation, Wrap,
     },
 };
+use unicode_segmentation::UnicodeSegmentation;
 
+use crate::backend::calendar::{calendar, relative_day, today};
+use crate::backend::collate::sort_text;
+use crate::backend::config::profile;
+use crate::backend::events::{Diff, FieldChange, TaskEvent};
 use crate::backend::task::Display;
-use crate::backend::task::{Status, Task, Urgency};
-use crate::display::theme::Theme;
-use crate::display::tui::{App, LayoutView};
+use crate::backend::task::{
+    agenda, format_duration, Criterion, RolledUp, Status, TagMatch, Task, Urgency,
+};
+use crate::backend::ticket::task_tickets;
+use crate::display::group::GroupRow;
+use crate::display::info_layout::InfoField;
+use crate::display::kanban::KANBAN_COLUMNS;
+use crate::display::row_format::{RowField, RowPiece};
+use crate::display::tag_manager::TagEdit;
+use crate::display::text::{first_graphemes, width};
+use crate::display::theme::{DateStyle, Theme};
+use crate::display::theme_editor::{field_values, theme_fields};
+use crate::display::tui::{App, Focus, LayoutView};
+use crate::display::window::visible_rows;
+
+/// Formats the date the way the theme's `dates` style asks for
+fn format_date(date: NaiveDate, theme: &Theme) -> String {
+    match theme.theme_styles.dates {
+        DateStyle::Absolute => date.to_string(),
+        DateStyle::Relative => relative_day(date, today()),
+        DateStyle::Both => format!("{} ({})", date, relative_day(date, today())),
+    }
+}
 
 impl Status {
     /// Based on the Enum value, will return a colored `Span`
-    pub fn to_colored_span(&self, theme: &Theme) -> Span<'_> {
+    pub fn to_colored_span(self, theme: &Theme) -> Span<'static> {
         match self {
             Status::Open => Span::styled(
                 String::from("Open"),
-                Style::default().fg(theme.text_colors.status_open),
+                Style::default()
+                    .fg(theme.text_colors.status_open)
+                    .add_modifier(theme.text_modifiers.status_open),
             ),
             Status::Working => Span::styled(
                 String::from("Working"),
-                Style::default().fg(theme.text_colors.status_working),
+                Style::default()
+                    .fg(theme.text_colors.status_working)
+                    .add_modifier(theme.text_modifiers.status_working),
             ),
             Status::Paused => Span::styled(
                 String::from("Paused"),
-                Style::default().fg(theme.text_colors.status_paused),
+                Style::default()
+                    .fg(theme.text_colors.status_paused)
+                    .add_modifier(theme.text_modifiers.status_paused),
             ),
             Status::Completed => Span::styled(
                 String::from("Completed"),
-                Style::default().fg(theme.text_colors.status_completed),
+                Style::default()
+                    .fg(theme.text_colors.status_completed)
+                    .add_modifier(theme.text_modifiers.status_completed),
             ),
         }
     }
@@ -44,45 +79,61 @@ impl Status {
 
 impl Urgency {
     /// Based on the Enum value, will return a colored `Span`
-    pub fn to_colored_span(&self, theme: &Theme) -> Span<'_> {
+    pub fn to_colored_span(self, theme: &Theme) -> Span<'static> {
         match self {
             Urgency::Low => Span::styled(
                 String::from("Low"),
-                Style::default().fg(theme.text_colors.urgency_low),
+                Style::default()
+                    .fg(theme.text_colors.urgency_low)
+                    .add_modifier(theme.text_modifiers.urgency_low),
             ),
             Urgency::Medium => Span::styled(
                 String::from("Medium"),
-                Style::default().fg(theme.text_colors.urgency_medium),
+                Style::default()
+                    .fg(theme.text_colors.urgency_medium)
+                    .add_modifier(theme.text_modifiers.urgency_medium),
             ),
             Urgency::High => Span::styled(
                 String::from("High"),
-                Style::default().fg(theme.text_colors.urgency_high),
+                Style::default()
+                    .fg(theme.text_colors.urgency_high)
+                    .add_modifier(theme.text_modifiers.urgency_high),
             ),
             Urgency::Critical => Span::styled(
                 String::from("Critical"),
-                Style::default().fg(theme.text_colors.urgency_critical),
+                Style::default()
+                    .fg(theme.text_colors.urgency_critical)
+                    .add_modifier(theme.text_modifiers.urgency_critical),
             ),
         }
     }
 
     /// Based on the Enum value, will return a colored `Span` of exclamation marks
-    pub fn to_colored_exclamation_marks(&self, theme: &Theme) -> Span<'_> {
+    pub fn to_colored_exclamation_marks(self, theme: &Theme) -> Span<'static> {
         match self {
             Urgency::Low => Span::styled(
                 String::from(&theme.theme_styles.urgency_low),
-                Style::default().fg(theme.text_colors.urgency_low),
+                Style::default()
+                    .fg(theme.text_colors.urgency_low)
+                    .add_modifier(theme.text_modifiers.urgency_low),
             ),
             Urgency::Medium => Span::styled(
                 String::from(&theme.theme_styles.urgency_medium),
-                Style::default().fg(theme.text_colors.urgency_medium),
+                Style::default()
+                    .fg(theme.text_colors.urgency_medium)
+                    .add_modifier(theme.text_modifiers.urgency_medium),
             ),
             Urgency::High => Span::styled(
                 String::from(&theme.theme_styles.urgency_high),
-                Style::default().fg(theme.text_colors.urgency_high),
+                Style::default()
+                    .fg(theme.text_colors.urgency_high)
+                    .add_modifier(theme.text_modifiers.urgency_high),
             ),
             Urgency::Critical => Span::styled(
                 String::from(&theme.theme_styles.urgency_critical),
-                Style::default().fg(theme.text_colors.urgency_critical),
+                Style::default()
+                    .fg(theme.text_colors.urgency_critical)
+                    .add_modifier(theme.text_modifiers.urgency_critical),
             ),
         }
     }
@@ -90,19 +141,25 @@ impl Urgency {
 
 impl Display {
     /// Based on the Enum value, will return a colored `Span`
-    pub fn to_colored_span(&self, theme: &Theme) -> Span<'_> {
+    pub fn to_colored_span(self, theme: &Theme) -> Span<'static> {
         match self {
             Display::All => Span::styled(
                 String::from("All"),
-                Style::default().fg(theme.text_colors.filter_status_all),
+                Style::default()
+                    .fg(theme.text_colors.filter_status_all)
+                    .add_modifier(theme.text_modifiers.filter_status_all),
             ),
             Display::Completed => Span::styled(
                 String::from("Completed"),
-                Style::default().fg(theme.text_colors.filter_status_completed),
+                Style::default()
+                    .fg(theme.text_colors.filter_status_completed)
+                    .add_modifier(theme.text_modifiers.filter_status_completed),
             ),
             Display::NotCompleted => Span::styled(
                 String::from("NotCompleted"),
-                Style::default().fg(theme.text_colors.filter_status_notcompleted),
+                Style::default()
+                    .fg(theme.text_colors.filter_status_notcompleted)
+                    .add_modifier(theme.text_modifiers.filter_status_notcompleted),
             ),
         }
     }
@@ -114,15 +171,27 @@ impl LayoutView {
         match self {
             LayoutView::Horizontal => Span::styled(
                 String::from("Horizontal"),
-                Style::default().fg(theme.text_colors.layout_horizontal),
+                Style::default()
+                    .fg(theme.text_colors.layout_horizontal)
+                    .add_modifier(theme.text_modifiers.layout_horizontal),
             ),
             LayoutView::Vertical => Span::styled(
                 String::from("Vertical"),
-                Style::default().fg(theme.text_colors.layout_vertical),
+                Style::default()
+                    .fg(theme.text_colors.layout_vertical)
+                    .add_modifier(theme.text_modifiers.layout_vertical),
             ),
             LayoutView::Smart => Span::styled(
                 String::from("Smart"),
-                Style::default().fg(theme.text_colors.layout_smart),
+                Style::default()
+                    .fg(theme.text_colors.layout_smart)
+                    .add_modifier(theme.text_modifiers.layout_smart),
+            ),
+            LayoutView::Kanban => Span::styled(
+                String::from("Kanban"),
+                Style::default()
+                    .fg(theme.text_colors.layout_kanban)
+                    .add_modifier(theme.text_modifiers.layout_kanban),
             ),
         }
     }
@@ -130,18 +199,20 @@ impl LayoutView {
 
 impl Task {
     /// Returns the `Task` tags as a vector of `Span`
-    fn span_tags(&self, theme: &Theme) -> Vec<Span> {
+    fn span_tags(&self, theme: &Theme) -> Vec<Span<'_>> {
         let mut tags_span_vec = vec![Span::from("Tags:".to_string())];
         match &self.tags {
             Some(tags) => {
                 let mut task_tags_vec = Vec::from_iter(tags);
-                task_tags_vec.sort();
+                sort_text(&mut task_tags_vec);
                 //task_tags_vec.sort_by(|a, b| a.cmp(b));
 
                 for tag in task_tags_vec {
                     tags_span_vec.push(Span::styled(
                         format!(" {} ", tag),
-                        Style::default().fg(theme.text_colors.tags),
+                        Style::default()
+                            .fg(theme.text_colors.tags)
+                            .add_modifier(theme.text_modifiers.tags),
                     ));
                     tags_span_vec.push(Span::from("|"));
                 }
@@ -152,98 +223,461 @@ impl Task {
         }
     }
 
-    /// Returns a `ListItem` of the `Task`
-    pub fn to_listitem(&self, theme: &Theme) -> ListItem {
-        let line = match self.status {
-            Status::Completed => {
-                let spans = vec![
-                    Span::styled(
-                        theme.theme_styles.completed.clone(),
-                        Style::default().fg(theme.text_colors.status_completed),
-                    ),
-                    " | ".into(),
-                    self.status.to_colored_span(theme).clone(),
-                    " - ".into(),
-                    self.name.clone().into(),
-                ];
-                Line::from(spans)
+    /// Returns a dimmed `Span` saying how long ago something happened to the
+    /// `Task`, i.e. "added 3 days ago", unless dates are shown as YYYY-MM-DD
+    fn span_age(&self, what: &str, when: DateTime<Local>, theme: &Theme) -> Option<Span<'static>> {
+        if theme.theme_styles.dates == DateStyle::Absolute {
+            return None;
+        }
+        let age = relative_day(calendar().date_of(&when), today());
+        Some(Span::styled(
+            format!(" · {} {}", what, age),
+            Style::default().dim(),
+        ))
+    }
+
+    /// Returns a `ListItem` of the `Task` for the task list, laid out by the
+    /// theme's `row_format`, with how many `subtasks` it has if there are any
+    pub fn to_listitem(
+        &self,
+        theme: &Theme,
+        subtasks: usize,
+        rolled_up: Option<&RolledUp>,
+    ) -> ListItem<'_> {
+        let mut spans = vec![];
+        for piece in theme.theme_styles.row_format.pieces() {
+            match piece {
+                RowPiece::Text(text) => spans.push(Span::from(text.clone())),
+                RowPiece::Field(field) => {
+                    spans.extend(self.row_field_spans(*field, theme, subtasks, rolled_up))
+                }
+            }
+        }
+        ListItem::new(Line::from(spans))
+    }
+
+    /// Returns the spans of a field of the `Task` in its row of the task list,
+    /// which is none at all for one it doesn't have
+    fn row_field_spans(
+        &self,
+        field: RowField,
+        theme: &Theme,
+        subtasks: usize,
+        rolled_up: Option<&RolledUp>,
+    ) -> Vec<Span<'static>> {
+        let completed = self.status == Status::Completed;
+        match field {
+            RowField::UrgencyMarks if completed => vec![Span::styled(
+                theme.theme_styles.completed.clone(),
+                Style::default()
+                    .fg(theme.text_colors.status_completed)
+                    .add_modifier(theme.text_modifiers.status_completed),
+            )],
+            RowField::UrgencyMarks => vec![self.urgency.to_colored_exclamation_marks(theme)],
+            RowField::Urgency => vec![self.urgency.to_colored_span(theme)],
+            RowField::Status => vec![self.status.to_colored_span(theme)],
+            RowField::Name => vec![Span::from(self.name.clone())],
+            RowField::Subtasks => span_subtasks(subtasks).into_iter().collect(),
+            RowField::RolledUp if completed => vec![],
+            RowField::RolledUp => span_rolled_up(rolled_up, theme).into_iter().collect(),
+            RowField::Locked if self.locked => vec![Span::from(" 🔒")],
+            RowField::Locked => vec![],
+            // Flag tasks with a timer running
+            RowField::Timer if self.timer_started.is_some() && !completed => {
+                vec![Span::styled(
+                    " ⏱",
+                    Style::default()
+                        .fg(theme.text_colors.time_spent)
+                        .add_modifier(theme.text_modifiers.time_spent),
+                )]
             }
-            _ => {
-                let spans = vec![
-                    //"☐ - ".white(),
-                    self.urgency.to_colored_exclamation_marks(theme),
-                    " | ".into(),
-                    self.status.to_colored_span(theme).clone(),
-                    " - ".into(),
-                    self.name.clone().into(),
-                ];
-                Line::from(spans)
+            RowField::Timer => vec![],
+            RowField::Age => {
+                let age = match (completed, self.completed_on) {
+                    (true, Some(completed_on)) => self.span_age("completed", completed_on, theme),
+                    (true, None) => None,
+                    (false, _) => self.span_age("added", self.date_added, theme),
+                };
+                age.into_iter().collect()
             }
-        };
-        ListItem::new(line)
+            RowField::Tags => {
+                let mut tags: Vec<&String> = self.tags.iter().flatten().collect();
+                sort_text(&mut tags);
+                let mut spans = vec![];
+                for (i, tag) in tags.into_iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::from(", "));
+                    }
+                    spans.push(Span::styled(
+                        tag.clone(),
+                        Style::default()
+                            .fg(theme.text_colors.tags)
+                            .add_modifier(theme.text_modifiers.tags),
+                    ));
+                }
+                spans
+            }
+            RowField::Project => self
+                .project
+                .iter()
+                .map(|project| {
+                    Span::styled(
+                        project.clone(),
+                        Style::default()
+                            .fg(theme.text_colors.project)
+                            .add_modifier(theme.text_modifiers.project),
+                    )
+                })
+                .collect(),
+            RowField::Due => self
+                .due
+                .iter()
+                .map(|due| {
+                    let (color, modifier) = if self.is_overdue(today()) {
+                        (theme.text_colors.overdue, theme.text_modifiers.overdue)
+                    } else {
+                        (theme.text_colors.due_date, theme.text_modifiers.due_date)
+                    };
+                    Span::styled(
+                        format_date(*due, theme),
+                        Style::default().fg(color).add_modifier(modifier),
+                    )
+                })
+                .collect(),
+            RowField::Scheduled => self
+                .scheduled
+                .iter()
+                .map(|scheduled| {
+                    Span::styled(
+                        format_date(*scheduled, theme),
+                        Style::default()
+                            .fg(theme.text_colors.scheduled)
+                            .add_modifier(theme.text_modifiers.scheduled),
+                    )
+                })
+                .collect(),
+        }
     }
 
-    /// Returns a vector of `Line` containing several elements of the `Task`
-    pub fn to_text_vec(&self, theme: &Theme) -> Vec<Line> {
-        let completion_date = match self.completed_on {
-            Some(date) => format!(" - {}", date.date_naive()),
-            None => String::from(""),
-        };
-        let text = vec![
-            Line::from(vec![
-                Span::styled("Title: ", Style::default()),
-                Span::styled(&self.name, Style::default().fg(theme.text_colors.title)),
-            ]),
-            Line::from(vec![
-                Span::styled("Created: ", Style::default()),
-                Span::styled(
-                    self.date_added.date_naive().to_string(),
-                    Style::default().fg(theme.text_colors.created_date),
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("Status: ", Style::default()),
-                self.status.to_colored_span(theme),
-                Span::styled(
-                    completion_date,
-                    Style::default().fg(theme.text_colors.completed_date),
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("Urgency: ", Style::default()),
-                self.urgency.to_colored_span(theme),
-            ]),
-            Line::from(self.span_tags(theme)),
-            Line::from(vec![Span::styled("", Style::default())]),
-            Line::from(vec![Span::styled("Latest:", Style::default().underlined())]),
-            Line::from(vec![Span::styled(
-                self.latest.clone().unwrap_or("".to_string()),
-                Style::default().fg(theme.text_colors.latest),
-            )]),
-            Line::from(vec![Span::styled("", Style::default())]),
-            Line::from(vec![Span::styled(
-                "Description:",
-                Style::default().underlined(),
-            )]),
-            Line::from(vec![Span::styled(
-                self.description.clone().unwrap_or("".to_string()),
-                Style::default().fg(theme.text_colors.description),
-            )]),
+    /// Returns a `ListItem` of the `Task` as a card on the Kanban board
+    pub fn to_card(&self, theme: &Theme) -> ListItem<'_> {
+        let mut title = vec![
+            self.urgency.to_colored_exclamation_marks(theme),
+            " ".into(),
+            Span::styled(self.name.clone(), Style::default().bold()),
         ];
+        if self.locked {
+            title.push(" 🔒".into());
+        }
+        if self.timer_started.is_some() {
+            title.push(Span::styled(
+                " ⏱",
+                Style::default()
+                    .fg(theme.text_colors.time_spent)
+                    .add_modifier(theme.text_modifiers.time_spent),
+            ));
+        }
+
+        let mut details = vec![];
+        if let Some(due) = self.due {
+            details.push(Span::styled(
+                format!("due {} ", due),
+                Style::default()
+                    .fg(theme.text_colors.due_date)
+                    .add_modifier(theme.text_modifiers.due_date),
+            ));
+        }
+        if let Some(tags) = &self.tags {
+            let mut tags: Vec<&String> = tags.iter().collect();
+            sort_text(&mut tags);
+            for tag in tags {
+                details.push(Span::styled(
+                    format!("#{} ", tag),
+                    Style::default()
+                        .fg(theme.text_colors.tags)
+                        .add_modifier(theme.text_modifiers.tags),
+                ));
+            }
+        }
+
+        ListItem::new(vec![Line::from(title), Line::from(details), Line::from("")])
+    }
+
+    /// Returns a vector of `Line` containing the elements of the `Task` the
+    /// theme's `info_layout` asks for, in its order, along with what it rolls
+    /// up from its subtasks if it has any
+    pub fn to_text_vec(&self, theme: &Theme, rolled_up: Option<&RolledUp>) -> Vec<Line<'_>> {
+        theme
+            .theme_styles
+            .info_layout
+            .fields()
+            .iter()
+            .flat_map(|field| self.info_lines(*field, theme, rolled_up))
+            .collect()
+    }
+
+    /// Returns the lines showing one element of the `Task` in the `Task Info`
+    /// block, which is none for the elements it doesn't have
+    fn info_lines(
+        &self,
+        field: InfoField,
+        theme: &Theme,
+        rolled_up: Option<&RolledUp>,
+    ) -> Vec<Line<'_>> {
+        let mut text = vec![];
+        match field {
+            InfoField::Title => {
+                text.push(Line::from(vec![
+                    Span::styled("Title: ", Style::default()),
+                    Span::styled(
+                        &self.name,
+                        Style::default()
+                            .fg(theme.text_colors.title)
+                            .add_modifier(theme.text_modifiers.title),
+                    ),
+                ]));
+            }
+            InfoField::Created => {
+                text.push(Line::from(vec![
+                    Span::styled("Created: ", Style::default()),
+                    Span::styled(
+                        format_date(calendar().date_of(&self.date_added), theme),
+                        Style::default()
+                            .fg(theme.text_colors.created_date)
+                            .add_modifier(theme.text_modifiers.created_date),
+                    ),
+                ]));
+            }
+            InfoField::Status => {
+                let completion_date = match self.completed_on {
+                    Some(date) => format!(" - {}", format_date(calendar().date_of(&date), theme)),
+                    None => String::from(""),
+                };
+                text.push(Line::from(vec![
+                    Span::styled("Status: ", Style::default()),
+                    self.status.to_colored_span(theme),
+                    Span::styled(
+                        completion_date,
+                        Style::default()
+                            .fg(theme.text_colors.completed_date)
+                            .add_modifier(theme.text_modifiers.completed_date),
+                    ),
+                ]));
+            }
+            InfoField::Urgency => {
+                text.push(Line::from(vec![
+                    Span::styled("Urgency: ", Style::default()),
+                    self.urgency.to_colored_span(theme),
+                ]));
+            }
+            InfoField::Tags => {
+                text.push(Line::from(self.span_tags(theme)));
+            }
+            InfoField::RolledUp => {
+                if let Some(rolled_up) = rolled_up {
+                    text.extend(rolled_up_lines(rolled_up, theme));
+                }
+            }
+            InfoField::Locked => {
+                if self.locked {
+                    text.push(Line::from(vec![
+                        Span::styled("Locked: ", Style::default()),
+                        Span::styled("L unlocks it for editing", Style::default().dim()),
+                    ]));
+                }
+            }
+            InfoField::Project => {
+                if let Some(project) = &self.project {
+                    text.push(Line::from(vec![
+                        Span::styled("Project: ", Style::default()),
+                        Span::styled(
+                            project,
+                            Style::default()
+                                .fg(theme.text_colors.project)
+                                .add_modifier(theme.text_modifiers.project),
+                        ),
+                    ]));
+                }
+            }
+            InfoField::Due => {
+                if let Some(due) = self.due {
+                    let today = today();
+                    let (due_string, due_style) = if self.is_overdue(today) {
+                        (
+                            format!("{} (overdue)", format_date(due, theme)),
+                            Style::default()
+                                .fg(theme.text_colors.overdue)
+                                .add_modifier(theme.text_modifiers.overdue),
+                        )
+                    } else {
+                        (
+                            format_date(due, theme),
+                            Style::default()
+                                .fg(theme.text_colors.due_date)
+                                .add_modifier(theme.text_modifiers.due_date),
+                        )
+                    };
+                    text.push(Line::from(vec![
+                        Span::styled("Due: ", Style::default()),
+                        Span::styled(due_string, due_style),
+                    ]));
+                }
+            }
+            InfoField::Snoozed => {
+                if let Some(scheduled) = self.scheduled {
+                    if self.is_snoozed(today()) {
+                        text.push(Line::from(vec![
+                            Span::styled("Snoozed until: ", Style::default()),
+                            Span::styled(
+                                format_date(scheduled, theme),
+                                Style::default()
+                                    .fg(theme.text_colors.scheduled)
+                                    .add_modifier(theme.text_modifiers.scheduled),
+                            ),
+                        ]));
+                    }
+                }
+            }
+            InfoField::TimeSlot => {
+                if let Some((start, end)) = self.time_slot() {
+                    text.push(Line::from(vec![
+                        Span::styled("Time slot: ", Style::default()),
+                        Span::styled(
+                            format!(
+                                "{} {}–{}",
+                                format_date(calendar().date_of(&start), theme),
+                                start.format("%H:%M"),
+                                end.format("%H:%M")
+                            ),
+                            Style::default()
+                                .fg(theme.text_colors.scheduled)
+                                .add_modifier(theme.text_modifiers.scheduled),
+                        ),
+                    ]));
+                }
+            }
+            InfoField::TimeSpent => {
+                if self.timer_started.is_some() || self.time_spent > 0 {
+                    let running = if self.timer_started.is_some() {
+                        " (running)"
+                    } else {
+                        ""
+                    };
+                    text.push(Line::from(vec![
+                        Span::styled("Time spent: ", Style::default()),
+                        Span::styled(
+                            format!("{}{}", format_duration(self.total_time_spent()), running),
+                            Style::default()
+                                .fg(theme.text_colors.time_spent)
+                                .add_modifier(theme.text_modifiers.time_spent),
+                        ),
+                    ]));
+                }
+            }
+            InfoField::From => {
+                if let Some(external_ref) = &self.external_ref {
+                    let mut spans = vec![
+                        Span::styled("From: ", Style::default()),
+                        Span::styled(
+                            external_ref.to_string(),
+                            Style::default()
+                                .fg(theme.text_colors.ticket)
+                                .add_modifier(theme.text_modifiers.ticket),
+                        ),
+                    ];
+                    if let Some(url) = &external_ref.url {
+                        spans.push(Span::styled(format!(" ({})", url), Style::default()));
+                    }
+                    text.push(Line::from(spans));
+                }
+            }
+            InfoField::Tickets => {
+                let tickets = task_tickets(self);
+                if !tickets.is_empty() {
+                    let mut spans = vec![Span::styled("Tickets: ", Style::default())];
+                    for (i, ticket) in tickets.into_iter().enumerate() {
+                        if i > 0 {
+                            spans.push(Span::styled(", ", Style::default()));
+                        }
+                        spans.push(Span::styled(
+                            ticket,
+                            Style::default()
+                                .fg(theme.text_colors.ticket)
+                                .add_modifier(theme.text_modifiers.ticket)
+                                .underlined(),
+                        ));
+                    }
+                    text.push(Line::from(spans));
+                }
+            }
+            InfoField::Recurs => {
+                if let Some(recurrence) = self.recurrence {
+                    text.push(Line::from(vec![
+                        Span::styled("Recurs: ", Style::default()),
+                        Span::styled(
+                            recurrence.to_string(),
+                            Style::default()
+                                .fg(theme.text_colors.recurrence)
+                                .add_modifier(theme.text_modifiers.recurrence),
+                        ),
+                    ]));
+                }
+            }
+            InfoField::Criteria => {
+                if !self.criteria.is_empty() {
+                    let ticked = self.criteria.len() - self.criteria_left();
+                    text.push(Line::from(vec![Span::styled("", Style::default())]));
+                    text.push(Line::from(vec![Span::styled(
+                        format!("Done when ({}/{}):", ticked, self.criteria.len()),
+                        Style::default().underlined(),
+                    )]));
+                    for criterion in &self.criteria {
+                        text.push(Line::from(criterion_spans(criterion, theme)));
+                    }
+                }
+            }
+            InfoField::Latest => {
+                text.extend(vec![
+                    Line::from(vec![Span::styled("", Style::default())]),
+                    Line::from(vec![Span::styled("Latest:", Style::default().underlined())]),
+                    Line::from(vec![Span::styled(
+                        self.latest.clone().unwrap_or("".to_string()),
+                        Style::default()
+                            .fg(theme.text_colors.latest)
+                            .add_modifier(theme.text_modifiers.latest),
+                    )]),
+                ]);
+            }
+            InfoField::Description => {
+                text.extend(vec![
+                    Line::from(vec![Span::styled("", Style::default())]),
+                    Line::from(vec![Span::styled(
+                        "Description:",
+                        Style::default().underlined(),
+                    )]),
+                    Line::from(vec![Span::styled(
+                        self.description.clone().unwrap_or("".to_string()),
+                        Style::default()
+                            .fg(theme.text_colors.description)
+                            .add_modifier(theme.text_modifiers.description),
+                    )]),
+                ]);
+            }
+        }
         text
     }
 
     /// Returns a `Paragraph` of the `Task`. This is what is displayed
     /// in the `Task Info` block in the app
-    pub fn to_paragraph(&self, theme: &Theme) -> Paragraph {
-        let text = self.to_text_vec(theme);
+    pub fn to_paragraph(&self, theme: &Theme, rolled_up: Option<&RolledUp>) -> Paragraph<'_> {
+        let text = self.to_text_vec(theme, rolled_up);
 
         Paragraph::new(text)
     }
 }
 
 const fn alternate_colors(i: usize, normal_color: Color, alternate_color: Color) -> Color {
-    if i % 2 == 0 {
+    if i.is_multiple_of(2) {
         normal_color
     } else {
         alternate_color
@@ -308,28 +742,28 @@ fn map_string_to_lines(
     let mut hash_lines: BTreeMap<usize, Vec<String>> = BTreeMap::from([(0, vec![])]);
     let mut latest_quotient = 0;
 
-    for character in string.chars() {
-        if character == ' ' {
+    for character in string.graphemes(true) {
+        if character == " " {
             current_line_words.push(String::from(" "));
             word = String::new();
         } else {
-            word.push(character);
-            if word.len() > 1 {
+            if !word.is_empty() {
                 current_line_words.pop(); // replace last word
             }
+            word.push_str(character);
             current_line_words.push(word.clone());
         }
         hash_lines.insert(latest_quotient, current_line_words.clone());
 
         let total_chars: usize = hash_lines
-            .iter()
-            .map(|(_, v)| {
+            .values()
+            .map(|v| {
                 v.iter()
                     .map(|x| {
                         if x == "OVER FLOW" {
                             return 1;
                         }
-                        x.chars().count()
+                        width(x)
                     })
                     .sum::<usize>()
             })
@@ -338,7 +772,7 @@ fn map_string_to_lines(
         let new_character_quotient = total_chars / width_of_space as usize;
 
         if !quotients_seen.contains(&new_character_quotient) {
-            if character == ' ' {
+            if character == " " {
                 // space gets "absorbed" in the box, so can use a blank vec
                 current_line_words = vec![];
             } else {
@@ -346,7 +780,7 @@ fn map_string_to_lines(
                 // pop off last line
                 let latest_word = current_line_words.pop().unwrap();
                 // add number of spaces based on length of word remaining
-                let overflow_offset = latest_word.chars().count();
+                let overflow_offset = width(&latest_word);
                 for _ in 0..overflow_offset {
                     current_line_words.push(String::from("OVER FLOW"));
                 }
@@ -387,10 +821,10 @@ fn text_cursor_logic(
 
     let text_width = text_end_x - text_start_x;
 
-    let (strings_on_lines, _) = map_string_to_lines(current_string, text_width);
+    let (strings_on_lines, _) = map_string_to_lines(current_string.clone(), text_width);
 
-    // Cursor logic - adjustment
-    let mut x = app.character_index;
+    // Cursor logic - adjustment, in columns since wide characters take up two
+    let mut x = width(first_graphemes(&current_string, app.character_index));
     let mut row = 0;
 
     if app.character_index > 0 {
@@ -401,7 +835,7 @@ fn text_cursor_logic(
                     if x == "OVER FLOW" {
                         return 0;
                     }
-                    x.chars().count()
+                    width(x)
                 })
                 .sum();
             row = *k;
@@ -434,6 +868,16 @@ fn style_block(
     block
 }
 
+/// Returns the color to outline the pane with, which is the focused one
+/// when `j` and `k` move through it
+fn pane_outline(app: &App, pane: Focus, outline_color: Color) -> Color {
+    if app.focus == pane {
+        app.theme.theme_colors.focused_outline
+    } else {
+        outline_color
+    }
+}
+
 fn style_two_halves_block(
     title: String,
     title_alignment: Alignment,
@@ -480,11 +924,15 @@ pub fn render_state(f: &mut Frame, app: &mut App, rectangle: Rect) {
     let urgency_sort_string = match app.config.urgency_sort_desc {
         true => Span::styled(
             "descending".to_string(),
-            Style::default().fg(app.theme.text_colors.urgency_descending),
+            Style::default()
+                .fg(app.theme.text_colors.urgency_descending)
+                .add_modifier(app.theme.text_modifiers.urgency_descending),
         ),
         false => Span::styled(
             "ascending".to_string(),
-            Style::default().fg(app.theme.text_colors.urgency_ascending),
+            Style::default()
+                .fg(app.theme.text_colors.urgency_ascending)
+                .add_modifier(app.theme.text_modifiers.urgency_ascending),
         ),
     };
 
@@ -493,10 +941,10 @@ pub fn render_state(f: &mut Frame, app: &mut App, rectangle: Rect) {
         "State".to_string(),
         Alignment::Left,
         app.theme.theme_colors.state_box_bg,
-        app.theme.theme_colors.state_box_outline,
+        pane_outline(app, Focus::State, app.theme.theme_colors.state_box_outline),
     );
 
-    if app.enter_tags_filter {
+    if app.enter_tags_filter || app.enter_search || app.enter_query {
         state_block = state_block
             .border_style(
                 Style::new().fg(app.theme.theme_colors.state_box_outline_during_tags_edit),
@@ -504,31 +952,109 @@ pub fn render_state(f: &mut Frame, app: &mut App, rectangle: Rect) {
             .border_type(BorderType::Rounded);
     }
 
-    let state_vec_lines = vec![
+    let mut state_vec_lines = vec![
         Line::from("Filters:".underlined()),
         Line::from(vec![
             Span::styled("Status: ", Style::default()),
             app.config.display_filter.to_colored_span(&app.theme),
         ]),
         Line::from(vec![
-            Span::styled("Tag: ", Style::default()),
+            Span::styled(
+                format!("Tag ({}): ", app.config.tag_match),
+                Style::default(),
+            ),
             Span::styled(
                 app.tags_filter_value.clone(),
-                Style::default().fg(app.theme.text_colors.tags),
+                Style::default()
+                    .fg(app.theme.text_colors.tags)
+                    .add_modifier(app.theme.text_modifiers.tags),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Project: ", Style::default()),
+            Span::styled(
+                app.project_filter.clone().unwrap_or_default(),
+                Style::default()
+                    .fg(app.theme.text_colors.project)
+                    .add_modifier(app.theme.text_modifiers.project),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("Search: ", Style::default()),
+            Span::styled(app.search_value.clone(), Style::default()),
+        ]),
+        Line::from(vec![
+            Span::styled("Query: ", Style::default()),
+            Span::styled(app.query_value.clone(), Style::default()),
+        ]),
+        Line::from(
+            app.query_error
+                .clone()
+                .unwrap_or_default()
+                .fg(ratatui::style::Color::Red),
+        ),
         Line::from(""),
         Line::from("Sorts:".underlined()),
         Line::from(vec![
-            Span::styled("Urgency: ", Style::default()),
+            Span::styled("By: ", Style::default()),
+            Span::styled(app.config.sort_by.to_string(), Style::default()),
+            Span::styled(" (s to cycle)", Style::default().dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("Direction: ", Style::default()),
             urgency_sort_string,
+            Span::styled(" (S to flip)", Style::default().dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("Order: ", Style::default()),
+            if app.freeze_order {
+                Span::styled(
+                    "frozen",
+                    Style::default()
+                        .fg(app.theme.text_colors.urgency_ascending)
+                        .add_modifier(app.theme.text_modifiers.urgency_ascending),
+                )
+            } else {
+                Span::styled("live", Style::default())
+            },
+            Span::styled(" (o to toggle)", Style::default().dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("Group: ", Style::default()),
+            Span::styled(app.group_by.to_string(), Style::default()),
+            Span::styled(" (b to cycle)", Style::default().dim()),
+        ]),
+        Line::from(""),
+        Line::from("Hidden:".underlined()),
+        Line::from(vec![
+            Span::styled("Archived: ", Style::default()),
+            Span::styled(app.archived_count.to_string(), Style::default()),
+            Span::styled(" (CTRL a to view)", Style::default().dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("Snoozed: ", Style::default()),
+            Span::styled(app.snoozed_count.to_string(), Style::default()),
+            Span::styled(" (f to show All)", Style::default().dim()),
         ]),
     ];
+    if app.config.urgent_limit.is_some() {
+        state_vec_lines.push(Line::from(vec![
+            Span::styled("Urgent: ", Style::default()),
+            Span::styled(app.hidden_urgent.to_string(), Style::default()),
+            Span::styled(" (U to show all)", Style::default().dim()),
+        ]));
+    }
 
+    // Scrolled when it has focus, but never past the last line
+    app.scroll_info.state_scroll = app
+        .scroll_info
+        .state_scroll
+        .min(state_vec_lines.len().saturating_sub(1));
     let state_text = Text::from(state_vec_lines);
     let state_paragraph = Paragraph::new(state_text)
         .block(state_block)
-        .wrap(Wrap { trim: false });
+        .wrap(Wrap { trim: false })
+        .scroll((app.scroll_info.state_scroll as u16, 0));
 
     f.render_widget(state_paragraph, rectangle);
 }
@@ -556,78 +1082,172 @@ pub fn render_help(f: &mut Frame, app: &mut App, rectangle: Rect) {
         Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(vertical_chunks[1]);
 
-    let action_color = app.theme.text_colors.help_actions;
-    let quick_action_color = app.theme.text_colors.help_quick_actions;
-    let movement_color = app.theme.text_colors.help_movement;
-
-    let mappings = vec![
+    let action_style = Style::default()
+        .fg(app.theme.text_colors.help_actions)
+        .add_modifier(app.theme.text_modifiers.help_actions);
+    let quick_action_style = Style::default()
+        .fg(app.theme.text_colors.help_quick_actions)
+        .add_modifier(app.theme.text_modifiers.help_quick_actions);
+    let movement_style = Style::default()
+        .fg(app.theme.text_colors.help_movement)
+        .add_modifier(app.theme.text_modifiers.help_movement);
+
+    let mut mappings = vec![
         (
             vec![
-                Span::styled(
-                    "Actions:".to_string(),
-                    Style::default().underlined().fg(action_color),
-                ),
+                Span::styled("Actions:".to_string(), action_style.underlined()),
                 "         ".into(),
             ],
             "".into(),
         ),
         (
             vec!["a                ".into(), "".into()],
-            Span::styled("Add".to_string(), Style::default().fg(action_color)),
+            Span::styled("Add".to_string(), action_style),
         ),
         (
             vec!["u                ".into(), "".into()],
-            Span::styled("Update".to_string(), Style::default().fg(action_color)),
+            Span::styled("Update".to_string(), action_style),
         ),
         (
             vec!["d                ".into(), "".into()],
-            Span::styled("Delete".to_string(), Style::default().fg(action_color)),
+            Span::styled("Delete".to_string(), action_style),
         ),
         (
             vec!["x".into(), " or ".cyan(), "ESC         ".into(), "".into()],
-            Span::styled("Exit".to_string(), Style::default().fg(action_color)),
+            Span::styled("Exit".to_string(), action_style),
         ),
         (
             vec!["v                ".into(), "".into()],
-            Span::styled(
-                "Change layout view".to_string(),
-                Style::default().fg(action_color),
-            ),
+            Span::styled("Change layout view".to_string(), action_style),
+        ),
+        (
+            vec!["P                ".into(), "".into()],
+            Span::styled("Show/hide today's agenda".to_string(), action_style),
+        ),
+        (
+            vec!["r                ".into(), "".into()],
+            Span::styled("Refresh from database".to_string(), action_style),
+        ),
+        (
+            vec!["m                ".into(), "".into()],
+            Span::styled("Hand off selected task by email".to_string(), action_style),
+        ),
+        (
+            vec!["l                ".into(), "".into()],
+            Span::styled("Open tickets of selected task".to_string(), action_style),
         ),
         (
             vec!["f                ".into(), "".into()],
-            Span::styled(
-                "Filter on Status".to_string(),
-                Style::default().fg(action_color),
-            ),
+            Span::styled("Filter on Status".to_string(), action_style),
         ),
         (
             vec!["/ <TEXT>         ".into(), "".into()],
-            Span::styled(
-                "Filter task on Tag".to_string(),
-                Style::default().fg(action_color),
-            ),
+            Span::styled("Filter task on Tag".to_string(), action_style),
         ),
         (
             vec!["/ ENTER          ".into(), "".into()],
-            Span::styled(
-                "Remove Tag filter".to_string(),
-                Style::default().fg(action_color),
-            ),
+            Span::styled("Remove Tag filter".to_string(), action_style),
+        ),
+        (
+            vec!["t                ".into(), "".into()],
+            Span::styled("Start/stop timer".to_string(), action_style),
+        ),
+        (
+            vec!["p                ".into(), "".into()],
+            Span::styled("Cycle Project filter".to_string(), action_style),
+        ),
+        (
+            vec!["CTRL f           ".into(), "".into()],
+            Span::styled("Search task text".to_string(), action_style),
+        ),
+        (
+            vec!["A                ".into(), "".into()],
+            Span::styled("Archive if Completed".to_string(), action_style),
+        ),
+        (
+            vec!["L                ".into(), "".into()],
+            Span::styled("Lock/unlock task".to_string(), action_style),
+        ),
+        (
+            vec!["CTRL a           ".into(), "".into()],
+            Span::styled("Toggle Archive view".to_string(), action_style),
+        ),
+        (
+            vec!["z                ".into(), "".into()],
+            Span::styled("Undo".to_string(), action_style),
+        ),
+        (
+            vec!["Z                ".into(), "".into()],
+            Span::styled("Redo".to_string(), action_style),
+        ),
+        (
+            vec!["F <QUERY>        ".into(), "".into()],
+            Span::styled("Filter on a query".to_string(), action_style),
+        ),
+        (
+            vec!["c                ".into(), "".into()],
+            Span::styled("Clear filters and sort".to_string(), action_style),
         ),
         (
             vec!["s                ".into(), "".into()],
-            Span::styled(
-                "Sort on Urgency".to_string(),
-                Style::default().fg(action_color),
-            ),
+            Span::styled("Cycle sort field".to_string(), action_style),
+        ),
+        (
+            vec!["S                ".into(), "".into()],
+            Span::styled("Flip sort direction".to_string(), action_style),
+        ),
+        (
+            vec!["b                ".into(), "".into()],
+            Span::styled("Cycle grouping".to_string(), action_style),
         ),
+        (
+            vec!["SPACE            ".into(), "".into()],
+            Span::styled("Collapse/expand group".to_string(), action_style),
+        ),
+        (
+            vec!["o                ".into(), "".into()],
+            Span::styled("Freeze/unfreeze order".to_string(), action_style),
+        ),
+        (
+            vec!["1/2/3            ".into(), "".into()],
+            Span::styled("Snooze reminder 10m/1h/tomorrow".to_string(), action_style),
+        ),
+    ];
+    // Then whatever features have registered
+    for action in app.registry.actions() {
+        mappings.push((
+            vec![format!("{:<17}", action.key.label()).into(), "".into()],
+            Span::styled(action.description.to_string(), action_style),
+        ));
+    }
+    // And the keys the keymap adds on top of them all
+    let keymap_keys = app.config.keymap.help();
+    if !keymap_keys.is_empty() {
+        mappings.push((vec!["".into(), "".into()], "".into()));
+        mappings.push((
+            vec![
+                Span::styled(
+                    format!("{:?} Keymap:", app.config.keymap),
+                    action_style.underlined(),
+                ),
+                "         ".into(),
+            ],
+            "".into(),
+        ));
+        for (keys, description) in keymap_keys {
+            mappings.push((
+                vec![format!("{:<17}", keys).into(), "".into()],
+                Span::styled(description.to_string(), action_style),
+            ));
+        }
+    }
+    mappings.extend(vec![
         (vec!["".into(), "".into()], "".into()),
         (
             vec![
                 Span::styled(
                     "Quick Actions:".to_string(),
-                    Style::default().underlined().fg(quick_action_color),
+                    quick_action_style.underlined(),
                 ),
                 "         ".into(),
             ],
@@ -635,93 +1255,98 @@ pub fn render_help(f: &mut Frame, app: &mut App, rectangle: Rect) {
         ),
         (
             vec!["qa               ".into(), "".into()],
-            Span::styled(
-                "Quick Add".to_string(),
-                Style::default().fg(quick_action_color),
-            ),
+            Span::styled("Quick Add".to_string(), quick_action_style),
         ),
         (
             vec!["qc               ".into(), "".into()],
-            Span::styled(
-                "Quick Complete".to_string(),
-                Style::default().fg(quick_action_color),
-            ),
+            Span::styled("Quick Complete".to_string(), quick_action_style),
+        ),
+        (
+            vec!["qs               ".into(), "".into()],
+            Span::styled("Quick Snooze".to_string(), quick_action_style),
+        ),
+        (
+            vec!["qt               ".into(), "".into()],
+            Span::styled("Quick Time Slot (today)".to_string(), quick_action_style),
         ),
         (
             vec!["dd               ".into(), "".into()],
-            Span::styled(
-                "Quick Delete".to_string(),
-                Style::default().fg(quick_action_color),
-            ),
+            Span::styled("Quick Delete".to_string(), quick_action_style),
+        ),
+        (
+            vec!["yy               ".into(), "".into()],
+            Span::styled("Quick Clone".to_string(), quick_action_style),
+        ),
+        (
+            vec!["yo               ".into(), "".into()],
+            Span::styled("Quick Clone (as Open)".to_string(), quick_action_style),
+        ),
+        (
+            vec!["yc               ".into(), "".into()],
+            Span::styled("Copy task as text".to_string(), quick_action_style),
         ),
         (vec!["".into(), "".into()], "".into()),
         (
             vec![
-                Span::styled(
-                    "Move/Adjustment:".to_string(),
-                    Style::default().underlined().fg(movement_color),
-                ),
+                Span::styled("Move/Adjustment:".to_string(), movement_style.underlined()),
                 "         ".into(),
             ],
             "".into(),
         ),
+        (
+            vec!["TAB".into(), " or ".cyan(), "SHIFT TAB ".into(), "".into()],
+            Span::styled("Focus next/previous pane".to_string(), movement_style),
+        ),
         (
             vec!["↑".into(), " or ".cyan(), "k           ".into(), "".into()],
             Span::styled(
-                "Move up task".to_string(),
-                Style::default().fg(movement_color),
+                "Move up task (or scroll focused pane)".to_string(),
+                movement_style,
             ),
         ),
         (
             vec!["↓".into(), " or ".cyan(), "j           ".into(), "".into()],
             Span::styled(
-                "Move down task".to_string(),
-                Style::default().fg(movement_color),
+                "Move down task (or scroll focused pane)".to_string(),
+                movement_style,
             ),
         ),
         (
             vec!["HOME".into(), " or ".cyan(), "g        ".into(), "".into()],
-            Span::styled(
-                "Move to first task".to_string(),
-                Style::default().fg(movement_color),
-            ),
+            Span::styled("Move to first task".to_string(), movement_style),
         ),
         (
             vec!["END".into(), " or ".cyan(), "G         ".into(), "".into()],
-            Span::styled(
-                "Move to last task".to_string(),
-                Style::default().fg(movement_color),
-            ),
+            Span::styled("Move to last task".to_string(), movement_style),
+        ),
+        (
+            vec!["[".into(), " or ".cyan(), "]           ".into(), "".into()],
+            Span::styled("Move between Kanban columns".to_string(), movement_style),
+        ),
+        (
+            vec!["←".into(), " or ".cyan(), "→           ".into(), "".into()],
+            Span::styled("Move task to Kanban column".to_string(), movement_style),
         ),
         (
             vec!["CTRL ←           ".into(), "".into()],
-            Span::styled(
-                "Adjust Task Info pane (bigger)".to_string(),
-                Style::default().fg(movement_color),
-            ),
+            Span::styled("Adjust Task Info pane (bigger)".to_string(), movement_style),
         ),
         (
             vec!["CTRL →           ".into(), "".into()],
             Span::styled(
                 "Adjust Task Info pane (smaller)".to_string(),
-                Style::default().fg(movement_color),
+                movement_style,
             ),
         ),
         (
             vec!["CTRL ↑".into(), " or ".cyan(), "k      ".into(), "".into()],
-            Span::styled(
-                "Scroll Task Info up".to_string(),
-                Style::default().fg(movement_color),
-            ),
+            Span::styled("Scroll Task Info up".to_string(), movement_style),
         ),
         (
             vec!["CTRL ↓".into(), " or ".cyan(), "j      ".into(), "".into()],
-            Span::styled(
-                "Scroll Task Info down".to_string(),
-                Style::default().fg(movement_color),
-            ),
+            Span::styled("Scroll Task Info down".to_string(), movement_style),
         ),
-    ];
+    ]);
     let help_vec_lines_len = mappings.len();
 
     let mut titles = vec![];
@@ -771,45 +1396,320 @@ pub fn render_help(f: &mut Frame, app: &mut App, rectangle: Rect) {
     );
 }
 
-/// Renders the `Task` block in the TUI
-pub fn render_tasks(f: &mut Frame, app: &mut App, rectangle: Rect) {
-    // Now render our tasks
-    let list_block = style_block(
-        "Tasks".to_string(),
-        Alignment::Left,
-        app.theme.theme_colors.tasks_box_bg,
-        app.theme.theme_colors.tasks_box_outline,
-    );
-
-    // Iterate through all elements in the `items` and stylize them.
-    let items: Vec<ListItem> = app
-        .tasklist
-        .tasks
-        .iter()
-        .enumerate()
-        .map(|(i, task_item)| {
-            let color = alternate_colors(
-                i,
-                app.theme.theme_colors.normal_row_bg,
-                app.theme.theme_colors.alt_row_bg,
-            );
-            let list_item = task_item.to_listitem(&app.theme);
-            list_item.bg(color)
-        })
-        .collect();
-
-    // Create a List from all list items and highlight the currently selected one
-    let list = List::new(items)
-        .block(list_block)
-        .highlight_style(
-            Style::new()
-                .bg(app.theme.theme_colors.selected_style)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(&app.theme.theme_styles.highlight_symbol)
-        .highlight_spacing(HighlightSpacing::Always);
+/// Returns a compact line summing up the filters and sort in use,
+/// and how many of the tasks are shown because of them
+fn filter_breadcrumb(app: &App) -> String {
+    let mut crumbs = vec![if app.show_archive {
+        String::from("Archive")
+    } else {
+        app.config.display_filter.to_string()
+    }];
+    if !app.tags_filter_value.is_empty() {
+        let tag_match = match app.config.tag_match {
+            TagMatch::Any => "",
+            TagMatch::All => " (all)",
+        };
+        crumbs.push(format!("tag:{}{}", app.tags_filter_value, tag_match));
+    }
+    if let Some(project) = &app.project_filter {
+        crumbs.push(format!("project:{}", project));
+    }
+    if !app.search_value.is_empty() {
+        crumbs.push(format!("search:\"{}\"", app.search_value));
+    }
+    if app.query_filter.is_some() {
+        crumbs.push(format!("query:{}", app.query_value));
+    }
+    let arrow = if app.config.urgency_sort_desc {
+        "↓"
+    } else {
+        "↑"
+    };
+    let frozen = if app.freeze_order { " (frozen)" } else { "" };
+    crumbs.push(format!(
+        "sort:{} {}{}",
+        app.config.sort_by.to_string().to_lowercase(),
+        arrow,
+        frozen
+    ));
+    if app.is_grouped() {
+        crumbs.push(format!("group:{}", app.group_by.to_string().to_lowercase()));
+    }
+    crumbs.join(" · ")
+}
 
-    f.render_stateful_widget(list, rectangle, &mut app.tasklist.state);
+/// Returns how many tasks are showing out of all of them, followed by the
+/// filters narrowing them down if there are any, for the title of the `Tasks`
+/// block, i.e. " (12/87) — filter: tag=work, status=NotCompleted"
+fn tasks_title_summary(app: &App) -> String {
+    let mut filters = vec![];
+    if !app.tags_filter_value.is_empty() {
+        filters.push(format!("tag={}", app.tags_filter_value));
+    }
+    if let Some(project) = &app.project_filter {
+        filters.push(format!("project={}", project));
+    }
+    // Everything archived is completed, so the status filter isn't used there
+    if !app.show_archive && app.config.display_filter != Display::All {
+        filters.push(format!("status={}", app.config.display_filter));
+    }
+    if !app.search_value.is_empty() {
+        filters.push(format!("search=\"{}\"", app.search_value));
+    }
+    if app.query_filter.is_some() {
+        filters.push(format!("query={}", app.query_value));
+    }
+
+    let counts = format!(" ({}/{})", app.page.total, app.total_count);
+    if filters.is_empty() {
+        counts
+    } else {
+        format!("{} — filter: {}", counts, filters.join(", "))
+    }
+}
+
+/// Returns a dim marker with how many subtasks a `Task` has, or nothing if
+/// it has none
+fn span_subtasks(subtasks: usize) -> Option<Span<'static>> {
+    if subtasks == 0 {
+        return None;
+    }
+    Some(Span::styled(
+        format!(" ▸ {}", subtasks),
+        Style::default().dim(),
+    ))
+}
+
+/// Returns a box, ticked or not, followed by the text of the `Criterion`
+fn criterion_spans(criterion: &Criterion, theme: &Theme) -> Vec<Span<'static>> {
+    let (tick, style) = if criterion.done {
+        (
+            "[x] ",
+            Style::default()
+                .fg(theme.text_colors.status_completed)
+                .add_modifier(theme.text_modifiers.status_completed),
+        )
+    } else {
+        (
+            "[ ] ",
+            Style::default()
+                .fg(theme.text_colors.status_open)
+                .add_modifier(theme.text_modifiers.status_open),
+        )
+    };
+    vec![
+        Span::styled(tick, style),
+        Span::styled(criterion.text.clone(), Style::default()),
+    ]
+}
+
+/// Formats minutes as hours and minutes, i.e. "1h 30m"
+fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Returns a dim summary of what a parent `Task` rolls up from its open
+/// subtasks, i.e. " (High · due 2025-04-01 · 1h 30m)", or nothing if there isn't any
+fn span_rolled_up(rolled_up: Option<&RolledUp>, theme: &Theme) -> Option<Span<'static>> {
+    let rolled_up = rolled_up?;
+    let mut parts = vec![];
+    if let Some(urgency) = rolled_up.urgency {
+        parts.push(urgency.to_string());
+    }
+    if let Some(due) = rolled_up.due {
+        parts.push(format!("due {}", format_date(due, theme)));
+    }
+    if let Some(estimate) = rolled_up.estimate {
+        parts.push(format_minutes(estimate));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(Span::styled(
+        format!(" ({})", parts.join(" · ")),
+        Style::default().dim(),
+    ))
+}
+
+/// Returns the lines of the `Task Info` block with what a parent `Task` rolls
+/// up from its open subtasks
+fn rolled_up_lines(rolled_up: &RolledUp, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    if let Some(urgency) = rolled_up.urgency {
+        lines.push(Line::from(vec![
+            Span::styled("Subtask urgency: ", Style::default()),
+            urgency.to_colored_span(theme),
+        ]));
+    }
+    if let Some(due) = rolled_up.due {
+        let style = if due < today() {
+            Style::default()
+                .fg(theme.text_colors.overdue)
+                .add_modifier(theme.text_modifiers.overdue)
+        } else {
+            Style::default()
+                .fg(theme.text_colors.due_date)
+                .add_modifier(theme.text_modifiers.due_date)
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Subtasks due: ", Style::default()),
+            Span::styled(format_date(due, theme), style),
+        ]));
+    }
+    if let Some(estimate) = rolled_up.estimate {
+        lines.push(Line::from(vec![
+            Span::styled("Subtask estimate: ", Style::default()),
+            Span::styled(
+                format_minutes(estimate),
+                Style::default()
+                    .fg(theme.text_colors.scheduled)
+                    .add_modifier(theme.text_modifiers.scheduled),
+            ),
+        ]));
+    }
+    lines
+}
+
+/// Renders the `Task` block in the TUI
+pub fn render_tasks(f: &mut Frame, app: &mut App, rectangle: Rect) {
+    // Now render our tasks
+    let list_title = if app.show_archive {
+        String::from("Archive (read-only)")
+    } else if let Some(as_of) = &app.snapshot {
+        format!("Tasks as of {} (read-only)", as_of)
+    } else {
+        // Show the way down to the subtasks being listed
+        std::iter::once("Tasks")
+            .chain(app.drill_names.iter().map(String::as_str))
+            .collect::<Vec<&str>>()
+            .join(" › ")
+    };
+    let list_block = style_block(
+        list_title + &tasks_title_summary(app),
+        Alignment::Left,
+        app.theme.theme_colors.tasks_box_bg,
+        pane_outline(app, Focus::Tasks, app.theme.theme_colors.tasks_box_outline),
+    );
+
+    // A task may have been selected without going through the groups
+    if app.is_grouped() {
+        app.sync_group_selection();
+    }
+
+    // The breadcrumb sits on the first line inside the block, above the list,
+    // and a count of what the filters hide on the last line, below it
+    let hidden = app
+        .total_count
+        .saturating_sub(app.page.total + app.collapsed_count());
+    let footer_height = if hidden > 0 { 1 } else { 0 };
+    let inner = list_block.inner(rectangle);
+    f.render_widget(list_block, rectangle);
+    let chunks = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(footer_height),
+    ])
+    .split(inner);
+    let breadcrumb = Paragraph::new(filter_breadcrumb(app)).style(Style::new().dim());
+    f.render_widget(breadcrumb, chunks[0]);
+    if hidden > 0 {
+        let plural = if hidden == 1 { "task" } else { "tasks" };
+        let footer = Paragraph::new(format!(
+            "… {} {} hidden by filters (press c to clear)",
+            hidden, plural
+        ))
+        .style(Style::new().dim());
+        f.render_widget(footer, chunks[2]);
+    }
+
+    // Only the rows on screen are built, so long lists don't slow every frame
+    // down. Ungrouped, the rows count over the whole list, not the part read in.
+    let grouped = app.is_grouped();
+    let (total, selected, offset) = if grouped {
+        (
+            app.group_rows.len(),
+            app.group_state.selected(),
+            app.group_state.offset(),
+        )
+    } else {
+        (app.page.total, app.selected_index(), app.page.offset)
+    };
+    app.list_height = chunks[1].height as usize;
+    let window = visible_rows(total, app.list_height, selected, offset);
+
+    // Iterate through the `items` on screen and stylize them.
+    let task_item = |i: usize| {
+        let color = alternate_colors(
+            i,
+            app.theme.theme_colors.normal_row_bg,
+            app.theme.theme_colors.alt_row_bg,
+        );
+        let task = &app.tasklist.tasks[i];
+        let subtasks = app.subtask_counts.get(&task.get_id()).copied().unwrap_or(0);
+        let rolled_up = app.rolled_up.get(&task.get_id());
+        let row_style = app.theme.conditional_formatting.row_style(task);
+        task.to_listitem(&app.theme, subtasks, rolled_up)
+            .style(Style::new().bg(color).patch(row_style))
+    };
+    let items: Vec<ListItem> = if grouped {
+        app.group_rows[window.clone()]
+            .iter()
+            .map(|row| match row {
+                GroupRow::Header {
+                    name,
+                    count,
+                    collapsed,
+                } => {
+                    let arrow = if *collapsed { "▸" } else { "▾" };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} {}", arrow, name), Style::new().bold()),
+                        Span::styled(format!(" ({})", count), Style::new().dim()),
+                    ]))
+                }
+                GroupRow::Task(i) => task_item(*i),
+            })
+            .collect()
+    } else {
+        // A row not read in yet is left blank, until the next frame reads it in
+        let loaded = app.page.rows(app.tasklist.len());
+        window
+            .clone()
+            .map(|i| {
+                if loaded.contains(&i) {
+                    task_item(i - loaded.start)
+                } else {
+                    ListItem::new("")
+                }
+            })
+            .collect()
+    };
+
+    // Create a List from the list items and highlight the currently selected one
+    let list = List::new(items)
+        .highlight_style(
+            Style::new()
+                .bg(app.theme.theme_colors.selected_style)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(&app.theme.theme_styles.highlight_symbol)
+        .highlight_spacing(HighlightSpacing::Always);
+    let mut window_state = ListState::default().with_selected(
+        selected
+            .filter(|selected| window.contains(selected))
+            .map(|selected| selected - window.start),
+    );
+    f.render_stateful_widget(list, chunks[1], &mut window_state);
+
+    // Remember where the list is scrolled to, for the next frame
+    if grouped {
+        *app.group_state.offset_mut() = window.start;
+    } else {
+        app.page.offset = window.start;
+    }
 
     let list_scrollbar = style_scrollbar(
         ScrollbarOrientation::VerticalRight,
@@ -821,10 +1721,14 @@ pub fn render_tasks(f: &mut Frame, app: &mut App, rectangle: Rect) {
     );
 
     //Now the scrollbar
-    app.scroll_info.list_scroll_state = app
-        .scroll_info
-        .list_scroll_state
-        .content_length(app.tasklist.len());
+    app.scroll_info.list_scroll_state =
+        app.scroll_info
+            .list_scroll_state
+            .content_length(if app.is_grouped() {
+                app.group_rows.len()
+            } else {
+                app.page.total
+            });
 
     f.render_stateful_widget(
         list_scrollbar,
@@ -836,24 +1740,137 @@ pub fn render_tasks(f: &mut Frame, app: &mut App, rectangle: Rect) {
     );
 }
 
-/// Renders the `Task Info` block in the TUI
-pub fn render_task_info(f: &mut Frame, app: &mut App, rectangle: Rect) {
-    let info = if let Some(i) = app.tasklist.state.selected() {
-        app.tasklist.tasks[i].to_paragraph(&app.theme)
+/// Renders the `Task`s as a Kanban board, with a column for each `Status`
+pub fn render_kanban(f: &mut Frame, app: &mut App, rectangle: Rect) {
+    let board_title = if app.show_archive {
+        String::from("Archive (read-only)")
+    } else if let Some(as_of) = &app.snapshot {
+        format!("Board as of {} (read-only)", as_of)
+    } else {
+        String::from("Board")
+    };
+    let board_block = style_block(
+        board_title,
+        Alignment::Left,
+        app.theme.theme_colors.tasks_box_bg,
+        pane_outline(app, Focus::Tasks, app.theme.theme_colors.tasks_box_outline),
+    );
+    let inner = board_block.inner(rectangle);
+    f.render_widget(board_block, rectangle);
+
+    let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(inner);
+    let breadcrumb = Paragraph::new(filter_breadcrumb(app)).style(Style::new().dim());
+    f.render_widget(breadcrumb, chunks[0]);
+
+    let column_areas = Layout::horizontal([Constraint::Ratio(1, 4); 4]).split(chunks[1]);
+    let selected = app.tasklist.state.selected();
+    for ((status, tasks), area) in KANBAN_COLUMNS
+        .iter()
+        .zip(app.kanban_columns())
+        .zip(column_areas.iter())
+    {
+        let column_block = Block::bordered()
+            .title(Line::from(vec![
+                status.to_colored_span(&app.theme),
+                Span::styled(format!(" ({})", tasks.len()), Style::new().dim()),
+            ]))
+            .border_style(Style::new().fg(app.theme.theme_colors.tasks_box_outline));
+        let cards: Vec<ListItem> = tasks
+            .iter()
+            .map(|i| app.tasklist.tasks[*i].to_card(&app.theme))
+            .collect();
+        let mut state =
+            ListState::default().with_selected(tasks.iter().position(|i| Some(*i) == selected));
+        let column = List::new(cards).block(column_block).highlight_style(
+            Style::new()
+                .bg(app.theme.theme_colors.selected_style)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_stateful_widget(column, *area, &mut state);
+    }
+}
+
+/// Renders today's agenda: the `Task`s given a time slot today, in order.
+/// Slots that are over are dimmed and the one going on now is bold.
+pub fn render_agenda(f: &mut Frame, app: &mut App, rectangle: Rect) {
+    let block = style_block(
+        String::from("Today"),
+        Alignment::Left,
+        app.theme.theme_colors.tasks_box_bg,
+        app.theme.theme_colors.tasks_box_outline,
+    );
+
+    let now = Local::now();
+    let planned = agenda(&app.agenda_tasks, today());
+    let lines: Vec<Line> = if planned.is_empty() {
+        vec![
+            Line::from("Nothing planned yet"),
+            Line::from(""),
+            Line::from("qt gives the selected task a time slot").style(Style::new().dim()),
+        ]
     } else {
-        Paragraph::new("Nothing selected...")
+        planned
+            .into_iter()
+            .filter_map(|task| {
+                let (start, end) = task.time_slot()?;
+                let mut style = Style::default();
+                if end <= now || task.status == Status::Completed {
+                    style = style.dim();
+                } else if start <= now {
+                    style = style.bold();
+                }
+                Some(Line::from(vec![
+                    Span::styled(
+                        format!("{}–{} ", start.format("%H:%M"), end.format("%H:%M")),
+                        Style::default()
+                            .fg(app.theme.text_colors.scheduled)
+                            .add_modifier(app.theme.text_modifiers.scheduled),
+                    ),
+                    Span::styled(task.name.clone(), style),
+                ]))
+            })
+            .collect()
     };
 
-    let selected_task_len = match app.tasklist.state.selected() {
-        Some(task) => app.tasklist.tasks[task].to_text_vec(&app.theme).len(),
-        None => 0,
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, rectangle);
+}
+
+/// Renders the `Task Info` block in the TUI
+pub fn render_task_info(f: &mut Frame, app: &mut App, rectangle: Rect) {
+    let (info, selected_task_len) = match app.tasklist.state.selected() {
+        Some(_) if app.show_history => {
+            let lines = history_lines(&app.task_history);
+            let len = lines.len();
+            (Paragraph::new(lines), len)
+        }
+        Some(i) => {
+            let task = &app.tasklist.tasks[i];
+            let rolled_up = app.rolled_up.get(&task.get_id());
+            (
+                task.to_paragraph(&app.theme, rolled_up),
+                task.to_text_vec(&app.theme, rolled_up).len(),
+            )
+        }
+        None => (Paragraph::new("Nothing selected..."), 0),
     };
 
+    let title = if app.show_history {
+        "Task History"
+    } else {
+        "Task Info"
+    };
     let task_block = style_block(
-        "Task Info".to_string(),
+        title.to_string(),
         Alignment::Left,
         app.theme.theme_colors.tasks_info_box_bg,
-        app.theme.theme_colors.tasks_info_box_outline,
+        pane_outline(
+            app,
+            Focus::TaskInfo,
+            app.theme.theme_colors.tasks_info_box_outline,
+        ),
     );
 
     // We can now render the item info
@@ -889,11 +1906,73 @@ pub fn render_task_info(f: &mut Frame, app: &mut App, rectangle: Rect) {
     );
 }
 
+/// Returns a line for each change to the task, with words taken out
+/// struck through in red and words put in shown in green
+fn history_lines(events: &[TaskEvent]) -> Vec<Line<'static>> {
+    let removed = Style::default().fg(Color::Red).crossed_out();
+    let added = Style::default().fg(Color::Green);
+    let mut lines = vec![];
+    for event in events {
+        let Some(change) = FieldChange::from_event(event) else {
+            continue;
+        };
+        let mut spans: Vec<Span> = vec![event
+            .changed
+            .format("%Y-%m-%d %H:%M  ")
+            .to_string()
+            .dark_gray()];
+        match change {
+            FieldChange::Added(name) => {
+                spans.push("added as ".into());
+                spans.push(Span::styled(name, added));
+            }
+            FieldChange::Value {
+                field,
+                before,
+                after,
+            } => {
+                spans.push(format!("{}: ", field).bold());
+                spans.push(Span::styled(before, removed));
+                spans.push(" → ".into());
+                spans.push(Span::styled(after, added));
+            }
+            FieldChange::Text { field, diff } => {
+                spans.push(format!("{}:", field).bold());
+                for part in diff {
+                    spans.push(" ".into());
+                    spans.push(match part {
+                        Diff::Same(words) => words.into(),
+                        Diff::Removed(words) => Span::styled(words, removed),
+                        Diff::Added(words) => Span::styled(words, added),
+                    });
+                }
+            }
+            FieldChange::Tags {
+                added: put_on,
+                removed: taken_off,
+            } => {
+                spans.push("tags:".bold());
+                for tag in put_on {
+                    spans.push(Span::styled(format!(" +{}", tag), added));
+                }
+                for tag in taken_off {
+                    spans.push(Span::styled(format!(" -{}", tag), removed));
+                }
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No changes recorded"));
+    }
+    lines
+}
+
 /// Renders the `Status Bar` in the TUI
 pub fn render_status_bar(f: &mut Frame, app: &mut App, area: Rect) {
-    let chunks = Layout::horizontal([Constraint::Percentage(100), Constraint::Min(25)]).split(area);
-
-    let help_blurb = if app.show_help {
+    let help_blurb = if let Some(toast) = app.current_toast() {
+        Paragraph::new(Line::from(toast.bold()))
+    } else if app.show_help {
         Paragraph::new(Text::from(vec![Line::from(vec![
             "Press (".into(),
             "ESC".cyan(),
@@ -912,38 +1991,502 @@ pub fn render_status_bar(f: &mut Frame, app: &mut App, area: Rect) {
         .block(Block::new().bg(app.theme.theme_colors.status_bar))
         .alignment(Alignment::Left);
 
-    let layout_blurb = Paragraph::new(Text::from(vec![Line::from(vec![
-        "Layout View: ".into(),
-        app.layout_view.to_colored_span(&app.theme),
-    ])]));
-    let layout_contents = layout_blurb
-        .block(Block::new().bg(app.theme.theme_colors.status_bar))
-        .alignment(Alignment::Right);
+    let mut layout_spans = app.registry.status_spans(app);
+    layout_spans.push("Layout View: ".into());
+    layout_spans.push(app.layout_view.to_colored_span(&app.theme));
+    let layout_width = layout_spans.iter().map(|span| span.width()).sum::<usize>() as u16;
+    let layout_blurb = Paragraph::new(Line::from(layout_spans));
+    let layout_contents = layout_blurb
+        .block(Block::new().bg(app.theme.theme_colors.status_bar))
+        .alignment(Alignment::Right);
+
+    let chunks = Layout::horizontal([
+        Constraint::Percentage(100),
+        Constraint::Min(layout_width.max(25) + 1),
+    ])
+    .split(area);
+
+    f.render_widget(help_contents, chunks[0]);
+    f.render_widget(layout_contents, chunks[1]);
+}
+
+/// Renders the fuzzy finder pop-up, with the best matches for what
+/// has been typed listed under it
+pub fn render_finder_popup(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Find Task".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_area = centered_ratio_rect(2, 3, Some(14), Some(40), area);
+    // Leave room for the borders and the query line
+    let shown = popup_area.height.saturating_sub(4) as usize;
+    let candidates = app.finder_candidates();
+    // Keep the highlighted candidate in view
+    let skip = (app.finder_selected + 1).saturating_sub(shown);
+
+    let mut line_vec = vec![
+        Line::from(format!("> {}", app.finder_value)),
+        Line::from(""),
+    ];
+    if candidates.is_empty() {
+        line_vec.push(Line::from("No matching tasks"));
+    }
+    for (i, task) in candidates.iter().enumerate().skip(skip).take(shown) {
+        let line = Line::from(task.name.clone());
+        if i == app.finder_selected {
+            line_vec.push(
+                line.style(
+                    Style::new()
+                        .bg(app.theme.theme_colors.selected_style)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        } else {
+            line_vec.push(line);
+        }
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+    f.set_cursor_position((
+        popup_area.left() + 3 + width(&app.finder_value) as u16,
+        popup_area.top() + 1,
+    ));
+}
+
+/// Renders the tag manager, listing every tag with how many tasks use it
+pub fn render_tag_manager(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Tags".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_area = centered_ratio_rect(2, 3, Some(16), Some(40), area);
+    // Leave room for the borders and the prompt lines
+    let shown = popup_area.height.saturating_sub(5) as usize;
+    let skip = (app.tag_manager_selected + 1).saturating_sub(shown);
+    let selected = app
+        .selected_tag()
+        .map(|(tag, _)| tag.as_str())
+        .unwrap_or("");
+
+    let prompt = match (app.tag_manager_edit, app.tag_manager_delete) {
+        (Some(TagEdit::Rename), _) => {
+            format!("Rename '{}' to: {}", selected, app.tag_manager_value)
+        }
+        (Some(TagEdit::Merge), _) => {
+            format!("Merge '{}' into: {}", selected, app.tag_manager_value)
+        }
+        (None, true) => format!(
+            "Remove '{}' from {} task(s)? (y)es (n)o",
+            selected,
+            app.selected_tag().map_or(0, |(_, count)| *count)
+        ),
+        (None, false) => String::from("(r)ename (m)erge (d)elete, Esc to close"),
+    };
+    let mut line_vec = vec![Line::from(prompt.clone()), Line::from("")];
+    if app.tag_manager_tags.is_empty() {
+        line_vec.push(Line::from("No tasks are tagged"));
+    }
+    let name_width = app
+        .tag_manager_tags
+        .iter()
+        .map(|(tag, _)| width(tag))
+        .max()
+        .unwrap_or(0);
+    for (i, (tag, count)) in app
+        .tag_manager_tags
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .take(shown)
+    {
+        let padding = " ".repeat(name_width - width(tag));
+        let line = Line::from(format!("{}{}  {}", tag, padding, count));
+        if i == app.tag_manager_selected {
+            line_vec.push(
+                line.style(
+                    Style::new()
+                        .bg(app.theme.theme_colors.selected_style)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        } else {
+            line_vec.push(line);
+        }
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+    if app.tag_manager_edit.is_some() {
+        f.set_cursor_position((
+            popup_area.left() + 1 + width(&prompt) as u16,
+            popup_area.top() + 1,
+        ));
+    }
+}
+
+/// Renders the selected task's acceptance criteria as boxes to tick off
+pub fn render_criteria_popup(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Done when".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_area = centered_ratio_rect(2, 3, Some(12), Some(60), area);
+    // Leave room for the borders and the prompt lines
+    let shown = popup_area.height.saturating_sub(4) as usize;
+    let skip = (app.criteria_selected + 1).saturating_sub(shown);
+    let criteria = app.selected_criteria();
+
+    let prompt = if app.criteria_adding {
+        format!("Add: {}", app.criteria_value)
+    } else {
+        String::from("SPACE ticks, (a)dd (d)elete, Esc to close")
+    };
+    let mut line_vec = vec![Line::from(prompt.clone()), Line::from("")];
+    if criteria.is_empty() {
+        line_vec.push(Line::from("Nothing to tick off yet"));
+    }
+    for (i, criterion) in criteria.iter().enumerate().skip(skip).take(shown) {
+        let mut line = Line::from(criterion_spans(criterion, &app.theme));
+        if i == app.criteria_selected && !app.criteria_adding {
+            line = line.style(
+                Style::new()
+                    .bg(app.theme.theme_colors.selected_style)
+                    .add_modifier(Modifier::BOLD),
+            );
+        }
+        line_vec.push(line);
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+    if app.criteria_adding {
+        f.set_cursor_position((
+            popup_area.left() + 1 + width(&prompt) as u16,
+            popup_area.top() + 1,
+        ));
+    }
+}
+
+/// Renders the database picker, listing every profile with the database it uses
+pub fn render_db_picker(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Databases".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_area = centered_ratio_rect(2, 3, Some(10), Some(60), area);
+    // Leave room for the borders and the prompt lines
+    let shown = popup_area.height.saturating_sub(4) as usize;
+    let skip = (app.db_picker_selected + 1).saturating_sub(shown);
+
+    let mut line_vec = vec![Line::from("Enter to switch, Esc to close"), Line::from("")];
+    if app.db_picker_profiles.is_empty() {
+        line_vec.push(Line::from("No profiles have been set up"));
+    }
+    let current = profile();
+    let name_width = app
+        .db_picker_profiles
+        .iter()
+        .map(|profile| width(profile.label()))
+        .max()
+        .unwrap_or(0);
+    for (i, profile) in app
+        .db_picker_profiles
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .take(shown)
+    {
+        let marker = if profile.name == current { "* " } else { "  " };
+        let padding = " ".repeat(name_width - width(profile.label()));
+        let database = match &profile.config {
+            Ok(config) => format!("{}", config.db_path.display()),
+            Err(_) => String::from("(config can't be read)"),
+        };
+        let line = Line::from(vec![
+            Span::from(format!("{}{}{}  ", marker, profile.label(), padding)),
+            Span::styled(database, Style::default().dim()),
+        ]);
+        if i == app.db_picker_selected {
+            line_vec.push(
+                line.style(
+                    Style::new()
+                        .bg(app.theme.theme_colors.selected_style)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        } else {
+            line_vec.push(line);
+        }
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+}
+
+/// Renders the theme editor down the right side, leaving the rest of the
+/// TUI showing the changes as they're made
+pub fn render_theme_editor(f: &mut Frame, app: &App, area: Rect) {
+    let title = if app.theme_edited() {
+        "Theme (not saved)"
+    } else {
+        "Theme"
+    };
+    let block = style_block(
+        title.to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_width = (area.width / 2).max(50).min(area.width);
+    let popup_area = Rect::new(
+        area.right() - popup_width,
+        area.top(),
+        popup_width,
+        area.height,
+    );
+    // Leave room for the borders and the prompt lines
+    let shown = popup_area.height.saturating_sub(4) as usize;
+    let skip = (app.theme_editor_selected + 1).saturating_sub(shown);
+    let fields = theme_fields();
+    let values = field_values(&app.theme).unwrap_or_default();
+
+    let prompt = match (&app.theme_editor_input, app.selected_theme_field()) {
+        (Some(input), Some(field)) => format!("{}: {}", field.name, input),
+        (None, Some(field)) if !field.choices().is_empty() => format!(
+            "[{}] h/l to pick, (e)dit (r)eset (s)ave, Esc to close",
+            field.element
+        ),
+        (None, Some(field)) => format!("[{}] (e)dit (r)eset (s)ave, Esc to close", field.element),
+        (_, None) => String::from("Esc to close"),
+    };
+    let mut line_vec = vec![Line::from(prompt.clone()), Line::from("")];
+    let name_width = fields
+        .iter()
+        .map(|field| width(field.name))
+        .max()
+        .unwrap_or(0);
+    for (i, field) in fields.iter().enumerate().skip(skip).take(shown) {
+        let value = values.get(i).cloned().flatten().unwrap_or_default();
+        let padding = " ".repeat(name_width - width(field.name));
+        let mut spans = vec![Span::from(format!("{}{}  ", field.name, padding))];
+        if field.is_color() {
+            let swatch = Color::from_str(&value).unwrap_or_default();
+            spans.push(Span::styled("    ", Style::new().bg(swatch)));
+            spans.push(Span::from(" "));
+        }
+        spans.push(Span::from(value));
+        let line = Line::from(spans);
+        if i == app.theme_editor_selected {
+            line_vec.push(
+                line.style(
+                    Style::new()
+                        .bg(app.theme.theme_colors.selected_style)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        } else {
+            line_vec.push(line);
+        }
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+    if app.theme_editor_input.is_some() {
+        f.set_cursor_position((
+            popup_area.left() + 1 + width(&prompt) as u16,
+            popup_area.top() + 1,
+        ));
+    }
+}
+
+/// Renders the dependency graph of a `Task`, highlighting the one picked
+pub fn render_dependency_graph(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Dependencies".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let popup_area = centered_ratio_rect(2, 3, Some(10), Some(60), area);
+    // Leave room for the borders and the prompt lines
+    let shown = popup_area.height.saturating_sub(4) as usize;
+    let skip = (app.graph_selected + 1).saturating_sub(shown);
+
+    let mut line_vec = vec![
+        Line::from("Enter to go to a task, Esc to close"),
+        Line::from(""),
+    ];
+    for (i, graph_line) in app.graph_lines.iter().enumerate().skip(skip).take(shown) {
+        let line = match graph_line.id {
+            Some(_) => Line::from(graph_line.text.as_str()),
+            None => Line::from(graph_line.text.as_str()).style(Style::default().dim()),
+        };
+        if i == app.graph_selected {
+            line_vec.push(
+                line.style(
+                    Style::new()
+                        .bg(app.theme.theme_colors.selected_style)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        } else {
+            line_vec.push(line);
+        }
+    }
+
+    let popup_contents = Paragraph::new(Text::from(line_vec))
+        .block(block)
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+}
+
+/// Renders the pop-up when deleting a `Task`
+pub fn render_delete_popup(f: &mut Frame, app: &App, area: Rect) {
+    let delete_block = style_block(
+        "Delete current task?".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let blurb = Paragraph::new(Text::from(vec![Line::from("(y)es (n)o")]));
+
+    let delete_popup_contents = blurb
+        .block(delete_block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Center)
+        .bg(app.theme.theme_colors.pop_up_bg);
+
+    let delete_popup_area = centered_ratio_rect(2, 3, Some(3), Some(40), area);
+    f.render_widget(Clear, delete_popup_area);
+    f.render_widget(delete_popup_contents, delete_popup_area);
+}
+
+/// Renders the pop-up asking to confirm completing an important task
+pub fn render_complete_popup(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Complete this important task?".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let name = app
+        .tasklist
+        .state
+        .selected()
+        .map(|i| app.tasklist.tasks[i].name.clone())
+        .unwrap_or_default();
+    let blurb = Paragraph::new(Text::from(vec![
+        Line::from(name).bold(),
+        Line::from("(y)es (n)o"),
+    ]));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Center)
+        .bg(app.theme.theme_colors.pop_up_bg);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(4), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+}
+
+/// Renders the pop-up asking whether to complete a parent whose subtasks are all done
+pub fn render_complete_parent_popup(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Every subtask is done, complete it too?".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let name = app.complete_parent_name().unwrap_or_default().to_string();
+    let blurb = Paragraph::new(Text::from(vec![
+        Line::from(name).bold(),
+        Line::from("(y)es (n)o"),
+    ]));
 
-    f.render_widget(help_contents, chunks[0]);
-    f.render_widget(layout_contents, chunks[1]);
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Center)
+        .bg(app.theme.theme_colors.pop_up_bg);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(4), Some(50), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
 }
 
-/// Renders the pop-up when deleting a `Task`
-pub fn render_delete_popup(f: &mut Frame, app: &App, area: Rect) {
-    let delete_block = style_block(
-        "Delete current task?".to_string(),
+/// Renders the pop-up asking before quitting with tasks that only live in memory
+pub fn render_exit_popup(f: &mut Frame, app: &App, area: Rect) {
+    let block = style_block(
+        "Quit without saving?".to_string(),
         Alignment::Center,
         app.theme.theme_colors.pop_up_bg,
         app.theme.theme_colors.pop_up_outline,
     );
 
-    let blurb = Paragraph::new(Text::from(vec![Line::from("(y)es (n)o")]));
+    let unsaved = app.unsaved_count().unwrap_or_default();
+    let blurb = Paragraph::new(Text::from(vec![
+        Line::from(format!(
+            "{} task(s) only exist in memory and will be lost",
+            unsaved
+        )),
+        Line::from(""),
+        Line::from("(e)xport them to a CSV first"),
+        Line::from("(p)romote them to the real database"),
+        Line::from("(q)uit anyway"),
+        Line::from("(n)o, keep going"),
+    ]));
 
-    let delete_popup_contents = blurb
-        .block(delete_block)
+    let popup_contents = blurb
+        .block(block)
         .wrap(Wrap { trim: false })
         .alignment(Alignment::Center)
         .bg(app.theme.theme_colors.pop_up_bg);
 
-    let delete_popup_area = centered_ratio_rect(2, 3, Some(3), Some(40), area);
-    f.render_widget(Clear, delete_popup_area);
-    f.render_widget(delete_popup_contents, delete_popup_area);
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
 }
 
 /// Renders the pop-up when getting user input for what stage to update
@@ -964,6 +2507,12 @@ pub fn render_stage_popup(f: &mut Frame, app: &App, area: Rect) {
         Line::from("4. Description"),
         Line::from("5. Latest"),
         Line::from("6. Tags"),
+        Line::from("7. Recurrence"),
+        Line::from("8. Project"),
+        Line::from("9. Due date"),
+        Line::from("0. Snooze until"),
+        Line::from("c. Completed date (completed tasks only)"),
+        Line::from("t. Time slot today"),
     ]));
 
     let popup_contents = blurb
@@ -971,7 +2520,7 @@ pub fn render_stage_popup(f: &mut Frame, app: &App, area: Rect) {
         .wrap(Wrap { trim: false })
         .alignment(Alignment::Left);
 
-    let popup_area = centered_ratio_rect(2, 3, Some(10), Some(40), area);
+    let popup_area = centered_ratio_rect(2, 3, Some(16), Some(40), area);
     f.render_widget(Clear, popup_area);
     f.render_widget(popup_contents, popup_area);
 }
@@ -1117,7 +2666,7 @@ pub fn render_description_popup(f: &mut Frame, app: &mut App, area: Rect) {
         app.theme.theme_colors.pop_up_outline,
     );
 
-    let instructions = "Feel free to add a description";
+    let instructions = "Feel free to add a description (Ctrl+e opens $EDITOR)";
 
     let line_vec = vec![
         Line::from(instructions),
@@ -1160,7 +2709,7 @@ pub fn render_latest_popup(f: &mut Frame, app: &mut App, area: Rect) {
         app.theme.theme_colors.pop_up_outline,
     );
 
-    let instructions = "Any updates?";
+    let instructions = "Any updates? (Ctrl+e opens $EDITOR)";
     let instructions_len = instructions.chars().count();
     let line_vec = vec![
         Line::from(instructions),
@@ -1235,12 +2784,14 @@ pub fn render_tags_popup(f: &mut Frame, app: &mut App, area: Rect) {
 
     let mut tags_span_vec = vec![];
     let mut task_tags_vec = Vec::from_iter(app.inputs.tags.clone());
-    task_tags_vec.sort();
+    sort_text(&mut task_tags_vec);
 
     for (i, tag) in task_tags_vec.iter().enumerate() {
         let mut span_object = Span::styled(
             format!(" {} ", tag),
-            Style::default().fg(app.theme.text_colors.tags),
+            Style::default()
+                .fg(app.theme.text_colors.tags)
+                .add_modifier(app.theme.text_modifiers.tags),
         );
         if i == app.tags_highlight_value && app.highlight_tags {
             span_object = span_object.underlined();
@@ -1271,3 +2822,264 @@ pub fn render_tags_popup(f: &mut Frame, app: &mut App, area: Rect) {
         );
     }
 }
+
+/// Renders the pop-up when getting user input for `Task` recurrence
+pub fn render_recurrence_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Task Recurrence".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions = "Repeat daily, weekly, monthly, or every N days? Leave blank for never";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.recurrence.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.recurrence.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
+
+/// Renders the pop-up when getting user input for `Task` project
+pub fn render_project_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Task Project".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions = "What project does this belong to? Leave blank for none";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.project.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.project.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
+
+/// Renders the pop-up when getting user input for `Task` due date
+pub fn render_due_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Task Due Date".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions =
+        "When is this due? YYYY-MM-DD, tomorrow, fri, or in 3 days. Leave blank for never";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.due.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.due.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
+
+/// Renders the pop-up when getting user input for the date to snooze a `Task` until
+pub fn render_snooze_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Snooze Task".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions =
+        "Hide until when? YYYY-MM-DD, tomorrow, fri, or in 3 days. Leave blank to unsnooze";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.scheduled.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.scheduled.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
+
+/// Renders the pop-up when getting user input for a `Task`'s time slot today
+pub fn render_time_slot_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Time Slot".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions =
+        "When today, and for how long? i.e. 14:00 45m or 9:30 1h30m. Leave blank to unplan";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.time_slot.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.time_slot.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
+
+/// Renders the pop-up when getting user input for when a `Task` was completed
+pub fn render_completed_on_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = style_block(
+        "Task Completed Date".to_string(),
+        Alignment::Center,
+        app.theme.theme_colors.pop_up_bg,
+        app.theme.theme_colors.pop_up_outline,
+    );
+
+    let instructions = "When was this completed? YYYY-MM-DD HH:MM, YYYY-MM-DD, or yesterday";
+
+    let line_vec = vec![
+        Line::from(instructions),
+        Line::from(""),
+        Line::from(app.inputs.completed_on.as_str()),
+    ];
+    let line_vec_len = line_vec.len();
+
+    let blurb = Paragraph::new(Text::from(line_vec));
+
+    let popup_contents = blurb
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .alignment(Alignment::Left);
+
+    let popup_area = centered_ratio_rect(2, 3, Some(8), Some(40), area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup_contents, popup_area);
+
+    // If our text wraps, we want to start our cursor accordingly
+    let text_width = popup_area.right() - popup_area.left() - 1;
+    let (_, y_offset) = map_string_to_lines(instructions.to_string(), text_width);
+
+    text_cursor_logic(
+        f,
+        app,
+        popup_area,
+        app.inputs.completed_on.to_string(),
+        1,
+        line_vec_len as u16 + y_offset as u16,
+    );
+}
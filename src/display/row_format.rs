@@ -0,0 +1,179 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// How rows of the task list are laid out unless the theme says otherwise
+pub const DEFAULT_ROW_FORMAT: &str =
+    "{urgency_marks} | {status} - {name}{subtasks}{rolled_up}{locked}{timer}{age}";
+
+/// Something about a `Task` a row of the task list can show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowField {
+    /// The urgency's exclamation marks, or the completed mark once it's done
+    UrgencyMarks,
+    Urgency,
+    Status,
+    Name,
+    /// How many subtasks there are, i.e. " ▸ 3"
+    Subtasks,
+    /// What's rolled up from the subtasks, for tasks that aren't done
+    RolledUp,
+    /// A lock, if the task is locked
+    Locked,
+    /// A stopwatch, while a timer is running on a task that isn't done
+    Timer,
+    /// How long ago the task was added, or completed
+    Age,
+    Tags,
+    Project,
+    Due,
+    Scheduled,
+}
+
+/// The name each `RowField` goes by in a row format, between braces
+const ROW_FIELDS: [(&str, RowField); 13] = [
+    ("urgency_marks", RowField::UrgencyMarks),
+    ("urgency", RowField::Urgency),
+    ("status", RowField::Status),
+    ("name", RowField::Name),
+    ("subtasks", RowField::Subtasks),
+    ("rolled_up", RowField::RolledUp),
+    ("locked", RowField::Locked),
+    ("timer", RowField::Timer),
+    ("age", RowField::Age),
+    ("tags", RowField::Tags),
+    ("project", RowField::Project),
+    ("due", RowField::Due),
+    ("scheduled", RowField::Scheduled),
+];
+
+/// A piece of a row format: either text shown as is, or a field of the `Task`
+#[derive(Clone, Debug, PartialEq)]
+pub enum RowPiece {
+    Text(String),
+    Field(RowField),
+}
+
+/// What rows of the task list show and in what order, written like
+/// "{urgency_marks} {status} {name} [{tags}] {due}". Braces are written
+/// twice to show one. It's kept as written, to save it back the same way.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RowFormat {
+    text: String,
+    pieces: Vec<RowPiece>,
+}
+
+/// Splits a row format into text and the fields between braces
+fn parse_pieces(format: &str) -> Result<Vec<RowPiece>> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for ch in chars.by_ref() {
+                    if ch == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(ch);
+                }
+                if !closed {
+                    bail!("'{{{}' in the row format is missing its '}}'", name);
+                }
+                let Some((_, field)) = ROW_FIELDS.iter().find(|(known, _)| *known == name) else {
+                    let names: Vec<&str> = ROW_FIELDS.iter().map(|(name, _)| *name).collect();
+                    bail!(
+                        "'{{{}}}' isn't something a row can show, use any of {}",
+                        name,
+                        names.join(", ")
+                    );
+                };
+                if !text.is_empty() {
+                    pieces.push(RowPiece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(RowPiece::Field(*field));
+            }
+            '}' => {
+                bail!("A '}}' in the row format isn't closing anything, write '}}}}' to show one")
+            }
+            ch => text.push(ch),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(RowPiece::Text(text));
+    }
+    Ok(pieces)
+}
+
+impl RowFormat {
+    /// Returns the text and fields of the row, in order
+    pub fn pieces(&self) -> &[RowPiece] {
+        &self.pieces
+    }
+}
+
+impl Default for RowFormat {
+    fn default() -> Self {
+        Self::try_from(DEFAULT_ROW_FORMAT.to_string()).expect("The default row format is valid")
+    }
+}
+
+impl TryFrom<String> for RowFormat {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match parse_pieces(&text) {
+            Ok(pieces) => Ok(Self { text, pieces }),
+            Err(e) => Err(format!("{:#}", e)),
+        }
+    }
+}
+
+impl From<RowFormat> for String {
+    fn from(format: RowFormat) -> Self {
+        format.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pieces() {
+        assert_eq!(
+            parse_pieces("{status} {name} [{tags}]").unwrap(),
+            vec![
+                RowPiece::Field(RowField::Status),
+                RowPiece::Text(String::from(" ")),
+                RowPiece::Field(RowField::Name),
+                RowPiece::Text(String::from(" [")),
+                RowPiece::Field(RowField::Tags),
+                RowPiece::Text(String::from("]")),
+            ]
+        );
+        assert_eq!(
+            parse_pieces("{{{name}}}").unwrap(),
+            vec![
+                RowPiece::Text(String::from("{")),
+                RowPiece::Field(RowField::Name),
+                RowPiece::Text(String::from("}")),
+            ]
+        );
+        assert!(parse_pieces("{colour}").is_err());
+        assert!(parse_pieces("{name").is_err());
+        assert!(parse_pieces("name}").is_err());
+        assert_eq!(RowFormat::default().pieces().len(), 10);
+    }
+}
@@ -0,0 +1,128 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::query::Query;
+use crate::backend::task::Task;
+use crate::display::theme::{modifier_names, no_modifier};
+
+/// Which tasks a `RowRule` applies to, written in the query language, i.e.
+/// "urgency:critical status:open". It's kept as written, to save it back the
+/// same way.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    text: String,
+    query: Query,
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match text.parse() {
+            Ok(query) => Ok(Self { text, query }),
+            Err(e) => Err(format!("'{}' isn't a rule condition: {:#}", text, e)),
+        }
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.text
+    }
+}
+
+/// Restyles the rows of the tasks matching `when` in the task list, i.e.
+/// giving critical tasks a red background, or dimming blocked ones
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RowRule {
+    pub when: Condition,
+    /// Colors the text of the row that doesn't have a color of its own
+    #[serde(default)]
+    pub fg: Option<Color>,
+    #[serde(default)]
+    pub bg: Option<Color>,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub modifiers: Modifier,
+}
+
+impl RowRule {
+    /// Returns the style the rule puts on the rows it applies to
+    pub fn style(&self) -> Style {
+        let mut style = Style::new().add_modifier(self.modifiers);
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        style
+    }
+}
+
+/// Rules restyling rows of the task list, written in theme.toml as
+/// `[[conditional_formatting.rules]]`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ConditionalFormatting {
+    #[serde(default)]
+    pub rules: Vec<RowRule>,
+}
+
+impl ConditionalFormatting {
+    /// Returns the style the rules matching the `Task` put on its row, with
+    /// later rules winning out over earlier ones where they both say
+    pub fn row_style(&self, task: &Task) -> Style {
+        self.rules
+            .iter()
+            .filter(|rule| rule.when.query.matches(task))
+            .fold(Style::new(), |style, rule| style.patch(rule.style()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::task::{Status, Urgency};
+    use crate::display::theme::parse_theme;
+
+    #[test]
+    fn test_row_style() {
+        let (theme, _) = parse_theme(String::from(
+            r#"
+            [[conditional_formatting.rules]]
+            when = "urgency:critical status:open"
+            bg = "Red"
+
+            [[conditional_formatting.rules]]
+            when = "tag:blocked"
+            modifiers = "Dim"
+            "#,
+        ))
+        .unwrap();
+        let formatting = &theme.conditional_formatting;
+        assert_eq!(formatting.rules.len(), 2);
+
+        let mut task = Task::new(String::from("Taxes"), None, None, None, None, None);
+        assert_eq!(formatting.row_style(&task), Style::new());
+
+        task.urgency = Urgency::Critical;
+        assert_eq!(formatting.row_style(&task), Style::new().bg(Color::Red));
+        task.status = Status::Completed;
+        assert_eq!(formatting.row_style(&task), Style::new());
+
+        task.tags = Some(["blocked".to_string()].into());
+        assert_eq!(
+            formatting.row_style(&task),
+            Style::new().add_modifier(Modifier::DIM)
+        );
+
+        // Saved back as written
+        let saved = toml::to_string(&theme).unwrap();
+        assert!(saved.contains("when = \"urgency:critical status:open\""));
+
+        assert!(parse_theme(String::from(
+            "[[conditional_formatting.rules]]\nwhen = \"urgency:loud\""
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent};
+use uuid::Uuid;
+
+use crate::backend::database::TaskSelect;
+use crate::backend::history::Change;
+use crate::backend::task::{
+    parent_ready_to_complete, reopen_parents, CompleteParents, Status, Task,
+};
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::tui::App;
+
+/// Registers drilling into subtasks and back out again
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Drill into the selected task's subtasks",
+        key: KeyBinding::code(KeyCode::Enter),
+        writes: false,
+        run: App::drill_in,
+    })?;
+    registry.register_action(Action {
+        description: "Go back up from a task's subtasks",
+        key: KeyBinding::code(KeyCode::Backspace),
+        writes: false,
+        run: App::drill_out,
+    })
+}
+
+impl App {
+    /// Lists the subtasks of the selected `Task` in place of the current list.
+    /// New tasks get added under it until going back up.
+    pub fn drill_in(&mut self) -> Result<()> {
+        if self.show_archive {
+            return Ok(());
+        }
+        let Some(id) = self.tasklist.selected_id() else {
+            return Ok(());
+        };
+        self.drill_path.push(id);
+        self.tasklist.state.select(None);
+        self.update_tasklist()?;
+        self.select_index(Some(0))
+    }
+
+    /// Goes back up to the list the current parent is in, selecting it
+    pub fn drill_out(&mut self) -> Result<()> {
+        let Some(id) = self.drill_path.pop() else {
+            return Ok(());
+        };
+        self.update_tasklist()?;
+        self.select_task(id)?;
+        Ok(())
+    }
+
+    /// Keeps the parents of the `Task` with the id in step with it once its
+    /// status has changed, returning the changes made so they get undone
+    /// along with it. Reopening it reopens any `Completed` parents, and
+    /// completing the last subtask left completes the parent too, or asks
+    /// first, as `complete_parents` in the config says.
+    pub fn roll_up_parents(&mut self, id: Uuid) -> Result<Vec<Change>> {
+        let mut tasks = self.family(id)?;
+        let Some(task) = tasks.iter().find(|task| task.get_id() == id).cloned() else {
+            return Ok(vec![]);
+        };
+
+        let mut changes = vec![];
+        if task.status != Status::Completed {
+            for parent in reopen_parents(&tasks, &task) {
+                let Some(before) = tasks.iter().find(|t| t.get_id() == parent.get_id()) else {
+                    continue;
+                };
+                self.store.update(&parent)?;
+                changes.push(Change::Updated {
+                    before: Box::new(before.clone()),
+                    after: parent,
+                });
+            }
+            return Ok(changes);
+        }
+
+        let mut done = task;
+        while let Some(parent) = parent_ready_to_complete(&tasks, &done) {
+            match self.config.complete_parents {
+                CompleteParents::Always => {}
+                CompleteParents::Never => break,
+                CompleteParents::Ask => {
+                    self.complete_parent = Some(parent);
+                    break;
+                }
+            }
+            changes.extend(self.complete_parent_task(&parent)?);
+            tasks = self.family(parent.get_id())?;
+            done = parent;
+        }
+        Ok(changes)
+    }
+
+    /// Reads in the `Task` with the id, the parents above it, and the subtasks
+    /// of each of them, which is all rolling its status up looks at
+    fn family(&self, id: Uuid) -> Result<Vec<Task>> {
+        let mut family = self.store.select(&TaskSelect::new().ids(&[id]))?.tasks;
+        let mut next = family.first().and_then(|task| task.parent);
+        // Stops at a loop of parents, as well as at the top
+        while let Some(parent_id) = next.filter(|id| !family.iter().any(|t| t.get_id() == *id)) {
+            let parent = self
+                .store
+                .select(&TaskSelect::new().ids(&[parent_id]))?
+                .tasks;
+            next = parent.first().and_then(|task| task.parent);
+            let subtasks = self
+                .store
+                .select(&TaskSelect::new().parent(Some(parent_id)))?
+                .tasks;
+            for task in parent.into_iter().chain(subtasks) {
+                if !family.iter().any(|t| t.get_id() == task.get_id()) {
+                    family.push(task);
+                }
+            }
+        }
+        Ok(family)
+    }
+
+    /// Completes a parent whose subtasks are all done, lining up its next
+    /// occurrence if it recurs
+    fn complete_parent_task(&mut self, parent: &Task) -> Result<Vec<Change>> {
+        let mut completed = parent.clone();
+        completed.status = Status::Completed;
+        completed.completed_on = Some(Local::now());
+        completed.stop_timer();
+        self.store.update(&completed)?;
+
+        let mut changes = vec![Change::Updated {
+            before: Box::new(parent.clone()),
+            after: completed.clone(),
+        }];
+        if let Some(next_task) = self.add_next_occurrence(&completed)? {
+            changes.push(Change::Added(next_task));
+        }
+        Ok(changes)
+    }
+
+    /// Handles keys while asking whether to complete a parent whose
+    /// subtasks are all done
+    pub fn handle_keys_for_complete_parent(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('c') => {
+                let Some(asked) = self.complete_parent.take() else {
+                    return Ok(());
+                };
+                let id = asked.get_id();
+                // It may have changed while asking
+                let Some(parent) = self
+                    .store
+                    .select(&TaskSelect::new().ids(&[id]))?
+                    .tasks
+                    .pop()
+                else {
+                    return Ok(());
+                };
+                let mut operation = self.complete_parent_task(&parent)?;
+                // Its own parent may be done with now too
+                operation.extend(self.roll_up_parents(id)?);
+                self.history.record(operation);
+                self.show_undo_toast(format!("Completed '{}'", parent.name));
+                self.update_tasklist()?;
+            }
+            KeyCode::Char('n')
+            | KeyCode::Char('N')
+            | KeyCode::Char('x')
+            | KeyCode::Esc
+            | KeyCode::Backspace => self.complete_parent = None,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the name of the parent waiting on whether to complete it
+    pub fn complete_parent_name(&self) -> Option<&str> {
+        self.complete_parent
+            .as_ref()
+            .map(|parent| parent.name.as_str())
+    }
+}
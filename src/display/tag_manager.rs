@@ -0,0 +1,144 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::text::pop_grapheme;
+use crate::display::tui::App;
+
+/// What the name being typed into the tag manager is for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagEdit {
+    /// Renaming the highlighted tag to a tag that isn't in use yet
+    Rename,
+    /// Folding the highlighted tag into another tag
+    Merge,
+}
+
+/// Registers opening the tag manager
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Manage tags (rename, merge, delete)",
+        key: KeyBinding::key('T'),
+        // Edits are turned off inside it when looking at a snapshot
+        writes: false,
+        run: App::open_tag_manager,
+    })
+}
+
+impl App {
+    /// Opens the screen listing every tag with how many tasks use it
+    pub fn open_tag_manager(&mut self) -> Result<()> {
+        self.tag_manager_tags = self.store.tag_counts()?;
+        self.tag_manager = true;
+        self.tag_manager_selected = 0;
+        self.tag_manager_edit = None;
+        self.tag_manager_delete = false;
+        Ok(())
+    }
+
+    /// Returns the highlighted tag and how many tasks use it
+    pub fn selected_tag(&self) -> Option<&(String, usize)> {
+        self.tag_manager_tags.get(self.tag_manager_selected)
+    }
+
+    /// Handles keys while the tag manager is open
+    pub fn handle_keys_for_tag_manager(&mut self, key: KeyEvent) -> Result<()> {
+        if let Some(edit) = self.tag_manager_edit {
+            match key.code {
+                KeyCode::Esc => self.tag_manager_edit = None,
+                KeyCode::Enter => self.apply_tag_edit(edit)?,
+                KeyCode::Backspace => pop_grapheme(&mut self.tag_manager_value),
+                KeyCode::Char(ch) => self.tag_manager_value.push(ch),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.tag_manager_delete {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('d') => {
+                    self.tag_manager_delete = false;
+                    if let Some((tag, _)) = self.selected_tag().cloned() {
+                        self.retag_selected(None, format!("Removed '{}'", tag))?;
+                    }
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.tag_manager_delete = false
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => self.tag_manager = false,
+            KeyCode::Char('j') | KeyCode::Down
+                if self.tag_manager_selected + 1 < self.tag_manager_tags.len() =>
+            {
+                self.tag_manager_selected += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.tag_manager_selected = self.tag_manager_selected.saturating_sub(1)
+            }
+            KeyCode::Char('r') | KeyCode::Char('m') | KeyCode::Char('d')
+                if self.snapshot.is_some() =>
+            {
+                self.show_toast("Snapshots are read-only")
+            }
+            KeyCode::Char('r') => {
+                if let Some((tag, _)) = self.selected_tag().cloned() {
+                    self.tag_manager_value = tag;
+                    self.tag_manager_edit = Some(TagEdit::Rename);
+                }
+            }
+            KeyCode::Char('m') if self.selected_tag().is_some() => {
+                self.tag_manager_value = String::new();
+                self.tag_manager_edit = Some(TagEdit::Merge);
+            }
+            KeyCode::Char('d') if self.selected_tag().is_some() => self.tag_manager_delete = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Renames or merges the highlighted tag into the name typed
+    fn apply_tag_edit(&mut self, edit: TagEdit) -> Result<()> {
+        let Some((tag, _)) = self.selected_tag().cloned() else {
+            return Ok(());
+        };
+        let new = self.tag_manager_value.trim().to_string();
+        if new == tag {
+            self.tag_manager_edit = None;
+            return Ok(());
+        }
+        let in_use = self.tag_manager_tags.iter().any(|(used, _)| *used == new);
+        let done = match edit {
+            TagEdit::Rename if in_use => {
+                self.show_toast(format!("'{}' is already a tag, m merges into it", new));
+                return Ok(());
+            }
+            TagEdit::Rename => format!("Renamed '{}' to '{}'", tag, new),
+            TagEdit::Merge => format!("Merged '{}' into '{}'", tag, new),
+        };
+        self.tag_manager_edit = None;
+        self.retag_selected(Some(&new), done)
+    }
+
+    /// Swaps the highlighted tag for `to` (or takes it off, if there is no `to`)
+    /// on every task, then reloads the tags and tasks
+    fn retag_selected(&mut self, to: Option<&str>, done: String) -> Result<()> {
+        let Some((tag, _)) = self.selected_tag().cloned() else {
+            return Ok(());
+        };
+        match self.store.retag(&[tag], to) {
+            Ok(retagged) => self.show_toast(retagged.message(&done)),
+            Err(e) => self.show_toast(format!("{}", e)),
+        }
+
+        self.tag_manager_tags = self.store.tag_counts()?;
+        self.tag_manager_selected = self
+            .tag_manager_selected
+            .min(self.tag_manager_tags.len().saturating_sub(1));
+        self.update_tasklist()
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::tui::App;
+
+/// Registers switching `Task Info` over to the selected task's history
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Show how the selected task changed over time",
+        key: KeyBinding::key('i'),
+        writes: false,
+        run: App::toggle_history,
+    })
+}
+
+impl App {
+    /// Switches `Task Info` between the task's details and its history
+    pub fn toggle_history(&mut self) -> Result<()> {
+        if !self.show_history {
+            // Finds out the store can't keep a history before switching to it
+            if let Some(id) = self.tasklist.selected_id() {
+                self.store.task_events(id)?;
+            }
+        }
+        self.show_history = !self.show_history;
+        self.scroll_info.task_info_scroll = 0;
+        self.load_history();
+        Ok(())
+    }
+
+    /// Reads the selected task's history in, if it's being shown
+    pub fn load_history(&mut self) {
+        if !self.show_history {
+            return;
+        }
+        let Some(id) = self.tasklist.selected_id() else {
+            self.task_history.clear();
+            return;
+        };
+        match self.store.task_events(id) {
+            Ok(events) => self.task_history = events,
+            Err(e) => {
+                self.task_history.clear();
+                self.show_toast(format!("{}", e));
+            }
+        }
+    }
+}
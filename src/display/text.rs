@@ -0,0 +1,89 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Text being typed is edited a grapheme at a time (what the user sees as a
+// single character, like an emoji or a letter with an accent on it), and
+// laid out by how many columns it takes up on screen, since CJK characters
+// and emoji are two columns wide.
+
+/// Returns how many graphemes are in the text
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Returns the byte offset where the grapheme at `index` starts,
+/// or the length of the text if `index` is past the end
+pub fn byte_index(text: &str, index: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(index)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Returns the first `count` graphemes of the text
+pub fn first_graphemes(text: &str, count: usize) -> &str {
+    &text[..byte_index(text, count)]
+}
+
+/// Returns how many columns the text takes up on screen
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// Inserts the character before the grapheme at `index`, returning the
+/// index of the grapheme after it. A character that joins onto the one
+/// before it (i.e. a combining accent) doesn't start a new grapheme.
+pub fn insert_char(text: &mut String, index: usize, ch: char) -> usize {
+    let at = byte_index(text, index);
+    text.insert(at, ch);
+    grapheme_count(&text[..at + ch.len_utf8()])
+}
+
+/// Removes the grapheme before `index`, returning true if there was one
+pub fn remove_before(text: &mut String, index: usize) -> bool {
+    if index == 0 {
+        return false;
+    }
+    let start = byte_index(text, index - 1);
+    let end = byte_index(text, index);
+    text.replace_range(start..end, "");
+    start != end
+}
+
+/// Removes the last grapheme of the text, if there is one
+pub fn pop_grapheme(text: &mut String) {
+    let count = grapheme_count(text);
+    remove_before(text, count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grapheme_editing() {
+        let mut text = String::from("café 🇯🇵 日本");
+        assert_eq!(grapheme_count(&text), 9);
+        assert_eq!(width(&text), 12);
+        assert_eq!(first_graphemes(&text, 6), "café 🇯🇵");
+        assert_eq!(byte_index(&text, 100), text.len());
+
+        // Deleting the flag takes both of its code points with it
+        assert!(remove_before(&mut text, 6));
+        assert_eq!(text, "café  日本");
+        assert!(!remove_before(&mut text, 0));
+
+        assert_eq!(insert_char(&mut text, 5, '🙂'), 6);
+        assert_eq!(text, "café 🙂 日本");
+
+        // A combining accent joins the letter before it
+        let mut text = String::from("Jose");
+        assert_eq!(insert_char(&mut text, 4, '\u{301}'), 4);
+        assert_eq!(grapheme_count(&text), 4);
+        assert!(remove_before(&mut text, 4));
+        assert_eq!(text, "Jos");
+
+        let mut text = String::from("ok 👍🏽");
+        pop_grapheme(&mut text);
+        assert_eq!(text, "ok ");
+    }
+}
@@ -2,15 +2,18 @@ use std::fs::{rename, File};
 use std::io::{prelude::*, BufReader};
 use std::path::PathBuf;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use ratatui::style::{
     palette::tailwind::{EMERALD, SLATE},
-    Color,
+    Color, Modifier,
 };
 use serde::{Deserialize, Serialize};
 use struct_field_names_as_array::FieldNamesAsArray;
 
-use crate::backend::config::get_config_dir;
+use crate::backend::config::{get_config_dir, profile_file_name};
+use crate::display::info_layout::InfoLayout;
+use crate::display::row_format::RowFormat;
+use crate::display::row_rules::ConditionalFormatting;
 
 // Default colors
 fn slate_950() -> Color {
@@ -49,7 +52,7 @@ fn red_default() -> Color {
 
 /// Struct holds all the color configurations for `checklist`
 /// that the user can change
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, FieldNamesAsArray)]
 pub struct ThemeColors {
     #[serde(default = "slate_950")]
     pub normal_row_bg: Color,
@@ -89,9 +92,11 @@ pub struct ThemeColors {
     pub pop_up_outline: Color,
     #[serde(default = "blue_default")]
     pub state_box_outline_during_tags_edit: Color,
+    #[serde(default = "cyan_default")]
+    pub focused_outline: Color,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, FieldNamesAsArray)]
 pub struct ThemeText {
     #[serde(default = "cyan_default")]
     pub status_open: Color,
@@ -125,12 +130,32 @@ pub struct ThemeText {
     pub description: Color,
     #[serde(default = "blue_default")]
     pub tags: Color,
+    #[serde(default = "cyan_default")]
+    pub recurrence: Color,
+    #[serde(default = "yellow_default")]
+    pub project: Color,
+    #[serde(default = "green_default")]
+    pub time_spent: Color,
+    #[serde(default = "yellow_default")]
+    pub due_date: Color,
+    #[serde(default = "red_default")]
+    pub overdue: Color,
+    #[serde(default = "blue_default")]
+    pub scheduled: Color,
+    #[serde(default = "blue_default")]
+    pub ticket: Color,
+    #[serde(default = "cyan_default")]
+    pub database: Color,
+    #[serde(default = "red_default")]
+    pub scratch_database: Color,
     #[serde(default = "yellow_default")]
     pub layout_smart: Color,
     #[serde(default = "cyan_default")]
     pub layout_horizontal: Color,
     #[serde(default = "blue_default")]
     pub layout_vertical: Color,
+    #[serde(default = "magenta_default")]
+    pub layout_kanban: Color,
     #[serde(default = "cyan_default")]
     pub filter_status_all: Color,
     #[serde(default = "green_default")]
@@ -145,6 +170,151 @@ pub struct ThemeText {
     pub help_movement: Color,
 }
 
+/// The names modifiers are written with in theme.toml
+const MODIFIER_NAMES: [(&str, Modifier); 9] = [
+    ("Bold", Modifier::BOLD),
+    ("Dim", Modifier::DIM),
+    ("Italic", Modifier::ITALIC),
+    ("Underlined", Modifier::UNDERLINED),
+    ("SlowBlink", Modifier::SLOW_BLINK),
+    ("RapidBlink", Modifier::RAPID_BLINK),
+    ("Reversed", Modifier::REVERSED),
+    ("Hidden", Modifier::HIDDEN),
+    ("CrossedOut", Modifier::CROSSED_OUT),
+];
+
+/// Returns the `Modifier` for names like "Bold Italic", in any case and
+/// split by spaces, commas, or `|`. Nothing at all is no modifier.
+pub fn parse_modifiers(names: &str) -> Result<Modifier> {
+    let mut modifier = Modifier::empty();
+    for name in names
+        .split(|ch: char| ch.is_whitespace() || ch == ',' || ch == '|')
+        .filter(|name| !name.is_empty())
+    {
+        let Some((_, named)) = MODIFIER_NAMES
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        else {
+            bail!(
+                "'{}' isn't a modifier, use any of {}",
+                name,
+                modifier_names::names()
+            );
+        };
+        modifier |= *named;
+    }
+    Ok(modifier)
+}
+
+pub fn no_modifier() -> Modifier {
+    Modifier::empty()
+}
+
+/// Writes a `Modifier` to theme.toml as the names of its parts, i.e.
+/// "Bold Italic", and reads it back with `parse_modifiers`
+pub mod modifier_names {
+    use ratatui::style::Modifier;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::{parse_modifiers, MODIFIER_NAMES};
+
+    /// Every modifier name, for saying what can be used
+    pub fn names() -> String {
+        let names: Vec<&str> = MODIFIER_NAMES.iter().map(|(name, _)| *name).collect();
+        names.join(", ")
+    }
+
+    pub fn serialize<S: Serializer>(modifier: &Modifier, serializer: S) -> Result<S::Ok, S::Error> {
+        let names: Vec<&str> = MODIFIER_NAMES
+            .iter()
+            .filter(|(_, named)| modifier.contains(*named))
+            .map(|(name, _)| *name)
+            .collect();
+        serializer.serialize_str(&names.join(" "))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Modifier, D::Error> {
+        let names = String::deserialize(deserializer)?;
+        parse_modifiers(&names).map_err(|e| D::Error::custom(format!("{:#}", e)))
+    }
+}
+
+/// Modifiers like bold or italic for the text colored by `ThemeText`, by
+/// the same names, i.e. `urgency_critical = "Bold"`
+#[derive(Debug, Default, Deserialize, Serialize, FieldNamesAsArray)]
+pub struct ThemeModifiers {
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub status_open: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub status_working: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub status_paused: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub status_completed: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_low: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_medium: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_high: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_critical: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_ascending: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub urgency_descending: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub title: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub created_date: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub completed_date: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub latest: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub description: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub tags: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub recurrence: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub project: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub time_spent: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub due_date: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub overdue: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub scheduled: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub ticket: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub database: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub scratch_database: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub layout_smart: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub layout_horizontal: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub layout_vertical: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub layout_kanban: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub filter_status_all: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub filter_status_completed: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub filter_status_notcompleted: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub help_actions: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub help_quick_actions: Modifier,
+    #[serde(default = "no_modifier", with = "modifier_names")]
+    pub help_movement: Modifier,
+}
+
 // Default Theme styles
 fn scroll_begin() -> Option<String> {
     Some(String::from("↑"))
@@ -177,8 +347,20 @@ fn completed() -> String {
     String::from("✓  ")
 }
 
+/// How dates are shown in the TUI
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum DateStyle {
+    /// As YYYY-MM-DD
+    Absolute,
+    /// In words, like "3 days ago"
+    Relative,
+    /// As YYYY-MM-DD, followed by how long ago it was in words
+    #[default]
+    Both,
+}
+
 /// Struct that holds different elements the user can style
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, FieldNamesAsArray)]
 pub struct ThemeStyles {
     #[serde(default = "scroll_begin")]
     pub scrollbar_begin: Option<String>,
@@ -200,6 +382,12 @@ pub struct ThemeStyles {
     pub urgency_critical: String,
     #[serde(default = "completed")]
     pub completed: String,
+    #[serde(default)]
+    pub dates: DateStyle,
+    #[serde(default)]
+    pub row_format: RowFormat,
+    #[serde(default)]
+    pub info_layout: InfoLayout,
 }
 
 /// Overall struct that holds `ThemeColors` and `ThemeStyles`
@@ -211,6 +399,12 @@ pub struct Theme {
     pub text_colors: ThemeText,
     // Styles
     pub theme_styles: ThemeStyles,
+    // Text modifiers, left out of theme.toml files from before they were around
+    #[serde(default)]
+    pub text_modifiers: ThemeModifiers,
+    // Rules restyling rows of the task list
+    #[serde(default)]
+    pub conditional_formatting: ConditionalFormatting,
 }
 
 pub fn create_empty_theme_toml() -> Result<()> {
@@ -236,54 +430,65 @@ impl Theme {
     /// Saves the `Theme` to a theme.toml file.
     /// Save location is based on `directories::BaseDirs`.
     pub fn save(&self) -> Result<()> {
-        match get_config_dir() {
-            Ok(conf_local_dir) => {
-                // For when we want to save the toml file
-                // We can do this by creating a .tmp file and renaming it
-                // This minimizes the chance of data being lost if an error
-                // happens mid-write
-                let toml_file = String::from("theme.toml");
-                let tmp_file = format!("{}.tmp", toml_file);
-
-                let toml_file_path = conf_local_dir.join(&toml_file);
-                let tmp_file_path = conf_local_dir.join(&tmp_file);
-
-                let toml_string =
-                    toml::to_string(self).context("Had an issue serializing the toml file")?;
-
-                // Create a .tmp file
-                let mut file =
-                    File::create(&tmp_file_path).context("Failed to make a .tmp file")?;
-                file.write_all(toml_string.as_bytes())
-                    .context("Failed to write theme toml file")?;
-
-                // Rename .tmp file to old file
-                rename(&tmp_file_path, &toml_file_path)
-                    .with_context(|| { format!("Failed to update config file with rename:\ntmp_file: {:?}\nconfig_file:{:?}", tmp_file, toml_file)})?;
-            }
-            Err(e) => {
-                println!("Failed getting the configuration location: {:?}", e);
-                panic!()
-            }
-        }
+        let toml_file_path = get_toml_file()?;
+        // For when we want to save the toml file
+        // We can do this by creating a .tmp file and renaming it
+        // This minimizes the chance of data being lost if an error
+        // happens mid-write
+        let tmp_file_path = toml_file_path.with_extension("toml.tmp");
+
+        let toml_string =
+            toml::to_string(self).context("Had an issue serializing the toml file")?;
+
+        // Create a .tmp file
+        let mut file = File::create(&tmp_file_path).context("Failed to make a .tmp file")?;
+        file.write_all(toml_string.as_bytes())
+            .context("Failed to write theme toml file")?;
+
+        // Rename .tmp file to old file
+        rename(&tmp_file_path, &toml_file_path).with_context(|| {
+            format!(
+                "Failed to update config file with rename:\ntmp_file: {:?}\nconfig_file:{:?}",
+                tmp_file_path, toml_file_path
+            )
+        })?;
         Ok(())
     }
 }
 
-/// Returns a `Result<PathBuf>` of the theme.toml file
+/// Returns a `Result<PathBuf>` of the theme.toml file. A profile uses its
+/// own (i.e. theme.work.toml) if it has one, and the shared one otherwise.
 pub fn get_toml_file() -> Result<PathBuf> {
-    match get_config_dir() {
-        Ok(local_config_dir) => {
-            let toml_f = String::from("theme.toml");
-            let toml_file_path = local_config_dir.join(&toml_f);
+    let local_config_dir = get_config_dir()?;
+    let profile_toml = local_config_dir.join(profile_file_name("theme.toml", false));
+    if profile_toml.exists() {
+        return Ok(profile_toml);
+    }
+    let toml_f = String::from("theme.toml");
+    let toml_file_path = local_config_dir.join(&toml_f);
 
-            Ok(toml_file_path)
-        }
-        Err(e) => {
-            println!("Failed getting the theme toml at: {:?}", e);
-            panic!()
+    Ok(toml_file_path)
+}
+
+/// Parses the contents of a theme.toml file into a `Theme`, returning the
+/// theme elements that were missing from it along with it
+pub fn parse_theme(mut buf: String) -> Result<(Theme, Vec<&'static str>)> {
+    // Check if all theme elements are present
+    let mut added = vec![];
+    for element in Theme::FIELD_NAMES_AS_ARRAY {
+        // If one isn't, add it
+        // This allows the toml file to get read in
+        // by Theme, which can then fill in defaults
+        // as needed
+        if !buf.contains(element) {
+            buf.push_str(&format!("\n[{}]", element));
+            added.push(element);
         }
     }
+
+    let theme: Theme =
+        toml::from_str(&buf).context("Failed to parse toml string to Theme struct")?;
+    Ok((theme, added))
 }
 
 /// Returns a `Result<Theme>` from the theme.toml file
@@ -298,22 +503,11 @@ pub fn read_theme() -> Result<Theme> {
         .read_to_string(&mut buf)
         .context("Failed to read file contents to string")?;
 
-    // Check if all theme elements are present
-    let theme_elements = Theme::FIELD_NAMES_AS_ARRAY;
-    for element in theme_elements {
-        // If one isn't, add it
-        // This allows the toml file to get read in
-        // by Theme, which can then fill in defaults
-        // as needed
-        if !buf.contains(element) {
-            buf.push_str(&format!("\n[{}]", element));
-            println!("Added new theme element [{}] into the theme.toml", element);
-        }
+    let (theme, added) = parse_theme(buf)?;
+    for element in added {
+        println!("Added new theme element [{}] into the theme.toml", element);
     }
 
-    let theme: Theme =
-        toml::from_str(&buf).context("Failed to parse toml string to Theme struct")?;
-
     // Save in case attributes are missing
     // or new theme elements were added in
     // i.e. if user updates to a checklist version
@@ -323,6 +517,15 @@ pub fn read_theme() -> Result<Theme> {
     Ok(theme)
 }
 
+/// Reads the `Theme` from the theme.toml file in use without printing or
+/// saving anything, for when the TUI has the terminal
+pub fn load_theme() -> Result<Theme> {
+    let toml_file_path = get_toml_file()?;
+    let buf = std::fs::read_to_string(&toml_file_path)
+        .with_context(|| format!("Failed to open {:?}", toml_file_path))?;
+    Ok(parse_theme(buf)?.0)
+}
+
 #[cfg(test)]
 mod tests {
     use ratatui::style::{palette::tailwind::SLATE, Color};
@@ -352,12 +555,33 @@ mod tests {
     fn read_from_toml() {
         let theme: Theme = toml::from_str(
             r#"
+        [theme_colors]
         normal_row_bg = '#020617'
         alt_row_bg = '#020600'
         selected_style = '#020650'
+
+        [text_colors]
+
+        [theme_styles]
         "#,
         )
         .unwrap();
         println!("{:?}", theme);
     }
+
+    #[test]
+    fn modifiers_from_names() {
+        assert_eq!(parse_modifiers("").unwrap(), Modifier::empty());
+        assert_eq!(
+            parse_modifiers("bold, Italic").unwrap(),
+            Modifier::BOLD | Modifier::ITALIC
+        );
+        assert!(parse_modifiers("Loud").is_err());
+
+        let (theme, _) = parse_theme(String::from("[text_modifiers]\noverdue = 'Italic'")).unwrap();
+        assert_eq!(theme.text_modifiers.overdue, Modifier::ITALIC);
+        assert_eq!(theme.text_modifiers.title, Modifier::empty());
+        let saved = toml::to_string(&theme).unwrap();
+        assert!(saved.contains("overdue = \"Italic\""));
+    }
 }
@@ -0,0 +1,319 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::Color;
+use toml::Value;
+
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::text::pop_grapheme;
+use crate::display::theme::{
+    parse_theme, Theme, ThemeColors, ThemeModifiers, ThemeStyles, ThemeText,
+};
+use crate::display::tui::App;
+
+/// The named colors `h` and `l` go through, past which any other color can
+/// be typed in, i.e. "#1e293b"
+pub const NAMED_COLORS: [Color; 17] = [
+    Color::Reset,
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Each way dates can be shown, for going through with `h` and `l`
+const DATE_STYLES: [&str; 3] = ["Absolute", "Relative", "Both"];
+
+/// The modifiers `h` and `l` go through one at a time, past which more than
+/// one can be typed in, i.e. "Bold Italic"
+const MODIFIER_CHOICES: [&str; 7] = [
+    "",
+    "Bold",
+    "Italic",
+    "Underlined",
+    "Dim",
+    "Reversed",
+    "CrossedOut",
+];
+
+/// A field of the `Theme`, named by the element it's under and its own name
+/// in theme.toml
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeField {
+    pub element: &'static str,
+    pub name: &'static str,
+}
+
+impl ThemeField {
+    /// Whether the field holds a color, rather than a style or modifiers
+    pub fn is_color(&self) -> bool {
+        matches!(self.element, "theme_colors" | "text_colors")
+    }
+
+    /// Returns what `h` and `l` go through for the field, which is nothing
+    /// for the styles that can be any text
+    pub fn choices(&self) -> Vec<String> {
+        if self.is_color() {
+            NAMED_COLORS.iter().map(Color::to_string).collect()
+        } else if self.element == "text_modifiers" {
+            MODIFIER_CHOICES
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        } else if self.name == "dates" {
+            DATE_STYLES.iter().map(|style| style.to_string()).collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// Returns every field of the `Theme`, in the order they're in theme.toml
+pub fn theme_fields() -> Vec<ThemeField> {
+    let elements: [(&'static str, &[&'static str]); 4] = [
+        ("theme_colors", &ThemeColors::FIELD_NAMES_AS_ARRAY),
+        ("text_colors", &ThemeText::FIELD_NAMES_AS_ARRAY),
+        ("theme_styles", &ThemeStyles::FIELD_NAMES_AS_ARRAY),
+        ("text_modifiers", &ThemeModifiers::FIELD_NAMES_AS_ARRAY),
+    ];
+    elements
+        .into_iter()
+        .flat_map(|(element, names)| names.iter().map(move |&name| ThemeField { element, name }))
+        .collect()
+}
+
+/// Returns what each of the `theme_fields` is set to, as it's written in
+/// theme.toml, or nothing for a scrollbar symbol that's turned off
+pub fn field_values(theme: &Theme) -> Result<Vec<Option<String>>> {
+    let value = Value::try_from(theme).context("Failed to read the theme")?;
+    Ok(theme_fields()
+        .iter()
+        .map(|field| {
+            value
+                .get(field.element)
+                .and_then(|element| element.get(field.name))
+                .and_then(Value::as_str)
+                .map(String::from)
+        })
+        .collect())
+}
+
+/// Returns the `Theme` with the field set to what's given, or back to its
+/// default if nothing is. Fails if it doesn't fit the field, like a color
+/// that isn't one.
+pub fn with_field(theme: &Theme, field: ThemeField, to: Option<&str>) -> Result<Theme> {
+    let mut value = Value::try_from(theme).context("Failed to read the theme")?;
+    if let Some(Value::Table(element)) = value.get_mut(field.element) {
+        match to {
+            Some(to) => element.insert(field.name.to_string(), Value::String(to.to_string())),
+            None => element.remove(field.name),
+        };
+    }
+    value.try_into().with_context(|| match to {
+        Some(to) => format!("'{}' doesn't work for {}", to, field.name),
+        None => format!("Failed to reset {}", field.name),
+    })
+}
+
+/// Registers opening the theme editor
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Edit the theme's colors and styles",
+        key: KeyBinding::key('O'),
+        // Only theme.toml is written to
+        writes: false,
+        run: App::open_theme_editor,
+    })
+}
+
+impl App {
+    /// Opens the editor listing every color and style of the theme, which
+    /// the rest of the TUI shows the changes to as they're made
+    pub fn open_theme_editor(&mut self) -> Result<()> {
+        self.theme_editor_original =
+            toml::to_string(&self.theme).context("Failed to read the theme")?;
+        self.theme_editor = true;
+        self.theme_editor_selected = 0;
+        self.theme_editor_input = None;
+        Ok(())
+    }
+
+    /// Returns the highlighted field
+    pub fn selected_theme_field(&self) -> Option<ThemeField> {
+        theme_fields().get(self.theme_editor_selected).copied()
+    }
+
+    /// Handles keys while the theme editor is open
+    pub fn handle_keys_for_theme_editor(&mut self, key: KeyEvent) -> Result<()> {
+        if let Some(input) = &mut self.theme_editor_input {
+            match key.code {
+                KeyCode::Esc => self.theme_editor_input = None,
+                KeyCode::Enter => {
+                    let typed = input.trim().to_string();
+                    self.theme_editor_input = None;
+                    self.set_theme_field(Some(&typed));
+                }
+                KeyCode::Backspace => pop_grapheme(input),
+                KeyCode::Char(ch) => input.push(ch),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('O') => self.close_theme_editor()?,
+            KeyCode::Char('j') | KeyCode::Down
+                if self.theme_editor_selected + 1 < theme_fields().len() =>
+            {
+                self.theme_editor_selected += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.theme_editor_selected = self.theme_editor_selected.saturating_sub(1)
+            }
+            KeyCode::Char('l') | KeyCode::Right => self.cycle_theme_field(true)?,
+            KeyCode::Char('h') | KeyCode::Left => self.cycle_theme_field(false)?,
+            KeyCode::Enter | KeyCode::Char('e') => {
+                let values = field_values(&self.theme)?;
+                let current = values.get(self.theme_editor_selected).cloned().flatten();
+                self.theme_editor_input = Some(current.unwrap_or_default());
+            }
+            KeyCode::Char('r') => self.set_theme_field(None),
+            KeyCode::Char('s') => self.save_theme_editor()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Sets the highlighted field, or resets it to its default, saying in
+    /// the status bar if what was typed doesn't fit it
+    fn set_theme_field(&mut self, to: Option<&str>) {
+        let Some(field) = self.selected_theme_field() else {
+            return;
+        };
+        match with_field(&self.theme, field, to) {
+            Ok(theme) => self.theme = theme,
+            Err(e) => self.show_toast(format!("{:#}", e)),
+        }
+    }
+
+    /// Moves the highlighted field on to the next (or previous) named color,
+    /// or way of showing dates, starting from the first if it's on neither
+    fn cycle_theme_field(&mut self, forward: bool) -> Result<()> {
+        let Some(field) = self.selected_theme_field() else {
+            return Ok(());
+        };
+        let choices = field.choices();
+        if choices.is_empty() {
+            self.show_toast(format!("Press ENTER to type in the {}", field.name));
+            return Ok(());
+        }
+        let values = field_values(&self.theme)?;
+        let current = values.get(self.theme_editor_selected).cloned().flatten();
+        // Colors can be written more than one way, i.e. "blue" or "Blue"
+        let position = current.and_then(|current| {
+            choices.iter().position(|choice| {
+                choice.eq_ignore_ascii_case(&current)
+                    || (field.is_color()
+                        && Color::from_str(choice).ok() == Color::from_str(&current).ok())
+            })
+        });
+        let next = match (position, forward) {
+            (Some(i), true) => (i + 1) % choices.len(),
+            (Some(i), false) => (i + choices.len() - 1) % choices.len(),
+            (None, _) => 0,
+        };
+        self.set_theme_field(Some(&choices[next]));
+        Ok(())
+    }
+
+    /// Whether the theme has been changed since the editor was opened
+    pub fn theme_edited(&self) -> bool {
+        toml::to_string(&self.theme).is_ok_and(|theme| theme != self.theme_editor_original)
+    }
+
+    /// Closes the editor, putting back the theme it was opened with if the
+    /// changes weren't saved
+    fn close_theme_editor(&mut self) -> Result<()> {
+        if self.theme_edited() {
+            self.theme = parse_theme(self.theme_editor_original.clone())?.0;
+            self.show_toast("Put the theme back, press s to save changes next time");
+        }
+        self.theme_editor = false;
+        Ok(())
+    }
+
+    /// Saves the theme to theme.toml and closes the editor
+    fn save_theme_editor(&mut self) -> Result<()> {
+        self.theme.save()?;
+        self.theme_editor = false;
+        if let Some(preset) = self.config.theme {
+            self.show_toast(format!(
+                "Saved to theme.toml, but the config's \"theme\" is {:?}, which is used instead",
+                preset
+            ));
+        } else {
+            self.show_toast("Saved the theme to theme.toml");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Modifier;
+
+    use super::*;
+
+    #[test]
+    fn test_with_field() {
+        let (theme, _) = parse_theme(String::new()).unwrap();
+        let fields = theme_fields();
+        assert_eq!(fields.len(), field_values(&theme).unwrap().len());
+        let row_bg = fields
+            .iter()
+            .find(|field| field.name == "normal_row_bg")
+            .copied()
+            .unwrap();
+        assert!(row_bg.is_color());
+
+        let edited = with_field(&theme, row_bg, Some("#282828")).unwrap();
+        assert_eq!(edited.theme_colors.normal_row_bg, Color::from_u32(0x282828));
+        assert!(with_field(&theme, row_bg, Some("not a color")).is_err());
+
+        // Nothing puts it back to the default
+        let reset = with_field(&edited, row_bg, None).unwrap();
+        assert_eq!(
+            reset.theme_colors.normal_row_bg,
+            theme.theme_colors.normal_row_bg
+        );
+
+        let dates = ThemeField {
+            element: "theme_styles",
+            name: "dates",
+        };
+        assert_eq!(dates.choices().len(), 3);
+        assert!(with_field(&theme, dates, Some("Relative")).is_ok());
+
+        let critical = ThemeField {
+            element: "text_modifiers",
+            name: "urgency_critical",
+        };
+        assert!(!critical.is_color());
+        let bold = with_field(&theme, critical, Some("bold")).unwrap();
+        assert_eq!(bold.text_modifiers.urgency_critical, Modifier::BOLD);
+        assert!(with_field(&theme, critical, Some("Loud")).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use ratatui::style::palette::tailwind::{EMERALD, SLATE};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::display::theme::{get_toml_file, parse_theme, Theme};
+
+/// A theme that comes with checklist, picked by name with `"theme"` in the
+/// config file, or written out to theme.toml with `checklist theme apply`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    /// The dark slate theme checklist starts out with
+    #[default]
+    Default,
+    /// Dark text on a pale background, for light terminals
+    Light,
+    /// Solarized dark
+    Solarized,
+    /// Gruvbox dark
+    Gruvbox,
+    /// Bright colors on black, for reading at a glance
+    HighContrast,
+}
+
+/// The colors a preset puts in place of the ones the default theme uses
+struct Palette {
+    background: Color,
+    alt_background: Color,
+    selected: Color,
+    status_bar: Color,
+    /// In place of white, for outlines and scrollbars
+    foreground: Color,
+    red: Color,
+    green: Color,
+    yellow: Color,
+    blue: Color,
+    magenta: Color,
+    cyan: Color,
+}
+
+const LIGHT: Palette = Palette {
+    background: Color::from_u32(0xf8fafc),
+    alt_background: Color::from_u32(0xf1f5f9),
+    selected: Color::from_u32(0xcbd5e1),
+    status_bar: Color::from_u32(0xd1fae5),
+    foreground: Color::from_u32(0x0f172a),
+    red: Color::from_u32(0xb91c1c),
+    green: Color::from_u32(0x15803d),
+    yellow: Color::from_u32(0xa16207),
+    blue: Color::from_u32(0x1d4ed8),
+    magenta: Color::from_u32(0xa21caf),
+    cyan: Color::from_u32(0x0e7490),
+};
+
+const SOLARIZED: Palette = Palette {
+    background: Color::from_u32(0x002b36),
+    alt_background: Color::from_u32(0x073642),
+    selected: Color::from_u32(0x29505a),
+    status_bar: Color::from_u32(0x073642),
+    foreground: Color::from_u32(0x93a1a1),
+    red: Color::from_u32(0xdc322f),
+    green: Color::from_u32(0x859900),
+    yellow: Color::from_u32(0xb58900),
+    blue: Color::from_u32(0x268bd2),
+    magenta: Color::from_u32(0xd33682),
+    cyan: Color::from_u32(0x2aa198),
+};
+
+const GRUVBOX: Palette = Palette {
+    background: Color::from_u32(0x282828),
+    alt_background: Color::from_u32(0x32302f),
+    selected: Color::from_u32(0x504945),
+    status_bar: Color::from_u32(0x3c3836),
+    foreground: Color::from_u32(0xebdbb2),
+    red: Color::from_u32(0xfb4934),
+    green: Color::from_u32(0xb8bb26),
+    yellow: Color::from_u32(0xfabd2f),
+    blue: Color::from_u32(0x83a598),
+    magenta: Color::from_u32(0xd3869b),
+    cyan: Color::from_u32(0x8ec07c),
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    background: Color::from_u32(0x000000),
+    alt_background: Color::from_u32(0x121212),
+    selected: Color::from_u32(0x005fd7),
+    status_bar: Color::from_u32(0x000000),
+    foreground: Color::from_u32(0xffffff),
+    red: Color::from_u32(0xff5f5f),
+    green: Color::from_u32(0x5fff5f),
+    yellow: Color::from_u32(0xffff5f),
+    blue: Color::from_u32(0x5fafff),
+    magenta: Color::from_u32(0xff5fff),
+    cyan: Color::from_u32(0x5fffff),
+};
+
+impl Palette {
+    /// Returns what the palette has in place of the default theme's color,
+    /// if it has anything
+    fn swap(&self, color: Color) -> Option<Color> {
+        let swaps = [
+            (SLATE.c950, self.background),
+            (SLATE.c900, self.alt_background),
+            (SLATE.c800, self.selected),
+            (EMERALD.c950, self.status_bar),
+            (Color::White, self.foreground),
+            (Color::Red, self.red),
+            (Color::Green, self.green),
+            (Color::Yellow, self.yellow),
+            (Color::Blue, self.blue),
+            (Color::Magenta, self.magenta),
+            (Color::Cyan, self.cyan),
+        ];
+        swaps
+            .into_iter()
+            .find(|(default, _)| *default == color)
+            .map(|(_, swapped)| swapped)
+    }
+}
+
+impl ThemePreset {
+    fn palette(&self) -> Option<&'static Palette> {
+        match self {
+            ThemePreset::Default => None,
+            ThemePreset::Light => Some(&LIGHT),
+            ThemePreset::Solarized => Some(&SOLARIZED),
+            ThemePreset::Gruvbox => Some(&GRUVBOX),
+            ThemePreset::HighContrast => Some(&HIGH_CONTRAST),
+        }
+    }
+
+    /// Returns the `Theme`, which is the default one with each of its colors
+    /// swapped for the preset's, and its styles left as they are
+    pub fn theme(&self) -> Result<Theme> {
+        let (theme, _) = parse_theme(String::new())?;
+        let Some(palette) = self.palette() else {
+            return Ok(theme);
+        };
+
+        // Going through the fields by name keeps new colors in step
+        let mut value = Value::try_from(&theme).context("Failed to read the default theme")?;
+        for element in ["theme_colors", "text_colors"] {
+            let Some(Value::Table(colors)) = value.get_mut(element) else {
+                continue;
+            };
+            for (_, color) in colors.iter_mut() {
+                let swapped = color
+                    .as_str()
+                    .and_then(|name| Color::from_str(name).ok())
+                    .and_then(|default| palette.swap(default));
+                if let Some(swapped) = swapped {
+                    *color = Value::String(swapped.to_string());
+                }
+            }
+        }
+        value
+            .try_into()
+            .with_context(|| format!("Failed to make the {:?} theme", self))
+    }
+}
+
+/// Writes the preset out to the theme.toml file in use, to change from there.
+/// The theme.toml it replaces is kept next to it as theme.toml.bak, and its
+/// path is returned along with the new one's, if there was one.
+pub fn apply_preset(preset: ThemePreset) -> Result<(PathBuf, Option<PathBuf>)> {
+    let theme = preset.theme()?;
+    let toml_file_path = get_toml_file()?;
+    let mut backup = None;
+    if toml_file_path.exists() {
+        let backup_path = toml_file_path.with_extension("toml.bak");
+        std::fs::copy(&toml_file_path, &backup_path)
+            .with_context(|| format!("Failed to back up {:?}", toml_file_path))?;
+        backup = Some(backup_path);
+    }
+    theme.save()?;
+    Ok((toml_file_path, backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in ThemePreset::value_variants() {
+            preset.theme().unwrap();
+        }
+
+        let default = ThemePreset::Default.theme().unwrap();
+        assert_eq!(default.theme_colors.normal_row_bg, SLATE.c950);
+        assert_eq!(default.text_colors.urgency_critical, Color::Red);
+
+        let gruvbox = ThemePreset::Gruvbox.theme().unwrap();
+        assert_eq!(gruvbox.theme_colors.normal_row_bg, GRUVBOX.background);
+        assert_eq!(gruvbox.theme_colors.tasks_box_outline, GRUVBOX.foreground);
+        assert_eq!(gruvbox.text_colors.urgency_critical, GRUVBOX.red);
+        assert_eq!(gruvbox.theme_styles.highlight_symbol, ">");
+
+        let preset: ThemePreset = serde_json::from_str("\"high-contrast\"").unwrap();
+        assert_eq!(preset, ThemePreset::HighContrast);
+    }
+
+    #[test]
+    fn test_apply_preset() {
+        let (path, backup) = apply_preset(ThemePreset::Gruvbox).unwrap();
+        assert!(backup.is_none());
+        let (theme, missing) = parse_theme(std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(missing.is_empty());
+        assert_eq!(theme.theme_colors.normal_row_bg, GRUVBOX.background);
+
+        let (path, backup) = apply_preset(ThemePreset::Solarized).unwrap();
+        let (old, _) = parse_theme(std::fs::read_to_string(backup.unwrap()).unwrap()).unwrap();
+        assert_eq!(old.theme_colors.normal_row_bg, GRUVBOX.background);
+        let (theme, _) = parse_theme(std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(theme.theme_colors.normal_row_bg, SOLARIZED.background);
+    }
+}
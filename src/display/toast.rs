@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::backend::notify::{Reminder, SnoozeFor};
+use crate::display::tui::App;
+
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// How long a toast offering to undo something stays on screen
+const UNDO_TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// How long a reminder about a task stays on screen, giving time to snooze it
+const REMINDER_TOAST_DURATION: Duration = Duration::from_secs(10);
+
+/// A short message shown in the status bar for a few seconds
+pub struct Toast {
+    pub message: String,
+    shown_at: Instant,
+    duration: Duration,
+    /// Whether to count down the seconds left after the message
+    countdown: bool,
+    /// The task being reminded about, if the toast is a reminder that can be snoozed
+    reminder: Option<Uuid>,
+}
+
+impl Toast {
+    /// Returns the text to show for the toast, or `None` once it has run out
+    fn text(&self) -> Option<String> {
+        let remaining = self.duration.checked_sub(self.shown_at.elapsed())?;
+        if remaining.is_zero() {
+            return None;
+        }
+        if self.countdown {
+            // Round up, so the count never shows 0s while still on screen
+            let seconds = remaining.as_millis().div_ceil(1000);
+            Some(format!("{} ({}s)", self.message, seconds))
+        } else {
+            Some(self.message.clone())
+        }
+    }
+}
+
+impl App {
+    /// Shows a message in the status bar for a few seconds
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            shown_at: Instant::now(),
+            duration: TOAST_DURATION,
+            countdown: false,
+            reminder: None,
+        });
+    }
+
+    /// Shows what was just done in the status bar, with a reminder that it
+    /// can be undone and a countdown until the message goes away
+    pub fn show_undo_toast(&mut self, done: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: format!("{} — press z to undo", done.into()),
+            shown_at: Instant::now(),
+            duration: UNDO_TOAST_DURATION,
+            countdown: true,
+            reminder: None,
+        });
+    }
+
+    /// Shows the reminders held back during quiet hours in the status bar, for
+    /// as long as a single reminder would be shown
+    pub fn show_digest_toast(&mut self, digest: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: format!("⏰ {}", digest.into()),
+            shown_at: Instant::now(),
+            duration: REMINDER_TOAST_DURATION,
+            countdown: true,
+            reminder: None,
+        });
+    }
+
+    /// Shows a reminder about a task in the status bar, along with the keys
+    /// that snooze it and a countdown until it goes away
+    pub fn show_reminder_toast(&mut self, reminder: &Reminder) {
+        let keys: Vec<String> = SnoozeFor::ALL
+            .iter()
+            .map(|snooze| format!("{} {}", snooze.key(), snooze.label()))
+            .collect();
+        self.toast = Some(Toast {
+            message: format!("⏰ {} — snooze: {}", reminder.message(), keys.join(", ")),
+            shown_at: Instant::now(),
+            duration: REMINDER_TOAST_DURATION,
+            countdown: true,
+            reminder: Some(reminder.id),
+        });
+    }
+
+    /// Returns the text of the toast being shown, if it hasn't run out yet
+    pub fn current_toast(&self) -> Option<String> {
+        self.toast.as_ref().and_then(Toast::text)
+    }
+
+    /// Returns the id of the task the toast being shown reminds about, if it's a reminder
+    pub fn current_reminder(&self) -> Option<Uuid> {
+        self.toast
+            .as_ref()
+            .filter(|toast| toast.text().is_some())
+            .and_then(|toast| toast.reminder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast_text() {
+        let toast = |elapsed: u64, countdown: bool| Toast {
+            message: String::from("Completed 'Fix build'"),
+            shown_at: Instant::now() - Duration::from_millis(elapsed),
+            duration: UNDO_TOAST_DURATION,
+            countdown,
+            reminder: None,
+        };
+
+        assert_eq!(toast(0, true).text().unwrap(), "Completed 'Fix build' (5s)");
+        assert_eq!(
+            toast(1500, true).text().unwrap(),
+            "Completed 'Fix build' (4s)"
+        );
+        assert_eq!(toast(1500, false).text().unwrap(), "Completed 'Fix build'");
+        assert!(toast(5000, true).text().is_none());
+    }
+}
@@ -1,4 +1,8 @@
-use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
 use clap::ValueEnum;
 use crossterm::event::KeyModifiers;
 use ratatui::Frame;
@@ -6,23 +10,58 @@ use ratatui::{
     backend::Backend,
     crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
     layout::{Constraint, Layout},
-    widgets::ScrollbarState,
+    style::{Style, Stylize},
+    text::Span,
+    widgets::{ListState, ScrollbarState},
     Terminal,
 };
-use rusqlite::Connection;
+use tracing::{error, trace, warn};
+use uuid::Uuid;
 
-use crate::backend::config::Config;
-use crate::backend::database::{delete_task_in_db, get_all_db_contents, get_db};
-use crate::backend::task::TaskList;
+use crate::backend::calendar::{calendar, today};
+use crate::backend::cli::promoted_message;
+use crate::backend::clipboard::copy_to_clipboard;
+use crate::backend::config::{get_config_dir, profile, Config, Profile};
+use crate::backend::database::{get_db, TaskSelect, DEFAULT_DB_NAME};
+use crate::backend::dependencies::GraphLine;
+use crate::backend::events::TaskEvent;
+use crate::backend::export::write_tasks_csv;
+use crate::backend::handoff::{hand_off, handoff_body};
+use crate::backend::history::{Change, History};
+use crate::backend::notify::{Reminder, SnoozeFor};
+use crate::backend::open::open_uri;
+use crate::backend::query::{DateField, Op, Query};
+use crate::backend::report::session_report;
+use crate::backend::snapshot::open_snapshot;
+use crate::backend::store::{open_store, Storage, TaskStore};
+use crate::backend::task::{Display, RolledUp, SortBy, Status, Task, TaskList};
+use crate::backend::ticket::{task_tickets, ticket_url};
 use crate::display::add::{EntryMode, Inputs, Stage};
+use crate::display::colors::{degrade_buffer, ColorSupport};
+use crate::display::group::{group_rows, GroupBy, GroupRow};
+use crate::display::keymap::{KeyContext, Keymap};
+use crate::display::registry::{Action, KeyBinding, Registry};
 use crate::display::render::{
-    render_delete_popup, render_description_popup, render_help, render_latest_popup,
-    render_name_popup, render_stage_popup, render_state, render_status_bar, render_status_popup,
-    render_tags_popup, render_task_info, render_tasks, render_urgency_popup,
+    render_agenda, render_complete_parent_popup, render_complete_popup, render_completed_on_popup,
+    render_criteria_popup, render_db_picker, render_delete_popup, render_dependency_graph,
+    render_description_popup, render_due_popup, render_exit_popup, render_finder_popup,
+    render_help, render_kanban, render_latest_popup, render_name_popup, render_project_popup,
+    render_recurrence_popup, render_snooze_popup, render_stage_popup, render_state,
+    render_status_bar, render_status_popup, render_tag_manager, render_tags_popup,
+    render_task_info, render_tasks, render_theme_editor, render_time_slot_popup,
+    render_urgency_popup,
 };
+use crate::display::tag_manager::TagEdit;
+use crate::display::text::pop_grapheme;
 use crate::display::theme::Theme;
+use crate::display::toast::Toast;
+use crate::display::window::{page_around, visible_rows, Page};
+
+use self::common::{init_terminal, install_hooks, restore_terminal, resume_terminal};
 
-use self::common::{init_terminal, install_hooks, restore_terminal};
+/// How long the config waits after changing before it's saved, so cycling
+/// through the sorts or filters doesn't write out the file on each press
+const CONFIG_SAVE_DELAY: Duration = Duration::from_secs(1);
 
 pub fn run_tui(
     memory: bool,
@@ -30,23 +69,36 @@ pub fn run_tui(
     config: Config,
     theme: Theme,
     view: Option<LayoutView>,
+    project: Option<String>,
+    as_of: Option<String>,
 ) -> color_eyre::Result<(), anyhow::Error> {
     install_hooks()?;
+    // Open the snapshot before taking over the terminal, so any problem with it shows up plainly
+    let mut app = App::new(memory, testing, config, theme, view, project, as_of)?;
+
     //let _clean_up = CleanUp;
     let terminal = init_terminal()?;
 
-    let mut app = App::new(memory, testing, config, theme, view)?;
-    app.run(terminal)?;
-
+    // The terminal is given back even if the app fails, so the error can be read
+    let result = app.run(terminal);
     restore_terminal()?;
+    result?;
+    if let Some(message) = &app.exit_message {
+        println!("{}", message);
+    }
+    for line in app.session_summary()? {
+        println!("{}", line);
+    }
 
     Ok(())
 }
 
+#[derive(PartialEq)]
 enum Runtime {
     Memory,
     Test,
     Real,
+    Snapshot,
 }
 
 #[derive(Default, PartialEq, Eq, Debug, Clone, ValueEnum)]
@@ -55,6 +107,8 @@ pub enum LayoutView {
     Vertical,
     #[default]
     Smart,
+    /// A board with a column for each `Status`
+    Kanban,
 }
 
 impl LayoutView {
@@ -62,7 +116,35 @@ impl LayoutView {
         match self {
             LayoutView::Smart => *self = LayoutView::Horizontal,
             LayoutView::Horizontal => *self = LayoutView::Vertical,
-            LayoutView::Vertical => *self = LayoutView::Smart,
+            LayoutView::Vertical => *self = LayoutView::Kanban,
+            LayoutView::Kanban => *self = LayoutView::Smart,
+        }
+    }
+}
+
+/// The pane `j` and `k` move through, which `TAB` moves between
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Focus {
+    #[default]
+    Tasks,
+    TaskInfo,
+    State,
+}
+
+impl Focus {
+    fn next(&mut self) {
+        match self {
+            Focus::Tasks => *self = Focus::TaskInfo,
+            Focus::TaskInfo => *self = Focus::State,
+            Focus::State => *self = Focus::Tasks,
+        }
+    }
+
+    fn previous(&mut self) {
+        match self {
+            Focus::Tasks => *self = Focus::State,
+            Focus::TaskInfo => *self = Focus::Tasks,
+            Focus::State => *self = Focus::TaskInfo,
         }
     }
 }
@@ -78,6 +160,8 @@ pub struct ScrollInfo {
     // keys info
     pub keys_scroll_state: ScrollbarState,
     pub keys_scroll: usize,
+    // state
+    pub state_scroll: usize,
 }
 
 #[derive(Default)]
@@ -89,16 +173,19 @@ pub struct CursorInfo {
 pub struct App {
     // Exit condition
     should_exit: bool,
-    // DB connection
-    pub conn: Connection,
+    // Where the tasks are kept
+    pub store: Box<dyn TaskStore>,
     // What type of database connection we have
     runtime: Runtime,
     // Config
     pub config: Config,
     // Theme
     pub theme: Theme,
+    pub color_support: ColorSupport,
     // Layout View
     pub layout_view: LayoutView,
+    // Which pane j and k move through
+    pub focus: Focus,
     // Cursor info
     pub cursor_info: CursorInfo,
     // Task related
@@ -109,6 +196,20 @@ pub struct App {
     list_box_sizing: u16,
     // Popup related
     delete_popup: bool,
+    // Asking before quick completing an important task
+    pub complete_popup: bool,
+    // Asking before quitting with tasks that only live in memory
+    pub exit_popup: bool,
+    // Printed once the terminal is back to normal
+    exit_message: Option<String>,
+    // Every task as the session started, for the summary printed on quitting
+    session_start: Option<Vec<Task>>,
+    // The config as it was last saved (or read in), and when it was found to
+    // have changed since, so it's only written out when it needs to be
+    saved_config: Option<String>,
+    config_changed: Option<Instant>,
+    // Waiting to hand the text being typed over to $EDITOR
+    pub open_editor: bool,
     // Entry related (add, quick_add, or update)
     pub entry_mode: EntryMode,
     // Add related
@@ -125,10 +226,140 @@ pub struct App {
     // Tags filtering
     pub enter_tags_filter: bool,
     pub tags_filter_value: String,
+    // Fuzzy finder
+    pub finder_popup: bool,
+    pub finder_value: String,
+    pub finder_selected: usize,
+    pub finder_tasks: Vec<Task>,
+    // Tag manager
+    pub tag_manager: bool,
+    pub tag_manager_tags: Vec<(String, usize)>,
+    pub tag_manager_selected: usize,
+    pub tag_manager_edit: Option<TagEdit>,
+    pub tag_manager_value: String,
+    pub tag_manager_delete: bool,
+    // Acceptance criteria of the selected task
+    pub criteria_popup: bool,
+    pub criteria_selected: usize,
+    pub criteria_adding: bool,
+    pub criteria_value: String,
+    // Switching databases
+    pub db_picker: bool,
+    pub db_picker_profiles: Vec<Profile>,
+    pub db_picker_selected: usize,
+    // Theme editor
+    pub theme_editor: bool,
+    pub theme_editor_selected: usize,
+    pub theme_editor_input: Option<String>,
+    pub theme_editor_original: String,
+    // Dependency graph
+    pub dependency_graph: bool,
+    pub graph_lines: Vec<GraphLine>,
+    pub graph_selected: usize,
+    // Task history
+    pub show_history: bool,
+    pub task_history: Vec<TaskEvent>,
+    // Full-text search
+    pub enter_search: bool,
+    pub search_value: String,
+    // Query filtering
+    pub enter_query: bool,
+    pub query_value: String,
+    pub query_filter: Option<Query>,
+    pub query_error: Option<String>,
+    // Project filtering
+    pub project_filter: Option<String>,
+    // Keeps tasks where they are instead of re-sorting them
+    pub freeze_order: bool,
+    // Shows today's time slots next to the list
+    pub show_agenda: bool,
+    // Grouping the list into sections
+    pub group_by: GroupBy,
+    pub collapsed_groups: HashSet<String>,
+    pub group_rows: Vec<GroupRow>,
+    pub group_state: ListState,
+    // Actions and status bar segments features have hooked in
+    pub registry: Registry,
     // Quick actions
     quick_action: bool,
+    // Waiting on the second key of `yy`, `yo`, or `yc`
+    clone_action: bool,
+    // Waiting on the second key of `gg` or `dd`, under the vim keymap
+    pending_key: Option<char>,
     // Show help
     pub show_help: bool,
+    // Show archived tasks instead
+    pub show_archive: bool,
+    // How many tasks are tucked away out of the list
+    pub archived_count: usize,
+    pub snoozed_count: usize,
+    pub hidden_urgent: usize,
+    // Whether to list every urgent task, past the `urgent_limit`
+    pub show_all_urgent: bool,
+    // How many tasks there are before filtering
+    pub total_count: usize,
+    // Where the loaded tasks sit in the whole list, and how tall it was last drawn
+    pub page: Page,
+    pub list_height: usize,
+    // What a read-only snapshot was taken as of, if looking at one
+    pub snapshot: Option<String>,
+    // Operations that can be undone or redone
+    pub history: History,
+    // When the tasks were last loaded, to tell what a refresh changed
+    pub loaded_at: Option<DateTime<Local>>,
+    // Tasks with a time slot today, for the agenda
+    pub agenda_tasks: Vec<Task>,
+    // The tasks drilled into to list their subtasks, outermost first
+    pub drill_path: Vec<Uuid>,
+    pub drill_names: Vec<String>,
+    // How many subtasks each listed task with any has
+    pub subtask_counts: HashMap<Uuid, usize>,
+    // What each listed task with open subtasks rolls up from them
+    pub rolled_up: HashMap<Uuid, RolledUp>,
+    // Parent whose subtasks are all done, waiting on whether to complete it
+    pub complete_parent: Option<Task>,
+    // Message shown in the status bar for a moment
+    pub toast: Option<Toast>,
+    // Reminders about tasks coming due, waiting for the status bar to be free
+    pub reminders: VecDeque<Reminder>,
+    // Tasks already reminded about this session, unless snoozed since
+    pub reminded: HashSet<Uuid>,
+    pub reminders_checked: Option<Instant>,
+    // Whether reminders were held back for quiet hours, to show as a digest once they end
+    pub reminders_held: bool,
+    // Whether checking for reminders failed, already said in the status bar
+    pub reminders_failed: bool,
+}
+
+/// Registers promoting scratch sessions, and the status bar segment
+/// saying which database is open
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Promote --memory/--test tasks to real database",
+        key: KeyBinding::key('M'),
+        writes: false,
+        run: |app| {
+            if let Some(message) = app.promote() {
+                app.show_toast(message);
+            }
+            Ok(())
+        },
+    })?;
+    // Make it hard to miss when edits are going somewhere other than usual
+    registry.register_status(|app| {
+        let (database, scratch) = app.database_label()?;
+        let style = if scratch {
+            Style::default()
+                .fg(app.theme.text_colors.scratch_database)
+                .add_modifier(app.theme.text_modifiers.scratch_database)
+        } else {
+            Style::default()
+                .fg(app.theme.text_colors.database)
+                .add_modifier(app.theme.text_modifiers.database)
+        };
+        Some(vec!["DB: ".into(), Span::styled(database, style.bold())])
+    });
+    Ok(())
 }
 
 impl App {
@@ -138,11 +369,21 @@ impl App {
         config: Config,
         theme: Theme,
         view: Option<LayoutView>,
+        project: Option<String>,
+        as_of: Option<String>,
     ) -> Result<Self> {
-        let conn = get_db(memory, testing)?;
+        let store: Box<dyn TaskStore> = match &as_of {
+            Some(as_of) => Box::new(open_snapshot(as_of, memory, testing)?),
+            None if memory || config.storage == Storage::Sqlite => {
+                Box::new(get_db(memory, testing)?)
+            }
+            None => open_store(&config, None)?,
+        };
         let tasklist = TaskList::new();
 
-        let runtime = if memory {
+        let runtime = if as_of.is_some() {
+            Runtime::Snapshot
+        } else if memory {
             Runtime::Memory
         } else if testing {
             Runtime::Test
@@ -151,19 +392,30 @@ impl App {
         };
 
         let layout_view = view.unwrap_or_default();
+        let saved_config = serde_json::to_string(&config).ok();
+        let color_support = config.colors.unwrap_or_else(ColorSupport::detect);
 
         Ok(Self {
             should_exit: false,
-            conn,
+            store,
             runtime,
             config,
             theme,
+            color_support,
             layout_view,
+            focus: Focus::default(),
             cursor_info: CursorInfo::default(),
             tasklist,
             scroll_info: ScrollInfo::default(),
             list_box_sizing: 30,
             delete_popup: false,
+            complete_popup: false,
+            open_editor: false,
+            exit_popup: false,
+            exit_message: None,
+            session_start: None,
+            saved_config,
+            config_changed: None,
             entry_mode: EntryMode::Add,
             add_popup: false,
             add_stage: Stage::default(),
@@ -175,29 +427,144 @@ impl App {
             tags_highlight_value: 0,
             enter_tags_filter: false,
             tags_filter_value: String::new(),
+            finder_popup: false,
+            finder_value: String::new(),
+            finder_selected: 0,
+            finder_tasks: vec![],
+            tag_manager: false,
+            tag_manager_tags: vec![],
+            tag_manager_selected: 0,
+            tag_manager_edit: None,
+            tag_manager_value: String::new(),
+            tag_manager_delete: false,
+            criteria_popup: false,
+            criteria_selected: 0,
+            criteria_adding: false,
+            criteria_value: String::new(),
+            db_picker: false,
+            db_picker_profiles: vec![],
+            db_picker_selected: 0,
+            theme_editor: false,
+            theme_editor_selected: 0,
+            theme_editor_input: None,
+            theme_editor_original: String::new(),
+            dependency_graph: false,
+            graph_lines: vec![],
+            graph_selected: 0,
+            show_history: false,
+            task_history: vec![],
+            enter_search: false,
+            search_value: String::new(),
+            enter_query: false,
+            query_value: String::new(),
+            query_filter: None,
+            query_error: None,
+            project_filter: project,
+            freeze_order: false,
+            show_agenda: false,
+            group_by: GroupBy::default(),
+            collapsed_groups: HashSet::new(),
+            group_rows: vec![],
+            group_state: ListState::default(),
+            registry: Registry::builtin()?,
             quick_action: false,
+            clone_action: false,
+            pending_key: None,
             show_help: false,
+            show_archive: false,
+            archived_count: 0,
+            snoozed_count: 0,
+            hidden_urgent: 0,
+            show_all_urgent: false,
+            total_count: 0,
+            page: Page::default(),
+            list_height: 0,
+            snapshot: as_of,
+            history: History::default(),
+            loaded_at: None,
+            agenda_tasks: vec![],
+            drill_path: vec![],
+            drill_names: vec![],
+            subtask_counts: HashMap::new(),
+            rolled_up: HashMap::new(),
+            complete_parent: None,
+            toast: None,
+            reminders: VecDeque::new(),
+            reminded: HashSet::new(),
+            reminders_checked: None,
+            reminders_held: false,
+            reminders_failed: false,
         })
     }
 
     fn run(&mut self, mut terminal: Terminal<impl Backend>) -> std::io::Result<()> {
-        match self.update_tasklist() {
-            Ok(()) => {}
-            Err(e) => panic!("Got an error dealing with update_tasklist(): {e:?}"),
+        self.update_tasklist()
+            .map_err(|e| std::io::Error::other(format!("{:#}", e)))?;
+        self.start_session();
+        if self.config.notifications && self.snapshot.is_none() {
+            // Not having a notifier around shouldn't stop the app from opening
+            let _ = self
+                .store
+                .notify_due(1, self.config.quiet_hours().ok().flatten());
         }
         while !self.should_exit {
+            self.check_reminders();
+            self.show_next_reminder();
+            // The list may have been drawn taller since it was last read in
+            if let Err(e) = self.fill_page() {
+                error!("Failed reading in the list: {:#}", e);
+                self.show_toast(format!("{:#}", e));
+            }
             terminal.draw(|f| ui(f, &mut *self))?;
+            // Wake up every so often, so toasts go away on their own
+            if !event::poll(Duration::from_millis(250))? {
+                continue;
+            }
             if let Event::Key(key) = event::read()? {
-                match self.handle_key(key) {
-                    Ok(()) => {}
-                    Err(e) => panic!("Got an error handling key: {key:?} - {e:?}"),
+                trace!("Handling {:?}", key);
+                // Whatever went wrong is shown, instead of taking the terminal down with it
+                if let Err(e) = self.handle_key(key) {
+                    error!("Failed handling {:?}: {:#}", key, e);
+                    self.show_toast(format!("{:#}", e));
                 }
+                // The selection or the task may have changed
+                self.load_history();
             };
-            match self.runtime {
-                Runtime::Test => self.config.save(true).unwrap(),
-                Runtime::Real => self.config.save(false).unwrap(),
-                _ => {}
+            if self.open_editor {
+                self.open_editor = false;
+                restore_terminal()?;
+                let edited = self.edit_current_input();
+                resume_terminal(&mut terminal)?;
+                if let Err(e) = edited {
+                    warn!("Editing in the external editor failed: {:#}", e);
+                    self.show_toast(format!("Editing failed: {}", e));
+                }
             }
+            self.save_config(false).map_err(std::io::Error::other)?;
+        }
+        self.save_config(true).map_err(std::io::Error::other)?;
+        Ok(())
+    }
+
+    /// Saves the config if it changed since it was last saved, once
+    /// `CONFIG_SAVE_DELAY` has gone by since it did, or straight away if `now`
+    pub fn save_config(&mut self, now: bool) -> Result<()> {
+        let testing = match self.runtime {
+            Runtime::Test => true,
+            Runtime::Real => false,
+            _ => return Ok(()),
+        };
+        let current =
+            serde_json::to_string(&self.config).context("Failed to write out the config")?;
+        if self.saved_config.as_ref() == Some(&current) {
+            self.config_changed = None;
+            return Ok(());
+        }
+        let changed = *self.config_changed.get_or_insert_with(Instant::now);
+        if now || changed.elapsed() >= CONFIG_SAVE_DELAY {
+            self.config.save(testing)?;
+            self.saved_config = Some(current);
+            self.config_changed = None;
         }
         Ok(())
     }
@@ -218,6 +585,7 @@ impl App {
         }
 
         if self.enter_tags_filter {
+            let key = self.config.keymap.translate(key, KeyContext::Line);
             match key.code {
                 KeyCode::Esc => {
                     self.enter_tags_filter = !self.enter_tags_filter;
@@ -225,19 +593,95 @@ impl App {
                 }
                 KeyCode::Enter => self.enter_tags_filter = !self.enter_tags_filter,
                 KeyCode::Backspace => {
-                    self.tags_filter_value.pop();
+                    pop_grapheme(&mut self.tags_filter_value);
                 }
                 KeyCode::Char(ch) => {
                     self.tags_filter_value.push(ch);
                 }
                 KeyCode::Down => {
                     self.enter_tags_filter = !self.enter_tags_filter;
-                    self.select_next();
+                    self.select_next()?;
                     self.adjust_list_scrollbar_down();
                 }
                 KeyCode::Up => {
                     self.enter_tags_filter = !self.enter_tags_filter;
-                    self.select_previous();
+                    self.select_previous()?;
+                    self.adjust_list_scrollbar_up();
+                }
+                _ => {}
+            }
+            self.update_tasklist()?;
+            return Ok(());
+        }
+
+        if self.finder_popup {
+            return self.handle_keys_for_finder(key);
+        }
+
+        if self.tag_manager {
+            return self.handle_keys_for_tag_manager(key);
+        }
+
+        if self.criteria_popup {
+            return self.handle_keys_for_criteria(key);
+        }
+
+        if self.db_picker {
+            return self.handle_keys_for_db_picker(key);
+        }
+
+        if self.theme_editor {
+            return self.handle_keys_for_theme_editor(key);
+        }
+
+        if self.dependency_graph {
+            return self.handle_keys_for_dependency_graph(key);
+        }
+
+        if self.enter_query {
+            let key = self.config.keymap.translate(key, KeyContext::Line);
+            match key.code {
+                KeyCode::Esc => {
+                    self.enter_query = !self.enter_query;
+                    self.query_value = String::new();
+                    self.query_filter = None;
+                    self.query_error = None;
+                }
+                KeyCode::Enter => self.apply_query(),
+                KeyCode::Backspace => {
+                    pop_grapheme(&mut self.query_value);
+                }
+                KeyCode::Char(ch) => {
+                    self.query_value.push(ch);
+                }
+                _ => {}
+            }
+            self.update_tasklist()?;
+            return Ok(());
+        }
+
+        if self.enter_search {
+            let key = self.config.keymap.translate(key, KeyContext::Line);
+            match key.code {
+                KeyCode::Esc => {
+                    self.enter_search = !self.enter_search;
+                    self.search_value = String::new();
+                }
+                KeyCode::Enter => self.enter_search = !self.enter_search,
+                KeyCode::Backspace => {
+                    pop_grapheme(&mut self.search_value);
+                }
+                KeyCode::Char(ch) => {
+                    self.search_value.push(ch);
+                }
+                KeyCode::Down => {
+                    self.enter_search = !self.enter_search;
+                    self.select_next()?;
+                    self.adjust_list_scrollbar_down();
+                }
+                KeyCode::Up => {
+                    self.enter_search = !self.enter_search;
+                    self.select_previous()?;
                     self.adjust_list_scrollbar_up();
                 }
                 _ => {}
@@ -257,6 +701,14 @@ impl App {
                     self.quick_status()?;
                     self.quick_action = !self.quick_action;
                 }
+                KeyCode::Char('s') => {
+                    self.quick_snooze_setup();
+                    self.quick_action = !self.quick_action;
+                }
+                KeyCode::Char('t') => {
+                    self.quick_time_slot_setup();
+                    self.quick_action = !self.quick_action;
+                }
                 _ => {
                     self.quick_action = !self.quick_action;
                 }
@@ -264,19 +716,67 @@ impl App {
             return Ok(());
         }
 
-        if self.delete_popup {
+        if self.clone_action {
             match key.code {
-                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('d') => {
-                    let current_selection = self.tasklist.state.selected().unwrap();
-                    delete_task_in_db(&self.conn, &self.tasklist.tasks[current_selection])?;
-                    self.update_tasklist()?;
+                KeyCode::Char('y') => self.clone_selected(false)?,
+                KeyCode::Char('o') => self.clone_selected(true)?,
+                KeyCode::Char('c') => self.copy_selected(),
+                _ => {}
+            }
+            self.clone_action = !self.clone_action;
+            return Ok(());
+        }
 
-                    // Sets selector to where it would have been
-                    if current_selection == 0 {
-                        self.tasklist.state.select(Some(current_selection));
-                    } else {
-                        self.tasklist.state.select(Some(current_selection - 1));
+        if self.exit_popup {
+            match key.code {
+                KeyCode::Char('e') | KeyCode::Char('E') => {
+                    self.exit_popup = !self.exit_popup;
+                    self.export_memory_tasks()?;
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    self.exit_popup = !self.exit_popup;
+                    if let Some(message) = self.promote() {
+                        self.exit_message = Some(message);
+                        self.should_exit = true;
                     }
+                }
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Char('y') => {
+                    self.should_exit = true
+                }
+                KeyCode::Char('n')
+                | KeyCode::Char('N')
+                | KeyCode::Char('x')
+                | KeyCode::Esc
+                | KeyCode::Backspace => self.exit_popup = !self.exit_popup,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.complete_parent.is_some() {
+            return self.handle_keys_for_complete_parent(key);
+        }
+
+        if self.complete_popup {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('c') => {
+                    self.set_selected_status(Status::Completed)?;
+                    self.complete_popup = !self.complete_popup
+                }
+                KeyCode::Char('n')
+                | KeyCode::Char('N')
+                | KeyCode::Char('x')
+                | KeyCode::Esc
+                | KeyCode::Backspace => self.complete_popup = !self.complete_popup,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.delete_popup {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('d') => {
+                    self.delete_selected()?;
                     self.delete_popup = !self.delete_popup
                 }
                 KeyCode::Char('n')
@@ -297,6 +797,9 @@ impl App {
                 Stage::Description => self.handle_keys_for_text_inputs(key),
                 Stage::Latest => self.handle_keys_for_text_inputs(key),
                 Stage::Tags => self.handle_keys_for_tags(key),
+                Stage::Recurrence => self.handle_keys_for_text_inputs(key),
+                Stage::Project => self.handle_keys_for_text_inputs(key),
+                Stage::Due => self.handle_keys_for_text_inputs(key),
                 _ => {}
             }
             if self.add_stage == Stage::Finished {
@@ -315,6 +818,12 @@ impl App {
                 Stage::Description => self.handle_keys_for_text_inputs(key),
                 Stage::Latest => self.handle_keys_for_text_inputs(key),
                 Stage::Tags => self.handle_keys_for_tags(key),
+                Stage::Recurrence => self.handle_keys_for_text_inputs(key),
+                Stage::Project => self.handle_keys_for_text_inputs(key),
+                Stage::Due => self.handle_keys_for_text_inputs(key),
+                Stage::Scheduled => self.handle_keys_for_text_inputs(key),
+                Stage::CompletedOn => self.handle_keys_for_text_inputs(key),
+                Stage::TimeSlot => self.handle_keys_for_text_inputs(key),
                 _ => {}
             }
             if self.update_stage == Stage::Finished {
@@ -324,8 +833,83 @@ impl App {
             return Ok(());
         }
 
+        let key = self.config.keymap.translate(key, KeyContext::List);
+
+        // The archive and snapshots are read-only
+        if (self.show_archive || self.snapshot.is_some())
+            && matches!(
+                key.code,
+                KeyCode::Char('a' | 'u' | 'd' | 'q' | 't' | 'y' | 'A' | 'L' | 'z' | 'Z')
+            )
+            && key.modifiers != KeyModifiers::CONTROL
+        {
+            return Ok(());
+        }
+
+        // On the board, arrows move tasks between columns
+        if self.layout_view == LayoutView::Kanban && key.modifiers == KeyModifiers::NONE {
+            let read_only = self.show_archive || self.snapshot.is_some();
+            match key.code {
+                KeyCode::Left | KeyCode::Right if read_only => {}
+                KeyCode::Left => self.kanban_move_selected(false)?,
+                KeyCode::Right => self.kanban_move_selected(true)?,
+                KeyCode::Char('[') => self.kanban_select_column(false),
+                KeyCode::Char(']') => self.kanban_select_column(true),
+                KeyCode::Char('j') | KeyCode::Down if self.focus == Focus::Tasks => {
+                    self.kanban_select_in_column(true)
+                }
+                KeyCode::Char('k') | KeyCode::Up if self.focus == Focus::Tasks => {
+                    self.kanban_select_in_column(false)
+                }
+                _ => return self.handle_main_key(key),
+            }
+            return Ok(());
+        }
+
+        self.handle_main_key(key)
+    }
+
+    /// Handles keys when no popup or prompt is open
+    fn handle_main_key(&mut self, key: KeyEvent) -> Result<()> {
+        // A reminder being shown can be snoozed straight from the status bar
+        if let KeyCode::Char(ch) = key.code {
+            if let Some(snooze) = SnoozeFor::from_key(ch) {
+                if self.current_reminder().is_some() {
+                    return self.snooze_reminder(snooze);
+                }
+            }
+        }
+
+        if self.handle_vim_sequence(key)? {
+            return Ok(());
+        }
+
+        if let Some(action) = self.registry.action_for(&key) {
+            let (run, writes) = (action.run, action.writes);
+            if !(writes && (self.show_archive || self.snapshot.is_some())) {
+                run(self)?;
+            }
+            return Ok(());
+        }
+
+        // Terminals differ on whether SHIFT comes along with it
+        if key.code == KeyCode::BackTab {
+            self.focus.previous();
+            return Ok(());
+        }
+
         match key.modifiers {
             KeyModifiers::CONTROL => match key.code {
+                KeyCode::Char('a') => {
+                    self.show_archive = !self.show_archive;
+                    self.tasklist.state.select(None);
+                    self.update_tasklist()?;
+                }
+                KeyCode::Char('f') => {
+                    self.enter_search = !self.enter_search;
+                    self.search_value = String::new();
+                    self.update_tasklist()?;
+                }
                 KeyCode::Right => self.adjust_listbox_sizing_right(),
                 KeyCode::Left => self.adjust_listbox_sizing_left(),
                 KeyCode::Up | KeyCode::Char('k') => self.adjust_task_info_scrollbar_up(),
@@ -333,42 +917,60 @@ impl App {
                 _ => {}
             },
             KeyModifiers::SHIFT => match key.code {
+                KeyCode::Char('F') => {
+                    self.enter_query = !self.enter_query;
+                    self.query_error = None;
+                }
                 KeyCode::Char('G') => {
-                    self.select_last();
+                    self.select_last()?;
                     self.adjust_list_scrollbar_last();
                 }
+                KeyCode::Char('A') => self.archive_selected()?,
+                KeyCode::Char('L') => self.toggle_lock()?,
+                KeyCode::Char('Z') => self.redo()?,
+                KeyCode::Char('S') => {
+                    self.config.urgency_sort_desc = !self.config.urgency_sort_desc;
+                    self.resort()?;
+                }
                 _ => {}
             },
             KeyModifiers::NONE => match key.code {
-                KeyCode::Char('x') | KeyCode::Esc => self.should_exit = true,
-                KeyCode::Char('v') => self.layout_view.next(),
-                KeyCode::Char('s') => {
-                    self.config.urgency_sort_desc = !self.config.urgency_sort_desc;
+                KeyCode::Char('x') | KeyCode::Esc => self.request_exit()?,
+                KeyCode::Char('v') => {
+                    self.layout_view.next();
+                    // The Kanban board has every listed task on it
                     self.update_tasklist()?;
                 }
+                KeyCode::Char('s') => {
+                    self.config.sort_by.next();
+                    // Asking for a sort re-sorts even when the order is frozen
+                    self.resort()?;
+                }
+                KeyCode::Char('o') => self.toggle_freeze_order()?,
+                KeyCode::Char('P') => self.show_agenda = !self.show_agenda,
+                KeyCode::Char('b') => self.next_group_by()?,
+                KeyCode::Char('r') => self.refresh()?,
+                KeyCode::Char('m') => self.hand_off_selected(),
+                KeyCode::Char('l') => self.open_selected_tickets(),
+                KeyCode::Char(' ') if self.is_grouped() => self.toggle_group()?,
                 KeyCode::Char('f') => {
                     self.config.display_filter.next();
                     self.update_tasklist()?;
                 }
                 KeyCode::Left => self.select_none(),
                 KeyCode::Char('h') => self.show_help = !self.show_help,
-                KeyCode::Char('j') | KeyCode::Down => {
-                    self.select_next();
-                    self.adjust_list_scrollbar_down();
-                }
-                KeyCode::Char('k') | KeyCode::Up => {
-                    self.select_previous();
-                    self.adjust_list_scrollbar_up();
-                }
+                KeyCode::Tab => self.focus.next(),
+                KeyCode::Char('j') | KeyCode::Down => self.move_in_focus(true)?,
+                KeyCode::Char('k') | KeyCode::Up => self.move_in_focus(false)?,
                 KeyCode::Char('g') | KeyCode::Home => {
-                    self.select_first();
+                    self.select_first()?;
                     self.adjust_list_scrollbar_first();
                 }
-                KeyCode::End => self.select_last(),
-                KeyCode::Char('d') => {
-                    if self.tasklist.state.selected().is_some() {
-                        self.delete_popup = !self.delete_popup
-                    }
+                KeyCode::End => self.select_last()?,
+                KeyCode::Char('d')
+                    if self.tasklist.state.selected().is_some() && !self.selected_is_locked() =>
+                {
+                    self.delete_popup = !self.delete_popup
                 }
                 KeyCode::Char('a') => {
                     self.add_popup = !self.add_popup;
@@ -379,7 +981,7 @@ impl App {
                     self.highlight_tags = false;
                     self.tags_highlight_value = 0;
                 }
-                KeyCode::Char('u') => {
+                KeyCode::Char('u') if !self.selected_is_locked() => {
                     if let Some(current_index) = self.tasklist.state.selected() {
                         self.update_popup = !self.update_popup;
                         self.entry_mode = EntryMode::Update;
@@ -392,6 +994,16 @@ impl App {
                 KeyCode::Char('q') => {
                     self.quick_action = !self.quick_action;
                 }
+                KeyCode::Char('y') if self.tasklist.state.selected().is_some() => {
+                    self.clone_action = !self.clone_action;
+                }
+                KeyCode::Char('t') => self.toggle_timer()?,
+                KeyCode::Char('z') => self.undo()?,
+                KeyCode::Char('p') => {
+                    self.next_project_filter()?;
+                    self.update_tasklist()?;
+                }
+                KeyCode::Char('c') => self.clear_filters()?,
                 KeyCode::Char('/') => {
                     self.enter_tags_filter = !self.enter_tags_filter;
                     self.tags_filter_value = String::new();
@@ -404,6 +1016,66 @@ impl App {
         Ok(())
     }
 
+    /// Picks out `gg` and `dd` under the vim keymap, returning true if the
+    /// key pressed was part of one
+    fn handle_vim_sequence(&mut self, key: KeyEvent) -> Result<bool> {
+        let pending = self.pending_key.take();
+        if self.config.keymap != Keymap::Vim || key.modifiers != KeyModifiers::NONE {
+            return Ok(false);
+        }
+        match (pending, key.code) {
+            (Some('g'), KeyCode::Char('g')) => {
+                self.select_first()?;
+                self.adjust_list_scrollbar_first();
+            }
+            (Some('d'), KeyCode::Char('d')) => self.delete_selected()?,
+            (_, KeyCode::Char(ch @ ('g' | 'd'))) => self.pending_key = Some(ch),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Deletes the selected task, unless it's locked, with a toast to undo it
+    fn delete_selected(&mut self) -> Result<()> {
+        let Some(current_selection) = self.tasklist.state.selected() else {
+            return Ok(());
+        };
+        if self.selected_is_locked() {
+            return Ok(());
+        }
+        self.store.delete(&self.tasklist.tasks[current_selection])?;
+        self.history.record(vec![Change::Deleted(
+            self.tasklist.tasks[current_selection].clone(),
+        )]);
+        let name = self.tasklist.tasks[current_selection].name.clone();
+        self.show_undo_toast(format!("Deleted '{}'", name));
+        self.update_tasklist()
+    }
+
+    /// Moves down (or up) through whichever pane has focus: from task to
+    /// task in the list, or a line at a time in the others
+    fn move_in_focus(&mut self, down: bool) -> Result<()> {
+        match (self.focus, down) {
+            (Focus::Tasks, true) => {
+                self.select_next()?;
+                self.adjust_list_scrollbar_down();
+            }
+            (Focus::Tasks, false) => {
+                self.select_previous()?;
+                self.adjust_list_scrollbar_up();
+            }
+            (Focus::TaskInfo, true) => self.adjust_task_info_scrollbar_down(),
+            (Focus::TaskInfo, false) => self.adjust_task_info_scrollbar_up(),
+            (Focus::State, true) => {
+                self.scroll_info.state_scroll = self.scroll_info.state_scroll.saturating_add(1)
+            }
+            (Focus::State, false) => {
+                self.scroll_info.state_scroll = self.scroll_info.state_scroll.saturating_sub(1)
+            }
+        }
+        Ok(())
+    }
+
     fn adjust_list_scrollbar_up(&mut self) {
         self.scroll_info.list_scroll = self.scroll_info.list_scroll.saturating_sub(1);
         self.scroll_info.list_scroll_state = self
@@ -429,7 +1101,7 @@ impl App {
     }
 
     fn adjust_list_scrollbar_last(&mut self) {
-        let task_len = self.tasklist.tasks.len();
+        let task_len = self.page.total;
         self.scroll_info.list_scroll = task_len;
         self.scroll_info.list_scroll_state = self.scroll_info.list_scroll_state.position(task_len);
     }
@@ -468,37 +1140,584 @@ impl App {
 
     fn select_none(&mut self) {
         self.tasklist.state.select(None);
+        self.group_state.select(None);
+    }
+
+    fn select_next(&mut self) -> Result<()> {
+        if self.is_grouped() {
+            let row = self.group_state.selected().map_or(0, |row| row + 1);
+            self.select_row(Some(row));
+            Ok(())
+        } else {
+            let index = self.selected_index().map_or(0, |index| index + 1);
+            self.select_index(Some(index))
+        }
+    }
+    fn select_previous(&mut self) -> Result<()> {
+        if self.is_grouped() {
+            let row = self
+                .group_state
+                .selected()
+                .map_or(usize::MAX, |row| row.saturating_sub(1));
+            self.select_row(Some(row));
+            Ok(())
+        } else {
+            let index = self
+                .selected_index()
+                .map_or(usize::MAX, |index| index.saturating_sub(1));
+            self.select_index(Some(index))
+        }
+    }
+
+    fn select_first(&mut self) -> Result<()> {
+        if self.is_grouped() {
+            self.select_row(Some(0));
+            Ok(())
+        } else {
+            self.select_index(Some(0))
+        }
+    }
+
+    fn select_last(&mut self) -> Result<()> {
+        if self.is_grouped() {
+            self.select_row(Some(usize::MAX));
+            Ok(())
+        } else {
+            self.select_index(Some(usize::MAX))
+        }
+    }
+
+    /// Returns where the selected task is in the whole list, rather than
+    /// in the part of it loaded
+    pub fn selected_index(&self) -> Option<usize> {
+        self.tasklist
+            .state
+            .selected()
+            .map(|selected| self.page.start + selected)
+    }
+
+    /// Selects the task at the `index` of the whole (ungrouped) list, or the
+    /// last one if it's past the end, loading the part of the list it's in
+    /// if it isn't already
+    pub fn select_index(&mut self, index: Option<usize>) -> Result<()> {
+        let index = index
+            .filter(|_| self.page.total > 0)
+            .map(|index| index.min(self.page.total - 1));
+        match index {
+            Some(index) if !self.page.rows(self.tasklist.len()).contains(&index) => {
+                self.load_page(Some(index))
+            }
+            _ => {
+                self.tasklist
+                    .state
+                    .select(index.map(|index| index - self.page.start));
+                self.fill_page()
+            }
+        }
+    }
+
+    /// Selects the `Task` with the id if it's listed, returning whether it is
+    pub fn select_task(&mut self, id: Uuid) -> Result<bool> {
+        if !self.is_paged() {
+            let listed = self.tasklist.tasks.iter().any(|task| task.get_id() == id);
+            if listed {
+                self.tasklist.select_id(id);
+            }
+            return Ok(listed);
+        }
+        let Some(index) = self.store.position(&self.list_select(), id)? else {
+            return Ok(false);
+        };
+        self.select_index(Some(index))?;
+        Ok(true)
+    }
+
+    /// Whether the list is read in a page at a time. Grouping, freezing the
+    /// order, holding back urgent tasks, and the Kanban board all work over
+    /// every listed task, so read them all in.
+    fn is_paged(&self) -> bool {
+        let capped =
+            self.config.urgent_limit.is_some() && !self.show_archive && !self.show_all_urgent;
+        !self.is_grouped()
+            && !self.freeze_order
+            && !capped
+            && self.layout_view != LayoutView::Kanban
+    }
+
+    /// Returns the `TaskSelect` for the tasks to list, filtered and ordered
+    /// in the store, so only the ones listed are read in
+    fn list_select(&self) -> TaskSelect {
+        let mut select = TaskSelect::new()
+            .tags(&self.tags_filter_value, self.config.tag_match)
+            .order_by(self.config.sort_by, self.config.urgency_sort_desc);
+        select = if self.show_archive {
+            // Everything archived is completed, so there's nothing more to narrow down
+            select.archived()
+        } else {
+            select
+                .display(self.config.display_filter)
+                .search(&self.search_value)
+                .parent(self.drill_path.last().copied())
+        };
+        if let Some(project) = &self.project_filter {
+            select = select.project(project);
+        }
+        if let Some(query) = &self.query_filter {
+            select = select.query(query);
+        }
+        select
     }
 
-    fn select_next(&mut self) {
-        self.tasklist.state.select_next();
+    /// Returns every listed task, for when not all of them are read in
+    pub fn list_tasks(&self) -> Result<Vec<Task>> {
+        if self.is_paged() {
+            Ok(self.store.select(&self.list_select())?.tasks)
+        } else {
+            Ok(self.tasklist.tasks.clone())
+        }
     }
-    fn select_previous(&mut self) {
-        self.tasklist.state.select_previous();
+
+    /// Reads in the part of the list around the task at the `index` of the
+    /// whole list, with enough either side to move around in, and selects it
+    fn load_page(&mut self, selected: Option<usize>) -> Result<()> {
+        // Before the list is first drawn, there's at least the selected row
+        let height = self.list_height.max(1);
+        let window = visible_rows(self.page.total, height, selected, self.page.offset);
+        let rows = page_around(window, height, self.page.total);
+        self.tasklist = self.store.select(&self.list_select().rows(rows.clone()))?;
+        self.page.start = rows.start;
+        self.tasklist
+            .state
+            .select(selected.map(|selected| selected - rows.start));
+        self.load_subtask_counts()
     }
 
-    fn select_first(&mut self) {
-        self.tasklist.state.select_first();
+    /// Counts the subtasks of the tasks read in, and what they roll up to
+    fn load_subtask_counts(&mut self) -> Result<()> {
+        let ids: Vec<Uuid> = self.tasklist.tasks.iter().map(|t| t.get_id()).collect();
+        self.subtask_counts = self.store.subtask_counts(&ids)?;
+        self.rolled_up = self.store.roll_up(&ids, &self.config.roll_up)?;
+        Ok(())
     }
 
-    fn select_last(&mut self) {
-        self.tasklist.state.select_last();
+    /// Reads in the rows about to be on screen, if they aren't already, for
+    /// when the list has scrolled past what was read in or is drawn taller
+    pub fn fill_page(&mut self) -> Result<()> {
+        if !self.is_paged() {
+            return Ok(());
+        }
+        let selected = self.selected_index();
+        let window = visible_rows(
+            self.page.total,
+            self.list_height,
+            selected,
+            self.page.offset,
+        );
+        let loaded = self.page.rows(self.tasklist.len());
+        if window.start < loaded.start || window.end > loaded.end {
+            self.load_page(selected)?;
+        }
+        Ok(())
     }
 
+    /// Reloads the tasks from the database, then filters and sorts them.
+    /// The selected task stays selected wherever it ends up, or if it is
+    /// no longer listed, the one nearest to where it was. Only the part of
+    /// the list around it is read in, unless the whole list is needed.
     pub fn update_tasklist(&mut self) -> Result<()> {
-        // Get data
-        let task_list = get_all_db_contents(&self.conn).unwrap();
-        self.tasklist = task_list;
-
-        // Filter tasks
-        self.tasklist.filter_tasks(
-            Some(self.config.display_filter),
-            self.tags_filter_value.clone(),
+        let previous_order: Vec<Uuid> = self.tasklist.tasks.iter().map(|t| t.get_id()).collect();
+        let whole_list = self.page.start == 0 && previous_order.len() == self.page.total;
+        let selected_id = self.tasklist.selected_id();
+        let selected_index = self.selected_index();
+
+        self.loaded_at = Some(Local::now());
+        // Keep track of what is hidden away, so it doesn't get forgotten about
+        self.archived_count = self.store.count_archived()?;
+        let today = today();
+        let snoozed = Query::And(
+            Box::new(Query::Status(Op::NotEq, Status::Completed)),
+            Box::new(Query::Date(DateField::Scheduled, Op::Gt, Some(today))),
         );
+        self.snoozed_count = self.store.count(&TaskSelect::new().query(&snoozed))?;
+        self.total_count = if self.show_archive {
+            self.archived_count
+        } else {
+            self.store.count(&TaskSelect::new())?
+        };
+        // A task drilled into may have been deleted or archived since
+        let drilled = self
+            .store
+            .select(&TaskSelect::new().ids(&self.drill_path))?
+            .tasks;
+        if let Some(gone) = self
+            .drill_path
+            .iter()
+            .position(|id| !drilled.iter().any(|task| task.get_id() == *id))
+        {
+            self.drill_path.truncate(gone);
+        }
+        self.drill_names = self
+            .drill_path
+            .iter()
+            .filter_map(|id| drilled.iter().find(|task| task.get_id() == *id))
+            .map(|task| task.name.clone())
+            .collect();
+        let (start, end) = calendar().day_bounds(today);
+        self.agenda_tasks = self
+            .store
+            .select(
+                &TaskSelect::new().slotted(start.with_timezone(&Local), end.with_timezone(&Local)),
+            )?
+            .tasks;
 
-        // Order tasks here
-        self.tasklist.sort_by_urgency(self.config.urgency_sort_desc);
+        let select = self.list_select();
+        if self.is_paged() {
+            self.hidden_urgent = 0;
+            self.group_rows = vec![];
+            self.page.total = self.store.count(&select)?;
+            // Whichever task moved up into its place takes over from one that's gone
+            let selected = match selected_id {
+                Some(id) => self.store.position(&select, id)?.or(selected_index),
+                None => None,
+            };
+            let selected = selected
+                .filter(|_| self.page.total > 0)
+                .map(|selected| selected.min(self.page.total - 1));
+            return self.load_page(selected);
+        }
+        self.tasklist = self.store.select(&select)?;
 
+        // Hold back the urgent tasks past the limit, so the list isn't a wall of red
+        self.hidden_urgent = match self.config.urgent_limit {
+            Some(limit) if !self.show_archive && !self.show_all_urgent => {
+                self.tasklist.cap_urgent(limit)
+            }
+            _ => 0,
+        };
+        // A page of the list was already in the order it was read in
+        if self.freeze_order && whole_list {
+            self.tasklist.keep_order(&previous_order);
+        }
+        self.group_rows = if self.is_grouped() {
+            group_rows(&mut self.tasklist, self.group_by, &self.collapsed_groups)
+        } else {
+            vec![]
+        };
+
+        if let Some(id) = selected_id {
+            self.tasklist.select_nearest(id, &previous_order);
+        }
+        self.page = Page {
+            start: 0,
+            total: self.tasklist.len(),
+            offset: self.page.offset,
+        };
+
+        self.load_subtask_counts()
+    }
+
+    /// Loads the tasks from the database again, for when something else has
+    /// changed it, and shows how many tasks changed
+    fn refresh(&mut self) -> Result<()> {
+        let changed = match self.loaded_at {
+            Some(loaded_at) => self.store.changed_since(&loaded_at)?,
+            None => None,
+        };
+        self.resort()?;
+        let message = match changed {
+            None => String::from("Refreshed"),
+            Some(0) => String::from("Refreshed, nothing changed"),
+            Some(1) => String::from("Refreshed, 1 task changed"),
+            Some(changed) => format!("Refreshed, {} tasks changed", changed),
+        };
+        self.show_toast(message);
+        Ok(())
+    }
+
+    /// Opens an email (or runs the configured hand off command) with the
+    /// details of the selected task, saying how it went in the status bar
+    fn hand_off_selected(&mut self) {
+        let Some(task) = self
+            .tasklist
+            .state
+            .selected()
+            .map(|i| &self.tasklist.tasks[i])
+        else {
+            return;
+        };
+        let message = match hand_off(task, self.config.handoff_command.as_deref()) {
+            Ok(()) => format!("Handed off '{}'", task.name),
+            Err(e) => format!("Hand off failed: {}", e),
+        };
+        self.show_toast(message);
+    }
+
+    /// Returns how many tasks (archived ones included) would be lost on quitting,
+    /// which is all of them when running in memory
+    pub fn unsaved_count(&self) -> Result<usize> {
+        if self.runtime != Runtime::Memory {
+            return Ok(0);
+        }
+        Ok(self.store.count(&TaskSelect::new())? + self.store.count_archived()?)
+    }
+
+    /// Quits, unless that would lose tasks only kept in memory,
+    /// in which case it asks first
+    fn request_exit(&mut self) -> Result<()> {
+        if self.unsaved_count()? > 0 {
+            self.exit_popup = true;
+        } else {
+            self.should_exit = true;
+        }
+        Ok(())
+    }
+
+    /// Saves the tasks kept in memory to a CSV in the current directory and
+    /// quits, or stays open saying what went wrong if they couldn't be saved
+    fn export_memory_tasks(&mut self) -> Result<()> {
+        let mut tasks = self.store.tasks()?.tasks;
+        tasks.extend(self.store.archived()?.tasks);
+        let path = std::env::current_dir()?.join(format!(
+            "checklist-memory-{}.csv",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        match write_tasks_csv(&path, &tasks) {
+            Ok(()) => {
+                self.exit_message = Some(format!(
+                    "Saved {} task(s) to {}",
+                    tasks.len(),
+                    path.display()
+                ));
+                self.should_exit = true;
+            }
+            Err(e) => self.show_toast(format!("Export failed: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// Copies the tasks of a `--memory` or `--test` session into the real database,
+    /// returning what was promoted, or showing why nothing could be
+    fn promote(&mut self) -> Option<String> {
+        if !matches!(self.runtime, Runtime::Memory | Runtime::Test) {
+            self.show_toast("Only --memory and --test sessions can be promoted");
+            return None;
+        }
+        match get_db(false, false).and_then(|real| self.store.promote_into(&real)) {
+            Ok((promoted, skipped)) => Some(promoted_message(promoted, skipped)),
+            Err(e) => {
+                self.show_toast(format!("Promoting failed: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Returns which database is open, if it isn't the usual one, and whether
+    /// it is a throwaway one (in memory, or the test database)
+    pub fn database_label(&self) -> Option<(String, bool)> {
+        match self.runtime {
+            Runtime::Memory => Some((String::from("in memory"), true)),
+            Runtime::Test => Some((String::from("test"), true)),
+            Runtime::Snapshot => Some((String::from("snapshot"), false)),
+            Runtime::Real => {
+                if let Some(profile) = profile() {
+                    return Some((profile.to_string(), false));
+                }
+                match self.config.storage {
+                    Storage::Sqlite => {}
+                    Storage::Postgres => return Some((String::from("postgres"), false)),
+                    Storage::Json | Storage::Toml => {
+                        let tasks_dir = self.config.tasks_dir();
+                        let name = tasks_dir.file_name()?.to_string_lossy();
+                        return Some((name.into_owned(), false));
+                    }
+                }
+                let default_path = get_config_dir().ok()?.join(DEFAULT_DB_NAME);
+                if self.config.db_path == default_path {
+                    return None;
+                }
+                let name = self.config.db_path.file_name()?.to_string_lossy();
+                Some((name.into_owned(), false))
+            }
+        }
+    }
+
+    /// Returns true if the tasks only live in memory, and are gone on exit
+    pub fn in_memory(&self) -> bool {
+        self.runtime == Runtime::Memory
+    }
+
+    /// Swaps over to the tasks in the `TaskStore` and its `Config`, leaving
+    /// behind the undo history, filters, and reminders of the one before
+    pub fn use_database(&mut self, store: Box<dyn TaskStore>, config: Config) {
+        self.saved_config = serde_json::to_string(&config).ok();
+        self.config_changed = None;
+        self.store = store;
+        self.color_support = config.colors.unwrap_or_else(ColorSupport::detect);
+        self.config = config;
+        self.runtime = Runtime::Real;
+        self.snapshot = None;
+        self.history = History::default();
+        self.show_archive = false;
+        self.show_all_urgent = false;
+        self.project_filter = None;
+        self.query_filter = None;
+        self.query_value.clear();
+        self.search_value.clear();
+        self.tags_filter_value.clear();
+        self.collapsed_groups.clear();
+        self.task_history.clear();
+        self.loaded_at = None;
+        self.drill_path.clear();
+        self.reminders.clear();
+        self.reminded.clear();
+        self.reminders_checked = None;
+        self.reminders_failed = false;
+        self.tasklist.state.select(None);
+        self.page = Page::default();
+        self.start_session();
+    }
+
+    /// Returns every task, archived ones included
+    fn all_tasks(&self) -> Result<Vec<Task>> {
+        let mut tasks = self.store.tasks()?.tasks;
+        tasks.extend(self.store.archived()?.tasks);
+        Ok(tasks)
+    }
+
+    /// Takes note of every task as it is now, when `exit_summary` is on, so
+    /// quitting can say what changed. Snapshots can't change, so are left out.
+    fn start_session(&mut self) {
+        self.session_start = None;
+        if self.config.exit_summary && self.snapshot.is_none() {
+            // Not being able to read the tasks only loses the summary
+            self.session_start = self.all_tasks().ok();
+        }
+    }
+
+    /// Returns the summary of what changed since the session started, or
+    /// nothing if `exit_summary` is off
+    fn session_summary(&self) -> Result<Vec<String>> {
+        match &self.session_start {
+            Some(before) => Ok(session_report(before, &self.all_tasks()?)),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Copies the selected task to the clipboard as text,
+    /// saying how it went in the status bar
+    fn copy_selected(&mut self) {
+        let Some(task) = self
+            .tasklist
+            .state
+            .selected()
+            .map(|i| &self.tasklist.tasks[i])
+        else {
+            return;
+        };
+        let message = match copy_to_clipboard(&handoff_body(task)) {
+            Ok(()) => format!("Copied '{}'", task.name),
+            Err(e) => format!("Copy failed: {}", e),
+        };
+        self.show_toast(message);
+    }
+
+    /// Opens the tickets mentioned in the selected task in the browser,
+    /// saying how it went in the status bar
+    fn open_selected_tickets(&mut self) {
+        let Some(task) = self
+            .tasklist
+            .state
+            .selected()
+            .map(|i| &self.tasklist.tasks[i])
+        else {
+            return;
+        };
+        let Some(template) = self.config.ticket_url.as_deref() else {
+            self.show_toast("Set ticket_url in the config file to open tickets");
+            return;
+        };
+        let tickets = task_tickets(task);
+        let message = if tickets.is_empty() {
+            String::from("No tickets in the selected task")
+        } else {
+            match tickets
+                .iter()
+                .try_for_each(|ticket| open_uri(&ticket_url(template, ticket)))
+            {
+                Ok(()) => format!("Opened {}", tickets.join(", ")),
+                Err(e) => format!("Opening tickets failed: {}", e),
+            }
+        };
+        self.show_toast(message);
+    }
+
+    /// Sorts the tasks again, even if their order is frozen
+    fn resort(&mut self) -> Result<()> {
+        let frozen = self.freeze_order;
+        self.freeze_order = false;
+        let result = self.update_tasklist();
+        self.freeze_order = frozen;
+        result
+    }
+
+    /// Freezes the order of the tasks, so they stay where they are when
+    /// changed, or unfreezes it and sorts them again
+    fn toggle_freeze_order(&mut self) -> Result<()> {
+        self.freeze_order = !self.freeze_order;
+        if self.freeze_order {
+            // Every listed task is read in, to keep them where they are
+            self.update_tasklist()
+        } else {
+            self.resort()
+        }
+    }
+
+    /// Filters on what has been typed into the query prompt, closing it if
+    /// the query is valid. An empty query stops filtering on one.
+    fn apply_query(&mut self) {
+        if self.query_value.trim().is_empty() {
+            self.query_filter = None;
+            self.query_error = None;
+            self.enter_query = false;
+            return;
+        }
+        match self.query_value.parse::<Query>() {
+            Ok(query) => {
+                self.query_filter = Some(query);
+                self.query_error = None;
+                self.enter_query = false;
+            }
+            Err(e) => self.query_error = Some(e.to_string()),
+        }
+    }
+
+    /// Clears every filter (status, tag, search, and project) and puts the
+    /// sort back to the default, most urgent first
+    fn clear_filters(&mut self) -> Result<()> {
+        self.config.display_filter = Display::All;
+        self.config.urgency_sort_desc = true;
+        self.config.sort_by = SortBy::Urgency;
+        self.tags_filter_value = String::new();
+        self.search_value = String::new();
+        self.query_value = String::new();
+        self.query_filter = None;
+        self.query_error = None;
+        self.project_filter = None;
+        self.resort()
+    }
+
+    /// Rotates the project filter through each project in the database,
+    /// going back to no project filter after the last one
+    fn next_project_filter(&mut self) -> Result<()> {
+        let projects = self.store.tasks()?.projects();
+        let next_index = match &self.project_filter {
+            Some(current) => projects
+                .iter()
+                .position(|project| project.to_lowercase() == current.to_lowercase())
+                .map(|i| i + 1),
+            None => Some(0),
+        };
+        self.project_filter = next_index.and_then(|i| projects.get(i).cloned());
         Ok(())
     }
 
@@ -533,6 +1752,16 @@ fn ui(f: &mut Frame, app: &mut App) {
     if app.show_help {
         render_help(f, app, chunks[0]);
         render_status_bar(f, app, chunks[1])
+    } else if app.layout_view == LayoutView::Kanban {
+        let board =
+            Layout::vertical([Constraint::Percentage(65), Constraint::Min(10)]).split(chunks[0]);
+        let details =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Min(25)]).split(board[1]);
+
+        render_kanban(f, app, board[0]);
+        render_task_info(f, app, details[0]);
+        render_state(f, app, details[1]);
+        render_status_bar(f, app, chunks[1]);
     } else {
         let information = if app.layout_view == LayoutView::Vertical {
             Layout::vertical([
@@ -558,8 +1787,15 @@ fn ui(f: &mut Frame, app: &mut App) {
             .split(chunks[0])
         };
 
-        // Render tasks
-        render_tasks(f, app, information[0]);
+        // Render tasks, with today's agenda next to them if it's open
+        if app.show_agenda {
+            let list = Layout::horizontal([Constraint::Min(20), Constraint::Length(34)])
+                .split(information[0]);
+            render_tasks(f, app, list[0]);
+            render_agenda(f, app, list[1]);
+        } else {
+            render_tasks(f, app, information[0]);
+        }
 
         // Render task info
         render_task_info(f, app, information[1]);
@@ -573,10 +1809,51 @@ fn ui(f: &mut Frame, app: &mut App) {
 
     // popup renders
     // delete
+    if app.complete_popup {
+        render_complete_popup(f, app, area);
+    }
+
+    // completing a parent
+    if app.complete_parent.is_some() {
+        render_complete_parent_popup(f, app, area);
+    }
+    if app.exit_popup {
+        render_exit_popup(f, app, area);
+    }
     if app.delete_popup {
         render_delete_popup(f, app, area);
     }
 
+    // finder
+    if app.finder_popup {
+        render_finder_popup(f, app, area);
+    }
+
+    // tag manager
+    if app.tag_manager {
+        render_tag_manager(f, app, area);
+    }
+
+    // acceptance criteria
+    if app.criteria_popup {
+        render_criteria_popup(f, app, area);
+    }
+
+    // database picker
+    if app.db_picker {
+        render_db_picker(f, app, area);
+    }
+
+    // theme editor
+    if app.theme_editor {
+        render_theme_editor(f, app, area);
+    }
+
+    // dependency graph
+    if app.dependency_graph {
+        render_dependency_graph(f, app, area);
+    }
+
     // add
     if app.add_popup {
         match app.add_stage {
@@ -586,6 +1863,9 @@ fn ui(f: &mut Frame, app: &mut App) {
             Stage::Description => render_description_popup(f, app, area),
             Stage::Latest => render_latest_popup(f, app, area),
             Stage::Tags => render_tags_popup(f, app, area),
+            Stage::Recurrence => render_recurrence_popup(f, app, area),
+            Stage::Project => render_project_popup(f, app, area),
+            Stage::Due => render_due_popup(f, app, area),
             _ => {}
         }
     }
@@ -599,9 +1879,17 @@ fn ui(f: &mut Frame, app: &mut App) {
             Stage::Description => render_description_popup(f, app, area),
             Stage::Latest => render_latest_popup(f, app, area),
             Stage::Tags => render_tags_popup(f, app, area),
+            Stage::Recurrence => render_recurrence_popup(f, app, area),
+            Stage::Project => render_project_popup(f, app, area),
+            Stage::Due => render_due_popup(f, app, area),
+            Stage::Scheduled => render_snooze_popup(f, app, area),
+            Stage::CompletedOn => render_completed_on_popup(f, app, area),
+            Stage::TimeSlot => render_time_slot_popup(f, app, area),
             _ => {}
         }
     }
+
+    degrade_buffer(f.buffer_mut(), app.color_support);
 }
 
 mod common {
@@ -631,6 +1919,15 @@ mod common {
         Terminal::new(CrosstermBackend::new(stdout()))
     }
 
+    /// Takes the terminal back over after `restore_terminal`, i.e. once an
+    /// editor the app handed off to has exited
+    pub fn resume_terminal(terminal: &mut Terminal<impl Backend>) -> io::Result<()> {
+        stdout().execute(EnterAlternateScreen)?;
+        enable_raw_mode()?;
+        // Everything on screen is gone, so draw it all again
+        terminal.clear()
+    }
+
     /// Restore the terminal to its original state.
     pub fn restore_terminal() -> io::Result<()> {
         disable_raw_mode()?;
@@ -669,3 +1966,126 @@ mod common {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::backend::task::RollUp;
+    use crate::display::theme_presets::ThemePreset;
+
+    /// Keeps count of how many tasks are read out of the database under it
+    struct CountingStore {
+        conn: Connection,
+        read: Rc<Cell<usize>>,
+    }
+
+    impl CountingStore {
+        fn counted(&self, task_list: Result<TaskList>) -> Result<TaskList> {
+            let task_list = task_list?;
+            self.read.set(self.read.get() + task_list.len());
+            Ok(task_list)
+        }
+    }
+
+    impl TaskStore for CountingStore {
+        fn add(&self, task: &Task) -> Result<()> {
+            self.conn.add(task)
+        }
+
+        fn update(&self, task: &Task) -> Result<()> {
+            self.conn.update(task)
+        }
+
+        fn delete(&self, task: &Task) -> Result<()> {
+            self.conn.delete(task)
+        }
+
+        fn archive(&self, tasks: &[Task]) -> Result<()> {
+            self.conn.archive(tasks)
+        }
+
+        fn tasks(&self) -> Result<TaskList> {
+            self.counted(self.conn.tasks())
+        }
+
+        fn archived(&self) -> Result<TaskList> {
+            self.counted(self.conn.archived())
+        }
+
+        fn count_archived(&self) -> Result<usize> {
+            self.conn.count_archived()
+        }
+
+        fn select(&self, select: &TaskSelect) -> Result<TaskList> {
+            self.counted(self.conn.select(select))
+        }
+
+        fn count(&self, select: &TaskSelect) -> Result<usize> {
+            self.conn.count(select)
+        }
+
+        fn position(&self, select: &TaskSelect, id: Uuid) -> Result<Option<usize>> {
+            self.conn.position(select, id)
+        }
+
+        fn subtask_counts(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, usize>> {
+            self.conn.subtask_counts(ids)
+        }
+
+        fn roll_up(&self, ids: &[Uuid], fields: &[RollUp]) -> Result<HashMap<Uuid, RolledUp>> {
+            self.conn.roll_up(ids, fields)
+        }
+
+        fn changed_since(&self, since: &DateTime<Local>) -> Result<Option<usize>> {
+            self.conn.changed_since(since)
+        }
+    }
+
+    #[test]
+    fn read_only_a_page() {
+        let theme = ThemePreset::Default.theme().unwrap();
+        let mut app = App::new(
+            true,
+            true,
+            Config::new(PathBuf::new()),
+            theme,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let read = Rc::new(Cell::new(0));
+        let store = CountingStore {
+            conn: get_db(true, true).unwrap(),
+            read: Rc::clone(&read),
+        };
+        for i in 0..1000 {
+            let task = Task::new(format!("Task {}", i), None, None, None, None, None);
+            store.add(&task).unwrap();
+        }
+        app.use_database(Box::new(store), Config::new(PathBuf::new()));
+        app.list_height = 20;
+        assert!(app.is_paged());
+
+        // Refreshing reads in the page around what's on screen, not every task
+        let page_size = page_around(0..20, 20, 1000).len();
+        read.set(0);
+        app.update_tasklist().unwrap();
+        assert_eq!(app.page.total, 1000);
+        assert_eq!(app.total_count, 1000);
+        assert!(read.get() <= page_size, "read {} tasks", read.get());
+
+        // As does moving to the far end of the list
+        read.set(0);
+        app.select_last().unwrap();
+        app.fill_page().unwrap();
+        assert_eq!(app.selected_index(), Some(999));
+        assert!(read.get() <= page_size, "read {} tasks", read.get());
+    }
+}
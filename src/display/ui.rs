@@ -9,8 +9,9 @@ use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateS
 use crossterm::{cursor, execute, ExecutableCommand, QueueableCommand};
 use rusqlite::Connection;
 
+use crate::backend::collate::sort_text;
 use crate::backend::database::{get_all_db_contents, get_db};
-use crate::backend::task::{Display, Task, TaskList};
+use crate::backend::task::{Display, TagMatch, Task, TaskList};
 
 struct CleanUp;
 
@@ -271,6 +272,7 @@ impl Renderer {
         self.taskinfo.total_tasklist.filter_tasks(
             Some(self.taskinfo.display_filter),
             self.taskinfo.tags_filter.clone(),
+            TagMatch::Any,
         );
 
         // Order tasks here
@@ -282,13 +284,13 @@ impl Renderer {
     }
 
     fn update_task_window(&mut self) {
-        let current_tasks_in_window: &[Task];
-        if self.taskinfo.total_tasklist.len() <= self.taskwindow.tasks_that_can_fit as usize {
-            current_tasks_in_window = &self.taskinfo.total_tasklist.tasks[0..];
-        } else {
-            current_tasks_in_window = &self.taskinfo.total_tasklist.tasks
-                [self.taskwindow.window_start as usize..=self.taskwindow.window_end as usize]
-        }
+        let current_tasks_in_window: &[Task] =
+            if self.taskinfo.total_tasklist.len() <= self.taskwindow.tasks_that_can_fit as usize {
+                &self.taskinfo.total_tasklist.tasks[0..]
+            } else {
+                &self.taskinfo.total_tasklist.tasks
+                    [self.taskwindow.window_start as usize..=self.taskwindow.window_end as usize]
+            };
 
         self.taskinfo.display_tasklist = TaskList::from(current_tasks_in_window.to_vec());
     }
@@ -308,7 +310,7 @@ impl Renderer {
             let name = task.name.clone();
             let task_tags = task.tags.clone().unwrap_or(HashSet::new());
             let mut task_tags_vec: Vec<&String> = task_tags.iter().collect();
-            task_tags_vec.sort();
+            sort_text(&mut task_tags_vec);
             //task_tags_vec.sort_by(|a, b| a.cmp(b));
 
             // Print out tasks
@@ -366,7 +368,7 @@ impl Renderer {
 
         let task_tags = current_task.tags.clone().unwrap_or_default();
         let mut task_tags_vec: Vec<&String> = task_tags.iter().collect();
-        task_tags_vec.sort();
+        sort_text(&mut task_tags_vec);
 
         let column = self.detail_box_start.0 + 1;
         let mut row = self.detail_box_start.1 + 1;
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Span;
+
+use crate::display::registry::{Action, KeyBinding, Registry};
+use crate::display::tui::App;
+
+/// Registers showing every urgent task past the `urgent_limit`, and a status
+/// bar segment saying how many are held back
+pub fn register(registry: &mut Registry) -> Result<()> {
+    registry.register_action(Action {
+        description: "Show all Critical and High tasks, past the urgent_limit",
+        key: KeyBinding::key('U'),
+        writes: false,
+        run: App::toggle_all_urgent,
+    })?;
+    registry.register_status(|app| {
+        if app.hidden_urgent == 0 {
+            return None;
+        }
+        Some(vec![
+            Span::styled(
+                format!("{} more urgent", app.hidden_urgent),
+                Style::default()
+                    .fg(app.theme.text_colors.urgency_critical)
+                    .add_modifier(app.theme.text_modifiers.urgency_critical)
+                    .bold(),
+            ),
+            Span::styled(" (U)", Style::default().dim()),
+        ])
+    });
+    Ok(())
+}
+
+impl App {
+    /// Switches between listing only the first `urgent_limit` Critical and
+    /// High tasks, and listing all of them
+    pub fn toggle_all_urgent(&mut self) -> Result<()> {
+        let Some(limit) = self.config.urgent_limit else {
+            self.show_toast(String::from(
+                "Set \"urgent_limit\" in the config file to only list that many Critical and High tasks at once",
+            ));
+            return Ok(());
+        };
+        self.show_all_urgent = !self.show_all_urgent;
+        self.update_tasklist()?;
+        let message = if self.show_all_urgent {
+            String::from("Showing every urgent task")
+        } else {
+            format!("Showing the first {} urgent tasks", limit)
+        };
+        self.show_toast(message);
+        Ok(())
+    }
+}
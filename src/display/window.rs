@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+/// Returns the rows of a list `total` rows long that fit in `height` rows on
+/// screen, starting from the `offset` it was last scrolled to and moved just
+/// enough to keep the `selected` row in view. Only these rows need to be built
+/// each frame, however long the list is.
+pub fn visible_rows(
+    total: usize,
+    height: usize,
+    selected: Option<usize>,
+    offset: usize,
+) -> Range<usize> {
+    // Fill the screen, even once the list has shrunk from under the offset
+    let mut start = offset.min(total.saturating_sub(height));
+    if let Some(selected) = selected.filter(|selected| *selected < total) {
+        if selected < start {
+            start = selected;
+        } else if height > 0 && selected >= start + height {
+            start = selected + 1 - height;
+        }
+    }
+    start..(start + height).min(total)
+}
+
+/// The fewest rows to load either side of those on screen, so moving around
+/// a little doesn't go back to the database on every key
+const PAGE_MARGIN: usize = 50;
+
+/// Where the loaded part of a list sits in the whole of it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Page {
+    /// The row the loaded part starts at
+    pub start: usize,
+    /// How many rows there are in the whole list
+    pub total: usize,
+    /// The row the list is scrolled to
+    pub offset: usize,
+}
+
+impl Page {
+    /// Returns the rows of the whole list the loaded part of it covers,
+    /// given how many rows were loaded
+    pub fn rows(&self, loaded: usize) -> Range<usize> {
+        self.start..self.start + loaded
+    }
+}
+
+/// Returns the rows to load for the `window` on screen of a list `total`
+/// rows long, with a screen's worth (or more) either side of it to move into
+pub fn page_around(window: Range<usize>, height: usize, total: usize) -> Range<usize> {
+    let margin = height.max(PAGE_MARGIN);
+    window.start.saturating_sub(margin)..(window.end + margin).min(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_rows() {
+        // Shorter than the screen
+        assert_eq!(visible_rows(3, 10, Some(2), 0), 0..3);
+        // Stays put while the selection is in view
+        assert_eq!(visible_rows(1000, 10, Some(15), 10), 10..20);
+        // Scrolls down just enough to show the selection
+        assert_eq!(visible_rows(1000, 10, Some(25), 10), 16..26);
+        // And back up
+        assert_eq!(visible_rows(1000, 10, Some(3), 10), 3..13);
+        // A list that shrank still fills the screen
+        assert_eq!(visible_rows(12, 10, None, 500), 2..12);
+        assert_eq!(visible_rows(0, 10, None, 4), 0..0);
+        assert_eq!(visible_rows(50, 0, Some(5), 0), 0..0);
+    }
+
+    #[test]
+    fn test_page_around() {
+        // A screen either side of the window
+        assert_eq!(page_around(500..560, 60, 1000), 440..620);
+        // But never less than the margin
+        assert_eq!(page_around(500..510, 10, 1000), 450..560);
+        // Cut off at either end of the list
+        assert_eq!(page_around(0..10, 10, 30), 0..30);
+        assert_eq!(page_around(990..1000, 10, 1000), 940..1000);
+        assert_eq!(page_around(0..0, 0, 0), 0..0);
+
+        let page = Page {
+            start: 440,
+            total: 1000,
+            offset: 500,
+        };
+        assert_eq!(page.rows(180), 440..620);
+    }
+}
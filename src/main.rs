@@ -1,16 +1,48 @@
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use tracing::error;
 
 mod backend;
 mod display;
 
-use backend::config::{get_config_dir, read_config, set_new_path};
-use backend::database::{create_sqlite_db, get_db};
+use backend::archive::{archive_completed, parse_age};
+use backend::caldav::{sync_caldav, CaldavAccount};
+use backend::calendar::set_calendar;
+use backend::cli::{
+    add_task, clone_task, complain, complete_task, depend_task, encrypt_db, import_file,
+    import_taskwarrior_file, import_todoist_source, list_tasks, merge_tags, open_ticket,
+    plan_tasks, promote_session, remove_tag, rename_tag, run_doctor, say, search_tasks, set_quiet,
+    set_task_lock, show_graph, show_history, show_profiles, show_task, update_task, watch_tasks,
+    CommandStatus, ListFilters, NewTask, QrContent, TaskChanges,
+};
+use backend::collate::set_collation;
+use backend::config::{
+    get_config_dir, get_config_file, profile_file_name, read_config, set_new_path, set_profile,
+    PROFILE_VAR,
+};
+use backend::database::{create_sqlite_db, get_db, DEFAULT_DB_NAME};
+use backend::error::ChecklistError;
+use backend::export::{print_export, ExportFormat};
+use backend::hook::pre_commit;
+use backend::log::init_logging;
+use backend::notify::{notify_due_tasks, Delivery};
+use backend::plan::{parse_capacity, DEFAULT_CAPACITY};
+use backend::query::Query;
+use backend::report::{
+    print_completed_report, print_digest, print_focus_report, print_stats, print_time_report,
+    FocusBy,
+};
+use backend::sync::{serve, sync_tasks, DEFAULT_LISTEN};
+use backend::task::{CompleteParents, Display, Status, TagMatch, Urgency};
 use backend::wipe::wipe_tasks;
 
+use display::prompt::run_prompt;
 use display::theme::{create_empty_theme_toml, get_toml_file, read_theme};
+use display::theme_presets::{apply_preset, ThemePreset};
 use display::tui::{run_tui, LayoutView};
 use display::ui::run_ui;
 
@@ -27,6 +59,28 @@ struct Cli {
     #[arg(short, long)]
     test: bool,
 
+    /// Copies every task in the test database into the real one,
+    /// skipping any already there. An in-memory database is gone
+    /// once checklist exits, so promote those from the TUI with 'M'.
+    #[arg(long, requires = "test", conflicts_with = "memory")]
+    promote: bool,
+
+    /// Only print errors. Useful in scripts that
+    /// just need the exit code of a command.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Use a separate database and config, i.e. "work" or "personal".
+    /// Can also be picked with the CHECKLIST_PROFILE environment variable
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Log more to the files in the logs folder of the config directory:
+    /// -v for info, -vv for debug, -vvv for everything.
+    /// Warnings and errors are always logged.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -43,6 +97,10 @@ enum Commands {
         set: Option<PathBuf>,
     },
 
+    /// Encrypts the database with a passphrase, asked for whenever it's opened.
+    /// Needs checklist built with `--features encryption`
+    Encrypt,
+
     /// Wipe tasks in the database
     Wipe {
         /// Bypass confirmation check
@@ -55,6 +113,378 @@ enum Commands {
         hard: bool,
     },
 
+    /// Adds a task
+    Add {
+        /// Name of the task
+        name: String,
+
+        /// Description of the task
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Urgency of the task
+        #[arg(short, long, value_enum)]
+        urgency: Option<Urgency>,
+
+        /// Status of the task
+        #[arg(short, long, value_enum)]
+        status: Option<Status>,
+
+        /// Tag for the task. Can be passed in more than once
+        #[arg(short, long)]
+        tag: Vec<String>,
+
+        /// Project the task belongs to
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Due date for the task, as YYYY-MM-DD, or in words like tomorrow, fri,
+        /// next tuesday, or "in 3 days"
+        #[arg(long)]
+        due: Option<String>,
+
+        /// When the task was originally created, as YYYY-MM-DD, YYYY-MM-DD HH:MM,
+        /// or an RFC3339 timestamp. Handy for tasks brought over from elsewhere
+        #[arg(long)]
+        date_added: Option<String>,
+
+        /// Where the task came from in another system, as SYSTEM:ID (i.e. github:42).
+        /// If a task already has it, nothing is added, so imports can be run again
+        #[arg(long)]
+        external_ref: Option<String>,
+
+        /// Link to the task in the other system, to go with --external-ref
+        #[arg(long)]
+        external_url: Option<String>,
+
+        /// Name (or the start of the id) of the task to add it as a subtask of
+        #[arg(long)]
+        parent: Option<String>,
+    },
+
+    /// Prints tasks as a plain list
+    List {
+        /// Which tasks to show based on their status. Defaults to not-completed,
+        /// or all when using --query
+        #[arg(short, long, value_enum)]
+        status: Option<Display>,
+
+        /// Only show tasks with tags containing these, separated by commas or spaces.
+        /// Tags starting with '-' leave out the tasks that have them
+        #[arg(short, long, default_value_t = String::new())]
+        tag: String,
+
+        /// Whether tasks need any or all of the tags given to --tag.
+        /// Defaults to the tag_match in the config
+        #[arg(long, value_enum)]
+        tag_match: Option<TagMatch>,
+
+        /// Only show tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only show tasks matching a query, like
+        /// 'status:open AND (tag:work OR urgency>=high) due<2025-01-01'
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Keep the list up to date, re-printing it whenever tasks change
+        #[arg(short, long)]
+        watch: bool,
+
+        /// How often to check for changes when watching, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+
+        /// Exit with a non-zero code if no tasks are listed
+        #[arg(long)]
+        fail_if_empty: bool,
+    },
+
+    /// Searches the name, description, and latest update of tasks, best matches first
+    Search {
+        /// Words to search for. Each matches any word starting with it
+        query: String,
+
+        /// Which tasks to show based on their status
+        #[arg(short, long, value_enum, default_value_t = Display::All)]
+        status: Display,
+    },
+
+    /// Prints out the details of a task. If more than one task matches,
+    /// you will be asked to pick between them
+    Show {
+        /// Name (or the start of the id) of the task to show
+        query: String,
+
+        /// Also print a QR code of the task, for scanning it onto a phone.
+        /// Holds the task's summary, or a checklist:// link to it with --qr uri
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "summary")]
+        qr: Option<QrContent>,
+    },
+
+    /// Prints how a task changed over time, field by field. If more than one
+    /// task matches, you will be asked to pick between them
+    History {
+        /// Name (or the start of the id) of the task
+        query: String,
+    },
+
+    /// Makes a task wait on another one, so it's blocked until that one is
+    /// done. If more than one task matches, you will be asked to pick between them
+    Depend {
+        /// Name (or the start of the id) of the task that has to wait
+        query: String,
+
+        /// Name (or the start of the id) of the task it waits on
+        #[arg(long)]
+        on: String,
+
+        /// Stop it waiting instead
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Prints the chain of dependencies around a task: what waits on it,
+    /// and what it waits on, as far as each goes
+    Graph {
+        /// Name (or the start of the id) of the task
+        query: String,
+    },
+
+    /// Opens the tickets (i.e. ABC-123) mentioned in a task in the browser, using
+    /// the "ticket_url" set in the config file. If more than one task matches,
+    /// you will be asked to pick between them
+    OpenTicket {
+        /// Name (or the start of the id) of the task with the tickets
+        query: String,
+    },
+
+    /// Locks a task so it can't be updated, completed, or deleted until it is
+    /// unlocked. If more than one task matches, you will be asked to pick between them
+    Lock {
+        /// Name (or the start of the id) of the task to lock
+        query: String,
+    },
+
+    /// Unlocks a locked task. If more than one task matches,
+    /// you will be asked to pick between them
+    Unlock {
+        /// Name (or the start of the id) of the task to unlock
+        query: String,
+    },
+
+    /// Renames, merges, or removes a tag across every task, archived ones included.
+    /// Locked tasks are left as they are.
+    Tag {
+        #[command(subcommand)]
+        action: TagActions,
+    },
+
+    /// Adds a copy of a task, with its own id. If more than one task matches,
+    /// you will be asked to pick between them
+    Clone {
+        /// Name (or the start of the id) of the task to copy
+        query: String,
+
+        /// Start the copy out as Open instead of keeping the status
+        #[arg(short, long)]
+        open: bool,
+    },
+
+    /// Marks a task as Completed. If more than one task matches,
+    /// you will be asked to pick between them
+    Done {
+        /// Name (or the start of the id) of the task to complete
+        query: String,
+
+        /// Only consider tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Updates a task. If more than one task matches,
+    /// you will be asked to pick between them
+    Update {
+        /// Name (or the start of the id) of the task to update
+        query: String,
+
+        /// New name for the task
+        #[arg(long)]
+        name: Option<String>,
+
+        /// New status for the task
+        #[arg(short, long, value_enum)]
+        status: Option<Status>,
+
+        /// New urgency for the task
+        #[arg(short, long, value_enum)]
+        urgency: Option<Urgency>,
+
+        /// New description for the task. Pass in "" to clear it
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// New latest update for the task. Pass in "" to clear it
+        #[arg(short, long)]
+        latest: Option<String>,
+
+        /// New project for the task. Pass in "" to clear it
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// New due date for the task, as YYYY-MM-DD, or in words like tomorrow,
+        /// fri, or "in 3 days". Pass in "" to clear it
+        #[arg(long)]
+        due: Option<String>,
+
+        /// Snooze the task, hiding it until this date (YYYY-MM-DD, or in words
+        /// like tomorrow, next mon, or "in 2 weeks"). Pass in "" to unsnooze it
+        #[arg(long)]
+        snooze: Option<String>,
+
+        /// When the task was completed, as YYYY-MM-DD HH:MM, YYYY-MM-DD, or yesterday.
+        /// Only for completed tasks
+        #[arg(long)]
+        completed_on: Option<String>,
+
+        /// Acceptance criterion to add, which has to be ticked off before the
+        /// task can be completed. Can be passed in more than once
+        #[arg(long)]
+        criterion: Vec<String>,
+
+        /// Number of the acceptance criterion to tick off, counting from 1.
+        /// Can be passed in more than once
+        #[arg(long)]
+        tick: Vec<usize>,
+    },
+
+    /// Moves completed tasks into the archive, where they can still
+    /// be seen from the TUI (CTRL a) but no longer clutter the task list
+    Archive {
+        /// Only archive tasks completed longer ago than this, i.e. 12h, 30d or 2w
+        #[arg(long, default_value_t = String::from("30d"))]
+        older_than: String,
+    },
+
+    /// Sends desktop notifications for tasks that are overdue or due soon.
+    /// Suitable for running from cron
+    Notify {
+        /// How many days ahead count as due soon
+        #[arg(short, long, default_value_t = 1)]
+        days: u64,
+    },
+
+    /// Prints a summary of what's overdue, what's due today, and what was
+    /// completed yesterday. Suitable for running from cron each morning
+    Digest {
+        /// Hand the digest to the `digest_command` in the config file instead
+        /// of printing it, i.e. to email it
+        #[arg(long)]
+        send: bool,
+    },
+
+    /// Suggests which of the coming days to do the open tasks on, going by their
+    /// due dates and estimates (the length of their time slots), without putting
+    /// more on a day than it has room for. Once you agree, each task is scheduled
+    /// for its day, hiding it until then
+    Plan {
+        /// How many days to plan, starting today
+        #[arg(short, long, default_value_t = 7)]
+        days: u64,
+
+        /// How much time a day has for tasks, i.e. 6h or 4h30m. Defaults to
+        /// `daily_capacity` in the config file, or 6h
+        #[arg(short, long)]
+        capacity: Option<String>,
+
+        /// Schedule the tasks without asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Helpers meant to be run from git hooks
+    Hook {
+        #[command(subcommand)]
+        hook: Hooks,
+    },
+
+    /// Prints out reports on your tasks
+    Report {
+        #[command(subcommand)]
+        report: Reports,
+    },
+
+    /// Prints a summary of your tasks for a weekly review: how many there are per
+    /// status and per tag, how many were completed lately, and the oldest open ones
+    Stats {
+        /// Only summarize tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Prints your tasks as CSV, or with --format timelog, every stretch of time
+    /// tracked with the timer, for invoicing
+    Export {
+        /// What to export
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        /// Only export tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only export these fields, in this order, separated by commas
+        /// (i.e. name,status,due,tags). Exports every field if left out.
+        #[arg(long, value_delimiter = ',')]
+        fields: Option<Vec<String>>,
+    },
+
+    /// Adds the tasks in a CSV written by `checklist export`. Tasks already in the
+    /// database are skipped, so the same file can be imported again
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Import {
+        #[command(subcommand)]
+        from: Option<ImportFrom>,
+
+        /// The CSV to import, or - to read it from standard input
+        #[arg(required = true)]
+        path: Option<PathBuf>,
+    },
+
+    /// Serves your tasks over HTTP, for `checklist sync` on other machines to sync with.
+    /// Only plain HTTP is served, so put a proxy in front of it for HTTPS
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value_t = String::from(DEFAULT_LISTEN))]
+        listen: String,
+
+        /// Only answer requests that send this token. Defaults to the
+        /// "sync_token" in the config file
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Syncs your tasks with a `checklist serve` server. Whichever copy of a task
+    /// changed last wins, and deleting or archiving a task deletes it on both sides
+    #[command(args_conflicts_with_subcommands = true)]
+    Sync {
+        #[command(subcommand)]
+        with: Option<SyncWith>,
+
+        /// URL of the server, i.e. http://tasks.lan:7373. Defaults to the
+        /// "sync_url" in the config file
+        url: Option<String>,
+
+        /// Token to send to the server. Defaults to the "sync_token" in the config file
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Opens a line based prompt to add, list, complete and filter tasks
+    /// without taking over the terminal. Tab completes commands and task names.
+    Prompt,
+
     /// Displays tasks in an interactive terminal
     Display {
         /// For testing, switches between ratatui or my hand-rolled interface
@@ -64,6 +494,28 @@ enum Commands {
         /// What Layout View to start with
         #[arg(short, long, value_enum)]
         view: Option<LayoutView>,
+
+        /// Start with tasks filtered to this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Browse a read-only snapshot instead, from either a backup of the database,
+        /// a date (YYYY-MM-DD), or an age like 2w
+        #[arg(long)]
+        as_of: Option<String>,
+    },
+
+    /// Checks the config file, database, and theme.toml for problems,
+    /// printing how to fix any it finds. Exits with 8 if checklist can't work as is
+    Doctor,
+
+    /// Lists the profiles that have been set up, and the database each one uses
+    Profiles,
+
+    /// Writes one of the bundled themes into theme.toml, to start a theme from
+    Theme {
+        #[command(subcommand)]
+        action: ThemeActions,
     },
 
     /// Tells you where checklist files are stored
@@ -82,9 +534,159 @@ enum Commands {
     },
 }
 
-fn main() -> Result<()> {
+#[derive(Subcommand, Debug)]
+enum TagActions {
+    /// Renames a tag. Use merge instead to fold it into a tag already in use
+    Rename {
+        /// The tag to rename
+        old: String,
+        /// What to rename it to
+        new: String,
+    },
+
+    /// Folds one or more tags into another, i.e. `checklist tag merge wrk job --into work`
+    Merge {
+        /// The tags to fold in
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// The tag they become, which may or may not be in use already
+        #[arg(long)]
+        into: String,
+    },
+
+    /// Takes a tag off every task
+    Rm {
+        /// The tag to remove
+        tag: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportFrom {
+    /// Adds the tasks from Todoist, with projects and labels as tags and p1 to p4
+    /// as Critical to Low urgency. Tasks imported before are skipped
+    Todoist {
+        /// A JSON export of Todoist's API, - to read one from standard input, or
+        /// a Todoist API token to fetch your open tasks with
+        source: String,
+    },
+
+    /// Adds the tasks from a TaskWarrior export (`task export`), keeping their
+    /// uuids as ids along with the dependencies between them. Tasks already in
+    /// the database are skipped
+    Taskwarrior {
+        /// The JSON to import, or - to read it from standard input
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SyncWith {
+    /// Syncs your tasks with the to-dos in a CalDAV calendar (i.e. Nextcloud Tasks)
+    /// instead, set up by "caldav_url", "caldav_username", and "caldav_password" (or
+    /// "caldav_password_command") in the config file. Status, urgency, due date,
+    /// tags, description, and parent are synced
+    Caldav,
+}
+
+#[derive(Subcommand, Debug)]
+enum Hooks {
+    /// Fails if any open task for the current repository is tagged #blocking,
+    /// printing the tasks. Tasks belong to a repository through their project,
+    /// which should match the name of the repository's folder
+    PreCommit {
+        /// Check this project instead of the one named after the repository
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum Reports {
+    /// Summarizes the hours tracked with the timer, per task and per tag
+    Time {
+        /// Only report on tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Lists the tasks completed today, archived ones included
+    Done {
+        /// List the tasks completed this week instead
+        #[arg(short, long)]
+        week: bool,
+    },
+
+    /// Charts the hours tracked each week by tag or project, next to how the open tasks are spread
+    Focus {
+        /// How many weeks to chart, this one included
+        #[arg(short, long, default_value_t = 4)]
+        weeks: u32,
+
+        /// What to break the hours down by
+        #[arg(short, long, value_enum, default_value_t = FocusBy::Tag)]
+        by: FocusBy,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ThemeActions {
+    /// Lists the bundled themes
+    List,
+
+    /// Writes a bundled theme over theme.toml, keeping the old one as theme.toml.bak
+    Apply {
+        /// The bundled theme to write
+        #[arg(value_enum)]
+        name: ThemePreset,
+    },
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            error!("{:#}", e);
+            // Shown with its causes on one line, instead of the debug output of a panic
+            eprintln!("Error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<ExitCode> {
     let cli = Cli::parse();
+    set_quiet(cli.quiet);
+    // Not being able to log shouldn't stop checklist from running
+    if let Err(e) = init_logging(cli.verbose) {
+        complain(format!("Logging is off: {:#}", e));
+    }
+    let profile = cli.profile.clone().or_else(|| {
+        std::env::var(PROFILE_VAR)
+            .ok()
+            .filter(|profile| !profile.is_empty())
+    });
+    if let Some(profile) = profile {
+        set_profile(Some(&profile))?;
+    }
+    // Days and weeks follow the timezone and week start in the config, if there is one
+    // unless checking why the config isn't working, and text sorts by its collation
+    if let Ok(config) = read_config(cli.test) {
+        if matches!(cli.command, Some(Commands::Doctor)) {
+            set_collation(config.collation, config.collation_locale().ok().flatten());
+        } else {
+            set_collation(config.collation, config.collation_locale()?);
+            set_calendar(config.calendar()?);
+        }
+    }
 
+    if cli.promote {
+        let from = get_db(false, true)?;
+        let to = get_db(false, false)?;
+        return Ok(promote_session(&from, &to)?.exit_code());
+    }
+
+    let mut command_status = CommandStatus::Success;
     match cli.command {
         Some(Commands::Init { set }) => {
             if let Some(valid_path) = set {
@@ -105,18 +707,348 @@ fn main() -> Result<()> {
             }
         }
 
+        Some(Commands::Encrypt) => {
+            encrypt_db(get_db(cli.memory, cli.test)?, cli.test)?;
+        }
+
         Some(Commands::Wipe { yes, hard }) => {
             let conn = get_db(cli.memory, cli.test)?;
-            wipe_tasks(&conn, yes, hard)?
+            let backup_count = read_config(cli.test).map_or(0, |config| config.backup_count);
+            wipe_tasks(&conn, yes, hard, backup_count)?
+        }
+
+        Some(Commands::Add {
+            name,
+            description,
+            urgency,
+            status,
+            tag,
+            project,
+            due,
+            date_added,
+            external_ref,
+            external_url,
+            parent,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let new_task = NewTask {
+                name,
+                description,
+                urgency,
+                status,
+                tags: tag,
+                project,
+                due,
+                date_added,
+                external_ref,
+                external_url,
+                parent,
+            };
+            command_status = add_task(&conn, new_task)?
+        }
+
+        Some(Commands::List {
+            status,
+            tag,
+            tag_match,
+            project,
+            query,
+            watch,
+            interval,
+            fail_if_empty,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let query = query.map(|query| query.parse::<Query>()).transpose()?;
+            let display = status.unwrap_or(if query.is_some() {
+                Display::All
+            } else {
+                Display::NotCompleted
+            });
+            let tag_match = tag_match.unwrap_or_else(|| {
+                read_config(cli.test)
+                    .map(|config| config.tag_match)
+                    .unwrap_or_default()
+            });
+            let filters = ListFilters {
+                display,
+                tags: tag,
+                tag_match,
+                project,
+                query,
+            };
+            if watch {
+                watch_tasks(&conn, &filters, Duration::from_millis(interval))?
+            } else {
+                command_status = list_tasks(&conn, &filters, fail_if_empty)?
+            }
+        }
+
+        Some(Commands::Search { query, status }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = search_tasks(&conn, &query, status)?
+        }
+
+        Some(Commands::Show { query, qr }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = show_task(&conn, &query, qr)?
+        }
+
+        Some(Commands::Depend { query, on, remove }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = depend_task(&conn, &query, &on, remove)?
+        }
+
+        Some(Commands::Graph { query }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = show_graph(&conn, &query)?
+        }
+
+        Some(Commands::History { query }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = show_history(&conn, &query)?
+        }
+
+        Some(Commands::OpenTicket { query }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let template = read_config(cli.test)
+                .ok()
+                .and_then(|config| config.ticket_url);
+            command_status = open_ticket(&conn, &query, template.as_deref())?
+        }
+
+        Some(Commands::Lock { query }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = set_task_lock(&conn, &query, true)?
+        }
+
+        Some(Commands::Unlock { query }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = set_task_lock(&conn, &query, false)?
+        }
+
+        Some(Commands::Tag { action }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = match action {
+                TagActions::Rename { old, new } => rename_tag(&conn, &old, &new)?,
+                TagActions::Merge { tags, into } => merge_tags(&conn, &tags, &into)?,
+                TagActions::Rm { tag } => remove_tag(&conn, &tag)?,
+            }
+        }
+
+        Some(Commands::Clone { query, open }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            command_status = clone_task(&conn, &query, open)?
         }
 
-        Some(Commands::Display { old, view }) => {
+        Some(Commands::Done { query, project }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let complete_parents = read_config(cli.test)
+                .map_or(CompleteParents::default(), |config| config.complete_parents);
+            command_status = complete_task(&conn, &query, project.as_deref(), complete_parents)?
+        }
+
+        Some(Commands::Update {
+            query,
+            name,
+            status,
+            urgency,
+            description,
+            latest,
+            project,
+            due,
+            snooze,
+            completed_on,
+            criterion,
+            tick,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let changes = TaskChanges {
+                name,
+                status,
+                urgency,
+                description,
+                latest,
+                project,
+                due,
+                scheduled: snooze,
+                completed_on,
+                criteria: criterion,
+                tick,
+            };
+            let complete_parents = read_config(cli.test)
+                .map_or(CompleteParents::default(), |config| config.complete_parents);
+            command_status = update_task(&conn, &query, changes, complete_parents)?
+        }
+
+        Some(Commands::Archive { older_than }) => {
+            let older_than = parse_age(&older_than)?;
+            let conn = get_db(cli.memory, cli.test)?;
+            let archived = archive_completed(&conn, older_than)?;
+            say(format!("Archived {} completed task(s)", archived));
+        }
+
+        Some(Commands::Notify { days }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let quiet = match read_config(cli.test) {
+                Ok(config) => config.quiet_hours()?,
+                Err(_) => None,
+            };
+            match notify_due_tasks(&conn, days, quiet)? {
+                Delivery::Held(held) => say(format!(
+                    "Quiet hours, held back {} notification(s) until they end",
+                    held
+                )),
+                Delivery::Sent(notifications) if notifications.is_empty() => {
+                    say("Nothing is overdue or due soon")
+                }
+                Delivery::Sent(notifications) => {
+                    for notification in notifications {
+                        say(notification.summary);
+                    }
+                }
+            }
+        }
+
+        Some(Commands::Plan {
+            days,
+            capacity,
+            yes,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let capacity = match (capacity, read_config(cli.test)) {
+                (Some(capacity), _) => parse_capacity(&capacity)?,
+                (None, Ok(config)) => config.daily_capacity()?,
+                (None, Err(_)) => parse_capacity(DEFAULT_CAPACITY)?,
+            };
+            command_status = plan_tasks(&conn, days, capacity, yes)?
+        }
+
+        Some(Commands::Digest { send }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let command = read_config(cli.test)
+                .ok()
+                .and_then(|config| config.digest_command);
+            print_digest(&conn, send, command.as_deref())?;
+            if send {
+                say("Sent the digest");
+            }
+        }
+
+        Some(Commands::Hook { hook }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            match hook {
+                Hooks::PreCommit { project } => command_status = pre_commit(&conn, project)?,
+            }
+        }
+
+        Some(Commands::Report { report }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            match report {
+                Reports::Time { project } => print_time_report(&conn, project.as_deref())?,
+                Reports::Done { week } => print_completed_report(&conn, week)?,
+                Reports::Focus { weeks, by } => print_focus_report(&conn, weeks, by)?,
+            }
+        }
+
+        Some(Commands::Stats { project }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            print_stats(&conn, project.as_deref())?;
+        }
+
+        Some(Commands::Export {
+            format,
+            project,
+            fields,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            print_export(&conn, format, project.as_deref(), fields.as_deref())?;
+        }
+
+        Some(Commands::Import {
+            from: Some(ImportFrom::Todoist { source }),
+            ..
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let backup_count = read_config(cli.test).map_or(0, |config| config.backup_count);
+            command_status = import_todoist_source(&conn, &source, backup_count)?
+        }
+
+        Some(Commands::Import {
+            from: Some(ImportFrom::Taskwarrior { path }),
+            ..
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let backup_count = read_config(cli.test).map_or(0, |config| config.backup_count);
+            command_status = import_taskwarrior_file(&conn, &path, backup_count)?
+        }
+
+        Some(Commands::Import {
+            from: None,
+            path: Some(path),
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let backup_count = read_config(cli.test).map_or(0, |config| config.backup_count);
+            command_status = import_file(&conn, &path, backup_count)?
+        }
+
+        Some(Commands::Import {
+            from: None,
+            path: None,
+        }) => bail!("Pass in the CSV to import"),
+
+        Some(Commands::Serve { listen, token }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let token = token.or_else(|| {
+                read_config(cli.test)
+                    .ok()
+                    .and_then(|config| config.sync_token)
+            });
+            serve(&conn, &listen, token.as_deref())?
+        }
+
+        Some(Commands::Sync {
+            with: Some(SyncWith::Caldav),
+            ..
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let config = read_config(cli.test)?;
+            let account = CaldavAccount::from_config(&config)?;
+            let plan = sync_caldav(&conn, &account, config.backup_count)?;
+            say(plan.summary());
+        }
+
+        Some(Commands::Sync {
+            with: None,
+            url,
+            token,
+        }) => {
+            let conn = get_db(cli.memory, cli.test)?;
+            let config = read_config(cli.test).ok();
+            let Some(url) = url.or_else(|| config.as_ref()?.sync_url.clone()) else {
+                bail!("No server to sync with, pass in its URL or set \"sync_url\" in the config file");
+            };
+            let token = token.or_else(|| config.as_ref()?.sync_token.clone());
+            let backup_count = config.map_or(0, |config| config.backup_count);
+            let plan = sync_tasks(&conn, &url, token.as_deref(), backup_count)?;
+            say(plan.summary());
+        }
+
+        Some(Commands::Prompt) => run_prompt(cli.memory, cli.test)?,
+
+        Some(Commands::Display {
+            old,
+            view,
+            project,
+            as_of,
+        }) => {
             let config = match read_config(cli.test) {
                 Ok(config) => config,
                 Err(_) => {
                     create_sqlite_db(cli.test)?;
                     println!("Successfully created the database to store your items in!");
-                    read_config(cli.test).unwrap()
+                    let config_path = get_config_file(cli.test)?;
+                    read_config(cli.test)
+                        .with_context(|| ChecklistError::UnreadableConfig(config_path))?
                 }
             };
 
@@ -127,40 +1059,70 @@ fn main() -> Result<()> {
                 create_empty_theme_toml()?;
             }
 
-            // Now read it in
-            let theme = read_theme()?;
+            // Now read it in, unless a bundled theme is picked in the config
+            let theme = match config.theme {
+                Some(preset) => preset.theme()?,
+                None => read_theme()?,
+            };
             if old {
                 run_ui(cli.memory, cli.test)?;
             } else {
-                run_tui(cli.memory, cli.test, config, theme, view)?;
+                run_tui(cli.memory, cli.test, config, theme, view, project, as_of)?;
             }
         }
 
+        Some(Commands::Doctor) => command_status = run_doctor(cli.test)?,
+
+        Some(Commands::Profiles) => show_profiles()?,
+
+        Some(Commands::Theme { action }) => match action {
+            ThemeActions::List => {
+                for preset in ThemePreset::value_variants() {
+                    if let Some(name) = preset.to_possible_value() {
+                        let help = name.get_help().map(|help| help.to_string());
+                        println!("{:<14} {}", name.get_name(), help.unwrap_or_default());
+                    }
+                }
+            }
+            ThemeActions::Apply { name } => {
+                let (toml_file, backup) = apply_preset(name)?;
+                if let Some(backup) = backup {
+                    say(format!("Kept the old theme in {}", backup.display()));
+                }
+                say(format!(
+                    "Wrote the {:?} theme to {}",
+                    name,
+                    toml_file.display()
+                ));
+                if let Some(preset) = read_config(cli.test).ok().and_then(|config| config.theme) {
+                    say(format!(
+                        "The \"theme\" in the config file is {:?}, which is used instead of theme.toml",
+                        preset
+                    ));
+                }
+            }
+        },
+
         Some(Commands::Where { db, config, theme }) => match get_config_dir() {
             Ok(dir) => {
                 if !db & !config & !theme {
-                    println!("{}", dir.to_str().unwrap());
+                    println!("{}", dir.display());
                 }
                 if db {
-                    let db_path = if cli.test {
-                        dir.join(String::from("test.checklist.sqlite"))
-                    } else {
-                        dir.join(String::from("checklist.sqlite"))
+                    let db_path = match read_config(cli.test) {
+                        Ok(config) => config.db_path,
+                        Err(_) => dir.join(profile_file_name(DEFAULT_DB_NAME, cli.test)),
                     };
                     if db_path.exists() {
-                        println!("{}", db_path.to_str().unwrap());
+                        println!("{}", db_path.display());
                     } else {
                         println!("Could not find a SQLite database file.")
                     }
                 }
                 if config {
-                    let config_path = if cli.test {
-                        dir.join(String::from("test.config.json"))
-                    } else {
-                        dir.join(String::from("config.json"))
-                    };
+                    let config_path = get_config_file(cli.test)?;
                     if config_path.exists() {
-                        println!("{}", config_path.to_str().unwrap());
+                        println!("{}", config_path.display());
                     } else {
                         println!("Could not find a config file.")
                     }
@@ -168,7 +1130,7 @@ fn main() -> Result<()> {
                 if theme {
                     let theme_path = dir.join(String::from("theme.toml"));
                     if theme_path.exists() {
-                        println!("{}", theme_path.to_str().unwrap());
+                        println!("{}", theme_path.display());
                     } else {
                         println!("Could not find a theme file.")
                     }
@@ -186,7 +1148,9 @@ fn main() -> Result<()> {
                 Err(_) => {
                     create_sqlite_db(cli.test)?;
                     println!("Successfully created the database to store your items in!");
-                    read_config(cli.test).unwrap()
+                    let config_path = get_config_file(cli.test)?;
+                    read_config(cli.test)
+                        .with_context(|| ChecklistError::UnreadableConfig(config_path))?
                 }
             };
 
@@ -197,8 +1161,11 @@ fn main() -> Result<()> {
                 create_empty_theme_toml()?;
             }
 
-            // Now read it in
-            let theme = read_theme()?;
+            // Now read it in, unless a bundled theme is picked in the config
+            let theme = match config.theme {
+                Some(preset) => preset.theme()?,
+                None => read_theme()?,
+            };
 
             run_tui(
                 cli.memory,
@@ -206,9 +1173,23 @@ fn main() -> Result<()> {
                 config,
                 theme,
                 Some(LayoutView::default()),
+                None,
+                None,
             )?;
         }
     }
 
-    Ok(())
+    Ok(command_status.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn verify_cli() {
+        Cli::command().debug_assert();
+    }
 }